@@ -1,16 +1,25 @@
-use std::env;
-use std::fs::File;
-use std::io::*;
+use std::path::PathBuf;
+use std::{env, fs};
 
 use graphix_lib::graphql_api::api_schema_builder;
 
 fn main() -> std::io::Result<()> {
-    let path = env::current_dir()?.join("api_schema.graphql");
-    let mut f = File::create(&path)?;
+    let sdl = format!(
+        "# AUTOGENERATED. DO NOT MODIFY. ALL CHANGES WILL BE LOST.\n\n{}",
+        api_schema_builder().finish().sdl()
+    );
 
-    f.write_all(b"# AUTOGENERATED. DO NOT MODIFY. ALL CHANGES WILL BE LOST.\n\n")?;
-    f.write_all(api_schema_builder().finish().sdl().as_bytes())?;
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by Cargo"));
+    fs::write(out_dir.join("api_schema.graphql"), &sdl)?;
+
+    // The snapshot is only updated on request, so that the snapshot test
+    // catches unintended schema changes.
+    println!("cargo:rerun-if-env-changed=GRAPHIX_UPDATE_SCHEMA");
+    if env::var_os("GRAPHIX_UPDATE_SCHEMA").is_some() {
+        let path = env::current_dir()?.join("api_schema.graphql");
+        fs::write(&path, &sdl)?;
+        println!("cargo:warning=Updated: {}", path.display());
+    }
 
-    println!("Updated: {}", path.display());
     Ok(())
 }
@@ -0,0 +1,356 @@
+//! A structural diff between two versions of a GraphQL schema, which tells
+//! apart the changes that break existing clients from the ones that don't.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use async_graphql::parser::types::{
+    FieldDefinition, InputValueDefinition, TypeDefinition, TypeKind, TypeSystemDefinition,
+};
+use async_graphql::parser::{parse_schema, Positioned};
+
+/// A single difference between two versions of a GraphQL schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    /// The changed type, field (`Type.field`) or argument
+    /// (`Type.field(argument)`).
+    pub path: String,
+    pub kind: SchemaChangeKind,
+    /// Whether the change can break existing clients, e.g. because a field
+    /// they select was removed or renamed.
+    pub breaking: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChangeKind {
+    Added,
+    Removed,
+    TypeChanged { old: String, new: String },
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.breaking { "BREAKING" } else { "ok" };
+        match &self.kind {
+            SchemaChangeKind::Added => write!(f, "[{marker}] added {}", self.path),
+            SchemaChangeKind::Removed => write!(f, "[{marker}] removed {}", self.path),
+            SchemaChangeKind::TypeChanged { old, new } => {
+                write!(f, "[{marker}] changed {} from {old} to {new}", self.path)
+            }
+        }
+    }
+}
+
+/// Compares two GraphQL schemas in SDL format. Changes are sorted by path.
+pub fn diff_schemas(old_sdl: &str, new_sdl: &str) -> anyhow::Result<Vec<SchemaChange>> {
+    let old_types = types_by_name(old_sdl)?;
+    let new_types = types_by_name(new_sdl)?;
+    let mut changes = vec![];
+
+    for (name, old_type) in &old_types {
+        match new_types.get(name) {
+            Some(new_type) => diff_types(name, old_type, new_type, &mut changes),
+            None => changes.push(change(name.clone(), SchemaChangeKind::Removed, true)),
+        }
+    }
+    for name in new_types.keys() {
+        if !old_types.contains_key(name) {
+            changes.push(change(name.clone(), SchemaChangeKind::Added, false));
+        }
+    }
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+fn types_by_name(sdl: &str) -> anyhow::Result<BTreeMap<String, TypeDefinition>> {
+    let document = parse_schema(sdl)?;
+
+    Ok(document
+        .definitions
+        .into_iter()
+        .filter_map(|definition| match definition {
+            TypeSystemDefinition::Type(ty) => Some((ty.node.name.node.to_string(), ty.node)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn change(path: String, kind: SchemaChangeKind, breaking: bool) -> SchemaChange {
+    SchemaChange {
+        path,
+        kind,
+        breaking,
+    }
+}
+
+fn diff_types(
+    name: &str,
+    old_type: &TypeDefinition,
+    new_type: &TypeDefinition,
+    changes: &mut Vec<SchemaChange>,
+) {
+    match (&old_type.kind, &new_type.kind) {
+        (TypeKind::Object(old), TypeKind::Object(new)) => {
+            diff_fields(name, &old.fields, &new.fields, changes)
+        }
+        (TypeKind::Interface(old), TypeKind::Interface(new)) => {
+            diff_fields(name, &old.fields, &new.fields, changes)
+        }
+        (TypeKind::InputObject(old), TypeKind::InputObject(new)) => {
+            diff_input_values(name, &old.fields, &new.fields, changes)
+        }
+        (TypeKind::Enum(old), TypeKind::Enum(new)) => diff_names(
+            name,
+            old.values
+                .iter()
+                .map(|value| value.node.value.node.as_str()),
+            new.values
+                .iter()
+                .map(|value| value.node.value.node.as_str()),
+            changes,
+        ),
+        (TypeKind::Union(old), TypeKind::Union(new)) => diff_names(
+            name,
+            old.members.iter().map(|member| member.node.as_str()),
+            new.members.iter().map(|member| member.node.as_str()),
+            changes,
+        ),
+        (TypeKind::Scalar, TypeKind::Scalar) => {}
+        (old, new) => changes.push(change(
+            name.to_string(),
+            SchemaChangeKind::TypeChanged {
+                old: type_kind_name(old).to_string(),
+                new: type_kind_name(new).to_string(),
+            },
+            true,
+        )),
+    }
+}
+
+fn type_kind_name(kind: &TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Scalar => "scalar",
+        TypeKind::Object(_) => "object",
+        TypeKind::Interface(_) => "interface",
+        TypeKind::Union(_) => "union",
+        TypeKind::Enum(_) => "enum",
+        TypeKind::InputObject(_) => "input object",
+    }
+}
+
+fn diff_fields(
+    type_name: &str,
+    old_fields: &[Positioned<FieldDefinition>],
+    new_fields: &[Positioned<FieldDefinition>],
+    changes: &mut Vec<SchemaChange>,
+) {
+    for old_field in old_fields {
+        let old_field = &old_field.node;
+        let path = format!("{type_name}.{}", old_field.name.node);
+        let Some(new_field) = new_fields
+            .iter()
+            .find(|field| field.node.name.node == old_field.name.node)
+        else {
+            changes.push(change(path, SchemaChangeKind::Removed, true));
+            continue;
+        };
+
+        let (old_ty, new_ty) = (
+            old_field.ty.node.to_string(),
+            new_field.node.ty.node.to_string(),
+        );
+        if old_ty != new_ty {
+            // Clients can always handle a field that is no longer nullable.
+            let breaking = old_ty != new_ty.trim_end_matches('!');
+            changes.push(change(
+                path.clone(),
+                SchemaChangeKind::TypeChanged {
+                    old: old_ty,
+                    new: new_ty,
+                },
+                breaking,
+            ));
+        }
+        diff_input_values(
+            &path,
+            &old_field.arguments,
+            &new_field.node.arguments,
+            changes,
+        );
+    }
+
+    for new_field in new_fields {
+        if !old_fields
+            .iter()
+            .any(|field| field.node.name.node == new_field.node.name.node)
+        {
+            let path = format!("{type_name}.{}", new_field.node.name.node);
+            changes.push(change(path, SchemaChangeKind::Added, false));
+        }
+    }
+}
+
+/// Diffs arguments of a field (`parent` is `Type.field`) or fields of an input
+/// object (`parent` is `Type`).
+fn diff_input_values(
+    parent: &str,
+    old_values: &[Positioned<InputValueDefinition>],
+    new_values: &[Positioned<InputValueDefinition>],
+    changes: &mut Vec<SchemaChange>,
+) {
+    let path = |name: &str| {
+        if parent.contains('.') {
+            format!("{parent}({name})")
+        } else {
+            format!("{parent}.{name}")
+        }
+    };
+
+    for old_value in old_values {
+        let old_value = &old_value.node;
+        let Some(new_value) = new_values
+            .iter()
+            .find(|value| value.node.name.node == old_value.name.node)
+        else {
+            changes.push(change(
+                path(&old_value.name.node),
+                SchemaChangeKind::Removed,
+                true,
+            ));
+            continue;
+        };
+
+        let (old_ty, new_ty) = (
+            old_value.ty.node.to_string(),
+            new_value.node.ty.node.to_string(),
+        );
+        if old_ty != new_ty {
+            // Clients can always send a value to an input that became nullable.
+            let breaking = old_ty.trim_end_matches('!') != new_ty;
+            changes.push(change(
+                path(&old_value.name.node),
+                SchemaChangeKind::TypeChanged {
+                    old: old_ty,
+                    new: new_ty,
+                },
+                breaking,
+            ));
+        }
+    }
+
+    for new_value in new_values {
+        let new_value = &new_value.node;
+        if !old_values
+            .iter()
+            .any(|value| value.node.name.node == new_value.name.node)
+        {
+            // Existing clients don't send new inputs, so they must be optional.
+            let required = !new_value.ty.node.nullable && new_value.default_value.is_none();
+            changes.push(change(
+                path(&new_value.name.node),
+                SchemaChangeKind::Added,
+                required,
+            ));
+        }
+    }
+}
+
+fn diff_names<'a>(
+    type_name: &str,
+    old_names: impl Iterator<Item = &'a str> + Clone,
+    new_names: impl Iterator<Item = &'a str> + Clone,
+    changes: &mut Vec<SchemaChange>,
+) {
+    for old_name in old_names.clone() {
+        if !new_names.clone().any(|name| name == old_name) {
+            let path = format!("{type_name}.{old_name}");
+            changes.push(change(path, SchemaChangeKind::Removed, true));
+        }
+    }
+    for new_name in new_names {
+        if !old_names.clone().any(|name| name == new_name) {
+            let path = format!("{type_name}.{new_name}");
+            changes.push(change(path, SchemaChangeKind::Added, false));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"
+        type Query {
+            pois(limit: Int! = 100, network: String): [Poi!]!
+            version: String!
+        }
+        type Poi {
+            hash: String!
+            blockNumber: Int
+        }
+        enum Status { OK FAILED }
+    "#;
+
+    fn breaking_changes(new: &str) -> Vec<String> {
+        diff_schemas(OLD, new)
+            .unwrap()
+            .into_iter()
+            .filter(|change| change.breaking)
+            .map(|change| change.path)
+            .collect()
+    }
+
+    #[test]
+    fn identical_schemas_have_no_changes() {
+        assert_eq!(diff_schemas(OLD, OLD).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn additions_are_not_breaking() {
+        let new = r#"
+            type Query {
+                pois(limit: Int! = 100, network: String, epoch: Int): [Poi!]!
+                version: String!
+                instance: String
+            }
+            type Poi {
+                hash: String!
+                blockNumber: Int!
+            }
+            enum Status { OK FAILED PAUSED }
+            type Instance { name: String! }
+        "#;
+        let changes = diff_schemas(OLD, new).unwrap();
+
+        assert_eq!(changes.len(), 5);
+        assert!(changes.iter().all(|change| !change.breaking));
+    }
+
+    #[test]
+    fn renames_and_removals_are_breaking() {
+        let new = r#"
+            type Query {
+                pois(limit: Int! = 100, network: String!, epoch: Int!): [Poi!]!
+                graphixVersion: String!
+            }
+            type Poi {
+                poi: String!
+                blockNumber: String
+            }
+            enum Status { OK }
+        "#;
+
+        assert_eq!(
+            breaking_changes(new),
+            vec![
+                "Poi.blockNumber",
+                "Poi.hash",
+                "Query.pois(epoch)",
+                "Query.pois(network)",
+                "Query.version",
+                "Status.FAILED",
+            ]
+        );
+    }
+}
@@ -1 +1,13 @@
+//! Graphix's GraphQL API schema in SDL format, and a snapshot of it that is
+//! checked into the repository for API consumers, e.g. dashboards. Rebuild
+//! with `GRAPHIX_UPDATE_SCHEMA=1` to update the snapshot.
 
+mod diff;
+
+pub use diff::{diff_schemas, SchemaChange, SchemaChangeKind};
+
+/// The schema of the current API, generated at build time.
+pub const API_SCHEMA: &str = include_str!(concat!(env!("OUT_DIR"), "/api_schema.graphql"));
+
+/// The checked-in snapshot of the API schema.
+pub const API_SCHEMA_SNAPSHOT: &str = include_str!("../api_schema.graphql");
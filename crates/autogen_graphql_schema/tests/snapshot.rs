@@ -0,0 +1,23 @@
+use graphix_autogen_graphql_schema::{diff_schemas, API_SCHEMA, API_SCHEMA_SNAPSHOT};
+
+#[test]
+fn api_schema_matches_snapshot() {
+    if API_SCHEMA == API_SCHEMA_SNAPSHOT {
+        return;
+    }
+
+    let changes = diff_schemas(API_SCHEMA_SNAPSHOT, API_SCHEMA).unwrap();
+    let breaking = changes.iter().filter(|change| change.breaking).count();
+    let changes = changes
+        .iter()
+        .map(|change| format!("  {change}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    panic!(
+        "The GraphQL API schema doesn't match `api_schema.graphql` ({breaking} breaking changes):\n\
+         {changes}\n\
+         If these changes are intended, update the snapshot with \
+         `GRAPHIX_UPDATE_SCHEMA=1 cargo build` and commit it."
+    );
+}
@@ -3,6 +3,7 @@ use diesel::expression::AsExpression;
 use diesel::pg::{Pg, PgValue};
 use diesel::serialize::ToSql;
 use diesel::sql_types;
+use serde::Serialize;
 
 #[derive(
     Debug,
@@ -14,10 +15,18 @@ use diesel::sql_types;
     Ord,
     AsExpression,
     FromSqlRow,
+    Serialize,
     async_graphql::Enum,
 )]
 #[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ApiKeyPermissionLevel {
+    // Variants are ordered from least to most privileged.
+    /// Can manage its own webhooks, e.g. for the subgraphs of a subgraph team,
+    /// curate indexer and deployment metadata, and launch divergence
+    /// investigations.
+    Operator,
+    /// Full access.
     Admin,
 }
 
@@ -28,6 +37,9 @@ impl ToSql<sql_types::Integer, Pg> for ApiKeyPermissionLevel {
     ) -> diesel::serialize::Result {
         match self {
             ApiKeyPermissionLevel::Admin => <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(&1, out),
+            ApiKeyPermissionLevel::Operator => {
+                <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(&2, out)
+            }
         }
     }
 }
@@ -36,6 +48,7 @@ impl FromSql<sql_types::Integer, Pg> for ApiKeyPermissionLevel {
     fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
         match i32::from_sql(bytes)? {
             1 => Ok(ApiKeyPermissionLevel::Admin),
+            2 => Ok(ApiKeyPermissionLevel::Operator),
             _ => Err(anyhow::anyhow!("invalid permission level").into()),
         }
     }
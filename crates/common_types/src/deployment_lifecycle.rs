@@ -0,0 +1,114 @@
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::ToSql;
+use diesel::sql_types;
+use serde::Serialize;
+
+/// Whether indexers still report a subgraph deployment in their indexing
+/// statuses.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    async_graphql::Enum,
+)]
+#[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeploymentLifecycleStatus {
+    /// At least one indexer reports the deployment without pausing it.
+    Active,
+    /// All indexers that report the deployment paused it.
+    Paused,
+    /// All indexers that used to report the deployment stopped doing so.
+    Removed,
+}
+
+impl ToSql<sql_types::Integer, Pg> for DeploymentLifecycleStatus {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            DeploymentLifecycleStatus::Active => &1,
+            DeploymentLifecycleStatus::Paused => &2,
+            DeploymentLifecycleStatus::Removed => &3,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for DeploymentLifecycleStatus {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(DeploymentLifecycleStatus::Active),
+            2 => Ok(DeploymentLifecycleStatus::Paused),
+            3 => Ok(DeploymentLifecycleStatus::Removed),
+            _ => Err(anyhow::anyhow!("invalid deployment lifecycle status").into()),
+        }
+    }
+}
+
+/// A change in how an indexer reports a subgraph deployment in its indexing
+/// statuses, from one polling cycle to the next.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    async_graphql::Enum,
+)]
+#[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeploymentEventKind {
+    /// The indexer started reporting the deployment.
+    Added,
+    /// The indexer responded, but no longer reported the deployment.
+    Removed,
+    /// The indexer paused the deployment.
+    Paused,
+    /// The indexer unpaused the deployment.
+    Resumed,
+}
+
+impl ToSql<sql_types::Integer, Pg> for DeploymentEventKind {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            DeploymentEventKind::Added => &1,
+            DeploymentEventKind::Removed => &2,
+            DeploymentEventKind::Paused => &3,
+            DeploymentEventKind::Resumed => &4,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for DeploymentEventKind {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(DeploymentEventKind::Added),
+            2 => Ok(DeploymentEventKind::Removed),
+            3 => Ok(DeploymentEventKind::Paused),
+            4 => Ok(DeploymentEventKind::Resumed),
+            _ => Err(anyhow::anyhow!("invalid deployment event kind").into()),
+        }
+    }
+}
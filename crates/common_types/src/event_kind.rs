@@ -0,0 +1,65 @@
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::ToSql;
+use diesel::sql_types;
+use serde::{Deserialize, Serialize};
+
+/// The kind of an event in Graphix's event log, which GraphQL subscriptions
+/// and webhooks are fed from.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    Deserialize,
+    async_graphql::Enum,
+)]
+#[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EventKind {
+    /// The set of indexers whose PoIs diverge from the consensus PoI of a
+    /// subgraph deployment changed.
+    PoiDivergenceDetected,
+    /// A divergence investigation was concluded.
+    InvestigationCompleted,
+    /// An indexer could no longer be reached, e.g. because of DNS, TLS or
+    /// connection errors, or timeouts.
+    IndexerUnreachable,
+    /// The configuration of the Graphix instance changed.
+    ConfigChanged,
+}
+
+impl ToSql<sql_types::Integer, Pg> for EventKind {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            EventKind::PoiDivergenceDetected => &1,
+            EventKind::InvestigationCompleted => &2,
+            EventKind::IndexerUnreachable => &3,
+            EventKind::ConfigChanged => &4,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for EventKind {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(EventKind::PoiDivergenceDetected),
+            2 => Ok(EventKind::InvestigationCompleted),
+            3 => Ok(EventKind::IndexerUnreachable),
+            4 => Ok(EventKind::ConfigChanged),
+            _ => Err(anyhow::anyhow!("invalid event kind").into()),
+        }
+    }
+}
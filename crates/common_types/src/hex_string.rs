@@ -181,4 +181,79 @@ mod tests {
 
         hex_string == hex_string2
     }
+
+    #[quickcheck]
+    fn from_str_never_panics(s: String) -> bool {
+        let _ = s.parse::<HexString<Vec<u8>>>();
+        let _ = s.parse::<HexString<[u8; 32]>>();
+        true
+    }
+
+    #[quickcheck]
+    fn from_str_without_0x(hex_string: HexString<Vec<u8>>) -> bool {
+        let string = hex_string.to_string();
+        let hex_string2: HexString<Vec<u8>> = string[2..].parse().unwrap();
+
+        hex_string == hex_string2
+    }
+
+    #[quickcheck]
+    fn rejects_odd_lengths(hex_string: HexString<Vec<u8>>) -> bool {
+        let string = format!("{hex_string}0");
+        string.parse::<HexString<Vec<u8>>>().is_err()
+    }
+
+    #[quickcheck]
+    fn rejects_invalid_chars(hex_string: HexString<Vec<u8>>, index: usize, c: char) -> bool {
+        if c.is_ascii_hexdigit() {
+            return true;
+        }
+
+        // Replace a single digit, keeping the `0x` prefix.
+        let mut chars: Vec<char> = hex_string.to_string().chars().collect();
+        if chars.len() == 2 {
+            return true;
+        }
+        let index = 2 + index % (chars.len() - 2);
+        chars[index] = c;
+        let string: String = chars.into_iter().collect();
+
+        string.parse::<HexString<Vec<u8>>>().is_err()
+    }
+
+    #[quickcheck]
+    fn fixed_length_rejects_other_lengths(hex_string: HexString<Vec<u8>>) -> bool {
+        let result = hex_string.to_string().parse::<HexString<[u8; 32]>>();
+        if hex_string.0.len() == 32 {
+            result.is_ok()
+        } else {
+            result.is_err()
+        }
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for s in [
+            "0x0", "0x0x00", "0X00", "x00", " 0x00", "0x00 ", "0x-1", "0x+1", "0xé0", "0x\u{0}0",
+        ] {
+            assert!(s.parse::<HexString<Vec<u8>>>().is_err(), "{s:?}");
+        }
+
+        assert!("0x".parse::<HexString<[u8; 32]>>().is_err());
+        assert!(format!("0x{}", "00".repeat(33))
+            .parse::<HexString<[u8; 32]>>()
+            .is_err());
+    }
+
+    #[test]
+    fn async_graphql_rejects_non_strings() {
+        for value in [
+            async_graphql::Value::Null,
+            async_graphql::Value::Boolean(true),
+            async_graphql::Value::Number(42.into()),
+            async_graphql::Value::List(vec![]),
+        ] {
+            assert!(<HexString<Vec<u8>> as ScalarType>::parse(value).is_err());
+        }
+    }
 }
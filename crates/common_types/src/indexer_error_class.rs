@@ -0,0 +1,87 @@
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::ToSql;
+use diesel::sql_types;
+use serde::Serialize;
+
+/// Why a request to an indexer failed, as far as Graphix can tell from its
+/// side of the connection.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    async_graphql::Enum,
+)]
+#[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IndexerErrorClass {
+    /// The indexer's host name couldn't be resolved.
+    Dns,
+    /// The TLS handshake failed, e.g. because of an invalid certificate.
+    Tls,
+    /// The connection was refused or reset.
+    Connection,
+    /// The request timed out.
+    Timeout,
+    /// The indexer responded with an HTTP 5xx status.
+    HttpServerError,
+    /// The indexer responded with an HTTP 4xx status.
+    HttpClientError,
+    /// The indexer responded with GraphQL errors.
+    GraphqlError,
+    /// The response body couldn't be parsed.
+    InvalidResponse,
+    /// Any other failure.
+    Other,
+    /// The request wasn't sent, as the indexer failed too many times in a row
+    /// and is skipped for a while.
+    CircuitOpen,
+}
+
+impl ToSql<sql_types::Integer, Pg> for IndexerErrorClass {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            IndexerErrorClass::Dns => &1,
+            IndexerErrorClass::Tls => &2,
+            IndexerErrorClass::Connection => &3,
+            IndexerErrorClass::Timeout => &4,
+            IndexerErrorClass::HttpServerError => &5,
+            IndexerErrorClass::HttpClientError => &6,
+            IndexerErrorClass::GraphqlError => &7,
+            IndexerErrorClass::InvalidResponse => &8,
+            IndexerErrorClass::Other => &9,
+            IndexerErrorClass::CircuitOpen => &10,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for IndexerErrorClass {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(IndexerErrorClass::Dns),
+            2 => Ok(IndexerErrorClass::Tls),
+            3 => Ok(IndexerErrorClass::Connection),
+            4 => Ok(IndexerErrorClass::Timeout),
+            5 => Ok(IndexerErrorClass::HttpServerError),
+            6 => Ok(IndexerErrorClass::HttpClientError),
+            7 => Ok(IndexerErrorClass::GraphqlError),
+            8 => Ok(IndexerErrorClass::InvalidResponse),
+            9 => Ok(IndexerErrorClass::Other),
+            10 => Ok(IndexerErrorClass::CircuitOpen),
+            _ => Err(anyhow::anyhow!("invalid indexer error class").into()),
+        }
+    }
+}
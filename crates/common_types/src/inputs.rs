@@ -4,8 +4,9 @@
 use std::ops::{Bound, RangeBounds};
 
 use async_graphql::InputObject;
+use chrono::NaiveDateTime;
 
-use crate::{IndexerAddress, IpfsCid};
+use crate::{EventKind, IndexerAddress, IpfsCid, PoiBytes};
 
 /// A filter for subgraph deployments.
 #[derive(Default)]
@@ -20,6 +21,19 @@ pub struct SgDeploymentsQuery {
     pub limit: Option<u16>,
 }
 
+/// A filter for the event log.
+#[derive(Default, InputObject)]
+pub struct EventsQuery {
+    /// Restricts the query to events of these kinds. All kinds if empty.
+    pub kinds: Vec<EventKind>,
+    /// Restricts the query to events about the given subgraph deployment.
+    pub deployment: Option<IpfsCid>,
+    /// Restricts the query to events with IDs greater than this one.
+    pub after_id: Option<i64>,
+    /// Upper limit on the number of shown results.
+    pub limit: Option<u16>,
+}
+
 /// A filter for PoIs (proofs of indexing).
 #[derive(Default, InputObject)]
 pub struct PoisQuery {
@@ -31,6 +45,10 @@ pub struct PoisQuery {
     /// Restricts the query to PoIs that were collected in the given block
     /// range.
     pub block_range: Option<BlockRange>,
+    /// Restricts the query to PoIs for blocks in the given protocol epoch.
+    pub epoch: Option<u64>,
+    /// Restricts the query to PoIs of indexers with the given tag.
+    pub indexer_tag: Option<String>,
     /// Upper limit on the number of shown results.
     pub limit: Option<u16>,
 }
@@ -41,6 +59,28 @@ pub struct IndexersQuery {
     /// The address of the indexer, encoded as a hex string with a '0x'
     /// prefix.
     pub address: Option<IndexerAddress>,
+    /// Restricts the query to indexers with the given tag.
+    pub tag: Option<String>,
+    /// Upper limit on the number of shown results.
+    pub limit: Option<u16>,
+    /// Also returns indexers that were archived with `archiveIndexer`.
+    #[graphql(default)]
+    pub include_archived: bool,
+}
+
+/// A filter for the bisection runs of divergence investigations.
+#[derive(Default, InputObject)]
+pub struct BisectionRunsQuery {
+    /// Restricts the query to bisection runs for these subgraph deployments.
+    pub deployments: Vec<IpfsCid>,
+    /// Restricts the query to bisection runs that found the first diverging
+    /// block to be the given block number.
+    pub divergence_block_number: Option<u64>,
+    /// Restricts the query to bisection runs that were performed after the
+    /// given time.
+    pub since: Option<NaiveDateTime>,
+    /// Restricts the query to bisection runs that compared the given PoI.
+    pub poi: Option<PoiBytes>,
     /// Upper limit on the number of shown results.
     pub limit: Option<u16>,
 }
@@ -0,0 +1,44 @@
+use async_graphql::SimpleObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{IndexerAddress, IpfsCid};
+
+/// The configuration of a Graphix instance together with the metadata that's
+/// managed through the GraphQL API, as exported by `exportConfig` and
+/// restored by `importConfig`, e.g. to replicate a production setup on a
+/// staging instance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceConfig {
+    pub config: serde_json::Value,
+    #[serde(default)]
+    pub deployment_names: Vec<DeploymentName>,
+    #[serde(default)]
+    pub indexer_tags: Vec<IndexerTags>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentName {
+    pub ipfs_cid: IpfsCid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexerTags {
+    pub address: IndexerAddress,
+    pub tags: Vec<String>,
+}
+
+/// The outcome of importing an [`InstanceConfig`]. Names and tags can only be
+/// restored for subgraph deployments and indexers that this instance already
+/// knows about; the others are skipped, and importing the same document again
+/// after they've been polled restores them too.
+#[derive(Debug, Clone, Default, PartialEq, Eq, SimpleObject)]
+pub struct InstanceConfigImport {
+    pub named_deployments: u32,
+    pub skipped_deployments: Vec<IpfsCid>,
+    pub tagged_indexers: u32,
+    pub skipped_indexers: Vec<IndexerAddress>,
+}
@@ -11,6 +11,12 @@ use serde::{Deserialize, Serialize};
 
 /// A [`serde`], [`diesel`], and [`async_graphql`]-compatible type definition
 /// for IPFS CIDs and subgraph deployment IDs.
+///
+/// Subgraph deployment IDs come in several representations: CIDv0 (`Qm...`),
+/// CIDv1 (`bafy...`) and the `0x`-prefixed hex bytes32 of their SHA-256 digest
+/// (as used by the network subgraph and contracts). All of them are parsed,
+/// and canonicalized to CIDv0, so that they compare equal and are stored and
+/// displayed the same.
 #[derive(
     Debug,
     Clone,
@@ -24,11 +30,54 @@ use serde::{Deserialize, Serialize};
     AsExpression,
     FromSqlRow,
     derive_more::Display,
-    derive_more::FromStr,
 )]
 #[diesel(sql_type = sql_types::Text)]
 pub struct IpfsCid(cid::Cid);
 
+// The multicodec code of SHA-256.
+const SHA2_256: u64 = 0x12;
+// The multicodec code of DAG-PB, the only codec that CIDv0s can have.
+const DAG_PB: u64 = 0x70;
+
+impl IpfsCid {
+    /// The CIDv0 (`Qm...`) of content with the SHA-256 digest `digest`, i.e.
+    /// the format of subgraph deployment IDs.
+    pub fn from_sha256_digest(digest: [u8; 32]) -> Self {
+        let hash = cid::multihash::Multihash::wrap(SHA2_256, &digest)
+            .expect("SHA-256 digests fit into multihashes");
+        Self(cid::Cid::new_v0(hash).expect("SHA-256 multihashes are valid CIDv0s"))
+    }
+
+    /// Converts CIDv1s that have a CIDv0 equivalent to it.
+    fn canonical(cid: cid::Cid) -> Self {
+        if cid.version() == cid::Version::V1
+            && cid.codec() == DAG_PB
+            && cid.hash().code() == SHA2_256
+            && cid.hash().size() == 32
+        {
+            if let Ok(cid) = cid::Cid::new_v0(*cid.hash()) {
+                return Self(cid);
+            }
+        }
+        Self(cid)
+    }
+}
+
+impl FromStr for IpfsCid {
+    type Err = cid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix("0x") {
+            let mut digest = [0; 32];
+            return match hex::decode_to_slice(hex, &mut digest) {
+                Ok(()) => Ok(Self::from_sha256_digest(digest)),
+                Err(_) => Err(cid::Error::ParsingError),
+            };
+        }
+        cid::Cid::from_str(s).map(Self::canonical)
+    }
+}
+
 #[async_graphql::Scalar]
 impl async_graphql::ScalarType for IpfsCid {
     fn parse(value: async_graphql::Value) -> async_graphql::InputValueResult<Self> {
@@ -36,8 +85,7 @@ impl async_graphql::ScalarType for IpfsCid {
             return Err(async_graphql::InputValueError::expected_type(value));
         };
 
-        let cid = cid::Cid::from_str(&string)?;
-        Ok(IpfsCid(cid))
+        Ok(IpfsCid::from_str(&string)?)
     }
 
     fn to_value(&self) -> async_graphql::Value {
@@ -57,13 +105,13 @@ impl ToSql<sql_types::Text, Pg> for IpfsCid {
 impl FromSql<sql_types::Text, Pg> for IpfsCid {
     fn from_sql(bytes: <Pg as Backend>::RawValue<'_>) -> diesel::deserialize::Result<Self> {
         let s = String::from_sql(bytes)?;
-        Ok(IpfsCid(cid::Cid::from_str(&s)?))
+        Ok(IpfsCid::from_str(&s)?)
     }
 }
 
 impl Arbitrary for IpfsCid {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        Self(cid::Cid::arbitrary(g))
+        Self::canonical(cid::Cid::arbitrary(g))
     }
 }
 
@@ -98,6 +146,14 @@ mod tests {
         ipfs_cid == ipfs_cid2
     }
 
+    #[test]
+    fn from_sha256_digest() {
+        let ipfs_cid = IpfsCid::from_sha256_digest([0; 32]);
+
+        assert!(ipfs_cid.to_string().starts_with("Qm"));
+        assert_eq!(IpfsCid::from_str(&ipfs_cid.to_string()).unwrap(), ipfs_cid);
+    }
+
     #[test]
     fn from_str_roundtrip() {
         let deployment_id = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
@@ -105,4 +161,91 @@ mod tests {
 
         assert_eq!(ipfs_id.to_string(), deployment_id);
     }
+
+    #[test]
+    fn canonicalizes_representations() {
+        let deployment_id = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+        let ipfs_cid = IpfsCid::from_str(deployment_id).unwrap();
+        let v1 = cid::Cid::new_v1(DAG_PB, *ipfs_cid.0.hash()).to_string();
+        let hex = format!("0x{}", hex::encode(ipfs_cid.0.hash().digest()));
+        let upper_hex = format!("0x{}", hex::encode_upper(ipfs_cid.0.hash().digest()));
+
+        assert!(v1.starts_with("bafy"));
+        assert_eq!(IpfsCid::from_str(&v1).unwrap().to_string(), deployment_id);
+        assert_eq!(IpfsCid::from_str(&hex).unwrap().to_string(), deployment_id);
+        assert_eq!(IpfsCid::from_str(&upper_hex).unwrap(), ipfs_cid);
+    }
+
+    #[test]
+    fn keeps_cid_v1_without_v0_equivalent() {
+        // DAG-CBOR, which CIDv0s can't represent.
+        let cid = cid::Cid::new_v1(0x71, *IpfsCid::from_sha256_digest([1; 32]).0.hash());
+        let ipfs_cid = IpfsCid::from_str(&cid.to_string()).unwrap();
+
+        assert_eq!(ipfs_cid.to_string(), cid.to_string());
+    }
+
+    #[quickcheck]
+    fn from_str_never_panics(s: String) -> bool {
+        let _ = IpfsCid::from_str(&s);
+        let _ = <IpfsCid as ScalarType>::parse(async_graphql::Value::String(s));
+        true
+    }
+
+    #[quickcheck]
+    fn from_str_never_panics_on_mutations(ipfs_cid: IpfsCid, index: usize, c: char) -> bool {
+        // Mutations of valid CIDs get much further into the decoder than
+        // arbitrary strings.
+        let mut chars: Vec<char> = ipfs_cid.to_string().chars().collect();
+        let index = index % chars.len();
+        chars[index] = c;
+        let _ = IpfsCid::from_str(&chars.iter().collect::<String>());
+        chars.truncate(index);
+        let _ = IpfsCid::from_str(&chars.iter().collect::<String>());
+        true
+    }
+
+    #[quickcheck]
+    fn rejects_truncated_v0(len: usize) -> bool {
+        let deployment_id = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+        let truncated = &deployment_id[..len % deployment_id.len()];
+
+        IpfsCid::from_str(truncated).is_err()
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for s in [
+            "",
+            "Qm",
+            // Extra character.
+            "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGAx",
+            // `0`, `O`, `I` and `l` aren't part of the base58 alphabet.
+            "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSG0",
+            "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGO",
+            "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGI",
+            "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGl",
+            " QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA",
+            "0xdeadbeef",
+            // Not hex.
+            "0xgggggggggggggggggggggggggggggggggggggggggggggggggggggggggggggggg",
+            // 33 bytes.
+            "0x000000000000000000000000000000000000000000000000000000000000000000",
+            "bafy",
+        ] {
+            assert!(IpfsCid::from_str(s).is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn async_graphql_rejects_non_strings() {
+        for value in [
+            async_graphql::Value::Null,
+            async_graphql::Value::Boolean(true),
+            async_graphql::Value::Number(42.into()),
+            async_graphql::Value::List(vec![]),
+        ] {
+            assert!(<IpfsCid as ScalarType>::parse(value).is_err());
+        }
+    }
 }
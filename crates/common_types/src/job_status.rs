@@ -0,0 +1,45 @@
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::ToSql;
+use diesel::sql_types;
+use serde::Serialize;
+
+/// Where a job of a persistent job queue, e.g. a divergence investigation
+/// request, is in its lifecycle. Completed jobs are deleted from the queue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, AsExpression, FromSqlRow, Serialize)]
+#[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JobStatus {
+    /// Waiting to be claimed, possibly to be retried after a failed attempt.
+    Queued,
+    /// Claimed by a worker, which is still sending heartbeats.
+    Running,
+    /// Failed too many times; it won't be retried.
+    Failed,
+}
+
+impl ToSql<sql_types::Integer, Pg> for JobStatus {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            JobStatus::Queued => &1,
+            JobStatus::Running => &2,
+            JobStatus::Failed => &3,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for JobStatus {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(JobStatus::Queued),
+            2 => Ok(JobStatus::Running),
+            3 => Ok(JobStatus::Failed),
+            _ => Err(anyhow::anyhow!("invalid job status").into()),
+        }
+    }
+}
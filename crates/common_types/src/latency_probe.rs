@@ -0,0 +1,54 @@
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::ToSql;
+use diesel::sql_types;
+use serde::Serialize;
+
+/// A request that Graphix times to benchmark an indexer's responsiveness.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    async_graphql::Enum,
+)]
+#[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LatencyProbe {
+    /// A query for all indexing statuses on `/status`.
+    Status,
+    /// A query for the PoI of a single subgraph deployment at its latest
+    /// indexed block.
+    ProofOfIndexing,
+}
+
+impl ToSql<sql_types::Integer, Pg> for LatencyProbe {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            LatencyProbe::Status => &1,
+            LatencyProbe::ProofOfIndexing => &2,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for LatencyProbe {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(LatencyProbe::Status),
+            2 => Ok(LatencyProbe::ProofOfIndexing),
+            _ => Err(anyhow::anyhow!("invalid latency probe").into()),
+        }
+    }
+}
@@ -4,17 +4,37 @@
 //! separate? It would be cleaner, but at the cost of some code duplication.
 
 mod api_key_permission_level;
+mod clock;
+mod deployment_lifecycle;
+mod event_kind;
 mod hex_string;
+mod indexer_error_class;
 pub mod inputs;
+mod instance_config;
 mod ipfs_cid;
+mod job_status;
+mod latency_probe;
+mod poi_source;
+mod subgraph_health;
+mod subgraph_manifest;
 
 pub use api_key_permission_level::ApiKeyPermissionLevel;
 use async_graphql::*;
 use chrono::NaiveDateTime;
+pub use clock::{Clock, MockClock, SystemClock};
+pub use deployment_lifecycle::{DeploymentEventKind, DeploymentLifecycleStatus};
 pub use divergence_investigation::*;
+pub use event_kind::EventKind;
 pub use hex_string::HexString;
+pub use indexer_error_class::IndexerErrorClass;
+pub use instance_config::{DeploymentName, IndexerTags, InstanceConfig, InstanceConfigImport};
 pub use ipfs_cid::IpfsCid;
+pub use job_status::JobStatus;
+pub use latency_probe::LatencyProbe;
+pub use poi_source::PoiSource;
 use serde::{Deserialize, Serialize};
+pub use subgraph_health::SubgraphHealth;
+pub use subgraph_manifest::{Graft, SubgraphManifest};
 use uuid::Uuid;
 
 /// A PoI (proof of indexing) is always 32 bytes.
@@ -67,7 +87,7 @@ mod divergence_investigation {
         pub error: Option<String>,
     }
 
-    #[derive(Debug, Clone, Serialize, SimpleObject, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Serialize, SimpleObject, Deserialize)]
     pub struct DivergenceBlockBounds {
         pub lower_bound: PartialBlock,
         pub upper_bound: PartialBlock,
@@ -105,6 +125,12 @@ mod divergence_investigation {
         pub poi1: PoiBytes,
         /// The second PoI that was used to start the bisection run.
         pub poi2: PoiBytes,
+        /// How to reach the operator of the indexer that produced the first
+        /// PoI, if known.
+        pub indexer1_operator_contact: Option<String>,
+        /// How to reach the operator of the indexer that produced the second
+        /// PoI, if known.
+        pub indexer2_operator_contact: Option<String>,
         /// The lower and upper block bounds inside which the bisection run
         /// occurred.
         pub divergence_block_bounds: DivergenceBlockBounds,
@@ -115,10 +141,58 @@ mod divergence_investigation {
         /// If the bisection run failed before reaching a conclusion at a single
         /// block, this field contains the error message.
         pub error: Option<String>,
+        /// How the two indexers' entity changes differ at the diverging block,
+        /// if entity changes were requested and both indexers provided them.
+        #[serde(default)]
+        pub entity_diff: Option<EntityDiff>,
+        /// How the two indexers' cached Ethereum calls differ at the diverging
+        /// block, if eth call caches were requested and both indexers provided
+        /// them.
+        #[serde(default)]
+        pub eth_call_diff: Option<EthCallDiff>,
+        /// Set if the subgraph deployment is grafted and the diverging block
+        /// is at or below its graft block, i.e. the divergence was inherited
+        /// from a graft base.
+        #[serde(default)]
+        pub graft_divergence: Option<GraftDivergence>,
+        /// The graph-node version of the indexer that produced the first PoI,
+        /// if known.
+        #[serde(default)]
+        pub indexer1_graph_node_version: Option<String>,
+        /// The graph-node version of the indexer that produced the second PoI,
+        /// if known.
+        #[serde(default)]
+        pub indexer2_graph_node_version: Option<String>,
+    }
+
+    /// The graft base that a divergence was inherited from.
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct GraftDivergence {
+        /// The graft base that indexed the diverging block. If the graft base
+        /// is grafted itself, this is the base furthest down the graft chain
+        /// whose graft block is still at or above the diverging block.
+        pub base: IpfsCid,
+        /// The block up to which the data of `base` was grafted.
+        pub graft_block: i64,
+        /// The bisection of the two indexers' PoIs of `base`, if requested.
+        pub base_bisection: Option<GraftBaseBisection>,
+    }
+
+    /// A bisection of two indexers' PoIs of a graft base, up to the diverging
+    /// block of the grafted deployment.
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct GraftBaseBisection {
+        /// The lower and upper block bounds inside which the bisection
+        /// occurred.
+        pub divergence_block_bounds: DivergenceBlockBounds,
+        pub bisects: Vec<BisectionReport>,
+        /// Set if the graft base couldn't be bisected, e.g. because the
+        /// indexers' PoIs of the base agree at the diverging block.
+        pub error: Option<String>,
     }
 
     /// Metadata that was collected during a bisection run.
-    #[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
     pub struct BisectionReport {
         /// The block number and hash that this metadata pertains to.
         pub block: PartialBlock,
@@ -129,10 +203,87 @@ mod divergence_investigation {
         /// `graph-node` instance.
         pub indexer2_response: String,
     }
+
+    /// The difference between the entity changes that two indexers'
+    /// `graph-node` instances produced for the same block, keyed by entity
+    /// type and ID. "Added" and "removed" are relative to the first indexer.
+    #[derive(Debug, Clone, Default, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct EntityDiff {
+        /// The block number that the entity changes pertain to.
+        pub block_number: i64,
+        /// Entity changes that only the second indexer produced.
+        pub added: Vec<EntityChange>,
+        /// Entity changes that only the first indexer produced.
+        pub removed: Vec<EntityChange>,
+        /// Entities that both indexers changed, but differently.
+        pub changed: Vec<ChangedEntity>,
+    }
+
+    /// An entity that was updated or deleted in a block.
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct EntityChange {
+        pub entity_type: String,
+        pub id: String,
+        /// The entity after the update, or `null` if it was deleted.
+        pub data: Option<serde_json::Value>,
+    }
+
+    /// An entity that two indexers changed differently in the same block.
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct ChangedEntity {
+        pub entity_type: String,
+        pub id: String,
+        /// The entity according to the first indexer, or `null` if it was
+        /// deleted.
+        pub indexer1_data: Option<serde_json::Value>,
+        /// The entity according to the second indexer, or `null` if it was
+        /// deleted.
+        pub indexer2_data: Option<serde_json::Value>,
+        /// The fields whose values differ, sorted by name. Empty if only one
+        /// of the two indexers deleted the entity.
+        pub changed_fields: Vec<String>,
+    }
+
+    /// The difference between the Ethereum calls that two indexers'
+    /// `graph-node` instances cached for the same block, keyed by call ID
+    /// hash. "Added" and "removed" are relative to the first indexer.
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct EthCallDiff {
+        /// The block number that the cached calls pertain to.
+        pub block_number: i64,
+        /// The hash of the block according to the first indexer. Each
+        /// indexer's call cache is looked up by its own block hash.
+        pub indexer1_block_hash: BlockHash,
+        /// The hash of the block according to the second indexer.
+        pub indexer2_block_hash: BlockHash,
+        /// Calls that only the second indexer cached.
+        pub added: Vec<CachedEthCall>,
+        /// Calls that only the first indexer cached.
+        pub removed: Vec<CachedEthCall>,
+        /// Calls that both indexers cached, but with different return values.
+        pub changed: Vec<ChangedEthCall>,
+    }
+
+    /// An Ethereum call that `graph-node` cached for a block.
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct CachedEthCall {
+        pub id_hash: HexString<Vec<u8>>,
+        pub contract_address: HexString<Vec<u8>>,
+        pub return_value: HexString<Vec<u8>>,
+    }
+
+    /// An Ethereum call that two indexers cached with different return values.
+    #[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
+    pub struct ChangedEthCall {
+        pub id_hash: HexString<Vec<u8>>,
+        pub contract_address: HexString<Vec<u8>>,
+        pub indexer1_return_value: HexString<Vec<u8>>,
+        pub indexer2_return_value: HexString<Vec<u8>>,
+    }
 }
 
 /// A block number that may or may not also have an associated hash.
-#[derive(Debug, Clone, Serialize, SimpleObject, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, SimpleObject, Deserialize)]
 pub struct PartialBlock {
     /// The block number (or height).
     pub number: i64,
@@ -160,15 +311,21 @@ pub struct DivergingBlock {
     pub proof_of_indexing2: PoiBytes,
 }
 
+/// The comparison of the PoIs of two indexers for a subgraph deployment at a
+/// block.
 #[derive(SimpleObject)]
 #[graphql(name = "POICrossCheckReport")]
 pub struct PoiCrossCheckReport {
-    timestamp: String,
-    indexer1: String,
-    indexer2: String,
-    deployment: String,
-    block: PartialBlock,
-    proof_of_indexing1: PoiBytes,
-    proof_of_indexing2: PoiBytes,
-    diverging_block: Option<DivergingBlock>,
+    /// When the more recently collected of the two PoIs was collected, in RFC
+    /// 3339 format.
+    pub timestamp: String,
+    pub indexer1: String,
+    pub indexer2: String,
+    pub deployment: String,
+    pub block: PartialBlock,
+    pub proof_of_indexing1: PoiBytes,
+    pub proof_of_indexing2: PoiBytes,
+    /// The first block at which the two indexers' PoIs diverge, if the PoIs
+    /// differ and a divergence investigation found it.
+    pub diverging_block: Option<DivergingBlock>,
 }
@@ -0,0 +1,54 @@
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::ToSql;
+use diesel::sql_types;
+use serde::Serialize;
+
+/// How Graphix came by a PoI.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    async_graphql::Enum,
+)]
+#[diesel(sql_type = sql_types::Integer)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PoiSource {
+    /// Collected while polling indexers, or backfilling their PoIs.
+    #[default]
+    Polling,
+    /// Fetched while bisecting PoIs during a divergence investigation. These
+    /// PoIs are never live.
+    Investigation,
+}
+
+impl ToSql<sql_types::Integer, Pg> for PoiSource {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            PoiSource::Polling => &1,
+            PoiSource::Investigation => &2,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for PoiSource {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(PoiSource::Polling),
+            2 => Ok(PoiSource::Investigation),
+            _ => Err(anyhow::anyhow!("invalid PoI source").into()),
+        }
+    }
+}
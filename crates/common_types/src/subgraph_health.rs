@@ -0,0 +1,54 @@
+use diesel::deserialize::{FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::ToSql;
+use diesel::sql_types;
+
+/// The health of a subgraph deployment, as reported by `graph-node`.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    AsExpression,
+    FromSqlRow,
+    async_graphql::Enum,
+)]
+#[diesel(sql_type = sql_types::Integer)]
+pub enum SubgraphHealth {
+    /// Syncing normally.
+    Healthy,
+    /// Syncing, but with non-fatal errors.
+    Unhealthy,
+    /// Halted due to a fatal error.
+    Failed,
+}
+
+impl ToSql<sql_types::Integer, Pg> for SubgraphHealth {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        let value = match self {
+            SubgraphHealth::Healthy => &1,
+            SubgraphHealth::Unhealthy => &2,
+            SubgraphHealth::Failed => &3,
+        };
+        <i32 as ToSql<sql_types::Integer, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<sql_types::Integer, Pg> for SubgraphHealth {
+    fn from_sql(bytes: PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match i32::from_sql(bytes)? {
+            1 => Ok(SubgraphHealth::Healthy),
+            2 => Ok(SubgraphHealth::Unhealthy),
+            3 => Ok(SubgraphHealth::Failed),
+            _ => Err(anyhow::anyhow!("invalid subgraph health").into()),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use async_graphql::SimpleObject;
+use serde::{Deserialize, Serialize};
+
+use crate::IpfsCid;
+
+/// Metadata from a subgraph deployment's manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct SubgraphManifest {
+    /// The network of the manifest's first data source, if it has any.
+    pub network: Option<String>,
+    /// The lowest start block of all data sources.
+    pub start_block: i64,
+    /// The features that the manifest declares, e.g. `nonFatalErrors`.
+    pub features: Vec<String>,
+    /// The graft bases of the deployment, starting with its own and followed
+    /// by the base's base etc. Empty if the deployment isn't grafted.
+    pub graft_chain: Vec<Graft>,
+}
+
+/// A subgraph deployment that another one is grafted onto, i.e. whose data
+/// up to `block` the other one reuses instead of indexing it itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct Graft {
+    pub base: IpfsCid,
+    pub block: i64,
+}
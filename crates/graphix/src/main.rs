@@ -3,33 +3,70 @@
 use std::collections::HashSet;
 use std::env;
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
+use anyhow::Context;
 use clap::Parser;
+use graphix_common_types::{IndexerAddress, IpfsCid};
 use graphix_indexer_client::{IndexerClient, IndexerId};
+use graphix_lib::alerts::AlertRouter;
 use graphix_lib::bisect::handle_divergence_investigation_requests;
-use graphix_lib::config::Config;
+use graphix_lib::block_timestamps::sync_block_timestamps;
+use graphix_lib::campaigns::run_campaigns;
+use graphix_lib::config::{Config, ConfigReloadStatus, Environment};
+use graphix_lib::config_check::{check_config, ConfigCheckReport};
+use graphix_lib::deployment_names::sync_deployment_names_periodically;
+use graphix_lib::digests::DivergenceDigests;
+use graphix_lib::events::{DivergenceDetector, UnreachableIndexers};
+use graphix_lib::failed_queries::store_failed_queries;
 use graphix_lib::graphql_api::{axum_router, GraphixState};
-use graphix_lib::indexing_loop::{query_indexing_statuses, query_proofs_of_indexing};
-use graphix_lib::{config, metrics, CliOptions, PrometheusExporter};
-use graphix_store::{models, PoiLiveness, Store};
+use graphix_lib::indexing_lag::update_indexing_lags;
+use graphix_lib::indexing_loop::{
+    classify_indexer_errors, collect_proofs_of_indexing, query_indexing_statuses,
+    query_onchain_pois, sync_network_epochs,
+};
+use graphix_lib::ipfs::sync_subgraph_manifests;
+use graphix_lib::latency_benchmark::run_latency_benchmarks;
+use graphix_lib::leader_election::{LeaderElection, FOLLOWER_POLLING_PERIOD};
+use graphix_lib::network_subgraph_metadata::refresh_network_subgraph_metadata;
+use graphix_lib::poi_backfill::run_poi_backfills;
+use graphix_lib::polling_schedule::PollingSchedule;
+use graphix_lib::subscription_digests::send_subscription_digests_periodically;
+use graphix_lib::substreams::tag_substreams_deployments;
+use graphix_lib::watchlists::{self, Watchlists};
+use graphix_lib::webhooks::WebhookNotifier;
+use graphix_lib::{
+    block_choice, config, deployment_discovery, events, metrics, subgraph_feed, substreams,
+    CliOptions, Command, PrometheusExporter,
+};
+use graphix_store::{models, Store};
 use prometheus_exporter::prometheus;
 use tokio::net::TcpListener;
 use tokio::sync::watch;
+use tokio::time::Instant;
 use tracing::*;
 
-async fn load_config(store: &Store) -> anyhow::Result<Config> {
+/// Loads the configuration, in read-only mode if `read_only` is set and in
+/// the simulation environment if `simulation` is set, regardless of the
+/// stored configuration.
+async fn load_config(store: &Store, read_only: bool, simulation: bool) -> anyhow::Result<Config> {
     info!("Loading configuration from database...");
     let config_json_opt = store.current_config().await?;
 
-    Ok(if let Some(json) = config_json_opt {
+    let mut config = if let Some(json) = config_json_opt {
         serde_json::from_value(json)?
     } else {
         warn!("Missing configuration; using empty configuration");
         Config::default()
-    })
+    };
+    config.read_only |= read_only;
+    if simulation {
+        config.environment = Environment::Simulation;
+    }
+    Ok(config)
 }
 
 #[tokio::main]
@@ -38,18 +75,63 @@ async fn main() -> anyhow::Result<()> {
 
     let cli_options = CliOptions::parse();
 
-    info!("Initialize store and running migrations");
-    let store = Store::new(&cli_options.database_url).await?;
+    // Validating a configuration mustn't run migrations, nor require a
+    // database at all for configuration files.
+    if let Some(Command::CheckConfig { file, .. }) = &cli_options.command {
+        return run_check_config(file.clone(), cli_options.database_url.as_deref()).await;
+    }
+
+    let database_url = cli_options
+        .database_url
+        .clone()
+        .context("missing --database-url")?;
+    let read_only = cli_options.read_only;
+    let simulation = cli_options.simulation;
+    let read_replica_database_url = cli_options.read_replica_database_url.clone();
+    let read_replica_max_lag = Duration::from_secs(cli_options.read_replica_max_lag_seconds);
+    let store = if read_only {
+        info!("Initialize store in read-only mode, without running migrations");
+        Store::new_read_only(&database_url).await?
+    } else {
+        info!("Initialize store and running migrations");
+        Store::new(&database_url).await?
+    }
+    .with_metrics(metrics().store.clone());
     info!("Store initialization successful");
 
-    let (config_sender, config_receiver) = watch::channel(load_config(&store).await?);
+    if let Some(command) = cli_options.command {
+        return run_command(&store, command).await;
+    }
+
+    let (config_sender, config_receiver) =
+        watch::channel(load_config(&store, read_only, simulation).await?);
+    let (main_loop_heartbeat, main_loop_heartbeat_receiver) = watch::channel(None);
+    let (config_reload_status_sender, config_reload_status_receiver) =
+        watch::channel(ConfigReloadStatus::default());
 
+    // All replicas know about all indexers, e.g. to run divergence
+    // investigations, but only the leader polls them.
+    let (tx_indexers, rx_indexers) = watch::channel(vec![]);
+    let (tx_polled_indexers, rx_polled_indexers) = watch::channel(vec![]);
+    let (leader_sender, leader_receiver) = watch::channel(false);
     {
         let config_receiver = config_receiver.clone();
+        let rx_indexers = rx_indexers.clone();
         tokio::spawn(async move {
             axum::serve(
                 TcpListener::bind((Ipv4Addr::UNSPECIFIED, cli_options.port)).await?,
-                axum_router(&cli_options.database_url, config_receiver).await?,
+                axum_router(
+                    &database_url,
+                    read_replica_database_url
+                        .as_deref()
+                        .map(|url| (url, read_replica_max_lag)),
+                    config_receiver,
+                    main_loop_heartbeat_receiver,
+                    config_reload_status_receiver,
+                    rx_indexers,
+                    read_only,
+                )
+                .await?,
             )
             .await?;
 
@@ -57,7 +139,7 @@ async fn main() -> anyhow::Result<()> {
         });
     }
 
-    let mut config = load_config(&store).await?;
+    let mut config = load_config(&store, read_only, simulation).await?;
 
     // Prometheus metrics.
     let _exporter = PrometheusExporter::start(
@@ -66,11 +148,66 @@ async fn main() -> anyhow::Result<()> {
     )?;
 
     info!("Initializing bisect request handler");
-    let (tx_indexers, rx_indexers) = watch::channel(vec![]);
+    let webhook_notifier = WebhookNotifier::new();
+    let mut alert_router = AlertRouter::new();
+    let mut divergence_detector = DivergenceDetector::new();
+    let mut divergence_digests = DivergenceDigests::new();
+    let mut unreachable_indexers = UnreachableIndexers::new();
+    tokio::spawn(run_latency_benchmarks(
+        store.clone(),
+        config_receiver.clone(),
+        rx_polled_indexers.clone(),
+    ));
+    tokio::spawn(sync_subgraph_manifests(
+        store.clone(),
+        config_receiver.clone(),
+        leader_receiver.clone(),
+    ));
+    tokio::spawn(sync_block_timestamps(
+        store.clone(),
+        config_receiver.clone(),
+        leader_receiver.clone(),
+    ));
+    tokio::spawn(refresh_network_subgraph_metadata(
+        store.clone(),
+        config_receiver.clone(),
+        leader_receiver.clone(),
+        metrics(),
+    ));
+    tokio::spawn(sync_deployment_names_periodically(
+        store.clone(),
+        config_receiver.clone(),
+        leader_receiver.clone(),
+        metrics(),
+    ));
+    tokio::spawn(run_campaigns(
+        store.clone(),
+        config_receiver.clone(),
+        leader_receiver.clone(),
+        metrics(),
+    ));
+    tokio::spawn(send_subscription_digests_periodically(
+        store.clone(),
+        config_receiver.clone(),
+        leader_receiver,
+    ));
+    tokio::spawn(run_poi_backfills(
+        store.clone(),
+        config_receiver.clone(),
+        rx_polled_indexers.clone(),
+    ));
+    tokio::spawn(tag_substreams_deployments(
+        store.clone(),
+        config_receiver.clone(),
+        rx_polled_indexers,
+    ));
     {
         let store_clone = store.clone();
 
-        let ctx = GraphixState::new(store_clone.clone(), config_receiver.clone());
+        let ctx = Arc::new(GraphixState::new(
+            store_clone.clone(),
+            config_receiver.clone(),
+        ));
 
         let networks: Vec<models::NewNetwork> = config
             .chains
@@ -80,55 +217,428 @@ async fn main() -> anyhow::Result<()> {
                 caip2: config.caip2.clone(),
             })
             .collect();
-        store_clone.create_networks_if_missing(&networks).await?;
+        if !config.read_only {
+            store_clone.create_networks_if_missing(&networks).await?;
+        }
 
         tokio::spawn(async move {
-            handle_divergence_investigation_requests(&store_clone, rx_indexers, &ctx)
+            handle_divergence_investigation_requests(&store_clone, rx_indexers, ctx)
                 .await
                 .unwrap()
         });
     }
 
-    loop {
-        config = load_config(&store).await?;
-        config_sender.send(config.clone()).ok();
+    if let Some(checkpoint) = store.last_indexing_loop_checkpoint().await? {
+        info!(
+            at = %checkpoint.created_at,
+            indexers = checkpoint.indexer_addresses.len(),
+            "Last indexing loop checkpoint"
+        );
+    }
+
+    // The signal only interrupts the loop between polling cycle stages and
+    // while waiting for indexers' PoIs, so that database writes are never cut
+    // short.
+    let (shutdown_sender, mut shutdown_receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutdown signal received, finishing the current polling cycle stage");
+        shutdown_sender.send(true).ok();
+    });
+
+    let mut indexers = vec![];
+    let mut leader_election = LeaderElection::new(store.clone());
+    let mut polling_schedule = PollingSchedule::new();
+    let mut next_global_cycle_at = Instant::now();
+    let polled_indexers = loop {
+        let new_config = load_config(&store, read_only, simulation).await;
+        let mut config_reload_status = config_reload_status_sender.borrow().clone();
+        config_reload_status.record(&new_config);
+        metrics()
+            .config_reload_failures
+            .set(config_reload_status.consecutive_failures.into());
+        match new_config {
+            Ok(new_config) => {
+                match events::config_changed(&config, &new_config) {
+                    Ok(Some(_)) if new_config.read_only => {}
+                    Ok(Some(event)) => {
+                        if let Err(err) = store.write_events(&[event]).await {
+                            error!(error = %err, "Failed to write config change event");
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(err) => error!(error = %err, "Failed to compare configurations"),
+                }
+                config = new_config;
+                config_sender.send(config.clone()).ok();
+            }
+            Err(err) => error!(
+                error = %err,
+                consecutive_failures = config_reload_status.consecutive_failures,
+                "Failed to reload the configuration, using the previous one"
+            ),
+        }
+        if let Some(warning) = config_reload_status.warning() {
+            warn!("{warning}");
+        }
+        config_reload_status_sender.send(config_reload_status).ok();
+
+        // In read-only mode, stored data is still served, but nothing is
+        // polled or written.
+        if config.read_only {
+            info!("Read-only mode, skipping polling cycle");
+            main_loop_heartbeat.send_replace(Some(SystemTime::now()));
+            tokio::select! {
+                _ = tokio::time::sleep(config.polling_period()) => {}
+                _ = shutdown_receiver.changed() => break indexers,
+            }
+            continue;
+        }
+
+        // Only one replica polls indexers, while the others only keep track
+        // of them, and take over if the leader goes away.
+        let was_leader = leader_election.is_leader();
+        let leader = leader_election.elect(&config).await;
+        metrics().leader.set(leader.into());
+        leader_sender.send_replace(leader);
+        if !leader {
+            tx_polled_indexers.send_replace(vec![]);
+            if Instant::now() >= next_global_cycle_at {
+                next_global_cycle_at = Instant::now() + config.polling_period();
+                let loaded = async {
+                    let configured = config::config_to_indexers(config.clone(), metrics()).await?;
+                    load_indexers(&store, &configured).await
+                };
+                match loaded.await {
+                    Ok(loaded) => {
+                        indexers = loaded;
+                        tx_indexers.send_replace(indexers.clone());
+                    }
+                    Err(err) => error!(error = %err, "Failed to load indexers"),
+                }
+            }
+            main_loop_heartbeat.send_replace(Some(SystemTime::now()));
+            tokio::select! {
+                _ = tokio::time::sleep(config.polling_period().min(FOLLOWER_POLLING_PERIOD)) => {}
+                _ = shutdown_receiver.changed() => break indexers,
+            }
+            continue;
+        }
+        if !was_leader {
+            next_global_cycle_at = Instant::now();
+        }
+
+        // Indexers are looked up again, and global work is done, at the
+        // global polling period. Indexers and chains are polled whenever
+        // they're due, which may be more or less often.
+        if let Err(err) = watchlists::sync_watchlists(&store, &config).await {
+            error!(error = %err, "Failed to sync watchlists from the configuration");
+        }
+        let watchlists = match store.watchlists(None).await {
+            Ok(watchlists) => Watchlists::new(watchlists),
+            Err(err) => {
+                error!(error = %err, "Failed to load watchlists");
+                Watchlists::default()
+            }
+        };
+
+        let now = Instant::now();
+        let global_cycle = now >= next_global_cycle_at;
+        let mut polled_indexers = HashSet::new();
+        let mut version_errors = vec![];
+        if global_cycle {
+            next_global_cycle_at = now + config.polling_period();
+
+            info!("New main loop iteration");
+            info!("Initialize inputs (indexers, indexing statuses etc.)");
+
+            let configured_indexers = config::config_to_indexers(config.clone(), metrics()).await?;
+            indexers = load_indexers(&store, &configured_indexers).await?;
+
+            store.write_indexers(&indexers).await?;
+            // Before deduplication, indexers that were found on multiple
+            // network subgraphs are attributed to all of them.
+            store
+                .write_indexer_network_subgraphs(&configured_indexers)
+                .await?;
+
+            tx_indexers.send(indexers.clone())?;
+            tx_polled_indexers.send_replace(indexers.clone());
+
+            let graph_node_versions =
+                graphix_lib::indexing_loop::query_graph_node_versions(&indexers, metrics()).await;
+            version_errors = classify_indexer_errors(
+                graph_node_versions
+                    .iter()
+                    .filter_map(|(indexer, result)| Some((indexer, result.as_ref().err()?))),
+            );
+            store.write_graph_node_versions(graph_node_versions).await?;
+            polled_indexers.extend(indexers.iter().map(|indexer| indexer.address()));
+        }
 
-        let sleep_duration = Duration::from_secs(config.polling_period_in_seconds);
+        let due_indexers = polling_schedule.due_indexers(&config, &indexers, now);
+        polled_indexers.extend(due_indexers.iter().map(|indexer| indexer.address()));
+        info!(indexers = due_indexers.len(), "Polling due indexers");
 
-        info!("New main loop iteration");
-        info!("Initialize inputs (indexers, indexing statuses etc.)");
+        let (mut indexing_statuses, indexing_status_errors) =
+            query_indexing_statuses(&due_indexers, metrics()).await;
+        if let Err(err) = store_failed_queries(
+            &store,
+            &config.failed_queries,
+            "indexingStatuses",
+            &indexing_status_errors,
+            metrics(),
+        )
+        .await
+        {
+            error!(error = %err, "Failed to store failed queries");
+        }
+
+        let indexer_errors = version_errors
+            .into_iter()
+            .chain(classify_indexer_errors(
+                indexing_status_errors
+                    .iter()
+                    .map(|error| (&error.indexer, &error.inner)),
+            ))
+            .collect::<Vec<_>>();
+        if let Err(err) = store.write_indexer_errors(&indexer_errors).await {
+            error!(error = %err, "Failed to write indexer errors to database");
+        }
+        if let Err(err) = store.write_indexer_circuits(&indexers).await {
+            error!(error = %err, "Failed to write indexer circuit breakers to database");
+        }
+        let unreachable_events = unreachable_indexers.update(&polled_indexers, &indexer_errors);
+        if let Err(err) = store.write_events(&unreachable_events).await {
+            error!(error = %err, "Failed to write unreachable indexer events");
+        }
 
-        let mut indexers = config::config_to_indexers(config.clone(), metrics()).await?;
-        // Different data sources, especially network subgraphs, result in
-        // duplicate indexers.
-        indexers = deduplicate_indexers(&indexers);
+        // Disabled networks keep their data, but we don't collect new PoIs
+        // for them.
+        let disabled_networks = match store.disabled_networks().await {
+            Ok(disabled_networks) => disabled_networks,
+            Err(err) => {
+                error!(error = %err, "Failed to look up disabled networks");
+                Default::default()
+            }
+        };
+        if !disabled_networks.is_empty() {
+            info!(?disabled_networks, "Skipping disabled networks");
+            indexing_statuses.retain(|status| !disabled_networks.contains(&status.network));
+        }
+        if let Err(err) = deployment_discovery::retain_monitored_statuses(
+            &store,
+            &config.deployment_discovery,
+            &watchlists,
+            &mut indexing_statuses,
+        )
+        .await
+        {
+            error!(error = %err, "Failed to look up undiscovered subgraph deployments");
+        }
 
-        store.write_indexers(&indexers).await?;
+        let write_err = store
+            .write_indexing_statuses(&indexing_statuses)
+            .await
+            .err();
+        if let Some(err) = write_err {
+            error!(error = %err, "Failed to write indexing statuses to database");
+        }
+        if let Err(err) = update_indexing_lags(&store, &config, metrics()).await {
+            error!(error = %err, "Failed to update indexing lags");
+        }
 
-        tx_indexers.send(indexers.clone())?;
+        // Paused deployments are expected to fall behind, so they're left out
+        // of divergence detection.
+        let paused: HashSet<(IndexerAddress, IpfsCid)> = indexing_statuses
+            .iter()
+            .filter(|status| status.paused == Some(true))
+            .map(|status| (status.indexer.address(), status.deployment.clone()))
+            .collect();
 
-        let graph_node_versions =
-            graphix_lib::indexing_loop::query_graph_node_versions(&indexers, metrics()).await;
-        store.write_graph_node_versions(graph_node_versions).await?;
+        // All statuses of the polled indexers are written, but PoIs are only
+        // collected for the chains that are due.
+        let indexing_statuses =
+            polling_schedule.due_statuses(&config, &watchlists, &indexing_statuses, now);
 
-        let indexing_statuses = query_indexing_statuses(&indexers, metrics()).await;
+        if *shutdown_receiver.borrow() {
+            break indexers;
+        }
 
         info!("Monitor proofs of indexing");
-        let pois = query_proofs_of_indexing(indexing_statuses, config.block_choice_policy).await;
+        let pois = collect_proofs_of_indexing(
+            &store,
+            indexing_statuses,
+            &config.block_choice_policies(),
+            config.indexer_poi_deadline(),
+            &mut shutdown_receiver,
+            metrics(),
+        )
+        .await;
 
         info!(pois = pois.len(), "Finished tracking Pois");
 
-        let write_err = store.write_pois(pois, PoiLiveness::Live).await.err();
-        if let Some(err) = write_err {
-            error!(error = %err, "Failed to write POIs to database");
+        // The null PoIs of substreams-powered deployments aren't divergences.
+        let pois = match store.substreams_sg_deployments().await {
+            Ok(substreams_deployments) => {
+                substreams::without_expected_nulls(&pois, &substreams_deployments)
+            }
+            Err(err) => {
+                error!(error = %err, "Failed to look up substreams-powered deployments");
+                pois
+            }
+        };
+
+        if config.export_subgraph_feed {
+            if let Err(err) = subgraph_feed::publish(&store, &pois).await {
+                error!(error = %err, "Failed to publish to the subgraph feed");
+            }
+        }
+
+        match divergence_detector.detect(&store, &pois, &paused).await {
+            Ok(diverging_deployments) => {
+                if let Err(err) =
+                    watchlists::investigate_divergences(&store, &watchlists, &diverging_deployments)
+                        .await
+                {
+                    error!(error = %err, "Failed to investigate watchlisted divergences");
+                }
+            }
+            Err(err) => error!(error = %err, "Failed to detect PoI divergences"),
+        }
+        if let Err(err) = webhook_notifier.notify(&store, &watchlists).await {
+            error!(error = %err, "Failed to notify webhooks");
+        }
+        if let Err(err) = alert_router.route(&store, &config.alerts).await {
+            error!(error = %err, "Failed to route alerts");
+        }
+        if let Err(err) = divergence_digests
+            .send_due(
+                &store,
+                &config.alerts,
+                divergence_detector.diverging_indexers(),
+                chrono::Utc::now(),
+            )
+            .await
+        {
+            error!(error = %err, "Failed to send divergence digests");
+        }
+
+        if global_cycle {
+            if let Some(endpoint) = &config.epoch_block_oracle_subgraph {
+                if let Err(err) = sync_network_epochs(&store, endpoint).await {
+                    error!(error = %err, "Failed to sync network epochs");
+                }
+            }
+
+            if *shutdown_receiver.borrow() {
+                break indexers;
+            }
+
+            let onchain_pois =
+                query_onchain_pois(&store, &config.network_subgraphs(), metrics()).await;
+            if let Err(err) = store.write_onchain_pois(&onchain_pois).await {
+                error!(error = %err, "Failed to write on-chain PoIs to database");
+            }
+
+            match store.table_stats().await {
+                Ok(stats) => metrics().set_table_stats(&stats),
+                Err(err) => error!(error = %err, "Failed to sample table stats"),
+            }
         }
 
+        main_loop_heartbeat.send_replace(Some(SystemTime::now()));
+
+        let next_poll_at = polling_schedule
+            .next_poll_at(&config, &indexers)
+            .min(next_global_cycle_at);
         info!(
-            sleep_seconds = sleep_duration.as_secs(),
-            "Sleeping for a while before next main loop iteration"
+            sleep_seconds = next_poll_at
+                .saturating_duration_since(Instant::now())
+                .as_secs(),
+            "Sleeping until the next indexers or chains are due"
         );
-        tokio::time::sleep(sleep_duration).await;
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_poll_at) => {}
+            _ = shutdown_receiver.changed() => break indexers,
+        }
+    };
+
+    if config.read_only || !leader_election.is_leader() {
+        info!("Shutting down");
+        return Ok(());
+    }
+    let polled_indexers: Vec<_> = polled_indexers
+        .iter()
+        .map(|indexer| indexer.address())
+        .collect();
+    store
+        .write_indexing_loop_checkpoint(&polled_indexers)
+        .await?;
+    info!("Wrote indexing loop checkpoint, shutting down");
+
+    Ok(())
+}
+
+/// Resolves once SIGINT or SIGTERM (sent e.g. by Kubernetes) is received.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+async fn run_command(store: &Store, command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::ReplayBlockChoice { deployments } => {
+            for replayed in block_choice::replay(store, &deployments).await? {
+                println!("{replayed}");
+            }
+        }
+        Command::CheckConfig { .. } => unreachable!("handled before initializing the store"),
     }
+
+    Ok(())
+}
+
+/// Validates the configuration file at `file`, or the stored configuration
+/// if there's no file, and fails if it has errors.
+async fn run_check_config(file: Option<PathBuf>, database_url: Option<&str>) -> anyhow::Result<()> {
+    let config = match file {
+        Some(path) => Config::read(path),
+        None => {
+            let database_url = database_url.context("missing --database-url")?;
+            let store = Store::new_read_only(database_url).await?;
+            match store.current_config().await? {
+                Some(json) => serde_json::from_value(json).context("invalid stored configuration"),
+                None => Err(anyhow::anyhow!("no configuration is stored")),
+            }
+        }
+    };
+    let report = match config {
+        Ok(config) => check_config(&config).await,
+        Err(err) => ConfigCheckReport::unloadable(&err),
+    };
+
+    print!("{report}");
+    anyhow::ensure!(report.errors() == 0, "the configuration has errors");
+    Ok(())
 }
 
 fn init_tracing() {
@@ -146,6 +656,21 @@ fn init_tracing() {
         .init();
 }
 
+/// The indexers to poll out of those from all data sources of the
+/// configuration, i.e. without duplicates and archived ones.
+async fn load_indexers(
+    store: &Store,
+    configured_indexers: &[Arc<dyn IndexerClient>],
+) -> anyhow::Result<Vec<Arc<dyn IndexerClient>>> {
+    // Different data sources, especially network subgraphs, result in
+    // duplicate indexers.
+    let mut indexers = deduplicate_indexers(configured_indexers);
+    // Archived indexers keep their data, but we don't poll them anymore.
+    let archived_indexers = store.archived_indexers().await?;
+    indexers.retain(|indexer| !archived_indexers.contains(&indexer.address()));
+    Ok(indexers)
+}
+
 fn deduplicate_indexers(indexers: &[Arc<dyn IndexerClient>]) -> Vec<Arc<dyn IndexerClient>> {
     info!(len = indexers.len(), "Deduplicating indexers");
     let mut seen = HashSet::new();
@@ -0,0 +1,590 @@
+//! Routes events from the event log to alert channels according to
+//! [`AlertsConfig`], so that different teams can be notified of the subgraph
+//! deployments, networks and indexers they're responsible for. Events are
+//! matched against routes by the subgraph deployment they're about, its
+//! network, the tags of the indexers they involve, and their
+//! [`AlertSeverity`]. Mute windows and de-duplication keys keep channels from
+//! being flooded.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use graphix_common_types::inputs::EventsQuery;
+use graphix_common_types::{EventKind, IndexerAddress};
+use graphix_store::models::Event;
+use graphix_store::Store;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::{AlertChannelConfig, AlertMatchers, AlertsConfig, MuteWindowConfig};
+use crate::email::EmailNotifier;
+use crate::metrics;
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The name under which alert routing keeps track of the events it
+/// processed.
+const EVENT_CONSUMER: &str = "alerts";
+const EVENTS_PAGE_SIZE: u16 = 100;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    /// The severity of events of the given kind.
+    pub fn of(kind: EventKind) -> Self {
+        match kind {
+            EventKind::PoiDivergenceDetected => AlertSeverity::Critical,
+            EventKind::IndexerUnreachable => AlertSeverity::Warning,
+            EventKind::InvestigationCompleted | EventKind::ConfigChanged => AlertSeverity::Info,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Info => "info",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+/// An event, together with the details that routes and mute windows match
+/// against.
+#[derive(Debug, Clone)]
+pub struct Alert<'a> {
+    pub event: &'a Event,
+    pub severity: AlertSeverity,
+    /// The network of the subgraph deployment that the event is about.
+    pub network: Option<String>,
+    /// The indexers that the event involves, e.g. the diverging indexers of a
+    /// PoI divergence.
+    pub indexers: BTreeSet<IndexerAddress>,
+    /// The tags of [`Alert::indexers`].
+    pub indexer_tags: BTreeSet<String>,
+}
+
+impl<'a> Alert<'a> {
+    fn new(
+        event: &'a Event,
+        networks: &HashMap<String, String>,
+        indexer_tags: &HashMap<IndexerAddress, Vec<String>>,
+    ) -> Self {
+        let indexers = involved_indexers(event);
+        Self {
+            event,
+            severity: AlertSeverity::of(event.kind),
+            network: event
+                .deployment
+                .as_ref()
+                .and_then(|deployment| networks.get(&deployment.to_string()))
+                .cloned(),
+            indexer_tags: indexers
+                .iter()
+                .filter_map(|indexer| indexer_tags.get(indexer))
+                .flatten()
+                .cloned()
+                .collect(),
+            indexers,
+        }
+    }
+
+    fn matches(&self, matchers: &AlertMatchers) -> bool {
+        matchers.matches(
+            self.event.deployment.as_ref(),
+            self.network.as_deref(),
+            &self.indexer_tags,
+            self.severity,
+        )
+    }
+
+    fn is_muted(&self, mute_window: &MuteWindowConfig, channel: &str) -> bool {
+        let created_at = self.event.created_at.and_utc();
+        (mute_window.channels.is_empty() || mute_window.channels.iter().any(|c| c == channel))
+            && mute_window.start <= created_at
+            && created_at < mute_window.end
+            && self.matches(&mute_window.matchers)
+    }
+
+    /// The kind of the event as in its JSON representation, e.g.
+    /// `POI_DIVERGENCE_DETECTED`.
+    fn kind(&self) -> String {
+        serde_json::to_value(self.event.kind)
+            .ok()
+            .and_then(|kind| kind.as_str().map(ToOwned::to_owned))
+            .unwrap_or_default()
+    }
+
+    /// Increments the `alert_events` Prometheus counter once for each route
+    /// that matches, so that the rules of [`prometheus_rules`] can fire per
+    /// route. Routes are identified by their index, which keeps the number of
+    /// time series bounded by the configuration.
+    fn count(&self, config: &AlertsConfig) {
+        let kind = self.kind();
+        let network = self.network.as_deref().unwrap_or_default();
+        for (i, route) in config.routes.iter().enumerate() {
+            if self.matches(&route.matchers) {
+                metrics()
+                    .alert_events
+                    .with_label_values(&[&i.to_string(), &kind, self.severity.as_str(), network])
+                    .inc();
+            }
+        }
+    }
+
+    /// Fills in the placeholders of a de-duplication key template, see
+    /// [`crate::config::AlertRouteConfig::dedup_key`].
+    fn dedup_key(&self, template: &str) -> String {
+        let indexers = self
+            .indexers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        template
+            .replace("{kind}", &self.kind())
+            .replace("{severity}", self.severity.as_str())
+            .replace(
+                "{deployment}",
+                &self
+                    .event
+                    .deployment
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            )
+            .replace("{network}", self.network.as_deref().unwrap_or_default())
+            .replace("{indexers}", &indexers)
+    }
+}
+
+pub(crate) fn involved_indexers(event: &Event) -> BTreeSet<IndexerAddress> {
+    let addresses = match event.kind {
+        EventKind::PoiDivergenceDetected => event.payload["divergences"]
+            .as_array()
+            .map(|divergences| {
+                divergences
+                    .iter()
+                    .map(|divergence| &divergence["indexerAddress"])
+                    .collect()
+            })
+            .unwrap_or_default(),
+        EventKind::IndexerUnreachable => vec![&event.payload["indexerAddress"]],
+        EventKind::InvestigationCompleted | EventKind::ConfigChanged => vec![],
+    };
+    addresses
+        .into_iter()
+        .filter_map(|address| IndexerAddress::deserialize(address).ok())
+        .collect()
+}
+
+#[derive(Default)]
+pub struct AlertRouter {
+    client: reqwest::Client,
+    /// When an event with each de-duplication key was last delivered to each
+    /// channel. Only kept in memory, so a restart may deliver a duplicate.
+    delivered: HashMap<(String, String), NaiveDateTime>,
+}
+
+impl AlertRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delivers all events appended to the event log since the last call to
+    /// the channels of the routes that match them, starting with the events
+    /// appended after the first call. Failed deliveries are logged and not
+    /// retried.
+    pub async fn route(&mut self, store: &Store, config: &AlertsConfig) -> anyhow::Result<()> {
+        let mut cursor = match store.event_cursor(EVENT_CONSUMER).await? {
+            Some(cursor) => cursor,
+            // Alerting was just enabled, so there's no point in delivering
+            // the whole event history.
+            None => {
+                let cursor = store.latest_event_id().await?.unwrap_or(0);
+                store.set_event_cursor(EVENT_CONSUMER, cursor).await?;
+                cursor
+            }
+        };
+        let mut context = None;
+        let mut deliveries = 0;
+
+        loop {
+            let filter = EventsQuery {
+                after_id: Some(cursor),
+                limit: Some(EVENTS_PAGE_SIZE),
+                ..Default::default()
+            };
+            let events = store.events(filter).await?;
+            let Some(last_event) = events.last() else {
+                break;
+            };
+
+            if context.is_none() {
+                context = Some(routing_context(store).await?);
+            }
+            let (networks, indexer_tags) = context.as_ref().unwrap();
+            for event in &events {
+                let alert = Alert::new(event, networks, indexer_tags);
+                // Counted regardless of mute windows and de-duplication, which
+                // Alertmanager has its own take on.
+                alert.count(config);
+                for name in self.channels(config, &alert) {
+                    let Some(channel) = config.channel(&name) else {
+                        warn!(channel = name, "Alert route refers to an unknown channel");
+                        continue;
+                    };
+                    deliveries += 1;
+                    if let Err(err) = self.deliver(channel, &alert).await {
+                        warn!(channel = name, error = %err, "Failed to deliver alert");
+                    }
+                }
+            }
+
+            cursor = last_event.id;
+            store.set_event_cursor(EVENT_CONSUMER, cursor).await?;
+            if events.len() < EVENTS_PAGE_SIZE as usize {
+                break;
+            }
+        }
+
+        if deliveries > 0 {
+            info!(deliveries, "Delivered alerts");
+        }
+        Ok(())
+    }
+
+    /// The names of the channels that `alert` is to be delivered to, in the
+    /// order of the routes that match it. Records the delivery for
+    /// de-duplication.
+    pub fn channels(&mut self, config: &AlertsConfig, alert: &Alert) -> Vec<String> {
+        let created_at = alert.event.created_at;
+        let max_dedup_window = config
+            .routes
+            .iter()
+            .map(|route| route.dedup_window_in_seconds)
+            .max()
+            .unwrap_or(0);
+        self.delivered.retain(|_, delivered_at| {
+            created_at - *delivered_at < chrono::Duration::seconds(max_dedup_window as i64)
+        });
+
+        let mut channels: Vec<String> = vec![];
+        for route in config
+            .routes
+            .iter()
+            .filter(|route| alert.matches(&route.matchers))
+        {
+            for channel in &route.channels {
+                if channels.contains(channel)
+                    || config
+                        .mute_windows
+                        .iter()
+                        .any(|mute_window| alert.is_muted(mute_window, channel))
+                {
+                    continue;
+                }
+                if let Some(template) = &route.dedup_key {
+                    let key = (channel.clone(), alert.dedup_key(template));
+                    let window = chrono::Duration::seconds(route.dedup_window_in_seconds as i64);
+                    if let Some(delivered_at) = self.delivered.get(&key) {
+                        if created_at - *delivered_at < window {
+                            continue;
+                        }
+                    }
+                    self.delivered.insert(key, created_at);
+                }
+                channels.push(channel.clone());
+            }
+        }
+        channels
+    }
+
+    async fn deliver(&self, channel: &AlertChannelConfig, alert: &Alert<'_>) -> anyhow::Result<()> {
+        match channel {
+            AlertChannelConfig::Webhook(webhook) => {
+                self.client
+                    .post(webhook.url.clone())
+                    .timeout(DELIVERY_TIMEOUT)
+                    .json(alert.event)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            AlertChannelConfig::Email(email) => {
+                let mut subject =
+                    format!("[Graphix] {}: {}", alert.severity.as_str(), alert.kind());
+                if let Some(deployment) = &alert.event.deployment {
+                    subject.push_str(&format!(" on {deployment}"));
+                }
+                EmailNotifier::new(email)?
+                    .send(&subject, serde_json::to_string_pretty(alert.event)?)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How far back the rules of [`prometheus_rules`] look for new events. Longer
+/// than any polling period, so that no events are missed.
+const PROMETHEUS_RULES_RANGE: &str = "10m";
+
+/// A Prometheus rules file, see
+/// <https://prometheus.io/docs/prometheus/latest/configuration/alerting_rules/>.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrometheusRules {
+    pub groups: Vec<PrometheusRuleGroup>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrometheusRuleGroup {
+    pub name: String,
+    pub rules: Vec<PrometheusRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrometheusRule {
+    pub alert: String,
+    pub expr: String,
+    pub labels: BTreeMap<String, String>,
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// Renders [`AlertsConfig::routes`] as Prometheus alerting rules, for teams
+/// that route alerts through Alertmanager. Each route gets a rule per
+/// channel, which fires while events that match the route increase the
+/// `alert_events` counter, with the channel's name in the `channel` label for
+/// Alertmanager to route by. Mute windows and de-duplication keys aren't
+/// exported, as Alertmanager has silences and grouping for that.
+pub fn prometheus_rules(config: &AlertsConfig) -> PrometheusRules {
+    let rules = config
+        .routes
+        .iter()
+        .enumerate()
+        .flat_map(|(i, route)| {
+            let expr = format!(
+                "sum by (kind, severity, network) \
+                 (increase(alert_events{{route=\"{i}\"}}[{PROMETHEUS_RULES_RANGE}])) > 0",
+            );
+            route.channels.iter().map(move |channel| PrometheusRule {
+                alert: format!("GraphixAlertRoute{i}"),
+                expr: expr.clone(),
+                labels: BTreeMap::from([
+                    ("channel".to_string(), channel.clone()),
+                    ("graphix_route".to_string(), i.to_string()),
+                ]),
+                annotations: BTreeMap::from([(
+                    "summary".to_string(),
+                    "{{ $labels.kind }} ({{ $labels.severity }}) on {{ $labels.network }}"
+                        .to_string(),
+                )]),
+            })
+        })
+        .collect();
+
+    PrometheusRules {
+        groups: vec![PrometheusRuleGroup {
+            name: "graphix".to_string(),
+            rules,
+        }],
+    }
+}
+
+/// The network of each subgraph deployment, by IPFS CID, and the tags of
+/// each indexer.
+pub(crate) async fn routing_context(
+    store: &Store,
+) -> anyhow::Result<(
+    HashMap<String, String>,
+    HashMap<IndexerAddress, Vec<String>>,
+)> {
+    let network_names: HashMap<_, _> = store
+        .networks()
+        .await?
+        .into_iter()
+        .map(|network| (network.id, network.name))
+        .collect();
+    let networks = store
+        .sg_deployments(Default::default())
+        .await?
+        .into_iter()
+        .filter_map(|deployment| {
+            let network = network_names.get(&deployment.network_id)?.clone();
+            Some((deployment.cid.to_string(), network))
+        })
+        .collect();
+    let indexer_tags = store
+        .indexers(Default::default())
+        .await?
+        .into_iter()
+        .map(|indexer| (indexer.address, indexer.tags))
+        .collect();
+
+    Ok((networks, indexer_tags))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use graphix_common_types::IpfsCid;
+
+    use super::*;
+
+    const DEPLOYMENT: &str = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+
+    fn new_event(kind: EventKind, payload: serde_json::Value, minute: u32) -> Event {
+        Event {
+            id: 1,
+            kind,
+            deployment: Some(IpfsCid::from_str(DEPLOYMENT).unwrap()),
+            payload,
+            created_at: chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+                .unwrap()
+                .and_hms_opt(12, minute, 0)
+                .unwrap(),
+        }
+    }
+
+    fn divergence(indexer: u8, minute: u32) -> Event {
+        new_event(
+            EventKind::PoiDivergenceDetected,
+            serde_json::json!({
+                "divergences": [{ "indexerAddress": IndexerAddress::from([indexer; 20]) }],
+            }),
+            minute,
+        )
+    }
+
+    fn alert(event: &Event) -> Alert {
+        let networks = HashMap::from([(DEPLOYMENT.to_string(), "mainnet".to_string())]);
+        let indexer_tags = HashMap::from([
+            (IndexerAddress::from([1; 20]), vec!["team-a".to_string()]),
+            (IndexerAddress::from([2; 20]), vec!["team-b".to_string()]),
+        ]);
+        Alert::new(event, &networks, &indexer_tags)
+    }
+
+    fn config(yaml: &str) -> AlertsConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn routes_by_indexer_tag_and_severity() {
+        let config = config(
+            r#"
+            routes:
+              - indexerTags: [team-a]
+                channels: [team-a]
+              - networks: [mainnet]
+                minSeverity: critical
+                channels: [oncall, team-a]
+              - networks: [sepolia]
+                channels: [testnets]
+            "#,
+        );
+        let mut router = AlertRouter::new();
+
+        let event = divergence(1, 0);
+        assert_eq!(
+            router.channels(&config, &alert(&event)),
+            ["team-a", "oncall"]
+        );
+        let event = divergence(2, 0);
+        assert_eq!(
+            router.channels(&config, &alert(&event)),
+            ["oncall", "team-a"]
+        );
+        let event = new_event(
+            EventKind::IndexerUnreachable,
+            serde_json::json!({ "indexerAddress": IndexerAddress::from([2; 20]) }),
+            0,
+        );
+        assert!(router.channels(&config, &alert(&event)).is_empty());
+    }
+
+    #[test]
+    fn mute_windows_and_dedup_keys() {
+        let config = config(
+            r#"
+            routes:
+              - channels: [oncall, team-a]
+                dedupKey: "{kind}:{deployment}"
+                dedupWindowInSeconds: 600
+            muteWindows:
+              - start: 2025-01-01T12:30:00Z
+                end: 2025-01-01T13:00:00Z
+                channels: [team-a]
+            "#,
+        );
+        let mut router = AlertRouter::new();
+
+        let event = divergence(1, 0);
+        assert_eq!(
+            router.channels(&config, &alert(&event)),
+            ["oncall", "team-a"]
+        );
+        // Within the de-duplication window.
+        let event = divergence(2, 5);
+        assert!(router.channels(&config, &alert(&event)).is_empty());
+        let event = divergence(2, 10);
+        assert_eq!(
+            router.channels(&config, &alert(&event)),
+            ["oncall", "team-a"]
+        );
+        // Within the mute window of `team-a`.
+        let event = divergence(2, 30);
+        assert_eq!(router.channels(&config, &alert(&event)), ["oncall"]);
+        let event = divergence(2, 35);
+        assert!(router.channels(&config, &alert(&event)).is_empty());
+    }
+
+    #[test]
+    fn routes_are_rendered_as_prometheus_rules() {
+        let config = config(
+            r#"
+            routes:
+              - networks: [mainnet, "arbitrum-one"]
+                minSeverity: warning
+                channels: [oncall, team-a]
+              - indexerTags: ["a.b"]
+                channels: [team-b]
+            "#,
+        );
+        let rules = prometheus_rules(&config);
+
+        let rules = &rules.groups[0].rules;
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].alert, "GraphixAlertRoute0");
+        assert_eq!(rules[0].labels["channel"], "oncall");
+        assert_eq!(rules[1].labels["channel"], "team-a");
+        assert_eq!(
+            rules[0].expr,
+            r#"sum by (kind, severity, network) (increase(alert_events{route="0"}[10m])) > 0"#
+        );
+        assert_eq!(rules[2].labels["graphix_route"], "1");
+        assert!(rules[2].expr.contains(r#"{route="1"}"#));
+    }
+
+    #[test]
+    fn dedup_key_placeholders() {
+        let event = divergence(1, 0);
+        assert_eq!(
+            alert(&event).dedup_key("{kind}/{severity}/{network}/{deployment}/{indexers}"),
+            format!(
+                "POI_DIVERGENCE_DETECTED/critical/mainnet/{DEPLOYMENT}/{}",
+                IndexerAddress::from([1; 20])
+            )
+        );
+    }
+}
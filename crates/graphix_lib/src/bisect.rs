@@ -1,24 +1,60 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::Hash;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::anyhow;
 use graphix_common_types::{
-    BisectionReport, BisectionRunReport, DivergenceBlockBounds, DivergenceInvestigationReport,
-    DivergenceInvestigationStatus, DivergingBlock as DivergentBlock, HexString, PartialBlock,
+    BisectionReport, BisectionRunReport, BlockHash, CachedEthCall, ChangedEntity, ChangedEthCall,
+    DivergenceBlockBounds, DivergenceInvestigationReport, DivergenceInvestigationStatus,
+    DivergingBlock as DivergentBlock, EntityChange, EntityDiff, EthCallDiff, Graft,
+    GraftBaseBisection, GraftDivergence, HexString, IndexerAddress, IpfsCid, PartialBlock,
     PoiBytes,
 };
-use graphix_indexer_client::{IndexerClient, IndexerId, PoiRequest, ProofOfIndexing};
-use graphix_store::models::DivergenceInvestigationRequest;
+use graphix_indexer_client::{
+    BlockPointer, CachedEthereumCall, EntityChanges, IndexerClient, IndexerId, PoiRequest,
+    ProofOfIndexing,
+};
+use graphix_store::models::{self, BigIntId, DivergenceInvestigationRequest};
 use graphix_store::Store;
 use thiserror::Error;
-use tokio::sync::watch;
-use tracing::{debug, error, info};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+use crate::events;
 use crate::graphql_api::api_types::{self, Indexer};
 use crate::graphql_api::GraphixState;
+use crate::metrics;
+
+/// The most PoIs that a single divergence investigation compares.
+pub const MAX_INVESTIGATED_POIS: usize = 4;
+
+/// The maximum number of bisection runs of a single divergence investigation
+/// that are performed concurrently.
+const MAX_CONCURRENT_BISECTION_RUNS: usize = 4;
+
+/// The maximum number of concurrent PoI requests that bisection runs send to
+/// the same indexer. Each PoI of an investigation with four PoIs takes part in
+/// three bisection runs.
+const MAX_CONCURRENT_REQUESTS_PER_INDEXER: usize = 2;
+
+/// Per-indexer semaphores shared by the concurrent bisection runs of a
+/// divergence investigation, to cap the load on each indexer.
+#[derive(Clone, Default)]
+struct IndexerPermits(Arc<Mutex<HashMap<IndexerAddress, Arc<Semaphore>>>>);
+
+impl IndexerPermits {
+    fn get(&self, indexer: IndexerAddress) -> Arc<Semaphore> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(indexer)
+            .or_insert_with(|| Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS_PER_INDEXER)))
+            .clone()
+    }
+}
 
 pub struct DivergingBlock {
     pub poi1: ProofOfIndexing,
@@ -43,6 +79,8 @@ pub struct PoiBisectingContext {
     bisection_id: Uuid,
     poi1_data: PoiWithRelatedData,
     poi2_data: PoiWithRelatedData,
+    permits: IndexerPermits,
+    store: Store,
 }
 
 impl PoiBisectingContext {
@@ -51,6 +89,8 @@ impl PoiBisectingContext {
         bisection_id: Uuid,
         poi1_data: PoiWithRelatedData,
         poi2_data: PoiWithRelatedData,
+        permits: IndexerPermits,
+        store: Store,
     ) -> anyhow::Result<Self> {
         // Before attempting to bisect Pois, we need to make sure that the Pois refer to:
         // 1. the same subgraph deployment, and
@@ -70,6 +110,8 @@ impl PoiBisectingContext {
             bisection_id,
             poi1_data,
             poi2_data,
+            permits,
+            store,
         })
     }
 
@@ -78,76 +120,551 @@ impl PoiBisectingContext {
     }
 
     pub async fn start(mut self) -> (BisectionRunReport, u64) {
-        let deployment: api_types::SubgraphDeployment = self.deployment().clone();
-
-        let indexer1 = self.poi1_data.indexer_client.clone();
-        let indexer2 = self.poi2_data.indexer_client.clone();
+        let subject = BisectionSubject {
+            deployment: self.deployment().cid().clone(),
+            network: self.poi1_data.network.clone(),
+            indexer1: self.poi1_data.indexer_client.clone(),
+            indexer2: self.poi2_data.indexer_client.clone(),
+            store: Some(self.store.clone()),
+        };
 
         info!(
             bisection_id = %self.bisection_id,
-            deployment = ?deployment.cid(),
+            deployment = ?subject.deployment,
             "Starting Poi bisecting"
         );
 
+        let diverging_block = subject
+            .bisect(
+                &self.bisection_id,
+                &self.permits,
+                self.poi1_data.block.number(),
+                &mut self.report.bisects,
+                &mut self.report.divergence_block_bounds,
+            )
+            .await;
+        (self.report, diverging_block)
+    }
+}
+
+/// Two indexers whose PoIs of a subgraph deployment are bisected.
+struct BisectionSubject {
+    deployment: IpfsCid,
+    network: String,
+    indexer1: Arc<dyn IndexerClient>,
+    indexer2: Arc<dyn IndexerClient>,
+    /// Where PoIs fetched from the indexers are kept, and looked up before
+    /// fetching them again. PoIs of past blocks never change, so later
+    /// investigations of the same deployment reuse them.
+    store: Option<Store>,
+}
+
+impl BisectionSubject {
+    /// Bisects the two indexers' PoIs between the earliest block that both
+    /// still have indexed and `upper_bound`, recording each bisect and
+    /// narrowing down `bounds` as it goes. Returns the first block at which
+    /// the PoIs differ, assuming that they do differ at `upper_bound`. If they
+    /// already differ at the earliest block, that's the block returned, as
+    /// PoIs of pruned blocks can't be compared.
+    async fn bisect(
+        &self,
+        bisection_id: &Uuid,
+        permits: &IndexerPermits,
+        upper_bound: u64,
+        bisects: &mut Vec<BisectionReport>,
+        bounds: &mut DivergenceBlockBounds,
+    ) -> u64 {
+        let permits1 = permits.get(self.indexer1.address());
+        let permits2 = permits.get(self.indexer2.address());
+
+        let earliest_block = self
+            .earliest_block(&permits1, &permits2)
+            .await
+            .unwrap_or(0)
+            .min(upper_bound);
+        if earliest_block > 0 {
+            debug!(
+                deployment = %self.deployment,
+                earliest_block,
+                "Starting bisection at the earliest block both indexers indexed"
+            );
+            bounds.lower_bound.number = earliest_block as _;
+        }
+
         // The range of block numbers that we're investigating is bounded
         // inclusively both below and above. The bisection algorithm will
         // continue searching until only a single block number is left in the
         // range.
-        let mut bounds = 0..=self.poi1_data.block.number();
+        let mut range = earliest_block..=upper_bound;
 
         loop {
-            let block_number = (bounds.start() + bounds.end()) / 2;
+            let block_number = (range.start() + range.end()) / 2;
 
             debug!(
-                bisection_id = %self.bisection_id,
-                deployment = ?deployment.cid(),
-                lower_bound = ?bounds.start(),
-                upper_bound = ?bounds.end(),
+                %bisection_id,
+                deployment = ?self.deployment,
+                lower_bound = ?range.start(),
+                upper_bound = ?range.end(),
                 block_number,
                 "Bisecting Pois"
             );
 
-            let poi1 = indexer1
-                .clone()
-                .proof_of_indexing(PoiRequest {
-                    deployment: deployment.cid().clone(),
-                    block_number,
-                })
-                .await;
-            let poi2 = indexer2
-                .clone()
-                .proof_of_indexing(PoiRequest {
-                    deployment: deployment.cid().clone(),
-                    block_number,
-                })
-                .await;
+            let (poi1, poi2) = tokio::join!(
+                self.proof_of_indexing(&self.indexer1, &permits1, block_number),
+                self.proof_of_indexing(&self.indexer2, &permits2, block_number),
+            );
 
-            let bisect = BisectionReport {
+            metrics().bisection_steps.inc();
+            bisects.push(BisectionReport {
                 block: PartialBlock {
                     number: block_number as _,
                     hash: None,
                 },
                 indexer1_response: format!("{:?}", poi1),
                 indexer2_response: format!("{:?}", poi2),
+            });
+
+            // Only the PoIs themselves are compared, as the responses also
+            // carry the indexer that sent them.
+            let agree = match (&poi1, &poi2) {
+                (Ok(poi1), Ok(poi2)) => poi1.proof_of_indexing == poi2.proof_of_indexing,
+                (Err(_), Err(_)) => true,
+                _ => false,
             };
-            self.report.bisects.push(bisect);
 
-            if poi1.ok() == poi2.ok() {
-                bounds = block_number..=*bounds.end();
-                self.report.divergence_block_bounds.lower_bound.number = block_number as _;
+            // The search must skip `block_number` once it agrees, otherwise it
+            // never terminates when only two block numbers are left.
+            if agree {
+                range = block_number + 1..=*range.end();
+                bounds.lower_bound.number = block_number as _;
             } else {
-                bounds = *bounds.start()..=block_number;
-                self.report.divergence_block_bounds.upper_bound.number = block_number as _;
+                range = *range.start()..=block_number;
+                bounds.upper_bound.number = block_number as _;
             }
 
-            if bounds.start() == bounds.end() {
+            if range.start() >= range.end() {
                 break;
             }
         }
 
-        let diverging_block = *bounds.start();
-        (self.report, diverging_block)
+        *range.start()
+    }
+
+    /// The PoI of `indexer` at `block_number`, from the store if it's there
+    /// and otherwise from the indexer, in which case it's stored for later
+    /// bisections.
+    async fn proof_of_indexing(
+        &self,
+        indexer: &Arc<dyn IndexerClient>,
+        permits: &Semaphore,
+        block_number: u64,
+    ) -> anyhow::Result<ProofOfIndexing> {
+        let Some(store) = &self.store else {
+            let request = PoiRequest {
+                deployment: self.deployment.clone(),
+                network: self.network.clone(),
+                block_number,
+            };
+            return limited_proof_of_indexing(indexer, permits, request).await;
+        };
+
+        match store
+            .stored_poi(
+                &indexer.address(),
+                &self.deployment,
+                &self.network,
+                block_number,
+            )
+            .await
+        {
+            Ok(Some((poi, block_hash))) => {
+                return Ok(ProofOfIndexing {
+                    indexer: indexer.clone(),
+                    deployment: self.deployment.clone(),
+                    network: self.network.clone(),
+                    block: BlockPointer {
+                        number: block_number,
+                        hash: Some(block_hash),
+                    },
+                    proof_of_indexing: poi.poi,
+                });
+            }
+            Ok(None) => {}
+            Err(err) => {
+                warn!(
+                    indexer = %indexer.address(),
+                    deployment = %self.deployment,
+                    block_number,
+                    error = %err,
+                    "Failed to look up a stored PoI"
+                );
+            }
+        }
+
+        let request = PoiRequest {
+            deployment: self.deployment.clone(),
+            network: self.network.clone(),
+            block_number,
+        };
+        let poi = limited_proof_of_indexing(indexer, permits, request).await?;
+        if let Err(err) = store.write_investigation_pois(vec![poi.clone()]).await {
+            warn!(
+                indexer = %indexer.address(),
+                deployment = %self.deployment,
+                block_number,
+                error = %err,
+                "Failed to store a PoI fetched while bisecting"
+            );
+        }
+
+        Ok(poi)
+    }
+
+    /// The earliest block that both indexers have indexed, according to their
+    /// indexing statuses, or that either has if the other's is unknown. Below
+    /// it, at least one of them has pruned its PoIs.
+    async fn earliest_block(&self, permits1: &Semaphore, permits2: &Semaphore) -> Option<u64> {
+        let (earliest1, earliest2) = tokio::join!(
+            limited_earliest_block(&self.indexer1, permits1, &self.deployment, &self.network),
+            limited_earliest_block(&self.indexer2, permits2, &self.deployment, &self.network),
+        );
+
+        [(&self.indexer1, earliest1), (&self.indexer2, earliest2)]
+            .into_iter()
+            .filter_map(|(indexer, earliest_block)| match earliest_block {
+                Ok(earliest_block) => earliest_block,
+                Err(err) => {
+                    warn!(
+                        indexer = %indexer.address(),
+                        deployment = %self.deployment,
+                        error = %err,
+                        "Failed to fetch the earliest indexed block"
+                    );
+                    None
+                }
+            })
+            .max()
+    }
+}
+
+/// The graft in `graft_chain` whose base indexed `diverging_block`, if any.
+/// That's the base furthest down the chain whose graft block is still at or
+/// above the diverging block.
+pub fn graft_of_diverging_block(graft_chain: &[Graft], diverging_block: u64) -> Option<&Graft> {
+    graft_chain
+        .iter()
+        .take_while(|graft| diverging_block as i64 <= graft.block)
+        .last()
+}
+
+/// Bisects the two indexers' PoIs of a graft base up to the diverging block of
+/// the deployment grafted onto it, if they differ at that block.
+async fn bisect_graft_base(
+    subject: &BisectionSubject,
+    bisection_id: &Uuid,
+    permits: &IndexerPermits,
+    diverging_block: u64,
+) -> GraftBaseBisection {
+    let mut bisection = GraftBaseBisection {
+        divergence_block_bounds: DivergenceBlockBounds {
+            lower_bound: PartialBlock {
+                number: 0,
+                hash: None,
+            },
+            upper_bound: PartialBlock {
+                number: diverging_block as i64,
+                hash: None,
+            },
+        },
+        bisects: vec![],
+        error: None,
+    };
+
+    let permits1 = permits.get(subject.indexer1.address());
+    let permits2 = permits.get(subject.indexer2.address());
+    let (poi1, poi2) = tokio::join!(
+        subject.proof_of_indexing(&subject.indexer1, &permits1, diverging_block),
+        subject.proof_of_indexing(&subject.indexer2, &permits2, diverging_block),
+    );
+    match (poi1, poi2) {
+        (Err(err), _) | (_, Err(err)) => {
+            bisection.error = Some(format!("Failed to fetch a PoI of the graft base: {err}"));
+        }
+        (Ok(poi1), Ok(poi2)) if poi1.proof_of_indexing == poi2.proof_of_indexing => {
+            bisection.error =
+                Some("The PoIs of the graft base agree at the diverging block".to_string());
+        }
+        _ => {
+            subject
+                .bisect(
+                    bisection_id,
+                    permits,
+                    diverging_block,
+                    &mut bisection.bisects,
+                    &mut bisection.divergence_block_bounds,
+                )
+                .await;
+        }
+    }
+
+    bisection
+}
+
+async fn limited_proof_of_indexing(
+    indexer: &Arc<dyn IndexerClient>,
+    permits: &Semaphore,
+    request: PoiRequest,
+) -> anyhow::Result<ProofOfIndexing> {
+    let _permit = permits.acquire().await?;
+    indexer.clone().proof_of_indexing(request).await
+}
+
+/// The earliest block of `deployment` on `network` that `indexer` has
+/// indexed, or `None` if it doesn't index the deployment. Only the indexing
+/// status of `deployment` is requested, rather than those of all deployments
+/// of the indexer.
+async fn limited_earliest_block(
+    indexer: &Arc<dyn IndexerClient>,
+    permits: &Semaphore,
+    deployment: &IpfsCid,
+    network: &str,
+) -> anyhow::Result<Option<u64>> {
+    let _permit = permits.acquire().await?;
+    let statuses = indexer
+        .clone()
+        .deployment_indexing_statuses(deployment)
+        .await?;
+
+    Ok(statuses
+        .into_iter()
+        .find(|status| &status.deployment == deployment && status.network == network)
+        .map(|status| status.earliest_block_num))
+}
+
+async fn limited_entity_changes(
+    indexer: &Arc<dyn IndexerClient>,
+    permits: &Semaphore,
+    deployment: &IpfsCid,
+    block_number: u64,
+) -> anyhow::Result<EntityChanges> {
+    let _permit = permits.acquire().await?;
+    indexer
+        .clone()
+        .entity_changes(&deployment.to_string(), block_number)
+        .await
+}
+
+/// Fetches the entity changes of both indexers at `block_number` and diffs
+/// them.
+async fn fetch_entity_diff(
+    indexer1: &Arc<dyn IndexerClient>,
+    indexer2: &Arc<dyn IndexerClient>,
+    permits: &IndexerPermits,
+    deployment: &IpfsCid,
+    block_number: u64,
+) -> anyhow::Result<EntityDiff> {
+    let permits1 = permits.get(indexer1.address());
+    let permits2 = permits.get(indexer2.address());
+    let (changes1, changes2) = tokio::try_join!(
+        limited_entity_changes(indexer1, &permits1, deployment, block_number),
+        limited_entity_changes(indexer2, &permits2, deployment, block_number),
+    )?;
+
+    Ok(entity_diff(block_number as i64, &changes1, &changes2))
+}
+
+async fn limited_cached_eth_calls(
+    indexer: &Arc<dyn IndexerClient>,
+    permits: &Semaphore,
+    network: &str,
+    block_hash: &BlockHash,
+) -> anyhow::Result<Vec<CachedEthereumCall>> {
+    let _permit = permits.acquire().await?;
+    indexer
+        .clone()
+        .cached_eth_calls(network, &block_hash.0)
+        .await
+}
+
+/// Fetches the cached Ethereum calls of both indexers at `block_number` and
+/// diffs them. Call caches are keyed by block hash, so each indexer's is looked
+/// up by the block hash of its own PoI at that block.
+async fn fetch_eth_call_diff(
+    indexer1: &Arc<dyn IndexerClient>,
+    indexer2: &Arc<dyn IndexerClient>,
+    permits: &IndexerPermits,
+    deployment: &IpfsCid,
+    network: &str,
+    block_number: u64,
+) -> anyhow::Result<EthCallDiff> {
+    let permits1 = permits.get(indexer1.address());
+    let permits2 = permits.get(indexer2.address());
+    let request = PoiRequest {
+        deployment: deployment.clone(),
+        network: network.to_string(),
+        block_number,
+    };
+    let (poi1, poi2) = tokio::try_join!(
+        limited_proof_of_indexing(indexer1, &permits1, request.clone()),
+        limited_proof_of_indexing(indexer2, &permits2, request),
+    )?;
+    let block_hash = |poi: ProofOfIndexing| {
+        poi.block.hash.ok_or_else(|| {
+            anyhow!(
+                "indexer {} reported no hash for block {}",
+                poi.indexer.address(),
+                block_number
+            )
+        })
+    };
+    let (hash1, hash2) = (block_hash(poi1)?, block_hash(poi2)?);
+
+    let (calls1, calls2) = tokio::try_join!(
+        limited_cached_eth_calls(indexer1, &permits1, network, &hash1),
+        limited_cached_eth_calls(indexer2, &permits2, network, &hash2),
+    )?;
+
+    Ok(eth_call_diff(
+        block_number as i64,
+        hash1,
+        hash2,
+        &calls1,
+        &calls2,
+    ))
+}
+
+/// Computes which Ethereum calls only one of two indexers cached at a block,
+/// and which they both cached but with different return values. The result is
+/// sorted by call ID hash.
+pub fn eth_call_diff(
+    block_number: i64,
+    indexer1_block_hash: BlockHash,
+    indexer2_block_hash: BlockHash,
+    calls1: &[CachedEthereumCall],
+    calls2: &[CachedEthereumCall],
+) -> EthCallDiff {
+    fn by_id_hash(calls: &[CachedEthereumCall]) -> BTreeMap<Vec<u8>, &CachedEthereumCall> {
+        calls
+            .iter()
+            .map(|call| (call.id_hash.clone(), call))
+            .collect()
+    }
+    let cached = |call: &CachedEthereumCall| CachedEthCall {
+        id_hash: HexString(call.id_hash.clone()),
+        contract_address: HexString(call.contract_address.clone()),
+        return_value: HexString(call.return_value.clone()),
+    };
+    let mut calls1 = by_id_hash(calls1);
+    let calls2 = by_id_hash(calls2);
+
+    let mut diff = EthCallDiff {
+        block_number,
+        indexer1_block_hash,
+        indexer2_block_hash,
+        added: vec![],
+        removed: vec![],
+        changed: vec![],
+    };
+    for (id_hash, call2) in calls2 {
+        match calls1.remove(&id_hash) {
+            None => diff.added.push(cached(call2)),
+            Some(call1) if call1.return_value != call2.return_value => {
+                diff.changed.push(ChangedEthCall {
+                    id_hash: HexString(id_hash),
+                    contract_address: HexString(call2.contract_address.clone()),
+                    indexer1_return_value: HexString(call1.return_value.clone()),
+                    indexer2_return_value: HexString(call2.return_value.clone()),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    diff.removed = calls1.into_values().map(cached).collect();
+
+    diff
+}
+
+/// All changed entities by entity type and ID, with `None` for deletions.
+fn entity_changes_by_key(
+    changes: &EntityChanges,
+) -> BTreeMap<(String, String), Option<serde_json::Value>> {
+    let mut by_key = BTreeMap::new();
+    for (entity_type, entities) in &changes.updates {
+        for entity in entities {
+            let id = match entity.get("id") {
+                Some(serde_json::Value::String(id)) => id.clone(),
+                Some(id) => id.to_string(),
+                None => String::new(),
+            };
+            by_key.insert((entity_type.clone(), id), Some(entity.clone()));
+        }
+    }
+    for (entity_type, ids) in &changes.deletions {
+        for id in ids {
+            by_key.insert((entity_type.clone(), id.clone()), None);
+        }
+    }
+    by_key
+}
+
+/// Computes which entities only one of two indexers changed at a block, and
+/// which they both changed but differently. The result is sorted by entity
+/// type and ID.
+pub fn entity_diff(
+    block_number: i64,
+    changes1: &EntityChanges,
+    changes2: &EntityChanges,
+) -> EntityDiff {
+    let mut changes1 = entity_changes_by_key(changes1);
+    let changes2 = entity_changes_by_key(changes2);
+
+    let mut diff = EntityDiff {
+        block_number,
+        ..Default::default()
+    };
+    for ((entity_type, id), data2) in changes2 {
+        match changes1.remove(&(entity_type.clone(), id.clone())) {
+            None => diff.added.push(EntityChange {
+                entity_type,
+                id,
+                data: data2,
+            }),
+            Some(data1) if data1 != data2 => {
+                let changed_fields = match (&data1, &data2) {
+                    (
+                        Some(serde_json::Value::Object(fields1)),
+                        Some(serde_json::Value::Object(fields2)),
+                    ) => fields1
+                        .keys()
+                        .chain(fields2.keys())
+                        .filter(|field| fields1.get(*field) != fields2.get(*field))
+                        .cloned()
+                        .collect::<BTreeSet<_>>()
+                        .into_iter()
+                        .collect(),
+                    _ => vec![],
+                };
+                diff.changed.push(ChangedEntity {
+                    entity_type,
+                    id,
+                    indexer1_data: data1,
+                    indexer2_data: data2,
+                    changed_fields,
+                });
+            }
+            Some(_) => {}
+        }
     }
+    diff.removed = changes1
+        .into_iter()
+        .map(|((entity_type, id), data)| EntityChange {
+            entity_type,
+            id,
+            data,
+        })
+        .collect();
+
+    diff
 }
 
 #[derive(Debug, Error)]
@@ -178,51 +695,224 @@ pub enum DivergenceInvestigationError {
     Database(anyhow::Error),
 }
 
+impl DivergenceInvestigationError {
+    /// The `error` label of the `divergence_investigation_errors` metric.
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::TooManyPois { .. } => "too_many_pois",
+            Self::IndexerNotFound { .. } => "indexer_not_found",
+            Self::SameIndexer { .. } => "same_indexer",
+            Self::DifferentDeployments { .. } => "different_deployments",
+            Self::DifferentBlocks { .. } => "different_blocks",
+            Self::Database(_) => "database",
+        }
+    }
+}
+
+/// The error message of a failed report, counted by the
+/// `divergence_investigation_errors` metric.
+fn report_error(err: DivergenceInvestigationError) -> Option<String> {
+    metrics()
+        .divergence_investigation_errors
+        .with_label_values(&[err.kind()])
+        .inc();
+    Some(err.to_string())
+}
+
+/// How often a worker tells the others that it's still running a divergence
+/// investigation.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// For how long a running divergence investigation can go without a
+/// heartbeat before another worker takes over, assuming the first one died.
+const STALE_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(60);
+/// How many times a divergence investigation is attempted before giving up.
+const MAX_INVESTIGATION_ATTEMPTS: i32 = 5;
+/// How long to wait before retrying a failed divergence investigation. It
+/// doubles with each failed attempt.
+const INVESTIGATION_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Works off the queue of divergence investigation requests. Several workers,
+/// e.g. one per Graphix replica, can do so concurrently, since each request
+/// is claimed by a single worker at a time. Failed requests are retried with
+/// exponential backoff.
 pub async fn handle_divergence_investigation_requests(
     store: &Store,
     indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
-    ctx: &GraphixState,
+    ctx: Arc<GraphixState>,
 ) -> anyhow::Result<()> {
+    let worker = worker_id();
+    info!(%worker, "Handling divergence investigation requests");
+
     loop {
         debug!("Checking for new divergence investigation requests");
 
-        let (req_uuid, req_contents_blob) = {
-            loop {
-                let req_opt = store
-                    .get_first_pending_divergence_investigation_request()
-                    .await?;
-                if let Some(req) = req_opt {
-                    break req;
-                } else {
-                    tokio::time::sleep(Duration::from_secs(3)).await;
-                    continue;
+        let job = loop {
+            // Pending requests are left alone in read-only mode.
+            if ctx.config().read_only {
+                tokio::time::sleep(Duration::from_secs(3)).await;
+                continue;
+            }
+            match store.divergence_investigation_queue_depth().await {
+                Ok(depths) => metrics().set_divergence_investigation_queue_depth(&depths),
+                Err(err) => {
+                    warn!(error = %err, "Failed to measure the divergence investigation queue depth")
                 }
             }
+            let job_opt = store
+                .claim_divergence_investigation_job(
+                    &worker,
+                    STALE_HEARTBEAT_TIMEOUT,
+                    MAX_INVESTIGATION_ATTEMPTS,
+                )
+                .await?;
+            if let Some(job) = job_opt {
+                break job;
+            } else {
+                tokio::time::sleep(Duration::from_secs(3)).await;
+                continue;
+            }
         };
-        debug!(?req_uuid, "Found new divergence investigation request");
-
-        let req_contents =
-            serde_json::from_value(req_contents_blob).expect("invalid request blob; this is a bug");
-        let report = handle_divergence_investigation_request(
-            store,
-            &req_uuid,
-            req_contents,
-            indexers.clone(),
-            ctx,
-        )
-        .await;
-
-        let serialized_report = serde_json::to_value(&report).unwrap();
+        let req_uuid = job.uuid;
         debug!(
             ?req_uuid,
-            "Writing divergence investigation report to database"
+            attempt = job.attempts,
+            "Claimed divergence investigation request"
         );
-        store
-            .create_or_update_divergence_investigation_report(&req_uuid, serialized_report)
-            .await?;
-        store
-            .delete_divergence_investigation_request(&req_uuid)
-            .await?;
+
+        let result = tokio::select! {
+            result = run_divergence_investigation_job(
+                store,
+                &req_uuid,
+                job.request,
+                indexers.clone(),
+                ctx.clone(),
+            ) => result,
+            () = send_heartbeats(store, &req_uuid, &worker) => {
+                warn!(
+                    ?req_uuid,
+                    "Divergence investigation request was claimed by another worker, abandoning it"
+                );
+                continue;
+            }
+        };
+
+        if let Err(err) = result {
+            warn!(
+                ?req_uuid,
+                attempt = job.attempts,
+                error = %format!("{err:#}"),
+                "Divergence investigation failed"
+            );
+            store
+                .fail_divergence_investigation_job(
+                    &req_uuid,
+                    &worker,
+                    &format!("{err:#}"),
+                    MAX_INVESTIGATION_ATTEMPTS,
+                    INVESTIGATION_RETRY_BACKOFF,
+                )
+                .await?;
+        }
+    }
+}
+
+async fn run_divergence_investigation_job(
+    store: &Store,
+    req_uuid: &Uuid,
+    req_contents_blob: serde_json::Value,
+    indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
+    ctx: Arc<GraphixState>,
+) -> anyhow::Result<()> {
+    let req_contents: DivergenceInvestigationRequest = serde_json::from_value(req_contents_blob)
+        .map_err(|err| anyhow!("invalid divergence investigation request: {err}"))?;
+    let first_poi = req_contents.pois.first().copied();
+    let report = handle_divergence_investigation_request(
+        store,
+        req_uuid,
+        req_contents,
+        indexers,
+        ctx.clone(),
+    )
+    .await;
+
+    debug!(
+        ?req_uuid,
+        "Writing divergence investigation report to database"
+    );
+    store
+        .create_or_update_divergence_investigation_report(&report)
+        .await?;
+    store
+        .delete_divergence_investigation_request(req_uuid)
+        .await?;
+
+    let deployment = match first_poi {
+        Some(poi) => poi_deployment(store, &poi, &ctx).await?,
+        None => None,
+    };
+    store
+        .write_events(&[events::investigation_completed(&report, deployment)?])
+        .await?;
+    Ok(())
+}
+
+/// Keeps the heartbeat of a claimed divergence investigation request fresh.
+/// Returns once another worker claimed it, which happens if heartbeats were
+/// missed for too long.
+async fn send_heartbeats(store: &Store, req_uuid: &Uuid, worker: &str) {
+    loop {
+        tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+        match store
+            .heartbeat_divergence_investigation_job(req_uuid, worker)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                warn!(?req_uuid, error = %err, "Failed to send divergence investigation heartbeat")
+            }
+        }
+    }
+}
+
+/// Identifies this process among the workers of the divergence investigation
+/// queue. The host name helps to tell replicas apart when debugging.
+fn worker_id() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "graphix".to_string());
+    format!("{host}-{}", Uuid::new_v4())
+}
+
+/// The subgraph deployment of a collected PoI, if it's known.
+async fn poi_deployment(
+    store: &Store,
+    poi: &PoiBytes,
+    ctx: &GraphixState,
+) -> anyhow::Result<Option<IpfsCid>> {
+    let Some(model) = store.poi(poi).await? else {
+        return Ok(None);
+    };
+    let deployment = api_types::ProofOfIndexing { model }
+        .deployment(ctx)
+        .await
+        .map_err(|err| {
+            DivergenceInvestigationError::Database(anyhow!(
+                "failed to load deployment: {}",
+                err.message()
+            ))
+        })?;
+
+    Ok(Some(deployment.cid().clone()))
+}
+
+/// The graph-node version that an indexer last reported, if it's known.
+/// Failing to load it doesn't fail the bisection.
+async fn graph_node_version(indexer: &Indexer, ctx: &GraphixState) -> Option<String> {
+    match indexer.graph_node_version(ctx).await {
+        Ok(version) => version.and_then(|v| v.version_string),
+        Err(err) => {
+            warn!(error = %err.message(), "Failed to load graph-node version");
+            None
+        }
     }
 }
 
@@ -230,6 +920,8 @@ pub async fn handle_divergence_investigation_requests(
 /// bisection.
 struct PoiWithRelatedData {
     deployment: api_types::SubgraphDeployment,
+    /// The name of the deployment's network.
+    network: String,
     block: api_types::Block,
     indexer: Indexer,
     indexer_client: Arc<dyn IndexerClient>,
@@ -241,36 +933,61 @@ impl PoiWithRelatedData {
         store: &Store,
         indexers: &[Arc<dyn IndexerClient>],
         ctx: &GraphixState,
-    ) -> anyhow::Result<Option<Self>> {
-        let Some(poi_model) = store.poi(poi_bytes).await? else {
+    ) -> Result<Option<Self>, DivergenceInvestigationError> {
+        let Some(poi_model) = store
+            .poi(poi_bytes)
+            .await
+            .map_err(DivergenceInvestigationError::Database)?
+        else {
             return Ok(None);
         };
 
         let poi = api_types::ProofOfIndexing { model: poi_model };
 
-        let deployment = poi
-            .deployment(ctx)
-            .await
-            .map_err(|err| anyhow!("failed to load deployment: {err}"))?;
+        let deployment = poi.deployment(ctx).await.map_err(|err| {
+            DivergenceInvestigationError::Database(anyhow!(
+                "failed to load deployment: {}",
+                err.message()
+            ))
+        })?;
 
-        let block = poi
-            .block(ctx)
+        let network = deployment
+            .network(ctx)
             .await
-            .map_err(|err| anyhow!("failed to load block: {err}"))?;
+            .map_err(|err| {
+                DivergenceInvestigationError::Database(anyhow!(
+                    "failed to load network: {}",
+                    err.message()
+                ))
+            })?
+            .name()
+            .to_string();
 
-        let indexer = poi
-            .indexer(ctx)
-            .await
-            .map_err(|err| anyhow!("failed to load indexer: {err}"))?;
+        let block = poi.block(ctx).await.map_err(|err| {
+            DivergenceInvestigationError::Database(anyhow!(
+                "failed to load block: {}",
+                err.message()
+            ))
+        })?;
+
+        let indexer = poi.indexer(ctx).await.map_err(|err| {
+            DivergenceInvestigationError::Database(anyhow!(
+                "failed to load indexer: {}",
+                err.message()
+            ))
+        })?;
 
         let indexer_client = indexers
             .iter()
-            .find(|indexer| indexer.address() == indexer.address())
+            .find(|client| client.address() == indexer.address())
             .cloned()
-            .ok_or_else(|| anyhow!("indexer not found"))?;
+            .ok_or_else(|| DivergenceInvestigationError::IndexerNotFound {
+                poi: poi_bytes.to_string(),
+            })?;
 
         Ok(Some(Self {
             deployment,
+            network,
             block,
             indexer,
             indexer_client,
@@ -278,6 +995,7 @@ impl PoiWithRelatedData {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_divergence_investigation_request_pair(
     store: &Store,
     indexers: &[Arc<dyn IndexerClient>],
@@ -285,6 +1003,8 @@ async fn handle_divergence_investigation_request_pair(
     poi1_s: &PoiBytes,
     poi2_s: &PoiBytes,
     ctx: &GraphixState,
+    permits: IndexerPermits,
+    request: &DivergenceInvestigationRequest,
 ) -> BisectionRunReport {
     debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, "Bisecting Pois");
 
@@ -293,6 +1013,8 @@ async fn handle_divergence_investigation_request_pair(
         uuid: Uuid::new_v4(),
         poi1: *poi1_s,
         poi2: *poi2_s,
+        indexer1_operator_contact: None,
+        indexer2_operator_contact: None,
         divergence_block_bounds: DivergenceBlockBounds {
             lower_bound: PartialBlock {
                 number: 1,
@@ -304,6 +1026,11 @@ async fn handle_divergence_investigation_request_pair(
             },
         },
         error: None,
+        entity_diff: None,
+        eth_call_diff: None,
+        graft_divergence: None,
+        indexer1_graph_node_version: None,
+        indexer2_graph_node_version: None,
     };
 
     debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, "Fetching Pois");
@@ -311,7 +1038,7 @@ async fn handle_divergence_investigation_request_pair(
         Ok(Some(data)) => data,
         Ok(None) => return report,
         Err(err) => {
-            report.error = Some(err.to_string());
+            report.error = report_error(err);
             return report;
         }
     };
@@ -319,39 +1046,43 @@ async fn handle_divergence_investigation_request_pair(
         Ok(Some(data)) => data,
         Ok(None) => return report,
         Err(err) => {
-            report.error = Some(err.to_string());
+            report.error = report_error(err);
             return report;
         }
     };
 
     debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, "Fetched Pois");
 
+    report.indexer1_operator_contact = poi1_data.indexer.operator_contact().map(str::to_string);
+    report.indexer2_operator_contact = poi2_data.indexer.operator_contact().map(str::to_string);
+    report.indexer1_graph_node_version = graph_node_version(&poi1_data.indexer, ctx).await;
+    report.indexer2_graph_node_version = graph_node_version(&poi2_data.indexer, ctx).await;
+
     report.divergence_block_bounds.upper_bound.number = poi1_data.block.number_i64();
 
-    // Two PoIs need to relate to the same subgraph deployment to be comparable.
-    if poi1_data.deployment.cid() != poi2_data.deployment.cid() {
-        report.error = Some(
-            DivergenceInvestigationError::DifferentDeployments {
-                poi1: poi1_s.to_string(),
-                poi2: poi2_s.to_string(),
-                poi1_deployment: poi1_data.deployment.cid().to_string(),
-                poi2_deployment: poi2_data.deployment.cid().to_string(),
-            }
-            .to_string(),
-        );
+    // Two PoIs need to relate to the same subgraph deployment, on the same
+    // network, to be comparable.
+    if (poi1_data.deployment.cid(), &poi1_data.network)
+        != (poi2_data.deployment.cid(), &poi2_data.network)
+    {
+        report.error = report_error(DivergenceInvestigationError::DifferentDeployments {
+            poi1: poi1_s.to_string(),
+            poi2: poi2_s.to_string(),
+            poi1_deployment: format!("{} on {}", poi1_data.deployment.cid(), poi1_data.network),
+            poi2_deployment: format!("{} on {}", poi2_data.deployment.cid(), poi2_data.network),
+        });
+        return report;
     }
 
     // Two PoIs need to have the same block number to be comparable.
     if poi1_data.block.number() != poi2_data.block.number() {
-        report.error = Some(
-            DivergenceInvestigationError::DifferentBlocks {
-                poi1: poi1_s.to_string(),
-                poi2: poi2_s.to_string(),
-                poi1_block: poi1_data.block.number_i64(),
-                poi2_block: poi2_data.block.number_i64(),
-            }
-            .to_string(),
-        );
+        report.error = report_error(DivergenceInvestigationError::DifferentBlocks {
+            poi1: poi1_s.to_string(),
+            poi2: poi2_s.to_string(),
+            poi1_block: poi1_data.block.number_i64(),
+            poi2_block: poi2_data.block.number_i64(),
+        });
+        return report;
     }
 
     debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, "Fetching indexers");
@@ -359,15 +1090,86 @@ async fn handle_divergence_investigation_request_pair(
     debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, "Fetched indexers");
     if poi1_data.indexer.address() == poi2_data.indexer.address() {
         let indexer_id = poi1_data.indexer.address().to_string();
-        report.error = Some(DivergenceInvestigationError::SameIndexer { indexer_id }.to_string());
+        report.error = report_error(DivergenceInvestigationError::SameIndexer { indexer_id });
         return report;
     }
 
+    let manifest = match poi1_data.deployment.subgraph_manifest() {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            warn!(?req_uuid, error = %err, "Failed to parse subgraph manifest");
+            None
+        }
+    };
+
     let bisection_uuid = Uuid::new_v4();
+    let deployment = poi1_data.deployment.cid().clone();
+    let network = poi1_data.network.clone();
+    let indexer1 = poi1_data.indexer_client.clone();
+    let indexer2 = poi2_data.indexer_client.clone();
 
-    let context = PoiBisectingContext::new(report, bisection_uuid, poi1_data, poi2_data)
-        .expect("bisect context creation failed");
-    let (report, _block_num) = context.start().await;
+    let context = PoiBisectingContext::new(
+        report,
+        bisection_uuid,
+        poi1_data,
+        poi2_data,
+        permits.clone(),
+        store.clone(),
+    )
+    .expect("bisect context creation failed");
+    let timer = metrics().bisection_duration_seconds.start_timer();
+    let (mut report, diverging_block) = context.start().await;
+    timer.observe_duration();
+
+    if request.query_entity_changes {
+        match fetch_entity_diff(&indexer1, &indexer2, &permits, &deployment, diverging_block).await
+        {
+            Ok(diff) => report.entity_diff = Some(diff),
+            Err(err) => {
+                warn!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, error = %err, "Failed to fetch entity changes at the diverging block");
+            }
+        }
+    }
+    if request.query_eth_call_caches {
+        match fetch_eth_call_diff(
+            &indexer1,
+            &indexer2,
+            &permits,
+            &deployment,
+            &network,
+            diverging_block,
+        )
+        .await
+        {
+            Ok(diff) => report.eth_call_diff = Some(diff),
+            Err(err) => {
+                warn!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, error = %err, "Failed to fetch cached eth calls at the diverging block");
+            }
+        }
+    }
+    let graft = manifest
+        .as_ref()
+        .and_then(|manifest| graft_of_diverging_block(&manifest.graft_chain, diverging_block));
+    if let Some(graft) = graft {
+        debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, base = %graft.base, "Divergence was inherited from a graft base");
+        let base_bisection = if request.bisect_graft_bases {
+            let subject = BisectionSubject {
+                deployment: graft.base.clone(),
+                network: network.clone(),
+                indexer1: indexer1.clone(),
+                indexer2: indexer2.clone(),
+                store: Some(store.clone()),
+            };
+            Some(bisect_graft_base(&subject, &bisection_uuid, &permits, diverging_block).await)
+        } else {
+            None
+        };
+        report.graft_divergence = Some(GraftDivergence {
+            base: graft.base.clone(),
+            graft_block: graft.block,
+            base_bisection,
+        });
+    }
 
     report
 }
@@ -377,7 +1179,7 @@ async fn handle_divergence_investigation_request(
     req_uuid: &Uuid,
     req_contents: DivergenceInvestigationRequest,
     indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
-    ctx: &GraphixState,
+    ctx: Arc<GraphixState>,
 ) -> DivergenceInvestigationReport {
     let mut report = DivergenceInvestigationReport {
         uuid: *req_uuid,
@@ -391,29 +1193,52 @@ async fn handle_divergence_investigation_request(
     const MAX_NUMBER_OF_POIS_PER_REQUEST: u32 = 4;
 
     if req_contents.pois.len() > MAX_NUMBER_OF_POIS_PER_REQUEST as usize {
-        report.error = Some(
-            DivergenceInvestigationError::TooManyPois {
-                max: MAX_NUMBER_OF_POIS_PER_REQUEST,
-            }
-            .to_string(),
-        );
+        report.error = report_error(DivergenceInvestigationError::TooManyPois {
+            max: MAX_NUMBER_OF_POIS_PER_REQUEST,
+        });
         return report;
     }
 
     let indexers = indexers.borrow().clone();
 
-    let poi_pairs = unordered_pairs_combinations(req_contents.pois.into_iter());
+    let request = Arc::new(req_contents.clone());
+    let mut poi_pairs = unordered_pairs_combinations(req_contents.pois.into_iter()).into_iter();
+    let permits = IndexerPermits::default();
+    let mut bisection_runs = JoinSet::new();
 
-    for (poi1_s, poi2_s) in poi_pairs.into_iter() {
-        let bisection_run_report = handle_divergence_investigation_request_pair(
-            store, &indexers, req_uuid, &poi1_s, &poi2_s, ctx,
-        )
-        .await;
-        debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, "Finished bisection run");
-        report.bisection_runs.push(bisection_run_report);
-        let report_json = serde_json::to_value(&report).unwrap();
+    loop {
+        while bisection_runs.len() < MAX_CONCURRENT_BISECTION_RUNS {
+            let Some((poi1_s, poi2_s)) = poi_pairs.next() else {
+                break;
+            };
+            let store = store.clone();
+            let indexers = indexers.clone();
+            let req_uuid = *req_uuid;
+            let ctx = ctx.clone();
+            let permits = permits.clone();
+            let request = request.clone();
+            bisection_runs.spawn(async move {
+                let bisection_run_report = handle_divergence_investigation_request_pair(
+                    &store, &indexers, &req_uuid, &poi1_s, &poi2_s, &ctx, permits, &request,
+                )
+                .await;
+                debug!(?req_uuid, poi1 = %poi1_s, poi2 = %poi2_s, "Finished bisection run");
+                bisection_run_report
+            });
+        }
+
+        let Some(result) = bisection_runs.join_next().await else {
+            break;
+        };
+        match result {
+            Ok(bisection_run_report) => report.bisection_runs.push(bisection_run_report),
+            Err(err) => {
+                error!(?req_uuid, error = %err, "Bisection run failed to complete");
+                continue;
+            }
+        }
         if let Err(err) = store
-            .create_or_update_divergence_investigation_report(req_uuid, report_json)
+            .create_or_update_divergence_investigation_report(&report)
             .await
         {
             error!(?req_uuid, error = %err, "Failed to upsert divergence investigation report to the database");
@@ -440,10 +1265,75 @@ where
     pairs
 }
 
+/// Groups `pois` by block, and returns one PoI of each of the (up to
+/// [`MAX_INVESTIGATED_POIS`]) largest clusters of identical PoIs, for each
+/// block with more than one cluster.
+pub fn diverging_poi_clusters(pois: &[models::Poi]) -> Vec<Vec<PoiBytes>> {
+    let mut poi_counts_by_block: BTreeMap<BigIntId, BTreeMap<PoiBytes, usize>> = BTreeMap::new();
+    for poi in pois {
+        *poi_counts_by_block
+            .entry(poi.block_id)
+            .or_default()
+            .entry(poi.poi)
+            .or_default() += 1;
+    }
+
+    poi_counts_by_block
+        .into_values()
+        .filter(|poi_counts| poi_counts.len() > 1)
+        .map(|poi_counts| {
+            let mut clusters: Vec<(PoiBytes, usize)> = poi_counts.into_iter().collect();
+            clusters.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            clusters
+                .into_iter()
+                .take(MAX_INVESTIGATED_POIS)
+                .map(|(poi, _)| poi)
+                .collect()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod unit_tests {
+    use graphix_common_types::PoiSource;
+
     use super::*;
 
+    fn poi(block_id: BigIntId, poi_byte: u8) -> models::Poi {
+        models::Poi {
+            id: 0,
+            poi: [poi_byte; 32].into(),
+            sg_deployment_id: 1,
+            indexer_id: 1,
+            block_id,
+            created_at: Default::default(),
+            poi_value_id: poi_byte.into(),
+            epoch: None,
+            source: PoiSource::Polling,
+        }
+    }
+
+    #[test]
+    fn diverging_pois_are_clustered_by_block() {
+        let pois = [
+            // All indexers agree at block 1.
+            poi(1, 1),
+            poi(1, 1),
+            // The largest clusters at block 2 are investigated first.
+            poi(2, 1),
+            poi(2, 2),
+            poi(2, 2),
+            poi(2, 3),
+            poi(2, 4),
+            poi(2, 5),
+        ];
+
+        let clusters = diverging_poi_clusters(&pois);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].len(), MAX_INVESTIGATED_POIS);
+        assert_eq!(clusters[0][0], [2; 32].into());
+    }
+
     fn test_unordered_pairs_combinations(original: Vec<u32>, combinations: Vec<(u32, u32)>) {
         assert_eq!(
             unordered_pairs_combinations(original.into_iter()),
@@ -458,4 +1348,293 @@ mod unit_tests {
         test_unordered_pairs_combinations(vec![1, 2], vec![(1, 2)]);
         test_unordered_pairs_combinations(vec![1, 2, 3], vec![(1, 2), (2, 3), (1, 3)]);
     }
+
+    #[test]
+    fn indexer_permits_are_shared_per_indexer() {
+        let permits = IndexerPermits::default();
+        let indexer1 = IndexerAddress::from([1; 20]);
+        let indexer2 = IndexerAddress::from([2; 20]);
+
+        assert!(Arc::ptr_eq(
+            &permits.get(indexer1),
+            &permits.clone().get(indexer1)
+        ));
+        assert!(!Arc::ptr_eq(&permits.get(indexer1), &permits.get(indexer2)));
+        assert_eq!(
+            permits.get(indexer1).available_permits(),
+            MAX_CONCURRENT_REQUESTS_PER_INDEXER
+        );
+    }
+
+    fn entity_changes(
+        updates: &[(&str, serde_json::Value)],
+        deletions: &[(&str, &str)],
+    ) -> EntityChanges {
+        let mut changes = EntityChanges {
+            updates: HashMap::new(),
+            deletions: HashMap::new(),
+        };
+        for (entity_type, entity) in updates {
+            changes
+                .updates
+                .entry(entity_type.to_string())
+                .or_default()
+                .push(entity.clone());
+        }
+        for (entity_type, id) in deletions {
+            changes
+                .deletions
+                .entry(entity_type.to_string())
+                .or_default()
+                .push(id.to_string());
+        }
+        changes
+    }
+
+    #[test]
+    fn entity_diff_of_identical_changes_is_empty() {
+        let changes = entity_changes(
+            &[("Token", serde_json::json!({ "id": "0x1", "supply": "10" }))],
+            &[("Pool", "0x2")],
+        );
+
+        let diff = entity_diff(42, &changes, &changes);
+        assert_eq!(
+            diff,
+            EntityDiff {
+                block_number: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn entity_diff_is_keyed_by_entity_type_and_id() {
+        let changes1 = entity_changes(
+            &[
+                (
+                    "Token",
+                    serde_json::json!({ "id": "0x1", "supply": "10", "name": "A" }),
+                ),
+                ("Token", serde_json::json!({ "id": "0x2", "supply": "20" })),
+                ("Pool", serde_json::json!({ "id": "0x1", "fee": 3 })),
+            ],
+            &[("Swap", "0x4")],
+        );
+        let changes2 = entity_changes(
+            &[
+                (
+                    "Token",
+                    serde_json::json!({ "id": "0x1", "supply": "11", "name": "A" }),
+                ),
+                ("Pool", serde_json::json!({ "id": "0x1", "fee": 3 })),
+                ("Pool", serde_json::json!({ "id": "0x3", "fee": 5 })),
+                ("Swap", serde_json::json!({ "id": "0x4" })),
+            ],
+            &[],
+        );
+
+        let diff = entity_diff(1, &changes1, &changes2);
+        assert_eq!(
+            diff.added,
+            vec![EntityChange {
+                entity_type: "Pool".to_string(),
+                id: "0x3".to_string(),
+                data: Some(serde_json::json!({ "id": "0x3", "fee": 5 })),
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![EntityChange {
+                entity_type: "Token".to_string(),
+                id: "0x2".to_string(),
+                data: Some(serde_json::json!({ "id": "0x2", "supply": "20" })),
+            }]
+        );
+        assert_eq!(
+            diff.changed,
+            vec![
+                ChangedEntity {
+                    entity_type: "Swap".to_string(),
+                    id: "0x4".to_string(),
+                    indexer1_data: None,
+                    indexer2_data: Some(serde_json::json!({ "id": "0x4" })),
+                    changed_fields: vec![],
+                },
+                ChangedEntity {
+                    entity_type: "Token".to_string(),
+                    id: "0x1".to_string(),
+                    indexer1_data: Some(
+                        serde_json::json!({ "id": "0x1", "supply": "10", "name": "A" })
+                    ),
+                    indexer2_data: Some(
+                        serde_json::json!({ "id": "0x1", "supply": "11", "name": "A" })
+                    ),
+                    changed_fields: vec!["supply".to_string()],
+                },
+            ]
+        );
+    }
+
+    fn cached_eth_call(id_hash: u8, return_value: u8) -> CachedEthereumCall {
+        CachedEthereumCall {
+            id_hash: vec![id_hash; 32],
+            return_value: vec![return_value],
+            contract_address: vec![0xaa; 20],
+        }
+    }
+
+    #[test]
+    fn eth_call_diff_is_keyed_by_id_hash() {
+        let hash1: BlockHash = vec![1; 32].into();
+        let hash2: BlockHash = vec![2; 32].into();
+        let calls1 = [
+            cached_eth_call(1, 10),
+            cached_eth_call(2, 20),
+            cached_eth_call(3, 30),
+        ];
+        let calls2 = [
+            cached_eth_call(2, 21),
+            cached_eth_call(3, 30),
+            cached_eth_call(4, 40),
+        ];
+
+        let diff = eth_call_diff(7, hash1.clone(), hash2.clone(), &calls1, &calls2);
+        assert_eq!(diff.block_number, 7);
+        assert_eq!(diff.indexer1_block_hash, hash1);
+        assert_eq!(diff.indexer2_block_hash, hash2);
+        assert_eq!(
+            diff.added,
+            vec![CachedEthCall {
+                id_hash: vec![4; 32].into(),
+                contract_address: vec![0xaa; 20].into(),
+                return_value: vec![40].into(),
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![CachedEthCall {
+                id_hash: vec![1; 32].into(),
+                contract_address: vec![0xaa; 20].into(),
+                return_value: vec![10].into(),
+            }]
+        );
+        assert_eq!(
+            diff.changed,
+            vec![ChangedEthCall {
+                id_hash: vec![2; 32].into(),
+                contract_address: vec![0xaa; 20].into(),
+                indexer1_return_value: vec![20].into(),
+                indexer2_return_value: vec![21].into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn graft_of_diverging_block_is_furthest_down_the_chain() {
+        let graft = |base: &str, block| Graft {
+            base: crate::test_utils::ipfs_cid(base),
+            block,
+        };
+        let chain = [
+            graft(crate::test_utils::deployments::ARB1_LIDO, 200),
+            graft(crate::test_utils::deployments::ARB1_PREMIA_BLUE, 50),
+        ];
+
+        assert_eq!(graft_of_diverging_block(&chain, 201), None);
+        assert_eq!(graft_of_diverging_block(&chain, 200), Some(&chain[0]));
+        assert_eq!(graft_of_diverging_block(&chain, 51), Some(&chain[0]));
+        assert_eq!(graft_of_diverging_block(&chain, 50), Some(&chain[1]));
+        assert_eq!(graft_of_diverging_block(&[], 0), None);
+    }
+
+    #[tokio::test]
+    async fn graft_bases_are_bisected() {
+        use crate::test_utils::graph_node::{MockDeployment, MockGraphNode};
+        use crate::test_utils::{deployments, ipfs_cid};
+
+        let base = ipfs_cid(deployments::ARB1_LIDO);
+        let graph_node1 = MockGraphNode::start(
+            "indexer-1",
+            vec![MockDeployment::new(base.clone(), "arbitrum-one", 0, 100)],
+        )
+        .await;
+        let graph_node2 = MockGraphNode::start(
+            "indexer-2",
+            vec![MockDeployment::new(base.clone(), "arbitrum-one", 0, 100).diverging_at(37)],
+        )
+        .await;
+        let subject = BisectionSubject {
+            deployment: base,
+            network: "arbitrum-one".to_string(),
+            indexer1: graph_node1.indexer(),
+            indexer2: graph_node2.indexer(),
+            store: None,
+        };
+        let permits = IndexerPermits::default();
+
+        let bisection = bisect_graft_base(&subject, &Uuid::new_v4(), &permits, 80).await;
+        assert_eq!(bisection.error, None);
+        assert_eq!(bisection.divergence_block_bounds.upper_bound.number, 37);
+
+        // The indexers agree below the divergence.
+        let bisection = bisect_graft_base(&subject, &Uuid::new_v4(), &permits, 20).await;
+        assert!(bisection.error.is_some());
+        assert!(bisection.bisects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn bisection_starts_at_earliest_common_block() {
+        use crate::test_utils::graph_node::{MockDeployment, MockGraphNode};
+        use crate::test_utils::{deployments, ipfs_cid};
+
+        let deployment = ipfs_cid(deployments::ARB1_LIDO);
+        let graph_node1 = MockGraphNode::start(
+            "indexer-1",
+            vec![MockDeployment::new(
+                deployment.clone(),
+                "arbitrum-one",
+                30,
+                100,
+            )],
+        )
+        .await;
+        let graph_node2 = MockGraphNode::start(
+            "indexer-2",
+            vec![MockDeployment::new(deployment.clone(), "arbitrum-one", 50, 100).diverging_at(60)],
+        )
+        .await;
+        let subject = BisectionSubject {
+            deployment,
+            network: "arbitrum-one".to_string(),
+            indexer1: graph_node1.indexer(),
+            indexer2: graph_node2.indexer(),
+            store: None,
+        };
+        let mut bisects = vec![];
+        let mut bounds = DivergenceBlockBounds {
+            lower_bound: PartialBlock {
+                number: 0,
+                hash: None,
+            },
+            upper_bound: PartialBlock {
+                number: 100,
+                hash: None,
+            },
+        };
+
+        let diverging_block = subject
+            .bisect(
+                &Uuid::new_v4(),
+                &IndexerPermits::default(),
+                100,
+                &mut bisects,
+                &mut bounds,
+            )
+            .await;
+        assert_eq!(diverging_block, 60);
+        assert!(bounds.lower_bound.number >= 50);
+        // Blocks that indexer-2 pruned are never bisected.
+        assert!(bisects.iter().all(|bisect| bisect.block.number >= 50));
+    }
 }
@@ -1,8 +1,16 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::num::NonZeroU64;
+
+use chrono::NaiveDateTime;
+use graphix_common_types::{inputs, IpfsCid, PoiBytes};
 use graphix_indexer_client::IndexingStatus;
+use graphix_store::models::{IndexingStatusHistoryEntry, IntId, Poi};
+use graphix_store::Store;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum BlockChoicePolicy {
     // Use the earliest block that all indexers have in common
@@ -10,39 +18,77 @@ pub enum BlockChoicePolicy {
     Earliest,
     // Use the block that maximizes the total number of blocks synced across all indexers
     MaxSyncedBlocks,
+    // Use the latest of some predetermined blocks that all indexers have in
+    // common, so that PoIs are collected at deterministic checkpoints
+    FixedBlocks(FixedBlocks),
+}
+
+/// The checkpoints of [`BlockChoicePolicy::FixedBlocks`]: a list of block
+/// numbers, every multiple of an interval, or both.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FixedBlocks {
+    #[serde(default)]
+    pub blocks: Vec<u64>,
+    /// E.g. `10000` for every 10,000th block.
+    #[serde(default)]
+    pub interval: Option<NonZeroU64>,
+}
+
+impl FixedBlocks {
+    /// The latest checkpoint at or before `block`.
+    fn latest_at(&self, block: u64) -> Option<u64> {
+        let listed = self.blocks.iter().copied().filter(|b| *b <= block).max();
+        let interval = self
+            .interval
+            .map(|interval| block - block % interval.get())
+            .filter(|b| *b > 0);
+        listed.max(interval)
+    }
 }
 
 impl BlockChoicePolicy {
+    /// All available policies that don't need to be configured.
+    pub const ALL: [BlockChoicePolicy; 2] = [
+        BlockChoicePolicy::Earliest,
+        BlockChoicePolicy::MaxSyncedBlocks,
+    ];
+
     pub fn choose_block<'a>(
         &self,
         statuses: impl Iterator<Item = &'a IndexingStatus>,
     ) -> Option<u64> {
+        self.choose_block_number(
+            statuses.map(|status| (status.latest_block.number, status.earliest_block_num)),
+        )
+    }
+
+    /// Same as [`BlockChoicePolicy::choose_block`], but takes `(latest block,
+    /// earliest block)` pairs instead of indexing statuses.
+    pub fn choose_block_number(&self, blocks: impl Iterator<Item = (u64, u64)>) -> Option<u64> {
         match self {
-            BlockChoicePolicy::Earliest => statuses
-                .map(|status| &status.latest_block.number)
-                .min()
-                .copied(),
+            BlockChoicePolicy::Earliest => blocks.map(|(latest_block, _)| latest_block).min(),
             BlockChoicePolicy::MaxSyncedBlocks => {
                 // Assuming that all statuses have the same `deployment` and `earliest_block_num`,
                 // this will return the block number that maximizes the total number of blocks
                 // synced across all indexers.
 
-                let mut indexers_ascending: Vec<&'a IndexingStatus> = statuses.collect();
-                indexers_ascending.sort_by_key(|status| status.latest_block.number);
+                let mut indexers_ascending: Vec<(u64, u64)> = blocks.collect();
+                indexers_ascending.sort_by_key(|(latest_block, _)| *latest_block);
 
                 let mut max_utility = 0;
                 let mut best_block: Option<u64> = None;
 
-                for (i, status) in indexers_ascending.iter().enumerate() {
+                for (i, (block_number, earliest_block_num)) in
+                    indexers_ascending.iter().copied().enumerate()
+                {
                     let remaining_statuses = indexers_ascending.len() - i;
-                    let block_number = status.latest_block.number;
-                    if block_number < status.earliest_block_num {
+                    if block_number < earliest_block_num {
                         // This status is inconsistent, ignore it, avoiding overflow.
                         continue;
                     }
 
-                    let utility =
-                        remaining_statuses as u64 * (block_number - status.earliest_block_num);
+                    let utility = remaining_statuses as u64 * (block_number - earliest_block_num);
 
                     if utility > max_utility {
                         max_utility = utility;
@@ -52,6 +98,379 @@ impl BlockChoicePolicy {
 
                 best_block
             }
+            BlockChoicePolicy::FixedBlocks(fixed_blocks) => {
+                let (latest_block, earliest_block) =
+                    blocks.reduce(|(latest_a, earliest_a), (latest_b, earliest_b)| {
+                        (latest_a.min(latest_b), earliest_a.max(earliest_b))
+                    })?;
+                fixed_blocks
+                    .latest_at(latest_block)
+                    .filter(|block| *block >= earliest_block)
+            }
+        }
+    }
+}
+
+/// The block choice policy of each chain, i.e. [`Config::block_choice_policy`]
+/// unless overridden by [`ChainConfig::block_choice_policy`].
+///
+/// [`Config::block_choice_policy`]: crate::config::Config::block_choice_policy
+/// [`ChainConfig::block_choice_policy`]: crate::config::ChainConfig::block_choice_policy
+#[derive(Clone, Debug, Default)]
+pub struct BlockChoicePolicies {
+    pub default: BlockChoicePolicy,
+    pub by_chain: HashMap<String, BlockChoicePolicy>,
+}
+
+impl BlockChoicePolicies {
+    pub fn for_chain(&self, chain: &str) -> &BlockChoicePolicy {
+        self.by_chain.get(chain).unwrap_or(&self.default)
+    }
+}
+
+impl From<BlockChoicePolicy> for BlockChoicePolicies {
+    fn from(default: BlockChoicePolicy) -> Self {
+        Self {
+            default,
+            by_chain: HashMap::new(),
+        }
+    }
+}
+
+/// The outcome of replaying all block choice policies on the stored indexing
+/// statuses of a subgraph deployment, as they changed over time.
+#[derive(Debug)]
+pub struct ReplayedDeployment {
+    pub deployment: IpfsCid,
+    pub network: String,
+    /// Oldest first, ending with the latest indexing statuses.
+    pub snapshots: Vec<ReplayedSnapshot>,
+}
+
+/// The block choices for the indexing statuses of a subgraph deployment's
+/// indexers at some point in time.
+#[derive(Debug)]
+pub struct ReplayedSnapshot {
+    /// When the indexing status of one of the indexers changed, or `None` for
+    /// the latest indexing statuses.
+    pub at: Option<NaiveDateTime>,
+    pub indexer_count: usize,
+    pub choices: Vec<ReplayedChoice>,
+}
+
+#[derive(Debug)]
+pub struct ReplayedChoice {
+    pub policy: BlockChoicePolicy,
+    pub block: Option<u64>,
+    /// The number of indexers that agree on the most common PoI at `block`,
+    /// and the number of indexers with a stored PoI at `block`. `None` if no
+    /// PoIs were ever collected for `block`.
+    pub agreement: Option<(usize, usize)>,
+}
+
+impl fmt::Display for ReplayedDeployment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} on {}", self.deployment, self.network)?;
+        for snapshot in &self.snapshots {
+            match snapshot.at {
+                Some(at) => write!(f, "  {at}")?,
+                None => write!(f, "  latest")?,
+            }
+            writeln!(f, " ({} indexers)", snapshot.indexer_count)?;
+            for choice in &snapshot.choices {
+                write!(f, "    {:?}: ", choice.policy)?;
+                match choice.block {
+                    Some(block) => write!(f, "block #{block}, ")?,
+                    None => write!(f, "no block, ")?,
+                }
+                match choice.agreement {
+                    Some((agreeing, total)) => writeln!(f, "{agreeing}/{total} PoIs agree")?,
+                    None => writeln!(f, "no PoIs collected")?,
+                }
+            }
         }
+        Ok(())
+    }
+}
+
+/// Replays the stored indexing statuses of the given subgraph deployments (or
+/// all of them, if empty) through every [`BlockChoicePolicy`], and checks
+/// which of the chosen blocks have stored PoIs to compare. Policies are
+/// replayed at every point in the indexing status history at which the status
+/// of one of a deployment's indexers changed, and on the latest statuses.
+pub async fn replay(
+    store: &Store,
+    deployments: &[IpfsCid],
+) -> anyhow::Result<Vec<ReplayedDeployment>> {
+    let network_names: HashMap<IntId, String> = store
+        .networks()
+        .await?
+        .into_iter()
+        .map(|network| (network.id, network.name))
+        .collect();
+    let sg_deployments: HashMap<IntId, (IpfsCid, &str)> = store
+        .sg_deployments(inputs::SgDeploymentsQuery::default())
+        .await?
+        .into_iter()
+        .filter(|deployment| deployments.is_empty() || deployments.contains(&deployment.cid))
+        .filter_map(|deployment| {
+            let network = network_names.get(&deployment.network_id)?;
+            Some((deployment.id, (deployment.cid, network.as_str())))
+        })
+        .collect();
+    if sg_deployments.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let sg_deployment_ids: Vec<IntId> = sg_deployments.keys().copied().collect();
+    let mut history_by_deployment: HashMap<IntId, Vec<IndexingStatusHistoryEntry>> = HashMap::new();
+    for entry in store
+        .indexing_status_history_of_deployments(&sg_deployment_ids)
+        .await?
+    {
+        history_by_deployment
+            .entry(entry.sg_deployment_id)
+            .or_default()
+            .push(entry);
+    }
+
+    let cids: Vec<IpfsCid> = sg_deployments
+        .values()
+        .map(|(cid, _)| cid.clone())
+        .collect();
+    let mut latest_blocks_by_deployment: HashMap<IntId, IndexerBlocks> = HashMap::new();
+    for status in store.indexing_statuses(&cids, None, None).await? {
+        let (Some(latest_block), Some(earliest_block)) =
+            (status.latest_block_number, status.earliest_block_number)
+        else {
+            continue;
+        };
+        latest_blocks_by_deployment
+            .entry(status.sg_deployment_id)
+            .or_default()
+            .push((latest_block.try_into()?, earliest_block.try_into()?));
+    }
+
+    let mut sg_deployments: Vec<(IntId, (IpfsCid, &str))> = sg_deployments.into_iter().collect();
+    sg_deployments.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+    let mut replayed = vec![];
+    for (sg_deployment_id, (deployment, network)) in sg_deployments {
+        let history = history_by_deployment
+            .remove(&sg_deployment_id)
+            .unwrap_or_default();
+        let mut snapshots: Vec<(Option<NaiveDateTime>, IndexerBlocks)> =
+            history_snapshots(&history)?
+                .into_iter()
+                .map(|(at, blocks)| (Some(at), blocks))
+                .collect();
+        if let Some(blocks) = latest_blocks_by_deployment.remove(&sg_deployment_id) {
+            snapshots.push((None, blocks));
+        }
+        if snapshots.is_empty() {
+            continue;
+        }
+
+        // Snapshots often choose the same blocks.
+        let mut agreements: HashMap<u64, Option<(usize, usize)>> = HashMap::new();
+        let mut replayed_snapshots = vec![];
+        for (at, blocks) in snapshots {
+            let mut choices = vec![];
+            for policy in BlockChoicePolicy::ALL {
+                let block = policy.choose_block_number(blocks.iter().copied());
+                let agreement = match block {
+                    Some(block) => match agreements.get(&block) {
+                        Some(agreement) => *agreement,
+                        None => {
+                            let block_range = inputs::BlockRange {
+                                start: Some(block),
+                                end: Some(block),
+                            };
+                            let pois = store
+                                .pois(
+                                    Some(network),
+                                    std::slice::from_ref(&deployment),
+                                    Some(block_range),
+                                    None,
+                                    None,
+                                    None,
+                                )
+                                .await?;
+                            *agreements.entry(block).or_insert(agreement(&pois))
+                        }
+                    },
+                    None => None,
+                };
+                choices.push(ReplayedChoice {
+                    policy,
+                    block,
+                    agreement,
+                });
+            }
+
+            replayed_snapshots.push(ReplayedSnapshot {
+                at,
+                indexer_count: blocks.len(),
+                choices,
+            });
+        }
+
+        replayed.push(ReplayedDeployment {
+            deployment,
+            network: network.to_string(),
+            snapshots: replayed_snapshots,
+        });
+    }
+
+    Ok(replayed)
+}
+
+/// The `(latest block, earliest block)` pairs of all indexers of a subgraph
+/// deployment at some point in time.
+type IndexerBlocks = Vec<(u64, u64)>;
+
+/// Reconstructs the `(latest block, earliest block)` pairs of all indexers of
+/// a subgraph deployment at every point in time at which one of their indexing
+/// statuses changed, from the deployment's history, oldest first.
+fn history_snapshots(
+    history: &[IndexingStatusHistoryEntry],
+) -> anyhow::Result<Vec<(NaiveDateTime, IndexerBlocks)>> {
+    let mut blocks_by_indexer: BTreeMap<IntId, (u64, u64)> = BTreeMap::new();
+    let mut snapshots: Vec<(NaiveDateTime, IndexerBlocks)> = vec![];
+    for entry in history {
+        blocks_by_indexer.insert(
+            entry.indexer_id,
+            (
+                entry.latest_block_number.try_into()?,
+                entry.earliest_block_number.try_into()?,
+            ),
+        );
+        let blocks = blocks_by_indexer.values().copied().collect();
+        // Changes that were recorded at the same time, e.g. in the same
+        // polling cycle, make up a single snapshot.
+        match snapshots.last_mut() {
+            Some((at, last_blocks)) if *at == entry.created_at => *last_blocks = blocks,
+            _ => snapshots.push((entry.created_at, blocks)),
+        }
+    }
+
+    Ok(snapshots)
+}
+
+/// Returns how many indexers agree on the most common PoI, out of all
+/// indexers with a PoI. Only the most recent PoI of each indexer counts.
+fn agreement(pois: &[Poi]) -> Option<(usize, usize)> {
+    let mut latest_pois: HashMap<IntId, &Poi> = HashMap::new();
+    for poi in pois {
+        let latest = latest_pois.entry(poi.indexer_id).or_insert(poi);
+        if poi.created_at > latest.created_at {
+            *latest = poi;
+        }
+    }
+
+    let mut poi_counts: HashMap<PoiBytes, usize> = HashMap::new();
+    for poi in latest_pois.values() {
+        *poi_counts.entry(poi.poi).or_default() += 1;
+    }
+
+    let max_count = poi_counts.into_values().max()?;
+    Some((max_count, latest_pois.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use graphix_common_types::{PoiSource, SubgraphHealth};
+
+    use super::*;
+
+    fn poi(indexer_id: IntId, poi_byte: u8, created_at: i64) -> Poi {
+        Poi {
+            id: 0,
+            poi: [poi_byte; 32].into(),
+            sg_deployment_id: 1,
+            indexer_id,
+            block_id: 1,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap().naive_utc(),
+            poi_value_id: poi_byte.into(),
+            epoch: None,
+            source: PoiSource::Polling,
+        }
+    }
+
+    #[test]
+    fn choose_block_number() {
+        let blocks = [(10, 0), (20, 0), (30, 0)];
+        let choose = |policy: BlockChoicePolicy| policy.choose_block_number(blocks.into_iter());
+
+        assert_eq!(choose(BlockChoicePolicy::Earliest), Some(10));
+        // 2 indexers * 20 blocks beats 3 * 10 and 1 * 30.
+        assert_eq!(choose(BlockChoicePolicy::MaxSyncedBlocks), Some(20));
+    }
+
+    #[test]
+    fn choose_fixed_blocks() {
+        let blocks = [(25_000, 0), (31_000, 0), (42_000, 0)];
+        let choose = |fixed_blocks: FixedBlocks| {
+            BlockChoicePolicy::FixedBlocks(fixed_blocks).choose_block_number(blocks.into_iter())
+        };
+
+        let interval = FixedBlocks {
+            blocks: vec![],
+            interval: NonZeroU64::new(10_000),
+        };
+        assert_eq!(choose(interval.clone()), Some(20_000));
+        let listed = FixedBlocks {
+            blocks: vec![30_000, 24_000, 1_000],
+            interval: None,
+        };
+        assert_eq!(choose(listed.clone()), Some(24_000));
+        let both = FixedBlocks {
+            blocks: vec![24_000],
+            ..interval
+        };
+        assert_eq!(choose(both), Some(24_000));
+
+        // Checkpoints that some indexers have pruned can't be chosen.
+        let pruned = [(25_000, 21_000), (31_000, 0)];
+        assert_eq!(
+            BlockChoicePolicy::FixedBlocks(listed).choose_block_number(pruned.into_iter()),
+            Some(24_000)
+        );
+        assert_eq!(
+            BlockChoicePolicy::FixedBlocks(FixedBlocks {
+                blocks: vec![20_000],
+                interval: None,
+            })
+            .choose_block_number(pruned.into_iter()),
+            None
+        );
+    }
+
+    #[test]
+    fn history_snapshots_carry_over_unchanged_indexers() {
+        let entry = |indexer_id, latest_block_number, created_at| IndexingStatusHistoryEntry {
+            id: 0,
+            indexer_id,
+            sg_deployment_id: 1,
+            health: SubgraphHealth::Healthy,
+            synced: false,
+            paused: None,
+            latest_block_number,
+            earliest_block_number: 0,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap().naive_utc(),
+        };
+        let history = [entry(1, 10, 0), entry(2, 20, 0), entry(1, 30, 1)];
+
+        let snapshots = history_snapshots(&history).unwrap();
+        let blocks: Vec<_> = snapshots.into_iter().map(|(_, blocks)| blocks).collect();
+        assert_eq!(blocks, [vec![(10, 0), (20, 0)], vec![(30, 0), (20, 0)]]);
+    }
+
+    #[test]
+    fn agreement_uses_latest_poi_per_indexer() {
+        let pois = [poi(1, 1, 0), poi(1, 2, 1), poi(2, 2, 0), poi(3, 3, 0)];
+        assert_eq!(agreement(&pois), Some((2, 3)));
+        assert_eq!(agreement(&[]), None);
     }
 }
@@ -0,0 +1,235 @@
+//! Resolves the timestamps and canonical hashes of stored blocks from chains'
+//! JSON-RPC endpoints ([`crate::config::ChainConfig::rpc_url`]), so that
+//! investigations and PoI exports carry accurate block times rather than
+//! estimates. A canonical
+//! hash that differs from the stored one means that indexers reported PoIs
+//! for a block that was reorged out.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use graphix_common_types::BlockHash;
+use graphix_store::Store;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tracing::*;
+use url::Url;
+
+use crate::config::Config;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often to look for blocks without a timestamp.
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many blocks of each chain are resolved per sync.
+const BLOCKS_PER_SYNC: u16 = 500;
+
+/// Blocks that the endpoint doesn't know about, e.g. because it isn't an
+/// archive node, are retried this often.
+const RECHECK_AFTER: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The parts of an `eth_getBlockByNumber` response that Graphix is interested
+/// in.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct RpcBlock {
+    hash: BlockHash,
+    /// Seconds since the Unix epoch, as a hex quantity.
+    timestamp: String,
+}
+
+impl RpcBlock {
+    fn timestamp(&self) -> anyhow::Result<NaiveDateTime> {
+        let seconds = i64::from_str_radix(self.timestamp.trim_start_matches("0x"), 16)
+            .with_context(|| format!("invalid block timestamp {}", self.timestamp))?;
+        chrono::DateTime::from_timestamp(seconds, 0)
+            .map(|timestamp| timestamp.naive_utc())
+            .with_context(|| format!("block timestamp {seconds} is out of range"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// A client of a chain's Ethereum JSON-RPC endpoint.
+pub struct RpcClient {
+    url: Url,
+    client: reqwest::Client,
+}
+
+impl RpcClient {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetches the timestamp and hash of the canonical block at height
+    /// `number`, or `None` if the endpoint doesn't know about it.
+    pub async fn block(&self, number: i64) -> anyhow::Result<Option<(NaiveDateTime, BlockHash)>> {
+        let response: RpcResponse<RpcBlock> = self
+            .client
+            .post(self.url.clone())
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_getBlockByNumber",
+                "params": [format!("{number:#x}"), false],
+            }))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(RpcError { code, message }) = response.error {
+            anyhow::bail!("JSON-RPC error {code}: {message}");
+        }
+        response
+            .result
+            .map(|block| Ok((block.timestamp()?, block.hash)))
+            .transpose()
+    }
+
+    /// Fetches the number of the latest block.
+    pub async fn block_number(&self) -> anyhow::Result<i64> {
+        let response: RpcResponse<String> = self
+            .client
+            .post(self.url.clone())
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_blockNumber",
+                "params": [],
+            }))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(RpcError { code, message }) = response.error {
+            anyhow::bail!("JSON-RPC error {code}: {message}");
+        }
+        let number = response.result.context("missing block number")?;
+        i64::from_str_radix(number.trim_start_matches("0x"), 16)
+            .with_context(|| format!("invalid block number {number}"))
+    }
+}
+
+/// Resolves the timestamps of stored blocks on all chains with
+/// [`crate::config::ChainConfig::rpc_url`], unless in read-only mode or if
+/// this replica isn't the `leader`. Runs forever.
+pub async fn sync_block_timestamps(
+    store: Store,
+    config: watch::Receiver<Config>,
+    leader: watch::Receiver<bool>,
+) {
+    loop {
+        let rpc_urls: Vec<(String, Url)> = {
+            let config = config.borrow();
+            if config.read_only || !*leader.borrow() {
+                vec![]
+            } else {
+                config
+                    .chains
+                    .iter()
+                    .filter_map(|(name, chain)| Some((name.clone(), chain.rpc_url.clone()?)))
+                    .collect()
+            }
+        };
+
+        for (network, url) in rpc_urls {
+            if let Err(err) = sync_network(&store, &network, RpcClient::new(url)).await {
+                warn!(%network, error = %err, "Failed to resolve block timestamps");
+            }
+        }
+
+        tokio::time::sleep(SYNC_INTERVAL).await;
+    }
+}
+
+async fn sync_network(store: &Store, network: &str, client: RpcClient) -> anyhow::Result<()> {
+    let blocks = store
+        .blocks_without_timestamp(network, RECHECK_AFTER, BLOCKS_PER_SYNC)
+        .await?;
+
+    for block in blocks {
+        let resolved = client.block(block.number).await?;
+        if let Some((_, canonical_hash)) = &resolved {
+            if canonical_hash != &block.hash {
+                warn!(
+                    %network,
+                    number = block.number,
+                    hash = %block.hash,
+                    %canonical_hash,
+                    "Stored block is not on the canonical chain"
+                );
+            }
+        }
+        store
+            .set_block_timestamp(
+                block.id,
+                resolved
+                    .as_ref()
+                    .map(|(timestamp, hash)| (*timestamp, hash)),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_block() {
+        let response: RpcResponse<RpcBlock> = serde_json::from_str(
+            r#"{
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "hash": "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6",
+                    "number": "0x1",
+                    "timestamp": "0x55ba4224",
+                    "transactions": []
+                }
+            }"#,
+        )
+        .unwrap();
+        let block = response.result.unwrap();
+
+        assert_eq!(
+            block.hash.to_string(),
+            "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6"
+        );
+        assert_eq!(
+            block.timestamp().unwrap().to_string(),
+            "2015-07-30 15:26:28"
+        );
+    }
+
+    #[test]
+    fn parse_unknown_block() {
+        let response: RpcResponse<RpcBlock> =
+            serde_json::from_str(r#"{"jsonrpc": "2.0", "id": 1, "result": null}"#).unwrap();
+
+        assert!(response.result.is_none());
+        assert!(response.error.is_none());
+    }
+}
@@ -0,0 +1,385 @@
+//! Divergence investigation campaigns. On a cron-like schedule, each campaign
+//! ranks subgraph deployments by curation signal on a network subgraph, and
+//! launches a divergence investigation for every block at which the live PoIs
+//! of the top deployments disagree, so that nobody has to go looking for
+//! divergences first. Every run is recorded in the database, for the
+//! `campaignRuns` GraphQL query.
+
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context};
+use chrono::{DateTime, Datelike, DurationRound, Timelike, Utc};
+use graphix_common_types::IpfsCid;
+use graphix_store::models::{self, DivergenceInvestigationRequest};
+use graphix_store::Store;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::*;
+use uuid::Uuid;
+
+use crate::bisect::diverging_poi_clusters;
+use crate::config::{CampaignConfig, Config};
+use crate::substreams::comparable_live_pois;
+use crate::{metrics, PrometheusMetrics};
+
+/// A cron-like schedule with five space-separated fields: minute (0-59), hour
+/// (0-23), day of month (1-31), month (1-12) and day of week (0-7, where both
+/// 0 and 7 are Sunday), in UTC. Each field is `*`, a number, a range like
+/// `1-5`, or a comma-separated list of them, each optionally followed by a
+/// step like `/15`. As in cron, when both the day of month and the day of
+/// week are restricted, a day that matches either of them matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    // One bit per allowed value.
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    /// Whether the schedule matches the minute that `time` falls into.
+    pub fn matches(&self, time: DateTime<Utc>) -> bool {
+        let allowed = |values: u64, value: u32| values & (1 << value) != 0;
+
+        let day_of_month = allowed(self.days_of_month, time.day());
+        let day_of_week = allowed(self.days_of_week, time.weekday().num_days_from_sunday());
+        let day = if self.days_of_month_restricted && self.days_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        };
+
+        day && allowed(self.minutes, time.minute())
+            && allowed(self.hours, time.hour())
+            && allowed(self.months, time.month())
+    }
+
+    /// Whether the schedule matches any minute after the one that `after`
+    /// falls into, up to and including `until`.
+    pub fn is_due(&self, after: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+        let mut minute = start_of_minute(after) + chrono::Duration::minutes(1);
+        while minute <= until {
+            if self.matches(minute) {
+                return true;
+            }
+            minute += chrono::Duration::minutes(1);
+        }
+        false
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(expression: &str) -> anyhow::Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            bail!(
+                "expected five fields (minute, hour, day of month, month and day of week): \
+                 {expression}"
+            );
+        };
+
+        let mut days_of_week = parse_field(day_of_week, 0, 7).context("invalid day of week")?;
+        // Both 0 and 7 are Sunday.
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+
+        Ok(Self {
+            expression: expression.to_string(),
+            minutes: parse_field(minute, 0, 59).context("invalid minute")?,
+            hours: parse_field(hour, 0, 23).context("invalid hour")?,
+            days_of_month: parse_field(day_of_month, 1, 31).context("invalid day of month")?,
+            months: parse_field(month, 1, 12).context("invalid month")?,
+            days_of_week,
+            days_of_month_restricted: !day_of_month.starts_with('*'),
+            days_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = anyhow::Error;
+
+    fn try_from(expression: String) -> anyhow::Result<Self> {
+        expression.parse()
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+/// Parses a single field of a [`CronSchedule`] into a bit set of the values
+/// that it allows.
+fn parse_field(field: &str, min: u32, max: u32) -> anyhow::Result<u64> {
+    let mut values = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>()?),
+            None => (item, 1),
+        };
+        ensure!(step > 0, "the step of {item} can't be zero");
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse()?, end.parse()?)
+        } else {
+            let start = range.parse()?;
+            // `5/15` is short for `5-59/15`.
+            (start, if step > 1 { max } else { start })
+        };
+        ensure!(
+            min <= start && start <= end && end <= max,
+            "{item} is not within {min}-{max}"
+        );
+
+        for value in (start..=end).step_by(step) {
+            values |= 1 << value;
+        }
+    }
+
+    Ok(values)
+}
+
+fn start_of_minute(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(chrono::Duration::minutes(1))
+        .expect("minutes can't overflow")
+}
+
+/// Runs the configured campaigns whenever their schedules are due, unless in
+/// read-only mode or if this replica isn't the `leader`. Runs forever.
+pub async fn run_campaigns(
+    store: Store,
+    config: watch::Receiver<Config>,
+    leader: watch::Receiver<bool>,
+    metrics: &'static PrometheusMetrics,
+) {
+    let mut last_check = Utc::now();
+    loop {
+        let now = Utc::now();
+        let next_minute = start_of_minute(now) + chrono::Duration::minutes(1);
+        tokio::time::sleep((next_minute - now).to_std().unwrap_or_default()).await;
+
+        let now = Utc::now();
+        let config = config.borrow().clone();
+        if config.read_only || !*leader.borrow() {
+            last_check = now;
+            continue;
+        }
+        for campaign in &config.campaigns {
+            if !campaign.schedule.is_due(last_check, now) {
+                continue;
+            }
+
+            info!(campaign = %campaign.name, "Running divergence investigation campaign");
+            match run_campaign(&store, &config, campaign, metrics).await {
+                Ok(run) => info!(
+                    campaign = %campaign.name,
+                    deployments = run.deployments.len(),
+                    diverging_deployments = run.diverging_deployments.len(),
+                    investigations = run.investigation_uuids.len(),
+                    error = ?run.error,
+                    "Finished divergence investigation campaign"
+                ),
+                Err(err) => error!(
+                    campaign = %campaign.name,
+                    error = %err,
+                    "Failed to record divergence investigation campaign run"
+                ),
+            }
+        }
+        last_check = now;
+    }
+}
+
+/// Runs a campaign once, and records the run.
+async fn run_campaign(
+    store: &Store,
+    config: &Config,
+    campaign: &CampaignConfig,
+    metrics: &PrometheusMetrics,
+) -> anyhow::Result<models::CampaignRun> {
+    match top_deployments_by_signal(store, config, campaign, metrics).await {
+        Ok(deployments) => investigate_deployments(store, campaign, &deployments).await,
+        Err(err) => {
+            store
+                .write_campaign_run(&campaign.name, &[], &[], &[], Some(&format!("{err:#}")))
+                .await
+        }
+    }
+}
+
+/// The [`CampaignConfig::top_deployments`] subgraph deployments with the most
+/// curation signal on the campaign's network subgraph, which they're
+/// attributed to.
+async fn top_deployments_by_signal(
+    store: &Store,
+    config: &Config,
+    campaign: &CampaignConfig,
+    metrics: &PrometheusMetrics,
+) -> anyhow::Result<Vec<IpfsCid>> {
+    let network_subgraph_name = campaign.network_subgraph.as_deref();
+    let network_subgraph_config = config.network_subgraph(network_subgraph_name).ok_or_else(
+        || match network_subgraph_name {
+            Some(name) => anyhow::anyhow!("network subgraph not found: {name}"),
+            None => anyhow::anyhow!("campaigns require a network subgraph"),
+        },
+    )?;
+    let network_subgraph = network_subgraph_config.client(metrics)?.with_rate_limit(
+        config.indexer_rate_limit,
+        metrics.throttled_indexer_requests.clone(),
+    );
+
+    let mut deployments = network_subgraph
+        .subgraph_deployments_by_signal(Some(campaign.top_deployments))
+        .await?;
+    deployments.truncate(campaign.top_deployments as usize);
+
+    let cids = deployments
+        .iter()
+        .map(|deployment| {
+            IpfsCid::from_str(&deployment.ipfs_hash)
+                .with_context(|| format!("invalid IPFS CID: {}", deployment.ipfs_hash))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if let Some(name) = deployments
+        .first()
+        .and_then(|d| d.network_subgraph.as_deref())
+    {
+        store
+            .write_sg_deployment_network_subgraphs(name, &cids)
+            .await?;
+    }
+    Ok(cids)
+}
+
+/// Launches a divergence investigation for each block at which the live PoIs
+/// of one of `deployments` disagree, and records the campaign run. If
+/// launching fails, the investigations that were launched until then are
+/// still recorded, along with the error.
+pub async fn investigate_deployments(
+    store: &Store,
+    campaign: &CampaignConfig,
+    deployments: &[IpfsCid],
+) -> anyhow::Result<models::CampaignRun> {
+    let mut diverging_deployments = vec![];
+    let mut investigation_uuids = vec![];
+    let mut error = None;
+    for deployment in deployments {
+        match investigate_deployment(store, deployment, campaign.bisect_graft_bases, 0).await {
+            Ok(uuids) if uuids.is_empty() => {}
+            Ok(uuids) => {
+                diverging_deployments.push(deployment.clone());
+                investigation_uuids.extend(uuids);
+            }
+            Err(err) => {
+                error = Some(format!("{err:#}"));
+                break;
+            }
+        }
+    }
+
+    store
+        .write_campaign_run(
+            &campaign.name,
+            deployments,
+            &diverging_deployments,
+            &investigation_uuids,
+            error.as_deref(),
+        )
+        .await
+}
+
+/// Does what the `investigateDeployment` GraphQL mutation does.
+pub(crate) async fn investigate_deployment(
+    store: &Store,
+    deployment: &IpfsCid,
+    bisect_graft_bases: bool,
+    priority: i32,
+) -> anyhow::Result<Vec<Uuid>> {
+    let live_pois = comparable_live_pois(store, deployment).await?;
+
+    let mut uuids = vec![];
+    for pois in diverging_poi_clusters(&live_pois) {
+        let req = DivergenceInvestigationRequest {
+            pois,
+            query_block_caches: true,
+            query_eth_call_caches: true,
+            query_entity_changes: true,
+            bisect_graft_bases,
+        };
+        uuids.push(
+            store
+                .create_divergence_investigation_request(serde_json::to_value(req)?, priority)
+                .await?,
+        );
+        metrics().divergence_investigations_launched.inc();
+    }
+
+    Ok(uuids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    #[test]
+    fn cron_schedules_are_parsed() {
+        let schedule = CronSchedule::from_str("*/15 2,14 * * 1-5").unwrap();
+        // 2025-01-06 is a Monday.
+        assert!(schedule.matches(time("2025-01-06T02:45:10Z")));
+        assert!(schedule.matches(time("2025-01-10T14:00:00Z")));
+        assert!(!schedule.matches(time("2025-01-06T02:44:00Z")));
+        assert!(!schedule.matches(time("2025-01-06T03:00:00Z")));
+        assert!(!schedule.matches(time("2025-01-11T02:00:00Z")));
+
+        for invalid in [
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(CronSchedule::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn cron_days_match_either_day_of_month_or_week() {
+        // On the 1st and on Sundays.
+        let schedule = CronSchedule::from_str("0 0 1 * 7").unwrap();
+        assert!(schedule.matches(time("2025-01-01T00:00:00Z")));
+        assert!(schedule.matches(time("2025-01-05T00:00:00Z")));
+        assert!(!schedule.matches(time("2025-01-06T00:00:00Z")));
+
+        // Only on Sundays.
+        let schedule = CronSchedule::from_str("0 0 * * 0").unwrap();
+        assert!(!schedule.matches(time("2025-01-01T00:00:00Z")));
+        assert!(schedule.matches(time("2025-01-05T00:00:00Z")));
+    }
+
+    #[test]
+    fn cron_schedules_are_due_once_per_matching_minute() {
+        let schedule = CronSchedule::from_str("30 * * * *").unwrap();
+        let due = |after, until| schedule.is_due(time(after), time(until));
+        assert!(due("2025-01-01T00:29:59Z", "2025-01-01T00:30:00Z"));
+        assert!(!due("2025-01-01T00:30:00Z", "2025-01-01T00:30:59Z"));
+        assert!(due("2025-01-01T00:00:00Z", "2025-01-01T02:00:00Z"));
+        assert!(!due("2025-01-01T00:31:00Z", "2025-01-01T01:29:00Z"));
+    }
+}
@@ -1,16 +1,76 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use graphix_common_types::IpfsCid;
 
 #[derive(Parser, Debug)]
 #[clap(author, about, version)]
 pub struct CliOptions {
     /// The URL of the PostgreSQL database to use. Can also be set via env.
-    /// var..
+    /// var.. Only optional when validating a configuration file with
+    /// `check-config`.
     #[clap(long, env = "GRAPHIX_DB_URL")]
-    pub database_url: String,
+    pub database_url: Option<String>,
+    /// The URL of a read replica of the database. Heavy read queries of the
+    /// APIs, e.g. of PoIs, go to the replica rather than to the primary
+    /// database, unless it lags behind by more than
+    /// `--read-replica-max-lag-seconds` or can't be reached.
+    #[clap(long, env = "GRAPHIX_READ_REPLICA_DB_URL")]
+    pub read_replica_database_url: Option<String>,
+    /// How far the read replica can lag behind the primary database before
+    /// queries fall back to the primary.
+    #[clap(
+        long,
+        env = "GRAPHIX_READ_REPLICA_MAX_LAG_SECONDS",
+        default_value_t = 30
+    )]
+    pub read_replica_max_lag_seconds: u64,
     /// The port on which the GraphQL API server should listen.
     #[clap(long, default_value_t = 8000)]
     pub port: u16,
     /// The port on which the Prometheus exporter should listen.
     #[clap(long, default_value_t = 9184)]
     pub prometheus_port: u16,
+    /// Serves the stored data without ever writing to the database, e.g. when
+    /// pointing a staging UI at the production database. Database migrations
+    /// aren't run, nothing is polled, and mutations fail. Can also be enabled
+    /// with the `readOnly` configuration option, which doesn't affect
+    /// migrations.
+    #[clap(long, env = "GRAPHIX_READ_ONLY")]
+    pub read_only: bool,
+    /// Runs against a synthetic network of in-process indexers, some of
+    /// which report diverging PoIs, e.g. to try Graphix out without real
+    /// indexers. Can also be enabled with `environment: simulation` in the
+    /// configuration.
+    #[clap(long, env = "GRAPHIX_SIMULATION")]
+    pub simulation: bool,
+    /// Runs a one-off command instead of the Graphix server.
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Replays the stored indexing statuses, as they changed over time,
+    /// through every block choice policy, and prints the blocks each policy
+    /// would have chosen together with the agreement among the PoIs collected
+    /// for those blocks.
+    ReplayBlockChoice {
+        /// Only replay these subgraph deployments (by IPFS CID). All of them
+        /// by default.
+        deployments: Vec<IpfsCid>,
+    },
+    /// Validates a configuration, e.g. before applying it with the
+    /// `setConfiguration` mutation, and prints the issues found: unreachable
+    /// endpoints, chains without CAIP-2 IDs, indexers that don't dedupe
+    /// cleanly and malformed block explorer URL templates. Exits with a
+    /// nonzero status if there are errors.
+    CheckConfig {
+        /// The YAML configuration file to validate.
+        #[clap(required_unless_present = "from_db")]
+        file: Option<PathBuf>,
+        /// Validates the configuration stored in the database instead.
+        #[clap(long, conflicts_with = "file")]
+        from_db: bool,
+    },
 }
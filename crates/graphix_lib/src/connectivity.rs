@@ -0,0 +1,104 @@
+//! Connectivity tests of indexers, to debug the endpoint of a newly added
+//! indexer from the GraphQL API without shell access. An indexer is sent the
+//! cheapest requests that Graphix relies on, one after the other, and the
+//! latency and outcome of each are reported. Nothing is stored.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use graphix_common_types::IndexerErrorClass;
+use graphix_indexer_client::{classify_error, IndexerClient};
+
+/// The outcome of a single request of a connectivity test.
+#[derive(Debug)]
+pub struct ConnectivityCheck {
+    /// The name of the request, e.g. `ping`.
+    pub request: &'static str,
+    pub latency: Duration,
+    /// A summary of the response, or the class of the error and why the
+    /// request failed.
+    pub outcome: Result<String, (IndexerErrorClass, String)>,
+}
+
+impl ConnectivityCheck {
+    pub fn succeeded(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+/// Sends `indexer` a `ping`, a `version` and an `indexingStatuses` request.
+/// All of them are sent even if some fail, as they fail for different
+/// reasons.
+pub async fn test_connectivity(indexer: Arc<dyn IndexerClient>) -> Vec<ConnectivityCheck> {
+    let started_at = Instant::now();
+    let ping = indexer.clone().ping().await;
+    let ping = check(
+        "ping",
+        started_at,
+        ping.map(|()| "Indexer responded".to_string()),
+    );
+
+    let started_at = Instant::now();
+    let version = indexer.clone().version().await.map(|version| {
+        format!(
+            "graph-node {} ({})",
+            version.version.as_deref().unwrap_or("unknown version"),
+            version.commit.as_deref().unwrap_or("unknown commit")
+        )
+    });
+    let version = check("version", started_at, version);
+
+    let started_at = Instant::now();
+    let statuses = indexer
+        .indexing_statuses()
+        .await
+        .map(|statuses| format!("Indexer returned {} indexing statuses", statuses.len()));
+    let statuses = check("indexingStatuses", started_at, statuses);
+
+    vec![ping, version, statuses]
+}
+
+fn check(
+    request: &'static str,
+    started_at: Instant,
+    result: anyhow::Result<String>,
+) -> ConnectivityCheck {
+    ConnectivityCheck {
+        request,
+        latency: started_at.elapsed(),
+        outcome: result.map_err(|err| (classify_error(&err), format!("{err:#}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mocks::MockIndexer;
+
+    #[tokio::test]
+    async fn failed_requests_dont_stop_the_test() {
+        let indexer = Arc::new(MockIndexer {
+            name: "indexer".to_string(),
+            deployment_details: vec![],
+            fail_indexing_statuses: true,
+        });
+
+        let checks = test_connectivity(indexer).await;
+        let outcomes: Vec<_> = checks
+            .iter()
+            .map(|check| (check.request, check.succeeded()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("ping", true),
+                ("version", true),
+                ("indexingStatuses", false)
+            ]
+        );
+        assert_eq!(
+            checks[1].outcome.as_deref().unwrap(),
+            "graph-node 0.0.0 (no-commit-hash)"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Graphix monitors the subgraph deployments that indexers report in their
+//! indexing statuses. By default, that's all of them, so that self-hosted
+//! fleets with subgraphs that nobody allocated to are fully covered, and
+//! unknown deployments are created as they're first reported. Discovery can
+//! be restricted to some networks, or disabled, in which case only the
+//! deployments that Graphix already knows (including those that were
+//! discovered earlier), those that indexers allocated to on the network
+//! subgraph and those on watchlists are monitored.
+
+use std::collections::HashSet;
+
+use graphix_common_types::IpfsCid;
+use graphix_indexer_client::IndexingStatus;
+use graphix_store::Store;
+use tracing::*;
+
+use crate::config::DeploymentDiscoveryConfig;
+use crate::watchlists::Watchlists;
+
+/// Keeps only the indexing statuses of the subgraph deployments that are
+/// monitored according to `config`. On error, `statuses` are left as they
+/// are.
+pub async fn retain_monitored_statuses(
+    store: &Store,
+    config: &DeploymentDiscoveryConfig,
+    watchlists: &Watchlists,
+    statuses: &mut Vec<IndexingStatus>,
+) -> anyhow::Result<()> {
+    let undiscovered: Vec<IpfsCid> = statuses
+        .iter()
+        .filter(|status| !config.discovers(&status.network))
+        .map(|status| status.deployment.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if undiscovered.is_empty() {
+        return Ok(());
+    }
+
+    let known = store.known_sg_deployments(&undiscovered).await?;
+    let allocated: HashSet<IpfsCid> = store
+        .indexer_allocations(&undiscovered)
+        .await?
+        .into_iter()
+        .map(|allocation| allocation.deployment)
+        .collect();
+
+    let count = statuses.len();
+    statuses.retain(|status| {
+        config.discovers(&status.network)
+            || known.contains(&(status.deployment.clone(), status.network.clone()))
+            || allocated.contains(&status.deployment)
+            || watchlists.contains(&status.deployment)
+    });
+
+    debug!(
+        skipped = count - statuses.len(),
+        "Skipped indexing statuses of undiscovered subgraph deployments"
+    );
+    Ok(())
+}
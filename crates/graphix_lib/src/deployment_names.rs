@@ -0,0 +1,161 @@
+//! Names subgraph deployments after the subgraphs they were published as, so
+//! that they don't have to be named one by one with `setDeploymentName`. Every
+//! so often, the unnamed deployments are looked up on all configured network
+//! subgraphs, and named after the display name and version label of their
+//! most recently published subgraph version, e.g. `Uniswap V3 v0.0.1`.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use graphix_common_types::inputs::SgDeploymentsQuery;
+use graphix_common_types::{DeploymentName, IpfsCid};
+use graphix_network_sg_client::DeploymentVersion;
+use graphix_store::Store;
+use tokio::sync::watch;
+use tracing::*;
+
+use crate::config::Config;
+use crate::PrometheusMetrics;
+
+/// How often to check whether a network subgraph was configured, naming
+/// enabled, or leadership acquired in the meantime.
+const DISABLED_POLLING_PERIOD: Duration = Duration::from_secs(60);
+
+/// Names the unnamed subgraph deployments whenever
+/// [`Config::deployment_names`] says so, as long as any network subgraphs are
+/// configured, not in read-only mode, and this replica is the `leader`. Runs
+/// forever.
+pub async fn sync_deployment_names_periodically(
+    store: Store,
+    config: watch::Receiver<Config>,
+    leader: watch::Receiver<bool>,
+    metrics: &'static PrometheusMetrics,
+) {
+    loop {
+        let config = config.borrow().clone();
+        if config.read_only
+            || !*leader.borrow()
+            || !config.deployment_names.enabled
+            || config.network_subgraphs().is_empty()
+        {
+            tokio::time::sleep(DISABLED_POLLING_PERIOD).await;
+            continue;
+        }
+
+        match sync_deployment_names(&store, &config, metrics, false).await {
+            Ok(named) => debug!(named, "Synced subgraph deployment names"),
+            Err(err) => error!(error = %err, "Failed to sync subgraph deployment names"),
+        }
+
+        tokio::time::sleep(Duration::from_secs(
+            config.deployment_names.sync_interval_in_seconds,
+        ))
+        .await;
+    }
+}
+
+/// Names subgraph deployments after the subgraph versions they were published
+/// as on the configured network subgraphs, and returns how many were named.
+/// Unless `overwrite` is set, only unnamed deployments are named. If several
+/// network subgraphs know a deployment, the first one wins. A network
+/// subgraph that fails doesn't prevent the others from being queried.
+pub async fn sync_deployment_names(
+    store: &Store,
+    config: &Config,
+    metrics: &PrometheusMetrics,
+    overwrite: bool,
+) -> anyhow::Result<usize> {
+    let mut unnamed: Vec<String> = store
+        .sg_deployments(SgDeploymentsQuery::default())
+        .await?
+        .into_iter()
+        .filter(|deployment| overwrite || deployment.name.is_none())
+        .map(|deployment| deployment.cid.to_string())
+        .collect();
+    unnamed.dedup();
+
+    let mut names: BTreeMap<String, String> = BTreeMap::new();
+    for network_subgraph_config in config.network_subgraphs() {
+        if unnamed.is_empty() {
+            break;
+        }
+
+        let network_subgraph = network_subgraph_config.client(metrics)?;
+        let versions = match network_subgraph.deployment_versions(&unnamed).await {
+            Ok(versions) => versions,
+            Err(err) => {
+                error!(
+                    network_subgraph = ?network_subgraph_config.name,
+                    error = %err,
+                    "Failed to query deployment versions from the network subgraph"
+                );
+                continue;
+            }
+        };
+
+        for version in versions {
+            if let Some(name) = deployment_name(&version) {
+                names.entry(version.deployment).or_insert(name);
+            }
+        }
+        unnamed.retain(|deployment| !names.contains_key(deployment));
+    }
+
+    let names: Vec<DeploymentName> = names
+        .into_iter()
+        .filter_map(|(deployment, name)| {
+            let Ok(ipfs_cid) = deployment.parse::<IpfsCid>() else {
+                warn!(deployment, "Invalid subgraph deployment; ignoring");
+                return None;
+            };
+            Some(DeploymentName { ipfs_cid, name })
+        })
+        .collect();
+    store.set_deployment_names(&names, overwrite).await
+}
+
+/// The display name of the subgraph that `version` belongs to, followed by
+/// the version label if there is one. Versions of subgraphs without a display
+/// name aren't worth naming a deployment after.
+fn deployment_name(version: &DeploymentVersion) -> Option<String> {
+    let display_name = version.display_name.as_deref()?.trim();
+    if display_name.is_empty() {
+        return None;
+    }
+
+    match version.label.as_deref().map(str::trim) {
+        Some(label) if !label.is_empty() => Some(format!("{display_name} {label}")),
+        _ => Some(display_name.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(display_name: Option<&str>, label: Option<&str>) -> DeploymentVersion {
+        DeploymentVersion {
+            deployment: "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA".to_string(),
+            display_name: display_name.map(str::to_string),
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn deployments_are_named_after_display_name_and_label() {
+        assert_eq!(
+            deployment_name(&version(Some("Uniswap V3"), Some("v0.0.1"))).as_deref(),
+            Some("Uniswap V3 v0.0.1")
+        );
+        assert_eq!(
+            deployment_name(&version(Some("Uniswap V3 "), Some(" "))).as_deref(),
+            Some("Uniswap V3")
+        );
+        assert_eq!(
+            deployment_name(&version(Some("Uniswap V3"), None)).as_deref(),
+            Some("Uniswap V3")
+        );
+        assert_eq!(deployment_name(&version(Some(""), Some("v0.0.1"))), None);
+        assert_eq!(deployment_name(&version(None, Some("v0.0.1"))), None);
+    }
+}
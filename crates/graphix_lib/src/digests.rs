@@ -0,0 +1,328 @@
+//! Daily digest emails that summarize the PoI divergences that were detected,
+//! and those that were resolved, since the previous digest
+//! ([`crate::config::AlertsConfig::digests`]). Unlike alerts, which are sent
+//! as soon as a divergence is detected, a digest gives teams that live in
+//! their inbox one message a day to work from.
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use graphix_common_types::inputs::EventsQuery;
+use graphix_common_types::{EventKind, IndexerAddress, IpfsCid};
+use graphix_store::Store;
+use tracing::{info, warn};
+
+use crate::alerts::{involved_indexers, routing_context, AlertSeverity};
+use crate::config::{AlertChannelConfig, AlertMatchers, AlertsConfig, DigestConfig};
+use crate::email::EmailNotifier;
+
+const EVENTS_PAGE_SIZE: u16 = 100;
+
+/// A subgraph deployment and an indexer whose PoI for it diverges from the
+/// consensus PoI.
+type Divergence = (IpfsCid, IndexerAddress);
+
+/// The name under which each digest keeps track of the events it
+/// summarized.
+fn event_consumer(channel: &str) -> String {
+    format!("digest:{channel}")
+}
+
+/// The contents of a digest.
+#[derive(Debug, Default, PartialEq)]
+pub struct Digest {
+    /// Divergences that were detected since the previous digest.
+    pub new: BTreeSet<Divergence>,
+    /// Divergences that were diverging at the time of the previous digest, or
+    /// were detected since, and no longer are.
+    pub resolved: BTreeSet<Divergence>,
+    /// How many divergences are ongoing.
+    pub ongoing: usize,
+}
+
+impl Digest {
+    pub fn new(
+        previous: &BTreeSet<Divergence>,
+        detected: &BTreeSet<Divergence>,
+        current: &BTreeSet<Divergence>,
+    ) -> Self {
+        Self {
+            new: detected.difference(previous).cloned().collect(),
+            resolved: previous
+                .union(detected)
+                .filter(|divergence| !current.contains(divergence))
+                .cloned()
+                .collect(),
+            ongoing: current.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.resolved.is_empty()
+    }
+
+    pub fn subject(&self) -> String {
+        format!(
+            "[Graphix] PoI divergence digest: {} new, {} resolved",
+            self.new.len(),
+            self.resolved.len()
+        )
+    }
+
+    /// Renders the digest as plain text, with the network of each subgraph
+    /// deployment as given by `networks`.
+    pub fn body(&self, networks: &HashMap<String, String>) -> String {
+        let mut body = String::new();
+        for (title, divergences) in [
+            ("New divergences", &self.new),
+            ("Resolved divergences", &self.resolved),
+        ] {
+            body.push_str(&format!("{title} ({}):\n", divergences.len()));
+            for (deployment, indexer) in divergences {
+                let deployment = deployment.to_string();
+                match networks.get(&deployment) {
+                    Some(network) => {
+                        body.push_str(&format!("  - {deployment} ({network}): {indexer}\n"))
+                    }
+                    None => body.push_str(&format!("  - {deployment}: {indexer}\n")),
+                }
+            }
+            body.push('\n');
+        }
+        body.push_str(&format!("Ongoing divergences: {}\n", self.ongoing));
+        body
+    }
+}
+
+/// What was diverging when a digest was last sent.
+struct SentDigest {
+    date: NaiveDate,
+    diverging: BTreeSet<Divergence>,
+}
+
+#[derive(Default)]
+pub struct DivergenceDigests {
+    /// By channel name. Only kept in memory, so after a restart the first
+    /// digest can't tell which divergences were resolved before the restart.
+    sent: HashMap<String, SentDigest>,
+}
+
+impl DivergenceDigests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the digests that are due at `now`, i.e. those that weren't sent
+    /// yet today and whose hour has come. `diverging_indexers` are the
+    /// indexers that currently diverge, as returned by
+    /// [`crate::events::DivergenceDetector::diverging_indexers`]. Digests
+    /// without new or resolved divergences aren't sent.
+    pub async fn send_due(
+        &mut self,
+        store: &Store,
+        config: &AlertsConfig,
+        diverging_indexers: &HashMap<IpfsCid, BTreeSet<IndexerAddress>>,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let today = now.date_naive();
+        let due: Vec<&DigestConfig> = config
+            .digests
+            .iter()
+            .filter(|digest| {
+                now.hour() >= digest.hour_utc
+                    && self
+                        .sent
+                        .get(&digest.channel)
+                        .map_or(true, |sent| sent.date < today)
+            })
+            .collect();
+        if due.is_empty() {
+            return Ok(());
+        }
+
+        let (networks, indexer_tags) = routing_context(store).await?;
+        let matches = |matchers: &AlertMatchers, (deployment, indexer): &Divergence| {
+            let tags = indexer_tags
+                .get(indexer)
+                .map(|tags| tags.iter().cloned().collect())
+                .unwrap_or_default();
+            matchers.matches(
+                Some(deployment),
+                networks.get(&deployment.to_string()).map(String::as_str),
+                &tags,
+                AlertSeverity::Critical,
+            )
+        };
+        let current: BTreeSet<Divergence> = diverging_indexers
+            .iter()
+            .flat_map(|(deployment, indexers)| {
+                indexers
+                    .iter()
+                    .map(|indexer| (deployment.clone(), *indexer))
+            })
+            .collect();
+
+        for digest in due {
+            let Some(AlertChannelConfig::Email(channel)) = config.channel(&digest.channel) else {
+                warn!(
+                    channel = digest.channel,
+                    "Digest refers to a channel that isn't an email channel"
+                );
+                continue;
+            };
+
+            let consumer = event_consumer(&digest.channel);
+            let (detected, cursor) = detected_divergences(store, &consumer).await?;
+            let previous = self
+                .sent
+                .get(&digest.channel)
+                .map(|sent| sent.diverging.clone())
+                .unwrap_or_default();
+            let filter = |divergences: &BTreeSet<Divergence>| -> BTreeSet<Divergence> {
+                divergences
+                    .iter()
+                    .filter(|divergence| matches(&digest.matchers, divergence))
+                    .cloned()
+                    .collect()
+            };
+            let current = filter(&current);
+            let summary = Digest::new(&filter(&previous), &filter(&detected), &current);
+
+            if !summary.is_empty() {
+                let sent = match EmailNotifier::new(channel) {
+                    Ok(notifier) => {
+                        notifier
+                            .send(&summary.subject(), summary.body(&networks))
+                            .await
+                    }
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = sent {
+                    // Leave the cursor and the previous digest alone, so that
+                    // sending is retried with the same contents.
+                    warn!(channel = digest.channel, error = %err, "Failed to send digest");
+                    continue;
+                }
+                info!(
+                    channel = digest.channel,
+                    new = summary.new.len(),
+                    resolved = summary.resolved.len(),
+                    "Sent divergence digest"
+                );
+            }
+
+            store.set_event_cursor(&consumer, cursor).await?;
+            self.sent.insert(
+                digest.channel.clone(),
+                SentDigest {
+                    date: today,
+                    diverging: current,
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// The divergences in the [`EventKind::PoiDivergenceDetected`] events after
+/// the cursor of `consumer`, and the cursor to continue from. Without a
+/// cursor, the digest was just enabled and starts with the events appended
+/// from now on.
+async fn detected_divergences(
+    store: &Store,
+    consumer: &str,
+) -> anyhow::Result<(BTreeSet<Divergence>, i64)> {
+    let Some(mut cursor) = store.event_cursor(consumer).await? else {
+        let cursor = store.latest_event_id().await?.unwrap_or(0);
+        return Ok((BTreeSet::new(), cursor));
+    };
+
+    let mut detected = BTreeSet::new();
+    loop {
+        let filter = EventsQuery {
+            kinds: vec![EventKind::PoiDivergenceDetected],
+            after_id: Some(cursor),
+            limit: Some(EVENTS_PAGE_SIZE),
+            ..Default::default()
+        };
+        let events = store.events(filter).await?;
+        let Some(last_event) = events.last() else {
+            break;
+        };
+
+        for event in &events {
+            let Some(deployment) = &event.deployment else {
+                continue;
+            };
+            for indexer in involved_indexers(event) {
+                detected.insert((deployment.clone(), indexer));
+            }
+        }
+
+        cursor = last_event.id;
+        if events.len() < EVENTS_PAGE_SIZE as usize {
+            break;
+        }
+    }
+
+    Ok((detected, cursor))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const DEPLOYMENT: &str = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+
+    fn divergence(indexer: u8) -> Divergence {
+        (
+            IpfsCid::from_str(DEPLOYMENT).unwrap(),
+            IndexerAddress::from([indexer; 20]),
+        )
+    }
+
+    fn divergences(indexers: &[u8]) -> BTreeSet<Divergence> {
+        indexers.iter().copied().map(divergence).collect()
+    }
+
+    #[test]
+    fn new_and_resolved_divergences() {
+        // 1 was diverging at the time of the previous digest and still is, 2
+        // was and no longer is, 3 and 4 were detected since and only 4 still
+        // diverges.
+        let digest = Digest::new(
+            &divergences(&[1, 2]),
+            &divergences(&[1, 3, 4]),
+            &divergences(&[1, 4]),
+        );
+
+        assert_eq!(digest.new, divergences(&[3, 4]));
+        assert_eq!(digest.resolved, divergences(&[2, 3]));
+        assert_eq!(digest.ongoing, 2);
+        assert!(Digest::new(&divergences(&[1]), &divergences(&[1]), &divergences(&[1])).is_empty());
+    }
+
+    #[test]
+    fn render_digest() {
+        let digest = Digest::new(&divergences(&[2]), &divergences(&[1]), &divergences(&[1]));
+        let networks = HashMap::from([(DEPLOYMENT.to_string(), "mainnet".to_string())]);
+
+        assert_eq!(
+            digest.subject(),
+            "[Graphix] PoI divergence digest: 1 new, 1 resolved"
+        );
+        assert_eq!(
+            digest.body(&networks),
+            format!(
+                "New divergences (1):\n  - {DEPLOYMENT} (mainnet): {}\n\n\
+                 Resolved divergences (1):\n  - {DEPLOYMENT} (mainnet): {}\n\n\
+                 Ongoing divergences: 1\n",
+                divergence(1).1,
+                divergence(2).1,
+            )
+        );
+    }
+}
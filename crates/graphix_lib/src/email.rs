@@ -0,0 +1,60 @@
+//! Sends plain text emails through an SMTP server, for the email channels of
+//! [`crate::alerts`] and [`crate::digests`].
+
+use std::time::Duration;
+
+use anyhow::Context;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::EmailChannelConfig;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends emails to the recipients of an [`EmailChannelConfig`].
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailNotifier {
+    pub fn new(config: &EmailChannelConfig) -> anyhow::Result<Self> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(&config.smtp_url)
+            .context("invalid SMTP URL")?
+            .timeout(Some(SEND_TIMEOUT))
+            .build();
+        let from = config
+            .from
+            .parse()
+            .with_context(|| format!("invalid sender address {}", config.from))?;
+        let to = config
+            .to
+            .iter()
+            .map(|to| {
+                to.parse()
+                    .with_context(|| format!("invalid recipient address {to}"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            transport,
+            from,
+            to,
+        })
+    }
+
+    pub async fn send(&self, subject: &str, body: String) -> anyhow::Result<()> {
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+
+        self.transport.send(message.body(body)?).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,438 @@
+//! Producers of the event log. Subsystems append events to it (see
+//! [`EventKind`]), and GraphQL subscriptions and webhooks read them back, so
+//! that there's a single place where Graphix signals that something happened.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use graphix_common_types::{
+    DivergenceInvestigationReport, EventKind, IndexerAddress, IndexerErrorClass, IpfsCid, PoiBytes,
+};
+use graphix_indexer_client::{ProofOfIndexing, WithIndexer};
+use graphix_store::models::{NewEvent, NewIndexerError};
+use graphix_store::Store;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::subgraph_feed::feed_entries;
+
+/// The payload of [`EventKind::PoiDivergenceDetected`] events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoiDivergenceDetected {
+    pub deployment: IpfsCid,
+    pub block_number: i64,
+    pub consensus_poi: PoiBytes,
+    pub divergences: Vec<DivergingIndexer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergingIndexer {
+    pub indexer_address: IndexerAddress,
+    pub poi: PoiBytes,
+    /// How to reach the operator of the indexer, if known.
+    pub operator_contact: Option<String>,
+}
+
+/// The payload of [`EventKind::InvestigationCompleted`] events. The full
+/// report can be queried with its UUID.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvestigationCompleted {
+    pub uuid: Uuid,
+    pub bisection_runs: usize,
+    pub error: Option<String>,
+}
+
+/// The payload of [`EventKind::IndexerUnreachable`] events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexerUnreachable {
+    pub indexer_address: IndexerAddress,
+    pub error_class: IndexerErrorClass,
+    pub message: String,
+}
+
+/// The payload of [`EventKind::ConfigChanged`] events. Only the names of the
+/// changed fields are included, as the configuration may contain secrets.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChanged {
+    pub changed_fields: Vec<String>,
+}
+
+/// Keeps track of which divergences were already reported, so that an event
+/// is only appended when the set of diverging indexers of a subgraph
+/// deployment changes, rather than on every polling cycle.
+#[derive(Default)]
+pub struct DivergenceDetector {
+    diverging_indexers: HashMap<IpfsCid, BTreeSet<IndexerAddress>>,
+}
+
+impl DivergenceDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The indexers whose PoIs diverged from the consensus PoI of each
+    /// subgraph deployment when it was last polled.
+    pub fn diverging_indexers(&self) -> &HashMap<IpfsCid, BTreeSet<IndexerAddress>> {
+        &self.diverging_indexers
+    }
+
+    /// Appends a [`EventKind::PoiDivergenceDetected`] event for each subgraph
+    /// deployment with new divergences among `pois`, and returns those
+    /// subgraph deployments. The PoIs of deployments that are `paused` on
+    /// their indexer are ignored, as they're expected to fall behind.
+    pub async fn detect(
+        &mut self,
+        store: &Store,
+        pois: &[ProofOfIndexing],
+        paused: &HashSet<(IndexerAddress, IpfsCid)>,
+    ) -> anyhow::Result<Vec<IpfsCid>> {
+        let mut divergences = self.new_divergences(pois, paused)?;
+        if divergences.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let operator_contacts: HashMap<IndexerAddress, String> = store
+            .indexers(Default::default())
+            .await?
+            .into_iter()
+            .filter_map(|indexer| Some((indexer.address, indexer.operator_contact()?.to_string())))
+            .collect();
+        for divergence in divergences
+            .iter_mut()
+            .flat_map(|divergence| &mut divergence.divergences)
+        {
+            divergence.operator_contact =
+                operator_contacts.get(&divergence.indexer_address).cloned();
+        }
+
+        let events = divergences
+            .iter()
+            .map(|divergence| {
+                Ok(NewEvent {
+                    kind: EventKind::PoiDivergenceDetected,
+                    deployment: Some(divergence.deployment.clone()),
+                    payload: serde_json::to_value(divergence)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        store.write_events(&events).await?;
+
+        Ok(divergences
+            .into_iter()
+            .map(|divergence| divergence.deployment)
+            .collect())
+    }
+
+    fn new_divergences(
+        &mut self,
+        pois: &[ProofOfIndexing],
+        paused: &HashSet<(IndexerAddress, IpfsCid)>,
+    ) -> anyhow::Result<Vec<PoiDivergenceDetected>> {
+        let pois: Vec<ProofOfIndexing> = pois
+            .iter()
+            .filter(|poi| !paused.contains(&(poi.indexer.address(), poi.deployment.clone())))
+            .cloned()
+            .collect();
+        let (consensus, divergences) = feed_entries(&pois)?;
+
+        let mut detected: BTreeMap<IpfsCid, PoiDivergenceDetected> = BTreeMap::new();
+        for divergence in divergences {
+            detected
+                .entry(divergence.deployment.clone())
+                .or_insert_with(|| PoiDivergenceDetected {
+                    deployment: divergence.deployment,
+                    block_number: divergence.block_number,
+                    consensus_poi: divergence.consensus_poi,
+                    divergences: vec![],
+                })
+                .divergences
+                .push(DivergingIndexer {
+                    indexer_address: divergence.indexer_address,
+                    poi: divergence.poi,
+                    operator_contact: None,
+                });
+        }
+
+        // Deployments without divergences are reset, so that a later
+        // divergence is reported again.
+        for entry in consensus {
+            let diverging_indexers = detected
+                .get(&entry.deployment)
+                .map(|d| d.divergences.iter().map(|d| d.indexer_address).collect())
+                .unwrap_or_default();
+            let previous = self
+                .diverging_indexers
+                .insert(entry.deployment.clone(), diverging_indexers);
+            if previous.as_ref() == self.diverging_indexers.get(&entry.deployment) {
+                detected.remove(&entry.deployment);
+            }
+        }
+
+        Ok(detected.into_values().collect())
+    }
+}
+
+/// Keeps track of which indexers couldn't be reached during the previous
+/// polling cycle, so that an event is only appended when an indexer becomes
+/// unreachable.
+#[derive(Default)]
+pub struct UnreachableIndexers {
+    unreachable: HashSet<IndexerAddress>,
+}
+
+impl UnreachableIndexers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns [`EventKind::IndexerUnreachable`] events for the indexers that
+    /// `errors` of the current polling cycle show as unreachable, unless they
+    /// already were during the previous one. Indexers that weren't `polled`
+    /// in this cycle keep their previous state.
+    pub fn update(
+        &mut self,
+        polled: &HashSet<IndexerAddress>,
+        errors: &[WithIndexer<NewIndexerError>],
+    ) -> Vec<NewEvent> {
+        let mut unreachable: HashSet<IndexerAddress> = self
+            .unreachable
+            .iter()
+            .filter(|address| !polled.contains(address))
+            .copied()
+            .collect();
+        let mut events = vec![];
+        for error in errors
+            .iter()
+            .filter(|error| is_unreachable(error.inner.class))
+        {
+            let indexer_address = error.indexer.address();
+            if polled.contains(&indexer_address)
+                && unreachable.insert(indexer_address)
+                && !self.unreachable.contains(&indexer_address)
+            {
+                let payload = IndexerUnreachable {
+                    indexer_address,
+                    error_class: error.inner.class,
+                    message: error.inner.message.clone(),
+                };
+                events.push(NewEvent {
+                    kind: EventKind::IndexerUnreachable,
+                    deployment: None,
+                    payload: serde_json::to_value(payload).unwrap(),
+                });
+            }
+        }
+
+        self.unreachable = unreachable;
+        events
+    }
+}
+
+fn is_unreachable(class: IndexerErrorClass) -> bool {
+    matches!(
+        class,
+        IndexerErrorClass::Dns
+            | IndexerErrorClass::Tls
+            | IndexerErrorClass::Connection
+            | IndexerErrorClass::Timeout
+    )
+}
+
+/// Returns a [`EventKind::ConfigChanged`] event if any top-level field of the
+/// configuration changed.
+pub fn config_changed(previous: &Config, current: &Config) -> anyhow::Result<Option<NewEvent>> {
+    let changed_fields = changed_config_fields(
+        &serde_json::to_value(previous)?,
+        &serde_json::to_value(current)?,
+    )?;
+    if changed_fields.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(NewEvent {
+        kind: EventKind::ConfigChanged,
+        deployment: None,
+        payload: serde_json::to_value(ConfigChanged { changed_fields })?,
+    }))
+}
+
+/// Returns the names of the top-level fields that differ between two
+/// serialized configurations, without their values, which may be secret.
+pub fn changed_config_fields(
+    previous: &serde_json::Value,
+    current: &serde_json::Value,
+) -> anyhow::Result<Vec<String>> {
+    let (serde_json::Value::Object(previous), serde_json::Value::Object(current)) =
+        (previous, current)
+    else {
+        anyhow::bail!("the configuration isn't serialized as a JSON object");
+    };
+
+    let fields: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+    Ok(fields
+        .into_iter()
+        .filter(|field| previous.get(*field) != current.get(*field))
+        .cloned()
+        .collect())
+}
+
+/// Returns the [`EventKind::InvestigationCompleted`] event of a concluded
+/// divergence investigation of PoIs of `deployment`.
+pub fn investigation_completed(
+    report: &DivergenceInvestigationReport,
+    deployment: Option<IpfsCid>,
+) -> anyhow::Result<NewEvent> {
+    let payload = InvestigationCompleted {
+        uuid: report.uuid,
+        bisection_runs: report.bisection_runs.len(),
+        error: report.error.clone(),
+    };
+
+    Ok(NewEvent {
+        kind: EventKind::InvestigationCompleted,
+        deployment,
+        payload: serde_json::to_value(payload)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use graphix_indexer_client::{BlockPointer, IndexerClient};
+
+    use super::*;
+    use crate::test_utils::mocks::MockIndexer;
+
+    fn mock_indexer(name: &str) -> Arc<dyn IndexerClient> {
+        Arc::new(MockIndexer {
+            name: name.to_string(),
+            deployment_details: vec![],
+            fail_indexing_statuses: false,
+        })
+    }
+
+    fn poi(indexer_name: &str, poi_byte: u8) -> ProofOfIndexing {
+        ProofOfIndexing {
+            indexer: mock_indexer(indexer_name),
+            deployment: IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA")
+                .unwrap(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: None,
+            },
+            proof_of_indexing: [poi_byte; 32].into(),
+        }
+    }
+
+    fn error(indexer_name: &str, class: IndexerErrorClass) -> WithIndexer<NewIndexerError> {
+        WithIndexer::new(
+            mock_indexer(indexer_name),
+            NewIndexerError {
+                class,
+                message: "boo".to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn divergences_are_detected_once() {
+        let mut detector = DivergenceDetector::new();
+        let diverging = [poi("a", 1), poi("b", 1), poi("c", 2)];
+
+        let detected = detector
+            .new_divergences(&diverging, &HashSet::new())
+            .unwrap();
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].consensus_poi, [1; 32].into());
+        assert_eq!(detected[0].divergences.len(), 1);
+
+        assert!(detector
+            .new_divergences(&diverging, &HashSet::new())
+            .unwrap()
+            .is_empty());
+
+        // Once resolved, a new divergence is detected again.
+        let agreeing = [poi("a", 1), poi("b", 1), poi("c", 1)];
+        assert!(detector
+            .new_divergences(&agreeing, &HashSet::new())
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            detector
+                .new_divergences(&diverging, &HashSet::new())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn paused_deployments_are_ignored() {
+        let mut detector = DivergenceDetector::new();
+        let diverging = [poi("a", 1), poi("b", 1), poi("c", 2)];
+        let paused =
+            HashSet::from([(mock_indexer("c").address(), diverging[2].deployment.clone())]);
+
+        assert!(detector
+            .new_divergences(&diverging, &paused)
+            .unwrap()
+            .is_empty());
+        assert!(detector.diverging_indexers()[&diverging[2].deployment].is_empty());
+
+        // Once resumed, the divergence is reported.
+        assert_eq!(
+            detector
+                .new_divergences(&diverging, &HashSet::new())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn indexers_are_reported_unreachable_once() {
+        let mut unreachable = UnreachableIndexers::new();
+        let errors = [
+            error("a", IndexerErrorClass::Timeout),
+            error("a", IndexerErrorClass::Dns),
+            error("b", IndexerErrorClass::GraphqlError),
+        ];
+
+        let polled = errors.iter().map(|error| error.indexer.address()).collect();
+
+        assert_eq!(unreachable.update(&polled, &errors).len(), 1);
+        assert!(unreachable.update(&polled, &errors).is_empty());
+
+        // Indexers that weren't polled stay unreachable.
+        assert!(unreachable.update(&HashSet::new(), &[]).is_empty());
+        assert!(unreachable.update(&polled, &errors).is_empty());
+
+        // Once reachable again, the indexer is reported again.
+        assert!(unreachable.update(&polled, &[]).is_empty());
+        assert_eq!(unreachable.update(&polled, &errors).len(), 1);
+    }
+
+    #[test]
+    fn config_changes_list_changed_fields() {
+        let previous = Config::default();
+        assert!(config_changed(&previous, &previous).unwrap().is_none());
+
+        let current = Config {
+            polling_period_in_seconds: previous.polling_period_in_seconds + 1,
+            ..Config::default()
+        };
+        let event = config_changed(&previous, &current).unwrap().unwrap();
+        assert_eq!(
+            event.payload,
+            serde_json::json!({ "changedFields": ["pollingPeriodInSeconds"] })
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Storage of failed indexer queries for debugging. Queries and responses are
+//! redacted and truncated according to [`FailedQueriesConfig`] first, because
+//! responses can be huge or contain sensitive data echoed back by proxies.
+//! Failed queries can be inspected, and retried, through the GraphQL API.
+
+use std::sync::Arc;
+
+use graphix_indexer_client::{IndexerClient, IndexerId, QueryFailure, WithIndexer};
+use graphix_store::Store;
+use regex::Regex;
+use tracing::*;
+
+use crate::config::FailedQueriesConfig;
+use crate::PrometheusMetrics;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Redacts and truncates failed queries before they're stored.
+#[derive(Debug)]
+pub struct FailedQuerySanitizer {
+    max_bytes: usize,
+    redaction_patterns: Vec<Regex>,
+}
+
+/// The stored contents of a failed query.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SanitizedQuery {
+    pub raw_query: String,
+    pub response: String,
+    pub truncated: bool,
+    pub redacted: bool,
+}
+
+impl FailedQuerySanitizer {
+    pub fn new(config: &FailedQueriesConfig) -> anyhow::Result<Self> {
+        let redaction_patterns = config
+            .redaction_patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            max_bytes: config.max_response_bytes,
+            redaction_patterns,
+        })
+    }
+
+    pub fn sanitize(&self, failure: &QueryFailure) -> SanitizedQuery {
+        let (raw_query, query_redacted) = self.redact(&failure.raw_query);
+        let (response, response_redacted) = self.redact(&failure.response);
+        let (raw_query, query_truncated) = truncate(raw_query, self.max_bytes);
+        let (response, response_truncated) = truncate(response, self.max_bytes);
+
+        SanitizedQuery {
+            raw_query,
+            response,
+            truncated: query_truncated || response_truncated,
+            redacted: query_redacted || response_redacted,
+        }
+    }
+
+    fn redact(&self, s: &str) -> (String, bool) {
+        let mut redacted = false;
+        let mut s = s.to_string();
+        for pattern in &self.redaction_patterns {
+            if pattern.is_match(&s) {
+                s = pattern.replace_all(&s, REDACTED).into_owned();
+                redacted = true;
+            }
+        }
+        (s, redacted)
+    }
+}
+
+/// Truncates `s` to at most `max_bytes` (on a character boundary), followed by
+/// a note of how much was cut.
+fn truncate(mut s: String, max_bytes: usize) -> (String, bool) {
+    if s.len() <= max_bytes {
+        return (s, false);
+    }
+
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    let truncated_bytes = s.len() - end;
+    s.truncate(end);
+    s.push_str(&format!("... [{truncated_bytes} bytes truncated]"));
+    (s, true)
+}
+
+/// Sanitizes and stores the failures of the query `query_name` that indexers
+/// responded to, then deletes failed queries past their retention period.
+pub async fn store_failed_queries(
+    store: &Store,
+    config: &FailedQueriesConfig,
+    query_name: &str,
+    errors: &[WithIndexer<anyhow::Error>],
+    metrics: &PrometheusMetrics,
+) -> anyhow::Result<()> {
+    let sanitizer = FailedQuerySanitizer::new(config)?;
+
+    for (indexer, failure) in errors.iter().filter_map(|error| {
+        let failure = error.inner.downcast_ref::<QueryFailure>()?;
+        Some((&error.indexer, failure))
+    }) {
+        let sanitized = sanitizer.sanitize(failure);
+        if sanitized.truncated {
+            metrics
+                .failed_queries_truncated
+                .with_label_values(&[query_name])
+                .inc();
+        }
+        if sanitized.redacted {
+            metrics
+                .failed_queries_redacted
+                .with_label_values(&[query_name])
+                .inc();
+        }
+
+        store
+            .write_failed_query(
+                indexer,
+                query_name,
+                &sanitized.raw_query,
+                &sanitized.response,
+            )
+            .await?;
+        debug!(
+            indexer = %indexer.address_string(),
+            query_name,
+            truncated = sanitized.truncated,
+            redacted = sanitized.redacted,
+            "Stored failed query"
+        );
+    }
+
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(config.retention_days.into());
+    let deleted = store
+        .delete_failed_queries_before(cutoff.naive_utc())
+        .await?;
+    if deleted > 0 {
+        debug!(
+            deleted,
+            "Deleted failed queries past their retention period"
+        );
+    }
+
+    Ok(())
+}
+
+/// The outcome of re-issuing a failed query with [`retry_failed_query`].
+#[derive(Debug)]
+pub struct RetriedQuery {
+    pub succeeded: bool,
+    /// A summary of the response, or why the query failed again.
+    pub message: String,
+    /// The sanitized response of the indexer, if the query failed again.
+    pub response: Option<String>,
+}
+
+/// Re-issues the query `query_name` that `indexer` failed to respond to.
+/// Fails if queries named `query_name` can't be retried.
+pub async fn retry_failed_query(
+    indexer: Arc<dyn IndexerClient>,
+    query_name: &str,
+    config: &FailedQueriesConfig,
+) -> anyhow::Result<RetriedQuery> {
+    let result = match query_name {
+        "indexingStatuses" => indexer
+            .indexing_statuses()
+            .await
+            .map(|statuses| format!("Indexer returned {} indexing statuses", statuses.len())),
+        _ => anyhow::bail!("Retrying `{query_name}` queries isn't supported"),
+    };
+
+    Ok(match result {
+        Ok(message) => RetriedQuery {
+            succeeded: true,
+            message,
+            response: None,
+        },
+        Err(err) => {
+            let response = match err.downcast_ref::<QueryFailure>() {
+                Some(failure) => Some(
+                    FailedQuerySanitizer::new(config)?
+                        .sanitize(failure)
+                        .response,
+                ),
+                None => None,
+            };
+            RetriedQuery {
+                succeeded: false,
+                message: format!("{err:#}"),
+                response,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use graphix_common_types::IndexerErrorClass;
+
+    use super::*;
+
+    fn failure(raw_query: &str, response: &str) -> QueryFailure {
+        QueryFailure {
+            raw_query: raw_query.to_string(),
+            response: response.to_string(),
+            class: IndexerErrorClass::GraphqlError,
+            message: "Indexer returned errors".to_string(),
+        }
+    }
+
+    #[test]
+    fn truncates_on_char_boundaries() {
+        assert_eq!(
+            truncate("short".to_string(), 10),
+            ("short".to_string(), false)
+        );
+        // 'é' is 2 bytes long, so it can't be cut in half.
+        assert_eq!(
+            truncate("abcé".to_string(), 4),
+            ("abc... [2 bytes truncated]".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn redacts_before_truncating() {
+        let sanitizer = FailedQuerySanitizer::new(&FailedQueriesConfig {
+            max_response_bytes: 32,
+            redaction_patterns: vec![r"(?i)authorization: .+".to_string()],
+            retention_days: 1,
+        })
+        .unwrap();
+
+        let sanitized = sanitizer.sanitize(&failure(
+            "{ indexingStatuses { subgraph } }",
+            "Authorization: Bearer secret\nno such subgraph",
+        ));
+        assert_eq!(sanitized.response, "[REDACTED]\nno such subgraph");
+        assert!(sanitized.redacted);
+        assert!(sanitized.truncated);
+        assert_eq!(
+            sanitized.raw_query,
+            "{ indexingStatuses { subgraph } ... [1 bytes truncated]"
+        );
+    }
+
+    #[test]
+    fn invalid_redaction_patterns_are_rejected() {
+        let config = FailedQueriesConfig {
+            redaction_patterns: vec!["(".to_string()],
+            ..Default::default()
+        };
+        assert!(FailedQuerySanitizer::new(&config).is_err());
+    }
+}
@@ -1,13 +1,20 @@
 //! GraphQL-compatible wrapper types around [`graphix_store::models`] and other
 //! Graphix internal types.
 
-use async_graphql::{ComplexObject, Context, Object, SimpleObject};
-use common::{IndexerAddress, IpfsCid};
+use std::collections::BTreeMap;
+
+use async_graphql::{ComplexObject, Context, Enum, Interface, Object, SimpleObject};
+use common::{IndexerAddress, IndexerErrorClass, IpfsCid};
 use graphix_common_types::{self as common, ApiKeyPermissionLevel};
-use graphix_store::models::{self, IntId};
+use graphix_store::models::{self, BigIntId, IntId};
+use graphix_store::IdenticalPoisKey;
 use num_traits::cast::ToPrimitive;
 
-use super::{ctx_data, GraphixState};
+use super::{
+    ctx_data, poi_agreement, require_permission_level, ApiResult, GraphixApiError, GraphixState,
+};
+use crate::connectivity::ConnectivityCheck;
+use crate::substreams::NULL_POI;
 
 #[derive(Clone, derive_more::From)]
 pub struct SubgraphDeployment {
@@ -23,7 +30,17 @@ impl SubgraphDeployment {
         self.model.name.as_deref()
     }
 
-    pub async fn network(&self, ctx: &GraphixState) -> Result<Network, String> {
+    /// See [`graphix_store::models::SgDeployment::manifest`].
+    pub fn subgraph_manifest(&self) -> anyhow::Result<Option<common::SubgraphManifest>> {
+        Ok(self
+            .model
+            .manifest
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()?)
+    }
+
+    pub async fn network(&self, ctx: &GraphixState) -> ApiResult<Network> {
         let loader = &ctx.loader_network;
 
         loader
@@ -31,7 +48,9 @@ impl SubgraphDeployment {
             .await
             .map(|opt| opt.map(Into::into))
             .map_err(Into::into)
-            .and_then(|opt: Option<Network>| opt.ok_or_else(|| "Network not found".to_string()))
+            .and_then(|opt: Option<Network>| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Network not found".to_string()))
+            })
     }
 }
 
@@ -51,9 +70,95 @@ impl SubgraphDeployment {
 
     /// Network of the subgraph deployment.
     #[graphql(name = "network")]
-    async fn graphql_network(&self, ctx: &Context<'_>) -> Result<Network, String> {
+    async fn graphql_network(&self, ctx: &Context<'_>) -> ApiResult<Network> {
         self.network(ctx_data(ctx)).await
     }
+
+    /// Metadata from the subgraph deployment's manifest, including its graft
+    /// lineage. Only available once the manifest was fetched from IPFS.
+    async fn manifest(&self) -> ApiResult<Option<common::SubgraphManifest>> {
+        Ok(self.subgraph_manifest()?)
+    }
+
+    /// The names of the network subgraphs on which indexers allocated to the
+    /// subgraph deployment.
+    async fn network_subgraphs(&self, ctx: &Context<'_>) -> ApiResult<Vec<String>> {
+        let loader = &ctx_data(ctx).loader_sg_deployment_network_subgraphs;
+        Ok(loader.load_one(self.model.id).await?.unwrap_or_default())
+    }
+
+    /// When an indexer first reported the subgraph deployment in its indexing
+    /// statuses.
+    async fn first_seen_at(&self) -> Option<chrono::NaiveDateTime> {
+        self.model.first_seen_at
+    }
+
+    /// When an indexer last reported the subgraph deployment in its indexing
+    /// statuses. If it's stale while `status` is still `ACTIVE` or `PAUSED`,
+    /// the indexers that report it are no longer polled, e.g. because they
+    /// are unreachable or no longer discovered.
+    async fn last_seen_at(&self) -> Option<chrono::NaiveDateTime> {
+        self.model.last_seen_at
+    }
+
+    /// Whether indexers still report the subgraph deployment in their
+    /// indexing statuses. `null` if no indexer reported it yet.
+    async fn status(&self) -> Option<common::DeploymentLifecycleStatus> {
+        self.model.status
+    }
+
+    /// Whether the subgraph deployment is substreams-powered, in which case
+    /// indexers may report null PoIs for it. `null` until an indexer was
+    /// asked.
+    async fn substreams(&self) -> Option<bool> {
+        self.model.substreams
+    }
+
+    /// The latest indexing statuses that indexers reported for the subgraph
+    /// deployment, most recently updated first.
+    async fn indexing_statuses(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<IndexingStatus>> {
+        // The same IPFS CID may be indexed on other networks.
+        let statuses = ctx_data(ctx)
+            .store
+            .indexing_statuses(&[self.model.cid.clone()], None, None)
+            .await?;
+
+        Ok(statuses
+            .into_iter()
+            .filter(|status| status.sg_deployment_id == self.model.id)
+            .take(limit.into())
+            .map(Into::into)
+            .collect())
+    }
+
+    /// How the indexing statuses that indexers reported for the subgraph
+    /// deployment changed, most recent first.
+    async fn indexing_status_history(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<IndexingStatusChange>> {
+        let history = ctx_data(ctx)
+            .store
+            .indexing_status_history(None, Some(self.model.id), limit)
+            .await?;
+
+        Ok(history.into_iter().map(Into::into).collect())
+    }
 }
 
 pub struct ApiKey {
@@ -92,6 +197,10 @@ impl Network {
     pub fn caip2(&self) -> Option<&str> {
         self.model.caip2.as_deref()
     }
+
+    pub fn enabled(&self) -> bool {
+        self.model.enabled
+    }
 }
 
 #[Object]
@@ -108,6 +217,13 @@ impl Network {
     pub async fn graphql_caip2(&self) -> Option<&str> {
         self.caip2()
     }
+
+    /// Whether Graphix is currently polling indexers for this network. Disabled
+    /// networks keep all of their data.
+    #[graphql(name = "enabled")]
+    pub async fn graphql_enabled(&self) -> bool {
+        self.enabled()
+    }
 }
 
 /// An indexer that is known to Graphix.
@@ -125,10 +241,14 @@ impl Indexer {
         self.model.name.as_deref()
     }
 
+    pub fn operator_contact(&self) -> Option<&str> {
+        self.model.operator_contact()
+    }
+
     pub async fn graph_node_version(
         &self,
         ctx: &GraphixState,
-    ) -> Result<Option<models::GraphNodeCollectedVersion>, String> {
+    ) -> ApiResult<Option<models::GraphNodeCollectedVersion>> {
         let loader = &ctx.loader_graph_node_collected_version;
 
         if let Some(id) = self.model.graph_node_version {
@@ -150,20 +270,113 @@ impl Indexer {
         self.model.name.clone()
     }
 
+    /// The names of the network subgraphs on which this indexer was found,
+    /// e.g. both `arbitrum-one` and `mainnet`.
+    async fn network_subgraphs(&self, ctx: &Context<'_>) -> ApiResult<Vec<String>> {
+        let loader = &ctx_data(ctx).loader_indexer_network_subgraphs;
+        Ok(loader.load_one(self.model.id).await?.unwrap_or_default())
+    }
+
+    /// How to reach the operator of this indexer, if known. Contact
+    /// information that was set through `setIndexerOperatorContact` takes
+    /// precedence over the network subgraph's account metadata.
+    #[graphql(name = "operatorContact")]
+    async fn graphql_operator_contact(&self) -> Option<String> {
+        self.operator_contact().map(str::to_string)
+    }
+
+    /// The tags that operators assigned to this indexer through
+    /// `addIndexerTag`, sorted alphabetically.
+    async fn tags(&self) -> &[String] {
+        &self.model.tags
+    }
+
+    /// When this indexer was archived with `archiveIndexer`, if it was.
+    async fn archived_at(&self) -> Option<chrono::NaiveDateTime> {
+        self.model.archived_at
+    }
+
+    /// Until when requests to this indexer are skipped by its circuit
+    /// breaker, after too many consecutive failures, if they are.
+    async fn circuit_open_until(&self) -> Option<chrono::NaiveDateTime> {
+        self.model.circuit_open_until
+    }
+
     /// The version of the indexer.
     #[graphql(name = "graphNodeVersion")]
     async fn graphql_graph_node_version(
         &self,
         ctx: &Context<'_>,
-    ) -> Result<Option<models::GraphNodeCollectedVersion>, String> {
+    ) -> ApiResult<Option<models::GraphNodeCollectedVersion>> {
         self.graph_node_version(ctx_data(ctx)).await
     }
 
+    /// The graph-node versions that the indexer ran, oldest first.
+    async fn graph_node_version_history(
+        &self,
+        ctx: &Context<'_>,
+    ) -> ApiResult<Vec<models::IndexerGraphNodeVersion>> {
+        Ok(ctx_data(ctx)
+            .store
+            .indexer_graph_node_version_history(self.model.id)
+            .await?)
+    }
+
+    /// The latest indexing statuses that the indexer reported for its
+    /// subgraph deployments, most recently updated first.
+    async fn indexing_statuses(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<IndexingStatus>> {
+        let statuses = ctx_data(ctx)
+            .store
+            .indexing_statuses(&[], Some(&self.model.address), Some(limit))
+            .await?;
+
+        Ok(statuses.into_iter().map(Into::into).collect())
+    }
+
+    /// How the indexing statuses that the indexer reported for its subgraph
+    /// deployments changed, most recent first.
+    async fn indexing_status_history(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<IndexingStatusChange>> {
+        let history = ctx_data(ctx)
+            .store
+            .indexing_status_history(Some(self.model.id), None, limit)
+            .await?;
+
+        Ok(history.into_iter().map(Into::into).collect())
+    }
+
+    /// How often requests from Graphix to this indexer failed, by error
+    /// class, most frequent first.
+    async fn errors(&self, ctx: &Context<'_>) -> ApiResult<Vec<models::IndexerErrorCount>> {
+        ctx_data(ctx)
+            .store
+            .indexer_errors(self.model.id)
+            .await
+            .map_err(Into::into)
+    }
+
     /// The network subgraph metadata of the indexer.
     async fn network_subgraph_metadata(
         &self,
         ctx: &Context<'_>,
-    ) -> Result<Option<IndexerNetworkSubgraphMetadata>, String> {
+    ) -> ApiResult<Option<IndexerNetworkSubgraphMetadata>> {
         let loader = &ctx_data(ctx).loader_indexer_network_subgraph_metadata;
 
         if let Some(id) = self.model.network_subgraph_metadata {
@@ -176,6 +389,31 @@ impl Indexer {
             Ok(None)
         }
     }
+
+    /// Whether the network subgraph metadata of the indexer wasn't refreshed
+    /// for longer than `networkSubgraphMetadata.staleAfterInSeconds`, e.g.
+    /// because the indexer is no longer on the network subgraph. `null` if
+    /// there's no metadata.
+    async fn network_subgraph_metadata_is_stale(
+        &self,
+        ctx: &Context<'_>,
+    ) -> ApiResult<Option<bool>> {
+        let ctx_data = ctx_data(ctx);
+        let Some(id) = self.model.network_subgraph_metadata else {
+            return Ok(None);
+        };
+        let Some(metadata) = ctx_data
+            .loader_indexer_network_subgraph_metadata
+            .load_one(id)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let stale_after = ctx_data.config().network_subgraph_metadata.stale_after();
+        let age = chrono::Utc::now().naive_utc() - metadata.last_updated_at;
+        Ok(Some(age.to_std().unwrap_or_default() > stale_after))
+    }
 }
 
 #[derive(derive_more::From)]
@@ -249,7 +487,7 @@ impl Block {
         self.model.hash.clone()
     }
 
-    pub async fn network(&self, ctx: &GraphixState) -> Result<Network, String> {
+    pub async fn network(&self, ctx: &GraphixState) -> ApiResult<Network> {
         let loader = &ctx.loader_network;
 
         loader
@@ -257,7 +495,7 @@ impl Block {
             .await
             .map_err(Into::into)
             .and_then(|opt| {
-                opt.ok_or_else(|| "Network not found".to_string())
+                opt.ok_or_else(|| GraphixApiError::NotFound("Network not found".to_string()))
                     .map(Into::into)
             })
     }
@@ -265,13 +503,18 @@ impl Block {
 
 #[Object]
 impl Block {
-    /// Returns an estimate of the timestamp of the block, based on the
-    /// network's block speed and the block's number.
+    /// Returns the timestamp of the block if it was resolved from the
+    /// network's JSON-RPC endpoint, or else an estimate based on the network's
+    /// block speed and the block's number.
     #[graphql(name = "estimatedTimestamp")]
     pub async fn graphql_estimated_timestamp(
         &self,
         ctx: &Context<'_>,
     ) -> Option<chrono::DateTime<chrono::Utc>> {
+        if let Some(timestamp) = self.model.timestamp {
+            return Some(timestamp.and_utc());
+        }
+
         let network = self.network(ctx_data(ctx)).await.ok()?;
         let config = ctx_data(ctx).config();
         let chain_config = config.chains.get(network.name())?;
@@ -309,9 +552,24 @@ impl Block {
         self.model.hash.clone()
     }
 
+    /// The timestamp of the block, as resolved from the network's JSON-RPC
+    /// endpoint. Null if none is configured, or it wasn't resolved yet.
+    #[graphql(name = "timestamp")]
+    async fn graphql_timestamp(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.model.timestamp.map(|timestamp| timestamp.and_utc())
+    }
+
+    /// The hash of the block at this height on the canonical chain, as
+    /// resolved from the network's JSON-RPC endpoint. If it differs from
+    /// `hash`, the block was reorged out.
+    #[graphql(name = "canonicalHash")]
+    async fn graphql_canonical_hash(&self) -> Option<common::BlockHash> {
+        self.model.canonical_hash.clone()
+    }
+
     /// The network that this block belongs to.
     #[graphql(name = "network")]
-    pub async fn graphql_network(&self, ctx: &Context<'_>) -> Result<Network, String> {
+    pub async fn graphql_network(&self, ctx: &Context<'_>) -> ApiResult<Network> {
         self.network(ctx_data(ctx)).await
     }
 }
@@ -328,36 +586,44 @@ impl ProofOfIndexing {
         self.model.poi
     }
 
-    pub async fn deployment(&self, ctx: &GraphixState) -> Result<SubgraphDeployment, String> {
+    pub async fn deployment(&self, ctx: &GraphixState) -> ApiResult<SubgraphDeployment> {
         let loader = &ctx.loader_subgraph_deployment;
 
         loader
             .load_one(self.model.sg_deployment_id)
             .await
             .map_err(Into::into)
-            .and_then(|opt| opt.ok_or_else(|| "Subgraph deployment not found".to_string()))
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
             .map(Into::into)
     }
 
-    pub async fn block(&self, ctx: &GraphixState) -> Result<Block, String> {
+    pub async fn block(&self, ctx: &GraphixState) -> ApiResult<Block> {
         let loader = &ctx.loader_block;
 
         loader
             .load_one(self.model.block_id)
             .await
             .map_err(Into::into)
-            .and_then(|opt| opt.ok_or_else(|| "Block not found".to_string()))
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Block not found".to_string()))
+            })
             .map(Into::into)
     }
 
-    pub async fn indexer(&self, ctx: &GraphixState) -> Result<Indexer, String> {
+    pub async fn indexer(&self, ctx: &GraphixState) -> ApiResult<Indexer> {
         let loader = &ctx.loader_indexer;
 
         loader
             .load_one(self.model.indexer_id)
             .await
             .map_err(Into::into)
-            .and_then(|opt| opt.ok_or_else(|| "Indexer not found".to_string()))
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Indexer not found".to_string()))
+            })
             .map(Into::into)
     }
 }
@@ -366,7 +632,7 @@ impl ProofOfIndexing {
 impl ProofOfIndexing {
     /// The block height and hash for which this PoI is valid.
     #[graphql(name = "block")]
-    async fn graphql_block(&self, ctx: &Context<'_>) -> Result<Block, String> {
+    async fn graphql_block(&self, ctx: &Context<'_>) -> ApiResult<Block> {
         self.block(ctx_data(ctx)).await
     }
 
@@ -378,55 +644,1677 @@ impl ProofOfIndexing {
 
     /// The subgraph deployment that this PoI is for.
     #[graphql(name = "deployment")]
-    async fn graphql_deployment(&self, ctx: &Context<'_>) -> Result<SubgraphDeployment, String> {
+    async fn graphql_deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
         self.deployment(ctx_data(ctx)).await
     }
 
     /// The indexer that produced this PoI.
     #[graphql(name = "indexer")]
-    async fn graphql_indexer(&self, ctx: &Context<'_>) -> Result<Indexer, String> {
+    async fn graphql_indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
         self.indexer(ctx_data(ctx)).await
     }
+
+    /// The protocol epoch during which the PoI's block was produced, if known.
+    async fn epoch(&self) -> Option<i64> {
+        self.model.epoch
+    }
+
+    /// How Graphix came to know about the PoI: polled from the indexer, or
+    /// fetched while bisecting a divergence.
+    async fn source(&self) -> common::PoiSource {
+        self.model.source
+    }
+
+    /// The tokens that the indexer allocated to the subgraph deployment, as
+    /// of the last refresh of network subgraph metadata. Null if the indexer
+    /// has no active allocations to it.
+    async fn allocated_tokens(&self, ctx: &Context<'_>) -> ApiResult<Option<f64>> {
+        let ctx_data = ctx_data(ctx);
+        let deployment = self.deployment(ctx_data).await?;
+        let indexer = self.indexer(ctx_data).await?;
+        let allocations = ctx_data
+            .store
+            .indexer_allocations(&[deployment.cid().clone()])
+            .await?;
+
+        Ok(allocations
+            .into_iter()
+            .find(|allocation| allocation.indexer_address == indexer.address())
+            .and_then(|allocation| allocation.allocated_tokens.to_f64()))
+    }
+
+    /// Other collected PoIs with the exact same hash, e.g. from other indexers,
+    /// newest first.
+    async fn identical_pois(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default = 100,
+            desc = "Upper limit on the number of shown results. Capped at `maxListInputItems` from the configuration."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<ProofOfIndexing>> {
+        let ctx_data = ctx_data(ctx);
+        let max_items = u16::try_from(ctx_data.config().max_list_input_items).unwrap_or(u16::MAX);
+        let key = IdenticalPoisKey {
+            poi_id: self.model.id,
+            limit: limit.min(max_items),
+        };
+        let pois = ctx_data
+            .loader_identical_pois
+            .load_one(key)
+            .await?
+            .unwrap_or_default();
+
+        Ok(pois.into_iter().map(Into::into).collect())
+    }
 }
 
-/// A specific indexer can use `PoiAgreementRatio` to check in how much agreement it is with other
-/// indexers, given its own poi for each deployment. A consensus currently means a majority of
-/// indexers agreeing on a particular POI.
-#[derive(SimpleObject, Debug)]
-#[graphql(complex)]
-pub struct PoiAgreementRatio {
-    #[graphql(skip)]
-    pub poi_id: IntId,
+/// A PoI, typed by how Graphix came to know about it: collected from an
+/// indexer and still live (`LivePoi`) or since superseded (`HistoricalPoi`),
+/// or compared in a bisection run (`BisectionPoi`).
+#[derive(Interface)]
+#[graphql(
+    name = "Poi",
+    field(name = "hash", ty = "common::PoiBytes", desc = "The PoI's hash."),
+    field(
+        name = "block",
+        ty = "ApiResult<Block>",
+        desc = "The block height and hash for which this PoI is valid."
+    ),
+    field(
+        name = "deployment",
+        ty = "ApiResult<SubgraphDeployment>",
+        desc = "The subgraph deployment that this PoI is for."
+    ),
+    field(
+        name = "indexer",
+        ty = "ApiResult<Indexer>",
+        desc = "The indexer that produced this PoI."
+    )
+)]
+pub enum Poi {
+    Live(LivePoi),
+    Historical(HistoricalPoi),
+    Bisection(BisectionPoi),
+}
 
-    /// Total number of indexers that have live pois for the deployment.
-    pub total_indexers: u32,
+/// A PoI that is the most recent one collected from its indexer for its
+/// subgraph deployment.
+pub struct LivePoi {
+    poi: ProofOfIndexing,
+}
 
-    /// Number of indexers that agree on the POI with the specified indexer,
-    /// including the indexer itself.
-    pub n_agreeing_indexers: u32,
+#[Object]
+impl LivePoi {
+    async fn hash(&self) -> common::PoiBytes {
+        self.poi.hash()
+    }
 
-    /// Number of indexers that disagree on the POI with the specified indexer.
-    pub n_disagreeing_indexers: u32,
+    async fn block(&self, ctx: &Context<'_>) -> ApiResult<Block> {
+        self.poi.block(ctx_data(ctx)).await
+    }
 
-    /// Indicates if a consensus on the POI exists among indexers.
-    pub has_consensus: bool,
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        self.poi.deployment(ctx_data(ctx)).await
+    }
 
-    /// Indicates if the specified indexer's POI is part of the consensus.
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        self.poi.indexer(ctx_data(ctx)).await
+    }
+
+    async fn epoch(&self) -> Option<i64> {
+        self.poi.model.epoch
+    }
+
+    async fn collected_at(&self) -> chrono::NaiveDateTime {
+        self.poi.model.created_at
+    }
+
+    /// The agreement of this PoI with the live PoIs of other indexers for the
+    /// same subgraph deployment.
+    async fn agreement(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] consensus_mode: ConsensusMode,
+    ) -> ApiResult<LivePoiAgreement> {
+        let deployment_id = self.poi.model.sg_deployment_id;
+        let tally = poi_agreement::live_poi_tallies(ctx_data(ctx), &[deployment_id])
+            .await?
+            .remove(&deployment_id)
+            .unwrap_or_default()
+            .tally;
+        let consensus_poi = tally.consensus(consensus_mode);
+
+        Ok(LivePoiAgreement {
+            total_indexers: tally.total_indexers(),
+            n_agreeing_indexers: tally.indexers(&self.poi.hash()),
+            has_consensus: consensus_poi.is_some(),
+            in_consensus: consensus_poi == Some(self.poi.hash()),
+            disagreement_stake_weight: tally.disagreement_stake_weight(&self.poi.hash()),
+        })
+    }
+}
+
+/// How many indexers agree with a `LivePoi`, using the same consensus rule as
+/// `poiAgreementRatios`.
+#[derive(SimpleObject, Debug)]
+pub struct LivePoiAgreement {
+    /// Total number of indexers that have live PoIs for the deployment.
+    pub total_indexers: u32,
+    /// Number of indexers whose live PoI is identical, including the PoI's
+    /// own indexer.
+    pub n_agreeing_indexers: u32,
+    /// Whether a majority of indexers, or of their stake, agree on a PoI.
+    pub has_consensus: bool,
+    /// Whether this PoI is the consensus PoI.
     pub in_consensus: bool,
+    /// The share, between 0 and 1, of the stake of all indexers with live
+    /// PoIs for the deployment that belongs to indexers with a different PoI.
+    /// Null if none of them has a stake.
+    pub disagreement_stake_weight: Option<f64>,
 }
 
-#[ComplexObject]
-impl PoiAgreementRatio {
-    /// The PoI in question.
-    #[graphql(name = "poi")]
-    async fn graphql_poi(&self, ctx: &Context<'_>) -> Result<ProofOfIndexing, String> {
-        let loader = &ctx_data(ctx).loader_poi;
+/// A PoI that was collected from an indexer, but has since been superseded by
+/// a more recent PoI or is no longer live because the indexer stopped
+/// reporting PoIs for its subgraph deployment.
+pub struct HistoricalPoi {
+    poi: ProofOfIndexing,
+}
 
-        loader
-            .load_one(self.poi_id)
+#[Object]
+impl HistoricalPoi {
+    async fn hash(&self) -> common::PoiBytes {
+        self.poi.hash()
+    }
+
+    async fn block(&self, ctx: &Context<'_>) -> ApiResult<Block> {
+        self.poi.block(ctx_data(ctx)).await
+    }
+
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        self.poi.deployment(ctx_data(ctx)).await
+    }
+
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        self.poi.indexer(ctx_data(ctx)).await
+    }
+
+    async fn epoch(&self) -> Option<i64> {
+        self.poi.model.epoch
+    }
+
+    async fn collected_at(&self) -> chrono::NaiveDateTime {
+        self.poi.model.created_at
+    }
+
+    /// The live PoI of the same indexer for the same subgraph deployment, if
+    /// any.
+    async fn superseded_by(&self, ctx: &Context<'_>) -> ApiResult<Option<LivePoi>> {
+        let ctx_data = ctx_data(ctx);
+        let deployment = self.poi.deployment(ctx_data).await?;
+        let network = deployment.network(ctx_data).await?;
+        let indexer = self.poi.indexer(ctx_data).await?;
+        let live_pois = ctx_data
+            .store
+            .live_pois(
+                Some(&indexer.address()),
+                Some(network.name()),
+                Some(&[deployment.cid().clone()]),
+                None,
+                None,
+                Some(1),
+            )
+            .await?;
+
+        Ok(live_pois
+            .into_iter()
+            .next()
+            .map(|model| LivePoi { poi: model.into() }))
+    }
+}
+
+/// A collected PoI that a bisection run of a divergence investigation
+/// compared against another PoI.
+pub struct BisectionPoi {
+    poi: ProofOfIndexing,
+    bisection_run: Box<models::BisectionRun>,
+}
+
+#[Object]
+impl BisectionPoi {
+    async fn hash(&self) -> common::PoiBytes {
+        self.poi.hash()
+    }
+
+    async fn block(&self, ctx: &Context<'_>) -> ApiResult<Block> {
+        self.poi.block(ctx_data(ctx)).await
+    }
+
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        self.poi.deployment(ctx_data(ctx)).await
+    }
+
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        self.poi.indexer(ctx_data(ctx)).await
+    }
+
+    async fn bisection_run(&self) -> BisectionRun {
+        (*self.bisection_run).clone().into()
+    }
+
+    /// The PoI that this PoI was compared against.
+    async fn counterpart(&self) -> common::PoiBytes {
+        if self.bisection_run.poi1 == self.poi.hash() {
+            self.bisection_run.poi2
+        } else {
+            self.bisection_run.poi1
+        }
+    }
+}
+
+impl Poi {
+    /// All known occurrences of a PoI hash: every collected PoI with that
+    /// hash, and every bisection run that compared one of them. Optionally
+    /// restricted to subgraph deployments of a single network.
+    pub async fn all_with_hash(
+        ctx: &GraphixState,
+        hash: &common::PoiBytes,
+        network: Option<&str>,
+    ) -> ApiResult<Vec<Poi>> {
+        let mut pois = vec![];
+        let collected = ctx.store.pois_by_value(hash, network).await?;
+        for (model, live) in &collected {
+            let poi = ProofOfIndexing::from(model.clone());
+            pois.push(if *live {
+                Poi::Live(LivePoi { poi })
+            } else {
+                Poi::Historical(HistoricalPoi { poi })
+            });
+        }
+
+        let filter = common::inputs::BisectionRunsQuery {
+            poi: Some(*hash),
+            ..Default::default()
+        };
+        for bisection_run in ctx.store.bisection_runs(&filter).await? {
+            let poi_id = if bisection_run.poi1 == *hash {
+                bisection_run.poi1_id
+            } else {
+                bisection_run.poi2_id
+            };
+            // Bisection runs of PoIs that are no longer stored can't be
+            // represented.
+            let Some(model) = collected
+                .iter()
+                .map(|(model, _)| model)
+                .find(|model| Some(model.id) == poi_id)
+            else {
+                continue;
+            };
+            pois.push(Poi::Bisection(BisectionPoi {
+                poi: model.clone().into(),
+                bisection_run: Box::new(bisection_run),
+            }));
+        }
+
+        Ok(pois)
+    }
+}
+
+/// The latest indexing status flags reported by an indexer for a subgraph
+/// deployment. A paused or failed deployment explains a stale PoI, and is not
+/// by itself a sign of divergence.
+#[derive(derive_more::From)]
+pub struct IndexingStatus {
+    model: models::IndexingStatus,
+}
+
+#[Object]
+impl IndexingStatus {
+    /// The indexer that reported this status.
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        ctx_data(ctx)
+            .loader_indexer
+            .load_one(self.model.indexer_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Indexer not found".to_string()))
+            })
+            .map(Into::into)
+    }
+
+    /// The subgraph deployment this status refers to.
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(self.model.sg_deployment_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
+            .map(Into::into)
+    }
+
+    async fn health(&self) -> common::SubgraphHealth {
+        self.model.health
+    }
+
+    /// Whether the deployment has caught up with the chain head.
+    async fn synced(&self) -> bool {
+        self.model.synced
+    }
+
+    /// Whether the deployment is paused. `null` if it's not assigned to an
+    /// index node.
+    async fn paused(&self) -> Option<bool> {
+        self.model.paused
+    }
+
+    /// The latest block that the deployment was indexed up to. `null` for
+    /// statuses that were stored before block numbers were.
+    async fn latest_block_number(&self) -> Option<i64> {
+        self.model.latest_block_number
+    }
+
+    /// The earliest block that the indexer still has data for, e.g. because
+    /// older blocks were pruned. `null` for statuses that were stored before
+    /// block numbers were.
+    async fn earliest_block_number(&self) -> Option<i64> {
+        self.model.earliest_block_number
+    }
+
+    /// When Graphix last received this status.
+    async fn updated_at(&self) -> chrono::NaiveDateTime {
+        self.model.updated_at
+    }
+}
+
+/// An indexing status that an indexer reported for a subgraph deployment when
+/// its health, sync or pause flags changed, or when the indexer first
+/// reported the deployment.
+#[derive(derive_more::From)]
+pub struct IndexingStatusChange {
+    model: models::IndexingStatusHistoryEntry,
+}
+
+#[Object]
+impl IndexingStatusChange {
+    /// The indexer that reported this status.
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        ctx_data(ctx)
+            .loader_indexer
+            .load_one(self.model.indexer_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Indexer not found".to_string()))
+            })
+            .map(Into::into)
+    }
+
+    /// The subgraph deployment this status refers to.
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(self.model.sg_deployment_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
+            .map(Into::into)
+    }
+
+    async fn health(&self) -> common::SubgraphHealth {
+        self.model.health
+    }
+
+    async fn synced(&self) -> bool {
+        self.model.synced
+    }
+
+    async fn paused(&self) -> Option<bool> {
+        self.model.paused
+    }
+
+    async fn latest_block_number(&self) -> i64 {
+        self.model.latest_block_number
+    }
+
+    async fn earliest_block_number(&self) -> i64 {
+        self.model.earliest_block_number
+    }
+
+    /// When Graphix received this status.
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+}
+
+/// An error that an indexer's `graph-node` reported while indexing a subgraph
+/// deployment.
+#[derive(derive_more::From)]
+pub struct IndexingError {
+    model: models::IndexingError,
+}
+
+#[Object]
+impl IndexingError {
+    /// The indexer that reported this error.
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        ctx_data(ctx)
+            .loader_indexer
+            .load_one(self.model.indexer_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Indexer not found".to_string()))
+            })
+            .map(Into::into)
+    }
+
+    /// The subgraph deployment that failed to index.
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(self.model.sg_deployment_id)
             .await
             .map_err(Into::into)
-            .and_then(|opt| opt.ok_or_else(|| "PoI not found".to_string()))
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
             .map(Into::into)
     }
+
+    /// Whether the error halted indexing.
+    async fn fatal(&self) -> bool {
+        self.model.fatal
+    }
+
+    /// Whether `graph-node` is certain that the error is deterministic.
+    async fn deterministic(&self) -> bool {
+        self.model.deterministic
+    }
+
+    async fn message(&self) -> &str {
+        &self.model.message
+    }
+
+    /// The number of the block at which the error occurred, if known.
+    async fn block_number(&self) -> Option<i64> {
+        self.model.block_number
+    }
+
+    async fn block_hash(&self) -> Option<&common::BlockHash> {
+        self.model.block_hash.as_ref()
+    }
+
+    /// The handler that failed, if known.
+    async fn handler(&self) -> Option<&str> {
+        self.model.handler.as_deref()
+    }
+
+    /// When Graphix first received this error.
+    async fn first_seen_at(&self) -> chrono::NaiveDateTime {
+        self.model.first_seen_at
+    }
+
+    /// When Graphix last received this error.
+    async fn last_seen_at(&self) -> chrono::NaiveDateTime {
+        self.model.last_seen_at
+    }
+}
+
+/// A change in how an indexer reported a subgraph deployment in its indexing
+/// statuses, from one polling cycle to the next.
+#[derive(derive_more::From)]
+pub struct DeploymentEvent {
+    model: models::SgDeploymentEvent,
+}
+
+#[Object]
+impl DeploymentEvent {
+    /// The indexer whose indexing statuses changed.
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        ctx_data(ctx)
+            .loader_indexer
+            .load_one(self.model.indexer_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Indexer not found".to_string()))
+            })
+            .map(Into::into)
+    }
+
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(self.model.sg_deployment_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
+            .map(Into::into)
+    }
+
+    async fn kind(&self) -> common::DeploymentEventKind {
+        self.model.kind
+    }
+
+    /// The polling cycle during which Graphix noticed the change.
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+}
+
+/// How far the latest indexing status of an indexer for a subgraph deployment
+/// is behind the head of its chain, i.e. the latest block according to the
+/// chain's JSON-RPC endpoint, or else to the most advanced indexer.
+#[derive(derive_more::From)]
+pub struct IndexingLag {
+    model: models::IndexingLag,
+}
+
+#[Object]
+impl IndexingLag {
+    /// The lagging subgraph deployment.
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(self.model.sg_deployment_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
+            .map(Into::into)
+    }
+
+    async fn network(&self) -> &str {
+        &self.model.network
+    }
+
+    /// The health of the subgraph deployment. Failed deployments don't
+    /// progress, so their lag only grows.
+    async fn health(&self) -> common::SubgraphHealth {
+        self.model.health
+    }
+
+    async fn latest_block_number(&self) -> i64 {
+        self.model.latest_block_number
+    }
+
+    async fn chain_head_block_number(&self) -> i64 {
+        self.model.chain_head_block_number
+    }
+
+    async fn blocks_behind(&self) -> i64 {
+        self.model.blocks_behind()
+    }
+
+    /// When the indexer reported its indexing status.
+    async fn updated_at(&self) -> chrono::NaiveDateTime {
+        self.model.updated_at
+    }
+}
+
+/// Latency percentiles and success rate of one kind of benchmark request, of
+/// a single indexer or of all indexers together.
+#[derive(derive_more::From)]
+pub struct IndexerLatencyStats {
+    model: models::IndexerLatencyStats,
+}
+
+#[Object]
+impl IndexerLatencyStats {
+    /// The benchmarked indexer, or `null` for all indexers together.
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Option<Indexer>> {
+        let Some(indexer_id) = self.model.indexer_id else {
+            return Ok(None);
+        };
+
+        ctx_data(ctx)
+            .loader_indexer
+            .load_one(indexer_id)
+            .await
+            .map_err(Into::into)
+            .map(|opt| opt.map(Into::into))
+    }
+
+    async fn probe(&self) -> common::LatencyProbe {
+        self.model.probe
+    }
+
+    async fn sample_count(&self) -> i64 {
+        self.model.sample_count
+    }
+
+    /// The share of requests that succeeded, between 0 and 1.
+    async fn success_rate(&self) -> f64 {
+        self.model.success_rate
+    }
+
+    /// The median latency of successful requests, in milliseconds. `null` if
+    /// none succeeded.
+    async fn p50_ms(&self) -> Option<f64> {
+        self.model.p50_ms
+    }
+
+    /// The 90th percentile latency of successful requests, in milliseconds.
+    async fn p90_ms(&self) -> Option<f64> {
+        self.model.p90_ms
+    }
+
+    /// The 99th percentile latency of successful requests, in milliseconds.
+    async fn p99_ms(&self) -> Option<f64> {
+        self.model.p99_ms
+    }
+}
+
+/// A PoI that an indexer submitted on-chain when closing an allocation,
+/// compared with the PoI that Graphix collected from the same indexer's
+/// `graph-node` for the same block.
+#[derive(SimpleObject, Debug)]
+pub struct OnchainPoiCrossCheck {
+    /// ID of the closed allocation.
+    pub allocation_id: String,
+    pub indexer_address: IndexerAddress,
+    pub deployment: IpfsCid,
+    /// The epoch in which the allocation was closed.
+    pub epoch: i64,
+    /// The block the PoI refers to, i.e. the first block of `epoch`.
+    pub block_number: i64,
+    /// The network subgraph the on-chain PoI was found on, if named.
+    pub network_subgraph: Option<String>,
+    pub onchain_poi: common::PoiBytes,
+    /// The PoI that Graphix collected for the same indexer, deployment, and
+    /// block, if any.
+    pub collected_poi: Option<common::PoiBytes>,
+    /// Whether the two PoIs match. `null` if Graphix didn't collect a PoI to
+    /// compare with, if either PoI is the null PoI of a substreams-powered
+    /// subgraph deployment, which is expected, or if they differ at a
+    /// possible reorg.
+    pub matches: Option<bool>,
+    /// Whether the subgraph deployment is substreams-powered.
+    pub substreams: bool,
+    /// Whether indexers reported different block hashes for the block, so
+    /// that the collected PoI may refer to another fork.
+    pub possible_reorg: bool,
+}
+
+impl OnchainPoiCrossCheck {
+    pub fn new(
+        onchain_poi: models::OnchainPoi,
+        collected_poi: Option<common::PoiBytes>,
+        substreams: bool,
+        possible_reorg: bool,
+    ) -> Self {
+        let expected_null =
+            substreams && (onchain_poi.poi == NULL_POI || collected_poi == Some(NULL_POI));
+        let reorged = possible_reorg && collected_poi != Some(onchain_poi.poi);
+
+        Self {
+            allocation_id: onchain_poi.allocation_id,
+            indexer_address: onchain_poi.indexer_address,
+            deployment: onchain_poi.deployment,
+            epoch: onchain_poi.epoch,
+            block_number: onchain_poi.block_number,
+            network_subgraph: onchain_poi.network_subgraph,
+            onchain_poi: onchain_poi.poi,
+            matches: collected_poi
+                .filter(|_| !expected_null && !reorged)
+                .map(|poi| poi == onchain_poi.poi),
+            collected_poi,
+            substreams,
+            possible_reorg,
+        }
+    }
+}
+
+/// A specific indexer can use `PoiAgreementRatio` to check in how much agreement it is with other
+/// indexers, given its own poi for each deployment. A consensus means a majority of indexers, or
+/// of their stake (see `ConsensusMode`), agreeing on a particular POI.
+#[derive(SimpleObject, Debug)]
+#[graphql(complex)]
+pub struct PoiAgreementRatio {
+    #[graphql(skip)]
+    pub poi_id: IntId,
+
+    /// Total number of indexers that have live pois for the deployment.
+    pub total_indexers: u32,
+
+    /// Number of indexers that agree on the POI with the specified indexer,
+    /// including the indexer itself.
+    pub n_agreeing_indexers: u32,
+
+    /// Number of indexers that disagree on the POI with the specified indexer.
+    pub n_disagreeing_indexers: u32,
+
+    /// Indicates if a consensus on the POI exists among indexers.
+    pub has_consensus: bool,
+
+    /// Indicates if the specified indexer's POI is part of the consensus.
+    pub in_consensus: bool,
+
+    /// The share, between 0 and 1, of the stake of all indexers with live
+    /// PoIs for the deployment that belongs to indexers that disagree with
+    /// the specified indexer. Null if none of them has a stake.
+    pub disagreement_stake_weight: Option<f64>,
+}
+
+#[ComplexObject]
+impl PoiAgreementRatio {
+    /// The PoI in question.
+    #[graphql(name = "poi")]
+    async fn graphql_poi(&self, ctx: &Context<'_>) -> ApiResult<ProofOfIndexing> {
+        let loader = &ctx_data(ctx).loader_poi;
+
+        loader
+            .load_one(self.poi_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("PoI not found".to_string()))
+            })
+            .map(Into::into)
+    }
+}
+
+/// Where an indexer's live PoIs stand with respect to the consensus of all
+/// indexers, as returned by `myPoiReport`.
+#[derive(SimpleObject)]
+pub struct IndexerPoiReport {
+    pub indexer_address: IndexerAddress,
+    /// Number of subgraph deployments for which the indexer has a live PoI.
+    pub deployments: u32,
+    /// Number of subgraph deployments on which the indexer's live PoI is part
+    /// of the consensus.
+    pub deployments_in_consensus: u32,
+    /// Number of subgraph deployments on which no PoI has the support of a
+    /// majority of indexers.
+    pub deployments_without_consensus: u32,
+    /// The subgraph deployments on which the indexer's live PoI disagrees
+    /// with the consensus.
+    pub disagreements: Vec<PoiDisagreement>,
+}
+
+/// A subgraph deployment on which an indexer's live PoI disagrees with the
+/// consensus of all indexers.
+pub struct PoiDisagreement {
+    pub poi: ProofOfIndexing,
+    pub consensus_poi: ProofOfIndexing,
+    pub total_indexers: u32,
+    pub n_agreeing_indexers: u32,
+    pub n_consensus_indexers: u32,
+    pub bisection_run: Option<BisectionRun>,
+}
+
+#[Object]
+impl PoiDisagreement {
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        self.poi.deployment(ctx_data(ctx)).await
+    }
+
+    /// The indexer's live PoI.
+    async fn poi(&self) -> &ProofOfIndexing {
+        &self.poi
+    }
+
+    /// The live PoI of one of the indexers in the consensus. It may be for a
+    /// different block than the indexer's own PoI.
+    async fn consensus_poi(&self) -> &ProofOfIndexing {
+        &self.consensus_poi
+    }
+
+    /// Total number of indexers that have live pois for the deployment.
+    async fn total_indexers(&self) -> u32 {
+        self.total_indexers
+    }
+
+    /// Number of indexers that agree on the POI with the specified indexer,
+    /// including the indexer itself.
+    async fn n_agreeing_indexers(&self) -> u32 {
+        self.n_agreeing_indexers
+    }
+
+    /// Number of indexers that agree on the consensus PoI.
+    async fn n_consensus_indexers(&self) -> u32 {
+        self.n_consensus_indexers
+    }
+
+    /// The latest bisection run that compared the indexer's PoI with another
+    /// PoI, if any. Its `investigationUuid` can be passed to
+    /// `divergenceInvestigationReport` for the full report.
+    async fn bisection_run(&self) -> Option<&BisectionRun> {
+        self.bisection_run.as_ref()
+    }
+
+    /// The block range within which the indexer's PoI first diverged, as
+    /// found by the latest successful bisection run.
+    async fn divergence_block_bounds(&self) -> Option<common::DivergenceBlockBounds> {
+        let run = &self.bisection_run.as_ref()?.model;
+        if run.error.is_some() {
+            return None;
+        }
+
+        Some(common::DivergenceBlockBounds {
+            lower_bound: common::PartialBlock {
+                number: run.divergence_lower_bound,
+                hash: None,
+            },
+            upper_bound: common::PartialBlock {
+                number: run.divergence_upper_bound,
+                hash: None,
+            },
+        })
+    }
+}
+
+/// How indexers' PoIs are weighed when looking for a consensus PoI.
+#[derive(Enum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConsensusMode {
+    /// Every indexer has the same weight, so a consensus means a majority of
+    /// indexers agreeing on a PoI.
+    #[default]
+    Count,
+    /// Indexers are weighed by their stake in the subgraph deployment, so a
+    /// consensus means a PoI backed by a majority of the stake, and many
+    /// small indexers can't out-vote a few large ones. The stake is the
+    /// tokens an indexer allocated to the deployment
+    /// (`ProofOfIndexing.allocatedTokens`), or its staked tokens if no
+    /// indexer allocated to it.
+    StakeWeighted,
+}
+
+/// How finely `poiAgreementHistory` reports the agreement of an indexer with
+/// other indexers.
+#[derive(Enum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PoiAgreementGranularity {
+    /// One data point per block for which the indexer has a PoI.
+    #[default]
+    Block,
+    /// One data point per protocol epoch, at the latest block of the epoch for
+    /// which the indexer has a PoI. PoIs without a known epoch are skipped.
+    Epoch,
+}
+
+/// The agreement of an indexer's PoI with those of other indexers at a block.
+#[derive(SimpleObject, Debug)]
+pub struct PoiAgreementHistoryPoint {
+    pub block_number: i64,
+    pub epoch: Option<i64>,
+    /// The indexer's PoI.
+    pub poi: common::PoiBytes,
+    /// Total number of indexers that have PoIs for the block, with the same
+    /// block hash as the indexer.
+    pub total_indexers: u32,
+    /// Number of indexers that agree with the indexer, including itself.
+    pub n_agreeing_indexers: u32,
+    /// Number of indexers that disagree with the indexer.
+    pub n_disagreeing_indexers: u32,
+    /// Indicates if a consensus on the PoI exists among indexers.
+    pub has_consensus: bool,
+    /// Indicates if the indexer's PoI is part of the consensus.
+    pub in_consensus: bool,
+    /// Whether other indexers reported a different block hash for the block
+    /// number. Their PoIs aren't compared with the indexer's.
+    pub possible_reorg: bool,
+}
+
+/// A webhook that is called with the PoI divergences found in the given
+/// subgraph deployments.
+#[derive(derive_more::From)]
+pub struct Webhook {
+    model: models::Webhook,
+}
+
+#[Object]
+impl Webhook {
+    async fn id(&self) -> IntId {
+        self.model.id
+    }
+
+    /// The URL that divergence notifications are sent to, with a `POST`
+    /// request.
+    async fn url(&self) -> &str {
+        &self.model.url
+    }
+
+    /// The subgraph deployments that this webhook is notified about.
+    async fn deployments(&self) -> Vec<IpfsCid> {
+        self.model.deployments().cloned().collect()
+    }
+
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+}
+
+/// A subscription to a single subgraph deployment, whose webhook URL receives
+/// periodic digests of its new PoIs, divergences and investigation outcomes.
+#[derive(derive_more::From)]
+pub struct DeploymentSubscription {
+    model: models::DeploymentSubscription,
+}
+
+#[Object]
+impl DeploymentSubscription {
+    async fn id(&self) -> IntId {
+        self.model.id
+    }
+
+    async fn deployment(&self) -> &IpfsCid {
+        &self.model.deployment
+    }
+
+    /// The URL that digests are sent to, with a `POST` request.
+    async fn webhook_url(&self) -> &str {
+        &self.model.webhook_url
+    }
+
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+}
+
+/// A named set of subgraph deployments that are monitored more closely than
+/// the others.
+#[derive(derive_more::From)]
+pub struct Watchlist {
+    model: models::Watchlist,
+}
+
+#[Object]
+impl Watchlist {
+    async fn name(&self) -> &str {
+        &self.model.name
+    }
+
+    async fn deployments(&self) -> Vec<IpfsCid> {
+        self.model.deployments().cloned().collect()
+    }
+
+    /// How often the indexers of the subgraph deployments are polled for
+    /// their PoIs, if more often than they would be otherwise.
+    async fn polling_period_in_seconds(&self) -> Option<i32> {
+        self.model.polling_period_in_seconds
+    }
+
+    /// Whether new divergences of the subgraph deployments launch a
+    /// divergence investigation.
+    async fn investigate_divergences(&self) -> bool {
+        self.model.investigate_divergences
+    }
+
+    /// The URL that events about the subgraph deployments are `POST`ed to, in
+    /// addition to webhooks. Requires the `admin` permission level.
+    async fn webhook_url(&self, ctx: &Context<'_>) -> ApiResult<Option<&str>> {
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+        Ok(self.model.webhook_url.as_deref())
+    }
+
+    /// Whether the watchlist is defined in the configuration, in which case
+    /// it can't be changed through the API.
+    async fn from_config(&self) -> bool {
+        self.model.from_config
+    }
+
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+}
+
+/// A summary of the live PoIs of a subgraph deployment: which indexers hold
+/// each distinct PoI, and whether they reach a consensus.
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct DeploymentStatus {
+    #[graphql(skip)]
+    deployment: models::SgDeployment,
+    #[graphql(skip)]
+    block_id: Option<BigIntId>,
+    /// The distinct live PoIs, held by the most indexers first.
+    pub pois: Vec<DeploymentPoi>,
+    /// Indicates if more than half of all indexers agree on the same PoI.
+    pub has_consensus: bool,
+}
+
+impl DeploymentStatus {
+    pub fn new(deployment: models::SgDeployment, live_pois: Vec<models::Poi>) -> Self {
+        // Live PoIs are always collected for the same block, but in case they
+        // aren't, only the block with the most PoIs is considered.
+        let mut poi_counts_by_block: BTreeMap<BigIntId, usize> = BTreeMap::new();
+        for poi in &live_pois {
+            *poi_counts_by_block.entry(poi.block_id).or_default() += 1;
+        }
+        let block_id = poi_counts_by_block
+            .into_iter()
+            .max_by_key(|(block_id, count)| (*count, *block_id))
+            .map(|(block_id, _)| block_id);
+
+        let mut indexer_ids_by_poi: BTreeMap<common::PoiBytes, Vec<IntId>> = BTreeMap::new();
+        let mut total_indexers = 0;
+        for poi in live_pois
+            .iter()
+            .filter(|poi| Some(poi.block_id) == block_id)
+        {
+            indexer_ids_by_poi
+                .entry(poi.poi)
+                .or_default()
+                .push(poi.indexer_id);
+            total_indexers += 1;
+        }
+
+        let mut pois: Vec<DeploymentPoi> = indexer_ids_by_poi
+            .into_iter()
+            .map(|(hash, indexer_ids)| DeploymentPoi {
+                in_consensus: indexer_ids.len() > total_indexers / 2,
+                hash,
+                indexer_ids,
+            })
+            .collect();
+        pois.sort_by_key(|poi| std::cmp::Reverse(poi.indexer_ids.len()));
+
+        Self {
+            deployment,
+            block_id,
+            has_consensus: pois.iter().any(|poi| poi.in_consensus),
+            pois,
+        }
+    }
+}
+
+#[ComplexObject]
+impl DeploymentStatus {
+    async fn deployment(&self) -> SubgraphDeployment {
+        self.deployment.clone().into()
+    }
+
+    /// The latest block for which live PoIs were collected from all indexers.
+    async fn latest_common_block(&self, ctx: &Context<'_>) -> ApiResult<Option<Block>> {
+        let Some(block_id) = self.block_id else {
+            return Ok(None);
+        };
+
+        ctx_data(ctx)
+            .loader_block
+            .load_one(block_id)
+            .await
+            .map_err(Into::into)
+            .map(|opt| opt.map(Into::into))
+    }
+}
+
+/// A live PoI of a subgraph deployment, together with all indexers that hold
+/// it.
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct DeploymentPoi {
+    /// The PoI's hash.
+    pub hash: common::PoiBytes,
+    #[graphql(skip)]
+    indexer_ids: Vec<IntId>,
+    /// Indicates if more than half of all indexers hold this PoI.
+    pub in_consensus: bool,
+}
+
+#[ComplexObject]
+impl DeploymentPoi {
+    /// The indexers that hold this PoI.
+    async fn indexers(&self, ctx: &Context<'_>) -> ApiResult<Vec<Indexer>> {
+        let mut indexers = ctx_data(ctx)
+            .loader_indexer
+            .load_many(self.indexer_ids.iter().copied())
+            .await?;
+
+        Ok(self
+            .indexer_ids
+            .iter()
+            .filter_map(|id| indexers.remove(id))
+            .map(Into::into)
+            .collect())
+    }
+}
+
+/// A run of a divergence investigation campaign, which launched a divergence
+/// investigation for each block at which the live PoIs of the subgraph
+/// deployments with the most curation signal disagreed.
+#[derive(derive_more::From)]
+pub struct CampaignRun {
+    model: models::CampaignRun,
+}
+
+#[Object]
+impl CampaignRun {
+    /// The name of the campaign.
+    async fn campaign(&self) -> &str {
+        &self.model.campaign
+    }
+
+    /// The subgraph deployments that were checked for divergences.
+    async fn deployments(&self) -> Vec<IpfsCid> {
+        self.model.deployments.iter().flatten().cloned().collect()
+    }
+
+    /// The subgraph deployments whose live PoIs diverged.
+    async fn diverging_deployments(&self) -> Vec<IpfsCid> {
+        self.model
+            .diverging_deployments
+            .iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// The UUIDs of the launched divergence investigations, whose reports are
+    /// available through `divergenceInvestigationReport`.
+    async fn investigation_uuids(&self) -> Vec<uuid::Uuid> {
+        self.model
+            .investigation_uuids
+            .iter()
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// If the run failed, this field contains the error message. The
+    /// investigations that were launched until then are still listed.
+    async fn error(&self) -> Option<&str> {
+        self.model.error.as_deref()
+    }
+
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+}
+
+/// A request to collect the historical PoIs of a subgraph deployment over a
+/// block range, which is worked through in the background.
+#[derive(derive_more::From)]
+pub struct PoiBackfill {
+    model: models::PoiBackfill,
+}
+
+#[Object]
+impl PoiBackfill {
+    async fn id(&self) -> i32 {
+        self.model.id
+    }
+
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(self.model.sg_deployment_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
+            .map(Into::into)
+    }
+
+    async fn from_block(&self) -> i64 {
+        self.model.from_block
+    }
+
+    async fn to_block(&self) -> i64 {
+        self.model.to_block
+    }
+
+    async fn step(&self) -> i64 {
+        self.model.step
+    }
+
+    /// The next block at which PoIs will be requested, or `null` once the
+    /// backfill is done.
+    async fn next_block(&self) -> Option<i64> {
+        self.model.next_block
+    }
+
+    /// How many PoIs were collected so far.
+    async fn pois_written(&self) -> i32 {
+        self.model.pois_written
+    }
+
+    /// If the backfill failed, this field contains the error message. The
+    /// PoIs that were collected until then are kept.
+    async fn error(&self) -> Option<&str> {
+        self.model.error.as_deref()
+    }
+
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+
+    async fn completed_at(&self) -> Option<chrono::NaiveDateTime> {
+        self.model.completed_at
+    }
+}
+
+/// A query that an indexer failed to respond to, with the query and response
+/// as they were stored, i.e. possibly redacted and truncated.
+#[derive(derive_more::From)]
+pub struct FailedQuery {
+    model: models::FailedQueryRow,
+}
+
+#[Object]
+impl FailedQuery {
+    async fn id(&self) -> i32 {
+        self.model.id
+    }
+
+    async fn indexer(&self, ctx: &Context<'_>) -> ApiResult<Indexer> {
+        ctx_data(ctx)
+            .loader_indexer
+            .load_one(self.model.indexer_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| GraphixApiError::NotFound("Indexer not found".to_string()))
+            })
+            .map(Into::into)
+    }
+
+    /// The name of the query, e.g. `indexingStatuses`.
+    async fn query_name(&self) -> &str {
+        &self.model.query_name
+    }
+
+    async fn raw_query(&self) -> &str {
+        &self.model.raw_query
+    }
+
+    async fn response(&self) -> &str {
+        &self.model.response
+    }
+
+    /// When the query was sent.
+    async fn timestamp(&self) -> chrono::NaiveDateTime {
+        self.model.timestamp
+    }
+}
+
+/// The outcome of retrying a failed query with `retryFailedQuery`.
+#[derive(SimpleObject)]
+pub struct FailedQueryRetry {
+    pub succeeded: bool,
+    /// A summary of the response, or why the query failed again.
+    pub message: String,
+    /// The response of the indexer, redacted and truncated like stored
+    /// failed queries, if the query failed again.
+    pub response: Option<String>,
+}
+
+/// The outcome of `testIndexerConnectivity`.
+#[derive(SimpleObject)]
+pub struct IndexerConnectivity {
+    /// The address of the tested indexer, made up for URLs.
+    pub address: IndexerAddress,
+    /// Whether all requests succeeded.
+    pub reachable: bool,
+    pub checks: Vec<IndexerConnectivityCheck>,
+}
+
+/// A single request of `testIndexerConnectivity`.
+#[derive(SimpleObject)]
+pub struct IndexerConnectivityCheck {
+    /// `ping`, `version` or `indexingStatuses`.
+    pub request: String,
+    pub succeeded: bool,
+    pub latency_ms: u64,
+    /// A summary of the response, or why the request failed.
+    pub message: String,
+    pub error_class: Option<IndexerErrorClass>,
+}
+
+impl From<ConnectivityCheck> for IndexerConnectivityCheck {
+    fn from(check: ConnectivityCheck) -> Self {
+        let (message, error_class) = match check.outcome {
+            Ok(message) => (message, None),
+            Err((class, message)) => (message, Some(class)),
+        };
+        Self {
+            request: check.request.to_string(),
+            succeeded: error_class.is_none(),
+            latency_ms: check.latency.as_millis().try_into().unwrap_or(u64::MAX),
+            message,
+            error_class,
+        }
+    }
+}
+
+/// A block number for which indexers reported different block hashes, e.g.
+/// because some of them were on a chain reorg. PoIs at such a block that
+/// don't match are a possible reorg rather than a PoI divergence.
+#[derive(derive_more::From)]
+pub struct BlockHashConflict {
+    model: models::BlockHashConflict,
+}
+
+#[Object]
+impl BlockHashConflict {
+    async fn id(&self) -> IntId {
+        self.model.id
+    }
+
+    async fn network(&self) -> &str {
+        &self.model.network
+    }
+
+    async fn block_number(&self) -> i64 {
+        self.model.block_number
+    }
+
+    /// When the conflict was first noticed.
+    async fn detected_at(&self) -> chrono::NaiveDateTime {
+        self.model.detected_at
+    }
+
+    /// The block hashes that were reported for the block number.
+    async fn hashes(&self) -> Vec<ReportedBlockHash> {
+        self.model
+            .hashes
+            .iter()
+            .map(|(hash, indexers)| ReportedBlockHash {
+                hash: hash.clone(),
+                indexers: indexers.clone(),
+            })
+            .collect()
+    }
+}
+
+/// A block hash that indexers reported for a block number.
+#[derive(SimpleObject)]
+pub struct ReportedBlockHash {
+    pub hash: common::BlockHash,
+    /// The indexers whose PoIs refer to this block hash.
+    pub indexers: Vec<IndexerAddress>,
+}
+
+/// A bisection run of a divergence investigation, which compared two PoIs.
+/// The full report, including all bisection steps, is available through
+/// `divergenceInvestigationReport`.
+#[derive(derive_more::From)]
+pub struct BisectionRun {
+    model: models::BisectionRun,
+}
+
+#[Object]
+impl BisectionRun {
+    async fn uuid(&self) -> uuid::Uuid {
+        self.model.uuid
+    }
+
+    /// The UUID of the divergence investigation that this bisection run is
+    /// part of.
+    async fn investigation_uuid(&self) -> uuid::Uuid {
+        self.model.investigation_uuid
+    }
+
+    async fn poi1(&self) -> common::PoiBytes {
+        self.model.poi1
+    }
+
+    async fn poi2(&self) -> common::PoiBytes {
+        self.model.poi2
+    }
+
+    /// The subgraph deployment of the bisected PoIs, if known.
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<Option<SubgraphDeployment>> {
+        let Some(id) = self.model.sg_deployment_id else {
+            return Ok(None);
+        };
+
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(id)
+            .await
+            .map_err(Into::into)
+            .map(|opt| opt.map(Into::into))
+    }
+
+    /// The block of the bisected PoIs, if known.
+    async fn block(&self, ctx: &Context<'_>) -> ApiResult<Option<Block>> {
+        let Some(id) = self.model.block_id else {
+            return Ok(None);
+        };
+
+        ctx_data(ctx)
+            .loader_block
+            .load_one(id)
+            .await
+            .map_err(Into::into)
+            .map(|opt| opt.map(Into::into))
+    }
+
+    /// The first block number at which the two PoIs diverge.
+    async fn divergence_block_number(&self) -> i64 {
+        self.model.divergence_upper_bound
+    }
+
+    async fn indexer1_operator_contact(&self) -> Option<&str> {
+        self.model.indexer1_operator_contact.as_deref()
+    }
+
+    async fn indexer2_operator_contact(&self) -> Option<&str> {
+        self.model.indexer2_operator_contact.as_deref()
+    }
+
+    /// The graph-node version that the first indexer ran when the bisection
+    /// was performed, if it's known.
+    async fn indexer1_graph_node_version(&self) -> Option<&str> {
+        self.model.indexer1_graph_node_version.as_deref()
+    }
+
+    /// The graph-node version that the second indexer ran when the bisection
+    /// was performed, if it's known.
+    async fn indexer2_graph_node_version(&self) -> Option<&str> {
+        self.model.indexer2_graph_node_version.as_deref()
+    }
+
+    /// Whether the two indexers ran different graph-node versions. Null if
+    /// either version is unknown.
+    async fn graph_node_versions_differ(&self) -> Option<bool> {
+        graph_node_versions_differ(
+            self.model.indexer1_graph_node_version.as_deref(),
+            self.model.indexer2_graph_node_version.as_deref(),
+        )
+    }
+
+    /// If the bisection run failed, this field contains the error message.
+    async fn error(&self) -> Option<&str> {
+        self.model.error.as_deref()
+    }
+
+    /// How the two indexers' entity changes differ at the diverging block, if
+    /// entity changes were requested and both indexers provided them.
+    async fn entity_diff(&self) -> ApiResult<Option<common::EntityDiff>> {
+        self.model
+            .entity_diff
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|err| anyhow::Error::from(err).into())
+    }
+
+    /// How the two indexers' cached Ethereum calls differ at the diverging
+    /// block, if eth call caches were requested and both indexers provided
+    /// them.
+    async fn eth_call_diff(&self) -> ApiResult<Option<common::EthCallDiff>> {
+        self.model
+            .eth_call_diff
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|err| anyhow::Error::from(err).into())
+    }
+
+    /// Set if the subgraph deployment is grafted and the diverging block is
+    /// at or below its graft block, i.e. the divergence was inherited from a
+    /// graft base.
+    async fn graft_divergence(&self) -> ApiResult<Option<common::GraftDivergence>> {
+        self.model
+            .graft_divergence
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|err| anyhow::Error::from(err).into())
+    }
+
+    async fn created_at(&self) -> chrono::NaiveDateTime {
+        self.model.created_at
+    }
+}
+
+/// Whether two graph-node versions differ, if both are known.
+fn graph_node_versions_differ(version1: Option<&str>, version2: Option<&str>) -> Option<bool> {
+    Some(version1? != version2?)
+}
+
+/// How many divergences were found between indexers that ran a pair of
+/// graph-node versions.
+#[derive(derive_more::From)]
+pub struct DivergenceVersionPair {
+    model: models::DivergenceVersionPair,
+}
+
+#[Object]
+impl DivergenceVersionPair {
+    /// One of the two graph-node versions, null if it's unknown.
+    async fn version1(&self) -> Option<&str> {
+        self.model.version1.as_deref()
+    }
+
+    /// The other graph-node version, null if it's unknown.
+    async fn version2(&self) -> Option<&str> {
+        self.model.version2.as_deref()
+    }
+
+    /// Whether the two versions differ. Null if either version is unknown.
+    async fn versions_differ(&self) -> Option<bool> {
+        graph_node_versions_differ(
+            self.model.version1.as_deref(),
+            self.model.version2.as_deref(),
+        )
+    }
+
+    /// The number of bisection runs that found a divergence.
+    async fn bisection_runs(&self) -> i64 {
+        self.model.bisection_runs
+    }
+
+    /// The number of distinct subgraph deployments among those bisection runs.
+    async fn deployments(&self) -> i64 {
+        self.model.deployments
+    }
+}
+
+/// PoI statistics of a subgraph deployment during a single protocol epoch.
+#[derive(derive_more::From)]
+pub struct EpochSummary {
+    model: models::EpochSummary,
+}
+
+#[Object]
+impl EpochSummary {
+    async fn deployment(&self, ctx: &Context<'_>) -> ApiResult<SubgraphDeployment> {
+        ctx_data(ctx)
+            .loader_subgraph_deployment
+            .load_one(self.model.sg_deployment_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|opt| {
+                opt.ok_or_else(|| {
+                    GraphixApiError::NotFound("Subgraph deployment not found".to_string())
+                })
+            })
+            .map(Into::into)
+    }
+
+    async fn epoch(&self) -> i64 {
+        self.model.epoch
+    }
+
+    /// The number of PoIs collected during the epoch.
+    async fn poi_count(&self) -> i64 {
+        self.model.poi_count
+    }
+
+    /// The number of indexers that PoIs were collected from.
+    async fn indexer_count(&self) -> i64 {
+        self.model.indexer_count
+    }
+
+    /// The number of blocks that PoIs were collected for.
+    async fn block_count(&self) -> i64 {
+        self.model.block_count
+    }
+
+    /// The number of distinct PoI values. Anything above `blockCount`
+    /// indicates that indexers diverged during the epoch.
+    async fn distinct_poi_count(&self) -> i64 {
+        self.model.distinct_poi_count
+    }
+}
+
+/// General information about a Graphix instance.
+#[derive(SimpleObject)]
+pub struct Instance {
+    /// The configured name of the instance.
+    pub name: String,
+    pub version: String,
+    /// The names of all networks known to the instance.
+    pub networks: Vec<String>,
+    /// When PoIs were last collected by the instance, if ever.
+    pub last_poll_cycle: Option<chrono::NaiveDateTime>,
+    /// A warning to display prominently, e.g. as a banner, if the instance
+    /// is running with a stale configuration.
+    pub config_warning: Option<String>,
+    /// Whether the instance is in read-only mode, i.e. serves stored data but
+    /// neither polls indexers nor accepts mutations.
+    pub read_only: bool,
+}
+
+/// What's deployed, and how much data has accumulated in the database.
+#[derive(SimpleObject)]
+pub struct GraphixVersionInfo {
+    pub version: String,
+    /// The git commit that Graphix was built from, if known.
+    pub git_commit: Option<String>,
+    /// The version of the latest database migration that was run, e.g.
+    /// `20250113000000`.
+    pub schema_migration_version: Option<String>,
+    /// The on-disk size of the whole database.
+    pub database_size_bytes: i64,
+    /// Row counts and on-disk sizes of all database tables.
+    pub tables: Vec<models::TableStats>,
+}
+
+/// The response of a remote Graphix instance to a federated query.
+#[derive(SimpleObject)]
+pub struct FederatedQueryResult {
+    /// The configured name of the remote instance.
+    pub instance: String,
+    pub data: Option<serde_json::Value>,
+    /// GraphQL errors returned by the remote instance, or the reason why it
+    /// couldn't be queried.
+    pub errors: Vec<String>,
 }
@@ -0,0 +1,170 @@
+//! Errors of the GraphQL API. Each of them has a stable `code` in its GraphQL
+//! error extensions, so that frontends and automations can branch on the kind
+//! of error rather than on its message.
+
+use std::sync::Arc;
+
+use async_graphql::ErrorExtensions;
+use graphix_indexer_client::QueryFailure;
+use graphix_store::WebhookLimitReached;
+
+pub type ApiResult<T> = Result<T, GraphixApiError>;
+
+/// An error returned by a GraphQL resolver.
+///
+/// It deliberately doesn't implement [`std::fmt::Display`]: `async-graphql`
+/// converts any `Display` type into an error without extensions, which would
+/// silently drop the `code`. Use [`GraphixApiError::message`] instead.
+#[derive(Debug, Clone)]
+pub enum GraphixApiError {
+    /// The requested entity doesn't exist.
+    NotFound(String),
+    /// The API key is missing, unknown, or lacks the required permission
+    /// level.
+    Unauthorized(String),
+    /// An argument is invalid, e.g. a malformed URL.
+    InvalidInput(String),
+    /// A list argument has more items than the configuration allows.
+    ListInputTooLarge(ListInputTooLarge),
+    /// A mutation was requested while the instance is in read-only mode.
+    ReadOnly(String),
+    /// An indexer that Graphix queried on behalf of the request failed.
+    UpstreamIndexerError(Arc<anyhow::Error>),
+    /// Any other failure, most likely a database error.
+    Database(Arc<anyhow::Error>),
+}
+
+impl GraphixApiError {
+    /// The value of the `code` error extension.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::Unauthorized(_) => "UNAUTHORIZED",
+            Self::InvalidInput(_) => "INVALID_INPUT",
+            Self::ListInputTooLarge(_) => "LIST_INPUT_TOO_LARGE",
+            Self::ReadOnly(_) => "READ_ONLY",
+            Self::UpstreamIndexerError(_) => "UPSTREAM_INDEXER_ERROR",
+            Self::Database(_) => "DATABASE_ERROR",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::NotFound(msg)
+            | Self::Unauthorized(msg)
+            | Self::InvalidInput(msg)
+            | Self::ReadOnly(msg) => msg.clone(),
+            Self::ListInputTooLarge(err) => err.to_string(),
+            Self::UpstreamIndexerError(err) => format!("Indexer error: {err:#}"),
+            Self::Database(err) => format!("{err:#}"),
+        }
+    }
+}
+
+impl From<anyhow::Error> for GraphixApiError {
+    fn from(err: anyhow::Error) -> Self {
+        if err.is::<QueryFailure>() {
+            Self::UpstreamIndexerError(Arc::new(err))
+        } else if err.is::<WebhookLimitReached>() {
+            Self::InvalidInput(err.to_string())
+        } else {
+            Self::Database(Arc::new(err))
+        }
+    }
+}
+
+/// Data loaders report database errors as strings.
+impl From<String> for GraphixApiError {
+    fn from(err: String) -> Self {
+        Self::Database(Arc::new(anyhow::anyhow!(err)))
+    }
+}
+
+impl From<ListInputTooLarge> for GraphixApiError {
+    fn from(err: ListInputTooLarge) -> Self {
+        Self::ListInputTooLarge(err)
+    }
+}
+
+impl From<GraphixApiError> for async_graphql::Error {
+    fn from(err: GraphixApiError) -> Self {
+        async_graphql::Error::new(err.message()).extend_with(|_, extensions| {
+            extensions.set("code", err.code());
+            if let GraphixApiError::ListInputTooLarge(err) = &err {
+                extensions.set("argument", err.argument);
+                extensions.set("items", err.items as u64);
+                extensions.set("maxItems", err.max_items);
+            }
+        })
+    }
+}
+
+/// A list argument with more items than [`Config::max_list_input_items`]
+/// allows. Long lists of e.g. subgraph deployments turn into huge SQL `IN`
+/// clauses, so they're rejected before reaching the database.
+///
+/// [`Config::max_list_input_items`]: crate::config::Config::max_list_input_items
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("`{argument}` has {items} items, but at most {max_items} are allowed")]
+pub struct ListInputTooLarge {
+    pub argument: &'static str,
+    pub items: usize,
+    pub max_items: u32,
+}
+
+impl ListInputTooLarge {
+    pub fn check(argument: &'static str, items: usize, max_items: u32) -> Result<(), Self> {
+        if items > max_items as usize {
+            Err(Self {
+                argument,
+                items,
+                max_items,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(err: GraphixApiError) -> async_graphql::Value {
+        let err: async_graphql::Error = err.into();
+        err.extensions.unwrap().get("code").unwrap().clone()
+    }
+
+    #[test]
+    fn errors_have_stable_codes() {
+        assert_eq!(
+            code(GraphixApiError::NotFound("Network not found".to_string())),
+            async_graphql::Value::from("NOT_FOUND")
+        );
+        assert_eq!(
+            code(anyhow::anyhow!("connection refused").into()),
+            async_graphql::Value::from("DATABASE_ERROR")
+        );
+        assert_eq!(
+            code(anyhow::Error::from(WebhookLimitReached { max_webhooks: 1 }).into()),
+            async_graphql::Value::from("INVALID_INPUT")
+        );
+    }
+
+    #[test]
+    fn list_inputs_over_the_limit_are_rejected() {
+        assert!(ListInputTooLarge::check("deployments", 100, 100).is_ok());
+
+        let err = ListInputTooLarge::check("deployments", 101, 100).unwrap_err();
+        let err: async_graphql::Error = GraphixApiError::from(err).into();
+        let extensions = err.extensions.unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&async_graphql::Value::from("LIST_INPUT_TOO_LARGE"))
+        );
+        assert_eq!(
+            extensions.get("maxItems"),
+            Some(&async_graphql::Value::from(100))
+        );
+    }
+}
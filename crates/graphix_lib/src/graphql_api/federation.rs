@@ -0,0 +1,101 @@
+//! Forwarding of read-only GraphQL queries to remote Graphix instances, so that
+//! a single instance can serve the data of several others, e.g. the ones that
+//! monitor mainnet and testnet.
+
+use std::time::Duration;
+
+use async_graphql::parser::types::OperationType;
+use futures::future::join_all;
+use serde::Deserialize;
+
+use super::api_types::FederatedQueryResult;
+use crate::config::RemoteInstanceConfig;
+
+const REMOTE_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct GraphqlResponse {
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphqlError {
+    message: String,
+}
+
+/// Fails unless `query` is a valid GraphQL document with only query
+/// operations, i.e. no mutations or subscriptions.
+pub fn ensure_read_only(query: &str) -> anyhow::Result<()> {
+    let document = async_graphql::parser::parse_query(query)?;
+    for (_, operation) in document.operations.iter() {
+        anyhow::ensure!(
+            operation.node.ty == OperationType::Query,
+            "Only queries can be federated, not {}s",
+            operation.node.ty
+        );
+    }
+
+    Ok(())
+}
+
+/// Sends `query` to all `remotes` concurrently. Failures are reported for each
+/// remote instance, rather than failing the whole federated query.
+pub async fn federate(
+    client: &reqwest::Client,
+    remotes: &[RemoteInstanceConfig],
+    query: &str,
+    variables: Option<&serde_json::Value>,
+) -> Vec<FederatedQueryResult> {
+    join_all(remotes.iter().map(|remote| async move {
+        match query_remote(client, remote, query, variables).await {
+            Ok(response) => FederatedQueryResult {
+                instance: remote.name.clone(),
+                data: response.data,
+                errors: response.errors.into_iter().map(|e| e.message).collect(),
+            },
+            Err(err) => FederatedQueryResult {
+                instance: remote.name.clone(),
+                data: None,
+                errors: vec![format!("{err:#}")],
+            },
+        }
+    }))
+    .await
+}
+
+async fn query_remote(
+    client: &reqwest::Client,
+    remote: &RemoteInstanceConfig,
+    query: &str,
+    variables: Option<&serde_json::Value>,
+) -> anyhow::Result<GraphqlResponse> {
+    let body = serde_json::json!({
+        "query": query,
+        "variables": variables,
+    });
+
+    Ok(client
+        .post(remote.endpoint.clone())
+        .json(&body)
+        .timeout(REMOTE_QUERY_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_queries_are_federated() {
+        assert!(ensure_read_only("{ instance { name } }").is_ok());
+        assert!(ensure_read_only("query A { version } query B { networks { name } }").is_ok());
+        assert!(ensure_read_only("mutation { deleteNetwork(network: \"mainnet\") }").is_err());
+        assert!(ensure_read_only("{ instance { name }").is_err());
+    }
+}
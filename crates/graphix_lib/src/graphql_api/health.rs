@@ -0,0 +1,136 @@
+//! `GET /healthz` and `GET /readyz`, for Kubernetes liveness and readiness
+//! probes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::GraphixState;
+use crate::GRAPHIX_VERSION;
+
+/// The main loop is considered stuck if it hasn't completed an iteration for
+/// this many polling periods.
+const MAIN_LOOP_STALENESS_FACTOR: u32 = 5;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    ok: bool,
+    version: &'static str,
+    checks: Vec<HealthCheck>,
+    /// Problems that operators should look into, but that don't make the
+    /// probe fail.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HealthCheck {
+    name: &'static str,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_main_loop_iteration: Option<DateTime<Utc>>,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, result: anyhow::Result<()>) -> Self {
+        Self {
+            name,
+            ok: result.is_ok(),
+            error: result.err().map(|err| format!("{err:#}")),
+            last_main_loop_iteration: None,
+        }
+    }
+}
+
+impl HealthReport {
+    fn new(state: &GraphixState, checks: Vec<HealthCheck>) -> (StatusCode, Json<Self>) {
+        let ok = checks.iter().all(|check| check.ok);
+        let status = if ok {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+
+        (
+            status,
+            Json(Self {
+                ok,
+                version: GRAPHIX_VERSION,
+                checks,
+                warnings: state
+                    .config_reload_status
+                    .borrow()
+                    .warning()
+                    .into_iter()
+                    .collect(),
+            }),
+        )
+    }
+}
+
+/// Liveness: fails only if the main loop is stuck, which a restart can fix.
+/// Both probes report a stale configuration as a warning, since neither a
+/// restart nor taking the instance out of service fixes it.
+pub async fn healthz_handler(
+    State(state): State<Arc<GraphixState>>,
+) -> (StatusCode, Json<HealthReport>) {
+    HealthReport::new(&state, vec![main_loop_check(&state, false)])
+}
+
+/// Readiness: fails unless the database is reachable, a configuration was
+/// provided, and the main loop has completed an iteration recently.
+pub async fn readyz_handler(
+    State(state): State<Arc<GraphixState>>,
+) -> (StatusCode, Json<HealthReport>) {
+    let database = state.store.check_connection().await;
+    let config = match state.store.current_config().await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(anyhow::anyhow!("No configuration in the database")),
+        Err(err) => Err(err),
+    };
+
+    HealthReport::new(
+        &state,
+        vec![
+            HealthCheck::new("database", database),
+            HealthCheck::new("config", config),
+            main_loop_check(&state, true),
+        ],
+    )
+}
+
+/// Checks when the main loop last completed an iteration. Before the first
+/// one, the check only fails if `require_iteration` is set.
+fn main_loop_check(state: &GraphixState, require_iteration: bool) -> HealthCheck {
+    let last_iteration = *state.main_loop_heartbeat.borrow();
+    let max_age =
+        Duration::from_secs(state.config().polling_period_in_seconds) * MAIN_LOOP_STALENESS_FACTOR;
+
+    let result = match last_iteration {
+        None if require_iteration => Err(anyhow::anyhow!(
+            "The main loop hasn't completed an iteration yet"
+        )),
+        None => Ok(()),
+        Some(at) => match at.elapsed() {
+            Ok(age) if age > max_age => Err(anyhow::anyhow!(
+                "The main loop hasn't completed an iteration for {}s",
+                age.as_secs()
+            )),
+            _ => Ok(()),
+        },
+    };
+
+    HealthCheck {
+        last_main_loop_iteration: last_iteration.map(DateTime::<Utc>::from),
+        ..HealthCheck::new("mainLoop", result)
+    }
+}
@@ -0,0 +1,188 @@
+//! Instrumentation of the GraphQL API, to tell which queries are responsible
+//! for database load. The latency and errors of every field resolver are
+//! recorded in Prometheus metrics labeled by `Type.field`, and queries that
+//! take longer than [`crate::config::Config::slow_query_threshold_in_msecs`]
+//! are logged.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextResolve,
+    ResolveInfo,
+};
+use async_graphql::parser::types::ExecutableDocument;
+use async_graphql::{QueryPathSegment, Response, ServerResult, Value, Variables};
+// See `crate::prometheus_metrics`.
+use prometheus_exporter::prometheus;
+use tracing::warn;
+
+pub struct Instrumentation {
+    slow_query_threshold: Duration,
+    field_duration_seconds: prometheus::HistogramVec,
+    field_errors: prometheus::IntCounterVec,
+}
+
+impl Instrumentation {
+    pub fn new(
+        slow_query_threshold: Duration,
+        field_duration_seconds: prometheus::HistogramVec,
+        field_errors: prometheus::IntCounterVec,
+    ) -> Self {
+        Self {
+            slow_query_threshold,
+            field_duration_seconds,
+            field_errors,
+        }
+    }
+}
+
+impl ExtensionFactory for Instrumentation {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(InstrumentationExtension {
+            slow_query_threshold: self.slow_query_threshold,
+            field_duration_seconds: self.field_duration_seconds.clone(),
+            field_errors: self.field_errors.clone(),
+            query: Mutex::new(None),
+        })
+    }
+}
+
+struct InstrumentationExtension {
+    slow_query_threshold: Duration,
+    field_duration_seconds: prometheus::HistogramVec,
+    field_errors: prometheus::IntCounterVec,
+    /// The query text of the request, for the slow query log.
+    query: Mutex<Option<String>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for InstrumentationExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        *self.query.lock().unwrap() = Some(query.to_string());
+        next.run(ctx, query, variables).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let start = Instant::now();
+        let response = next.run(ctx, operation_name).await;
+        let elapsed = start.elapsed();
+
+        if elapsed >= self.slow_query_threshold {
+            // Variables are left out, as they may contain secrets.
+            let query = self.query.lock().unwrap().take().unwrap_or_default();
+            warn!(
+                operation_name,
+                duration_in_msecs = elapsed.as_millis() as u64,
+                errors = response.errors.len(),
+                query,
+                "Slow GraphQL query"
+            );
+        }
+
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        // The items of lists are resolved with the same hook, but they're
+        // accounted for by their field.
+        if info.is_for_introspection || !matches!(info.path_node.segment, QueryPathSegment::Name(_))
+        {
+            return next.run(ctx, info).await;
+        }
+
+        let field = format!("{}.{}", info.parent_type, info.name);
+        let depth = std::iter::once(info.path_node)
+            .chain(info.path_node.parents())
+            .count();
+
+        let start = Instant::now();
+        let result = next.run(ctx, info).await;
+        self.field_duration_seconds
+            .with_label_values(&[&field])
+            .observe(start.elapsed().as_secs_f64());
+
+        // Errors of non-nullable subfields propagate to their parents, but
+        // they're only counted for the field that failed.
+        if let Err(err) = &result {
+            if err.path.len() <= depth {
+                self.field_errors.with_label_values(&[&field]).inc();
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    use super::*;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn parent(&self) -> Parent {
+            Parent
+        }
+    }
+
+    struct Parent;
+
+    #[Object]
+    impl Parent {
+        async fn child(&self) -> async_graphql::Result<u32> {
+            Err("failed".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_are_counted_for_the_failed_field() {
+        let field_duration_seconds = prometheus::HistogramVec::new(
+            prometheus::histogram_opts!("field_duration_seconds", "help"),
+            &["field"],
+        )
+        .unwrap();
+        let field_errors =
+            prometheus::IntCounterVec::new(prometheus::opts!("field_errors", "help"), &["field"])
+                .unwrap();
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(Instrumentation::new(
+                Duration::from_secs(1),
+                field_duration_seconds.clone(),
+                field_errors.clone(),
+            ))
+            .finish();
+
+        let response = schema.execute("{ parent { child } }").await;
+        assert_eq!(response.errors.len(), 1);
+
+        let count = |field: &str| {
+            field_duration_seconds
+                .with_label_values(&[field])
+                .get_sample_count()
+        };
+        assert_eq!(count("Query.parent"), 1);
+        assert_eq!(count("Parent.child"), 1);
+        assert_eq!(field_errors.with_label_values(&["Parent.child"]).get(), 1);
+        assert_eq!(field_errors.with_label_values(&["Query.parent"]).get(), 0);
+    }
+}
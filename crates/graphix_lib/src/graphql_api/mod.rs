@@ -1,30 +1,46 @@
 pub mod api_types;
+mod errors;
+mod federation;
+mod health;
+mod instrumentation;
 mod mutations;
+mod poi_agreement;
+mod poi_export;
+mod prometheus_rules;
 mod queries;
+mod rate_limit;
+mod subscriptions;
 
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use async_graphql::dataloader::DataLoader;
 use async_graphql::http::GraphiQLSource;
-use async_graphql::{Context, EmptySubscription, Schema, SchemaBuilder};
-use async_graphql_axum::GraphQL;
-use axum::extract::State;
-use axum::http::StatusCode;
+use async_graphql::{Context, Schema, SchemaBuilder};
+use async_graphql_axum::{GraphQL, GraphQLProtocol, GraphQLWebSocket};
+use axum::extract::{State, WebSocketUpgrade};
+use axum::http::{HeaderMap, StatusCode};
 use axum::Json;
 use graphix_common_types::ApiKeyPermissionLevel;
+use graphix_indexer_client::IndexerClient;
 use graphix_store::models::{self, ApiKey};
 use graphix_store::{Store, StoreLoader};
 use tokio::sync::watch;
 use tower_service::Service;
 
+pub use self::errors::{ApiResult, GraphixApiError, ListInputTooLarge};
+use self::instrumentation::Instrumentation;
 use self::mutations::MutationRoot;
+pub(crate) use self::queries::poi_agreement_ratios;
 use self::queries::QueryRoot;
-use crate::config::Config;
+use self::rate_limit::{ApiRateLimiter, ANONYMOUS_BUCKET};
+use self::subscriptions::SubscriptionRoot;
+use crate::config::{Config, ConfigReloadStatus};
+use crate::grpc_api::GrpcApi;
 use crate::GRAPHIX_VERSION;
 
-pub type ApiSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+pub type ApiSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 pub const GRAPHIX_API_KEY_HEADER_NAME: &str = "Graphix-Api-Key";
 
@@ -41,32 +57,83 @@ pub struct RequestState {
 pub struct GraphixState {
     pub store: Store,
     pub loader_poi: DataLoader<StoreLoader<models::Poi>>,
+    pub loader_identical_pois: DataLoader<StoreLoader<Vec<models::Poi>>>,
     pub loader_network: DataLoader<StoreLoader<models::Network>>,
     pub loader_graph_node_collected_version:
         DataLoader<StoreLoader<models::GraphNodeCollectedVersion>>,
     pub loader_indexer_network_subgraph_metadata:
         DataLoader<StoreLoader<models::IndexerNetworkSubgraphMetadata>>,
+    pub loader_indexer_network_subgraphs: DataLoader<StoreLoader<models::IndexerNetworkSubgraph>>,
+    pub loader_sg_deployment_network_subgraphs:
+        DataLoader<StoreLoader<models::SgDeploymentNetworkSubgraph>>,
     pub loader_block: DataLoader<StoreLoader<models::Block>>,
     pub loader_indexer: DataLoader<StoreLoader<models::Indexer>>,
     pub loader_subgraph_deployment: DataLoader<StoreLoader<models::SgDeployment>>,
     config_receiver: watch::Receiver<Config>,
+    /// Used to forward federated queries to remote Graphix instances.
+    federation_client: reqwest::Client,
+    /// When the main loop last completed an iteration.
+    main_loop_heartbeat: watch::Receiver<Option<SystemTime>>,
+    /// Whether the main loop managed to reload the configuration.
+    config_reload_status: watch::Receiver<ConfigReloadStatus>,
+    /// The indexers that the main loop polls, e.g. to retry failed queries.
+    indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
+    rate_limiter: ApiRateLimiter,
 }
 
 impl GraphixState {
     pub fn new(store: Store, config_receiver: watch::Receiver<Config>) -> Self {
         Self {
             loader_poi: new_data_loader(&store),
+            loader_identical_pois: new_data_loader(&store),
             loader_network: new_data_loader(&store),
             loader_graph_node_collected_version: new_data_loader(&store),
             loader_indexer_network_subgraph_metadata: new_data_loader(&store),
+            loader_indexer_network_subgraphs: new_data_loader(&store),
+            loader_sg_deployment_network_subgraphs: new_data_loader(&store),
             loader_block: new_data_loader(&store),
             loader_indexer: new_data_loader(&store),
             loader_subgraph_deployment: new_data_loader(&store),
             store,
             config_receiver,
+            federation_client: reqwest::Client::new(),
+            main_loop_heartbeat: watch::channel(None).1,
+            config_reload_status: watch::channel(Default::default()).1,
+            indexers: watch::channel(vec![]).1,
+            rate_limiter: ApiRateLimiter::default(),
         }
     }
 
+    /// Lets health checks know when the main loop last completed an
+    /// iteration.
+    pub fn with_main_loop_heartbeat(
+        mut self,
+        main_loop_heartbeat: watch::Receiver<Option<SystemTime>>,
+    ) -> Self {
+        self.main_loop_heartbeat = main_loop_heartbeat;
+        self
+    }
+
+    /// Lets health checks and the `instance` query know whether the main loop
+    /// managed to reload the configuration.
+    pub fn with_config_reload_status(
+        mut self,
+        config_reload_status: watch::Receiver<ConfigReloadStatus>,
+    ) -> Self {
+        self.config_reload_status = config_reload_status;
+        self
+    }
+
+    /// Lets the GraphQL API talk to the indexers that the main loop polls.
+    pub fn with_indexers(mut self, indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>) -> Self {
+        self.indexers = indexers;
+        self
+    }
+
+    pub fn indexers(&self) -> Vec<Arc<dyn IndexerClient>> {
+        self.indexers.borrow().clone()
+    }
+
     pub fn config(&self) -> Config {
         self.config_receiver.borrow().clone()
     }
@@ -80,8 +147,8 @@ fn new_data_loader<T>(store: &Store) -> DataLoader<StoreLoader<T>> {
     DataLoader::new(StoreLoader::new(store.clone()), tokio::task::spawn).delay(delay)
 }
 
-pub fn api_schema_builder() -> SchemaBuilder<QueryRoot, MutationRoot, EmptySubscription> {
-    Schema::build(QueryRoot, MutationRoot, EmptySubscription).enable_federation()
+pub fn api_schema_builder() -> SchemaBuilder<QueryRoot, MutationRoot, SubscriptionRoot> {
+    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot).enable_federation()
 }
 
 pub fn ctx_data<'a>(ctx: &'a Context) -> &'a RequestState {
@@ -89,14 +156,34 @@ pub fn ctx_data<'a>(ctx: &'a Context) -> &'a RequestState {
         .expect("Failed to get API context")
 }
 
+/// Serves the APIs from a dedicated store, which sends heavy read queries to
+/// the read replica if there's one, along with its maximum lag.
 pub async fn axum_router(
     database_url: &str,
+    read_replica: Option<(&str, Duration)>,
     config_receiver: watch::Receiver<Config>,
+    main_loop_heartbeat: watch::Receiver<Option<SystemTime>>,
+    config_reload_status: watch::Receiver<ConfigReloadStatus>,
+    indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
+    read_only: bool,
 ) -> anyhow::Result<axum::Router<()>> {
     use axum::routing::get;
 
-    let store = Store::new(database_url).await?;
-    let server_state = GraphixState::new(store.clone(), config_receiver);
+    let mut store = if read_only {
+        Store::new_read_only(database_url).await?
+    } else {
+        Store::new(database_url).await?
+    }
+    .with_metrics(crate::metrics().store.clone());
+    if let Some((read_replica_url, max_lag)) = read_replica {
+        store = store.with_read_replica(read_replica_url, max_lag)?;
+    }
+    let server_state = Arc::new(
+        GraphixState::new(store.clone(), config_receiver)
+            .with_main_loop_heartbeat(main_loop_heartbeat)
+            .with_config_reload_status(config_reload_status)
+            .with_indexers(indexers),
+    );
 
     Ok(axum::Router::new()
         .route(
@@ -108,36 +195,152 @@ pub async fn axum_router(
                 )
             }),
         )
+        .route("/healthz", get(health::healthz_handler))
+        .route("/readyz", get(health::readyz_handler))
         .route("/graphql", get(graphiql_route).post(graphql_handler))
-        .with_state(Arc::new(server_state)))
+        .route("/graphql/ws", get(graphql_ws_handler))
+        .route("/api/v1/pois/export", get(poi_export::export_pois_handler))
+        .route(
+            "/api/v1/alerts/prometheus-rules",
+            get(prometheus_rules::prometheus_rules_handler),
+        )
+        .with_state(server_state.clone())
+        .merge(GrpcApi::new(server_state).into_axum_router()))
 }
 
 async fn graphql_handler(
     State(state): State<Arc<GraphixState>>,
     request: axum::extract::Request,
 ) -> Result<axum::response::Response, (StatusCode, Json<serde_json::Value>)> {
-    let api_key = match request.headers().get(GRAPHIX_API_KEY_HEADER_NAME) {
+    let api_key = api_key_from_headers(request.headers())?;
+    check_rate_limit(&state, api_key.as_ref()).await?;
+
+    let api_schema = request_schema(state, api_key);
+    let mut service = GraphQL::new(api_schema);
+    service
+        .call(request)
+        .await
+        .map_err(|_| api_key_error("Internal server error"))
+}
+
+/// Serves GraphQL subscriptions over WebSockets. The API key is read from the
+/// headers of the upgrade request.
+async fn graphql_ws_handler(
+    State(state): State<Arc<GraphixState>>,
+    protocol: GraphQLProtocol,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<axum::response::Response, (StatusCode, Json<serde_json::Value>)> {
+    let api_key = api_key_from_headers(&headers)?;
+    check_rate_limit(&state, api_key.as_ref()).await?;
+
+    let api_schema = request_schema(state, api_key);
+
+    Ok(upgrade
+        .protocols(async_graphql::http::ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| GraphQLWebSocket::new(stream, api_schema, protocol).serve()))
+}
+
+/// Builds the schema that serves a single request, with the query limits and
+/// the slow query threshold of the current configuration.
+pub fn request_schema(state: Arc<GraphixState>, api_key: Option<ApiKey>) -> ApiSchema {
+    let config = state.config();
+    let metrics = crate::metrics();
+
+    api_schema_builder()
+        .limit_depth(config.max_query_depth)
+        .limit_complexity(config.max_query_complexity)
+        .extension(Instrumentation::new(
+            Duration::from_millis(config.slow_query_threshold_in_msecs),
+            metrics.graphql_field_duration_seconds.clone(),
+            metrics.graphql_field_errors.clone(),
+        ))
+        .data(RequestState {
+            api_key,
+            data: state,
+        })
+        .finish()
+}
+
+/// Fails with `429 Too Many Requests` if the request's API key, or requests
+/// without an API key altogether, exceeded their rate limit.
+async fn check_rate_limit(
+    state: &GraphixState,
+    api_key: Option<&ApiKey>,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    match exceeded_rate_limit(state, api_key).await {
+        Ok(None) => Ok(()),
+        Ok(Some(limit)) => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(serde_json::json!({
+                "message": "Rate limit exceeded",
+                "error": format!("At most {limit} requests per minute are allowed"),
+            })),
+        )),
+        Err(err) => Err(internal_error(err.into())),
+    }
+}
+
+/// Counts a request against the rate limit of its API key, and returns the
+/// limit in requests per minute if it's exceeded. Unknown API keys are
+/// rate-limited like requests without an API key.
+pub(crate) async fn exceeded_rate_limit(
+    state: &GraphixState,
+    api_key: Option<&ApiKey>,
+) -> anyhow::Result<Option<u32>> {
+    let default_limit = state.config().api_requests_per_minute;
+    let metadata = match api_key {
+        Some(api_key) => state.store.api_key_metadata(api_key).await?,
         None => None,
+    };
+
+    let (bucket, limit) = match metadata {
+        Some(metadata) => {
+            let limit = metadata
+                .requests_per_minute
+                .map(|rpm| rpm.max(0) as u32)
+                .or(default_limit);
+            (metadata.public_prefix, limit)
+        }
+        None => (ANONYMOUS_BUCKET.to_string(), default_limit),
+    };
+
+    if state
+        .rate_limiter
+        .try_acquire(&bucket, limit, Instant::now())
+    {
+        Ok(None)
+    } else {
+        Ok(Some(limit.unwrap_or_default()))
+    }
+}
+
+fn api_key_from_headers(
+    headers: &HeaderMap,
+) -> Result<Option<ApiKey>, (StatusCode, Json<serde_json::Value>)> {
+    match headers.get(GRAPHIX_API_KEY_HEADER_NAME) {
+        None => Ok(None),
         Some(value) => {
             let header_s = value.to_str().map_err(api_key_error)?;
             let api_key = ApiKey::from_str(header_s).map_err(api_key_error)?;
 
-            Some(api_key)
+            Ok(Some(api_key))
         }
-    };
-
-    let api_schema = api_schema_builder()
-        .data(RequestState {
-            api_key,
-            data: state.clone(),
-        })
-        .finish();
+    }
+}
 
-    let mut service = GraphQL::new(api_schema);
-    service
-        .call(request)
-        .await
-        .map_err(|_| api_key_error("Internal server error"))
+/// Fails with `500 Internal Server Error` and the `code` of `err`, like the
+/// GraphQL API does. The message is only logged, as it may contain details of
+/// the database.
+fn internal_error(err: GraphixApiError) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::error!(code = err.code(), error = %err.message(), "Failed to serve request");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({
+            "message": "Internal server error",
+            "code": err.code(),
+        })),
+    )
 }
 
 fn api_key_error(err: impl ToString) -> (StatusCode, Json<serde_json::Value>) {
@@ -151,31 +354,70 @@ fn api_key_error(err: impl ToString) -> (StatusCode, Json<serde_json::Value>) {
 }
 
 async fn graphiql_route() -> impl axum::response::IntoResponse {
-    axum::response::Html(GraphiQLSource::build().endpoint("/graphql").finish())
+    axum::response::Html(
+        GraphiQLSource::build()
+            .endpoint("/graphql")
+            .subscription_endpoint("/graphql/ws")
+            .finish(),
+    )
 }
 
+/// Fails unless the request's API key has at least the given permission level,
+/// and returns its actual permission level.
 async fn require_permission_level(
     ctx: &Context<'_>,
     required_permission_level: ApiKeyPermissionLevel,
-) -> async_graphql::Result<()> {
+) -> ApiResult<ApiKeyPermissionLevel> {
     let ctx_data = ctx_data(ctx);
     let api_key = ctx_data
         .api_key
         .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No API key provided"))?;
+        .ok_or_else(|| GraphixApiError::Unauthorized("No API key provided".to_string()))?;
 
     let Some(actual_permission_level) = ctx_data.store.permission_level(api_key).await? else {
-        return Err(anyhow::anyhow!("No permission level for API key").into());
+        return Err(GraphixApiError::Unauthorized(
+            "No permission level for API key".to_string(),
+        ));
     };
 
     if actual_permission_level < required_permission_level {
-        return Err(anyhow::anyhow!(
+        return Err(GraphixApiError::Unauthorized(format!(
             "Insufficient permission level for API key: expected {:?}, got {:?}",
-            required_permission_level,
-            actual_permission_level
-        )
-        .into());
+            required_permission_level, actual_permission_level
+        )));
+    }
+
+    Ok(actual_permission_level)
+}
+
+/// Fails with [`GraphixApiError::ReadOnly`] if the instance is in read-only
+/// mode. Every mutation checks this first.
+fn require_writable(ctx: &Context<'_>) -> ApiResult<()> {
+    if ctx_data(ctx).config().read_only {
+        return Err(GraphixApiError::ReadOnly(
+            "Graphix is running in read-only mode".to_string(),
+        ));
     }
 
     Ok(())
 }
+
+/// Returns the API key of the request, and whether it can act on the
+/// user-generated data (webhooks, subscriptions and watchlists) of all
+/// organizations. Requires the `operator` permission level.
+async fn require_webhook_owner<'a>(ctx: &'a Context<'_>) -> ApiResult<(&'a ApiKey, bool)> {
+    let permission_level = require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+    let api_key = ctx_data(ctx)
+        .api_key
+        .as_ref()
+        .ok_or_else(|| GraphixApiError::Unauthorized("No API key provided".to_string()))?;
+
+    Ok((api_key, permission_level >= ApiKeyPermissionLevel::Admin))
+}
+
+/// Fails with [`ListInputTooLarge`] if the list argument called `argument` has
+/// more items than the configuration allows.
+fn check_list_input<T>(ctx: &Context<'_>, argument: &'static str, items: &[T]) -> ApiResult<()> {
+    let max_items = ctx_data(ctx).config().max_list_input_items;
+    Ok(ListInputTooLarge::check(argument, items.len(), max_items)?)
+}
@@ -1,8 +1,30 @@
-use async_graphql::{Context, Object, Result};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_graphql::{Context, Object};
 use graphix_common_types::*;
-use graphix_store::models::{DivergenceInvestigationRequest, NewlyCreatedApiKey};
+use graphix_indexer_client::{IndexerClient, RealIndexer};
+use graphix_store::models::{
+    self, ApiKey, DivergenceInvestigationRequest, NewWatchlist, NewlyCreatedApiKey,
+};
+use graphix_store::{AuditedMutation, Store};
+use uuid::Uuid;
 
-use super::{ctx_data, require_permission_level};
+use super::poi_agreement::{indexer_stakes, PoiTally};
+use super::{
+    api_types, check_list_input, ctx_data, require_permission_level, require_webhook_owner,
+    require_writable, ApiResult, GraphixApiError,
+};
+use crate::bisect::diverging_poi_clusters;
+use crate::config::{add_indexer_source, remove_indexer_source, Config, IndexerConfig};
+use crate::connectivity::test_connectivity;
+use crate::deployment_names::sync_deployment_names;
+use crate::events;
+use crate::failed_queries::retry_failed_query;
+use crate::metrics;
+use crate::poi_backfill::MAX_POI_BACKFILL_BLOCKS;
+use crate::substreams::comparable_live_pois;
+use crate::webhooks;
 
 pub struct MutationRoot;
 
@@ -10,7 +32,8 @@ pub struct MutationRoot;
 impl MutationRoot {
     /// Launches a divergence investigation, which is a process of comparing
     /// two or more PoIs (up to four) and running a binary search to find the first
-    /// diverging block.
+    /// diverging block. Requires the `operator` permission level.
+    #[allow(clippy::too_many_arguments)]
     async fn launch_divergence_investigation(
         &self,
         ctx: &Context<'_>,
@@ -34,20 +57,28 @@ impl MutationRoot {
             desc = "Indicates whether to collect `graph-node`'s entity changes during bisection runs to include in the report."
         )]
         query_entity_changes: bool,
-    ) -> Result<DivergenceInvestigationReport> {
-        let ctx_data = ctx_data(ctx);
-        let store = &ctx_data.store;
+        #[graphql(
+            default = false,
+            desc = "Indicates whether to also bisect the PoIs of the graft base if a divergence turns out to be inherited from it."
+        )]
+        bisect_graft_bases: bool,
+        #[graphql(
+            default = 0,
+            desc = "Investigations with a higher priority are run first."
+        )]
+        priority: i32,
+    ) -> ApiResult<DivergenceInvestigationReport> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
 
         let req = DivergenceInvestigationRequest {
             pois,
             query_block_caches,
             query_eth_call_caches,
             query_entity_changes,
+            bisect_graft_bases,
         };
-        let request_serialized = serde_json::to_value(req).unwrap();
-        let uuid = store
-            .create_divergence_investigation_request(request_serialized)
-            .await?;
+        let uuid = create_divergence_investigation_request(ctx, req, priority).await?;
 
         let report = DivergenceInvestigationReport {
             uuid,
@@ -59,53 +90,472 @@ impl MutationRoot {
         Ok(report)
     }
 
-    async fn set_configuration(
+    /// Launches a divergence investigation for each block at which the live
+    /// PoIs of a subgraph deployment disagree, so that they don't need to be
+    /// looked up first. PoIs are clustered by value, and each investigation
+    /// compares one PoI of each of the (up to four) largest clusters. Returns
+    /// the UUIDs of the launched investigations, which is empty if all
+    /// indexers agree. Requires the `operator` permission level.
+    #[allow(clippy::too_many_arguments)]
+    async fn investigate_deployment(
         &self,
         ctx: &Context<'_>,
-        #[graphql(desc = "The configuration file to use")] config: serde_json::Value,
-    ) -> Result<bool> {
+        cid: IpfsCid,
+        #[graphql(
+            default = true,
+            desc = "Indicates whether to collect `graph-node`'s block cache contents during bisection runs to include in the report."
+        )]
+        query_block_caches: bool,
+        #[graphql(
+            default = true,
+            desc = "Indicates whether to collect `graph-node`'s ETH call cache contents during bisection runs to include in the report."
+        )]
+        query_eth_call_caches: bool,
+        #[graphql(
+            default = true,
+            desc = "Indicates whether to collect `graph-node`'s entity changes during bisection runs to include in the report."
+        )]
+        query_entity_changes: bool,
+        #[graphql(
+            default = false,
+            desc = "Indicates whether to also bisect the PoIs of the graft base if a divergence turns out to be inherited from it."
+        )]
+        bisect_graft_bases: bool,
+        #[graphql(
+            default = 0,
+            desc = "Investigations with a higher priority are run first."
+        )]
+        priority: i32,
+    ) -> ApiResult<Vec<Uuid>> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
+        let live_pois = comparable_live_pois(&ctx_data(ctx).store, &cid).await?;
+
+        let mut uuids = vec![];
+        for pois in diverging_poi_clusters(&live_pois) {
+            let req = DivergenceInvestigationRequest {
+                pois,
+                query_block_caches,
+                query_eth_call_caches,
+                query_entity_changes,
+                bisect_graft_bases,
+            };
+            uuids.push(create_divergence_investigation_request(ctx, req, priority).await?);
+        }
+
+        Ok(uuids)
+    }
+
+    /// Launches a divergence investigation between the live PoI of an indexer
+    /// for a subgraph deployment and the consensus PoI of the indexers with
+    /// live PoIs for the same block, so that the two don't need to be looked
+    /// up first. Fails if there's no consensus, or if the indexer is part of
+    /// it. Requires the `operator` permission level.
+    #[allow(clippy::too_many_arguments)]
+    async fn investigate_against_consensus(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+        deployment_cid: IpfsCid,
+        #[graphql(default)] consensus_mode: api_types::ConsensusMode,
+        #[graphql(
+            default = true,
+            desc = "Indicates whether to collect `graph-node`'s block cache contents during bisection runs to include in the report."
+        )]
+        query_block_caches: bool,
+        #[graphql(
+            default = true,
+            desc = "Indicates whether to collect `graph-node`'s ETH call cache contents during bisection runs to include in the report."
+        )]
+        query_eth_call_caches: bool,
+        #[graphql(
+            default = true,
+            desc = "Indicates whether to collect `graph-node`'s entity changes during bisection runs to include in the report."
+        )]
+        query_entity_changes: bool,
+        #[graphql(
+            default = false,
+            desc = "Indicates whether to also bisect the PoIs of the graft base if a divergence turns out to be inherited from it."
+        )]
+        bisect_graft_bases: bool,
+        #[graphql(
+            default = 0,
+            desc = "Investigations with a higher priority are run first."
+        )]
+        priority: i32,
+    ) -> ApiResult<DivergenceInvestigationReport> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
+        let ctx_data = ctx_data(ctx);
+        let live_pois = comparable_live_pois(&ctx_data.store, &deployment_cid).await?;
+        let indexers = ctx_data
+            .loader_indexer
+            .load_many(live_pois.iter().map(|poi| poi.indexer_id))
+            .await?;
+        let address = |poi: &models::Poi| indexers.get(&poi.indexer_id).map(|i| i.address);
+
+        let indexer_poi = live_pois
+            .iter()
+            .find(|poi| address(poi) == Some(indexer_address))
+            .ok_or_else(|| {
+                GraphixApiError::NotFound(format!(
+                    "Indexer {indexer_address} has no live PoI for {deployment_cid}"
+                ))
+            })?;
+
+        let pois_at_block: Vec<&models::Poi> = live_pois
+            .iter()
+            .filter(|poi| poi.block_id == indexer_poi.block_id)
+            .collect();
+        let addresses: Vec<IndexerAddress> = pois_at_block
+            .iter()
+            .filter_map(|poi| address(poi))
+            .collect();
+        let stakes = indexer_stakes(
+            &ctx_data.store,
+            std::slice::from_ref(&deployment_cid),
+            &addresses,
+        )
+        .await?
+        .remove(&deployment_cid)
+        .unwrap_or_default();
+
+        let mut tally = PoiTally::default();
+        for poi in pois_at_block {
+            let stake = address(poi)
+                .and_then(|address| stakes.get(&address))
+                .copied()
+                .unwrap_or(0.0);
+            tally.add(poi.poi, stake);
+        }
+
+        let Some(consensus_poi) = tally.consensus(consensus_mode) else {
+            return Err(GraphixApiError::InvalidInput(format!(
+                "There's no consensus on the PoI of {deployment_cid} at the block of indexer {indexer_address}'s live PoI"
+            )));
+        };
+        if consensus_poi == indexer_poi.poi {
+            return Err(GraphixApiError::InvalidInput(format!(
+                "Indexer {indexer_address} agrees with the consensus PoI of {deployment_cid}"
+            )));
+        }
+
+        let req = DivergenceInvestigationRequest {
+            pois: vec![indexer_poi.poi, consensus_poi],
+            query_block_caches,
+            query_eth_call_caches,
+            query_entity_changes,
+            bisect_graft_bases,
+        };
+        let uuid = create_divergence_investigation_request(ctx, req, priority).await?;
+
+        Ok(DivergenceInvestigationReport {
+            uuid,
+            status: DivergenceInvestigationStatus::Pending,
+            bisection_runs: vec![],
+            error: None,
+        })
+    }
+
+    /// Collects the historical PoIs of a subgraph deployment from all indexers
+    /// at every `step` blocks from `fromBlock` to `toBlock`, e.g. to
+    /// reconstruct the divergence history of a deployment that was added to
+    /// Graphix late. PoIs are collected in the background and stored as
+    /// non-live PoIs, and `poiBackfills` shows the progress. Requires the
+    /// `admin` permission level.
+    async fn backfill_pois(
+        &self,
+        ctx: &Context<'_>,
+        deployment: IpfsCid,
+        #[graphql(
+            desc = "The network that the subgraph deployment indexes. Only required if it's indexed on several networks."
+        )]
+        network: Option<String>,
+        from_block: u64,
+        to_block: u64,
+        #[graphql(validator(minimum = 1))] step: u64,
+    ) -> ApiResult<api_types::PoiBackfill> {
+        require_writable(ctx)?;
         require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
 
+        if from_block > to_block {
+            return Err(GraphixApiError::InvalidInput(
+                "`fromBlock` can't be after `toBlock`".to_string(),
+            ));
+        }
+        if (to_block - from_block) / step >= MAX_POI_BACKFILL_BLOCKS {
+            return Err(GraphixApiError::InvalidInput(format!(
+                "A PoI backfill can't cover more than {MAX_POI_BACKFILL_BLOCKS} blocks"
+            )));
+        }
+
+        let store = &ctx_data(ctx).store;
+        let filter = inputs::SgDeploymentsQuery {
+            ipfs_cid: Some(deployment.clone()),
+            network_name: network,
+            ..Default::default()
+        };
+        let sg_deployment_id = match store.sg_deployments(filter).await?.as_slice() {
+            [] => {
+                return Err(GraphixApiError::NotFound(format!(
+                    "Subgraph deployment not found: {deployment}"
+                )))
+            }
+            [sg_deployment] => sg_deployment.id,
+            _ => {
+                return Err(GraphixApiError::InvalidInput(format!(
+                    "Subgraph deployment {deployment} is indexed on several networks, `network` is required"
+                )))
+            }
+        };
+
+        let backfill = store
+            .create_poi_backfill(sg_deployment_id, from_block, to_block, step)
+            .await?;
+
+        Ok(backfill.into())
+    }
+
+    /// Sends a failed query to its indexer again and reports the outcome,
+    /// e.g. to check whether a fix on the indexer's side worked. Nothing is
+    /// stored. Requires the `operator` permission level.
+    async fn retry_failed_query(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+    ) -> ApiResult<api_types::FailedQueryRetry> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
         let ctx_data = ctx_data(ctx);
-        let store = &ctx_data.store;
+        let failed_query = ctx_data
+            .store
+            .failed_query_by_id(id)
+            .await?
+            .ok_or_else(|| GraphixApiError::NotFound(format!("Failed query not found: {id}")))?;
+        let indexer = ctx_data
+            .loader_indexer
+            .load_one(failed_query.indexer_id)
+            .await?
+            .ok_or_else(|| GraphixApiError::NotFound("Indexer not found".to_string()))?;
+        let indexer_client = ctx_data
+            .indexers()
+            .into_iter()
+            .find(|client| client.address() == indexer.address)
+            .ok_or_else(|| {
+                GraphixApiError::NotFound(format!(
+                    "Indexer {} isn't currently polled",
+                    indexer.address
+                ))
+            })?;
+
+        let retried = retry_failed_query(
+            indexer_client,
+            &failed_query.query_name,
+            &ctx_data.config().failed_queries,
+        )
+        .await
+        .map_err(|err| GraphixApiError::InvalidInput(format!("{err:#}")))?;
 
-        store.overwrite_config(config).await?;
+        Ok(api_types::FailedQueryRetry {
+            succeeded: retried.succeeded,
+            message: retried.message,
+            response: retried.response,
+        })
+    }
+
+    /// Sends an indexer a `ping`, a `version` and an `indexingStatuses`
+    /// request, and reports the latency and outcome of each, e.g. to debug the
+    /// endpoint of a new indexer. Takes either the `address` of an indexer
+    /// that is currently polled, or the `url` of any index node's GraphQL
+    /// status endpoint. Nothing is stored. Requires the `admin` permission
+    /// level.
+    async fn test_indexer_connectivity(
+        &self,
+        ctx: &Context<'_>,
+        address: Option<IndexerAddress>,
+        url: Option<String>,
+    ) -> ApiResult<api_types::IndexerConnectivity> {
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let ctx_data = ctx_data(ctx);
+        let indexer: Arc<dyn IndexerClient> = match (address, url) {
+            (Some(address), None) => ctx_data
+                .indexers()
+                .into_iter()
+                .find(|client| client.address() == address)
+                .ok_or_else(|| {
+                    GraphixApiError::NotFound(format!("Indexer {address} isn't currently polled"))
+                })?,
+            (None, Some(url)) => {
+                let parsed_url = url::Url::parse(&url)
+                    .map_err(|err| GraphixApiError::InvalidInput(format!("Invalid URL: {err}")))?;
+                if !["http", "https"].contains(&parsed_url.scheme()) {
+                    return Err(GraphixApiError::InvalidInput(
+                        "URLs must use HTTP(S)".to_string(),
+                    ));
+                }
+                Arc::new(RealIndexer::new(
+                    None,
+                    IndexerConfig::placeholder_address(&url),
+                    parsed_url.to_string(),
+                    metrics().public_proofs_of_indexing_requests.clone(),
+                ))
+            }
+            _ => {
+                return Err(GraphixApiError::InvalidInput(
+                    "Exactly one of `address` and `url` is required".to_string(),
+                ))
+            }
+        };
+
+        let address = indexer.address();
+        let checks: Vec<api_types::IndexerConnectivityCheck> = test_connectivity(indexer)
+            .await
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        Ok(api_types::IndexerConnectivity {
+            address,
+            reachable: checks.iter().all(|check| check.succeeded),
+            checks,
+        })
+    }
+
+    async fn set_configuration(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "The configuration file to use")] config: serde_json::Value,
+    ) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        // The configuration may contain secrets, so only the names of the
+        // changed fields are recorded.
+        ctx_data(ctx)
+            .store
+            .update_config(audited_mutation(ctx, "setConfiguration"), |stored| {
+                let changed_fields = events::changed_config_fields(stored, &config)?;
+                *stored = config;
+                anyhow::Ok(Some(serde_json::json!({ "changedFields": changed_fields })))
+            })
+            .await?
+            .map_err(|err| GraphixApiError::InvalidInput(format!("{err:#}")))?;
 
         Ok(true)
     }
 
+    /// Restores a document exported by `exportConfig`, e.g. from a
+    /// production instance: overwrites the configuration, and the names and
+    /// tags of the subgraph deployments and indexers that this instance
+    /// already knows about. Requires the `admin` permission level.
+    async fn import_config(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "A document exported by `exportConfig`.")] document: serde_json::Value,
+    ) -> ApiResult<InstanceConfigImport> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let invalid = |err: serde_json::Error| GraphixApiError::InvalidInput(err.to_string());
+        let mut instance_config: InstanceConfig =
+            serde_json::from_value(document).map_err(invalid)?;
+        // The main loop wouldn't be able to load an invalid configuration.
+        serde_json::from_value::<Config>(instance_config.config.clone()).map_err(invalid)?;
+        for indexer_tags in &mut instance_config.indexer_tags {
+            indexer_tags.tags = indexer_tags
+                .tags
+                .iter()
+                .map(|tag| validate_indexer_tag(tag).map(str::to_string))
+                .collect::<ApiResult<_>>()?;
+        }
+
+        // Like `setConfiguration`, only the names of the changed fields of
+        // the configuration are recorded.
+        let import = ctx_data(ctx)
+            .store
+            .import_instance_config(
+                &instance_config,
+                audited_mutation(ctx, "importConfig"),
+                |previous_config| {
+                    let changed_fields =
+                        events::changed_config_fields(previous_config, &instance_config.config)
+                            .unwrap_or_default();
+                    serde_json::json!({
+                        "changedFields": changed_fields,
+                        "deploymentNames": instance_config.deployment_names.len(),
+                        "indexerTags": instance_config.indexer_tags.len(),
+                    })
+                },
+            )
+            .await?;
+
+        Ok(import)
+    }
+
     /// Create a new API key with the given permission level. You'll need to
     /// authenticate with another API key with the `admin` permission level to
     /// do this.
     async fn create_api_key(
         &self,
         ctx: &Context<'_>,
-        #[graphql(desc = "Permission level of the API key. Use `admin` for full access.")]
+        #[graphql(
+            desc = "Permission level of the API key. Use `admin` for full access, or `operator` to only manage the webhooks of its organization."
+        )]
         permission_level: ApiKeyPermissionLevel,
         #[graphql(
             default,
             desc = "Not-encrypted notes to store in the database alongside the API key, to be used for debugging or identification purposes."
         )]
         notes: Option<String>,
-    ) -> Result<NewlyCreatedApiKey> {
-        // In order to create an API key with a certain permission level, you
-        // need to have that permission level yourself.
-        require_permission_level(ctx, permission_level).await?;
+        #[graphql(
+            default,
+            desc = "The organization (i.e. tenant) that the API key belongs to. API keys of the same organization share webhooks."
+        )]
+        organization: Option<String>,
+    ) -> ApiResult<NewlyCreatedApiKey> {
+        require_writable(ctx)?;
+        // Otherwise, operators could work around their webhook limits by
+        // creating more operator API keys.
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
 
         let ctx_data = ctx_data(ctx);
 
         let api_key = ctx_data
             .store
-            .create_api_key(notes.as_deref(), permission_level)
+            .create_api_key(notes.as_deref(), permission_level, organization.as_deref())
             .await?;
+        audit(
+            ctx,
+            "createApiKey",
+            serde_json::json!({
+                "publicPrefix": parse_api_key(&api_key.api_key)?.public_part_as_string(),
+                "permissionLevel": permission_level,
+                "notes": notes,
+                "organization": organization,
+            }),
+        )
+        .await?;
 
         Ok(api_key)
     }
 
-    async fn delete_api_key(&self, ctx: &Context<'_>, api_key: String) -> Result<bool> {
+    async fn delete_api_key(&self, ctx: &Context<'_>, api_key: String) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
         let ctx_data = ctx_data(ctx);
+        let public_prefix = parse_api_key(&api_key)?.public_part_as_string();
 
         ctx_data.store.delete_api_key(&api_key).await?;
+        audit(
+            ctx,
+            "deleteApiKey",
+            serde_json::json!({ "publicPrefix": public_prefix }),
+        )
+        .await?;
 
         Ok(true)
     }
@@ -119,8 +569,10 @@ impl MutationRoot {
         )]
         notes: Option<String>,
         permission_level: ApiKeyPermissionLevel,
-    ) -> Result<bool> {
-        require_permission_level(ctx, permission_level).await?;
+    ) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+        let public_prefix = parse_api_key(&api_key)?.public_part_as_string();
 
         let ctx_data = ctx_data(ctx);
 
@@ -128,33 +580,665 @@ impl MutationRoot {
             .store
             .modify_api_key(&api_key, notes.as_deref(), permission_level)
             .await?;
+        audit(
+            ctx,
+            "modifyApiKey",
+            serde_json::json!({
+                "publicPrefix": public_prefix,
+                "notes": notes,
+                "permissionLevel": permission_level,
+            }),
+        )
+        .await?;
 
         Ok(true)
     }
 
+    /// Overrides the configured rate limit of GraphQL API requests for an API
+    /// key. Returns `false` if the API key doesn't exist.
+    async fn set_api_key_rate_limit(
+        &self,
+        ctx: &Context<'_>,
+        api_key: String,
+        #[graphql(
+            desc = "The maximum number of requests per minute. Leave empty to use the configured rate limit."
+        )]
+        requests_per_minute: Option<u32>,
+    ) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+        let api_key = parse_api_key(&api_key)?;
+
+        let ctx_data = ctx_data(ctx);
+
+        let updated = ctx_data
+            .store
+            .set_api_key_rate_limit(&api_key, requests_per_minute)
+            .await?;
+        if updated {
+            audit(
+                ctx,
+                "setApiKeyRateLimit",
+                serde_json::json!({
+                    "publicPrefix": api_key.public_part_as_string(),
+                    "requestsPerMinute": requests_per_minute,
+                }),
+            )
+            .await?;
+        }
+
+        Ok(updated)
+    }
+
+    /// Registers a webhook that is called whenever indexers start diverging
+    /// from the consensus PoI of any of the given subgraph deployments.
+    /// Requires the `operator` permission level, and each API key can only
+    /// register a limited number of webhooks.
+    async fn create_webhook(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "An HTTP(S) URL that notifications are `POST`ed to, as JSON.")]
+        url: String,
+        #[graphql(validator(min_items = 1))] deployments: Vec<IpfsCid>,
+    ) -> ApiResult<api_types::Webhook> {
+        require_writable(ctx)?;
+        let (api_key, _) = require_webhook_owner(ctx).await?;
+        check_list_input(ctx, "deployments", &deployments)?;
+        check_webhook_url(&url)?;
+
+        let ctx_data = ctx_data(ctx);
+        let max_webhooks = ctx_data.config().max_webhooks_per_api_key;
+        let webhook = ctx_data
+            .store
+            .create_webhook(api_key, &url, &deployments, max_webhooks)
+            .await?;
+
+        Ok(webhook.into())
+    }
+
+    /// Deletes a webhook. Only `admin` API keys can delete webhooks that were
+    /// registered by other organizations.
+    async fn delete_webhook(&self, ctx: &Context<'_>, id: i32) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        let (api_key, is_admin) = require_webhook_owner(ctx).await?;
+
+        let deleted = ctx_data(ctx)
+            .store
+            .delete_webhook(id, (!is_admin).then_some(api_key))
+            .await?;
+        if deleted {
+            audit(ctx, "deleteWebhook", serde_json::json!({ "id": id })).await?;
+        }
+
+        Ok(deleted)
+    }
+
+    /// Subscribes a webhook URL to periodic digests of the new PoIs,
+    /// divergences and investigation outcomes of a single subgraph
+    /// deployment. Subscribing again returns the existing subscription.
+    /// Requires the `operator` permission level, and each API key can only
+    /// have as many subscriptions as webhooks.
+    async fn subscribe_to_deployment(
+        &self,
+        ctx: &Context<'_>,
+        cid: IpfsCid,
+        #[graphql(desc = "An HTTP(S) URL that digests are `POST`ed to, as JSON.")]
+        webhook_url: String,
+    ) -> ApiResult<api_types::DeploymentSubscription> {
+        require_writable(ctx)?;
+        let (api_key, _) = require_webhook_owner(ctx).await?;
+        check_webhook_url(&webhook_url)?;
+
+        let ctx_data = ctx_data(ctx);
+        let max_subscriptions = ctx_data.config().max_webhooks_per_api_key;
+        let subscription = ctx_data
+            .store
+            .create_deployment_subscription(api_key, &cid, &webhook_url, max_subscriptions)
+            .await?;
+
+        Ok(subscription.into())
+    }
+
+    /// Deletes a deployment subscription. Only `admin` API keys can delete
+    /// subscriptions of other organizations.
+    async fn unsubscribe_from_deployment(&self, ctx: &Context<'_>, id: i32) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        let (api_key, is_admin) = require_webhook_owner(ctx).await?;
+
+        let deleted = ctx_data(ctx)
+            .store
+            .delete_deployment_subscription(id, (!is_admin).then_some(api_key))
+            .await?;
+        if deleted {
+            audit(
+                ctx,
+                "unsubscribeFromDeployment",
+                serde_json::json!({ "id": id }),
+            )
+            .await?;
+        }
+
+        Ok(deleted)
+    }
+
+    /// Creates a watchlist, or replaces the one with the same name, unless
+    /// it's defined in the configuration. The watchlist belongs to the
+    /// organization of the request's API key, and only `admin` API keys can
+    /// replace watchlists of other organizations.
+    async fn set_watchlist(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        #[graphql(validator(min_items = 1))] deployments: Vec<IpfsCid>,
+        #[graphql(
+            validator(minimum = 1),
+            desc = "How often the indexers of the subgraph deployments are polled for their PoIs. Only takes effect if it's shorter than the polling period that applies otherwise."
+        )]
+        polling_period_in_seconds: Option<i32>,
+        #[graphql(
+            default = true,
+            desc = "Whether to launch a divergence investigation as soon as one of the subgraph deployments diverges."
+        )]
+        investigate_divergences: bool,
+        #[graphql(
+            desc = "An HTTP(S) URL that events about the subgraph deployments are `POST`ed to, as JSON."
+        )]
+        webhook_url: Option<String>,
+    ) -> ApiResult<api_types::Watchlist> {
+        require_writable(ctx)?;
+        let (api_key, is_admin) = require_webhook_owner(ctx).await?;
+        check_list_input(ctx, "deployments", &deployments)?;
+        if name.trim().is_empty() {
+            return Err(GraphixApiError::InvalidInput(
+                "Watchlist names can't be empty".to_string(),
+            ));
+        }
+        if let Some(url) = &webhook_url {
+            check_webhook_url(url)?;
+        }
+
+        let store = &ctx_data(ctx).store;
+        require_watchlist_not_from_config(store, &name).await?;
+        let watchlist = store
+            .set_watchlist(
+                &NewWatchlist {
+                    name: name.clone(),
+                    deployments: deployments.clone(),
+                    polling_period_in_seconds,
+                    investigate_divergences,
+                    webhook_url: webhook_url.clone(),
+                    from_config: false,
+                    api_key_public_prefix: Some(api_key.public_part_as_string()),
+                },
+                (!is_admin).then_some(api_key),
+            )
+            .await?
+            .ok_or_else(|| {
+                GraphixApiError::InvalidInput(format!(
+                    "Watchlist `{name}` belongs to another organization"
+                ))
+            })?;
+        audit(
+            ctx,
+            "setWatchlist",
+            serde_json::json!({
+                "name": name,
+                "deployments": deployments,
+                "pollingPeriodInSeconds": polling_period_in_seconds,
+                "investigateDivergences": investigate_divergences,
+                "webhookUrl": webhook_url,
+            }),
+        )
+        .await?;
+
+        Ok(watchlist.into())
+    }
+
+    /// Deletes a watchlist, unless it's defined in the configuration. Returns
+    /// whether it was deleted. Only `admin` API keys can delete watchlists of
+    /// other organizations.
+    async fn delete_watchlist(&self, ctx: &Context<'_>, name: String) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        let (api_key, is_admin) = require_webhook_owner(ctx).await?;
+
+        let store = &ctx_data(ctx).store;
+        require_watchlist_not_from_config(store, &name).await?;
+        let deleted = store
+            .delete_watchlist(&name, (!is_admin).then_some(api_key))
+            .await?;
+        if deleted {
+            audit(ctx, "deleteWatchlist", serde_json::json!({ "name": name })).await?;
+        }
+
+        Ok(deleted)
+    }
+
     async fn set_deployment_name(
         &self,
         ctx: &Context<'_>,
         deployment_ipfs_cid: String,
         name: String,
-    ) -> Result<Deployment> {
+    ) -> ApiResult<Deployment> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
         let ctx_data = ctx_data(ctx);
         let store = &ctx_data.store;
 
+        let ipfs_cid = IpfsCid::from_str(&deployment_ipfs_cid)
+            .map_err(|err| GraphixApiError::InvalidInput(format!("Invalid IPFS CID: {err}")))?;
+        let filter = inputs::SgDeploymentsQuery {
+            ipfs_cid: Some(ipfs_cid.clone()),
+            ..Default::default()
+        };
+        if store.sg_deployments(filter).await?.is_empty() {
+            return Err(GraphixApiError::NotFound(format!(
+                "Subgraph deployment not found: {deployment_ipfs_cid}"
+            )));
+        }
+
         store
-            .set_deployment_name(&deployment_ipfs_cid, &name)
+            .set_deployment_name(&ipfs_cid.to_string(), &name)
             .await?;
+        audit(
+            ctx,
+            "setDeploymentName",
+            serde_json::json!({ "deployment": ipfs_cid.to_string(), "name": name }),
+        )
+        .await?;
 
         Ok(Deployment {
-            id: deployment_ipfs_cid,
+            id: ipfs_cid.to_string(),
         })
     }
 
+    /// Names subgraph deployments after the subgraph versions they were
+    /// published as on the network subgraph, e.g. `Uniswap V3 v0.0.1`, and
+    /// returns how many were named. Deployments that already have a name keep
+    /// it, unless `overwrite` is set. This also happens periodically in the
+    /// background. Requires the `operator` permission level.
+    async fn sync_deployment_names(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default = false,
+            desc = "Whether to rename deployments that already have a name."
+        )]
+        overwrite: bool,
+    ) -> ApiResult<u64> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
+        let ctx_data = ctx_data(ctx);
+        let config = ctx_data.config();
+        if config.network_subgraphs().is_empty() {
+            return Err(GraphixApiError::InvalidInput(
+                "No network subgraph is configured".to_string(),
+            ));
+        }
+
+        let named =
+            sync_deployment_names(&ctx_data.store, &config, metrics(), overwrite).await? as u64;
+        audit(
+            ctx,
+            "syncDeploymentNames",
+            serde_json::json!({ "overwrite": overwrite, "named": named }),
+        )
+        .await?;
+
+        Ok(named)
+    }
+
+    /// Sets the contact information of an indexer's operator, e.g. an email
+    /// address or a Discord handle, to be included in divergence alerts and
+    /// investigation reports. `null` clears it. Requires the `admin` permission
+    /// level, since the contact is shown to everyone who is notified about the
+    /// indexer.
+    async fn set_indexer_operator_contact(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+        contact: Option<String>,
+    ) -> ApiResult<api_types::Indexer> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let store = &ctx_data(ctx).store;
+        let filter = inputs::IndexersQuery {
+            address: Some(indexer_address),
+            limit: Some(1),
+            include_archived: true,
+            ..Default::default()
+        };
+        if store.indexers(filter).await?.is_empty() {
+            return Err(GraphixApiError::NotFound(format!(
+                "Indexer not found: {indexer_address}"
+            )));
+        }
+
+        let indexer = store
+            .set_indexer_operator_contact(&indexer_address, contact.as_deref())
+            .await?;
+        audit(
+            ctx,
+            "setIndexerOperatorContact",
+            serde_json::json!({ "indexerAddress": indexer_address, "contact": contact }),
+        )
+        .await?;
+
+        Ok(indexer.into())
+    }
+
+    /// Tags an indexer, e.g. with "testnet" or "own-fleet", to group it with
+    /// other indexers. Tagging an indexer twice with the same tag is a no-op.
+    async fn add_indexer_tag(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+        tag: String,
+    ) -> ApiResult<api_types::Indexer> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
+        let tag = validate_indexer_tag(&tag)?;
+        let indexer = ctx_data(ctx)
+            .store
+            .add_indexer_tag(&indexer_address, tag)
+            .await?
+            .ok_or_else(|| {
+                GraphixApiError::NotFound(format!("Indexer not found: {indexer_address}"))
+            })?;
+        audit(
+            ctx,
+            "addIndexerTag",
+            serde_json::json!({ "indexerAddress": indexer_address, "tag": tag }),
+        )
+        .await?;
+
+        Ok(indexer.into())
+    }
+
+    /// Removes a tag from an indexer. Removing a tag that the indexer doesn't
+    /// have is a no-op.
+    async fn remove_indexer_tag(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+        tag: String,
+    ) -> ApiResult<api_types::Indexer> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
+        let indexer = ctx_data(ctx)
+            .store
+            .remove_indexer_tag(&indexer_address, tag.trim())
+            .await?
+            .ok_or_else(|| {
+                GraphixApiError::NotFound(format!("Indexer not found: {indexer_address}"))
+            })?;
+        audit(
+            ctx,
+            "removeIndexerTag",
+            serde_json::json!({ "indexerAddress": indexer_address, "tag": tag.trim() }),
+        )
+        .await?;
+
+        Ok(indexer.into())
+    }
+
+    /// Archives an indexer, e.g. a test indexer that no longer exists. An
+    /// archived indexer is no longer polled and its PoIs are no longer live,
+    /// but its data is kept for historical queries. `indexers` only lists it
+    /// with `includeArchived: true`.
+    async fn archive_indexer(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+    ) -> ApiResult<api_types::Indexer> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let indexer = ctx_data(ctx)
+            .store
+            .archive_indexer(&indexer_address)
+            .await?
+            .ok_or_else(|| {
+                GraphixApiError::NotFound(format!("Indexer not found: {indexer_address}"))
+            })?;
+        audit(
+            ctx,
+            "archiveIndexer",
+            serde_json::json!({ "indexerAddress": indexer_address }),
+        )
+        .await?;
+
+        Ok(indexer.into())
+    }
+
+    /// Completely deletes an indexer and all related data (PoIs, indexing
+    /// statuses, errors, etc.). An indexer that is still configured, or found
+    /// on a network subgraph, is added again on the next polling cycle;
+    /// archive it instead to stop polling it.
+    async fn delete_indexer(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+    ) -> ApiResult<IndexerAddress> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        if !ctx_data(ctx).store.delete_indexer(&indexer_address).await? {
+            return Err(GraphixApiError::NotFound(format!(
+                "Indexer not found: {indexer_address}"
+            )));
+        }
+        audit(
+            ctx,
+            "deleteIndexer",
+            serde_json::json!({ "indexerAddress": indexer_address }),
+        )
+        .await?;
+
+        Ok(indexer_address)
+    }
+
+    /// Adds an `indexer` source to the stored configuration, e.g. for a
+    /// self-hosted graph-node node that isn't a network participant, and
+    /// returns its address. It's polled from the next polling cycle on.
+    /// Without an `address`, one is made up from the `name`. Requires the
+    /// `admin` permission level.
+    async fn add_tracked_indexer(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        #[graphql(desc = "The URL of the index node's GraphQL status endpoint.")]
+        status_url: String,
+        address: Option<IndexerAddress>,
+    ) -> ApiResult<IndexerAddress> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(GraphixApiError::InvalidInput(
+                "Indexer names can't be empty".to_string(),
+            ));
+        }
+        let index_node_endpoint = url::Url::parse(&status_url)
+            .map_err(|err| GraphixApiError::InvalidInput(format!("Invalid status URL: {err}")))?;
+        if !["http", "https"].contains(&index_node_endpoint.scheme()) {
+            return Err(GraphixApiError::InvalidInput(
+                "Status URLs must use HTTP(S)".to_string(),
+            ));
+        }
+        let address = address.unwrap_or_else(|| IndexerConfig::placeholder_address(&name));
+
+        let indexer = IndexerConfig {
+            name: Some(name.clone()),
+            address,
+            index_node_endpoint,
+            rate_limit: None,
+            auth: None,
+            polling_period_in_seconds: None,
+        };
+        let payload =
+            serde_json::json!({ "name": name, "statusUrl": status_url, "address": address });
+        ctx_data(ctx)
+            .store
+            .update_config(audited_mutation(ctx, "addTrackedIndexer"), |config| {
+                add_indexer_source(config, indexer).map(|()| Some(payload))
+            })
+            .await?
+            .map_err(|err| GraphixApiError::InvalidInput(format!("{err:#}")))?;
+
+        Ok(address)
+    }
+
+    /// Removes the `indexer` source with the given address from the stored
+    /// configuration, so that it's no longer polled from the next polling
+    /// cycle on, and returns whether there was one. Its data is kept; see
+    /// `deleteIndexer`. Requires the `admin` permission level.
+    async fn remove_tracked_indexer(
+        &self,
+        ctx: &Context<'_>,
+        address: IndexerAddress,
+    ) -> ApiResult<bool> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let removed = ctx_data(ctx)
+            .store
+            .update_config(audited_mutation(ctx, "removeTrackedIndexer"), |config| {
+                let removed = remove_indexer_source(config, &address)?;
+                anyhow::Ok(removed.then(|| serde_json::json!({ "address": address })))
+            })
+            .await?
+            .map_err(|err| GraphixApiError::InvalidInput(format!("{err:#}")))?;
+
+        Ok(removed)
+    }
+
+    /// Pauses or resumes PoI collection for a whole network (e.g. during a known
+    /// chain halt). Unlike `deleteNetwork`, all configuration and data are
+    /// preserved.
+    async fn set_chain_enabled(
+        &self,
+        ctx: &Context<'_>,
+        network: String,
+        enabled: bool,
+    ) -> ApiResult<api_types::Network> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let ctx_data = ctx_data(ctx);
+        let network = ctx_data
+            .store
+            .set_network_enabled(&network, enabled)
+            .await?
+            .ok_or_else(|| GraphixApiError::NotFound(format!("Network not found: {network}")))?;
+        audit(
+            ctx,
+            "setChainEnabled",
+            serde_json::json!({ "network": network.name, "enabled": enabled }),
+        )
+        .await?;
+
+        Ok(network.into())
+    }
+
     /// Completely deletes a network and all related data (PoIs, indexers, subgraphs, etc.).
-    async fn delete_network(&self, ctx: &Context<'_>, network: String) -> Result<String> {
+    async fn delete_network(&self, ctx: &Context<'_>, network: String) -> ApiResult<String> {
+        require_writable(ctx)?;
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
         let ctx_data = ctx_data(ctx);
         ctx_data.store.delete_network(&network).await?;
+        audit(
+            ctx,
+            "deleteNetwork",
+            serde_json::json!({ "network": network }),
+        )
+        .await?;
 
         Ok(network)
     }
 }
+
+async fn create_divergence_investigation_request(
+    ctx: &Context<'_>,
+    req: DivergenceInvestigationRequest,
+    priority: i32,
+) -> ApiResult<Uuid> {
+    let request_serialized = serde_json::to_value(req).unwrap();
+
+    let uuid = ctx_data(ctx)
+        .store
+        .create_divergence_investigation_request(request_serialized, priority)
+        .await?;
+    metrics().divergence_investigations_launched.inc();
+    Ok(uuid)
+}
+
+/// Records a successful mutation in the admin audit log, with the API key that
+/// made it. `payload` must not contain any secrets, such as API keys.
+async fn audit(ctx: &Context<'_>, mutation: &str, payload: serde_json::Value) -> ApiResult<()> {
+    let ctx_data = ctx_data(ctx);
+    ctx_data
+        .store
+        .write_admin_audit_log_entry(ctx_data.api_key.as_ref(), mutation, payload)
+        .await?;
+
+    Ok(())
+}
+
+/// Like [`audit`], but for mutations whose audit log entry the store writes in
+/// the same transaction as the change itself.
+fn audited_mutation<'a>(ctx: &'a Context<'_>, mutation: &'a str) -> AuditedMutation<'a> {
+    AuditedMutation {
+        api_key: ctx_data(ctx).api_key.as_ref(),
+        mutation,
+    }
+}
+
+fn parse_api_key(api_key: &str) -> ApiResult<ApiKey> {
+    ApiKey::from_str(api_key)
+        .map_err(|err| GraphixApiError::InvalidInput(format!("Invalid API key: {err}")))
+}
+
+/// Indexer tags are short, human-readable labels.
+const MAX_INDEXER_TAG_LENGTH: usize = 64;
+
+fn validate_indexer_tag(tag: &str) -> ApiResult<&str> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        Err(GraphixApiError::InvalidInput(
+            "Indexer tags can't be empty".to_string(),
+        ))
+    } else if tag.chars().count() > MAX_INDEXER_TAG_LENGTH {
+        Err(GraphixApiError::InvalidInput(format!(
+            "Indexer tags can't be longer than {MAX_INDEXER_TAG_LENGTH} characters"
+        )))
+    } else {
+        Ok(tag)
+    }
+}
+
+fn check_webhook_url(url: &str) -> ApiResult<()> {
+    webhooks::check_webhook_url(url).map_err(GraphixApiError::InvalidInput)
+}
+
+/// Watchlists that are defined in the configuration would be overwritten with
+/// the configured ones in the next polling cycle anyway.
+async fn require_watchlist_not_from_config(store: &Store, name: &str) -> ApiResult<()> {
+    let watchlists = store.watchlists(None).await?;
+    if watchlists
+        .iter()
+        .any(|watchlist| watchlist.name == name && watchlist.from_config)
+    {
+        return Err(GraphixApiError::InvalidInput(format!(
+            "Watchlist `{name}` is defined in the configuration"
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,440 @@
+//! The agreement of indexers' PoIs with those of other indexers: the
+//! consensus rule shared by all agreement queries, and the agreement of an
+//! indexer over time, computed from all collected PoIs rather than just live
+//! ones, for post-mortems of divergences.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use futures::TryStreamExt;
+use graphix_common_types::inputs::{BlockRange, PoisQuery};
+use graphix_common_types::{BlockHash, IndexerAddress, IpfsCid, PoiBytes};
+use graphix_store::models::{IntId, PoiExportRow};
+use graphix_store::Store;
+use num_traits::cast::ToPrimitive;
+
+use super::api_types::{ConsensusMode, PoiAgreementGranularity, PoiAgreementHistoryPoint};
+use super::{ApiResult, GraphixState};
+
+const PAGE_SIZE: u16 = 1000;
+
+/// The indexers behind each PoI of a subgraph deployment at some block, and
+/// their stake.
+#[derive(Debug, Default)]
+pub struct PoiTally {
+    pois: BTreeMap<PoiBytes, (u32, f64)>,
+}
+
+impl PoiTally {
+    pub fn add(&mut self, poi: PoiBytes, stake: f64) {
+        let (indexers, total_stake) = self.pois.entry(poi).or_default();
+        *indexers += 1;
+        *total_stake += stake;
+    }
+
+    pub fn total_indexers(&self) -> u32 {
+        self.pois.values().map(|(indexers, _)| indexers).sum()
+    }
+
+    /// Number of indexers with the given PoI.
+    pub fn indexers(&self, poi: &PoiBytes) -> u32 {
+        self.pois.get(poi).map_or(0, |(indexers, _)| *indexers)
+    }
+
+    /// The PoI with the most weight behind it according to `mode`, if that's
+    /// more than half of the total weight.
+    pub fn consensus(&self, mode: ConsensusMode) -> Option<PoiBytes> {
+        let weight = |(indexers, stake): &(u32, f64)| match mode {
+            ConsensusMode::Count => *indexers as f64,
+            ConsensusMode::StakeWeighted => *stake,
+        };
+        let total_weight: f64 = self.pois.values().map(weight).sum();
+
+        self.pois
+            .iter()
+            .map(|(poi, tally)| (poi, weight(tally)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .filter(|(_, weight)| *weight > total_weight / 2.0)
+            .map(|(poi, _)| *poi)
+    }
+
+    /// The share, between 0 and 1, of the total stake that is behind other
+    /// PoIs than `poi`, or `None` if no indexer has a stake.
+    pub fn disagreement_stake_weight(&self, poi: &PoiBytes) -> Option<f64> {
+        let total_stake: f64 = self.pois.values().map(|(_, stake)| stake).sum();
+        let agreeing_stake = self.pois.get(poi).map_or(0.0, |(_, stake)| *stake);
+
+        (total_stake > 0.0).then(|| (total_stake - agreeing_stake) / total_stake)
+    }
+}
+
+/// The stake of each indexer in each of `deployments`, for
+/// [`ConsensusMode::StakeWeighted`]: the tokens it allocated to the
+/// deployment, as found on the network subgraph. If none of the indexers
+/// allocated to a deployment, e.g. because it isn't on the network subgraph,
+/// the total staked tokens of `indexers` are used instead. Indexers without
+/// either have no stake.
+pub async fn indexer_stakes(
+    store: &Store,
+    deployments: &[IpfsCid],
+    indexers: &[IndexerAddress],
+) -> anyhow::Result<HashMap<IpfsCid, HashMap<IndexerAddress, f64>>> {
+    let mut stakes: HashMap<IpfsCid, HashMap<IndexerAddress, f64>> = HashMap::new();
+    for allocation in store.indexer_allocations(deployments).await? {
+        if let Some(tokens) = allocation.allocated_tokens.to_f64() {
+            stakes
+                .entry(allocation.deployment)
+                .or_default()
+                .insert(allocation.indexer_address, tokens);
+        }
+    }
+
+    if deployments.iter().any(|d| !stakes.contains_key(d)) {
+        let staked_tokens: HashMap<IndexerAddress, f64> = store
+            .indexer_staked_tokens(indexers)
+            .await?
+            .into_iter()
+            .filter_map(|(address, tokens)| Some((address, tokens.to_f64()?)))
+            .collect();
+        for deployment in deployments {
+            stakes
+                .entry(deployment.clone())
+                .or_insert_with(|| staked_tokens.clone());
+        }
+    }
+
+    Ok(stakes)
+}
+
+/// The live PoIs of a subgraph deployment, tallied by value.
+#[derive(Debug, Default)]
+pub struct LivePoiTally {
+    pub tally: PoiTally,
+    /// The most recently collected live PoI with each value, by value.
+    pub poi_ids: HashMap<PoiBytes, IntId>,
+}
+
+/// Tallies the live PoIs of each of `deployment_ids`, weighing them by the
+/// stakes returned by [`indexer_stakes`]. Rather than loading every live PoI,
+/// they're grouped by value in the database, and the deployments and indexers
+/// are batch-loaded to look up their stakes.
+pub async fn live_poi_tallies(
+    ctx_data: &GraphixState,
+    deployment_ids: &[IntId],
+) -> ApiResult<HashMap<IntId, LivePoiTally>> {
+    let groups = ctx_data.store.live_poi_groups(deployment_ids).await?;
+    let deployments = ctx_data
+        .loader_subgraph_deployment
+        .load_many(deployment_ids.iter().copied())
+        .await?;
+    let indexers = ctx_data
+        .loader_indexer
+        .load_many(groups.iter().flat_map(|group| group.indexer_ids.clone()))
+        .await?;
+
+    let deployment_cids: Vec<IpfsCid> = deployments.values().map(|d| d.cid.clone()).collect();
+    let addresses: Vec<IndexerAddress> = indexers.values().map(|i| i.address).collect();
+    let stakes = indexer_stakes(&ctx_data.store, &deployment_cids, &addresses).await?;
+
+    let mut tallies: HashMap<IntId, LivePoiTally> = HashMap::new();
+    for group in groups {
+        let deployment_stakes = deployments
+            .get(&group.sg_deployment_id)
+            .and_then(|deployment| stakes.get(&deployment.cid));
+        let tally = tallies.entry(group.sg_deployment_id).or_default();
+        for indexer_id in &group.indexer_ids {
+            let stake = indexers
+                .get(indexer_id)
+                .zip(deployment_stakes)
+                .and_then(|(indexer, stakes)| stakes.get(&indexer.address))
+                .copied()
+                .unwrap_or(0.0);
+            tally.tally.add(group.poi, stake);
+        }
+        tally.poi_ids.insert(group.poi, group.poi_id);
+    }
+
+    Ok(tallies)
+}
+
+/// Computes the agreement of `indexer_address` with other indexers on PoIs of
+/// `deployment` (optionally only on `network`) within `block_range`, oldest
+/// first.
+pub async fn poi_agreement_history(
+    store: &Store,
+    indexer_address: IndexerAddress,
+    deployment: IpfsCid,
+    network: Option<String>,
+    block_range: BlockRange,
+    granularity: PoiAgreementGranularity,
+    consensus_mode: ConsensusMode,
+) -> anyhow::Result<Vec<PoiAgreementHistoryPoint>> {
+    let filter = PoisQuery {
+        network,
+        deployments: vec![deployment.clone()],
+        block_range: Some(block_range),
+        ..Default::default()
+    };
+
+    let mut history = AgreementHistory::default();
+    store
+        .poi_export_stream(filter, PAGE_SIZE)
+        .try_for_each(|rows| {
+            rows.iter().for_each(|row| history.add(row));
+            futures::future::ok(())
+        })
+        .await?;
+
+    let stakes = match consensus_mode {
+        ConsensusMode::Count => HashMap::new(),
+        ConsensusMode::StakeWeighted => {
+            let indexers: Vec<IndexerAddress> = history.indexers().into_iter().collect();
+            indexer_stakes(store, &[deployment.clone()], &indexers)
+                .await?
+                .remove(&deployment)
+                .unwrap_or_default()
+        }
+    };
+
+    Ok(history.points(indexer_address, granularity, consensus_mode, &stakes))
+}
+
+/// The latest PoI of each indexer, and the block hash it refers to, per block
+/// number.
+#[derive(Default)]
+struct AgreementHistory {
+    blocks: BTreeMap<i64, Block>,
+}
+
+#[derive(Default)]
+struct Block {
+    epoch: Option<i64>,
+    pois: HashMap<IndexerAddress, (BlockHash, PoiBytes)>,
+}
+
+impl AgreementHistory {
+    /// Rows must be added in ID order, so that a PoI that was collected again
+    /// replaces the earlier one.
+    fn add(&mut self, row: &PoiExportRow) {
+        let block = self.blocks.entry(row.block_number).or_default();
+        block.epoch = block.epoch.or(row.epoch);
+        block
+            .pois
+            .insert(row.indexer_address, (row.block_hash.clone(), row.poi));
+    }
+
+    fn indexers(&self) -> BTreeSet<IndexerAddress> {
+        self.blocks
+            .values()
+            .flat_map(|block| block.pois.keys().copied())
+            .collect()
+    }
+
+    /// Uses the same consensus rule as the `poiAgreementRatios` GraphQL query,
+    /// see [`PoiTally::consensus`]. Indexers are weighed by their current
+    /// `stakes`, as their stakes at the time aren't known. Only blocks for
+    /// which `indexer_address` has a PoI
+    /// are included, and with [`PoiAgreementGranularity::Epoch`] only the
+    /// latest of them in each epoch. If indexers reported different block
+    /// hashes, the indexer is only compared with those that reported the same
+    /// block hash, and the block is flagged as a possible reorg.
+    fn points(
+        &self,
+        indexer_address: IndexerAddress,
+        granularity: PoiAgreementGranularity,
+        consensus_mode: ConsensusMode,
+        stakes: &HashMap<IndexerAddress, f64>,
+    ) -> Vec<PoiAgreementHistoryPoint> {
+        let mut points: Vec<PoiAgreementHistoryPoint> = vec![];
+        for (block_number, block) in &self.blocks {
+            let Some((block_hash, poi)) = block.pois.get(&indexer_address) else {
+                continue;
+            };
+
+            let mut tally = PoiTally::default();
+            let mut possible_reorg = false;
+            for (indexer, (hash, poi)) in &block.pois {
+                if hash == block_hash {
+                    tally.add(*poi, stakes.get(indexer).copied().unwrap_or(0.0));
+                } else {
+                    possible_reorg = true;
+                }
+            }
+            let total_indexers = tally.total_indexers();
+            let consensus_poi = tally.consensus(consensus_mode);
+            let n_agreeing_indexers = tally.indexers(poi);
+
+            let point = PoiAgreementHistoryPoint {
+                block_number: *block_number,
+                epoch: block.epoch,
+                poi: *poi,
+                total_indexers,
+                n_agreeing_indexers,
+                n_disagreeing_indexers: total_indexers - n_agreeing_indexers,
+                has_consensus: consensus_poi.is_some(),
+                in_consensus: consensus_poi.as_ref() == Some(poi),
+                possible_reorg,
+            };
+
+            match granularity {
+                PoiAgreementGranularity::Block => points.push(point),
+                PoiAgreementGranularity::Epoch => match points.last_mut() {
+                    _ if point.epoch.is_none() => {}
+                    Some(last) if last.epoch == point.epoch => *last = point,
+                    _ => points.push(point),
+                },
+            }
+        }
+
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn row(id: i32, block_number: i64, epoch: Option<i64>, indexer: u8, poi: u8) -> PoiExportRow {
+        PoiExportRow {
+            id,
+            deployment: IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA")
+                .unwrap(),
+            network: "mainnet".to_string(),
+            block_number,
+            block_hash: vec![block_number as u8; 32].into(),
+            indexer_address: IndexerAddress::from([indexer; 20]),
+            poi: [poi; 32].into(),
+            created_at: Default::default(),
+            epoch,
+            block_timestamp: None,
+        }
+    }
+
+    fn history(rows: &[PoiExportRow]) -> AgreementHistory {
+        let mut history = AgreementHistory::default();
+        rows.iter().for_each(|row| history.add(row));
+        history
+    }
+
+    #[test]
+    fn shows_when_an_indexer_started_disagreeing() {
+        let history = history(&[
+            row(1, 10, Some(1), 1, 1),
+            row(2, 10, Some(1), 2, 1),
+            row(3, 10, Some(1), 3, 1),
+            row(4, 20, Some(1), 1, 2),
+            row(5, 20, Some(1), 2, 1),
+            row(6, 20, Some(1), 3, 1),
+            // Only other indexers have PoIs for this block.
+            row(7, 30, Some(2), 2, 1),
+        ]);
+
+        let points = history.points(
+            IndexerAddress::from([1; 20]),
+            PoiAgreementGranularity::Block,
+            ConsensusMode::Count,
+            &HashMap::new(),
+        );
+        assert_eq!(points.len(), 2);
+        assert!(points[0].in_consensus);
+        assert_eq!(points[0].n_agreeing_indexers, 3);
+        assert_eq!(points[1].block_number, 20);
+        assert!(points[1].has_consensus);
+        assert!(!points[1].in_consensus);
+        assert_eq!(points[1].n_disagreeing_indexers, 2);
+    }
+
+    #[test]
+    fn pois_on_other_forks_are_not_disagreements() {
+        let mut reorged = row(3, 10, None, 3, 2);
+        reorged.block_hash = vec![0xff; 32].into();
+        let history = history(&[row(1, 10, None, 1, 1), row(2, 10, None, 2, 1), reorged]);
+
+        let points = history.points(
+            IndexerAddress::from([1; 20]),
+            PoiAgreementGranularity::Block,
+            ConsensusMode::Count,
+            &HashMap::new(),
+        );
+        assert!(points[0].possible_reorg);
+        assert!(points[0].in_consensus);
+        assert_eq!(points[0].total_indexers, 2);
+        assert_eq!(points[0].n_disagreeing_indexers, 0);
+
+        let points = history.points(
+            IndexerAddress::from([3; 20]),
+            PoiAgreementGranularity::Block,
+            ConsensusMode::Count,
+            &HashMap::new(),
+        );
+        assert!(points[0].possible_reorg);
+        assert!(points[0].in_consensus);
+    }
+
+    #[test]
+    fn stake_weighted_consensus() {
+        let mut tally = PoiTally::default();
+        tally.add([1; 32].into(), 100.0);
+        tally.add([1; 32].into(), 100.0);
+        tally.add([2; 32].into(), 600.0);
+
+        assert_eq!(tally.consensus(ConsensusMode::Count), Some([1; 32].into()));
+        assert_eq!(
+            tally.consensus(ConsensusMode::StakeWeighted),
+            Some([2; 32].into())
+        );
+        assert_eq!(tally.disagreement_stake_weight(&[1; 32].into()), Some(0.75));
+        assert_eq!(tally.disagreement_stake_weight(&[2; 32].into()), Some(0.25));
+
+        // Without stakes, there's no stake-weighted consensus.
+        let mut tally = PoiTally::default();
+        tally.add([1; 32].into(), 0.0);
+        assert_eq!(tally.consensus(ConsensusMode::Count), Some([1; 32].into()));
+        assert_eq!(tally.consensus(ConsensusMode::StakeWeighted), None);
+        assert_eq!(tally.disagreement_stake_weight(&[1; 32].into()), None);
+    }
+
+    #[test]
+    fn history_weighed_by_stake() {
+        let history = history(&[
+            row(1, 10, None, 1, 1),
+            row(2, 10, None, 2, 1),
+            row(3, 10, None, 3, 2),
+        ]);
+        let stakes = HashMap::from([
+            (IndexerAddress::from([1; 20]), 100.0),
+            (IndexerAddress::from([3; 20]), 500.0),
+        ]);
+
+        let points = history.points(
+            IndexerAddress::from([3; 20]),
+            PoiAgreementGranularity::Block,
+            ConsensusMode::StakeWeighted,
+            &stakes,
+        );
+        assert!(points[0].in_consensus);
+        assert_eq!(points[0].n_disagreeing_indexers, 2);
+    }
+
+    #[test]
+    fn epochs_are_represented_by_their_latest_block() {
+        let history = history(&[
+            row(1, 10, Some(1), 1, 1),
+            row(2, 20, Some(1), 1, 1),
+            row(3, 30, None, 1, 1),
+            row(4, 40, Some(2), 1, 1),
+            // Collected again, replacing the earlier PoI.
+            row(5, 40, Some(2), 1, 2),
+        ]);
+
+        let points = history.points(
+            IndexerAddress::from([1; 20]),
+            PoiAgreementGranularity::Epoch,
+            ConsensusMode::Count,
+            &HashMap::new(),
+        );
+        let blocks: Vec<_> = points.iter().map(|p| (p.block_number, p.poi)).collect();
+        assert_eq!(blocks, vec![(20, [1; 32].into()), (40, [2; 32].into())]);
+    }
+}
@@ -0,0 +1,185 @@
+//! `GET /api/v1/pois/export`, which streams collected PoIs as CSV or NDJSON
+//! for use in notebooks and other tools, without GraphQL pagination.
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::Json;
+use futures::{stream, StreamExt};
+use graphix_common_types::inputs::{BlockRange, PoisQuery};
+use graphix_common_types::IpfsCid;
+use graphix_store::models::PoiExportRow;
+use graphix_store::Store;
+use serde::Deserialize;
+
+use super::{
+    api_key_error, api_key_from_headers, check_rate_limit, internal_error, GraphixState,
+    ListInputTooLarge,
+};
+
+const PAGE_SIZE: u16 = 1000;
+
+const CSV_HEADER: &str =
+    "id,deployment,network,blockNumber,blockHash,indexerAddress,poi,createdAt,epoch,blockTimestamp\n";
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn write_row(&self, row: &PoiExportRow, buf: &mut String) -> anyhow::Result<()> {
+        match self {
+            ExportFormat::Csv => {
+                let fields = [
+                    row.id.to_string(),
+                    row.deployment.to_string(),
+                    csv_field(&row.network),
+                    row.block_number.to_string(),
+                    row.block_hash.to_string(),
+                    row.indexer_address.to_string(),
+                    row.poi.to_string(),
+                    row.created_at.to_string(),
+                    row.epoch.map(|epoch| epoch.to_string()).unwrap_or_default(),
+                    row.block_timestamp
+                        .map(|timestamp| timestamp.to_string())
+                        .unwrap_or_default(),
+                ];
+                buf.push_str(&fields.join(","));
+            }
+            ExportFormat::Ndjson => buf.push_str(&serde_json::to_string(row)?),
+        }
+        buf.push('\n');
+        Ok(())
+    }
+}
+
+/// Query string parameters of the export endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+    /// Comma-separated IPFS CIDs.
+    deployments: Option<String>,
+    network: Option<String>,
+    block_start: Option<u64>,
+    block_end: Option<u64>,
+    epoch: Option<u64>,
+    indexer_tag: Option<String>,
+    limit: Option<u16>,
+}
+
+impl ExportParams {
+    fn pois_query(&self, max_list_input_items: u32) -> anyhow::Result<PoisQuery> {
+        let deployments = match &self.deployments {
+            Some(deployments) => deployments
+                .split(',')
+                .map(|cid| cid.trim().parse::<IpfsCid>())
+                .collect::<Result<_, _>>()?,
+            None => vec![],
+        };
+        ListInputTooLarge::check("deployments", deployments.len(), max_list_input_items)?;
+        let block_range =
+            (self.block_start.is_some() || self.block_end.is_some()).then_some(BlockRange {
+                start: self.block_start,
+                end: self.block_end,
+            });
+
+        Ok(PoisQuery {
+            network: self.network.clone(),
+            deployments,
+            block_range,
+            epoch: self.epoch,
+            indexer_tag: self.indexer_tag.clone(),
+            limit: self.limit,
+        })
+    }
+}
+
+pub async fn export_pois_handler(
+    State(state): State<Arc<GraphixState>>,
+    headers: HeaderMap,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let api_key = api_key_from_headers(&headers)?.ok_or_else(|| api_key_error("No API key"))?;
+    // Like `/graphql`, before the export starts streaming.
+    check_rate_limit(&state, Some(&api_key)).await?;
+    let permission_level = state
+        .store
+        .permission_level(&api_key)
+        .await
+        .map_err(|err| internal_error(err.into()))?;
+    if permission_level.is_none() {
+        return Err(api_key_error("Unknown API key"));
+    }
+
+    let filter = params
+        .pois_query(state.config().max_list_input_items)
+        .map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": err.to_string() })),
+            )
+        })?;
+
+    let body = Body::from_stream(export_stream(state.store.clone(), filter, params.format));
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, params.format.content_type())
+        .body(body)
+        .expect("valid response"))
+}
+
+/// Streams the PoIs that match `filter`, one page of rows per item, so that
+/// large exports don't need to be held in memory.
+fn export_stream(
+    store: Store,
+    filter: PoisQuery,
+    format: ExportFormat,
+) -> impl futures::Stream<Item = anyhow::Result<String>> {
+    let header = match format {
+        ExportFormat::Csv => Some(Ok(CSV_HEADER.to_string())),
+        ExportFormat::Ndjson => None,
+    };
+    let rows = store.poi_export_stream(filter, PAGE_SIZE).map(move |page| {
+        let mut chunk = String::new();
+        for row in &page? {
+            format.write_row(row, &mut chunk)?;
+        }
+        Ok(chunk)
+    });
+
+    stream::iter(header).chain(rows)
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_escaped() {
+        assert_eq!(csv_field("mainnet"), "mainnet");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,36 @@
+//! `GET /api/v1/alerts/prometheus-rules`, which renders the configured alert
+//! routes as a Prometheus rules file, see [`crate::alerts::prometheus_rules`].
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use super::{api_key_error, api_key_from_headers, internal_error, GraphixState};
+use crate::alerts::prometheus_rules;
+
+pub async fn prometheus_rules_handler(
+    State(state): State<Arc<GraphixState>>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let api_key = api_key_from_headers(&headers)?.ok_or_else(|| api_key_error("No API key"))?;
+    let permission_level = state
+        .store
+        .permission_level(&api_key)
+        .await
+        .map_err(|err| internal_error(err.into()))?;
+    if permission_level.is_none() {
+        return Err(api_key_error("Unknown API key"));
+    }
+
+    let rules = prometheus_rules(&state.config().alerts);
+    let yaml = serde_yaml::to_string(&rules).map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": err.to_string() })),
+        )
+    })?;
+    Ok(([(header::CONTENT_TYPE, "application/yaml")], yaml).into_response())
+}
@@ -1,23 +1,100 @@
-use std::collections::BTreeMap;
+use std::collections::HashMap;
 
 use anyhow::Context as _;
-use async_graphql::{Context, Object, Result};
-use futures::future::try_join_all;
+use async_graphql::{Context, Object};
 use graphix_common_types::*;
-use graphix_store::models::ApiKeyPublicMetadata;
+use graphix_store::models::{self, ApiKeyPublicMetadata};
 use uuid::Uuid;
 
-use super::{api_types, ctx_data, require_permission_level};
+use super::{
+    api_types, check_list_input, ctx_data, federation, poi_agreement, require_permission_level,
+    require_webhook_owner, ApiResult, GraphixApiError, GraphixState,
+};
+
+/// How many events the `events` query returns unless a limit is given.
+const DEFAULT_EVENTS_LIMIT: u16 = 100;
+/// The fewest hex digits (i.e. 4 bytes) of a PoI that `poiSearch` accepts, so
+/// that prefixes are specific enough to be useful.
+const MIN_POI_SEARCH_PREFIX_DIGITS: usize = 8;
 
 pub struct QueryRoot;
 
 #[Object]
 impl QueryRoot {
     /// Returns the version of the Graphix instance.
-    async fn version(&self) -> Result<String> {
+    async fn version(&self) -> ApiResult<String> {
         Ok(crate::GRAPHIX_VERSION.to_string())
     }
 
+    /// Returns general information about this Graphix instance.
+    async fn instance(&self, ctx: &Context<'_>) -> ApiResult<api_types::Instance> {
+        let ctx_data = ctx_data(ctx);
+        let networks = ctx_data.store.networks().await?;
+        let last_poll_cycle = ctx_data.store.last_poi_collection_time().await?;
+
+        Ok(api_types::Instance {
+            name: ctx_data.config().instance_name,
+            version: crate::GRAPHIX_VERSION.to_string(),
+            networks: networks.into_iter().map(|network| network.name).collect(),
+            last_poll_cycle,
+            config_warning: ctx_data.config_reload_status.borrow().warning(),
+            read_only: ctx_data.config().read_only,
+        })
+    }
+
+    /// Returns the version, git commit and database migration version of this
+    /// Graphix instance, along with the size of its database, so that
+    /// operators don't need direct database access to check them.
+    async fn graphix_version_info(
+        &self,
+        ctx: &Context<'_>,
+    ) -> ApiResult<api_types::GraphixVersionInfo> {
+        let store = &ctx_data(ctx).store;
+
+        Ok(api_types::GraphixVersionInfo {
+            version: crate::GRAPHIX_VERSION.to_string(),
+            git_commit: crate::GRAPHIX_GIT_COMMIT.map(str::to_string),
+            schema_migration_version: store.schema_migration_version().await?,
+            database_size_bytes: store.database_size_bytes().await?,
+            tables: store.table_stats().await?,
+        })
+    }
+
+    /// Forwards a read-only GraphQL query to the remote Graphix instances of
+    /// this one, and returns the response of each of them. For example,
+    /// `{ instance { name networks } }` lists the networks of all instances.
+    async fn federated_query(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        variables: Option<serde_json::Value>,
+        #[graphql(
+            default,
+            desc = "Restricts the query to the remote instances with these names. All of them by default."
+        )]
+        instances: Vec<String>,
+    ) -> ApiResult<Vec<api_types::FederatedQueryResult>> {
+        check_list_input(ctx, "instances", &instances)?;
+        federation::ensure_read_only(&query)
+            .map_err(|err| GraphixApiError::InvalidInput(format!("{err:#}")))?;
+
+        let ctx_data = ctx_data(ctx);
+        let remotes: Vec<_> = ctx_data
+            .config()
+            .remote_instances
+            .into_iter()
+            .filter(|remote| instances.is_empty() || instances.contains(&remote.name))
+            .collect();
+
+        Ok(federation::federate(
+            &ctx_data.federation_client,
+            &remotes,
+            &query,
+            variables.as_ref(),
+        )
+        .await)
+    }
+
     /// Fetches all tracked subgraph deploymens in this Graphix instance and
     /// filters them according to some filtering rules.
     async fn deployments(
@@ -33,7 +110,7 @@ impl QueryRoot {
             desc = "Upper limit on the number of shown results."
         )]
         limit: u16,
-    ) -> Result<Vec<api_types::SubgraphDeployment>> {
+    ) -> ApiResult<Vec<api_types::SubgraphDeployment>> {
         let ctx_data = ctx_data(ctx);
 
         let filter = inputs::SgDeploymentsQuery {
@@ -54,18 +131,28 @@ impl QueryRoot {
         ctx: &Context<'_>,
         #[graphql(desc = "The address of the indexer, encoded as a hex string with a '0x' prefix")]
         address: Option<IndexerAddress>,
+        #[graphql(desc = "Restricts the query to indexers with the given tag.")] tag: Option<
+            String,
+        >,
         #[graphql(
             default = 100,
             validator(maximum = 250),
             desc = "Upper limit on the number of shown results."
         )]
         limit: u16,
-    ) -> Result<Vec<api_types::Indexer>> {
+        #[graphql(
+            default = false,
+            desc = "Also returns indexers that were archived with `archiveIndexer`."
+        )]
+        include_archived: bool,
+    ) -> ApiResult<Vec<api_types::Indexer>> {
         let ctx_data = ctx_data(ctx);
 
         let filter = inputs::IndexersQuery {
             address,
+            tag,
             limit: Some(limit),
+            include_archived,
         };
         let indexers = ctx_data.store.indexers(filter).await?;
 
@@ -74,6 +161,7 @@ impl QueryRoot {
 
     /// Filters through all PoIs ever collected by this Graphix
     /// instance, according to some filtering rules specified in `filter`.
+    #[allow(clippy::too_many_arguments)]
     async fn proofs_of_indexing(
         &self,
         ctx: &Context<'_>,
@@ -90,31 +178,410 @@ impl QueryRoot {
             desc = "Restricts the query to PoIs that were collected in the given block range."
         )]
         block_range: Option<inputs::BlockRange>,
+        #[graphql(desc = "Restricts the query to PoIs for blocks in the given protocol epoch.")]
+        epoch: Option<u64>,
+        #[graphql(desc = "Restricts the query to PoIs of indexers with the given tag.")]
+        indexer_tag: Option<String>,
         #[graphql(
             default = 100,
             validator(maximum = 250),
             desc = "Upper limit on the number of shown results."
         )]
         limit: u16,
-    ) -> Result<Vec<api_types::ProofOfIndexing>> {
+    ) -> ApiResult<Vec<api_types::ProofOfIndexing>> {
+        check_list_input(ctx, "deployments", &deployments)?;
         let ctx_data = ctx_data(ctx);
 
         let filter = inputs::PoisQuery {
             network,
             deployments,
             block_range,
+            epoch,
+            indexer_tag,
             limit: Some(limit),
         };
         let pois = ctx_data
             .store
-            .pois(&filter.deployments, filter.block_range, filter.limit)
+            .pois(
+                filter.network.as_deref(),
+                &filter.deployments,
+                filter.block_range,
+                filter.epoch,
+                filter.indexer_tag.as_deref(),
+                filter.limit,
+            )
             .await?;
 
         Ok(pois.into_iter().map(Into::into).collect())
     }
 
+    /// Aggregates collected PoIs by subgraph deployment and protocol epoch,
+    /// most recent epoch first, to align analyses with protocol accounting
+    /// periods.
+    async fn epoch_summaries(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default,
+            desc = "Restricts the query to these subgraph deployments. All of them by default."
+        )]
+        deployments: Vec<IpfsCid>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 1000),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::EpochSummary>> {
+        check_list_input(ctx, "deployments", &deployments)?;
+        let summaries = ctx_data(ctx)
+            .store
+            .epoch_summaries(&deployments, Some(limit))
+            .await?;
+
+        Ok(summaries.into_iter().map(Into::into).collect())
+    }
+
+    /// Summarizes the live PoIs of a subgraph deployment: the latest common
+    /// block, the distinct PoIs and the indexers that hold each of them, and
+    /// whether there is a consensus.
+    async fn deployment_status(
+        &self,
+        ctx: &Context<'_>,
+        ipfs_cid: IpfsCid,
+        #[graphql(
+            desc = "The network of the subgraph deployment. Required if it's indexed on several networks."
+        )]
+        network: Option<String>,
+    ) -> ApiResult<Option<api_types::DeploymentStatus>> {
+        let ctx_data = ctx_data(ctx);
+
+        let filter = inputs::SgDeploymentsQuery {
+            ipfs_cid: Some(ipfs_cid.clone()),
+            network_name: network,
+            ..Default::default()
+        };
+        let mut deployments = ctx_data.store.sg_deployments(filter).await?;
+        if deployments.len() > 1 {
+            return Err(GraphixApiError::InvalidInput(format!(
+                "Subgraph deployment {ipfs_cid} is indexed on several networks, specify one with `network`"
+            )));
+        }
+        let Some(deployment) = deployments.pop() else {
+            return Ok(None);
+        };
+        let network: Option<models::Network> = ctx_data
+            .loader_network
+            .load_one(deployment.network_id)
+            .await?;
+        let network = network.map(|network| network.name);
+        let live_pois = ctx_data
+            .store
+            .live_pois(
+                None,
+                network.as_deref(),
+                Some(&[ipfs_cid]),
+                None,
+                None,
+                None,
+            )
+            .await?;
+
+        Ok(Some(api_types::DeploymentStatus::new(
+            deployment, live_pois,
+        )))
+    }
+
+    /// Row counts and on-disk sizes of all database tables, for capacity
+    /// planning.
+    async fn storage_stats(&self, ctx: &Context<'_>) -> ApiResult<Vec<models::TableStats>> {
+        Ok(ctx_data(ctx).store.table_stats().await?)
+    }
+
+    /// Returns the latest paused, synced, and health flags that indexers
+    /// reported for subgraph deployments.
+    async fn indexing_statuses(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default,
+            desc = "Restricts the query to these given subgraph deployments (by IPFS CID)."
+        )]
+        deployments: Vec<IpfsCid>,
+        #[graphql(desc = "The address of the indexer, encoded as a hex string with a '0x' prefix")]
+        indexer_address: Option<IndexerAddress>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::IndexingStatus>> {
+        check_list_input(ctx, "deployments", &deployments)?;
+        let ctx_data = ctx_data(ctx);
+        let statuses = ctx_data
+            .store
+            .indexing_statuses(&deployments, indexer_address.as_ref(), Some(limit))
+            .await?;
+
+        Ok(statuses.into_iter().map(Into::into).collect())
+    }
+
+    /// Returns the errors that indexers' `graph-node`s reported while indexing
+    /// subgraph deployments, most recently seen first, to correlate
+    /// divergences with subgraph failures.
+    async fn indexing_errors(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default,
+            desc = "Restricts the query to these given subgraph deployments (by IPFS CID)."
+        )]
+        deployments: Vec<IpfsCid>,
+        #[graphql(desc = "The address of the indexer, encoded as a hex string with a '0x' prefix")]
+        indexer_address: Option<IndexerAddress>,
+        #[graphql(
+            desc = "Restricts the query to fatal errors if `true`, or to non-fatal errors if `false`."
+        )]
+        fatal: Option<bool>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::IndexingError>> {
+        check_list_input(ctx, "deployments", &deployments)?;
+        let errors = ctx_data(ctx)
+            .store
+            .indexing_errors(&deployments, indexer_address.as_ref(), fatal, Some(limit))
+            .await?;
+
+        Ok(errors.into_iter().map(Into::into).collect())
+    }
+
+    /// Returns the changes in how indexers reported subgraph deployments in
+    /// their indexing statuses, most recent first, to tell whether a
+    /// deployment disappeared because indexers removed it.
+    async fn deployment_events(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default,
+            desc = "Restricts the query to these given subgraph deployments (by IPFS CID)."
+        )]
+        deployments: Vec<IpfsCid>,
+        #[graphql(desc = "The address of the indexer, encoded as a hex string with a '0x' prefix")]
+        indexer_address: Option<IndexerAddress>,
+        #[graphql(desc = "Restricts the query to events of this kind.")] kind: Option<
+            DeploymentEventKind,
+        >,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::DeploymentEvent>> {
+        check_list_input(ctx, "deployments", &deployments)?;
+        let events = ctx_data(ctx)
+            .store
+            .sg_deployment_events(&deployments, indexer_address.as_ref(), kind, Some(limit))
+            .await?;
+
+        Ok(events.into_iter().map(Into::into).collect())
+    }
+
+    /// Latency percentiles and success rates of the requests that Graphix
+    /// times to benchmark indexers' responsiveness, by kind of request. Lists
+    /// the stats of the given indexer (or of each indexer), followed by those
+    /// of all indexers together to compare against. Empty unless
+    /// `latencyBenchmark` is configured.
+    async fn indexer_latency_stats(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            desc = "The address of the indexer, encoded as a hex string with a '0x' prefix. All indexers by default."
+        )]
+        indexer_address: Option<IndexerAddress>,
+        #[graphql(
+            default = 24,
+            validator(minimum = 1, maximum = 720),
+            desc = "Only samples taken during this many past hours are taken into account."
+        )]
+        hours: u32,
+    ) -> ApiResult<Vec<api_types::IndexerLatencyStats>> {
+        let since = chrono::Utc::now() - chrono::Duration::hours(hours.into());
+        let stats = ctx_data(ctx)
+            .store
+            .indexer_latency_stats(indexer_address.as_ref(), since.naive_utc())
+            .await?;
+
+        Ok(stats.into_iter().map(Into::into).collect())
+    }
+
+    /// Compares the PoIs that indexers submitted on-chain when closing
+    /// allocations with the PoIs that Graphix collected from their
+    /// `graph-node`s, to catch indexers that submit a different PoI than the
+    /// one they report. Null PoIs of substreams-powered subgraph deployments
+    /// aren't compared, and differing PoIs at blocks with block hash conflicts
+    /// are flagged as a possible reorg instead.
+    async fn onchain_poi_cross_checks(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default,
+            desc = "Restricts the query to these given subgraph deployments (by IPFS CID)."
+        )]
+        deployments: Vec<IpfsCid>,
+        #[graphql(desc = "The address of the indexer, encoded as a hex string with a '0x' prefix")]
+        indexer_address: Option<IndexerAddress>,
+        #[graphql(desc = "Restricts the query to the subgraph deployments on this network.")]
+        network: Option<String>,
+        #[graphql(
+            default = false,
+            desc = "Only returns on-chain PoIs that differ from the collected ones."
+        )]
+        mismatches_only: bool,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::OnchainPoiCrossCheck>> {
+        check_list_input(ctx, "deployments", &deployments)?;
+        let ctx_data = ctx_data(ctx);
+        let cross_checks = ctx_data
+            .store
+            .onchain_poi_cross_checks(
+                &deployments,
+                indexer_address.as_ref(),
+                network.as_deref(),
+                mismatches_only,
+                Some(limit),
+            )
+            .await?;
+        let substreams_deployments = ctx_data.store.substreams_sg_deployments().await?;
+        let cross_checked_deployments: Vec<IpfsCid> = cross_checks
+            .iter()
+            .map(|(onchain_poi, _)| onchain_poi.deployment.clone())
+            .collect();
+        let block_hash_conflicts = ctx_data
+            .store
+            .block_hash_conflicts_of_deployments(&cross_checked_deployments)
+            .await?;
+
+        Ok(cross_checks
+            .into_iter()
+            .map(|(onchain_poi, collected_poi)| {
+                let substreams = substreams_deployments.contains(&onchain_poi.deployment);
+                let possible_reorg = block_hash_conflicts
+                    .contains(&(onchain_poi.deployment.clone(), onchain_poi.block_number));
+                api_types::OnchainPoiCrossCheck::new(
+                    onchain_poi,
+                    collected_poi,
+                    substreams,
+                    possible_reorg,
+                )
+            })
+            .collect())
+    }
+
+    /// Compares the PoIs of two indexers for a subgraph deployment at every
+    /// block at which Graphix collected PoIs from both, newest block first, so
+    /// that their operators can review the full history of their
+    /// disagreements rather than just their live PoIs. Where the PoIs differ,
+    /// the report includes the diverging block found by a divergence
+    /// investigation of the two PoIs, if there was one.
+    async fn poi_cross_check_reports(
+        &self,
+        ctx: &Context<'_>,
+        indexer1: IndexerAddress,
+        indexer2: IndexerAddress,
+        deployment: IpfsCid,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<PoiCrossCheckReport>> {
+        let store = &ctx_data(ctx).store;
+        let rows = store
+            .poi_cross_checks(&indexer1, &indexer2, &deployment, limit)
+            .await?;
+
+        let differing_pairs: Vec<_> = rows
+            .iter()
+            .filter(|row| row.poi1 != row.poi2)
+            .map(|row| (row.network.clone(), row.poi1, row.poi2))
+            .collect();
+        let divergences: HashMap<_, _> = store
+            .poi_cross_check_divergences(&indexer1, &indexer2, &deployment, &differing_pairs)
+            .await?
+            .into_iter()
+            .map(|divergence| {
+                let key = (divergence.network.clone(), divergence.poi1, divergence.poi2);
+                (key, divergence)
+            })
+            .collect();
+
+        let mut reports = vec![];
+        for row in rows {
+            let diverging_block = divergences
+                .get(&(row.network.clone(), row.poi1, row.poi2))
+                .map(|divergence| DivergingBlock {
+                    block: PartialBlock {
+                        number: divergence.block_number,
+                        hash: Some(divergence.block_hash.clone()),
+                    },
+                    proof_of_indexing1: divergence.diverging_poi1,
+                    proof_of_indexing2: divergence.diverging_poi2,
+                });
+            reports.push(PoiCrossCheckReport {
+                timestamp: row.collected_at.and_utc().to_rfc3339(),
+                indexer1: indexer1.to_string(),
+                indexer2: indexer2.to_string(),
+                deployment: deployment.to_string(),
+                block: PartialBlock {
+                    number: row.block_number,
+                    hash: Some(row.block_hash),
+                },
+                proof_of_indexing1: row.poi1,
+                proof_of_indexing2: row.poi2,
+                diverging_block,
+            });
+        }
+
+        Ok(reports)
+    }
+
+    /// Lists the block numbers for which indexers reported different block
+    /// hashes, most recently detected first. PoIs at those blocks may differ
+    /// because of a chain reorg rather than a PoI divergence.
+    async fn block_hash_conflicts(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Restricts the query to block hash conflicts on the given chain name.")]
+        network: Option<String>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::BlockHashConflict>> {
+        let conflicts = ctx_data(ctx)
+            .store
+            .block_hash_conflicts(network.as_deref(), Some(limit))
+            .await?;
+
+        Ok(conflicts.into_iter().map(Into::into).collect())
+    }
+
     /// A copy of the configuration file used to run Graphix.
-    async fn configuration(&self, ctx: &Context<'_>) -> Result<Option<serde_json::Value>> {
+    async fn configuration(&self, ctx: &Context<'_>) -> ApiResult<Option<serde_json::Value>> {
         require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
 
         let ctx_data = ctx_data(ctx);
@@ -123,6 +590,126 @@ impl QueryRoot {
         Ok(config)
     }
 
+    /// Exports the active configuration together with the names of subgraph
+    /// deployments and the tags of indexers as a single JSON document, which
+    /// `importConfig` restores, e.g. on a staging replica. Requires the
+    /// `admin` permission level.
+    async fn export_config(&self, ctx: &Context<'_>) -> ApiResult<serde_json::Value> {
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        let ctx_data = ctx_data(ctx);
+        let config =
+            serde_json::to_value(ctx_data.config()).context("failed to serialize configuration")?;
+        let instance_config = ctx_data.store.export_instance_config(config).await?;
+
+        Ok(serde_json::to_value(instance_config).context("failed to serialize export")?)
+    }
+
+    /// Counts how many indexers run each graph-node version and commit, most
+    /// common first, to correlate divergences with graph-node releases.
+    async fn graph_node_version_distribution(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            desc = "The point in time to count indexers at, instead of now. Archived indexers are only counted for past points in time."
+        )]
+        at: Option<chrono::NaiveDateTime>,
+    ) -> ApiResult<Vec<models::GraphNodeVersionCount>> {
+        Ok(ctx_data(ctx)
+            .store
+            .graph_node_version_distribution(at)
+            .await?)
+    }
+
+    /// Aggregates the divergences found by bisection runs by the pair of
+    /// graph-node versions that the two indexers ran, most common first, to
+    /// tell apart divergences caused by graph-node releases.
+    async fn divergence_by_version_pair(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Only count bisection runs created since this point in time.")]
+        since: Option<chrono::NaiveDateTime>,
+    ) -> ApiResult<Vec<api_types::DivergenceVersionPair>> {
+        let pairs = ctx_data(ctx)
+            .store
+            .divergences_by_graph_node_version_pair(since)
+            .await?;
+
+        Ok(pairs.into_iter().map(Into::into).collect())
+    }
+
+    /// Lists destructive and configuration-changing mutations, most recent
+    /// first, with the API keys that made them. Requires the `admin`
+    /// permission level.
+    async fn admin_audit_log(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Restricts the query to the given mutation, e.g. `deleteNetwork`.")]
+        mutation: Option<String>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<models::AdminAuditLogEntry>> {
+        require_permission_level(ctx, ApiKeyPermissionLevel::Admin).await?;
+
+        Ok(ctx_data(ctx)
+            .store
+            .admin_audit_log(mutation.as_deref(), Some(limit))
+            .await?)
+    }
+
+    /// Everything Graphix knows about a PoI hash: each collected PoI with this
+    /// hash as a `LivePoi` or `HistoricalPoi`, and each bisection run that
+    /// compared one of them as a `BisectionPoi`.
+    async fn poi_provenance(
+        &self,
+        ctx: &Context<'_>,
+        hash: PoiBytes,
+        #[graphql(
+            desc = "Restricts the query to PoIs for subgraph deployments that index the given chain name."
+        )]
+        network: Option<String>,
+    ) -> ApiResult<Vec<api_types::Poi>> {
+        api_types::Poi::all_with_hash(ctx_data(ctx), &hash, network.as_deref()).await
+    }
+
+    /// Finds collected PoIs by the first hex digits of their hash, e.g. when
+    /// only a truncated PoI was copied from logs. Returns the newest ones
+    /// first, along with their subgraph deployments and indexers.
+    async fn poi_search(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            desc = "At least 8 hex digits (4 bytes) of the PoI hash, with or without '0x' prefix."
+        )]
+        prefix: String,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::ProofOfIndexing>> {
+        let hex_prefix = prefix.strip_prefix("0x").unwrap_or(&prefix);
+        if !(MIN_POI_SEARCH_PREFIX_DIGITS..=64).contains(&hex_prefix.len())
+            || !hex_prefix.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(GraphixApiError::InvalidInput(format!(
+                "`prefix` must have {MIN_POI_SEARCH_PREFIX_DIGITS} to 64 hex digits, got `{prefix}`"
+            )));
+        }
+
+        let pois = ctx_data(ctx)
+            .store
+            .pois_by_prefix(hex_prefix, limit)
+            .await?;
+
+        Ok(pois.into_iter().map(|(poi, _live)| poi.into()).collect())
+    }
+
     /// Same as [`QueryRoot::proofs_of_indexing`], but only returns PoIs that
     /// are "live" i.e. they are the most recent PoI collected for their
     /// subgraph deployment.
@@ -130,14 +717,17 @@ impl QueryRoot {
         &self,
         ctx: &Context<'_>,
         filter: inputs::PoisQuery,
-    ) -> Result<Vec<api_types::ProofOfIndexing>> {
+    ) -> ApiResult<Vec<api_types::ProofOfIndexing>> {
+        check_list_input(ctx, "filter.deployments", &filter.deployments)?;
         let ctx_data = ctx_data(ctx);
         let pois = ctx_data
             .store
             .live_pois(
                 None,
+                filter.network.as_deref(),
                 Some(&filter.deployments),
                 filter.block_range,
+                filter.indexer_tag.as_deref(),
                 filter.limit,
             )
             .await?;
@@ -145,95 +735,210 @@ impl QueryRoot {
         Ok(pois.into_iter().map(Into::into).collect())
     }
 
-    async fn api_keys(&self, ctx: &Context<'_>) -> Result<Vec<ApiKeyPublicMetadata>> {
+    async fn api_keys(&self, ctx: &Context<'_>) -> ApiResult<Vec<ApiKeyPublicMetadata>> {
         let ctx_data = ctx_data(ctx);
         let api_keys = ctx_data.store.api_keys().await?;
 
         Ok(api_keys)
     }
 
-    async fn poi_agreement_ratios(
+    /// Lists the webhooks registered by the organization of the request's API
+    /// key, or all webhooks for `admin` API keys.
+    async fn webhooks(&self, ctx: &Context<'_>) -> ApiResult<Vec<api_types::Webhook>> {
+        let (api_key, is_admin) = require_webhook_owner(ctx).await?;
+
+        let webhooks = ctx_data(ctx)
+            .store
+            .webhooks((!is_admin).then_some(api_key))
+            .await?;
+
+        Ok(webhooks.into_iter().map(Into::into).collect())
+    }
+
+    /// Lists the deployment subscriptions of the organization of the request's
+    /// API key, or all of them for `admin` API keys.
+    async fn deployment_subscriptions(
         &self,
         ctx: &Context<'_>,
-        indexer_address: IndexerAddress,
-    ) -> Result<Vec<api_types::PoiAgreementRatio>> {
-        let ctx_data = ctx_data(ctx);
+    ) -> ApiResult<Vec<api_types::DeploymentSubscription>> {
+        let (api_key, is_admin) = require_webhook_owner(ctx).await?;
 
-        // Query live POIs of a the requested indexer.
-        let indexer_pois = live_pois(ctx, indexer_address).await?;
+        let subscriptions = ctx_data(ctx)
+            .store
+            .deployment_subscriptions((!is_admin).then_some(api_key))
+            .await?;
 
-        let deployments =
-            try_join_all(indexer_pois.iter().map(|poi| poi.deployment(ctx_data))).await?;
+        Ok(subscriptions.into_iter().map(Into::into).collect())
+    }
 
-        let deployment_cids: Vec<IpfsCid> = deployments.iter().map(|d| d.cid().clone()).collect();
+    /// Lists the watchlists that are defined in the configuration and those of
+    /// the organization of the request's API key, or all watchlists for
+    /// `admin` API keys. Ordered by name.
+    async fn watchlists(&self, ctx: &Context<'_>) -> ApiResult<Vec<api_types::Watchlist>> {
+        let (api_key, is_admin) = require_webhook_owner(ctx).await?;
 
-        // Query all live POIs for the specific deployments.
-        let all_deployment_pois = ctx_data
+        let watchlists = ctx_data(ctx)
             .store
-            .live_pois(None, Some(&deployment_cids), None, None)
+            .watchlists((!is_admin).then_some(api_key))
             .await?;
 
-        // Convert POIs to ProofOfIndexing and group by deployment
-        let mut deployment_to_pois: BTreeMap<String, Vec<api_types::ProofOfIndexing>> =
-            BTreeMap::new();
-        for poi in all_deployment_pois {
-            let proof_of_indexing: api_types::ProofOfIndexing = poi.into();
-            deployment_to_pois
-                .entry(
-                    proof_of_indexing
-                        .deployment(ctx_data)
-                        .await?
-                        .cid()
-                        .to_string(),
-                )
-                .or_default()
-                .push(proof_of_indexing);
-        }
+        Ok(watchlists.into_iter().map(Into::into).collect())
+    }
 
-        let mut agreement_ratios: Vec<api_types::PoiAgreementRatio> = Vec::new();
+    async fn poi_agreement_ratios(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+        #[graphql(
+            desc = "Restricts the query to PoIs for subgraph deployments that index the given chain name."
+        )]
+        network: Option<String>,
+        #[graphql(default)] consensus_mode: api_types::ConsensusMode,
+    ) -> ApiResult<Vec<api_types::PoiAgreementRatio>> {
+        let ratios = poi_agreement_ratios(
+            ctx_data(ctx),
+            indexer_address,
+            network.as_deref(),
+            consensus_mode,
+        )
+        .await?
+        .into_iter()
+        .map(|(_, ratio)| ratio)
+        .collect();
 
-        for poi in indexer_pois {
-            let deployment_pois = deployment_to_pois
-                .get(&poi.deployment(ctx_data).await?.cid().to_string())
-                .context("inconsistent pois table, no pois for deployment")?;
+        Ok(ratios)
+    }
 
-            let total_indexers = deployment_pois.len() as u32;
+    /// Lists how far each subgraph deployment of an indexer is behind the
+    /// head of its chain, most blocks behind first.
+    async fn indexer_lag(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "The address of the indexer, encoded as a hex string with a '0x' prefix")]
+        address: IndexerAddress,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::IndexingLag>> {
+        let lags = ctx_data(ctx)
+            .store
+            .indexing_lags(Some(&address), Some(limit))
+            .await?;
 
-            // Calculate POI agreement by creating a map to count unique POIs and their occurrence.
-            let mut poi_counts: BTreeMap<PoiBytes, u32> = BTreeMap::new();
-            for dp in deployment_pois {
-                *poi_counts.entry(dp.hash()).or_insert(0) += 1;
-            }
+        Ok(lags.into_iter().map(Into::into).collect())
+    }
 
-            // Define consensus and agreement based on the map.
-            let (max_poi, max_poi_count) = poi_counts
-                .iter()
-                .max_by_key(|(_, &v)| v)
-                .context("inconsistent pois table, no pois")?;
+    /// Lists all subgraph deployments on which the live PoI of an indexer
+    /// disagrees with the consensus of all indexers, together with the
+    /// consensus PoI and the outcome of any divergence investigation of the
+    /// indexer's PoI. Answers "where am I wrong?" for indexer operators.
+    async fn my_poi_report(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: IndexerAddress,
+        #[graphql(
+            desc = "Restricts the report to subgraph deployments that index the given chain name."
+        )]
+        network: Option<String>,
+        #[graphql(default)] consensus_mode: api_types::ConsensusMode,
+    ) -> ApiResult<api_types::IndexerPoiReport> {
+        let ctx_data = ctx_data(ctx);
+        let (indexer_pois, tallies) =
+            live_pois_with_tallies(ctx_data, indexer_address, network.as_deref()).await?;
 
-            let has_consensus = *max_poi_count > total_indexers / 2;
+        let mut report = api_types::IndexerPoiReport {
+            indexer_address,
+            deployments: indexer_pois.len() as u32,
+            deployments_in_consensus: 0,
+            deployments_without_consensus: 0,
+            disagreements: vec![],
+        };
+        let mut disagreements = vec![];
+        for poi in indexer_pois {
+            let poi_agreement::LivePoiTally { tally, poi_ids } = tallies
+                .get(&poi.model.sg_deployment_id)
+                .context("inconsistent pois table, no pois for deployment")?;
 
-            let n_agreeing_indexers = *poi_counts
-                .get(&poi.hash())
-                .context("inconsistent pois table, no matching poi")?;
+            let Some(consensus_poi) = tally.consensus(consensus_mode) else {
+                report.deployments_without_consensus += 1;
+                continue;
+            };
+            if consensus_poi == poi.hash() {
+                report.deployments_in_consensus += 1;
+                continue;
+            }
 
-            let n_disagreeing_indexers = total_indexers - n_agreeing_indexers;
+            let consensus_poi_id = *poi_ids
+                .get(&consensus_poi)
+                .context("inconsistent pois table, no consensus poi")?;
+            disagreements.push((poi, tally, consensus_poi, consensus_poi_id));
+        }
 
-            let in_consensus = has_consensus && max_poi == &poi.hash();
+        let consensus_pois = ctx_data
+            .loader_poi
+            .load_many(disagreements.iter().map(|(_, _, _, id)| *id))
+            .await?;
+        let pois: Vec<_> = disagreements.iter().map(|(poi, ..)| poi.hash()).collect();
+        let mut bisection_runs = ctx_data.store.latest_bisection_runs(&pois).await?;
 
-            let ratio = api_types::PoiAgreementRatio {
-                poi_id: poi.model.id,
-                total_indexers,
-                n_agreeing_indexers,
-                n_disagreeing_indexers,
-                has_consensus,
-                in_consensus,
-            };
+        for (poi, tally, consensus_poi, consensus_poi_id) in disagreements {
+            let consensus_poi_model = consensus_pois
+                .get(&consensus_poi_id)
+                .cloned()
+                .context("inconsistent pois table, no consensus poi")?;
+            let bisection_run = bisection_runs.remove(&poi.hash());
 
-            agreement_ratios.push(ratio);
+            report.disagreements.push(api_types::PoiDisagreement {
+                total_indexers: tally.total_indexers(),
+                n_agreeing_indexers: tally.indexers(&poi.hash()),
+                n_consensus_indexers: tally.indexers(&consensus_poi),
+                consensus_poi: consensus_poi_model.into(),
+                poi,
+                bisection_run: bisection_run.map(Into::into),
+            });
         }
 
-        Ok(agreement_ratios)
+        Ok(report)
+    }
+
+    /// Returns the agreement of an indexer's PoIs for a subgraph deployment
+    /// with those of other indexers over time, oldest first, to find out when
+    /// it started disagreeing. Unlike `poiAgreementRatios`, it takes all
+    /// collected PoIs into account rather than just live ones.
+    #[allow(clippy::too_many_arguments)]
+    async fn poi_agreement_history(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "The address of the indexer, encoded as a hex string with a '0x' prefix")]
+        indexer_address: IndexerAddress,
+        deployment_cid: IpfsCid,
+        #[graphql(
+            desc = "Restricts the query to PoIs for subgraph deployments that index the given chain name."
+        )]
+        network: Option<String>,
+        #[graphql(desc = "The first block number to include (inclusive).")] from_block: Option<u64>,
+        #[graphql(desc = "The last block number to include (inclusive).")] to_block: Option<u64>,
+        #[graphql(default)] granularity: api_types::PoiAgreementGranularity,
+        #[graphql(default)] consensus_mode: api_types::ConsensusMode,
+    ) -> ApiResult<Vec<api_types::PoiAgreementHistoryPoint>> {
+        let block_range = inputs::BlockRange {
+            start: from_block,
+            end: to_block,
+        };
+
+        Ok(poi_agreement::poi_agreement_history(
+            &ctx_data(ctx).store,
+            indexer_address,
+            deployment_cid,
+            network,
+            block_range,
+            granularity,
+            consensus_mode,
+        )
+        .await?)
     }
 
     async fn divergence_investigation_report(
@@ -243,53 +948,283 @@ impl QueryRoot {
             desc = "The UUID of the divergence investigation report to fetch. This is the UUID that was returned by the `launchDivergenceInvestigation` mutation."
         )]
         uuid: Uuid,
-    ) -> Result<Option<DivergenceInvestigationReport>> {
+    ) -> ApiResult<Option<DivergenceInvestigationReport>> {
         let ctx_data = ctx_data(ctx);
 
-        if let Some(report_json) = ctx_data
+        if let Some(report) = ctx_data
             .store
             .divergence_investigation_report(&uuid)
             .await?
         {
-            Ok(
-                serde_json::from_value(report_json)
-                    .expect("Can't deserialize report from database"),
-            )
-        } else if ctx_data
-            .store
-            .divergence_investigation_request_exists(&uuid)
-            .await?
-        {
+            Ok(Some(report))
+        } else if let Some(job) = ctx_data.store.divergence_investigation_job(&uuid).await? {
+            // Failed requests may not have written a report yet.
+            let (status, error) = match job.status {
+                JobStatus::Queued if job.attempts == 0 => {
+                    (DivergenceInvestigationStatus::Pending, None)
+                }
+                JobStatus::Queued | JobStatus::Running => {
+                    (DivergenceInvestigationStatus::InProgress, None)
+                }
+                JobStatus::Failed => (
+                    DivergenceInvestigationStatus::Complete,
+                    Some(format!(
+                        "Failed after {} attempts: {}",
+                        job.attempts,
+                        job.last_error.unwrap_or_default()
+                    )),
+                ),
+            };
             Ok(Some(DivergenceInvestigationReport {
                 uuid,
-                status: DivergenceInvestigationStatus::InProgress,
+                status,
                 bisection_runs: vec![],
-                error: None,
+                error,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Queries the bisection runs of all divergence investigations, most recent
+    /// first, e.g. to find all divergences at a given block or all subgraph
+    /// deployments that diverged recently.
+    async fn bisection_runs(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: inputs::BisectionRunsQuery,
+    ) -> ApiResult<Vec<api_types::BisectionRun>> {
+        check_list_input(ctx, "filter.deployments", &filter.deployments)?;
+        let runs = ctx_data(ctx).store.bisection_runs(&filter).await?;
+
+        Ok(runs.into_iter().map(Into::into).collect())
+    }
+
+    /// Queries the runs of divergence investigation campaigns, most recent
+    /// first. Campaigns are configured with the `campaigns` option.
+    async fn campaign_runs(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Restricts the query to the runs of the campaign with the given name.")]
+        campaign: Option<String>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::CampaignRun>> {
+        let runs = ctx_data(ctx)
+            .store
+            .campaign_runs(campaign.as_deref(), limit)
+            .await?;
+
+        Ok(runs.into_iter().map(Into::into).collect())
+    }
+
+    /// Queries the PoI backfills that were requested with `backfillPois`,
+    /// most recent first.
+    async fn poi_backfills(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default,
+            desc = "Restricts the query to these given subgraph deployments (by IPFS CID)."
+        )]
+        deployments: Vec<IpfsCid>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::PoiBackfill>> {
+        check_list_input(ctx, "deployments", &deployments)?;
+        let backfills = ctx_data(ctx)
+            .store
+            .poi_backfills(&deployments, limit)
+            .await?;
+
+        Ok(backfills.into_iter().map(Into::into).collect())
+    }
+
+    /// Queries the queries that indexers failed to respond to, most recent
+    /// first. Requires the `operator` permission level, as responses may
+    /// contain sensitive data.
+    async fn failed_queries(
+        &self,
+        ctx: &Context<'_>,
+        indexer_address: Option<IndexerAddress>,
+        #[graphql(
+            desc = "Restricts the query to failed queries with this name, e.g. `indexingStatuses`."
+        )]
+        query_name: Option<String>,
+        #[graphql(
+            default = 100,
+            validator(maximum = 250),
+            desc = "Upper limit on the number of shown results."
+        )]
+        limit: u16,
+    ) -> ApiResult<Vec<api_types::FailedQuery>> {
+        require_permission_level(ctx, ApiKeyPermissionLevel::Operator).await?;
+
+        let failed_queries = ctx_data(ctx)
+            .store
+            .failed_queries(indexer_address.as_ref(), query_name.as_deref(), limit)
+            .await?;
+
+        Ok(failed_queries.into_iter().map(Into::into).collect())
+    }
+
+    /// Queries the event log, oldest events first. Pass the ID of the last
+    /// event seen as `afterId` to page through it.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] mut filter: inputs::EventsQuery,
+    ) -> ApiResult<Vec<models::Event>> {
+        check_list_input(ctx, "filter.kinds", &filter.kinds)?;
+        filter.limit.get_or_insert(DEFAULT_EVENTS_LIMIT);
+        Ok(ctx_data(ctx).store.events(filter).await?)
+    }
+
     /// Returns all networks known to Graphix. Subgraphs indexing other networks
     /// won't be available in this Graphix database.
-    async fn networks(&self, ctx: &Context<'_>) -> Result<Vec<api_types::Network>> {
+    async fn networks(&self, ctx: &Context<'_>) -> ApiResult<Vec<api_types::Network>> {
         let ctx_data = ctx_data(ctx);
         let networks = ctx_data.store.networks().await?;
 
         Ok(networks.into_iter().map(Into::into).collect())
     }
+
+    // Federation entities
+    // -------------------
+    // Reference resolvers for the `@key` fields of `Indexer`,
+    // `SubgraphDeployment` and `ProofOfIndexing`, so that a federation gateway
+    // can extend them with fields of other subgraphs, e.g. the network
+    // subgraph, and resolve them from Graphix.
+
+    #[graphql(entity)]
+    async fn find_indexer_by_address(
+        &self,
+        ctx: &Context<'_>,
+        address: IndexerAddress,
+    ) -> ApiResult<Option<api_types::Indexer>> {
+        let filter = inputs::IndexersQuery {
+            address: Some(address),
+            tag: None,
+            limit: Some(1),
+            include_archived: true,
+        };
+        let indexers = ctx_data(ctx).store.indexers(filter).await?;
+
+        Ok(indexers.into_iter().next().map(Into::into))
+    }
+
+    /// Subgraph deployments that are indexed on several networks resolve to
+    /// one of them.
+    #[graphql(entity)]
+    async fn find_subgraph_deployment_by_cid(
+        &self,
+        ctx: &Context<'_>,
+        cid: IpfsCid,
+    ) -> ApiResult<Option<api_types::SubgraphDeployment>> {
+        let filter = inputs::SgDeploymentsQuery {
+            network_name: None,
+            name: None,
+            ipfs_cid: Some(cid),
+            limit: Some(1),
+        };
+        let deployments = ctx_data(ctx).store.sg_deployments(filter).await?;
+
+        Ok(deployments.into_iter().next().map(Into::into))
+    }
+
+    /// Identical PoIs of several indexers resolve to the most recently
+    /// collected one.
+    #[graphql(entity)]
+    async fn find_proof_of_indexing_by_hash(
+        &self,
+        ctx: &Context<'_>,
+        hash: PoiBytes,
+    ) -> ApiResult<Option<api_types::ProofOfIndexing>> {
+        let pois = ctx_data(ctx).store.pois_by_value(&hash, None).await?;
+
+        Ok(pois.into_iter().next().map(|(poi, _live)| poi.into()))
+    }
 }
 
-async fn live_pois(
-    ctx: &Context<'_>,
+/// How many indexers agree with each live PoI of an indexer, along with that
+/// PoI. Shared by the GraphQL and gRPC APIs.
+pub(crate) async fn poi_agreement_ratios(
+    state: &GraphixState,
     indexer_address: IndexerAddress,
-) -> Result<Vec<api_types::ProofOfIndexing>> {
-    let ctx_data = ctx_data(ctx);
+    network: Option<&str>,
+    consensus_mode: api_types::ConsensusMode,
+) -> ApiResult<Vec<(api_types::ProofOfIndexing, api_types::PoiAgreementRatio)>> {
+    let (indexer_pois, tallies) = live_pois_with_tallies(state, indexer_address, network).await?;
 
+    let mut agreement_ratios = Vec::new();
+
+    for poi in indexer_pois {
+        let tally = &tallies
+            .get(&poi.model.sg_deployment_id)
+            .context("inconsistent pois table, no pois for deployment")?
+            .tally;
+        let total_indexers = tally.total_indexers();
+        let consensus_poi = tally.consensus(consensus_mode);
+        // Includes the indexer's own PoI.
+        let n_agreeing_indexers = tally.indexers(&poi.hash());
+        let n_disagreeing_indexers = total_indexers - n_agreeing_indexers;
+
+        let ratio = api_types::PoiAgreementRatio {
+            poi_id: poi.model.id,
+            total_indexers,
+            n_agreeing_indexers,
+            n_disagreeing_indexers,
+            has_consensus: consensus_poi.is_some(),
+            in_consensus: consensus_poi == Some(poi.hash()),
+            disagreement_stake_weight: tally.disagreement_stake_weight(&poi.hash()),
+        };
+
+        agreement_ratios.push((poi, ratio));
+    }
+
+    Ok(agreement_ratios)
+}
+
+/// The live PoIs of an indexer, and the tallies of all live PoIs of the same
+/// subgraph deployments (including the indexer's own) by deployment ID. The
+/// same IPFS CID on another network is a different deployment.
+async fn live_pois_with_tallies(
+    ctx_data: &GraphixState,
+    indexer_address: IndexerAddress,
+    network: Option<&str>,
+) -> ApiResult<(
+    Vec<api_types::ProofOfIndexing>,
+    HashMap<models::IntId, poi_agreement::LivePoiTally>,
+)> {
+    let indexer_pois = live_pois(ctx_data, indexer_address, network).await?;
+
+    let mut deployment_ids: Vec<models::IntId> = indexer_pois
+        .iter()
+        .map(|poi| poi.model.sg_deployment_id)
+        .collect();
+    deployment_ids.sort();
+    deployment_ids.dedup();
+    let tallies = poi_agreement::live_poi_tallies(ctx_data, &deployment_ids).await?;
+
+    Ok((indexer_pois, tallies))
+}
+
+async fn live_pois(
+    ctx_data: &GraphixState,
+    indexer_address: IndexerAddress,
+    network: Option<&str>,
+) -> ApiResult<Vec<api_types::ProofOfIndexing>> {
     let pois = ctx_data
         .store
-        .live_pois(Some(&indexer_address), None, None, None)
+        .live_pois(Some(&indexer_address), network, None, None, None, None)
         .await?;
 
     Ok(pois.into_iter().map(Into::into).collect())
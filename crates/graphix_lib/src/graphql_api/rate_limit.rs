@@ -0,0 +1,103 @@
+//! Per-API-key rate limiting of GraphQL API requests. Each API key gets its own
+//! token bucket, and all requests without an API key share one.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// The bucket shared by all requests without a (valid) API key.
+pub const ANONYMOUS_BUCKET: &str = "anonymous";
+
+/// Token buckets that allow bursts of up to `requests_per_minute` requests,
+/// refilling at the same rate.
+#[derive(Debug, Default)]
+pub struct ApiRateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    requests_per_minute: u32,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl ApiRateLimiter {
+    /// Takes a token from the bucket called `key`, returning `false` if there
+    /// are none left. A limit of `None` means unlimited.
+    pub fn try_acquire(&self, key: &str, requests_per_minute: Option<u32>, now: Instant) -> bool {
+        let Some(requests_per_minute) = requests_per_minute else {
+            return true;
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key.to_string()).or_insert_with(|| Bucket {
+            requests_per_minute,
+            tokens: requests_per_minute as f64,
+            refilled_at: now,
+        });
+
+        // Start over if the limit was changed since the last request.
+        if bucket.requests_per_minute != requests_per_minute {
+            *bucket = Bucket {
+                requests_per_minute,
+                tokens: requests_per_minute as f64,
+                refilled_at: now,
+            };
+        }
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * requests_per_minute as f64 / 60.0)
+            .min(requests_per_minute as f64);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn unlimited_without_limit() {
+        let limiter = ApiRateLimiter::default();
+        let now = Instant::now();
+        assert!((0..1000).all(|_| limiter.try_acquire("key", None, now)));
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = ApiRateLimiter::default();
+        let now = Instant::now();
+
+        assert!((0..60).all(|_| limiter.try_acquire("key", Some(60), now)));
+        assert!(!limiter.try_acquire("key", Some(60), now));
+        // Other buckets are unaffected.
+        assert!(limiter.try_acquire(ANONYMOUS_BUCKET, Some(60), now));
+
+        // One token per second.
+        let later = now + Duration::from_secs(1);
+        assert!(limiter.try_acquire("key", Some(60), later));
+        assert!(!limiter.try_acquire("key", Some(60), later));
+    }
+
+    #[test]
+    fn changing_the_limit_resets_the_bucket() {
+        let limiter = ApiRateLimiter::default();
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire("key", Some(1), now));
+        assert!(!limiter.try_acquire("key", Some(1), now));
+        assert!(limiter.try_acquire("key", Some(2), now));
+        assert!(limiter.try_acquire("key", Some(2), now));
+        assert!(!limiter.try_acquire("key", Some(2), now));
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use async_graphql::{Context, Subscription};
+use futures::{stream, Stream, StreamExt};
+use graphix_common_types::{inputs, EventKind, IpfsCid};
+use graphix_store::models;
+use tracing::*;
+
+use super::{check_list_input, ctx_data, GraphixApiError};
+
+/// How often subscriptions poll the event log for new events.
+const EVENTS_POLLING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How many events subscriptions fetch from the event log at once.
+const EVENTS_PAGE_SIZE: u16 = 100;
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Streams events as they're appended to the event log, starting with the
+    /// first event after the subscription was made.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default,
+            desc = "Restricts the subscription to events of these kinds. All kinds by default."
+        )]
+        kinds: Vec<EventKind>,
+        #[graphql(desc = "Restricts the subscription to events about this subgraph deployment.")]
+        deployment: Option<IpfsCid>,
+    ) -> Result<impl Stream<Item = models::Event>, GraphixApiError> {
+        check_list_input(ctx, "kinds", &kinds)?;
+
+        let store = ctx_data(ctx).store.clone();
+        let after_id = store.latest_event_id().await?.unwrap_or_default();
+
+        // Waits before each poll once caught up with the event log.
+        let pages = stream::unfold((after_id, false), move |(after_id, caught_up)| {
+            let store = store.clone();
+            let filter = inputs::EventsQuery {
+                kinds: kinds.clone(),
+                deployment: deployment.clone(),
+                after_id: Some(after_id),
+                limit: Some(EVENTS_PAGE_SIZE),
+            };
+
+            async move {
+                if caught_up {
+                    tokio::time::sleep(EVENTS_POLLING_INTERVAL).await;
+                }
+
+                match store.events(filter).await {
+                    Ok(events) => {
+                        let caught_up = events.len() < EVENTS_PAGE_SIZE as usize;
+                        let after_id = events.last().map_or(after_id, |event| event.id);
+                        Some((events, (after_id, caught_up)))
+                    }
+                    Err(err) => {
+                        error!(error = %err, "Failed to poll the event log");
+                        Some((vec![], (after_id, true)))
+                    }
+                }
+            }
+        });
+
+        Ok(pages.flat_map(stream::iter))
+    }
+}
@@ -0,0 +1,277 @@
+//! The gRPC API, for automations that prefer a typed contract over assembling
+//! GraphQL queries. It's defined in `proto/graphix.proto`, covers a subset of
+//! the GraphQL API with the same semantics, and is served on the same port,
+//! from the same [`GraphixState`].
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use graphix_common_types::inputs::BlockRange;
+use graphix_common_types::{ApiKeyPermissionLevel, IndexerAddress, IpfsCid, PoiBytes};
+use graphix_store::models::{ApiKey, DivergenceInvestigationRequest};
+use tonic::{Request, Response, Status};
+
+use crate::graphql_api::api_types::ProofOfIndexing;
+use crate::graphql_api::{
+    exceeded_rate_limit, poi_agreement_ratios, GraphixApiError, GraphixState,
+    GRAPHIX_API_KEY_HEADER_NAME,
+};
+use crate::metrics;
+
+/// The code generated from `proto/graphix.proto`.
+pub mod proto {
+    tonic::include_proto!("graphix.v1");
+}
+
+use self::proto::graphix_server::{Graphix, GraphixServer};
+
+/// The same limits as for the `liveProofsOfIndexing` GraphQL query.
+const DEFAULT_LIVE_POIS_LIMIT: u32 = 100;
+const MAX_LIVE_POIS_LIMIT: u32 = 250;
+
+pub struct GrpcApi {
+    state: Arc<GraphixState>,
+}
+
+impl GrpcApi {
+    pub fn new(state: Arc<GraphixState>) -> Self {
+        Self { state }
+    }
+
+    /// Routes the gRPC API for [`axum_router`](crate::graphql_api::axum_router).
+    pub fn into_axum_router(self) -> axum::Router {
+        tonic::service::Routes::new(GraphixServer::new(self)).into_axum_router()
+    }
+
+    /// Reads the API key of the request, and counts the request against its
+    /// rate limit.
+    async fn authenticate<T>(&self, request: &Request<T>) -> Result<Option<ApiKey>, Status> {
+        let api_key = match request
+            .metadata()
+            .get(GRAPHIX_API_KEY_HEADER_NAME.to_lowercase())
+        {
+            None => None,
+            Some(value) => {
+                let value = value
+                    .to_str()
+                    .map_err(|err| Status::unauthenticated(format!("Invalid API key: {err}")))?;
+                let api_key = ApiKey::from_str(value)
+                    .map_err(|err| Status::unauthenticated(format!("Invalid API key: {err}")))?;
+                Some(api_key)
+            }
+        };
+
+        match exceeded_rate_limit(&self.state, api_key.as_ref()).await {
+            Ok(None) => Ok(api_key),
+            Ok(Some(limit)) => Err(Status::resource_exhausted(format!(
+                "At most {limit} requests per minute are allowed"
+            ))),
+            Err(err) => Err(Status::internal(format!("{err:#}"))),
+        }
+    }
+
+    async fn require_permission_level(
+        &self,
+        api_key: Option<&ApiKey>,
+        required_permission_level: ApiKeyPermissionLevel,
+    ) -> Result<(), Status> {
+        let api_key = api_key.ok_or_else(|| Status::unauthenticated("No API key provided"))?;
+        let permission_level = self
+            .state
+            .store
+            .permission_level(api_key)
+            .await
+            .map_err(|err| Status::internal(format!("{err:#}")))?;
+
+        match permission_level {
+            None => Err(Status::unauthenticated("No permission level for API key")),
+            Some(level) if level < required_permission_level => {
+                Err(Status::permission_denied(format!(
+                    "Insufficient permission level for API key: expected {required_permission_level:?}, got {level:?}"
+                )))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    async fn proof_of_indexing(
+        &self,
+        poi: &ProofOfIndexing,
+    ) -> Result<proto::ProofOfIndexing, Status> {
+        let deployment = poi.deployment(&self.state).await.map_err(status)?;
+        let block = poi.block(&self.state).await.map_err(status)?;
+        let network = block.network(&self.state).await.map_err(status)?;
+        let indexer = poi.indexer(&self.state).await.map_err(status)?;
+
+        Ok(proto::ProofOfIndexing {
+            hash: poi.hash().to_string(),
+            deployment: deployment.cid().to_string(),
+            network: network.name().to_string(),
+            indexer_address: indexer.address().to_string(),
+            block_number: block.number(),
+            block_hash: block.hash().to_string(),
+            epoch: poi.model.epoch,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl Graphix for GrpcApi {
+    async fn list_live_pois(
+        &self,
+        request: Request<proto::ListLivePoisRequest>,
+    ) -> Result<Response<proto::ListLivePoisResponse>, Status> {
+        self.authenticate(&request).await?;
+        let request = request.into_inner();
+
+        let max_items = self.state.config().max_list_input_items as usize;
+        if request.deployments.len() > max_items {
+            return Err(Status::invalid_argument(format!(
+                "`deployments` can have at most {max_items} items"
+            )));
+        }
+        let deployments = request
+            .deployments
+            .iter()
+            .map(|deployment| {
+                IpfsCid::from_str(deployment).map_err(|err| {
+                    Status::invalid_argument(format!("Invalid IPFS CID `{deployment}`: {err}"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let limit = request.limit.unwrap_or(DEFAULT_LIVE_POIS_LIMIT);
+        if limit > MAX_LIVE_POIS_LIMIT {
+            return Err(Status::invalid_argument(format!(
+                "`limit` can be at most {MAX_LIVE_POIS_LIMIT}"
+            )));
+        }
+        let block_range =
+            (request.start_block.is_some() || request.end_block.is_some()).then_some(BlockRange {
+                start: request.start_block,
+                end: request.end_block,
+            });
+
+        let pois = self
+            .state
+            .store
+            .live_pois(
+                None,
+                request.network.as_deref(),
+                Some(&deployments),
+                block_range,
+                request.indexer_tag.as_deref(),
+                Some(limit as u16),
+            )
+            .await
+            .map_err(|err| status(err.into()))?;
+
+        let mut response = proto::ListLivePoisResponse { pois: vec![] };
+        for poi in pois {
+            response
+                .pois
+                .push(self.proof_of_indexing(&poi.into()).await?);
+        }
+
+        Ok(Response::new(response))
+    }
+
+    async fn get_poi_agreement_ratios(
+        &self,
+        request: Request<proto::GetPoiAgreementRatiosRequest>,
+    ) -> Result<Response<proto::GetPoiAgreementRatiosResponse>, Status> {
+        self.authenticate(&request).await?;
+        let request = request.into_inner();
+
+        let indexer_address = IndexerAddress::from_str(&request.indexer_address)
+            .map_err(|err| Status::invalid_argument(format!("Invalid indexer address: {err}")))?;
+        let ratios = poi_agreement_ratios(
+            &self.state,
+            indexer_address,
+            request.network.as_deref(),
+            Default::default(),
+        )
+        .await
+        .map_err(status)?;
+
+        let mut response = proto::GetPoiAgreementRatiosResponse { ratios: vec![] };
+        for (poi, ratio) in ratios {
+            response.ratios.push(proto::PoiAgreementRatio {
+                poi: Some(self.proof_of_indexing(&poi).await?),
+                total_indexers: ratio.total_indexers,
+                agreeing_indexers: ratio.n_agreeing_indexers,
+                disagreeing_indexers: ratio.n_disagreeing_indexers,
+                has_consensus: ratio.has_consensus,
+                in_consensus: ratio.in_consensus,
+            });
+        }
+
+        Ok(Response::new(response))
+    }
+
+    async fn launch_divergence_investigation(
+        &self,
+        request: Request<proto::LaunchDivergenceInvestigationRequest>,
+    ) -> Result<Response<proto::LaunchDivergenceInvestigationResponse>, Status> {
+        let api_key = self.authenticate(&request).await?;
+        if self.state.config().read_only {
+            return Err(Status::failed_precondition(
+                "Graphix is running in read-only mode",
+            ));
+        }
+        self.require_permission_level(api_key.as_ref(), ApiKeyPermissionLevel::Operator)
+            .await?;
+        let request = request.into_inner();
+
+        if !(2..=4).contains(&request.pois.len()) {
+            return Err(Status::invalid_argument(
+                "Divergence investigations compare two to four PoIs",
+            ));
+        }
+        let pois = request
+            .pois
+            .iter()
+            .map(|poi| {
+                PoiBytes::from_str(poi)
+                    .map_err(|err| Status::invalid_argument(format!("Invalid PoI `{poi}`: {err}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let investigation = DivergenceInvestigationRequest {
+            pois,
+            query_block_caches: request.query_block_caches.unwrap_or(true),
+            query_eth_call_caches: request.query_eth_call_caches.unwrap_or(true),
+            query_entity_changes: request.query_entity_changes.unwrap_or(true),
+            bisect_graft_bases: request.bisect_graft_bases.unwrap_or(false),
+        };
+        let investigation =
+            serde_json::to_value(investigation).map_err(|err| Status::internal(err.to_string()))?;
+        let uuid = self
+            .state
+            .store
+            .create_divergence_investigation_request(investigation, request.priority.unwrap_or(0))
+            .await
+            .map_err(|err| status(err.into()))?;
+        metrics().divergence_investigations_launched.inc();
+
+        Ok(Response::new(
+            proto::LaunchDivergenceInvestigationResponse {
+                uuid: uuid.to_string(),
+            },
+        ))
+    }
+}
+
+/// Maps GraphQL API errors to the closest gRPC status codes.
+fn status(err: GraphixApiError) -> Status {
+    let message = err.message();
+    match err {
+        GraphixApiError::NotFound(_) => Status::not_found(message),
+        GraphixApiError::Unauthorized(_) => Status::unauthenticated(message),
+        GraphixApiError::InvalidInput(_) | GraphixApiError::ListInputTooLarge(_) => {
+            Status::invalid_argument(message)
+        }
+        GraphixApiError::ReadOnly(_) => Status::failed_precondition(message),
+        GraphixApiError::UpstreamIndexerError(_) => Status::unavailable(message),
+        GraphixApiError::Database(_) => Status::internal(message),
+    }
+}
@@ -0,0 +1,115 @@
+//! Divergence is not the only way for an indexer to fail: some are simply far
+//! behind the chain head. The head of each chain is taken from its JSON-RPC
+//! endpoint ([`crate::config::ChainConfig::rpc_url`]) if it has one, or else
+//! from the most advanced indexer, and stored on every polling cycle, so that
+//! the indexing lag of every indexer and subgraph deployment can be measured
+//! against it.
+
+use std::collections::HashMap;
+
+use futures::future::join_all;
+use graphix_common_types::SubgraphHealth;
+use graphix_store::models::IndexingLag;
+use graphix_store::Store;
+use tracing::*;
+
+use crate::block_timestamps::RpcClient;
+use crate::config::Config;
+use crate::PrometheusMetrics;
+
+/// Refreshes the stored chain heads from the latest indexing statuses and the
+/// chains' JSON-RPC endpoints, and updates the indexing lag metrics.
+pub async fn update_indexing_lags(
+    store: &Store,
+    config: &Config,
+    metrics: &PrometheusMetrics,
+) -> anyhow::Result<()> {
+    let reported_heads = store.reported_chain_heads().await?;
+    let heads = join_all(
+        reported_heads
+            .into_iter()
+            .map(|(network, reported)| chain_head(config, network, reported)),
+    )
+    .await;
+    store.write_chain_heads(&heads).await?;
+
+    metrics.set_indexing_lags(&store.indexing_lags(None, None).await?);
+    Ok(())
+}
+
+/// The chain head of `network` according to its JSON-RPC endpoint, if it has
+/// one, unless the most advanced indexer, at block `reported`, is ahead of it.
+async fn chain_head(config: &Config, network: String, reported: i64) -> (String, i64) {
+    let Some(url) = config
+        .chains
+        .get(&network)
+        .and_then(|chain| chain.rpc_url.clone())
+    else {
+        return (network, reported);
+    };
+
+    match RpcClient::new(url).block_number().await {
+        Ok(head) => (network, head.max(reported)),
+        Err(error) => {
+            warn!(
+                %network, %error,
+                "Failed to query the chain head, using the most advanced indexer's"
+            );
+            (network, reported)
+        }
+    }
+}
+
+/// The most blocks that any subgraph deployment of each indexer is behind on
+/// each network, by indexer address and network. Failed deployments are left
+/// out, as they don't progress anyway, and are reported as such.
+pub fn max_blocks_behind(lags: &[IndexingLag]) -> HashMap<(String, String), i64> {
+    let mut max_lags = HashMap::new();
+    for lag in lags
+        .iter()
+        .filter(|lag| lag.health != SubgraphHealth::Failed)
+    {
+        let key = (lag.indexer_address.to_string(), lag.network.clone());
+        let max_lag = max_lags.entry(key).or_insert(0);
+        *max_lag = lag.blocks_behind().max(*max_lag);
+    }
+    max_lags
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use graphix_common_types::IndexerAddress;
+
+    use super::*;
+
+    fn lag(indexer: u8, network: &str, health: SubgraphHealth, latest: i64) -> IndexingLag {
+        IndexingLag {
+            indexer_id: indexer.into(),
+            indexer_address: IndexerAddress::from([indexer; 20]),
+            sg_deployment_id: 1,
+            network: network.to_string(),
+            health,
+            latest_block_number: latest,
+            chain_head_block_number: 100,
+            updated_at: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn failed_deployments_dont_count_towards_max_lag() {
+        let lags = [
+            lag(1, "mainnet", SubgraphHealth::Healthy, 90),
+            lag(1, "mainnet", SubgraphHealth::Unhealthy, 40),
+            lag(1, "mainnet", SubgraphHealth::Failed, 10),
+            lag(1, "gnosis", SubgraphHealth::Healthy, 120),
+            lag(2, "mainnet", SubgraphHealth::Failed, 10),
+        ];
+
+        let max_lags = max_blocks_behind(&lags);
+        let address = IndexerAddress::from([1; 20]).to_string();
+        assert_eq!(max_lags.len(), 2);
+        assert_eq!(max_lags[&(address.clone(), "mainnet".to_string())], 60);
+        assert_eq!(max_lags[&(address, "gnosis".to_string())], 0);
+    }
+}
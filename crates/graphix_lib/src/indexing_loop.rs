@@ -1,28 +1,41 @@
 //! Logic related to the main indexing loop performed by Graphix:
 //!  1. Query `indexingStatuses` for all indexers.
 //!  2. Query PoIs for recent common blocks across all indexers.
-//!  3. Store the PoIs in the database.
+//!  3. Store the PoIs of each indexer in the database as soon as it responds.
 
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use graphix_common_types::{GraphNodeCollectedVersion, IpfsCid};
 use graphix_indexer_client::{
-    IndexerClient, IndexerId, IndexingStatus, PoiRequest, ProofOfIndexing,
+    classify_error, IndexerClient, IndexerId, IndexingStatus, PoiRequest, ProofOfIndexing,
+    WithIndexer,
 };
+use graphix_network_sg_client::{ClosedAllocationPoi, EpochBlockOracleClient};
+use graphix_store::models::{NewIndexerError, NewNetworkEpoch, NewOnchainPoi};
+use graphix_store::{PoiLiveness, Store};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 use tracing::*;
 
-use crate::block_choice::BlockChoicePolicy;
+use crate::block_choice::BlockChoicePolicies;
+use crate::config::NetworkSubgraphConfig;
 use crate::PrometheusMetrics;
 
-/// Queries all `indexingStatuses` for all the given indexers.
+/// How many of the most recently closed allocations to check for on-chain PoIs
+/// on every iteration. Allocations that were already stored are skipped.
+const ONCHAIN_POIS_LIMIT: u32 = 1000;
+
+/// Queries all `indexingStatuses` for all the given indexers. Also returns the
+/// errors of failed queries, so they can be stored for debugging.
 #[instrument(skip_all)]
 pub async fn query_indexing_statuses(
     indexers: &[Arc<dyn IndexerClient>],
     metrics: &PrometheusMetrics,
-) -> Vec<IndexingStatus> {
+) -> (Vec<IndexingStatus>, Vec<WithIndexer<anyhow::Error>>) {
     let indexers_count = indexers.len();
     debug!(
         indexers_count = indexers_count,
@@ -39,6 +52,7 @@ pub async fn query_indexing_statuses(
     assert_eq!(indexing_statuses_results.len(), indexers.len());
 
     let mut indexing_statuses = vec![];
+    let mut errors = vec![];
     let mut query_successes = 0;
     let mut query_failures = 0;
 
@@ -73,6 +87,7 @@ pub async fn query_indexing_statuses(
                     %error,
                     "Failed to query indexing statuses"
                 );
+                errors.push(WithIndexer::new(indexer, error));
             }
         }
     }
@@ -87,7 +102,203 @@ pub async fn query_indexing_statuses(
         "Finished querying indexing statuses for all indexers"
     );
 
-    indexing_statuses
+    (indexing_statuses, errors)
+}
+
+/// Classifies failed requests to indexers, so that they can be counted by
+/// error class for each indexer.
+pub fn classify_indexer_errors<'a>(
+    errors: impl IntoIterator<Item = (&'a Arc<dyn IndexerClient>, &'a anyhow::Error)>,
+) -> Vec<WithIndexer<NewIndexerError>> {
+    errors
+        .into_iter()
+        .map(|(indexer, error)| {
+            WithIndexer::new(
+                indexer.clone(),
+                NewIndexerError {
+                    class: classify_error(error),
+                    message: format!("{error:#}"),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Queries the PoIs that indexers submitted on-chain when closing their most
+/// recent allocations, from all network subgraphs with
+/// [`NetworkSubgraphConfig::collect_onchain_pois`] enabled, and resolves the
+/// blocks they refer to, see [`resolve_onchain_poi_blocks`].
+#[instrument(skip_all)]
+pub async fn query_onchain_pois(
+    store: &Store,
+    network_subgraphs: &[NetworkSubgraphConfig],
+    metrics: &PrometheusMetrics,
+) -> Vec<NewOnchainPoi> {
+    let mut closed_allocation_pois = vec![];
+
+    for config in network_subgraphs
+        .iter()
+        .filter(|config| config.collect_onchain_pois)
+    {
+        let network_subgraph = match config.client(metrics) {
+            Ok(network_subgraph) => network_subgraph,
+            Err(error) => {
+                warn!(
+                    endpoint = %config.redacted_endpoint(),
+                    %error,
+                    "Invalid network subgraph endpoint"
+                );
+                continue;
+            }
+        };
+
+        match network_subgraph
+            .closed_allocation_pois(Some(ONCHAIN_POIS_LIMIT))
+            .await
+        {
+            Ok(pois) => closed_allocation_pois.extend(pois),
+            Err(error) => {
+                warn!(
+                    endpoint = %config.redacted_endpoint(),
+                    name = ?config.name,
+                    %error,
+                    "Failed to query on-chain PoIs"
+                );
+            }
+        }
+    }
+
+    let onchain_pois = match resolve_onchain_poi_blocks(store, closed_allocation_pois).await {
+        Ok(onchain_pois) => onchain_pois,
+        Err(error) => {
+            warn!(%error, "Failed to resolve the blocks of on-chain PoIs");
+            vec![]
+        }
+    };
+
+    info!(
+        onchain_pois = onchain_pois.len(),
+        "Finished querying on-chain PoIs"
+    );
+
+    onchain_pois
+}
+
+/// On-chain PoIs refer to the first block of the epoch in which the
+/// allocation was closed, on the network of its subgraph deployment, which is
+/// looked up in the epochs synced from the epoch block oracle subgraph. PoIs
+/// whose block isn't known (yet), e.g. because the deployment is unknown,
+/// known on several networks, or its network's epochs aren't synced, are left
+/// out; they're queried again in later polling cycles.
+async fn resolve_onchain_poi_blocks(
+    store: &Store,
+    closed_allocation_pois: Vec<ClosedAllocationPoi>,
+) -> anyhow::Result<Vec<NewOnchainPoi>> {
+    let mut pois = vec![];
+    for poi in closed_allocation_pois {
+        match poi.deployment.parse::<IpfsCid>() {
+            Ok(deployment) => pois.push((deployment, poi)),
+            Err(error) => warn!(%error, "Received bad on-chain PoI; ignoring"),
+        }
+    }
+
+    let deployments: Vec<IpfsCid> = pois
+        .iter()
+        .map(|(deployment, _)| deployment.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let epochs: Vec<i64> = pois
+        .iter()
+        .filter_map(|(_, poi)| poi.epoch.try_into().ok())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    // `None` if the deployment's networks disagree on the start block.
+    let mut start_blocks: HashMap<(IpfsCid, i64), Option<i64>> = HashMap::new();
+    for (deployment, epoch, block_number) in store
+        .sg_deployment_epoch_start_blocks(&deployments, &epochs)
+        .await?
+    {
+        start_blocks
+            .entry((deployment, epoch))
+            .and_modify(|known| {
+                if *known != Some(block_number) {
+                    *known = None;
+                }
+            })
+            .or_insert(Some(block_number));
+    }
+
+    let mut onchain_pois = vec![];
+    let mut unresolved = 0;
+    for (deployment, poi) in pois {
+        let Ok(epoch) = i64::try_from(poi.epoch) else {
+            warn!(epoch = poi.epoch, "Received bad on-chain PoI; ignoring");
+            continue;
+        };
+        let Some(Some(block_number)) = start_blocks.get(&(deployment.clone(), epoch)).copied()
+        else {
+            unresolved += 1;
+            continue;
+        };
+        onchain_pois.push(NewOnchainPoi {
+            allocation_id: poi.allocation_id,
+            indexer_address: poi.indexer_address,
+            deployment,
+            poi: poi.poi,
+            epoch,
+            block_number,
+            network_subgraph: poi.network_subgraph,
+        });
+    }
+    if unresolved > 0 {
+        debug!(
+            unresolved,
+            "Skipped on-chain PoIs whose block on their deployment's network isn't known"
+        );
+    }
+
+    Ok(onchain_pois)
+}
+
+/// Syncs the start blocks of all protocol epochs that are newer than the
+/// stored ones from the epoch block oracle subgraph at `endpoint`, for all
+/// networks with a known CAIP-2 ID.
+#[instrument(skip_all)]
+pub async fn sync_network_epochs(store: &Store, endpoint: &str) -> anyhow::Result<()> {
+    let client = EpochBlockOracleClient::new(endpoint.parse()?);
+    let mut new_epochs = vec![];
+
+    for network in store.networks().await? {
+        let Some(caip2) = network.caip2.as_deref() else {
+            continue;
+        };
+        let after_epoch = store
+            .latest_network_epoch(network.id)
+            .await?
+            .map(u64::try_from)
+            .transpose()?;
+
+        match client.epoch_start_blocks(caip2, after_epoch).await {
+            Ok(epochs) => {
+                for epoch in epochs {
+                    new_epochs.push(NewNetworkEpoch {
+                        network_id: network.id,
+                        epoch: epoch.epoch.try_into()?,
+                        start_block_number: epoch.block_number.try_into()?,
+                    });
+                }
+            }
+            Err(error) => {
+                warn!(network = %network.name, %error, "Failed to query epoch start blocks");
+            }
+        }
+    }
+
+    info!(epochs = new_epochs.len(), "Finished syncing network epochs");
+
+    store.write_network_epochs(&new_epochs).await
 }
 
 /// Queries all `indexers` for their `graph-node` versions.
@@ -142,53 +353,170 @@ pub async fn query_graph_node_versions(
     versions
 }
 
+/// Queries the PoIs of all indexers for recent common blocks, and waits for
+/// all of them to respond.
 #[instrument(skip_all)]
 pub async fn query_proofs_of_indexing(
     indexing_statuses: Vec<IndexingStatus>,
-    block_choice_policy: BlockChoicePolicy,
+    block_choice_policies: &BlockChoicePolicies,
 ) -> Vec<ProofOfIndexing> {
     info!("Query POIs for recent common blocks across indexers");
 
+    poi_requests(&indexing_statuses, block_choice_policies)
+        .into_iter()
+        .map(|(indexer, poi_requests)| query_indexer_pois(indexer, poi_requests))
+        .collect::<FuturesUnordered<_>>()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+}
+
+/// Like [`query_proofs_of_indexing`], but stores the PoIs of each indexer as
+/// soon as it responds, so that a slow indexer doesn't hold back the others.
+/// Indexers that don't respond within `deadline` are skipped until the next
+/// polling cycle. When `shutdown` is signaled, the PoIs stored so far are kept
+/// and the remaining indexers are no longer waited for.
+///
+/// Returns the PoIs of all indexers that responded in time.
+#[instrument(skip_all)]
+pub async fn collect_proofs_of_indexing(
+    store: &Store,
+    indexing_statuses: Vec<IndexingStatus>,
+    block_choice_policies: &BlockChoicePolicies,
+    deadline: Duration,
+    shutdown: &mut watch::Receiver<bool>,
+    metrics: &PrometheusMetrics,
+) -> Vec<ProofOfIndexing> {
+    info!(
+        deadline_seconds = deadline.as_secs(),
+        "Collect POIs for recent common blocks across indexers"
+    );
+
+    let mut tasks = JoinSet::new();
+    for (indexer, poi_requests) in poi_requests(&indexing_statuses, block_choice_policies) {
+        tasks.spawn(async move {
+            let pois =
+                tokio::time::timeout(deadline, query_indexer_pois(indexer.clone(), poi_requests))
+                    .await;
+            (indexer, pois)
+        });
+    }
+
+    let mut collected_pois = vec![];
+    loop {
+        let (indexer, pois) = tokio::select! {
+            next = tasks.join_next() => match next {
+                Some(Ok(next)) => next,
+                Some(Err(error)) => {
+                    error!(%error, "POI collection task failed");
+                    continue;
+                }
+                None => break,
+            },
+            _ = shutdown.wait_for(|shutdown| *shutdown) => {
+                info!(
+                    pending_indexers = tasks.len(),
+                    "Shutting down, no longer waiting for POIs"
+                );
+                break;
+            }
+        };
+
+        let Ok(pois) = pois else {
+            warn!(
+                id = %indexer.address_string(),
+                "Indexer didn't respond with POIs before the deadline"
+            );
+            metrics
+                .poi_deadlines_missed
+                .with_label_values(&[&indexer.address_string()])
+                .inc();
+            continue;
+        };
+        if pois.is_empty() {
+            continue;
+        }
+
+        if let Err(error) = store.write_pois(pois.clone(), PoiLiveness::Live).await {
+            error!(
+                id = %indexer.address_string(), %error,
+                "Failed to write POIs to database"
+            );
+        }
+        collected_pois.extend(pois);
+    }
+
+    collected_pois
+}
+
+async fn query_indexer_pois(
+    indexer: Arc<dyn IndexerClient>,
+    poi_requests: Vec<PoiRequest>,
+) -> Vec<ProofOfIndexing> {
+    let pois = indexer.clone().proofs_of_indexing(poi_requests).await;
+
+    debug!(
+        id = %indexer.address_string(), pois = %pois.len(),
+        "Successfully queried POIs from indexer"
+    );
+
+    pois
+}
+
+/// Chooses a block for each deployment, and returns what each indexer should
+/// be asked for PoIs at those blocks.
+fn poi_requests(
+    indexing_statuses: &[IndexingStatus],
+    block_choice_policies: &BlockChoicePolicies,
+) -> Vec<(Arc<dyn IndexerClient>, Vec<PoiRequest>)> {
     // Identify all indexers
     let indexers = indexing_statuses
         .iter()
         .map(|status| status.indexer.clone())
         .collect::<HashSet<_>>();
 
-    // Identify all deployments
-    let deployments: HashSet<IpfsCid> = HashSet::from_iter(
+    // Identify all deployments. The same IPFS CID can be indexed on several
+    // networks, and PoIs are only comparable within one of them.
+    let deployments: HashSet<(IpfsCid, String)> = HashSet::from_iter(
         indexing_statuses
             .iter()
-            .map(|status| status.deployment.clone()),
+            .map(|status| (status.deployment.clone(), status.network.clone())),
     );
 
     // Group indexing statuses by deployment
-    let statuses_by_deployment: HashMap<IpfsCid, Vec<&IndexingStatus>> =
+    let statuses_by_deployment: HashMap<(IpfsCid, String), Vec<&IndexingStatus>> =
         HashMap::from_iter(deployments.iter().map(|deployment| {
             (
                 deployment.clone(),
                 indexing_statuses
                     .iter()
-                    .filter(|status| status.deployment.eq(deployment))
+                    .filter(|status| {
+                        status.deployment.eq(&deployment.0) && status.network.eq(&deployment.1)
+                    })
                     .collect(),
             )
         }));
 
     // For each deployment, chooose a block on which to query the Poi
-    let latest_blocks: HashMap<IpfsCid, Option<u64>> =
+    let latest_blocks: HashMap<(IpfsCid, String), Option<u64>> =
         HashMap::from_iter(deployments.iter().map(|deployment| {
+            let (_, network) = deployment;
             (
                 deployment.clone(),
                 statuses_by_deployment.get(deployment).and_then(|statuses| {
-                    block_choice_policy.choose_block(statuses.iter().copied())
+                    block_choice_policies
+                        .for_chain(network)
+                        .choose_block(statuses.iter().copied())
                 }),
             )
         }));
 
-    // Fetch POIs for the most recent common blocks
+    // Request POIs for the most recent common blocks
     indexers
-        .iter()
-        .map(|indexer| async {
+        .into_iter()
+        .map(|indexer| {
             let poi_requests = latest_blocks
                 .iter()
                 .filter(|(deployment, &block_number)| {
@@ -197,31 +525,20 @@ pub async fn query_proofs_of_indexing(
                         .expect("bug in matching deployments to latest blocks and indexers")
                         .iter()
                         .any(|status| {
-                            status.indexer.eq(indexer)
+                            status.indexer.eq(&indexer)
                                 && Some(status.latest_block.number) >= block_number
                         })
                 })
-                .filter_map(|(deployment, block_number)| {
+                .filter_map(|((deployment, network), block_number)| {
                     block_number.map(|block_number| PoiRequest {
                         deployment: deployment.clone(),
+                        network: network.clone(),
                         block_number,
                     })
                 })
                 .collect::<Vec<_>>();
 
-            let pois = indexer.clone().proofs_of_indexing(poi_requests).await;
-
-            debug!(
-                id = %indexer.address_string(), pois = %pois.len(),
-                "Successfully queried POIs from indexer"
-            );
-
-            pois
+            (indexer, poi_requests)
         })
-        .collect::<FuturesUnordered<_>>()
-        .collect::<Vec<_>>()
-        .await
-        .into_iter()
-        .flatten()
-        .collect::<Vec<_>>()
+        .collect()
 }
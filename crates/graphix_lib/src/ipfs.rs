@@ -0,0 +1,249 @@
+//! Fetches subgraph manifests from IPFS, so that the GraphQL API can show
+//! deployments' start blocks, features and graft lineage. The latter is
+//! essential when interpreting PoI divergences: a grafted deployment inherits
+//! the data, and any divergence, of its graft base up to the graft block.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use graphix_common_types::{Graft, IpfsCid, SubgraphManifest};
+use graphix_store::Store;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tracing::*;
+use url::Url;
+
+use crate::config::Config;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often to check whether IPFS was configured, or read-only mode
+/// disabled, in the meantime.
+const DISABLED_POLLING_PERIOD: Duration = Duration::from_secs(60);
+
+/// Graft chains are followed up to this many bases.
+const MAX_GRAFT_DEPTH: usize = 32;
+
+/// The parts of a subgraph manifest that Graphix is interested in.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawManifest {
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    graft: Option<RawGraft>,
+    #[serde(default)]
+    data_sources: Vec<RawDataSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct RawGraft {
+    /// Parsed separately, because [`IpfsCid`]s deserialize from bytes.
+    base: String,
+    block: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawDataSource {
+    #[serde(default)]
+    network: Option<String>,
+    #[serde(default)]
+    source: RawSource,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSource {
+    #[serde(default)]
+    start_block: Option<i64>,
+}
+
+/// A client of an IPFS node's HTTP API. Manifests never change, so all fetched
+/// manifests are cached.
+pub struct IpfsClient {
+    url: Url,
+    client: reqwest::Client,
+    manifests: Mutex<HashMap<IpfsCid, RawManifest>>,
+}
+
+impl IpfsClient {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+            manifests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    async fn cat(&self, cid: &IpfsCid) -> anyhow::Result<Vec<u8>> {
+        let url = format!("{}/api/v0/cat", self.url.as_str().trim_end_matches('/'));
+        let response = self
+            .client
+            .post(url)
+            .query(&[("arg", cid.to_string())])
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn raw_manifest(&self, cid: &IpfsCid) -> anyhow::Result<RawManifest> {
+        if let Some(manifest) = self.manifests.lock().unwrap().get(cid) {
+            return Ok(manifest.clone());
+        }
+
+        let bytes = self.cat(cid).await?;
+        let manifest: RawManifest = serde_yaml::from_slice(&bytes)
+            .with_context(|| format!("invalid subgraph manifest {cid}"))?;
+        self.manifests
+            .lock()
+            .unwrap()
+            .insert(cid.clone(), manifest.clone());
+
+        Ok(manifest)
+    }
+
+    /// Fetches the manifest of the subgraph deployment `cid`, and those of its
+    /// graft bases.
+    pub async fn subgraph_manifest(&self, cid: &IpfsCid) -> anyhow::Result<SubgraphManifest> {
+        let raw = self.raw_manifest(cid).await?;
+
+        let mut graft_chain = vec![];
+        let mut graft = raw.graft.clone();
+        while let Some(RawGraft { base, block }) = graft {
+            if graft_chain.len() == MAX_GRAFT_DEPTH {
+                anyhow::bail!("the graft chain of {cid} is longer than {MAX_GRAFT_DEPTH}");
+            }
+            let base: IpfsCid = base
+                .parse()
+                .with_context(|| format!("invalid graft base {base}"))?;
+            graft = self.raw_manifest(&base).await?.graft;
+            graft_chain.push(Graft { base, block });
+        }
+
+        Ok(SubgraphManifest {
+            network: raw
+                .data_sources
+                .iter()
+                .find_map(|data_source| data_source.network.clone()),
+            start_block: raw
+                .data_sources
+                .iter()
+                .map(|data_source| data_source.source.start_block.unwrap_or(0))
+                .min()
+                .unwrap_or(0),
+            features: raw.features,
+            graft_chain,
+        })
+    }
+}
+
+/// Fetches the manifests of all subgraph deployments without one whenever
+/// [`Config::ipfs`] is set, unless in read-only mode or if this replica isn't
+/// the `leader`. Runs forever.
+pub async fn sync_subgraph_manifests(
+    store: Store,
+    config: watch::Receiver<Config>,
+    leader: watch::Receiver<bool>,
+) {
+    let mut client: Option<IpfsClient> = None;
+
+    loop {
+        let ipfs_config = {
+            let config = config.borrow();
+            config
+                .ipfs
+                .clone()
+                .filter(|_| !config.read_only && *leader.borrow())
+        };
+        let Some(ipfs_config) = ipfs_config else {
+            tokio::time::sleep(DISABLED_POLLING_PERIOD).await;
+            continue;
+        };
+        if client.as_ref().map(IpfsClient::url) != Some(&ipfs_config.url) {
+            client = Some(IpfsClient::new(ipfs_config.url.clone()));
+        }
+        let client = client.as_ref().unwrap();
+
+        match store.sg_deployments_without_manifest().await {
+            Ok(cids) => {
+                for cid in cids {
+                    match client.subgraph_manifest(&cid).await {
+                        Ok(manifest) => {
+                            if let Err(err) =
+                                store.set_sg_deployment_manifest(&cid, &manifest).await
+                            {
+                                error!(%cid, error = %err, "Failed to store subgraph manifest");
+                            }
+                        }
+                        Err(err) => {
+                            warn!(%cid, error = %err, "Failed to fetch subgraph manifest")
+                        }
+                    }
+                }
+            }
+            Err(err) => error!(error = %err, "Failed to load subgraph deployments"),
+        }
+
+        tokio::time::sleep(Duration::from_secs(ipfs_config.sync_interval_in_seconds)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest: RawManifest = serde_yaml::from_str(
+            r#"
+specVersion: 0.0.5
+features:
+  - nonFatalErrors
+  - grafting
+graft:
+  base: QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA
+  block: 1000
+schema:
+  file:
+    /: /ipfs/QmSchema
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: arbitrum-one
+    source:
+      address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+      abi: Factory
+      startBlock: 165
+templates: []
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest,
+            RawManifest {
+                features: vec!["nonFatalErrors".to_string(), "grafting".to_string()],
+                graft: Some(RawGraft {
+                    base: "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA".to_string(),
+                    block: 1000,
+                }),
+                data_sources: vec![RawDataSource {
+                    network: Some("arbitrum-one".to_string()),
+                    source: RawSource {
+                        start_block: Some(165),
+                    },
+                }],
+            }
+        );
+    }
+}
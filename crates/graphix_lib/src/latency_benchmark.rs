@@ -0,0 +1,138 @@
+//! Benchmarks of indexers' responsiveness. Every so often, each indexer's
+//! indexing statuses and one of its PoIs are requested, and the latency and
+//! outcome of both requests are stored, so that indexer operators can compare
+//! their indexer against the fleet with the `indexerLatencyStats` GraphQL
+//! query.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use graphix_common_types::LatencyProbe;
+use graphix_indexer_client::{IndexerClient, PoiRequest, WithIndexer};
+use graphix_store::models::NewLatencySample;
+use graphix_store::Store;
+use tokio::sync::watch;
+use tracing::*;
+
+use crate::config::Config;
+
+/// How often to check whether benchmarks were enabled, or read-only mode
+/// disabled, in the meantime.
+const DISABLED_POLLING_PERIOD: Duration = Duration::from_secs(60);
+
+/// Benchmarks the current indexers whenever [`Config::latency_benchmark`] is
+/// set, unless in read-only mode, and deletes samples past their retention
+/// period. Runs forever.
+pub async fn run_latency_benchmarks(
+    store: Store,
+    config: watch::Receiver<Config>,
+    indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
+) {
+    loop {
+        let benchmark_config = {
+            let config = config.borrow();
+            config
+                .latency_benchmark
+                .clone()
+                .filter(|_| !config.read_only)
+        };
+        let Some(benchmark_config) = benchmark_config else {
+            tokio::time::sleep(DISABLED_POLLING_PERIOD).await;
+            continue;
+        };
+
+        let indexers = indexers.borrow().clone();
+        let samples = benchmark_indexers(&indexers).await;
+        debug!(
+            indexers = indexers.len(),
+            samples = samples.len(),
+            "Benchmarked indexers"
+        );
+        if let Err(err) = store.write_latency_samples(&samples).await {
+            error!(error = %err, "Failed to write latency samples");
+        }
+
+        let cutoff =
+            chrono::Utc::now() - chrono::Duration::days(benchmark_config.retention_days.into());
+        match store
+            .delete_latency_samples_before(cutoff.naive_utc())
+            .await
+        {
+            Ok(deleted) if deleted > 0 => {
+                debug!(
+                    deleted,
+                    "Deleted latency samples past their retention period"
+                )
+            }
+            Ok(_) => {}
+            Err(err) => error!(error = %err, "Failed to delete old latency samples"),
+        }
+
+        tokio::time::sleep(Duration::from_secs(benchmark_config.interval_in_seconds)).await;
+    }
+}
+
+/// Benchmarks all indexers concurrently.
+pub async fn benchmark_indexers(
+    indexers: &[Arc<dyn IndexerClient>],
+) -> Vec<WithIndexer<NewLatencySample>> {
+    indexers
+        .iter()
+        .map(|indexer| benchmark_indexer(indexer.clone()))
+        .collect::<FuturesUnordered<_>>()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Times a request for the indexer's indexing statuses and, if that succeeds,
+/// one for a PoI. The PoI is that of the indexer's first subgraph deployment
+/// (by IPFS CID) at its latest indexed block, so that subsequent benchmarks
+/// of the same indexer are comparable.
+async fn benchmark_indexer(indexer: Arc<dyn IndexerClient>) -> Vec<WithIndexer<NewLatencySample>> {
+    let sample = |probe, started_at: Instant, success| {
+        WithIndexer::new(
+            indexer.clone(),
+            NewLatencySample {
+                probe,
+                latency_ms: started_at
+                    .elapsed()
+                    .as_millis()
+                    .try_into()
+                    .unwrap_or(i32::MAX),
+                success,
+            },
+        )
+    };
+
+    let started_at = Instant::now();
+    let statuses = indexer.clone().indexing_statuses().await;
+    let mut samples = vec![sample(LatencyProbe::Status, started_at, statuses.is_ok())];
+
+    let Some(status) = statuses.ok().and_then(|statuses| {
+        statuses
+            .into_iter()
+            .min_by(|a, b| (&a.deployment, &a.network).cmp(&(&b.deployment, &b.network)))
+    }) else {
+        return samples;
+    };
+
+    let request = PoiRequest {
+        deployment: status.deployment,
+        network: status.network,
+        block_number: status.latest_block.number,
+    };
+    let started_at = Instant::now();
+    let poi = indexer.clone().proof_of_indexing(request).await;
+    samples.push(sample(
+        LatencyProbe::ProofOfIndexing,
+        started_at,
+        poi.is_ok(),
+    ));
+
+    samples
+}
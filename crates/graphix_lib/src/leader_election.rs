@@ -0,0 +1,67 @@
+//! Leader election among the Graphix replicas that share a database, so that
+//! only one of them polls indexers while all of them serve the API. The leader
+//! holds a PostgreSQL advisory lock, which is released as soon as it shuts
+//! down, dies or loses its database connection, at which point another
+//! replica takes over.
+
+use std::time::Duration;
+
+use graphix_store::{AdvisoryLock, Store};
+use sha2::{Digest, Sha256};
+use tracing::*;
+
+use crate::config::Config;
+
+/// How often replicas that aren't the leader check whether they should take
+/// over, unless the polling period is shorter.
+pub const FOLLOWER_POLLING_PERIOD: Duration = Duration::from_secs(15);
+
+pub struct LeaderElection {
+    store: Store,
+    lock: Option<AdvisoryLock>,
+}
+
+impl LeaderElection {
+    pub fn new(store: Store) -> Self {
+        Self { store, lock: None }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// Makes sure that this replica is still the leader, or tries to become
+    /// it otherwise. Returns whether it's the leader.
+    pub async fn elect(&mut self, config: &Config) -> bool {
+        let key = lock_key(&config.instance_name);
+        if let Some(lock) = &mut self.lock {
+            if lock.key() != key {
+                info!("Instance name changed, stepping down as the leader");
+                self.lock = None;
+            } else if let Err(err) = lock.check().await {
+                warn!(error = %err, "Lost the leader lock");
+                self.lock = None;
+            }
+        }
+
+        if self.lock.is_none() {
+            match self.store.try_advisory_lock(key).await {
+                Ok(Some(lock)) => {
+                    info!("Became the leader, polling indexers");
+                    self.lock = Some(lock);
+                }
+                Ok(None) => debug!("Another replica is the leader"),
+                Err(err) => error!(error = %err, "Failed to take the leader lock"),
+            }
+        }
+
+        self.is_leader()
+    }
+}
+
+/// Replicas of the same instance compete for the same lock, whereas different
+/// instances that share a database don't get in each other's way.
+fn lock_key(instance_name: &str) -> i64 {
+    let hash = Sha256::digest(format!("graphix-leader:{instance_name}"));
+    i64::from_be_bytes(hash[..8].try_into().unwrap())
+}
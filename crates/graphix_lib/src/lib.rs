@@ -1,15 +1,43 @@
+pub mod alerts;
 pub mod bisect;
 pub mod block_choice;
+pub mod block_timestamps;
+pub mod campaigns;
 mod cli;
 pub mod config;
+pub mod config_check;
+pub mod connectivity;
+pub mod deployment_discovery;
+pub mod deployment_names;
+pub mod digests;
+pub mod email;
+pub mod events;
+pub mod failed_queries;
 pub mod graphql_api;
+pub mod grpc_api;
+pub mod indexing_lag;
 pub mod indexing_loop;
+pub mod ipfs;
+pub mod latency_benchmark;
+pub mod leader_election;
+pub mod network_subgraph_metadata;
+pub mod poi_backfill;
+pub mod polling_schedule;
 mod prometheus_metrics;
+pub mod simulation;
+pub mod subgraph_feed;
+pub mod subscription_digests;
+pub mod substreams;
+pub mod watchlists;
+pub mod webhooks;
 
 #[cfg(feature = "tests")]
 pub mod test_utils;
 
-pub use cli::CliOptions;
+pub use cli::{CliOptions, Command};
 pub use prometheus_metrics::{metrics, PrometheusExporter, PrometheusMetrics};
 
 pub const GRAPHIX_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The git commit that Graphix was built from, if `GRAPHIX_GIT_COMMIT` was set
+/// at build time.
+pub const GRAPHIX_GIT_COMMIT: Option<&str> = option_env!("GRAPHIX_GIT_COMMIT");
@@ -0,0 +1,255 @@
+//! Keeps the network subgraph metadata of indexers, e.g. their stake, URL and
+//! geohash, up to date. Every so often, the metadata of all tracked indexers
+//! is looked up on the network subgraph they were found on (or the first
+//! configured one), and the GraphQL API flags metadata that wasn't refreshed
+//! in a while as stale. The tokens that indexers allocated to each subgraph
+//! deployment are refreshed along with it, to weigh PoIs by stake.
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Context;
+use bigdecimal::BigDecimal;
+use graphix_common_types::inputs::IndexersQuery;
+use graphix_common_types::{IndexerAddress, IpfsCid};
+use graphix_network_sg_client::{ActiveAllocation, IndexerMetadata};
+use graphix_store::models::{self, IntId, NewIndexerNetworkSubgraphMetadata};
+use graphix_store::Store;
+use tokio::sync::watch;
+use tracing::*;
+
+use crate::config::Config;
+use crate::PrometheusMetrics;
+
+/// How often to check whether a network subgraph was configured, read-only
+/// mode disabled, or leadership acquired in the meantime.
+const DISABLED_POLLING_PERIOD: Duration = Duration::from_secs(60);
+
+/// Refreshes the network subgraph metadata of all indexers whenever
+/// [`Config::network_subgraph_metadata`] says so, as long as any network
+/// subgraphs are configured, not in read-only mode, and this replica is the
+/// `leader`. Runs forever.
+pub async fn refresh_network_subgraph_metadata(
+    store: Store,
+    config: watch::Receiver<Config>,
+    leader: watch::Receiver<bool>,
+    metrics: &'static PrometheusMetrics,
+) {
+    loop {
+        let config = config.borrow().clone();
+        if config.read_only || !*leader.borrow() || config.network_subgraphs().is_empty() {
+            tokio::time::sleep(DISABLED_POLLING_PERIOD).await;
+            continue;
+        }
+
+        match refresh_indexers_metadata(&store, &config, metrics).await {
+            Ok(refreshed) => debug!(refreshed, "Refreshed network subgraph metadata"),
+            Err(err) => error!(error = %err, "Failed to refresh network subgraph metadata"),
+        }
+
+        tokio::time::sleep(Duration::from_secs(
+            config.network_subgraph_metadata.refresh_interval_in_seconds,
+        ))
+        .await;
+    }
+}
+
+/// Refreshes the network subgraph metadata of all indexers that aren't
+/// archived, and returns for how many indexers metadata was found. A network
+/// subgraph that fails doesn't prevent the others from being refreshed.
+///
+/// Indexers found on multiple network subgraphs get their metadata from the
+/// first of them by name, and their allocations from all of them. The
+/// subgraph deployments they allocated to are attributed to the network
+/// subgraphs of the allocations.
+pub async fn refresh_indexers_metadata(
+    store: &Store,
+    config: &Config,
+    metrics: &PrometheusMetrics,
+) -> anyhow::Result<usize> {
+    let indexers = store.indexers(IndexersQuery::default()).await?;
+    let indexer_ids: Vec<_> = indexers.iter().map(|indexer| indexer.id).collect();
+    let mut network_subgraph_names: HashMap<IntId, Vec<String>> = HashMap::new();
+    for attribution in store.indexer_network_subgraphs(&indexer_ids).await? {
+        network_subgraph_names
+            .entry(attribution.indexer_id)
+            .or_default()
+            .push(attribution.network_subgraph);
+    }
+
+    let mut indexers_by_network_subgraph: BTreeMap<Option<String>, Vec<models::Indexer>> =
+        BTreeMap::new();
+    for indexer in indexers {
+        match network_subgraph_names.get(&indexer.id) {
+            Some(names) => {
+                for name in names {
+                    indexers_by_network_subgraph
+                        .entry(Some(name.clone()))
+                        .or_default()
+                        .push(indexer.clone());
+                }
+            }
+            None => indexers_by_network_subgraph
+                .entry(None)
+                .or_default()
+                .push(indexer),
+        }
+    }
+
+    let mut refreshed = 0;
+    let mut allocated_indexers = vec![];
+    let mut allocations = vec![];
+    for (network_subgraph_name, indexers) in indexers_by_network_subgraph {
+        let Some(network_subgraph_config) =
+            config.network_subgraph(network_subgraph_name.as_deref())
+        else {
+            warn!(
+                network_subgraph = ?network_subgraph_name,
+                "Network subgraph not found, can't refresh indexer metadata"
+            );
+            continue;
+        };
+        let network_subgraph = network_subgraph_config.client(metrics)?;
+
+        let addresses: Vec<_> = indexers.iter().map(|indexer| indexer.address).collect();
+        let metadata = match network_subgraph.indexers_metadata(&addresses).await {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                error!(
+                    network_subgraph = ?network_subgraph_name,
+                    error = %err,
+                    "Failed to query indexer metadata from the network subgraph"
+                );
+                continue;
+            }
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        match network_subgraph.active_allocations(&addresses).await {
+            Ok(network_subgraph_allocations) => {
+                let network_subgraph_allocations =
+                    indexer_allocations(network_subgraph_allocations, now)?;
+                if let Some(name) = &network_subgraph_name {
+                    let deployments: Vec<_> = network_subgraph_allocations
+                        .iter()
+                        .map(|allocation| allocation.deployment.clone())
+                        .collect();
+                    store
+                        .write_sg_deployment_network_subgraphs(name, &deployments)
+                        .await?;
+                }
+                allocated_indexers.extend(addresses);
+                allocations.extend(network_subgraph_allocations);
+            }
+            Err(err) => error!(
+                network_subgraph = ?network_subgraph_name,
+                error = %err,
+                "Failed to query active allocations from the network subgraph"
+            ),
+        }
+        for metadata in metadata {
+            let Some(indexer) = indexers.iter().find(|i| i.address == metadata.id) else {
+                continue;
+            };
+            let first_network_subgraph = network_subgraph_names
+                .get(&indexer.id)
+                .and_then(|names| names.first());
+            if first_network_subgraph != network_subgraph_name.as_ref() {
+                continue;
+            }
+            store
+                .create_or_update_indexer_network_subgraph_metadata(
+                    indexer.id,
+                    new_metadata(metadata, now)?,
+                )
+                .await?;
+            refreshed += 1;
+        }
+    }
+
+    // Allocations to the same deployment on multiple network subgraphs add up.
+    store
+        .replace_indexer_allocations(&allocated_indexers, &merge_allocations(allocations))
+        .await?;
+
+    Ok(refreshed)
+}
+
+/// Sums the tokens of each indexer's active allocations by subgraph
+/// deployment.
+fn indexer_allocations(
+    allocations: Vec<ActiveAllocation>,
+    updated_at: chrono::NaiveDateTime,
+) -> anyhow::Result<Vec<models::IndexerAllocation>> {
+    let mut allocated_tokens: BTreeMap<(IndexerAddress, IpfsCid), BigDecimal> = BTreeMap::new();
+    for allocation in allocations {
+        let Ok(deployment) = IpfsCid::from_str(&allocation.deployment) else {
+            warn!(
+                deployment = allocation.deployment,
+                "Allocation to an invalid subgraph deployment; ignoring"
+            );
+            continue;
+        };
+        let tokens = BigDecimal::from_str(&allocation.allocated_tokens)
+            .with_context(|| format!("invalid token amount: {}", allocation.allocated_tokens))?;
+        *allocated_tokens
+            .entry((allocation.indexer_address, deployment))
+            .or_default() += tokens;
+    }
+
+    Ok(allocated_tokens
+        .into_iter()
+        .map(
+            |((indexer_address, deployment), allocated_tokens)| models::IndexerAllocation {
+                indexer_address,
+                deployment,
+                allocated_tokens,
+                updated_at,
+            },
+        )
+        .collect())
+}
+
+/// Sums allocations of the same indexer to the same subgraph deployment.
+fn merge_allocations(
+    allocations: Vec<models::IndexerAllocation>,
+) -> Vec<models::IndexerAllocation> {
+    let mut merged: BTreeMap<(IndexerAddress, IpfsCid), models::IndexerAllocation> =
+        BTreeMap::new();
+    for allocation in allocations {
+        match merged.entry((allocation.indexer_address, allocation.deployment.clone())) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().allocated_tokens += allocation.allocated_tokens;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(allocation);
+            }
+        }
+    }
+    merged.into_values().collect()
+}
+
+fn new_metadata(
+    metadata: IndexerMetadata,
+    last_updated_at: chrono::NaiveDateTime,
+) -> anyhow::Result<NewIndexerNetworkSubgraphMetadata> {
+    let tokens = |amount: &str| {
+        BigDecimal::from_str(amount).with_context(|| format!("invalid token amount: {amount}"))
+    };
+
+    Ok(NewIndexerNetworkSubgraphMetadata {
+        geohash: metadata.geohash,
+        indexer_url: metadata.url,
+        staked_tokens: tokens(&metadata.staked_tokens)?,
+        allocated_tokens: tokens(&metadata.allocated_tokens)?,
+        locked_tokens: tokens(&metadata.locked_tokens)?,
+        query_fees_collected: tokens(&metadata.query_fees_collected)?,
+        query_fee_rebates: tokens(&metadata.query_fee_rebates)?,
+        rewards_earned: tokens(&metadata.rewards_earned)?,
+        indexer_indexing_rewards: tokens(&metadata.indexer_indexing_rewards)?,
+        delegator_indexing_rewards: tokens(&metadata.delegator_indexing_rewards)?,
+        last_updated_at,
+    })
+}
@@ -0,0 +1,155 @@
+//! Backfills of historical PoIs, requested with the `backfillPois` GraphQL
+//! mutation. For a subgraph deployment that was added to Graphix late, all
+//! indexers that report it are asked for their PoIs at every `step` blocks of
+//! a block range, one block at a time, and the PoIs are stored as non-live
+//! PoIs. This reconstructs the divergence history of the deployment without
+//! affecting live PoIs.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use graphix_common_types::inputs::IndexersQuery;
+use graphix_common_types::{IndexerAddress, IpfsCid};
+use graphix_indexer_client::{IndexerClient, PoiRequest};
+use graphix_store::models::{IntId, PoiBackfill};
+use graphix_store::{PoiLiveness, Store};
+use tokio::sync::watch;
+use tracing::*;
+
+use crate::config::Config;
+
+/// The maximum number of blocks that a single backfill requests PoIs at.
+pub const MAX_POI_BACKFILL_BLOCKS: u64 = 10_000;
+
+/// How often to check for new backfills while there are none, or while in
+/// read-only mode.
+const IDLE_POLLING_PERIOD: Duration = Duration::from_secs(10);
+
+/// How long each indexer has to respond with its PoI at a block.
+const POI_DEADLINE: Duration = Duration::from_secs(60);
+
+/// Works through pending PoI backfills, oldest first, with the current
+/// indexers, unless in read-only mode. Runs forever.
+pub async fn run_poi_backfills(
+    store: Store,
+    config: watch::Receiver<Config>,
+    indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
+) {
+    loop {
+        if config.borrow().read_only {
+            tokio::time::sleep(IDLE_POLLING_PERIOD).await;
+            continue;
+        }
+
+        let indexers = indexers.borrow().clone();
+        match backfill_next_block(&store, &indexers).await {
+            Ok(true) => {}
+            Ok(false) => tokio::time::sleep(IDLE_POLLING_PERIOD).await,
+            Err(err) => {
+                error!(error = %err, "Failed to backfill PoIs");
+                tokio::time::sleep(IDLE_POLLING_PERIOD).await;
+            }
+        }
+    }
+}
+
+/// Collects the PoIs at the next block of the oldest pending backfill, and
+/// records its progress. A backfill that fails is marked as done, with the
+/// error. Returns whether any backfill was pending.
+pub async fn backfill_next_block(
+    store: &Store,
+    indexers: &[Arc<dyn IndexerClient>],
+) -> anyhow::Result<bool> {
+    let Some((backfill, deployment, network)) = store.first_pending_poi_backfill().await? else {
+        return Ok(false);
+    };
+    let Some(block) = backfill.next_block else {
+        return Ok(false);
+    };
+
+    let (pois_written, next_block, error) =
+        match backfill_block(store, indexers, &backfill, deployment, network, block).await {
+            Ok(pois_written) => (pois_written, next_block(&backfill, block), None),
+            Err(err) => (0, None, Some(err.to_string())),
+        };
+    debug!(
+        id = backfill.id,
+        block,
+        pois_written,
+        ?error,
+        "Backfilled PoIs"
+    );
+    store
+        .record_poi_backfill_progress(backfill.id, next_block, pois_written, error.as_deref())
+        .await?;
+
+    Ok(true)
+}
+
+/// Requests the PoIs at `block` from the indexers whose indexing statuses
+/// cover it, and stores them. Returns how many PoIs were stored.
+async fn backfill_block(
+    store: &Store,
+    indexers: &[Arc<dyn IndexerClient>],
+    backfill: &PoiBackfill,
+    deployment: IpfsCid,
+    network: String,
+    block: i64,
+) -> anyhow::Result<usize> {
+    let indexer_ids: HashSet<IntId> = store
+        .indexing_statuses(&[deployment.clone()], None, None)
+        .await?
+        .into_iter()
+        .filter(|status| status.sg_deployment_id == backfill.sg_deployment_id)
+        .filter(|status| {
+            status.earliest_block_number.map_or(true, |n| n <= block)
+                && status.latest_block_number.map_or(true, |n| n >= block)
+        })
+        .map(|status| status.indexer_id)
+        .collect();
+    let addresses: HashSet<IndexerAddress> = store
+        .indexers(IndexersQuery::default())
+        .await?
+        .into_iter()
+        .filter(|indexer| indexer_ids.contains(&indexer.id))
+        .map(|indexer| indexer.address)
+        .collect();
+
+    let block_number = u64::try_from(block)?;
+    let pois: Vec<_> = indexers
+        .iter()
+        .filter(|indexer| addresses.contains(&indexer.address()))
+        .map(|indexer| {
+            let request = PoiRequest {
+                deployment: deployment.clone(),
+                network: network.clone(),
+                block_number,
+            };
+            async move {
+                tokio::time::timeout(
+                    POI_DEADLINE,
+                    indexer.clone().proofs_of_indexing(vec![request]),
+                )
+                .await
+                .unwrap_or_default()
+            }
+        })
+        .collect::<FuturesUnordered<_>>()
+        .concat()
+        .await;
+
+    let pois_written = pois.len();
+    if pois_written > 0 {
+        store.write_pois(pois, PoiLiveness::NotLive).await?;
+    }
+    Ok(pois_written)
+}
+
+/// The block after `block` in the range of `backfill`, if any.
+fn next_block(backfill: &PoiBackfill, block: i64) -> Option<u64> {
+    let next = block.checked_add(backfill.step)?;
+    (next <= backfill.to_block).then(|| next.try_into().ok())?
+}
@@ -0,0 +1,385 @@
+//! Per-chain and per-indexer-group polling schedules. The main loop wakes up
+//! whenever any indexer or chain is due, rather than at a single global
+//! interval:
+//!  - Indexers are queried for their indexing statuses at the polling period
+//!    of their group, or else the shortest polling period of any chain.
+//!  - PoIs are only collected for the chains of an indexer whose polling
+//!    period elapsed, i.e. the longer of the chain's and the indexer group's.
+//!  - Subgraph deployments on a watchlist with a shorter polling period are
+//!    polled at that period instead, along with their indexers.
+//!
+//! Indexing statuses are always stored in full, so that the deployments of
+//! chains that aren't due don't look removed.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use graphix_common_types::{IndexerAddress, IpfsCid};
+use graphix_indexer_client::{IndexerClient, IndexingStatus};
+use tokio::time::Instant;
+
+use crate::config::Config;
+use crate::watchlists::Watchlists;
+
+/// Keeps track of when each indexer, and each chain and watchlisted subgraph
+/// deployment of each indexer, was last polled.
+#[derive(Default)]
+pub struct PollingSchedule {
+    indexers_polled_at: HashMap<IndexerAddress, Instant>,
+    chains_polled_at: HashMap<(IndexerAddress, String), Instant>,
+    deployments_polled_at: HashMap<(IndexerAddress, IpfsCid), Instant>,
+    /// The shortest watchlist polling period of the subgraph deployments that
+    /// each indexer reported in its last indexing statuses.
+    watchlist_periods: HashMap<IndexerAddress, Duration>,
+}
+
+impl PollingSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the indexers whose indexing statuses are due at `now`, and
+    /// marks them as polled.
+    pub fn due_indexers(
+        &mut self,
+        config: &Config,
+        indexers: &[Arc<dyn IndexerClient>],
+        now: Instant,
+    ) -> Vec<Arc<dyn IndexerClient>> {
+        let due: Vec<_> = indexers
+            .iter()
+            .filter(|indexer| {
+                is_due(
+                    self.indexers_polled_at.get(&indexer.address()),
+                    self.indexer_polling_period(config, indexer.as_ref()),
+                    now,
+                )
+            })
+            .cloned()
+            .collect();
+        for indexer in &due {
+            self.indexers_polled_at.insert(indexer.address(), now);
+        }
+        due
+    }
+
+    /// Keeps the indexing statuses of the chains, and of the watchlisted
+    /// subgraph deployments, whose PoIs are due at `now`, and marks them as
+    /// polled.
+    pub fn due_statuses(
+        &mut self,
+        config: &Config,
+        watchlists: &Watchlists,
+        statuses: &[IndexingStatus],
+        now: Instant,
+    ) -> Vec<IndexingStatus> {
+        let mut due_chains: HashMap<(IndexerAddress, String), bool> = HashMap::new();
+        let mut watchlist_periods: HashMap<IndexerAddress, Duration> = HashMap::new();
+        for status in statuses {
+            let key = (status.indexer.address(), status.network.clone());
+            if let Entry::Vacant(entry) = due_chains.entry(key) {
+                let period = chain_polling_period(config, status.indexer.as_ref(), &status.network);
+                let due = is_due(self.chains_polled_at.get(entry.key()), period, now);
+                entry.insert(due);
+            }
+            if let Some(period) = watchlists.polling_period(&status.deployment) {
+                watchlist_periods
+                    .entry(status.indexer.address())
+                    .and_modify(|shortest| *shortest = period.min(*shortest))
+                    .or_insert(period);
+            }
+        }
+
+        for status in statuses {
+            self.watchlist_periods.remove(&status.indexer.address());
+        }
+        self.watchlist_periods.extend(watchlist_periods);
+        for (key, _) in due_chains.iter().filter(|(_, due)| **due) {
+            self.chains_polled_at.insert(key.clone(), now);
+        }
+
+        let due: Vec<IndexingStatus> = statuses
+            .iter()
+            .filter(|status| {
+                due_chains[&(status.indexer.address(), status.network.clone())]
+                    || watchlists
+                        .polling_period(&status.deployment)
+                        .is_some_and(|period| {
+                            let key = (status.indexer.address(), status.deployment.clone());
+                            is_due(self.deployments_polled_at.get(&key), period, now)
+                        })
+            })
+            .cloned()
+            .collect();
+        for status in &due {
+            if watchlists.polling_period(&status.deployment).is_some() {
+                let key = (status.indexer.address(), status.deployment.clone());
+                self.deployments_polled_at.insert(key, now);
+            }
+        }
+        due
+    }
+
+    /// When the next one of `indexers` will be due.
+    pub fn next_poll_at(&self, config: &Config, indexers: &[Arc<dyn IndexerClient>]) -> Instant {
+        let now = Instant::now();
+        indexers
+            .iter()
+            .map(|indexer| {
+                let period = self.indexer_polling_period(config, indexer.as_ref());
+                match self.indexers_polled_at.get(&indexer.address()) {
+                    Some(polled_at) => *polled_at + period,
+                    None => now,
+                }
+            })
+            .min()
+            .unwrap_or(now + config.polling_period())
+    }
+
+    /// How often an indexer's indexing statuses are queried, taking the
+    /// watchlists of its subgraph deployments into account.
+    fn indexer_polling_period(&self, config: &Config, indexer: &dyn IndexerClient) -> Duration {
+        let period = indexer_polling_period(config, indexer);
+        match self.watchlist_periods.get(&indexer.address()) {
+            Some(watchlist_period) => period.min(*watchlist_period),
+            None => period,
+        }
+    }
+}
+
+fn is_due(polled_at: Option<&Instant>, period: Duration, now: Instant) -> bool {
+    polled_at.map_or(true, |polled_at| now.duration_since(*polled_at) >= period)
+}
+
+/// How often an indexer's indexing statuses are queried. Without a group
+/// override, that's as often as its fastest chain might need.
+fn indexer_polling_period(config: &Config, indexer: &dyn IndexerClient) -> Duration {
+    config
+        .indexer_polling_period(indexer)
+        .unwrap_or_else(|| config.shortest_chain_polling_period())
+}
+
+/// How often the PoIs of an indexer on a chain are collected.
+fn chain_polling_period(config: &Config, indexer: &dyn IndexerClient, chain: &str) -> Duration {
+    match (
+        config.indexer_polling_period(indexer),
+        config.chain_polling_period(chain),
+    ) {
+        (Some(indexer_period), Some(chain_period)) => indexer_period.max(chain_period),
+        (Some(period), None) | (None, Some(period)) => period,
+        (None, None) => config.polling_period(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use graphix_common_types::{IpfsCid, SubgraphHealth};
+    use graphix_indexer_client::BlockPointer;
+    use graphix_store::models;
+
+    use super::*;
+    use crate::config::ChainConfig;
+    use crate::test_utils::mocks::MockIndexer;
+
+    fn indexer(name: &str) -> Arc<dyn IndexerClient> {
+        Arc::new(MockIndexer {
+            name: name.to_string(),
+            deployment_details: vec![],
+            fail_indexing_statuses: false,
+        })
+    }
+
+    fn status(indexer: &Arc<dyn IndexerClient>, network: &str) -> IndexingStatus {
+        IndexingStatus {
+            indexer: indexer.clone(),
+            deployment: IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA")
+                .unwrap(),
+            network: network.to_string(),
+            latest_block: BlockPointer {
+                number: 42,
+                hash: None,
+            },
+            earliest_block_num: 0,
+            health: SubgraphHealth::Healthy,
+            synced: true,
+            paused: Some(false),
+            errors: vec![],
+        }
+    }
+
+    fn chain(polling_period_in_seconds: Option<u64>) -> ChainConfig {
+        ChainConfig {
+            caip2: None,
+            speed: None,
+            block_explorer_url_template_for_block: None,
+            block_choice_policy: None,
+            polling_period_in_seconds,
+            rpc_url: None,
+        }
+    }
+
+    #[test]
+    fn chains_are_polled_at_their_own_period() {
+        let config = Config {
+            polling_period_in_seconds: 60,
+            chains: [
+                ("fast".to_string(), chain(Some(10))),
+                ("slow".to_string(), chain(Some(600))),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let indexers = vec![indexer("a")];
+        let statuses = [
+            status(&indexers[0], "fast"),
+            status(&indexers[0], "slow"),
+            status(&indexers[0], "mainnet"),
+        ];
+        let networks = |statuses: Vec<IndexingStatus>| {
+            statuses
+                .into_iter()
+                .map(|status| status.network)
+                .collect::<Vec<_>>()
+        };
+
+        let mut schedule = PollingSchedule::new();
+        let start = Instant::now();
+        assert_eq!(schedule.due_indexers(&config, &indexers, start).len(), 1);
+        assert_eq!(
+            networks(schedule.due_statuses(&config, &Watchlists::default(), &statuses, start)),
+            ["fast", "slow", "mainnet"]
+        );
+
+        // Without a group override, the indexer is queried as often as its
+        // fastest chain.
+        let now = start + Duration::from_secs(5);
+        assert!(schedule.due_indexers(&config, &indexers, now).is_empty());
+        assert_eq!(
+            schedule.next_poll_at(&config, &indexers),
+            start + Duration::from_secs(10)
+        );
+
+        let now = start + Duration::from_secs(10);
+        assert_eq!(schedule.due_indexers(&config, &indexers, now).len(), 1);
+        assert_eq!(
+            networks(schedule.due_statuses(&config, &Watchlists::default(), &statuses, now)),
+            ["fast"]
+        );
+
+        let now = start + Duration::from_secs(60);
+        assert_eq!(
+            networks(schedule.due_statuses(&config, &Watchlists::default(), &statuses, now)),
+            ["fast", "mainnet"]
+        );
+    }
+
+    #[test]
+    fn indexer_groups_slow_down_chains() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+            pollingPeriodInSeconds: 60
+            chains:
+              fast:
+                pollingPeriodInSeconds: 10
+            sources:
+              - type: networkSubgraph
+                endpoint: https://example.com/mainnet
+                stakeThreshold: 0.0
+                pollingPeriodInSeconds: 300
+            "#,
+        )
+        .unwrap();
+        let indexers = vec![indexer("a")];
+        let statuses = [status(&indexers[0], "fast")];
+
+        let mut schedule = PollingSchedule::new();
+        let start = Instant::now();
+        assert_eq!(schedule.due_indexers(&config, &indexers, start).len(), 1);
+        assert_eq!(
+            schedule
+                .due_statuses(&config, &Watchlists::default(), &statuses, start)
+                .len(),
+            1
+        );
+
+        let now = start + Duration::from_secs(60);
+        assert!(schedule.due_indexers(&config, &indexers, now).is_empty());
+
+        let now = start + Duration::from_secs(300);
+        assert_eq!(schedule.due_indexers(&config, &indexers, now).len(), 1);
+        assert_eq!(
+            schedule
+                .due_statuses(&config, &Watchlists::default(), &statuses, now)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn watchlisted_deployments_are_polled_at_their_own_period() {
+        let config = Config {
+            polling_period_in_seconds: 60,
+            ..Default::default()
+        };
+        let indexers = vec![indexer("a")];
+        let watchlisted = status(&indexers[0], "mainnet");
+        let other = IndexingStatus {
+            deployment: IpfsCid::from_str("QmWmyoMoctfbAaiEs2G46gpeUmhqFRDW6KWo64y5r581Vz")
+                .unwrap(),
+            ..status(&indexers[0], "mainnet")
+        };
+        let statuses = [watchlisted.clone(), other];
+        let watchlists = Watchlists::new(vec![models::Watchlist {
+            id: 1,
+            name: "important".to_string(),
+            deployments: vec![Some(watchlisted.deployment.clone())],
+            polling_period_in_seconds: Some(10),
+            investigate_divergences: true,
+            webhook_url: None,
+            from_config: false,
+            created_at: Default::default(),
+            api_key_public_prefix: None,
+        }]);
+        let deployments = |statuses: Vec<IndexingStatus>| {
+            statuses
+                .into_iter()
+                .map(|status| status.deployment)
+                .collect::<Vec<_>>()
+        };
+
+        let mut schedule = PollingSchedule::new();
+        let start = Instant::now();
+        assert_eq!(schedule.due_indexers(&config, &indexers, start).len(), 1);
+        assert_eq!(
+            schedule
+                .due_statuses(&config, &watchlists, &statuses, start)
+                .len(),
+            2
+        );
+
+        let now = start + Duration::from_secs(10);
+        assert_eq!(schedule.due_indexers(&config, &indexers, now).len(), 1);
+        assert_eq!(
+            deployments(schedule.due_statuses(&config, &watchlists, &statuses, now)),
+            [watchlisted.deployment.clone()]
+        );
+        assert_eq!(
+            schedule.next_poll_at(&config, &indexers),
+            start + Duration::from_secs(20)
+        );
+
+        let now = start + Duration::from_secs(60);
+        assert_eq!(schedule.due_indexers(&config, &indexers, now).len(), 1);
+        assert_eq!(
+            schedule
+                .due_statuses(&config, &watchlists, &statuses, now)
+                .len(),
+            2
+        );
+    }
+}
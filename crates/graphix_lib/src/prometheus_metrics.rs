@@ -1,14 +1,38 @@
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::OnceLock;
 
+use graphix_common_types::JobStatus;
+use graphix_store::models::{IndexingLag, TableStats};
+use graphix_store::StoreMetrics;
 // It's important to use the exported crate `prometheus_exporter::prometheus`
 // instead of `prometheus`, as different versions of that crate have
 // incompatible global registries.
 use prometheus_exporter::prometheus;
 
+use crate::indexing_lag::max_blocks_behind;
+
 pub struct PrometheusMetrics {
     pub indexing_statuses_requests: prometheus::IntCounterVec,
     pub public_proofs_of_indexing_requests: prometheus::IntCounterVec,
+    pub poi_deadlines_missed: prometheus::IntCounterVec,
+    pub indexer_blocks_behind: prometheus::IntGaugeVec,
+    pub throttled_indexer_requests: prometheus::IntCounterVec,
+    pub indexer_circuit_breaker_events: prometheus::IntCounterVec,
+    pub failed_queries_truncated: prometheus::IntCounterVec,
+    pub failed_queries_redacted: prometheus::IntCounterVec,
+    pub table_rows: prometheus::IntGaugeVec,
+    pub table_size_bytes: prometheus::IntGaugeVec,
+    pub config_reload_failures: prometheus::IntGauge,
+    pub leader: prometheus::IntGauge,
+    pub divergence_investigations_launched: prometheus::IntCounter,
+    pub divergence_investigation_errors: prometheus::IntCounterVec,
+    pub divergence_investigation_queue_depth: prometheus::IntGaugeVec,
+    pub bisection_steps: prometheus::IntCounter,
+    pub bisection_duration_seconds: prometheus::Histogram,
+    pub graphql_field_duration_seconds: prometheus::HistogramVec,
+    pub graphql_field_errors: prometheus::IntCounterVec,
+    pub alert_events: prometheus::IntCounterVec,
+    pub store: StoreMetrics,
 }
 
 static METRICS: OnceLock<PrometheusMetrics> = OnceLock::new();
@@ -36,10 +60,224 @@ impl PrometheusMetrics {
                 registry
             )
             .unwrap();
+        let poi_deadlines_missed = prometheus::register_int_counter_vec_with_registry!(
+            "poi_deadlines_missed",
+            "Number of polling cycles in which an indexer didn't respond with its PoIs in time",
+            &["indexer"],
+            registry
+        )
+        .unwrap();
+        let indexer_blocks_behind = prometheus::register_int_gauge_vec_with_registry!(
+            "indexer_blocks_behind",
+            "Number of blocks that the furthest behind subgraph deployment of an indexer, unless failed, is behind the chain head",
+            &["indexer", "network"],
+            registry
+        )
+        .unwrap();
+        let throttled_indexer_requests = prometheus::register_int_counter_vec_with_registry!(
+            "throttled_indexer_requests",
+            "Number of requests to indexers delayed by rate limiting",
+            &["host"],
+            registry
+        )
+        .unwrap();
+        let indexer_circuit_breaker_events = prometheus::register_int_counter_vec_with_registry!(
+            "indexer_circuit_breaker_events",
+            "Number of times that an indexer's circuit breaker opened, and of requests to indexers that it skipped",
+            &["indexer", "event"],
+            registry
+        )
+        .unwrap();
+        let failed_queries_truncated = prometheus::register_int_counter_vec_with_registry!(
+            "failed_queries_truncated",
+            "Number of stored failed indexer queries that were truncated",
+            &["query_name"],
+            registry
+        )
+        .unwrap();
+        let failed_queries_redacted = prometheus::register_int_counter_vec_with_registry!(
+            "failed_queries_redacted",
+            "Number of stored failed indexer queries with redacted contents",
+            &["query_name"],
+            registry
+        )
+        .unwrap();
+        let table_rows = prometheus::register_int_gauge_vec_with_registry!(
+            "store_table_rows",
+            "Estimated number of rows in a database table",
+            &["table"],
+            registry
+        )
+        .unwrap();
+        let table_size_bytes = prometheus::register_int_gauge_vec_with_registry!(
+            "store_table_size_bytes",
+            "Size of a database table on disk, including indexes",
+            &["table"],
+            registry
+        )
+        .unwrap();
+        let config_reload_failures = prometheus::register_int_gauge_with_registry!(
+            "config_reload_failures",
+            "Number of consecutive polling cycles that failed to reload the configuration",
+            registry
+        )
+        .unwrap();
+        let leader = prometheus::register_int_gauge_with_registry!(
+            "leader",
+            "Whether this replica is the one that polls indexers (1) or not (0)",
+            registry
+        )
+        .unwrap();
+        let divergence_investigations_launched = prometheus::register_int_counter_with_registry!(
+            "divergence_investigations_launched",
+            "Number of divergence investigations that were requested",
+            registry
+        )
+        .unwrap();
+        let divergence_investigation_errors = prometheus::register_int_counter_vec_with_registry!(
+            "divergence_investigation_errors",
+            "Number of divergence investigations and bisection runs that failed, by error",
+            &["error"],
+            registry
+        )
+        .unwrap();
+        let divergence_investigation_queue_depth =
+            prometheus::register_int_gauge_vec_with_registry!(
+                "divergence_investigation_queue_depth",
+                "Number of divergence investigation requests in the job queue, by status",
+                &["status"],
+                registry
+            )
+            .unwrap();
+        let bisection_steps = prometheus::register_int_counter_with_registry!(
+            "bisection_steps",
+            "Number of blocks at which two indexers' PoIs were compared while bisecting",
+            registry
+        )
+        .unwrap();
+        let bisection_duration_seconds = prometheus::register_histogram_with_registry!(
+            "bisection_duration_seconds",
+            "Duration of bisection runs",
+            // From half a second to about 17 minutes.
+            prometheus::exponential_buckets(0.5, 2.0, 12).unwrap(),
+            registry
+        )
+        .unwrap();
+        let graphql_field_duration_seconds = prometheus::register_histogram_vec_with_registry!(
+            "graphql_field_duration_seconds",
+            "Duration of GraphQL field resolvers, by field",
+            &["field"],
+            registry
+        )
+        .unwrap();
+        let graphql_field_errors = prometheus::register_int_counter_vec_with_registry!(
+            "graphql_field_errors",
+            "Number of errors returned by GraphQL field resolvers, by field",
+            &["field"],
+            registry
+        )
+        .unwrap();
+        let alert_events = prometheus::register_int_counter_vec_with_registry!(
+            "alert_events",
+            "Number of events that match each alert route, by the route's index",
+            &["route", "kind", "severity", "network"],
+            registry
+        )
+        .unwrap();
+        let store = StoreMetrics {
+            query_duration_seconds: prometheus::register_histogram_vec_with_registry!(
+                "store_query_duration_seconds",
+                "Duration of database operations",
+                &["method"],
+                registry
+            )
+            .unwrap(),
+            query_errors: prometheus::register_int_counter_vec_with_registry!(
+                "store_query_errors",
+                "Number of failed database operations",
+                &["method"],
+                registry
+            )
+            .unwrap(),
+            pool_connections: prometheus::register_int_gauge_vec_with_registry!(
+                "store_pool_connections",
+                "Number of database connections in the pool, by state",
+                &["state"],
+                registry
+            )
+            .unwrap(),
+            read_replica_lag_seconds: prometheus::register_gauge_with_registry!(
+                "store_read_replica_lag_seconds",
+                "Replication lag of the read replica, as of the last time it was measured",
+                registry
+            )
+            .unwrap(),
+        };
 
         Self {
             indexing_statuses_requests,
             public_proofs_of_indexing_requests,
+            poi_deadlines_missed,
+            indexer_blocks_behind,
+            throttled_indexer_requests,
+            indexer_circuit_breaker_events,
+            failed_queries_truncated,
+            failed_queries_redacted,
+            table_rows,
+            table_size_bytes,
+            config_reload_failures,
+            leader,
+            divergence_investigations_launched,
+            divergence_investigation_errors,
+            divergence_investigation_queue_depth,
+            bisection_steps,
+            bisection_duration_seconds,
+            graphql_field_duration_seconds,
+            graphql_field_errors,
+            alert_events,
+            store,
+        }
+    }
+
+    pub fn set_table_stats(&self, stats: &[TableStats]) {
+        for table in stats {
+            let label = format!("{}.{}", table.schema_name, table.table_name);
+            self.table_rows
+                .with_label_values(&[&label])
+                .set(table.row_count);
+            self.table_size_bytes
+                .with_label_values(&[&label])
+                .set(table.total_size_bytes);
+        }
+    }
+
+    /// Sets how far each indexer is behind the chain heads, replacing the
+    /// values of indexers and networks that are no longer reported.
+    pub fn set_indexing_lags(&self, lags: &[IndexingLag]) {
+        self.indexer_blocks_behind.reset();
+        for ((indexer, network), blocks_behind) in max_blocks_behind(lags) {
+            self.indexer_blocks_behind
+                .with_label_values(&[&indexer, &network])
+                .set(blocks_behind);
+        }
+    }
+
+    /// Sets the depth of the divergence investigation job queue from the
+    /// number of requests with each status.
+    pub fn set_divergence_investigation_queue_depth(&self, depths: &[(JobStatus, i64)]) {
+        for status in [JobStatus::Queued, JobStatus::Running, JobStatus::Failed] {
+            let label = match status {
+                JobStatus::Queued => "queued",
+                JobStatus::Running => "running",
+                JobStatus::Failed => "failed",
+            };
+            let depth = depths
+                .iter()
+                .find(|(s, _)| *s == status)
+                .map_or(0, |(_, depth)| *depth);
+            self.divergence_investigation_queue_depth
+                .with_label_values(&[label])
+                .set(depth);
         }
     }
 }
@@ -0,0 +1,341 @@
+//! A synthetic network of in-process indexers, so that Graphix can be tried
+//! out without real indexers ([`crate::config::Environment::Simulation`]).
+//! Everything is derived from the seed of the [`SimulationConfig`]: indexer
+//! addresses, subgraph deployment IDs, and PoIs, some of which diverge.
+//!
+//! The simulated chain produces a block every
+//! [`SimulationConfig::block_time_in_msecs`] since [`GENESIS_TIMESTAMP`], so
+//! indexers make progress without keeping any state across polling cycles or
+//! restarts.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use graphix_common_types::{
+    BlockHash, GraphNodeCollectedVersion, IndexerAddress, IpfsCid, PoiBytes, SubgraphHealth,
+};
+use graphix_indexer_client::{
+    BlockPointer, CachedEthereumCall, EntityChanges, IndexerClient, IndexingStatus, PoiRequest,
+    ProofOfIndexing,
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::{SimulationConfig, WatchlistConfig};
+
+/// The Unix timestamp of block 0, 2024-01-01T00:00:00Z.
+pub const GENESIS_TIMESTAMP: i64 = 1_704_067_200;
+
+/// Divergent indexers diverge within this many blocks after genesis, so that
+/// divergences are visible as soon as the simulation starts.
+const DIVERGENCE_WINDOW: u64 = 100_000;
+
+/// The name of the watchlist that makes Graphix investigate the divergences
+/// of all simulated subgraph deployments.
+pub const SIMULATION_WATCHLIST: &str = "simulation";
+
+/// A hash of `parts`, prefixed by the seed of `config`.
+fn seeded_hash(config: &SimulationConfig, parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(config.seed.to_be_bytes());
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+pub fn simulated_deployments(config: &SimulationConfig) -> Vec<IpfsCid> {
+    (0..config.deployments)
+        .map(|i| {
+            IpfsCid::from_sha256_digest(seeded_hash(config, &[b"deployment", &i.to_be_bytes()]))
+        })
+        .collect()
+}
+
+/// The first [`SimulationConfig::divergent_indexers`] indexers are divergent.
+pub fn simulated_indexers(config: &SimulationConfig) -> Vec<Arc<dyn IndexerClient>> {
+    let deployments = simulated_deployments(config);
+
+    (0..config.indexers)
+        .map(|index| {
+            Arc::new(SimulatedIndexer {
+                index,
+                divergent: index < config.divergent_indexers,
+                deployments: deployments.clone(),
+                config: config.clone(),
+            }) as Arc<dyn IndexerClient>
+        })
+        .collect()
+}
+
+/// Watches all simulated subgraph deployments, so that their divergences are
+/// investigated as soon as they're detected.
+pub fn watchlist(config: &SimulationConfig) -> WatchlistConfig {
+    WatchlistConfig {
+        name: SIMULATION_WATCHLIST.to_string(),
+        deployments: simulated_deployments(config)
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        polling_period_in_seconds: None,
+        investigate_divergences: true,
+        webhook_url: None,
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulatedIndexer {
+    index: u32,
+    divergent: bool,
+    deployments: Vec<IpfsCid>,
+    config: SimulationConfig,
+}
+
+impl SimulatedIndexer {
+    /// Indexers lag behind the chain head by up to two blocks.
+    pub fn latest_block(&self) -> u64 {
+        let elapsed_msecs = (Utc::now().timestamp_millis() - GENESIS_TIMESTAMP * 1000).max(0);
+        let head = elapsed_msecs as u64 / self.config.block_time_in_msecs.max(1);
+        head.saturating_sub((self.index % 3) as u64)
+    }
+
+    /// The block from which this indexer's PoIs of `deployment` diverge, if
+    /// it's divergent.
+    pub fn diverging_block(&self, deployment: &IpfsCid) -> Option<u64> {
+        if !self.divergent {
+            return None;
+        }
+
+        let hash = seeded_hash(
+            &self.config,
+            &[
+                b"divergence",
+                &self.index.to_be_bytes(),
+                deployment.to_string().as_bytes(),
+            ],
+        );
+        let number = u64::from_be_bytes(hash[..8].try_into().unwrap());
+        Some(1 + number % DIVERGENCE_WINDOW)
+    }
+
+    pub fn poi(&self, deployment: &IpfsCid, block_number: u64) -> PoiBytes {
+        // All indexers agree on the PoIs of variant 0.
+        let variant = match self.diverging_block(deployment) {
+            Some(diverging_block) if block_number >= diverging_block => self.index + 1,
+            _ => 0,
+        };
+        seeded_hash(
+            &self.config,
+            &[
+                b"poi",
+                deployment.to_string().as_bytes(),
+                &block_number.to_be_bytes(),
+                &variant.to_be_bytes(),
+            ],
+        )
+        .into()
+    }
+
+    fn block(&self, number: u64) -> BlockPointer {
+        let hash: BlockHash = seeded_hash(&self.config, &[b"block", &number.to_be_bytes()])
+            .to_vec()
+            .into();
+        BlockPointer {
+            number,
+            hash: Some(hash),
+        }
+    }
+}
+
+#[async_trait]
+impl IndexerClient for SimulatedIndexer {
+    fn address(&self) -> IndexerAddress {
+        let hash = seeded_hash(&self.config, &[b"indexer", &self.index.to_be_bytes()]);
+        <[u8; 20]>::try_from(&hash[..20]).unwrap().into()
+    }
+
+    fn name(&self) -> Option<Cow<str>> {
+        Some(Cow::Owned(format!("simulated-indexer-{}", self.index)))
+    }
+
+    async fn ping(self: Arc<Self>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn indexing_statuses(self: Arc<Self>) -> anyhow::Result<Vec<IndexingStatus>> {
+        let latest_block = self.block(self.latest_block());
+
+        Ok(self
+            .deployments
+            .iter()
+            .map(|deployment| IndexingStatus {
+                indexer: self.clone(),
+                deployment: deployment.clone(),
+                network: self.config.network.clone(),
+                latest_block: latest_block.clone(),
+                earliest_block_num: 0,
+                health: SubgraphHealth::Healthy,
+                synced: true,
+                paused: Some(false),
+                errors: vec![],
+            })
+            .collect())
+    }
+
+    async fn proofs_of_indexing(
+        self: Arc<Self>,
+        requests: Vec<PoiRequest>,
+    ) -> Vec<ProofOfIndexing> {
+        let latest_block = self.latest_block();
+
+        requests
+            .into_iter()
+            .filter(|request| {
+                request.block_number <= latest_block
+                    && self.deployments.contains(&request.deployment)
+            })
+            .map(|request| ProofOfIndexing {
+                indexer: self.clone(),
+                block: self.block(request.block_number),
+                proof_of_indexing: self.poi(&request.deployment, request.block_number),
+                deployment: request.deployment,
+                network: request.network,
+            })
+            .collect()
+    }
+
+    async fn version(self: Arc<Self>) -> anyhow::Result<GraphNodeCollectedVersion> {
+        Ok(GraphNodeCollectedVersion {
+            version: Some("simulated".to_string()),
+            commit: None,
+            error_response: None,
+            collected_at: Utc::now().naive_utc(),
+        })
+    }
+
+    async fn subgraph_api_versions(
+        self: Arc<Self>,
+        _subgraph_id: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    async fn subgraph_data_source_kinds(
+        self: Arc<Self>,
+        _subgraph_id: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(vec!["ethereum/contract".to_string()])
+    }
+
+    async fn cached_eth_calls(
+        self: Arc<Self>,
+        _network: &str,
+        _block_hash: &[u8],
+    ) -> anyhow::Result<Vec<CachedEthereumCall>> {
+        Ok(vec![])
+    }
+
+    async fn block_cache_contents(
+        self: Arc<Self>,
+        _network: &str,
+        _block_hash: &[u8],
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        Ok(None)
+    }
+
+    async fn entity_changes(
+        self: Arc<Self>,
+        _subgraph_id: &str,
+        _block_number: u64,
+    ) -> anyhow::Result<EntityChanges> {
+        Ok(EntityChanges {
+            updates: Default::default(),
+            deletions: Default::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poi_request(deployment: &IpfsCid, block_number: u64) -> PoiRequest {
+        PoiRequest {
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block_number,
+        }
+    }
+
+    #[test]
+    fn simulations_are_deterministic() {
+        let config = SimulationConfig::default();
+        let addresses = |config: &SimulationConfig| -> Vec<IndexerAddress> {
+            simulated_indexers(config)
+                .iter()
+                .map(|indexer| indexer.address())
+                .collect()
+        };
+
+        assert_eq!(simulated_deployments(&config).len(), 5);
+        assert_eq!(
+            simulated_deployments(&config),
+            simulated_deployments(&config)
+        );
+        assert_eq!(addresses(&config), addresses(&config));
+
+        let other_seed = SimulationConfig {
+            seed: 1,
+            ..SimulationConfig::default()
+        };
+        assert_ne!(
+            simulated_deployments(&config),
+            simulated_deployments(&other_seed)
+        );
+        assert_ne!(addresses(&config), addresses(&other_seed));
+    }
+
+    #[tokio::test]
+    async fn divergent_indexers_diverge_from_a_block() {
+        let config = SimulationConfig {
+            indexers: 3,
+            divergent_indexers: 1,
+            ..SimulationConfig::default()
+        };
+        let deployment = simulated_deployments(&config)[0].clone();
+        let indexer = |index| SimulatedIndexer {
+            index,
+            divergent: index < config.divergent_indexers,
+            deployments: vec![deployment.clone()],
+            config: config.clone(),
+        };
+        let (divergent, honest1, honest2) = (indexer(0), indexer(1), indexer(2));
+        let diverging_block = divergent.diverging_block(&deployment).unwrap();
+
+        assert_eq!(honest1.diverging_block(&deployment), None);
+        assert_eq!(
+            divergent.poi(&deployment, diverging_block - 1),
+            honest1.poi(&deployment, diverging_block - 1)
+        );
+        assert_ne!(
+            divergent.poi(&deployment, diverging_block),
+            honest1.poi(&deployment, diverging_block)
+        );
+        assert_eq!(
+            honest1.poi(&deployment, diverging_block),
+            honest2.poi(&deployment, diverging_block)
+        );
+
+        // Indexers only report PoIs of blocks that they indexed.
+        let latest_block = honest1.latest_block();
+        let pois = Arc::new(honest1)
+            .proofs_of_indexing(vec![
+                poi_request(&deployment, diverging_block),
+                poi_request(&deployment, latest_block + 10),
+            ])
+            .await;
+        assert_eq!(pois.len(), 1);
+        assert_eq!(pois[0].block.number, diverging_block);
+    }
+}
@@ -0,0 +1,133 @@
+//! Publishes Graphix findings to the `subgraph_feed` database schema, for
+//! ingestion by an external subgraph. Enabled with
+//! [`Config::export_subgraph_feed`](crate::config::Config::export_subgraph_feed).
+
+use std::collections::BTreeMap;
+
+use graphix_common_types::{IpfsCid, PoiBytes};
+use graphix_indexer_client::{BlockPointer, ProofOfIndexing};
+use graphix_store::models::{NewFeedPoiConsensus, NewFeedPoiDivergence};
+use graphix_store::Store;
+use tracing::info;
+
+/// Computes the consensus PoI of each subgraph deployment among `pois`, and
+/// writes it to the feed together with any indexers that diverge from it.
+pub async fn publish(store: &Store, pois: &[ProofOfIndexing]) -> anyhow::Result<()> {
+    let (consensus, divergences) = feed_entries(pois)?;
+
+    store.write_subgraph_feed(&consensus, &divergences).await?;
+
+    info!(
+        consensus = consensus.len(),
+        divergences = divergences.len(),
+        "Published findings to the subgraph feed"
+    );
+    Ok(())
+}
+
+/// Same consensus rule as the `poiAgreementRatios` GraphQL query: the most
+/// common PoI is the consensus PoI if more than half of all indexers agree
+/// on it.
+pub(crate) fn feed_entries(
+    pois: &[ProofOfIndexing],
+) -> anyhow::Result<(Vec<NewFeedPoiConsensus>, Vec<NewFeedPoiDivergence>)> {
+    let mut grouped_pois: BTreeMap<(&IpfsCid, &str, &BlockPointer), Vec<&ProofOfIndexing>> =
+        BTreeMap::new();
+    for poi in pois {
+        grouped_pois
+            .entry((&poi.deployment, &poi.network, &poi.block))
+            .or_default()
+            .push(poi);
+    }
+
+    let mut consensus = vec![];
+    let mut divergences = vec![];
+    for ((deployment, _network, block), pois) in grouped_pois {
+        let block_number = block.number.try_into()?;
+
+        let mut poi_counts: BTreeMap<PoiBytes, usize> = BTreeMap::new();
+        for poi in pois.iter() {
+            *poi_counts.entry(poi.proof_of_indexing).or_default() += 1;
+        }
+        let (max_poi, max_poi_count) = poi_counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .expect("PoI groups are never empty");
+        let consensus_poi = (max_poi_count > pois.len() / 2).then_some(max_poi);
+
+        consensus.push(NewFeedPoiConsensus {
+            deployment: deployment.clone(),
+            block_number,
+            block_hash: block.hash.clone(),
+            consensus_poi,
+            indexer_count: pois.len().try_into()?,
+            agreeing_indexer_count: max_poi_count.try_into()?,
+        });
+
+        if let Some(consensus_poi) = consensus_poi {
+            for poi in pois
+                .iter()
+                .filter(|poi| poi.proof_of_indexing != consensus_poi)
+            {
+                divergences.push(NewFeedPoiDivergence {
+                    deployment: deployment.clone(),
+                    block_number,
+                    indexer_address: poi.indexer.address(),
+                    poi: poi.proof_of_indexing,
+                    consensus_poi,
+                });
+            }
+        }
+    }
+
+    Ok((consensus, divergences))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_utils::mocks::MockIndexer;
+
+    fn poi(indexer_name: &str, poi_byte: u8) -> ProofOfIndexing {
+        ProofOfIndexing {
+            indexer: Arc::new(MockIndexer {
+                name: indexer_name.to_string(),
+                deployment_details: vec![],
+                fail_indexing_statuses: false,
+            }),
+            deployment: IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA")
+                .unwrap(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: None,
+            },
+            proof_of_indexing: [poi_byte; 32].into(),
+        }
+    }
+
+    #[test]
+    fn majority_is_consensus() {
+        let pois = [poi("a", 1), poi("b", 1), poi("c", 2)];
+        let (consensus, divergences) = feed_entries(&pois).unwrap();
+
+        assert_eq!(consensus.len(), 1);
+        assert_eq!(consensus[0].consensus_poi, Some([1; 32].into()));
+        assert_eq!(consensus[0].indexer_count, 3);
+        assert_eq!(consensus[0].agreeing_indexer_count, 2);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].indexer_address, pois[2].indexer.address());
+    }
+
+    #[test]
+    fn no_majority_no_divergences() {
+        let pois = [poi("a", 1), poi("b", 2)];
+        let (consensus, divergences) = feed_entries(&pois).unwrap();
+
+        assert_eq!(consensus[0].consensus_poi, None);
+        assert!(divergences.is_empty());
+    }
+}
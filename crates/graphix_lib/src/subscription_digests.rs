@@ -0,0 +1,356 @@
+//! Periodic digests for the webhook URLs of deployment subscriptions
+//! ([`crate::config::AlertsConfig::subscription_digest_interval_in_seconds`]).
+//! Unlike webhooks, which receive every event of their subgraph deployments
+//! as it happens, a digest summarizes the new PoIs, divergences and
+//! investigation outcomes of just the deployments that a consumer subscribed
+//! to, e.g. a dapp developer who only cares about their own subgraphs.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use futures::future;
+use graphix_common_types::inputs::EventsQuery;
+use graphix_common_types::{EventKind, IpfsCid};
+use graphix_store::models::Event;
+use graphix_store::Store;
+use serde::Serialize;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::config::{AlertsConfig, Config};
+use crate::webhooks::WebhookClient;
+
+const EVENTS_PAGE_SIZE: u16 = 100;
+
+/// The name under which the digests of each webhook URL keep track of the
+/// events they summarized.
+fn event_consumer(webhook_url: &str) -> String {
+    format!("subscription-digest:{webhook_url}")
+}
+
+/// The JSON body that the webhook URL of deployment subscriptions is called
+/// with.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionDigest {
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    /// Only the subscribed deployments that anything happened to.
+    pub deployments: Vec<DeploymentDigest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentDigest {
+    pub deployment: IpfsCid,
+    /// How many PoIs were collected from indexers.
+    pub new_pois: i64,
+    /// The [`EventKind::PoiDivergenceDetected`] events.
+    pub divergences: Vec<Event>,
+    /// The [`EventKind::InvestigationCompleted`] events.
+    pub investigation_outcomes: Vec<Event>,
+}
+
+impl SubscriptionDigest {
+    /// Summarizes what happened to `deployments` between `since` and `until`,
+    /// given the number of PoIs collected for each and the events appended to
+    /// the event log.
+    pub fn new(
+        since: NaiveDateTime,
+        until: NaiveDateTime,
+        deployments: &BTreeSet<IpfsCid>,
+        poi_counts: &HashMap<IpfsCid, i64>,
+        events: &[Event],
+    ) -> Self {
+        let mut digests: BTreeMap<&IpfsCid, DeploymentDigest> = deployments
+            .iter()
+            .map(|deployment| {
+                let digest = DeploymentDigest {
+                    deployment: deployment.clone(),
+                    new_pois: poi_counts.get(deployment).copied().unwrap_or(0),
+                    divergences: vec![],
+                    investigation_outcomes: vec![],
+                };
+                (deployment, digest)
+            })
+            .collect();
+        for event in events {
+            let Some(digest) = event
+                .deployment
+                .as_ref()
+                .and_then(|deployment| digests.get_mut(deployment))
+            else {
+                continue;
+            };
+            match event.kind {
+                EventKind::PoiDivergenceDetected => digest.divergences.push(event.clone()),
+                EventKind::InvestigationCompleted => {
+                    digest.investigation_outcomes.push(event.clone())
+                }
+                EventKind::IndexerUnreachable | EventKind::ConfigChanged => {}
+            }
+        }
+
+        Self {
+            since,
+            until,
+            deployments: digests
+                .into_values()
+                .filter(|digest| {
+                    digest.new_pois > 0
+                        || !digest.divergences.is_empty()
+                        || !digest.investigation_outcomes.is_empty()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deployments.is_empty()
+    }
+}
+
+/// How often [`send_subscription_digests_periodically`] checks whether
+/// digests are due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Sends the digests of deployment subscriptions as they're due, separately
+/// from the main loop so that slow webhook URLs don't delay polling. Only the
+/// leader sends digests.
+pub async fn send_subscription_digests_periodically(
+    store: Store,
+    config: watch::Receiver<Config>,
+    leader: watch::Receiver<bool>,
+) {
+    let mut digests = SubscriptionDigests::new();
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+
+        let config = config.borrow().clone();
+        if config.read_only || !*leader.borrow() {
+            continue;
+        }
+        if let Err(err) = digests
+            .send_due(&store, &config.alerts, chrono::Utc::now().naive_utc())
+            .await
+        {
+            error!(error = %err, "Failed to send subscription digests");
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SubscriptionDigests {
+    client: WebhookClient,
+    /// When a digest was last sent to each webhook URL. Only kept in memory,
+    /// so after a restart the PoIs that were collected before it aren't
+    /// counted. Events are, as their cursors are stored.
+    sent: HashMap<String, NaiveDateTime>,
+}
+
+/// A digest that is due, and the event cursor to store once it's sent.
+struct DueDigest {
+    url: String,
+    digest: SubscriptionDigest,
+    cursor: i64,
+}
+
+impl SubscriptionDigests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls each webhook URL of deployment subscriptions whose digest is due
+    /// at `now` with a [`SubscriptionDigest`] of the deployments it's
+    /// subscribed to, several at a time. Failed calls are logged and retried
+    /// in the next call, whose digest then also covers what happened in
+    /// between.
+    pub async fn send_due(
+        &mut self,
+        store: &Store,
+        config: &AlertsConfig,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        let interval = chrono::Duration::seconds(
+            config
+                .subscription_digest_interval_in_seconds
+                .try_into()
+                .unwrap_or(i64::MAX),
+        );
+
+        let mut subscribed: BTreeMap<String, BTreeSet<IpfsCid>> = BTreeMap::new();
+        for subscription in store.deployment_subscriptions(None).await? {
+            subscribed
+                .entry(subscription.webhook_url)
+                .or_default()
+                .insert(subscription.deployment);
+        }
+        self.sent.retain(|url, _| subscribed.contains_key(url));
+
+        let mut due = vec![];
+        for (url, deployments) in subscribed {
+            let consumer = event_consumer(&url);
+            let Some(since) = self.sent.get(&url).copied() else {
+                // The first digest starts from now on.
+                if store.event_cursor(&consumer).await?.is_none() {
+                    let cursor = store.latest_event_id().await?.unwrap_or(0);
+                    store.set_event_cursor(&consumer, cursor).await?;
+                }
+                self.sent.insert(url, now);
+                continue;
+            };
+            if now - since < interval {
+                continue;
+            }
+
+            let cursor = store.event_cursor(&consumer).await?.unwrap_or(0);
+            let (events, cursor) = events_after(store, cursor).await?;
+            let deployments_vec: Vec<IpfsCid> = deployments.iter().cloned().collect();
+            let poi_counts: HashMap<IpfsCid, i64> = store
+                .poi_counts_since(&deployments_vec, since)
+                .await?
+                .into_iter()
+                .collect();
+            let digest = SubscriptionDigest::new(since, now, &deployments, &poi_counts, &events);
+            due.push(DueDigest {
+                url,
+                digest,
+                cursor,
+            });
+        }
+
+        let client = &self.client;
+        let outcomes = future::join_all(due.into_iter().map(|due| async move {
+            if due.digest.is_empty() {
+                return Some(due);
+            }
+            match client.call(&due.url, &due.digest, false).await {
+                Ok(()) => {
+                    info!(
+                        deployments = due.digest.deployments.len(),
+                        "Sent subscription digest"
+                    );
+                    Some(due)
+                }
+                Err(err) => {
+                    // Leave the cursor and the time of the previous digest
+                    // alone, so that the retry covers the same period.
+                    warn!(error = %err, "Failed to send subscription digest");
+                    None
+                }
+            }
+        }))
+        .await;
+
+        for sent in outcomes.into_iter().flatten() {
+            store
+                .set_event_cursor(&event_consumer(&sent.url), sent.cursor)
+                .await?;
+            self.sent.insert(sent.url, now);
+        }
+
+        Ok(())
+    }
+}
+
+/// The divergence and investigation events after `cursor`, and the cursor to
+/// continue from.
+async fn events_after(store: &Store, mut cursor: i64) -> anyhow::Result<(Vec<Event>, i64)> {
+    let mut events = vec![];
+    loop {
+        let filter = EventsQuery {
+            kinds: vec![
+                EventKind::PoiDivergenceDetected,
+                EventKind::InvestigationCompleted,
+            ],
+            after_id: Some(cursor),
+            limit: Some(EVENTS_PAGE_SIZE),
+            ..Default::default()
+        };
+        let page = store.events(filter).await?;
+        let Some(last_event) = page.last() else {
+            break;
+        };
+
+        cursor = last_event.id;
+        let page_len = page.len();
+        events.extend(page);
+        if page_len < EVENTS_PAGE_SIZE as usize {
+            break;
+        }
+    }
+
+    Ok((events, cursor))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const DEPLOYMENT_1: &str = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+    const DEPLOYMENT_2: &str = "QmYzsCjrVwwXtdsNm3PZVNziLGmb9o513GUzkq5wwhgXDT";
+    const DEPLOYMENT_3: &str = "QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7";
+
+    fn cid(s: &str) -> IpfsCid {
+        IpfsCid::from_str(s).unwrap()
+    }
+
+    fn ids(events: &[Event]) -> Vec<i64> {
+        events.iter().map(|event| event.id).collect()
+    }
+
+    fn event(id: i64, kind: EventKind, deployment: &str) -> Event {
+        Event {
+            id,
+            kind,
+            deployment: Some(cid(deployment)),
+            payload: serde_json::json!({}),
+            created_at: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn digest_only_covers_subscribed_deployments() {
+        let deployments = BTreeSet::from([cid(DEPLOYMENT_1), cid(DEPLOYMENT_2)]);
+        let poi_counts = HashMap::from([(cid(DEPLOYMENT_1), 3), (cid(DEPLOYMENT_3), 5)]);
+        let events = vec![
+            event(1, EventKind::PoiDivergenceDetected, DEPLOYMENT_2),
+            event(2, EventKind::PoiDivergenceDetected, DEPLOYMENT_3),
+            event(3, EventKind::InvestigationCompleted, DEPLOYMENT_2),
+        ];
+
+        let digest = SubscriptionDigest::new(
+            NaiveDateTime::default(),
+            NaiveDateTime::default(),
+            &deployments,
+            &poi_counts,
+            &events,
+        );
+
+        assert_eq!(digest.deployments.len(), 2);
+        assert_eq!(digest.deployments[0].deployment, cid(DEPLOYMENT_1));
+        assert_eq!(digest.deployments[0].new_pois, 3);
+        assert!(digest.deployments[0].divergences.is_empty());
+        assert_eq!(digest.deployments[1].deployment, cid(DEPLOYMENT_2));
+        assert_eq!(digest.deployments[1].new_pois, 0);
+        assert_eq!(ids(&digest.deployments[1].divergences), vec![1]);
+        assert_eq!(ids(&digest.deployments[1].investigation_outcomes), vec![3]);
+    }
+
+    #[test]
+    fn quiet_deployments_are_left_out() {
+        let deployments = BTreeSet::from([cid(DEPLOYMENT_1)]);
+        let digest = SubscriptionDigest::new(
+            NaiveDateTime::default(),
+            NaiveDateTime::default(),
+            &deployments,
+            &HashMap::new(),
+            &[event(1, EventKind::PoiDivergenceDetected, DEPLOYMENT_2)],
+        );
+
+        assert!(digest.is_empty());
+    }
+}
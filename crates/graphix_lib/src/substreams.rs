@@ -0,0 +1,223 @@
+//! Substreams-powered subgraphs don't have meaningful PoIs: some indexers
+//! report null (all-zero) PoIs for them, and close their allocations with null
+//! PoIs. Such deployments are tagged as substreams-powered by asking an
+//! indexer about their data sources, so that their null PoIs aren't mistaken
+//! for divergences.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use graphix_common_types::inputs::IndexersQuery;
+use graphix_common_types::{HexString, IndexerAddress, IpfsCid, PoiBytes};
+use graphix_indexer_client::{IndexerClient, ProofOfIndexing, SUBSTREAMS_DATA_SOURCE_KIND};
+use graphix_store::models::{self, IntId};
+use graphix_store::Store;
+use tokio::sync::watch;
+use tracing::*;
+
+use crate::config::Config;
+
+/// The PoI that indexers report when they don't have one.
+pub const NULL_POI: PoiBytes = HexString([0; 32]);
+
+/// How often to look for subgraph deployments that aren't tagged yet.
+const POLLING_PERIOD: Duration = Duration::from_secs(60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many subgraph deployments are looked up at the same time.
+const MAX_CONCURRENT_REQUESTS: usize = 16;
+
+/// Tags new subgraph deployments as substreams-powered, or not, unless in
+/// read-only mode. Runs forever.
+pub async fn tag_substreams_deployments(
+    store: Store,
+    config: watch::Receiver<Config>,
+    indexers: watch::Receiver<Vec<Arc<dyn IndexerClient>>>,
+) {
+    loop {
+        if !config.borrow().read_only {
+            let indexers = indexers.borrow().clone();
+            match tag_deployments(&store, &indexers).await {
+                Ok(tagged) => debug!(tagged, "Tagged substreams-powered subgraph deployments"),
+                Err(err) => error!(error = %err, "Failed to tag substreams-powered deployments"),
+            }
+        }
+
+        tokio::time::sleep(POLLING_PERIOD).await;
+    }
+}
+
+/// Asks the indexers that index each untagged subgraph deployment about its
+/// data sources, until one of them responds, and tags the deployment
+/// accordingly. Returns how many deployments were tagged.
+pub async fn tag_deployments(
+    store: &Store,
+    indexers: &[Arc<dyn IndexerClient>],
+) -> anyhow::Result<usize> {
+    let deployments = store.sg_deployments_with_unknown_kind().await?;
+    if deployments.is_empty() {
+        return Ok(0);
+    }
+
+    let addresses: HashMap<IntId, IndexerAddress> = store
+        .indexers(IndexersQuery::default())
+        .await?
+        .into_iter()
+        .map(|indexer| (indexer.id, indexer.address))
+        .collect();
+    let cids: HashMap<IntId, IpfsCid> = store
+        .sg_deployments(Default::default())
+        .await?
+        .into_iter()
+        .map(|deployment| (deployment.id, deployment.cid))
+        .collect();
+    let mut indexers_by_deployment: HashMap<&IpfsCid, HashSet<IndexerAddress>> = HashMap::new();
+    for status in store.indexing_statuses(&deployments, None, None).await? {
+        if let (Some(cid), Some(address)) = (
+            cids.get(&status.sg_deployment_id),
+            addresses.get(&status.indexer_id),
+        ) {
+            indexers_by_deployment
+                .entry(cid)
+                .or_default()
+                .insert(*address);
+        }
+    }
+
+    let tagged = futures::stream::iter(deployments)
+        .map(|deployment| {
+            let candidates: Vec<_> = indexers
+                .iter()
+                .filter(|indexer| {
+                    indexers_by_deployment
+                        .get(&deployment)
+                        .is_some_and(|addresses| addresses.contains(&indexer.address()))
+                })
+                .cloned()
+                .collect();
+            async move {
+                let substreams = is_substreams(&candidates, &deployment).await?;
+                store
+                    .set_sg_deployment_substreams(&deployment, substreams)
+                    .await
+                    .ok()
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_REQUESTS)
+        .filter_map(|tagged| async move { tagged })
+        .count()
+        .await;
+
+    Ok(tagged)
+}
+
+/// Whether `deployment` is substreams-powered according to the first of
+/// `indexers` that responds, if any.
+async fn is_substreams(indexers: &[Arc<dyn IndexerClient>], deployment: &IpfsCid) -> Option<bool> {
+    let subgraph_id = deployment.to_string();
+    for indexer in indexers {
+        let request = indexer.clone().subgraph_data_source_kinds(&subgraph_id);
+        match tokio::time::timeout(REQUEST_TIMEOUT, request).await {
+            Ok(Ok(kinds)) => {
+                return Some(kinds.iter().any(|kind| kind == SUBSTREAMS_DATA_SOURCE_KIND));
+            }
+            Ok(Err(err)) => debug!(
+                indexer = %indexer.address(),
+                %deployment,
+                error = %err,
+                "Failed to query subgraph features"
+            ),
+            Err(_) => debug!(
+                indexer = %indexer.address(),
+                %deployment,
+                "Timed out querying subgraph features"
+            ),
+        }
+    }
+
+    None
+}
+
+/// Leaves out the null PoIs of substreams-powered subgraph deployments, which
+/// are expected and not comparable with other PoIs.
+pub fn without_expected_nulls(
+    pois: &[ProofOfIndexing],
+    substreams_deployments: &HashSet<IpfsCid>,
+) -> Vec<ProofOfIndexing> {
+    pois.iter()
+        .filter(|poi| {
+            !(poi.proof_of_indexing == NULL_POI && substreams_deployments.contains(&poi.deployment))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The live PoIs of a subgraph deployment that can be compared with each
+/// other, i.e. without null PoIs if it's substreams-powered.
+pub async fn comparable_live_pois(
+    store: &Store,
+    deployment: &IpfsCid,
+) -> anyhow::Result<Vec<models::Poi>> {
+    let mut live_pois = store
+        .live_pois(
+            None,
+            None,
+            Some(std::slice::from_ref(deployment)),
+            None,
+            None,
+            None,
+        )
+        .await?;
+    if store
+        .substreams_sg_deployments()
+        .await?
+        .contains(deployment)
+    {
+        live_pois.retain(|poi| poi.poi != NULL_POI);
+    }
+
+    Ok(live_pois)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use graphix_indexer_client::BlockPointer;
+
+    use super::*;
+    use crate::test_utils::mocks::MockIndexer;
+
+    #[test]
+    fn null_pois_are_only_left_out_for_substreams_deployments() {
+        let indexer: Arc<dyn IndexerClient> = Arc::new(MockIndexer {
+            name: "indexer".to_string(),
+            deployment_details: vec![],
+            fail_indexing_statuses: false,
+        });
+        let substreams =
+            IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+        let other = IpfsCid::from_str("QmYiiCtcXmSHXN3m2nyqLaTM7zi81KjVdZ9WXkcrCKrkjr").unwrap();
+        let poi = |deployment: &IpfsCid, poi: PoiBytes| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 1,
+                hash: None,
+            },
+            proof_of_indexing: poi,
+        };
+
+        let pois = [
+            poi(&substreams, NULL_POI),
+            poi(&substreams, [1; 32].into()),
+            poi(&other, NULL_POI),
+        ];
+        let comparable = without_expected_nulls(&pois, &[substreams.clone()].into());
+        assert_eq!(comparable, pois[1..]);
+    }
+}
@@ -0,0 +1,425 @@
+//! An in-process mock of `graph-node`'s index node server, so that tests can
+//! exercise [`RealIndexer`] and everything built on top of it without hitting
+//! real indexers.
+//!
+//! Responses are deterministic: block hashes are derived from block numbers,
+//! and PoIs from the deployment and block number (see [`canonical_poi`]).
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use graphix_common_types::{BlockHash, IndexerAddress, IpfsCid, PoiBytes, SubgraphHealth};
+use graphix_indexer_client::{
+    BlockPointer, CachedEthereumCall, EntityChanges, IndexerAuth, IndexerClient, RealIndexer,
+    SubgraphError,
+};
+use prometheus_exporter::prometheus::{IntCounterVec, Opts};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use url::Url;
+
+/// A subgraph deployment served by a [`MockGraphNode`].
+#[derive(Clone, Debug)]
+pub struct MockDeployment {
+    pub deployment: IpfsCid,
+    pub network: String,
+    pub earliest_block: u64,
+    pub latest_block: u64,
+    pub health: SubgraphHealth,
+    pub synced: bool,
+    pub paused: Option<bool>,
+    /// The fatal error, if any, must come first.
+    pub errors: Vec<SubgraphError>,
+    /// PoIs differ from [`canonical_poi`] from this block onwards, as if the
+    /// indexer had diverged from other indexers there.
+    pub diverges_at: Option<u64>,
+}
+
+impl MockDeployment {
+    /// A healthy, synced deployment that has indexed `earliest_block..=latest_block`.
+    pub fn new(
+        deployment: IpfsCid,
+        network: impl Into<String>,
+        earliest_block: u64,
+        latest_block: u64,
+    ) -> Self {
+        Self {
+            deployment,
+            network: network.into(),
+            earliest_block,
+            latest_block,
+            health: SubgraphHealth::Healthy,
+            synced: true,
+            paused: Some(false),
+            errors: vec![],
+            diverges_at: None,
+        }
+    }
+
+    pub fn diverging_at(mut self, block_number: u64) -> Self {
+        self.diverges_at = Some(block_number);
+        self
+    }
+
+    /// The PoI that this deployment has for `block_number`, if it has indexed
+    /// that block.
+    pub fn poi(&self, block_number: u64) -> Option<PoiBytes> {
+        if !(self.earliest_block..=self.latest_block).contains(&block_number) {
+            return None;
+        }
+
+        let canonical = canonical_poi(&self.deployment, block_number);
+        match self.diverges_at {
+            Some(diverges_at) if block_number >= diverges_at => {
+                let mut hasher = Sha256::new();
+                hasher.update(b"diverged");
+                hasher.update(canonical.0);
+                Some(<[u8; 32]>::from(hasher.finalize()).into())
+            }
+            _ => Some(canonical),
+        }
+    }
+}
+
+/// The PoI that all non-diverging [`MockDeployment`]s agree on.
+pub fn canonical_poi(deployment: &IpfsCid, block_number: u64) -> PoiBytes {
+    let mut hasher = Sha256::new();
+    hasher.update(deployment.to_string().as_bytes());
+    hasher.update(block_number.to_be_bytes());
+    <[u8; 32]>::from(hasher.finalize()).into()
+}
+
+/// The hash of block `number` on all networks.
+pub fn mock_block_hash(number: u64) -> BlockHash {
+    let mut buf = [0u8; 32];
+    buf[24..32].clone_from_slice(&number.to_be_bytes());
+    buf.to_vec().into()
+}
+
+/// What the server responds with. Block data, cached calls and entity changes
+/// are rendered to JSON as soon as they're configured.
+#[derive(Default)]
+struct MockState {
+    deployments: Vec<MockDeployment>,
+    block_data: HashMap<(String, String), Value>,
+    cached_eth_calls: HashMap<(String, String), Value>,
+    entity_changes: HashMap<(String, u64), Value>,
+    api_versions: Vec<String>,
+    unavailable: bool,
+    required_header: Option<(String, String)>,
+    poi_response_delay: Option<Duration>,
+}
+
+/// An HTTP server that answers the same GraphQL queries as `graph-node`'s
+/// index node server, on a random local port. The server is shut down when
+/// the [`MockGraphNode`] is dropped.
+pub struct MockGraphNode {
+    name: String,
+    local_addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    server: JoinHandle<()>,
+}
+
+impl MockGraphNode {
+    pub async fn start(name: impl Into<String>, deployments: Vec<MockDeployment>) -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            deployments,
+            ..Default::default()
+        }));
+        let router = Router::new()
+            .route("/status", post(handle_query))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind mock graph-node");
+        let local_addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+
+        Self {
+            name: name.into(),
+            local_addr,
+            state,
+            server,
+        }
+    }
+
+    /// The URL of the index node server, i.e. `index_node_endpoint` in the
+    /// configuration.
+    pub fn status_url(&self) -> Url {
+        format!("http://{}/status", self.local_addr)
+            .parse()
+            .unwrap()
+    }
+
+    /// Derived from the name, the same way as [`super::mocks::MockIndexer`]
+    /// does.
+    pub fn address(&self) -> IndexerAddress {
+        let mut addr = self.name.clone().into_bytes();
+        addr.resize(20, 0);
+        <[u8; 20]>::try_from(addr).unwrap().into()
+    }
+
+    /// A [`RealIndexer`] that talks to this server.
+    pub fn indexer(&self) -> Arc<dyn IndexerClient> {
+        self.indexer_with_auth(None)
+    }
+
+    /// A [`RealIndexer`] that talks to this server, authenticating its
+    /// requests with `auth`.
+    pub fn indexer_with_auth(&self, auth: Option<IndexerAuth>) -> Arc<dyn IndexerClient> {
+        Arc::new(
+            RealIndexer::new(
+                Some(self.name.clone()),
+                self.address(),
+                self.status_url().to_string(),
+                IntCounterVec::new(Opts::new("foo", "bar"), &["a", "b"]).unwrap(),
+            )
+            .with_auth(auth),
+        )
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<MockState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Adds or replaces a deployment.
+    pub fn set_deployment(&self, deployment: MockDeployment) {
+        let deployments = &mut self.state().deployments;
+        deployments.retain(|d| d.deployment != deployment.deployment);
+        deployments.push(deployment);
+    }
+
+    /// Simulates indexing progress of `deployment`.
+    pub fn set_latest_block(&self, deployment: &IpfsCid, latest_block: u64) {
+        for d in self.state().deployments.iter_mut() {
+            if &d.deployment == deployment {
+                d.latest_block = latest_block;
+            }
+        }
+    }
+
+    /// Makes the server respond with HTTP 401 to all requests without this
+    /// header and value.
+    pub fn set_required_header(&self, name: &str, value: &str) {
+        self.state().required_header = Some((name.to_lowercase(), value.to_string()));
+    }
+
+    /// Makes the server respond with HTTP 503 to all requests while `true`.
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.state().unavailable = unavailable;
+    }
+
+    /// Makes the server respond to PoI queries only after `delay`.
+    pub fn set_poi_response_delay(&self, delay: Duration) {
+        self.state().poi_response_delay = Some(delay);
+    }
+
+    pub fn set_api_versions(&self, versions: Vec<String>) {
+        self.state().api_versions = versions;
+    }
+
+    pub fn set_block_data(&self, network: &str, block_hash: &[u8], block_data: Value) {
+        self.state()
+            .block_data
+            .insert((network.to_string(), hex::encode(block_hash)), block_data);
+    }
+
+    pub fn set_cached_eth_calls(
+        &self,
+        network: &str,
+        block: &BlockPointer,
+        calls: &[CachedEthereumCall],
+    ) {
+        let block_hash = block.hash.clone().expect("cached calls need a block hash");
+        let calls = calls
+            .iter()
+            .map(|call| {
+                json!({
+                    "idHash": bytes(&call.id_hash),
+                    "block": { "number": block.number.to_string(), "hash": block_hash.to_string() },
+                    "contractAddress": bytes(&call.contract_address),
+                    "returnValue": bytes(&call.return_value),
+                })
+            })
+            .collect();
+        self.state().cached_eth_calls.insert(
+            (network.to_string(), hex::encode(&block_hash.0)),
+            Value::Array(calls),
+        );
+    }
+
+    pub fn set_entity_changes(
+        &self,
+        deployment: &IpfsCid,
+        block_number: u64,
+        changes: &EntityChanges,
+    ) {
+        let updates: Vec<Value> = changes
+            .updates
+            .iter()
+            .map(|(entity_type, entities)| json!({ "type": entity_type, "entities": entities }))
+            .collect();
+        let deletions: Vec<Value> = changes
+            .deletions
+            .iter()
+            .map(|(entity_type, ids)| json!({ "type": entity_type, "entities": ids }))
+            .collect();
+        self.state().entity_changes.insert(
+            (deployment.to_string(), block_number),
+            json!({ "updates": updates, "deletions": deletions }),
+        );
+    }
+}
+
+impl Drop for MockGraphNode {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+fn bytes(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn block_json(number: u64) -> Value {
+    json!({ "number": number.to_string(), "hash": mock_block_hash(number).to_string() })
+}
+
+fn error_json(error: &SubgraphError) -> Value {
+    json!({
+        "message": error.message,
+        "block": error.block.as_ref().map(|block| json!({
+            "number": block.number.to_string(),
+            "hash": block.hash.as_ref().map_or_else(|| mock_block_hash(block.number), Clone::clone).to_string(),
+        })),
+        "handler": error.handler,
+        "deterministic": error.deterministic,
+    })
+}
+
+fn indexing_status_json(deployment: &MockDeployment) -> Value {
+    let health = match deployment.health {
+        SubgraphHealth::Healthy => "healthy",
+        SubgraphHealth::Unhealthy => "unhealthy",
+        SubgraphHealth::Failed => "failed",
+    };
+    let (fatal_error, non_fatal_errors) = match deployment.errors.split_first() {
+        Some((first, rest)) if first.fatal => (Some(error_json(first)), rest),
+        _ => (None, deployment.errors.as_slice()),
+    };
+
+    json!({
+        "subgraph": deployment.deployment.to_string(),
+        "synced": deployment.synced,
+        "health": health,
+        "paused": deployment.paused,
+        "fatalError": fatal_error,
+        "nonFatalErrors": non_fatal_errors.iter().map(error_json).collect::<Vec<_>>(),
+        "chains": [{
+            "__typename": "EthereumIndexingStatus",
+            "network": deployment.network,
+            "latestBlock": block_json(deployment.latest_block),
+            "earliestBlock": { "number": deployment.earliest_block.to_string(), "hash": "0x0" },
+        }],
+    })
+}
+
+/// Answers a GraphQL request based on its operation name, which is enough to
+/// tell apart the queries that [`RealIndexer`] sends.
+async fn handle_query(
+    State(state): State<Arc<Mutex<MockState>>>,
+    headers: HeaderMap,
+    Json(request): Json<Value>,
+) -> Response {
+    let poi_response_delay = state.lock().unwrap().poi_response_delay;
+    if let Some(delay) = poi_response_delay {
+        if request["operationName"] == "ProofsOfIndexing" {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    let state = state.lock().unwrap();
+    if state.unavailable {
+        return (StatusCode::SERVICE_UNAVAILABLE, "unavailable").into_response();
+    }
+    if let Some((name, value)) = &state.required_header {
+        if headers.get(name).and_then(|v| v.to_str().ok()) != Some(value) {
+            return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
+        }
+    }
+
+    let variables = &request["variables"];
+    let var = |name: &str| variables[name].as_str().unwrap_or_default().to_string();
+    let data = match request["operationName"].as_str().unwrap_or_default() {
+        "Typename" => json!({ "__typename": "Query" }),
+        "IndexingStatuses" => json!({
+            "indexingStatuses": state.deployments.iter().map(indexing_status_json).collect::<Vec<_>>(),
+        }),
+        "ProofsOfIndexing" => {
+            let requests = variables["requests"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            // Like `graph-node`, PoIs that aren't available are left out.
+            let pois: Vec<Value> = requests
+                .iter()
+                .filter_map(|request| {
+                    let deployment = request["deployment"].as_str()?;
+                    let block_number: u64 = request["blockNumber"].as_str()?.parse().ok()?;
+                    let poi = state
+                        .deployments
+                        .iter()
+                        .find(|d| d.deployment.to_string() == deployment)?
+                        .poi(block_number)?;
+                    Some(json!({
+                        "deployment": deployment,
+                        "block": block_json(block_number),
+                        "proofOfIndexing": poi.to_string(),
+                    }))
+                })
+                .collect();
+            json!({ "publicProofsOfIndexing": pois })
+        }
+        "IndexerVersion" => json!({
+            "version": { "version": "0.0.0", "commit": "no-commit-hash" },
+        }),
+        "SubgraphApiVersions" => json!({
+            "apiVersions": state.api_versions.iter().map(|version| json!({ "version": version })).collect::<Vec<_>>(),
+        }),
+        "BlockData" => json!({
+            "blockData": state.block_data.get(&(var("network"), var("blockHash"))),
+        }),
+        "CachedEthereumCalls" => json!({
+            "cachedEthereumCalls": state.cached_eth_calls.get(&(var("network"), var("blockHash"))),
+        }),
+        "EntityChangesInBlock" => {
+            let block_number = variables["blockNumber"].as_u64().unwrap_or_default();
+            let changes = state
+                .entity_changes
+                .get(&(var("subgraphId"), block_number))
+                .cloned()
+                .unwrap_or_else(|| json!({ "updates": [], "deletions": [] }));
+            json!({ "entityChangesInBlock": changes })
+        }
+        operation => {
+            return Json(json!({
+                "errors": [{ "message": format!("Unknown operation \"{operation}\"") }],
+            }))
+            .into_response()
+        }
+    };
+
+    Json(json!({ "data": data })).into_response()
+}
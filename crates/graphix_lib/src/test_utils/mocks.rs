@@ -3,10 +3,12 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
-use graphix_common_types::{GraphNodeCollectedVersion, IndexerAddress, IpfsCid, PoiBytes};
+use graphix_common_types::{
+    GraphNodeCollectedVersion, IndexerAddress, IpfsCid, PoiBytes, SubgraphHealth,
+};
 use graphix_indexer_client::{
     BlockPointer, CachedEthereumCall, EntityChanges, IndexerClient, IndexingStatus, PoiRequest,
-    ProofOfIndexing,
+    ProofOfIndexing, SUBSTREAMS_DATA_SOURCE_KIND,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -16,6 +18,7 @@ pub struct DeploymentDetails {
     pub latest_block: BlockPointer,
     pub canonical_pois: Vec<PartialProofOfIndexing>,
     pub earliest_block_num: u64,
+    pub substreams: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -51,6 +54,10 @@ impl IndexerClient for MockIndexer {
                     network: details.network,
                     latest_block: details.latest_block,
                     earliest_block_num: details.earliest_block_num,
+                    health: SubgraphHealth::Healthy,
+                    synced: true,
+                    paused: Some(false),
+                    errors: vec![],
                 })
                 .collect())
         }
@@ -78,11 +85,12 @@ impl IndexerClient for MockIndexer {
                     .canonical_pois
                     .iter()
                     .find(|poi| poi.block.number.eq(&request.block_number))
-                    .map(|poi| (detail, poi))
+                    .map(|poi| (request, detail, poi))
             })
-            .map(|(deployment_detail, poi)| ProofOfIndexing {
+            .map(|(request, deployment_detail, poi)| ProofOfIndexing {
                 indexer: self.clone(),
                 deployment: deployment_detail.deployment.clone(),
+                network: request.network,
                 block: poi.block.clone(),
                 proof_of_indexing: poi.proof_of_indexing,
             })
@@ -105,6 +113,24 @@ impl IndexerClient for MockIndexer {
         Ok(vec![])
     }
 
+    async fn subgraph_data_source_kinds(
+        self: Arc<Self>,
+        subgraph_id: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let details = self
+            .deployment_details
+            .iter()
+            .find(|details| details.deployment.to_string() == subgraph_id)
+            .ok_or_else(|| anyhow!("unknown subgraph {subgraph_id}"))?;
+        let kind = if details.substreams {
+            SUBSTREAMS_DATA_SOURCE_KIND
+        } else {
+            "ethereum/contract"
+        };
+
+        Ok(vec![kind.to_string()])
+    }
+
     async fn cached_eth_calls(
         self: Arc<Self>,
         _network: &str,
@@ -1,4 +1,5 @@
 pub mod gen;
+pub mod graph_node;
 pub mod mocks;
 
 use std::env;
@@ -24,11 +25,6 @@ pub mod deployments {
     pub const FUSE_TO_ETHEREUM_AMB: &str = "QmYU3Exnta8H52vWUFhGQi6Qm8LhXr5LqmypNrLba8rRem";
 }
 
-pub mod indexers {
-    pub const ARB1_DATA_NEXUS: &str = "https://arb-service.thegraph.datanexus.tech/";
-    pub const ARB1_ELLIPFRA: &str = "https://graph-l2prod.ellipfra.com/";
-}
-
 pub static TEST_SEED: Lazy<u64> = Lazy::new(|| {
     let seed = env::var("TEST_SEED")
         .map(|seed| seed.parse().expect("Invalid TEST_SEED value"))
@@ -55,6 +51,9 @@ pub fn test_indexer_from_url(url: impl Into<String>) -> Arc<impl IndexerClient>
         name: Some(url.host().unwrap().to_string()),
         address,
         index_node_endpoint: url.join("status").unwrap(),
+        rate_limit: None,
+        auth: None,
+        polling_period_in_seconds: None,
     };
     Arc::new(RealIndexer::new(
         conf.name,
@@ -0,0 +1,145 @@
+//! Watchlists are named sets of subgraph deployments that are monitored more
+//! closely than the others. Their indexers can be polled more often, their
+//! divergences are investigated as soon as they're detected, and their events
+//! can be sent to a dedicated webhook URL. Watchlists are either defined in
+//! the configuration or created with the `setWatchlist` GraphQL mutation.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Context;
+use graphix_common_types::IpfsCid;
+use graphix_store::models::{self, NewWatchlist};
+use graphix_store::Store;
+use tracing::*;
+use uuid::Uuid;
+
+use crate::campaigns::investigate_deployment;
+use crate::config::{Config, Environment, WatchlistConfig};
+use crate::simulation;
+
+/// The priority of divergence investigations that watchlists launch, so that
+/// they run before those that were launched manually or by campaigns with the
+/// default priority of 0.
+pub const WATCHLIST_INVESTIGATION_PRIORITY: i32 = 10;
+
+/// All watchlists of this instance, as of the current polling cycle.
+#[derive(Debug, Clone, Default)]
+pub struct Watchlists(Vec<models::Watchlist>);
+
+impl Watchlists {
+    pub fn new(watchlists: Vec<models::Watchlist>) -> Self {
+        Self(watchlists)
+    }
+
+    /// Whether any watchlist contains `deployment`.
+    pub fn contains(&self, deployment: &IpfsCid) -> bool {
+        self.containing(deployment).next().is_some()
+    }
+
+    /// The shortest polling period of the watchlists that contain
+    /// `deployment`, if any.
+    pub fn polling_period(&self, deployment: &IpfsCid) -> Option<Duration> {
+        self.containing(deployment)
+            .filter_map(|watchlist| watchlist.polling_period_in_seconds)
+            .map(|seconds| Duration::from_secs(seconds.max(1) as u64))
+            .min()
+    }
+
+    /// Whether divergences of `deployment` are investigated as soon as
+    /// they're detected.
+    pub fn investigates(&self, deployment: &IpfsCid) -> bool {
+        self.containing(deployment)
+            .any(|watchlist| watchlist.investigate_divergences)
+    }
+
+    /// The watchlists that contain `deployment` and have a webhook URL, along
+    /// with that URL.
+    pub fn webhook_urls<'a>(
+        &'a self,
+        deployment: &'a IpfsCid,
+    ) -> impl Iterator<Item = (&'a models::Watchlist, &'a str)> + 'a {
+        self.containing(deployment)
+            .filter_map(|watchlist| Some((watchlist, watchlist.webhook_url.as_deref()?)))
+    }
+
+    fn containing<'a>(
+        &'a self,
+        deployment: &'a IpfsCid,
+    ) -> impl Iterator<Item = &'a models::Watchlist> + 'a {
+        self.0
+            .iter()
+            .filter(move |watchlist| watchlist.deployments().any(|d| d == deployment))
+    }
+}
+
+/// Stores the watchlists of `config`, and deletes those that were removed
+/// from it since. In the simulation environment, that includes
+/// [`simulation::watchlist`].
+pub async fn sync_watchlists(store: &Store, config: &Config) -> anyhow::Result<()> {
+    let simulation_watchlist = (config.environment == Environment::Simulation)
+        .then(|| simulation::watchlist(&config.simulation));
+    let watchlists = config
+        .watchlists
+        .iter()
+        .chain(&simulation_watchlist)
+        .map(new_watchlist)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    store.sync_config_watchlists(&watchlists).await
+}
+
+fn new_watchlist(config: &WatchlistConfig) -> anyhow::Result<NewWatchlist> {
+    let polling_period_in_seconds = config
+        .polling_period_in_seconds
+        .map(i32::try_from)
+        .transpose()
+        .with_context(|| format!("polling period of watchlist `{}` is too long", config.name))?;
+
+    let deployments = config
+        .deployments
+        .iter()
+        .map(|deployment| {
+            IpfsCid::from_str(deployment).with_context(|| {
+                format!(
+                    "invalid IPFS CID in watchlist `{}`: {deployment}",
+                    config.name
+                )
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(NewWatchlist {
+        name: config.name.clone(),
+        deployments,
+        polling_period_in_seconds,
+        investigate_divergences: config.investigate_divergences,
+        webhook_url: config.webhook_url.as_ref().map(ToString::to_string),
+        from_config: true,
+        api_key_public_prefix: None,
+    })
+}
+
+/// Launches divergence investigations for those of `deployments` that are on
+/// a watchlist which investigates divergences.
+pub async fn investigate_divergences(
+    store: &Store,
+    watchlists: &Watchlists,
+    deployments: &[IpfsCid],
+) -> anyhow::Result<Vec<Uuid>> {
+    let mut uuids = vec![];
+    for deployment in deployments
+        .iter()
+        .filter(|deployment| watchlists.investigates(deployment))
+    {
+        let launched =
+            investigate_deployment(store, deployment, false, WATCHLIST_INVESTIGATION_PRIORITY)
+                .await?;
+        info!(
+            %deployment,
+            investigations = launched.len(),
+            "Launched divergence investigations for watchlisted subgraph deployment"
+        );
+        uuids.extend(launched);
+    }
+    Ok(uuids)
+}
@@ -0,0 +1,352 @@
+//! Delivers events from the event log to the webhooks that API key holders
+//! registered through the GraphQL API, for the subgraph deployments they care
+//! about, and to the webhook URLs of watchlists.
+
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use graphix_common_types::inputs::EventsQuery;
+use graphix_store::Store;
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+use url::{Host, Url};
+
+use crate::watchlists::Watchlists;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many webhook calls are in flight at most, across polling cycles.
+const MAX_CONCURRENT_CALLS: usize = 16;
+
+/// The name under which webhook delivery keeps track of the events it
+/// processed.
+const EVENT_CONSUMER: &str = "webhooks";
+const EVENTS_PAGE_SIZE: u16 = 100;
+
+/// Calls webhooks from spawned tasks, so that slow or unresponsive webhooks
+/// don't hold up the main loop, with at most [`MAX_CONCURRENT_CALLS`] calls in
+/// flight.
+#[derive(Clone)]
+pub struct WebhookClient {
+    client: reqwest::Client,
+    permits: Arc<Semaphore>,
+}
+
+impl Default for WebhookClient {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_CALLS)),
+        }
+    }
+}
+
+impl WebhookClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `POST`s `body` as JSON to `url` in the background, see
+    /// [`WebhookClient::call`]. `recipient` describes the webhook in logs,
+    /// e.g. `webhook 3`. Failed calls are logged and not retried.
+    pub fn spawn_call(&self, url: String, body: &impl Serialize, recipient: String, trusted: bool) {
+        let body = match serde_json::to_value(body) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!(recipient, error = %err, "Failed to serialize webhook body");
+                return;
+            }
+        };
+        let this = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = this.call(&url, &body, trusted).await {
+                warn!(recipient, error = %err, "Failed to call webhook");
+            }
+        });
+    }
+
+    /// `POST`s `body` as JSON to `url`, once fewer than
+    /// [`MAX_CONCURRENT_CALLS`] calls are in flight. Unless `trusted`, i.e.
+    /// configured by the operator rather than through the GraphQL API, URLs
+    /// whose host resolves to a non-public address aren't called, and
+    /// redirects aren't followed.
+    pub async fn call(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+        trusted: bool,
+    ) -> anyhow::Result<()> {
+        let _permit = self.permits.acquire().await?;
+        if trusted {
+            return post(&self.client, url, body).await;
+        }
+
+        // The URL was checked when the webhook was registered, but its host
+        // may resolve to other addresses since. The client connects to the
+        // checked addresses rather than resolving the host again.
+        check_webhook_url(url).map_err(anyhow::Error::msg)?;
+        let parsed_url = Url::parse(url)?;
+        let mut client = untrusted_client_builder();
+        if let Some(Host::Domain(domain)) = parsed_url.host() {
+            let port = parsed_url.port_or_known_default().unwrap_or(80);
+            client = client.resolve_to_addrs(domain, &public_addresses(domain, port).await?);
+        }
+        post(&client.build()?, url, body).await
+    }
+}
+
+/// Redirects aren't followed for webhooks registered through the GraphQL
+/// API, as they may point to non-public addresses.
+fn untrusted_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder().redirect(reqwest::redirect::Policy::none())
+}
+
+/// Resolves `host`, failing if any of its addresses isn't public.
+async fn public_addresses(host: &str, port: u16) -> anyhow::Result<Vec<SocketAddr>> {
+    let addrs: Vec<_> = tokio::net::lookup_host((host, port)).await?.collect();
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_address(addr.ip())) {
+        anyhow::bail!("{host} resolves to the non-public address {}", addr.ip());
+    }
+    Ok(addrs)
+}
+
+async fn post(client: &reqwest::Client, url: &str, body: &impl Serialize) -> anyhow::Result<()> {
+    let response = client
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(body)
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?
+        .error_for_status()
+        .map_err(reqwest::Error::without_url)?;
+    if response.status().is_redirection() {
+        anyhow::bail!("webhook responded with a redirect ({})", response.status());
+    }
+    Ok(())
+}
+
+/// Fails for URLs that Graphix shouldn't be made to call on behalf of API
+/// key holders, i.e. URLs of other schemes than HTTP(S), and URLs of
+/// private, loopback and link-local hosts, which may be services that are
+/// only reachable from within Graphix's network.
+pub fn check_webhook_url(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|err| format!("Invalid webhook URL: {err}"))?;
+    if !["http", "https"].contains(&url.scheme()) {
+        return Err("Webhook URLs must use HTTP(S)".to_string());
+    }
+
+    let ip = match url.host() {
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            if domain == "localhost" || domain.ends_with(".localhost") {
+                return Err("Webhook URLs can't point to localhost".to_string());
+            }
+            return Ok(());
+        }
+        None => return Err("Webhook URLs must have a host".to_string()),
+    };
+    if !is_public_address(ip) {
+        return Err(format!(
+            "Webhook URLs can't point to private, loopback or link-local addresses, got {ip}"
+        ));
+    }
+
+    Ok(())
+}
+
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        // Carrier-grade NAT, 100.64.0.0/10.
+        || (a == 100 && (b & 0b1100_0000) == 64))
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first_segment = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        // Unique local, fc00::/7.
+        || (first_segment & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10.
+        || (first_segment & 0xffc0) == 0xfe80)
+}
+
+#[derive(Default)]
+pub struct WebhookNotifier {
+    client: WebhookClient,
+}
+
+impl WebhookNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls the webhooks that are scoped to the subgraph deployments of all
+    /// events appended to the event log since the last call, as well as the
+    /// webhook URLs of the watchlists that contain them, with the event as
+    /// JSON body. Watchlist webhook URLs that already received an event as a
+    /// registered webhook aren't called again for it. Events that aren't
+    /// about a subgraph deployment aren't delivered. Webhooks are called in
+    /// the background, see [`WebhookClient`], so they may receive events out
+    /// of order.
+    pub async fn notify(&self, store: &Store, watchlists: &Watchlists) -> anyhow::Result<()> {
+        let mut cursor = store.event_cursor(EVENT_CONSUMER).await?.unwrap_or(0);
+        let webhooks = store.webhooks(None).await?;
+        let mut calls = 0;
+
+        loop {
+            let filter = EventsQuery {
+                after_id: Some(cursor),
+                limit: Some(EVENTS_PAGE_SIZE),
+                ..Default::default()
+            };
+            let events = store.events(filter).await?;
+            let Some(last_event) = events.last() else {
+                break;
+            };
+
+            for event in &events {
+                let Some(deployment) = &event.deployment else {
+                    continue;
+                };
+                let mut called_urls = HashSet::new();
+                for webhook in webhooks
+                    .iter()
+                    .filter(|webhook| webhook.deployments().any(|d| d == deployment))
+                {
+                    called_urls.insert(webhook.url.as_str());
+                    calls += 1;
+                    self.client.spawn_call(
+                        webhook.url.clone(),
+                        event,
+                        format!("webhook {}", webhook.id),
+                        false,
+                    );
+                }
+                for (watchlist, url) in watchlists.webhook_urls(deployment) {
+                    if !called_urls.insert(url) {
+                        continue;
+                    }
+                    calls += 1;
+                    self.client.spawn_call(
+                        url.to_string(),
+                        event,
+                        format!("watchlist {}", watchlist.name),
+                        watchlist.from_config,
+                    );
+                }
+            }
+
+            cursor = last_event.id;
+            store.set_event_cursor(EVENT_CONSUMER, cursor).await?;
+            if events.len() < EVENTS_PAGE_SIZE as usize {
+                break;
+            }
+        }
+
+        if calls > 0 {
+            info!(calls, "Calling webhooks about events");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_urls_must_be_public() {
+        for url in [
+            "https://example.com/hook",
+            "http://203.0.113.7:8080",
+            "https://[2001:db8::1]/hook",
+        ] {
+            assert_eq!(check_webhook_url(url), Ok(()), "{url}");
+        }
+        for url in [
+            "ftp://example.com",
+            "http://localhost:8000",
+            "http://api.localhost",
+            "http://127.0.0.1",
+            "http://10.1.2.3",
+            "http://172.16.0.1",
+            "http://192.168.1.1",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1",
+            "http://0.0.0.0",
+            "http://[::1]",
+            "http://[fd00::1]",
+            "http://[fe80::1]",
+            "http://[::ffff:127.0.0.1]",
+        ] {
+            assert!(check_webhook_url(url).is_err(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn redirects_of_untrusted_webhooks_are_refused() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use axum::http::{header, StatusCode};
+        use axum::{routing, Router};
+
+        async fn serve(router: Router) -> SocketAddr {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+            addr
+        }
+
+        let internal_calls = Arc::new(AtomicUsize::new(0));
+        let internal_addr = serve(Router::new().route(
+            "/",
+            routing::post({
+                let internal_calls = internal_calls.clone();
+                move || async move {
+                    internal_calls.fetch_add(1, Ordering::SeqCst);
+                }
+            }),
+        ))
+        .await;
+        let location = format!("http://{internal_addr}/");
+        let public_addr = serve(Router::new().route(
+            "/",
+            routing::post(
+                move || async move { (StatusCode::FOUND, [(header::LOCATION, location)]) },
+            ),
+        ))
+        .await;
+
+        // Pretend that the redirecting server is a public host.
+        let client = untrusted_client_builder()
+            .resolve("hook.example", public_addr)
+            .build()
+            .unwrap();
+        let url = format!("http://hook.example:{}/", public_addr.port());
+        assert!(post(&client, &url, &serde_json::json!({})).await.is_err());
+        assert_eq!(internal_calls.load(Ordering::SeqCst), 0);
+
+        assert!(public_addresses("localhost", 80).await.is_err());
+    }
+}
@@ -29,10 +29,8 @@ async fn indexing_statuses() {
             .flatten()
             .collect::<Vec<_>>();
 
-        let queried_statuses: Vec<IndexingStatus> = query_indexing_statuses(&indexers, metrics())
-            .await
-            .into_iter()
-            .collect();
+        let (queried_statuses, _): (Vec<IndexingStatus>, _) =
+            query_indexing_statuses(&indexers, metrics()).await;
 
         assert_eq!(expected_statuses, queried_statuses);
     }
@@ -0,0 +1,74 @@
+use graphix_indexer_client::IndexerAuth;
+use graphix_lib::test_utils::graph_node::{MockDeployment, MockGraphNode};
+use graphix_lib::test_utils::{deployments, ipfs_cid};
+
+async fn graph_node_with_required_header(name: &str, value: &str) -> MockGraphNode {
+    let graph_node = MockGraphNode::start(
+        "indexer",
+        vec![MockDeployment::new(
+            ipfs_cid(deployments::ARB1_LIDO),
+            "arbitrum-one",
+            0,
+            10,
+        )],
+    )
+    .await;
+    graph_node.set_required_header(name, value);
+    graph_node
+}
+
+#[tokio::test]
+async fn requests_without_auth_are_rejected() {
+    //// Given
+    let graph_node = graph_node_with_required_header("authorization", "Bearer token").await;
+
+    //// When
+    let response = graph_node.indexer().indexing_statuses().await;
+
+    //// Then
+    assert!(response.is_err());
+}
+
+#[tokio::test]
+async fn requests_are_authenticated() {
+    let cases = [
+        (
+            "authorization",
+            "Bearer token",
+            IndexerAuth::Bearer {
+                token: "token".to_string(),
+            },
+        ),
+        (
+            "authorization",
+            // "graphix:secret", base64-encoded.
+            "Basic Z3JhcGhpeDpzZWNyZXQ=",
+            IndexerAuth::Basic {
+                username: "graphix".to_string(),
+                password: Some("secret".to_string()),
+            },
+        ),
+        (
+            "x-api-key",
+            "secret",
+            IndexerAuth::Header {
+                name: "X-Api-Key".to_string(),
+                value: "secret".to_string(),
+            },
+        ),
+    ];
+
+    for (header, value, auth) in cases {
+        //// Given
+        let graph_node = graph_node_with_required_header(header, value).await;
+        let indexer = graph_node.indexer_with_auth(Some(auth.clone()));
+
+        //// When
+        let response = indexer.clone().indexing_statuses().await;
+        let entity_changes = indexer.entity_changes(deployments::ARB1_LIDO, 5).await;
+
+        //// Then
+        assert!(response.is_ok(), "{auth:?}: {response:?}");
+        assert!(entity_changes.is_ok(), "{auth:?}");
+    }
+}
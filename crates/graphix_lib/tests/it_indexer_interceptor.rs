@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use graphix_indexer_client::{IndexerClient, IndexerInterceptor, PoiRequest};
+use graphix_lib::test_utils::graph_node::{MockDeployment, MockGraphNode};
+use graphix_lib::test_utils::{deployments, ipfs_cid};
+
+async fn graph_node() -> MockGraphNode {
+    MockGraphNode::start(
+        "indexer",
+        vec![MockDeployment::new(
+            ipfs_cid(deployments::ARB1_LIDO),
+            "arbitrum-one",
+            0,
+            100,
+        )],
+    )
+    .await
+}
+
+fn poi_request(block_number: u64) -> PoiRequest {
+    PoiRequest {
+        deployment: ipfs_cid(deployments::ARB1_LIDO),
+        network: "arbitrum-one".to_string(),
+        block_number,
+    }
+}
+
+#[tokio::test]
+async fn pois_are_corrupted_from_the_chosen_block() {
+    //// Given
+    let graph_node = graph_node().await;
+    let interceptor = Arc::new(
+        IndexerInterceptor::new(graph_node.indexer(), 0xff).with_corrupt_from_block(Some(50)),
+    );
+
+    //// When
+    let pois = interceptor
+        .proofs_of_indexing(vec![poi_request(49), poi_request(50)])
+        .await;
+    let original_pois = graph_node
+        .indexer()
+        .proofs_of_indexing(vec![poi_request(49), poi_request(50)])
+        .await;
+
+    //// Then
+    assert_eq!(pois.len(), 2);
+    assert_eq!(
+        pois[0].proof_of_indexing,
+        original_pois[0].proof_of_indexing
+    );
+    assert_eq!(pois[1].proof_of_indexing, [0xff; 32].into());
+}
+
+#[tokio::test]
+async fn faults_are_injected() {
+    //// Given
+    let graph_node = graph_node().await;
+    let failing =
+        Arc::new(IndexerInterceptor::new(graph_node.indexer(), 0xff).with_error_rate(1.0));
+    let slow = Arc::new(
+        IndexerInterceptor::new(graph_node.indexer(), 0xff)
+            .with_latency(Duration::from_millis(200)),
+    );
+
+    //// When
+    let statuses = failing.clone().indexing_statuses().await;
+    let pois = failing.proofs_of_indexing(vec![poi_request(10)]).await;
+    let start = Instant::now();
+    let slow_statuses = slow.indexing_statuses().await;
+
+    //// Then
+    assert!(statuses.is_err());
+    assert!(pois.is_empty());
+    assert!(slow_statuses.is_ok());
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use graphix_common_types::IndexerErrorClass;
+use graphix_indexer_client::{BlockPointer, CachedEthereumCall, EntityChanges};
+use graphix_lib::block_choice::BlockChoicePolicy;
+use graphix_lib::test_utils::graph_node::{
+    canonical_poi, mock_block_hash, MockDeployment, MockGraphNode,
+};
+use graphix_lib::test_utils::{deployments, ipfs_cid};
+use graphix_lib::{indexing_loop, metrics};
+use serde_json::json;
+
+#[tokio::test]
+async fn pois_are_collected_at_a_common_block() {
+    //// Given
+    let deployment = ipfs_cid(deployments::ARB1_LIDO);
+    let graph_nodes = vec![
+        MockGraphNode::start(
+            "indexer-1",
+            vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 100)],
+        )
+        .await,
+        MockGraphNode::start(
+            "indexer-2",
+            vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 90)],
+        )
+        .await,
+        MockGraphNode::start(
+            "indexer-3",
+            vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 120).diverging_at(50)],
+        )
+        .await,
+        MockGraphNode::start("indexer-4", vec![]).await,
+    ];
+    graph_nodes[3].set_unavailable(true);
+    let indexers = graph_nodes
+        .iter()
+        .map(MockGraphNode::indexer)
+        .collect::<Vec<_>>();
+
+    //// When
+    let (indexing_statuses, errors) =
+        indexing_loop::query_indexing_statuses(&indexers, metrics()).await;
+    let pois = indexing_loop::query_proofs_of_indexing(
+        indexing_statuses.clone(),
+        &BlockChoicePolicy::Earliest.into(),
+    )
+    .await;
+
+    //// Then
+    assert_eq!(indexing_statuses.len(), 3);
+
+    let errors = indexing_loop::classify_indexer_errors(
+        errors.iter().map(|error| (&error.indexer, &error.inner)),
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].indexer.address(), graph_nodes[3].address());
+    assert_eq!(errors[0].inner.class, IndexerErrorClass::HttpServerError);
+
+    let pois_by_indexer = pois
+        .iter()
+        .map(|poi| (poi.indexer.address(), poi))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(pois_by_indexer.len(), 3);
+    assert!(pois.iter().all(|poi| poi.block.number == 90));
+    assert_eq!(
+        pois_by_indexer[&graph_nodes[0].address()].proof_of_indexing,
+        canonical_poi(&deployment, 90)
+    );
+    assert_eq!(
+        pois_by_indexer[&graph_nodes[1].address()].proof_of_indexing,
+        canonical_poi(&deployment, 90)
+    );
+    assert_ne!(
+        pois_by_indexer[&graph_nodes[2].address()].proof_of_indexing,
+        canonical_poi(&deployment, 90)
+    );
+}
+
+#[tokio::test]
+async fn blocks_are_chosen_per_network() {
+    //// Given
+    let deployment = ipfs_cid(deployments::ARB1_LIDO);
+    let graph_nodes = [
+        MockGraphNode::start(
+            "indexer-1",
+            vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 100)],
+        )
+        .await,
+        MockGraphNode::start(
+            "indexer-2",
+            vec![MockDeployment::new(deployment.clone(), "gnosis", 0, 40)],
+        )
+        .await,
+    ];
+    let indexers = graph_nodes
+        .iter()
+        .map(MockGraphNode::indexer)
+        .collect::<Vec<_>>();
+
+    //// When
+    let (indexing_statuses, _) = indexing_loop::query_indexing_statuses(&indexers, metrics()).await;
+    let pois = indexing_loop::query_proofs_of_indexing(
+        indexing_statuses,
+        &BlockChoicePolicy::Earliest.into(),
+    )
+    .await;
+
+    //// Then
+    // The block of one network doesn't hold back the other.
+    let blocks_by_network = pois
+        .iter()
+        .map(|poi| (poi.network.as_str(), poi.block.number))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(pois.len(), 2);
+    assert_eq!(
+        blocks_by_network,
+        HashMap::from([("mainnet", 100), ("gnosis", 40)])
+    );
+}
+
+#[tokio::test]
+async fn pois_follow_indexing_progress() {
+    //// Given
+    let deployment = ipfs_cid(deployments::ARB1_LIDO);
+    let graph_node = MockGraphNode::start(
+        "indexer",
+        vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 10)],
+    )
+    .await;
+    let indexers = vec![graph_node.indexer()];
+
+    //// When
+    graph_node.set_latest_block(&deployment, 20);
+    let (indexing_statuses, _) = indexing_loop::query_indexing_statuses(&indexers, metrics()).await;
+    let pois = indexing_loop::query_proofs_of_indexing(
+        indexing_statuses,
+        &BlockChoicePolicy::MaxSyncedBlocks.into(),
+    )
+    .await;
+
+    //// Then
+    assert_eq!(pois.len(), 1);
+    assert_eq!(pois[0].block.number, 20);
+    assert_eq!(pois[0].block.hash, Some(mock_block_hash(20)));
+}
+
+#[tokio::test]
+async fn block_cache_and_entity_changes_are_served() {
+    //// Given
+    let deployment = ipfs_cid(deployments::ARB1_LIDO);
+    let graph_node = MockGraphNode::start(
+        "indexer",
+        vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 10)],
+    )
+    .await;
+    let block = BlockPointer {
+        number: 5,
+        hash: Some(mock_block_hash(5)),
+    };
+    let block_hash = mock_block_hash(5).0;
+    graph_node.set_block_data("mainnet", &block_hash, json!({ "number": "0x5" }));
+    graph_node.set_cached_eth_calls(
+        "mainnet",
+        &block,
+        &[CachedEthereumCall {
+            id_hash: vec![1; 32],
+            return_value: vec![2; 32],
+            contract_address: vec![3; 20],
+        }],
+    );
+    graph_node.set_entity_changes(
+        &deployment,
+        5,
+        &EntityChanges {
+            updates: HashMap::from([("Token".to_string(), vec![json!({ "id": "0x1" })])]),
+            deletions: HashMap::from([("Pool".to_string(), vec!["0x2".to_string()])]),
+        },
+    );
+    let indexer = graph_node.indexer();
+
+    //// When
+    let block_data = indexer
+        .clone()
+        .block_cache_contents("mainnet", &block_hash)
+        .await
+        .unwrap();
+    let eth_calls = indexer
+        .clone()
+        .cached_eth_calls("mainnet", &block_hash)
+        .await
+        .unwrap();
+    let entity_changes = indexer
+        .clone()
+        .entity_changes(&deployment.to_string(), 5)
+        .await
+        .unwrap();
+    let unknown_block_data = indexer
+        .block_cache_contents("mainnet", &mock_block_hash(6).0)
+        .await
+        .unwrap();
+
+    //// Then
+    assert_eq!(block_data, Some(json!({ "number": "0x5" })));
+    assert_eq!(unknown_block_data, None);
+    assert_eq!(eth_calls.len(), 1);
+    assert_eq!(eth_calls[0].contract_address, vec![3; 20]);
+    assert_eq!(
+        entity_changes.updates["Token"],
+        vec![json!({ "id": "0x1" })]
+    );
+    assert_eq!(entity_changes.deletions["Pool"], vec!["0x2".to_string()]);
+}
@@ -1,14 +1,28 @@
 use std::time::Duration;
 
-use graphix_indexer_client::IndexerClient;
-use graphix_lib::test_utils::{deployments, indexers, ipfs_cid, test_indexer_from_url};
+use graphix_common_types::SubgraphHealth;
+use graphix_indexer_client::{IndexerClient, SubgraphError};
+use graphix_lib::test_utils::graph_node::{mock_block_hash, MockDeployment, MockGraphNode};
+use graphix_lib::test_utils::{deployments, ipfs_cid};
 
 #[tokio::test]
 async fn send_indexer_statuses_query() {
     //// Given
-    let indexer = test_indexer_from_url(indexers::ARB1_DATA_NEXUS);
-
     let test_deployment = ipfs_cid(deployments::ARB1_QUICKSWAP_V3);
+    let graph_node = MockGraphNode::start(
+        "indexer",
+        vec![
+            MockDeployment::new(
+                ipfs_cid(deployments::ARB1_PREMIA_BLUE),
+                "arbitrum-one",
+                0,
+                10,
+            ),
+            MockDeployment::new(test_deployment.clone(), "arbitrum-one", 5, 20),
+        ],
+    )
+    .await;
+    let indexer = graph_node.indexer();
 
     //// When
     let request_fut = IndexerClient::indexing_statuses(indexer);
@@ -20,8 +34,60 @@ async fn send_indexer_statuses_query() {
     assert!(response.is_ok());
 
     let response = response.unwrap();
-    assert!(!response.is_empty());
-    assert!(response
+    assert_eq!(response.len(), 2);
+
+    let status = response
         .iter()
-        .any(|status| status.deployment == test_deployment));
+        .find(|status| status.deployment == test_deployment)
+        .unwrap();
+    assert_eq!(status.network, "arbitrum-one");
+    assert_eq!(status.earliest_block_num, 5);
+    assert_eq!(status.latest_block.number, 20);
+    assert_eq!(status.latest_block.hash, Some(mock_block_hash(20)));
+    assert_eq!(status.health, SubgraphHealth::Healthy);
+}
+
+#[tokio::test]
+async fn send_indexer_statuses_query_of_failed_deployment() {
+    //// Given
+    let deployment = ipfs_cid(deployments::ARB1_LIDO);
+    let fatal_error = SubgraphError {
+        message: "Mapping aborted".to_string(),
+        block: None,
+        handler: Some("handleTransfer".to_string()),
+        deterministic: true,
+        fatal: true,
+    };
+    let graph_node = MockGraphNode::start(
+        "indexer",
+        vec![MockDeployment {
+            health: SubgraphHealth::Failed,
+            errors: vec![fatal_error.clone()],
+            ..MockDeployment::new(deployment.clone(), "mainnet", 0, 10)
+        }],
+    )
+    .await;
+
+    //// When
+    let response = graph_node.indexer().indexing_statuses().await.unwrap();
+
+    //// Then
+    assert_eq!(response.len(), 1);
+    assert_eq!(response[0].deployment, deployment);
+    assert_eq!(response[0].health, SubgraphHealth::Failed);
+    assert_eq!(response[0].errors, vec![fatal_error]);
+}
+
+#[tokio::test]
+async fn send_indexer_statuses_query_to_unavailable_indexer() {
+    //// Given
+    let graph_node = MockGraphNode::start("indexer", vec![]).await;
+    graph_node.set_unavailable(true);
+
+    //// When
+    let response = graph_node.indexer().indexing_statuses().await;
+
+    //// Then
+    let error = response.unwrap_err();
+    assert!(error.to_string().contains("HTTP 503"));
 }
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::Router;
+use graphix_common_types::{Graft, SubgraphManifest};
+use graphix_lib::ipfs::IpfsClient;
+use graphix_lib::test_utils::{deployments, ipfs_cid};
+use tokio::net::TcpListener;
+
+struct MockIpfs {
+    files: HashMap<String, String>,
+    requests: AtomicUsize,
+}
+
+/// Serves `files` by CID from the `cat` endpoint of the IPFS HTTP API.
+async fn start_mock_ipfs(files: HashMap<String, String>) -> (IpfsClient, Arc<MockIpfs>) {
+    let state = Arc::new(MockIpfs {
+        files,
+        requests: AtomicUsize::new(0),
+    });
+    let router = Router::new()
+        .route(
+            "/api/v0/cat",
+            post(
+                |State(state): State<Arc<MockIpfs>>,
+                 Query(query): Query<HashMap<String, String>>| async move {
+                    state.requests.fetch_add(1, Ordering::SeqCst);
+                    state
+                        .files
+                        .get(&query["arg"])
+                        .cloned()
+                        .ok_or(StatusCode::NOT_FOUND)
+                },
+            ),
+        )
+        .with_state(state.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    (IpfsClient::new(url.parse().unwrap()), state)
+}
+
+fn manifest(graft: Option<(&str, u64)>, start_blocks: &[u64]) -> String {
+    let mut manifest = "specVersion: 0.0.5\nfeatures:\n  - grafting\n".to_string();
+    if let Some((base, block)) = graft {
+        manifest += &format!("graft:\n  base: {base}\n  block: {block}\n");
+    }
+    manifest += "dataSources:\n";
+    for start_block in start_blocks {
+        manifest += &format!(
+            "  - kind: ethereum/contract\n    network: arbitrum-one\n    source:\n      startBlock: {start_block}\n"
+        );
+    }
+    manifest
+}
+
+#[tokio::test]
+async fn graft_chains_are_followed() {
+    //// Given
+    let files = HashMap::from([
+        (
+            deployments::ARB1_QUICKSWAP_V3.to_string(),
+            manifest(Some((deployments::ARB1_LIDO, 200)), &[150, 100]),
+        ),
+        (
+            deployments::ARB1_LIDO.to_string(),
+            manifest(Some((deployments::ARB1_PREMIA_BLUE, 50)), &[10]),
+        ),
+        (
+            deployments::ARB1_PREMIA_BLUE.to_string(),
+            manifest(None, &[0]),
+        ),
+    ]);
+    let (client, mock) = start_mock_ipfs(files).await;
+
+    //// When
+    let quickswap = client
+        .subgraph_manifest(&ipfs_cid(deployments::ARB1_QUICKSWAP_V3))
+        .await
+        .unwrap();
+    let lido = client
+        .subgraph_manifest(&ipfs_cid(deployments::ARB1_LIDO))
+        .await
+        .unwrap();
+
+    //// Then
+    assert_eq!(
+        quickswap,
+        SubgraphManifest {
+            network: Some("arbitrum-one".to_string()),
+            start_block: 100,
+            features: vec!["grafting".to_string()],
+            graft_chain: vec![
+                Graft {
+                    base: ipfs_cid(deployments::ARB1_LIDO),
+                    block: 200,
+                },
+                Graft {
+                    base: ipfs_cid(deployments::ARB1_PREMIA_BLUE),
+                    block: 50,
+                },
+            ],
+        }
+    );
+    assert_eq!(lido.start_block, 10);
+    assert_eq!(lido.graft_chain.len(), 1);
+    // Manifests are only fetched once.
+    assert_eq!(mock.requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn missing_graft_bases_are_errors() {
+    //// Given
+    let files = HashMap::from([(
+        deployments::ARB1_QUICKSWAP_V3.to_string(),
+        manifest(Some((deployments::ARB1_LIDO, 200)), &[0]),
+    )]);
+    let (client, _mock) = start_mock_ipfs(files).await;
+
+    //// When
+    let result = client
+        .subgraph_manifest(&ipfs_cid(deployments::ARB1_QUICKSWAP_V3))
+        .await;
+
+    //// Then
+    assert!(result.is_err());
+}
@@ -0,0 +1,55 @@
+use graphix_common_types::LatencyProbe;
+use graphix_lib::latency_benchmark::benchmark_indexers;
+use graphix_lib::test_utils::graph_node::{MockDeployment, MockGraphNode};
+use graphix_lib::test_utils::{deployments, ipfs_cid};
+
+#[tokio::test]
+async fn indexers_are_benchmarked() {
+    //// Given
+    let graph_nodes = [
+        MockGraphNode::start(
+            "indexer-1",
+            vec![
+                MockDeployment::new(ipfs_cid(deployments::ARB1_LIDO), "mainnet", 0, 100),
+                MockDeployment::new(ipfs_cid(deployments::ARB1_QUICKSWAP_V3), "mainnet", 0, 50),
+            ],
+        )
+        .await,
+        MockGraphNode::start("indexer-2", vec![]).await,
+        MockGraphNode::start("indexer-3", vec![]).await,
+    ];
+    graph_nodes[2].set_unavailable(true);
+    let indexers = graph_nodes
+        .iter()
+        .map(MockGraphNode::indexer)
+        .collect::<Vec<_>>();
+
+    //// When
+    let samples = benchmark_indexers(&indexers).await;
+
+    //// Then
+    let mut samples: Vec<_> = samples
+        .iter()
+        .map(|sample| {
+            (
+                sample.indexer.address(),
+                sample.inner.probe,
+                sample.inner.success,
+            )
+        })
+        .collect();
+    samples.sort();
+    let mut expected = vec![
+        (graph_nodes[0].address(), LatencyProbe::Status, true),
+        (
+            graph_nodes[0].address(),
+            LatencyProbe::ProofOfIndexing,
+            true,
+        ),
+        // Without any subgraph deployments, there's no PoI to request.
+        (graph_nodes[1].address(), LatencyProbe::Status, true),
+        (graph_nodes[2].address(), LatencyProbe::Status, false),
+    ];
+    expected.sort();
+    assert_eq!(samples, expected);
+}
@@ -1,17 +1,44 @@
 use std::time::Duration;
 
 use graphix_indexer_client::{IndexerClient, PoiRequest};
-use graphix_lib::test_utils::{deployments, indexers, ipfs_cid, test_indexer_from_url};
+use graphix_lib::test_utils::graph_node::{canonical_poi, MockDeployment, MockGraphNode};
+use graphix_lib::test_utils::{deployments, ipfs_cid};
+
+/// Serves PoIs of [`deployments::ARB1_PREMIA_BLUE`] and
+/// [`deployments::ARB1_QUICKSWAP_V3`] up to block 1000, but none of
+/// [`deployments::FUSE_TO_ETHEREUM_AMB`].
+async fn graph_node() -> MockGraphNode {
+    MockGraphNode::start(
+        "indexer",
+        vec![
+            MockDeployment::new(
+                ipfs_cid(deployments::ARB1_PREMIA_BLUE),
+                "arbitrum-one",
+                0,
+                1000,
+            ),
+            MockDeployment::new(
+                ipfs_cid(deployments::ARB1_QUICKSWAP_V3),
+                "arbitrum-one",
+                0,
+                1000,
+            ),
+        ],
+    )
+    .await
+}
 
 #[tokio::test]
 async fn send_single_query_and_process_result() {
     //// Given
-    let indexer = test_indexer_from_url(indexers::ARB1_DATA_NEXUS);
+    let graph_node = graph_node().await;
+    let indexer = graph_node.indexer();
 
     let deployment = ipfs_cid(deployments::ARB1_PREMIA_BLUE);
 
     let poi_request = PoiRequest {
         deployment: deployment.clone(),
+        network: "arbitrum-one".to_string(),
         block_number: 123,
     };
 
@@ -26,18 +53,22 @@ async fn send_single_query_and_process_result() {
 
     let response = response.unwrap();
     assert_eq!(response.deployment, deployment);
+    assert_eq!(response.network, "arbitrum-one");
     assert_eq!(response.block.number, 123);
+    assert_eq!(response.proof_of_indexing, canonical_poi(&deployment, 123));
 }
 
 #[tokio::test]
 async fn send_single_query_of_unknown_deployment_id_and_handle_error() {
     //// Given
-    let indexer = test_indexer_from_url(indexers::ARB1_DATA_NEXUS);
+    let graph_node = graph_node().await;
+    let indexer = graph_node.indexer();
 
     let deployment_unknown = ipfs_cid(deployments::FUSE_TO_ETHEREUM_AMB);
 
     let poi_request = PoiRequest {
         deployment: deployment_unknown.clone(),
+        network: "arbitrum-one".to_string(),
         block_number: 123,
     };
 
@@ -59,12 +90,14 @@ async fn send_single_query_of_unknown_deployment_id_and_handle_error() {
 #[tokio::test]
 async fn send_single_query_of_unknown_block_number_and_handle_error() {
     //// Given
-    let indexer = test_indexer_from_url(indexers::ARB1_DATA_NEXUS);
+    let graph_node = graph_node().await;
+    let indexer = graph_node.indexer();
 
     let deployment = ipfs_cid(deployments::ARB1_QUICKSWAP_V3);
 
     let poi_request = PoiRequest {
         deployment: deployment.clone(),
+        network: "arbitrum-one".to_string(),
         block_number: u64::MAX,
     };
 
@@ -92,13 +125,15 @@ async fn send_multiple_queries_and_process_results() {
     //  reduces the impact of this issue.
     const MAX_REQUESTS_PER_QUERY: usize = 1;
 
-    let indexer = test_indexer_from_url(indexers::ARB1_DATA_NEXUS);
+    let graph_node = graph_node().await;
+    let indexer = graph_node.indexer();
 
     let deployment = ipfs_cid(deployments::ARB1_QUICKSWAP_V3);
 
     let poi_requests = (1..=MAX_REQUESTS_PER_QUERY + 2)
         .map(|i| PoiRequest {
             deployment: deployment.clone(),
+            network: "arbitrum-one".to_string(),
             block_number: i as u64,
         })
         .collect::<Vec<_>>();
@@ -109,8 +144,6 @@ async fn send_multiple_queries_and_process_results() {
         .await
         .expect("Timeout");
 
-    dbg!(&response);
-
     // Then
     assert_eq!(response.len(), MAX_REQUESTS_PER_QUERY + 2);
 
@@ -127,7 +160,8 @@ async fn send_multiple_queries_and_process_results() {
 #[tokio::test]
 async fn send_multiple_queries_of_unknown_deployment_id_and_process_results() {
     //// Given
-    let indexer = test_indexer_from_url(indexers::ARB1_DATA_NEXUS);
+    let graph_node = graph_node().await;
+    let indexer = graph_node.indexer();
 
     let deployment0 = ipfs_cid(deployments::ARB1_PREMIA_BLUE);
     let deployment1 = ipfs_cid(deployments::ARB1_QUICKSWAP_V3);
@@ -136,14 +170,17 @@ async fn send_multiple_queries_of_unknown_deployment_id_and_process_results() {
     let poi_requests = vec![
         PoiRequest {
             deployment: deployment0.clone(),
+            network: "arbitrum-one".to_string(),
             block_number: 123,
         },
         PoiRequest {
             deployment: deployment_unknown.clone(),
+            network: "arbitrum-one".to_string(),
             block_number: 42,
         },
         PoiRequest {
             deployment: deployment1.clone(),
+            network: "arbitrum-one".to_string(),
             block_number: 456,
         },
     ];
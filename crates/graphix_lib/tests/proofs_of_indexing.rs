@@ -14,9 +14,11 @@ async fn proofs_of_indexing() {
         let max_indexers = i;
         let indexers = gen_indexers(&mut rng, max_indexers as usize);
 
-        let indexing_statuses = indexing_loop::query_indexing_statuses(&indexers, metrics()).await;
+        let (indexing_statuses, _) =
+            indexing_loop::query_indexing_statuses(&indexers, metrics()).await;
+        let block_choice_policies = BlockChoicePolicy::Earliest.into();
         let pois =
-            indexing_loop::query_proofs_of_indexing(indexing_statuses, BlockChoicePolicy::Earliest);
+            indexing_loop::query_proofs_of_indexing(indexing_statuses, &block_choice_policies);
 
         let actual_pois = pois.await.into_iter().collect::<BTreeSet<_>>();
 
@@ -0,0 +1,93 @@
+//! Authentication of requests to indexers' index node servers, for indexers
+//! that don't expose theirs publicly.
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How to authenticate requests to an indexer's index node server. Applies
+/// to all GraphQL requests that Graphix sends to the indexer, including those
+/// of divergence investigations.
+#[derive(Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IndexerAuth {
+    /// Sends `Authorization: Bearer <token>`.
+    Bearer { token: String },
+    /// HTTP basic authentication.
+    #[serde(rename_all = "camelCase")]
+    Basic {
+        username: String,
+        #[serde(default)]
+        password: Option<String>,
+    },
+    /// Sends an arbitrary header, e.g. an API key.
+    Header { name: String, value: String },
+}
+
+impl IndexerAuth {
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self {
+            IndexerAuth::Bearer { token } => request.bearer_auth(token),
+            IndexerAuth::Basic { username, password } => {
+                request.basic_auth(username, password.as_ref())
+            }
+            IndexerAuth::Header { name, value } => request.header(name, value),
+        }
+    }
+}
+
+/// Credentials are redacted, so that they don't end up in logs.
+impl fmt::Debug for IndexerAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerAuth::Bearer { .. } => f.debug_struct("Bearer").finish_non_exhaustive(),
+            IndexerAuth::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            IndexerAuth::Header { name, .. } => f
+                .debug_struct("Header")
+                .field("name", name)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_output_is_redacted() {
+        let auths = [
+            IndexerAuth::Bearer {
+                token: "secret".to_string(),
+            },
+            IndexerAuth::Basic {
+                username: "graphix".to_string(),
+                password: Some("secret".to_string()),
+            },
+            IndexerAuth::Header {
+                name: "x-api-key".to_string(),
+                value: "secret".to_string(),
+            },
+        ];
+        for auth in auths {
+            assert!(!format!("{auth:?}").contains("secret"));
+        }
+    }
+
+    #[test]
+    fn deserialize() {
+        let auth: IndexerAuth =
+            serde_json::from_str(r#"{"type": "basic", "username": "graphix"}"#).unwrap();
+        assert_eq!(
+            auth,
+            IndexerAuth::Basic {
+                username: "graphix".to_string(),
+                password: None,
+            }
+        );
+    }
+}
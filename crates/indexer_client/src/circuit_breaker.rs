@@ -0,0 +1,265 @@
+//! Per-indexer circuit breaking of requests to indexers that keep failing, so
+//! that dead indexers don't use up the timeout budget of every polling cycle.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use graphix_common_types::{IndexerAddress, IndexerErrorClass};
+use prometheus::IntCounterVec;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// After `failureThreshold` consecutive failed requests to an indexer, all
+/// requests to it are skipped for `cooldownInSeconds`. After that, a single
+/// request is let through: the indexer is back if it succeeds, or skipped for
+/// another cooldown period if it fails.
+///
+/// Only failures that suggest the indexer is down count, i.e. DNS, TLS and
+/// connection errors, timeouts and HTTP 5xx responses. An indexer that
+/// responds with GraphQL errors is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitBreakerConfig {
+    #[serde(default = "CircuitBreakerConfig::default_failure_threshold")]
+    pub failure_threshold: u32,
+    #[serde(default = "CircuitBreakerConfig::default_cooldown_in_seconds")]
+    pub cooldown_in_seconds: u64,
+}
+
+impl CircuitBreakerConfig {
+    fn default_failure_threshold() -> u32 {
+        5
+    }
+
+    fn default_cooldown_in_seconds() -> u64 {
+        300
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_in_seconds)
+    }
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: Self::default_failure_threshold(),
+            cooldown_in_seconds: Self::default_cooldown_in_seconds(),
+        }
+    }
+}
+
+/// Circuit breakers shared by all [`RealIndexer`](crate::RealIndexer)s, keyed
+/// by indexer address, so that they survive reconfiguration.
+static CIRCUIT_BREAKERS: OnceLock<Mutex<HashMap<IndexerAddress, Arc<CircuitBreaker>>>> =
+    OnceLock::new();
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    indexer: IndexerAddress,
+    config: CircuitBreakerConfig,
+    state: Mutex<CircuitState>,
+    /// By indexer and event, either `opened` or `skipped`.
+    events: IntCounterVec,
+}
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+    /// Requests are skipped until then. Also set while the single request
+    /// after a cooldown period is in flight.
+    open_until: Option<Instant>,
+}
+
+/// The error of requests that were skipped because the indexer's circuit
+/// breaker is open.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Skipped after {consecutive_failures} consecutive failed requests, \
+     the indexer will be retried in {}s",
+    retry_in.as_secs()
+)]
+pub struct CircuitOpen {
+    pub consecutive_failures: u32,
+    pub retry_in: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(
+        indexer: IndexerAddress,
+        config: CircuitBreakerConfig,
+        events: IntCounterVec,
+    ) -> Self {
+        Self {
+            indexer,
+            config,
+            state: Mutex::default(),
+            events,
+        }
+    }
+
+    /// Returns the circuit breaker of `indexer`, creating it if it doesn't
+    /// exist yet or if its configuration changed.
+    pub fn for_indexer(
+        indexer: IndexerAddress,
+        config: CircuitBreakerConfig,
+        events: IntCounterVec,
+    ) -> Arc<Self> {
+        let mut circuit_breakers = CIRCUIT_BREAKERS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap();
+        match circuit_breakers.get(&indexer) {
+            Some(circuit_breaker) if circuit_breaker.config == config => circuit_breaker.clone(),
+            _ => {
+                let circuit_breaker = Arc::new(Self::new(indexer, config, events));
+                circuit_breakers.insert(indexer, circuit_breaker.clone());
+                circuit_breaker
+            }
+        }
+    }
+
+    /// Fails if requests to the indexer are currently skipped. Once the
+    /// cooldown period is over, only the first caller gets through.
+    pub fn check(&self) -> Result<(), CircuitOpen> {
+        let mut state = self.state.lock().unwrap();
+        let Some(open_until) = state.open_until else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        if now < open_until {
+            self.inc_events("skipped");
+            return Err(CircuitOpen {
+                consecutive_failures: state.consecutive_failures,
+                retry_in: open_until - now,
+            });
+        }
+
+        // Everyone else waits for the outcome of this request.
+        state.open_until = Some(now + self.config.cooldown());
+        Ok(())
+    }
+
+    /// Records the outcome of a request that [`CircuitBreaker::check`] let
+    /// through.
+    pub fn record(&self, outcome: Result<(), IndexerErrorClass>) {
+        let mut state = self.state.lock().unwrap();
+        match outcome {
+            Err(class) if indicates_downtime(class) => {
+                state.consecutive_failures += 1;
+                if state.consecutive_failures >= self.config.failure_threshold {
+                    let was_open = state.open_until.is_some();
+                    state.open_until = Some(Instant::now() + self.config.cooldown());
+                    if !was_open {
+                        warn!(
+                            indexer = %self.indexer,
+                            consecutive_failures = state.consecutive_failures,
+                            cooldown_in_seconds = self.config.cooldown_in_seconds,
+                            "Skipping indexer after consecutive failed requests"
+                        );
+                        self.inc_events("opened");
+                    }
+                }
+            }
+            _ => *state = CircuitState::default(),
+        }
+    }
+
+    /// Until when requests to the indexer are skipped, if they are.
+    pub fn open_until(&self) -> Option<Instant> {
+        self.state.lock().unwrap().open_until
+    }
+
+    fn inc_events(&self, event: &str) {
+        self.events
+            .get_metric_with_label_values(&[&self.indexer.to_string(), event])
+            .unwrap()
+            .inc();
+    }
+}
+
+fn indicates_downtime(class: IndexerErrorClass) -> bool {
+    matches!(
+        class,
+        IndexerErrorClass::Dns
+            | IndexerErrorClass::Tls
+            | IndexerErrorClass::Connection
+            | IndexerErrorClass::Timeout
+            | IndexerErrorClass::HttpServerError
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> IntCounterVec {
+        IntCounterVec::new(prometheus::Opts::new("foo", "bar"), &["indexer", "event"]).unwrap()
+    }
+
+    fn circuit_breaker(cooldown_in_seconds: u64, events: IntCounterVec) -> CircuitBreaker {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown_in_seconds,
+        };
+        CircuitBreaker::new(IndexerAddress::from([1; 20]), config, events)
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let events = events();
+        let circuit_breaker = circuit_breaker(300, events.clone());
+        let count = |event| {
+            events
+                .with_label_values(&[&IndexerAddress::from([1; 20]).to_string(), event])
+                .get()
+        };
+
+        circuit_breaker.record(Err(IndexerErrorClass::Timeout));
+        circuit_breaker.record(Ok(()));
+        circuit_breaker.record(Err(IndexerErrorClass::Connection));
+        // The indexer is alive if it responds, even with errors.
+        circuit_breaker.record(Err(IndexerErrorClass::GraphqlError));
+        circuit_breaker.record(Err(IndexerErrorClass::Dns));
+        assert!(circuit_breaker.check().is_ok());
+        assert!(circuit_breaker.open_until().is_none());
+
+        circuit_breaker.record(Err(IndexerErrorClass::HttpServerError));
+        assert!(circuit_breaker.open_until().is_some());
+        let err = circuit_breaker.check().unwrap_err();
+        assert_eq!(err.consecutive_failures, 2);
+        assert!(err.retry_in <= Duration::from_secs(300));
+        assert_eq!(count("opened"), 1);
+        assert_eq!(count("skipped"), 1);
+    }
+
+    #[test]
+    fn lets_a_single_request_through_after_the_cooldown() {
+        let circuit_breaker = circuit_breaker(0, events());
+        circuit_breaker.record(Err(IndexerErrorClass::Timeout));
+        circuit_breaker.record(Err(IndexerErrorClass::Timeout));
+
+        // Without a cooldown period, the next request is the trial right away.
+        assert!(circuit_breaker.check().is_ok());
+        circuit_breaker.record(Err(IndexerErrorClass::Timeout));
+        assert!(circuit_breaker.open_until().is_some());
+
+        assert!(circuit_breaker.check().is_ok());
+        circuit_breaker.record(Ok(()));
+        assert!(circuit_breaker.open_until().is_none());
+    }
+
+    #[test]
+    fn shared_by_indexer() {
+        let config = CircuitBreakerConfig::default();
+        let a = CircuitBreaker::for_indexer(IndexerAddress::from([2; 20]), config, events());
+        let b = CircuitBreaker::for_indexer(IndexerAddress::from([2; 20]), config, events());
+        let c = CircuitBreaker::for_indexer(IndexerAddress::from([3; 20]), config, events());
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+}
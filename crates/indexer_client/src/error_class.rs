@@ -0,0 +1,95 @@
+use std::error::Error;
+
+use graphix_common_types::IndexerErrorClass;
+
+use crate::{CircuitOpen, QueryFailure};
+
+/// Tells apart the reasons why a request to an indexer can fail, so that
+/// operators can be told more than "it failed" when debugging connectivity.
+pub fn classify_error(error: &anyhow::Error) -> IndexerErrorClass {
+    if let Some(failure) = error.downcast_ref::<QueryFailure>() {
+        return failure.class;
+    }
+    if error.downcast_ref::<CircuitOpen>().is_some() {
+        return IndexerErrorClass::CircuitOpen;
+    }
+    if error
+        .downcast_ref::<tokio::time::error::Elapsed>()
+        .is_some()
+    {
+        return IndexerErrorClass::Timeout;
+    }
+    let Some(error) = error.downcast_ref::<reqwest::Error>() else {
+        return IndexerErrorClass::Other;
+    };
+
+    if error.is_timeout() {
+        IndexerErrorClass::Timeout
+    } else if error.is_connect() {
+        // reqwest doesn't expose DNS and TLS failures as such, but their
+        // sources (from hyper and the TLS backend) describe them.
+        let sources = source_messages(error);
+        if sources.contains("dns error") || sources.contains("failed to lookup address") {
+            IndexerErrorClass::Dns
+        } else if ["tls", "ssl", "certificate", "handshake"]
+            .iter()
+            .any(|keyword| sources.contains(keyword))
+        {
+            IndexerErrorClass::Tls
+        } else {
+            IndexerErrorClass::Connection
+        }
+    } else if let Some(status) = error.status() {
+        if status.is_server_error() {
+            IndexerErrorClass::HttpServerError
+        } else {
+            IndexerErrorClass::HttpClientError
+        }
+    } else if error.is_decode() {
+        IndexerErrorClass::InvalidResponse
+    } else {
+        IndexerErrorClass::Other
+    }
+}
+
+/// The lowercase messages of all errors in the source chain of `error`.
+fn source_messages(error: &reqwest::Error) -> String {
+    let mut messages = vec![];
+    let mut source = error.source();
+    while let Some(error) = source {
+        messages.push(error.to_string().to_lowercase());
+        source = error.source();
+    }
+    messages.join(": ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_failures_keep_their_class() {
+        let error = anyhow::Error::new(QueryFailure {
+            class: IndexerErrorClass::GraphqlError,
+            raw_query: String::new(),
+            response: String::new(),
+            message: "Indexer returned errors".to_string(),
+        })
+        .context("Failed to query indexing statuses");
+
+        assert_eq!(classify_error(&error), IndexerErrorClass::GraphqlError);
+        assert_eq!(
+            classify_error(&anyhow::anyhow!("unknown")),
+            IndexerErrorClass::Other
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_connections_are_classified() {
+        // Nothing listens on port 1.
+        let error = reqwest::get("http://127.0.0.1:1/graphql")
+            .await
+            .unwrap_err();
+        assert_eq!(classify_error(&error.into()), IndexerErrorClass::Connection);
+    }
+}
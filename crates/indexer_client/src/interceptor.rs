@@ -2,9 +2,12 @@
 
 use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::anyhow;
 use async_trait::async_trait;
-use graphix_common_types::{GraphNodeCollectedVersion, IndexerAddress};
+use chrono::{DateTime, Utc};
+use graphix_common_types::{GraphNodeCollectedVersion, IndexerAddress, IpfsCid};
 
 use super::{CachedEthereumCall, EntityChanges};
 use crate::{IndexerClient, IndexingStatus, PoiRequest, ProofOfIndexing};
@@ -13,15 +16,82 @@ use crate::{IndexerClient, IndexingStatus, PoiRequest, ProofOfIndexing};
 /// [`RealIndexer`](crate::indexer::RealIndexer) and then intercepting the
 /// responses to generate diverging Pois. The divergent pois will consist of a
 /// repetition of `poi_byte`. Interceptors have no [`IndexerClient::address`].
+///
+/// Interceptors can also inject faults, so that investigations can be
+/// exercised without real divergent or misbehaving indexers: they can delay
+/// every request, fail a share of them, and only corrupt PoIs from a given
+/// block on.
 #[derive(Debug)]
 pub struct IndexerInterceptor {
     target: Arc<dyn IndexerClient>,
     poi_byte: u8,
+    corrupt_from_block: Option<u64>,
+    latency: Duration,
+    error_rate: f64,
 }
 
 impl IndexerInterceptor {
     pub fn new(target: Arc<dyn IndexerClient>, poi_byte: u8) -> Self {
-        Self { target, poi_byte }
+        Self {
+            target,
+            poi_byte,
+            corrupt_from_block: None,
+            latency: Duration::ZERO,
+            error_rate: 0.0,
+        }
+    }
+
+    /// Only corrupts the PoIs of `block` and later blocks, like a real
+    /// divergence would, instead of all of them.
+    pub fn with_corrupt_from_block(mut self, block: Option<u64>) -> Self {
+        self.corrupt_from_block = block;
+        self
+    }
+
+    /// Delays every request by `latency`.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Fails requests with a probability of `error_rate`, between 0 and 1.
+    pub fn with_error_rate(mut self, error_rate: f64) -> Self {
+        self.error_rate = error_rate;
+        self
+    }
+
+    /// Waits out the injected latency, then fails if an error is injected.
+    async fn inject_faults(&self) -> anyhow::Result<()> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+        if self.error_rate > 0.0 && rand::random::<f64>() < self.error_rate {
+            return Err(anyhow!("injected fault"));
+        }
+        Ok(())
+    }
+
+    /// Attributes the target's indexing statuses to the interceptor.
+    fn hijack_statuses(self: &Arc<Self>, statuses: Vec<IndexingStatus>) -> Vec<IndexingStatus> {
+        statuses
+            .into_iter()
+            .map(|status| IndexingStatus {
+                indexer: self.clone(),
+                deployment: status.deployment,
+                network: status.network,
+                latest_block: status.latest_block,
+                earliest_block_num: status.earliest_block_num,
+                health: status.health,
+                synced: status.synced,
+                paused: status.paused,
+                errors: status.errors,
+            })
+            .collect()
+    }
+
+    fn corrupts(&self, block_number: u64) -> bool {
+        self.corrupt_from_block
+            .map_or(true, |block| block_number >= block)
     }
 }
 
@@ -38,26 +108,44 @@ impl IndexerClient for IndexerInterceptor {
         self.target.address()
     }
 
+    fn network_subgraph(&self) -> Option<Cow<str>> {
+        self.target.network_subgraph()
+    }
+
+    fn operator_contact(&self) -> Option<Cow<str>> {
+        self.target.operator_contact()
+    }
+
+    fn circuit_open_until(&self) -> Option<DateTime<Utc>> {
+        self.target.circuit_open_until()
+    }
+
     async fn ping(self: Arc<Self>) -> anyhow::Result<()> {
+        self.inject_faults().await?;
         self.target.clone().ping().await
     }
 
     async fn indexing_statuses(self: Arc<Self>) -> Result<Vec<IndexingStatus>, anyhow::Error> {
+        self.inject_faults().await?;
         let statuses = self.target.clone().indexing_statuses().await?;
-        let hijacked_statuses = statuses
-            .into_iter()
-            .map(|status| IndexingStatus {
-                indexer: self.clone(),
-                deployment: status.deployment,
-                network: status.network,
-                latest_block: status.latest_block,
-                earliest_block_num: status.earliest_block_num,
-            })
-            .collect();
-        Ok(hijacked_statuses)
+        Ok(self.hijack_statuses(statuses))
+    }
+
+    async fn deployment_indexing_statuses(
+        self: Arc<Self>,
+        deployment: &IpfsCid,
+    ) -> anyhow::Result<Vec<IndexingStatus>> {
+        self.inject_faults().await?;
+        let statuses = self
+            .target
+            .clone()
+            .deployment_indexing_statuses(deployment)
+            .await?;
+        Ok(self.hijack_statuses(statuses))
     }
 
     async fn version(self: Arc<Self>) -> anyhow::Result<GraphNodeCollectedVersion> {
+        self.inject_faults().await?;
         self.target.clone().version().await
     }
 
@@ -65,16 +153,25 @@ impl IndexerClient for IndexerInterceptor {
         self: Arc<Self>,
         requests: Vec<PoiRequest>,
     ) -> Vec<ProofOfIndexing> {
+        // Like real indexers, a failed request results in no PoIs.
+        if self.inject_faults().await.is_err() {
+            return vec![];
+        }
         let pois = self.target.clone().proofs_of_indexing(requests).await;
 
         pois.into_iter()
             .map(|poi| {
-                let divergent_poi = [self.poi_byte; 32].into();
+                let proof_of_indexing = if self.corrupts(poi.block.number) {
+                    [self.poi_byte; 32].into()
+                } else {
+                    poi.proof_of_indexing
+                };
                 ProofOfIndexing {
                     indexer: self.clone(),
                     deployment: poi.deployment,
+                    network: poi.network,
                     block: poi.block,
-                    proof_of_indexing: divergent_poi,
+                    proof_of_indexing,
                 }
             })
             .collect()
@@ -84,14 +181,27 @@ impl IndexerClient for IndexerInterceptor {
         self: Arc<Self>,
         subgraph_id: &str,
     ) -> anyhow::Result<Vec<String>> {
+        self.inject_faults().await?;
         self.target.clone().subgraph_api_versions(subgraph_id).await
     }
 
+    async fn subgraph_data_source_kinds(
+        self: Arc<Self>,
+        subgraph_id: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        self.inject_faults().await?;
+        self.target
+            .clone()
+            .subgraph_data_source_kinds(subgraph_id)
+            .await
+    }
+
     async fn cached_eth_calls(
         self: Arc<Self>,
         network: &str,
         block_hash: &[u8],
     ) -> anyhow::Result<Vec<CachedEthereumCall>> {
+        self.inject_faults().await?;
         self.target
             .clone()
             .cached_eth_calls(network, block_hash)
@@ -103,6 +213,7 @@ impl IndexerClient for IndexerInterceptor {
         network: &str,
         block_hash: &[u8],
     ) -> anyhow::Result<Option<serde_json::Value>> {
+        self.inject_faults().await?;
         self.target
             .clone()
             .block_cache_contents(network, block_hash)
@@ -114,6 +225,7 @@ impl IndexerClient for IndexerInterceptor {
         subgraph_id: &str,
         block_number: u64,
     ) -> anyhow::Result<EntityChanges> {
+        self.inject_faults().await?;
         self.target
             .clone()
             .entity_changes(subgraph_id, block_number)
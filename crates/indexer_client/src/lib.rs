@@ -1,4 +1,8 @@
+mod auth;
+mod circuit_breaker;
+mod error_class;
 mod interceptor;
+mod rate_limiter;
 mod real_indexer;
 
 use std::borrow::Cow;
@@ -9,10 +13,16 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
+pub use auth::IndexerAuth;
+use chrono::{DateTime, Utc};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitOpen};
+pub use error_class::classify_error;
 use graphix_common_types::{
-    BlockHash, GraphNodeCollectedVersion, IndexerAddress, IpfsCid, PoiBytes,
+    BlockHash, GraphNodeCollectedVersion, IndexerAddress, IndexerErrorClass, IpfsCid, PoiBytes,
+    SubgraphHealth,
 };
 pub use interceptor::IndexerInterceptor;
+pub use rate_limiter::{RateLimitConfig, RateLimiter};
 pub use real_indexer::RealIndexer;
 use serde::Serialize;
 
@@ -29,10 +39,42 @@ pub trait IndexerClient: Send + Sync + Debug {
     /// Human-readable name of the indexer.
     fn name(&self) -> Option<Cow<str>>;
 
+    /// The name of the network subgraph through which this indexer was
+    /// discovered, if any. Statically configured indexers have none.
+    fn network_subgraph(&self) -> Option<Cow<str>> {
+        None
+    }
+
+    /// How to reach the operator of this indexer, as published in their
+    /// network subgraph account metadata, if any.
+    fn operator_contact(&self) -> Option<Cow<str>> {
+        None
+    }
+
+    /// Until when requests to this indexer are skipped by its circuit
+    /// breaker, if they are. See [`CircuitBreakerConfig`].
+    fn circuit_open_until(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+
     async fn ping(self: Arc<Self>) -> anyhow::Result<()>;
 
     async fn indexing_statuses(self: Arc<Self>) -> anyhow::Result<Vec<IndexingStatus>>;
 
+    /// The indexing statuses of a single subgraph deployment, one per
+    /// network. Unlike [`IndexerClient::indexing_statuses`], indexers can
+    /// answer this without reporting on all of their deployments.
+    async fn deployment_indexing_statuses(
+        self: Arc<Self>,
+        deployment: &IpfsCid,
+    ) -> anyhow::Result<Vec<IndexingStatus>> {
+        let statuses = self.indexing_statuses().await?;
+        Ok(statuses
+            .into_iter()
+            .filter(|status| &status.deployment == deployment)
+            .collect())
+    }
+
     async fn proofs_of_indexing(self: Arc<Self>, requests: Vec<PoiRequest>)
         -> Vec<ProofOfIndexing>;
 
@@ -43,6 +85,13 @@ pub trait IndexerClient: Send + Sync + Debug {
         subgraph_id: &str,
     ) -> anyhow::Result<Vec<String>>;
 
+    /// Returns the kinds of the data sources of a subgraph deployment, e.g.
+    /// `ethereum/contract` or [`SUBSTREAMS_DATA_SOURCE_KIND`].
+    async fn subgraph_data_source_kinds(
+        self: Arc<Self>,
+        subgraph_id: &str,
+    ) -> anyhow::Result<Vec<String>>;
+
     /// Convenience wrapper around calling [`IndexerClient::proofs_of_indexing`] for a
     /// single POI.
     async fn proof_of_indexing(
@@ -79,6 +128,10 @@ pub trait IndexerClient: Send + Sync + Debug {
     ) -> anyhow::Result<EntityChanges>;
 }
 
+/// The data source kind of substreams-powered subgraphs. Indexers may report
+/// null (all-zero) PoIs for them.
+pub const SUBSTREAMS_DATA_SOURCE_KIND: &str = "substreams";
+
 /// Graphix defines an indexer's ID as either its Ethereum address (if it has
 /// one) or its name (if it doesn't have an address i.e. it's not a network
 /// participant), strictly in this order.
@@ -157,6 +210,19 @@ impl<T> WithIndexer<T> {
     }
 }
 
+/// A query that the indexer responded to, but with GraphQL errors or an
+/// unparsable body. Indexer clients return it wrapped in [`anyhow::Error`], so
+/// that callers can downcast it and keep the raw query and response around
+/// for debugging.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{message}")]
+pub struct QueryFailure {
+    pub class: IndexerErrorClass,
+    pub raw_query: String,
+    pub response: String,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub struct CachedEthereumCall {
     pub id_hash: Vec<u8>,
@@ -198,6 +264,27 @@ pub struct IndexingStatus {
     pub network: String,
     pub latest_block: BlockPointer,
     pub earliest_block_num: u64,
+    pub health: SubgraphHealth,
+    pub synced: bool,
+    /// `None` if the deployment is not assigned to an index node.
+    pub paused: Option<bool>,
+    /// The fatal error that halted indexing, if any, followed by any
+    /// non-fatal errors.
+    pub errors: Vec<SubgraphError>,
+}
+
+/// An error that `graph-node` reported while indexing a subgraph deployment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubgraphError {
+    pub message: String,
+    /// The block at which the error occurred, if known.
+    pub block: Option<BlockPointer>,
+    /// The handler that failed, if known.
+    pub handler: Option<String>,
+    /// Whether `graph-node` is certain that the error is deterministic.
+    pub deterministic: bool,
+    /// Whether the error halted indexing.
+    pub fatal: bool,
 }
 
 impl PartialEq for IndexingStatus {
@@ -213,6 +300,9 @@ impl PartialEq for IndexingStatus {
 pub struct ProofOfIndexing {
     pub indexer: Arc<dyn IndexerClient>,
     pub deployment: IpfsCid,
+    /// The name of the network that the deployment indexes, as reported by
+    /// `graph-node`.
+    pub network: String,
     pub block: BlockPointer,
     pub proof_of_indexing: PoiBytes,
 }
@@ -221,6 +311,7 @@ impl PartialEq for ProofOfIndexing {
     fn eq(&self, other: &Self) -> bool {
         self.indexer.as_ref() == other.indexer.as_ref()
             && self.deployment == other.deployment
+            && self.network == other.network
             && self.block == other.block
             && self.proof_of_indexing == other.proof_of_indexing
     }
@@ -230,6 +321,7 @@ pub trait WritablePoi {
     type IndexerId: IndexerId;
 
     fn deployment_cid(&self) -> IpfsCid;
+    fn network(&self) -> &str;
     fn indexer_id(&self) -> Self::IndexerId;
     fn block(&self) -> &BlockPointer;
     fn proof_of_indexing(&self) -> &PoiBytes;
@@ -242,6 +334,10 @@ impl WritablePoi for ProofOfIndexing {
         self.deployment.clone()
     }
 
+    fn network(&self) -> &str {
+        &self.network
+    }
+
     fn indexer_id(&self) -> Self::IndexerId {
         self.indexer.clone()
     }
@@ -272,5 +368,8 @@ pub struct POICrossCheckReport {
 #[derive(Debug, Clone)]
 pub struct PoiRequest {
     pub deployment: IpfsCid,
+    /// The network that the deployment indexes. The same deployment can be
+    /// indexed on several networks, so PoIs are only comparable within one.
+    pub network: String,
     pub block_number: u64,
 }
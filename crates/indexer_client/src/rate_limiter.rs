@@ -0,0 +1,260 @@
+//! Per-host rate limiting of requests to indexers.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use prometheus::IntCounterVec;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Token bucket rate limiting for requests to indexers. Limits are applied
+/// per indexer host, so indexers that share a host also share a budget.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitConfig {
+    /// Sustained number of requests per second. Must be positive.
+    #[serde(deserialize_with = "deserialize_requests_per_second")]
+    pub requests_per_second: f64,
+    /// Maximum number of requests that can be sent at once after a period of
+    /// inactivity. Defaults to `requestsPerSecond`, rounded up.
+    #[serde(default)]
+    pub burst: Option<u32>,
+    /// Maximum number of requests in flight at any given time. Unlimited by
+    /// default.
+    #[serde(default)]
+    pub max_concurrent_requests: Option<NonZeroU32>,
+}
+
+/// A rate of zero would block requests forever, and a negative or non-finite
+/// one can't be turned into a delay.
+fn deserialize_requests_per_second<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<f64, D::Error> {
+    let requests_per_second = f64::deserialize(deserializer)?;
+    if requests_per_second.is_finite() && requests_per_second > 0.0 {
+        Ok(requests_per_second)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "`requestsPerSecond` must be positive, got {requests_per_second}"
+        )))
+    }
+}
+
+impl RateLimitConfig {
+    fn burst(&self) -> f64 {
+        self.burst
+            .map(f64::from)
+            .unwrap_or(self.requests_per_second.ceil())
+            .max(1.0)
+    }
+}
+
+/// Rate limiters shared by all [`RealIndexer`](crate::RealIndexer)s, keyed by
+/// host, so that they survive reconfiguration.
+static RATE_LIMITERS: OnceLock<Mutex<HashMap<String, Arc<RateLimiter>>>> = OnceLock::new();
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    host: String,
+    config: RateLimitConfig,
+    bucket: Mutex<TokenBucket>,
+    concurrency: Option<Arc<Semaphore>>,
+    throttled_requests: IntCounterVec,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Must be held for the duration of a request.
+pub struct RateLimitPermit {
+    _concurrency_permit: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    pub fn new(host: String, config: RateLimitConfig, throttled_requests: IntCounterVec) -> Self {
+        Self {
+            host,
+            config,
+            bucket: Mutex::new(TokenBucket {
+                tokens: config.burst(),
+                last_refill: Instant::now(),
+            }),
+            concurrency: config
+                .max_concurrent_requests
+                .map(|n| Arc::new(Semaphore::new(n.get() as usize))),
+            throttled_requests,
+        }
+    }
+
+    /// Returns the rate limiter for the host of `endpoint`, creating it if it
+    /// doesn't exist yet or if its configuration changed.
+    pub fn for_endpoint(
+        endpoint: &str,
+        config: RateLimitConfig,
+        throttled_requests: IntCounterVec,
+    ) -> Arc<Self> {
+        let host = reqwest::Url::parse(endpoint)
+            .ok()
+            .and_then(|url| {
+                let host = url.host_str()?.to_string();
+                Some(match url.port() {
+                    Some(port) => format!("{host}:{port}"),
+                    None => host,
+                })
+            })
+            .unwrap_or_else(|| endpoint.to_string());
+
+        let mut rate_limiters = RATE_LIMITERS.get_or_init(Default::default).lock().unwrap();
+        match rate_limiters.get(&host) {
+            Some(rate_limiter) if rate_limiter.config == config => rate_limiter.clone(),
+            _ => {
+                let rate_limiter = Arc::new(Self::new(host.clone(), config, throttled_requests));
+                rate_limiters.insert(host, rate_limiter.clone());
+                rate_limiter
+            }
+        }
+    }
+
+    /// Waits until a request can be sent.
+    pub async fn acquire(&self) -> RateLimitPermit {
+        let concurrency_permit = match &self.concurrency {
+            Some(semaphore) => {
+                if semaphore.available_permits() == 0 {
+                    self.inc_throttled_requests();
+                }
+                Some(
+                    semaphore
+                        .clone()
+                        .acquire_owned()
+                        .await
+                        .expect("the semaphore is never closed"),
+                )
+            }
+            None => None,
+        };
+
+        let mut throttled = false;
+        while let Some(wait) = self.take_token() {
+            if !throttled {
+                self.inc_throttled_requests();
+                throttled = true;
+            }
+            tokio::time::sleep(wait).await;
+        }
+
+        RateLimitPermit {
+            _concurrency_permit: concurrency_permit,
+        }
+    }
+
+    /// Takes a token from the bucket, or returns how long to wait until one
+    /// becomes available.
+    fn take_token(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens =
+            (bucket.tokens + elapsed * self.config.requests_per_second).min(self.config.burst());
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            Some(Duration::from_secs_f64(
+                missing / self.config.requests_per_second,
+            ))
+        }
+    }
+
+    fn inc_throttled_requests(&self) {
+        self.throttled_requests
+            .get_metric_with_label_values(&[&self.host])
+            .unwrap()
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttled_requests() -> IntCounterVec {
+        IntCounterVec::new(prometheus::Opts::new("foo", "bar"), &["host"]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn throttles_after_burst() {
+        let config = RateLimitConfig {
+            requests_per_second: 20.0,
+            burst: Some(2),
+            max_concurrent_requests: None,
+        };
+        let throttled_requests = throttled_requests();
+        let rate_limiter = RateLimiter::new("host".to_string(), config, throttled_requests.clone());
+        let throttled = || throttled_requests.with_label_values(&["host"]).get();
+
+        let start = Instant::now();
+        rate_limiter.acquire().await;
+        rate_limiter.acquire().await;
+        assert_eq!(throttled(), 0);
+
+        rate_limiter.acquire().await;
+        assert_eq!(throttled(), 1);
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn shared_by_host() {
+        let config = RateLimitConfig {
+            requests_per_second: 1.0,
+            burst: None,
+            max_concurrent_requests: NonZeroU32::new(1),
+        };
+        let a = RateLimiter::for_endpoint(
+            "http://shared-host:8030/graphql",
+            config,
+            throttled_requests(),
+        );
+        let b = RateLimiter::for_endpoint(
+            "http://shared-host:8030/status",
+            config,
+            throttled_requests(),
+        );
+        let c = RateLimiter::for_endpoint("http://other-host/status", config, throttled_requests());
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+
+    #[test]
+    fn rejects_rates_and_concurrency_that_would_block_or_panic() {
+        let parse = |json: serde_json::Value| serde_json::from_value::<RateLimitConfig>(json);
+
+        for requests_per_second in [0.0, -1.0] {
+            let err =
+                parse(serde_json::json!({ "requestsPerSecond": requests_per_second })).unwrap_err();
+            assert!(err.to_string().contains("requestsPerSecond"), "{err}");
+        }
+        assert!(parse(serde_json::json!({
+            "requestsPerSecond": 1.0,
+            "maxConcurrentRequests": 0,
+        }))
+        .is_err());
+
+        let config = parse(serde_json::json!({
+            "requestsPerSecond": 0.5,
+            "maxConcurrentRequests": 2,
+        }))
+        .unwrap();
+        assert_eq!(config.max_concurrent_requests, NonZeroU32::new(2));
+    }
+}
@@ -1,18 +1,23 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{anyhow, Context};
 use async_trait::async_trait;
-use graphix_common_types::IndexerAddress;
+use chrono::{DateTime, Utc};
+use graphix_common_types::{IndexerAddress, IndexerErrorClass, IpfsCid};
 use graphql_client::{GraphQLQuery, Response};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use tracing::*;
 
-use super::{CachedEthereumCall, EntityChanges, IndexerClient};
+use super::{CachedEthereumCall, EntityChanges, IndexerAuth, IndexerClient};
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::rate_limiter::{RateLimitConfig, RateLimiter};
 use crate::{
-    GraphNodeCollectedVersion, IndexerId, IndexingStatus, PoiRequest, ProofOfIndexing, WithIndexer,
+    classify_error, GraphNodeCollectedVersion, IndexerId, IndexingStatus, PoiRequest,
+    ProofOfIndexing, QueryFailure, WithIndexer,
 };
 
 const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
@@ -21,8 +26,13 @@ const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 pub struct RealIndexer {
     address: IndexerAddress,
     name: Option<String>,
+    network_subgraph: Option<String>,
+    operator_contact: Option<String>,
     endpoint: String,
     client: reqwest::Client,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    auth: Option<IndexerAuth>,
     // Metrics
     // -------
     public_poi_requests: prometheus::IntCounterVec,
@@ -38,13 +48,64 @@ impl RealIndexer {
     ) -> Self {
         Self {
             name,
+            network_subgraph: None,
+            operator_contact: None,
             address,
             endpoint,
             client: reqwest::Client::new(),
+            rate_limiter: None,
+            circuit_breaker: None,
+            auth: None,
             public_poi_requests,
         }
     }
 
+    /// Tags this indexer with the name of the network subgraph it was
+    /// discovered through.
+    pub fn with_network_subgraph(mut self, network_subgraph: Option<String>) -> Self {
+        self.network_subgraph = network_subgraph;
+        self
+    }
+
+    /// Sets the operator contact information of this indexer, as found on the
+    /// network subgraph.
+    pub fn with_operator_contact(mut self, operator_contact: Option<String>) -> Self {
+        self.operator_contact = operator_contact;
+        self
+    }
+
+    /// Throttles all requests to this indexer according to `rate_limit`, with a
+    /// budget that is shared with other indexers on the same host.
+    pub fn with_rate_limit(
+        mut self,
+        rate_limit: Option<RateLimitConfig>,
+        throttled_requests: prometheus::IntCounterVec,
+    ) -> Self {
+        self.rate_limiter = rate_limit
+            .map(|config| RateLimiter::for_endpoint(&self.endpoint, config, throttled_requests));
+        self
+    }
+
+    /// Skips requests to this indexer for a while after it failed too many
+    /// times in a row. The circuit breaker's state is shared with all other
+    /// clients of the same indexer, so that it survives reconfiguration.
+    pub fn with_circuit_breaker(
+        mut self,
+        circuit_breaker: Option<CircuitBreakerConfig>,
+        circuit_breaker_events: prometheus::IntCounterVec,
+    ) -> Self {
+        self.circuit_breaker = circuit_breaker.map(|config| {
+            CircuitBreaker::for_indexer(self.address, config, circuit_breaker_events)
+        });
+        self
+    }
+
+    /// Authenticates all requests to this indexer with `auth`.
+    pub fn with_auth(mut self, auth: Option<IndexerAuth>) -> Self {
+        self.auth = auth;
+        self
+    }
+
     /// Internal utility method to make a GraphQL query to the indexer. `error`
     /// and `data` fields are treated as mutually exclusive (which is generally
     /// a good assumption, but some callers may want more control over error
@@ -53,15 +114,63 @@ impl RealIndexer {
         &self,
         request: I,
     ) -> anyhow::Result<O> {
-        let response_raw = self
+        let Some(circuit_breaker) = &self.circuit_breaker else {
+            return self.graphql_query_unchecked(request).await;
+        };
+
+        circuit_breaker.check()?;
+        let result = self.graphql_query_unchecked(request).await;
+        circuit_breaker.record(result.as_ref().map(|_| ()).map_err(classify_error));
+        result
+    }
+
+    /// [`RealIndexer::graphql_query`], regardless of the circuit breaker.
+    async fn graphql_query_unchecked<I: Serialize, O: DeserializeOwned>(
+        &self,
+        request: I,
+    ) -> anyhow::Result<O> {
+        let _permit = match &self.rate_limiter {
+            Some(rate_limiter) => Some(rate_limiter.acquire().await),
+            None => None,
+        };
+
+        let mut request_builder = self
             .client
             .post(self.endpoint.clone())
             .timeout(REQUEST_TIMEOUT)
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        if let Some(auth) = &self.auth {
+            request_builder = auth.apply(request_builder);
+        }
+        let response_raw = request_builder.send().await?;
+
+        let status = response_raw.status();
+        let body = response_raw.text().await?;
+        let failure = |class: IndexerErrorClass, message: String| QueryFailure {
+            class,
+            raw_query: serde_json::to_string(&request).unwrap_or_default(),
+            response: body.clone(),
+            message,
+        };
 
-        let response: Response<O> = response_raw.json().await?;
+        let response: Response<O> = serde_json::from_str(&body).map_err(|err| {
+            if status.is_server_error() {
+                failure(
+                    IndexerErrorClass::HttpServerError,
+                    format!("Indexer responded with HTTP {status}"),
+                )
+            } else if status.is_client_error() {
+                failure(
+                    IndexerErrorClass::HttpClientError,
+                    format!("Indexer responded with HTTP {status}"),
+                )
+            } else {
+                failure(
+                    IndexerErrorClass::InvalidResponse,
+                    format!("Invalid response from indexer: {err}"),
+                )
+            }
+        })?;
 
         if let Some(errors) = response.errors {
             let errors = errors
@@ -70,7 +179,11 @@ impl RealIndexer {
                 .collect::<Vec<_>>()
                 .join(",");
             warn!(%errors, "Indexer returned errors");
-            return Err(anyhow::anyhow!("Indexer returned errors: {}", errors));
+            return Err(failure(
+                IndexerErrorClass::GraphqlError,
+                format!("Indexer returned errors: {}", errors),
+            )
+            .into());
         }
 
         response.data.context("Indexer returned no data")
@@ -95,34 +208,27 @@ impl RealIndexer {
 
         let response: ResponseData = self.graphql_query(request).await?;
 
-        // Parse POI results
+        // Parse POI results, which are in the same order as the requests
         response
             .public_proofs_of_indexing
             .into_iter()
-            .map(|result| WithIndexer::new(self.clone(), result).try_into())
+            .zip(requests)
+            .map(|(result, request)| {
+                WithIndexer::new(self.clone(), (result, request.network.clone())).try_into()
+            })
             .collect::<Result<Vec<_>, _>>()
     }
-}
 
-#[async_trait]
-impl IndexerClient for RealIndexer {
-    fn address(&self) -> IndexerAddress {
-        self.address
-    }
-
-    fn name(&self) -> Option<Cow<str>> {
-        self.name.as_ref().map(|s| Cow::Borrowed(s.as_str()))
-    }
-
-    async fn ping(self: Arc<Self>) -> anyhow::Result<()> {
-        let request = gql_types::Typename::build_query(gql_types::typename::Variables);
-        self.graphql_query::<_, serde_json::Value>(request).await?;
-        Ok(())
-    }
-
-    async fn indexing_statuses(self: Arc<Self>) -> anyhow::Result<Vec<IndexingStatus>> {
+    /// The indexing statuses of the given subgraph deployments, or of all of
+    /// them if `subgraphs` is `None`.
+    async fn indexing_statuses_of(
+        self: Arc<Self>,
+        subgraphs: Option<Vec<String>>,
+    ) -> anyhow::Result<Vec<IndexingStatus>> {
         let request =
-            gql_types::IndexingStatuses::build_query(gql_types::indexing_statuses::Variables);
+            gql_types::IndexingStatuses::build_query(gql_types::indexing_statuses::Variables {
+                subgraphs,
+            });
 
         let response: gql_types::indexing_statuses::ResponseData =
             self.graphql_query(request).await?;
@@ -146,6 +252,49 @@ impl IndexerClient for RealIndexer {
 
         Ok(statuses)
     }
+}
+
+#[async_trait]
+impl IndexerClient for RealIndexer {
+    fn address(&self) -> IndexerAddress {
+        self.address
+    }
+
+    fn name(&self) -> Option<Cow<str>> {
+        self.name.as_ref().map(|s| Cow::Borrowed(s.as_str()))
+    }
+
+    fn network_subgraph(&self) -> Option<Cow<str>> {
+        self.network_subgraph.as_deref().map(Cow::Borrowed)
+    }
+
+    fn operator_contact(&self) -> Option<Cow<str>> {
+        self.operator_contact.as_deref().map(Cow::Borrowed)
+    }
+
+    fn circuit_open_until(&self) -> Option<DateTime<Utc>> {
+        let open_until = self.circuit_breaker.as_ref()?.open_until()?;
+        let remaining = open_until.saturating_duration_since(Instant::now());
+        Some(Utc::now() + remaining)
+    }
+
+    async fn ping(self: Arc<Self>) -> anyhow::Result<()> {
+        let request = gql_types::Typename::build_query(gql_types::typename::Variables);
+        self.graphql_query::<_, serde_json::Value>(request).await?;
+        Ok(())
+    }
+
+    async fn indexing_statuses(self: Arc<Self>) -> anyhow::Result<Vec<IndexingStatus>> {
+        self.indexing_statuses_of(None).await
+    }
+
+    async fn deployment_indexing_statuses(
+        self: Arc<Self>,
+        deployment: &IpfsCid,
+    ) -> anyhow::Result<Vec<IndexingStatus>> {
+        self.indexing_statuses_of(Some(vec![deployment.to_string()]))
+            .await
+    }
 
     async fn proofs_of_indexing(
         self: Arc<Self>,
@@ -225,6 +374,21 @@ impl IndexerClient for RealIndexer {
             .collect())
     }
 
+    async fn subgraph_data_source_kinds(
+        self: Arc<Self>,
+        subgraph_id: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let request =
+            gql_types::SubgraphFeatures::build_query(gql_types::subgraph_features::Variables {
+                subgraph_id: subgraph_id.to_string(),
+            });
+
+        let response: gql_types::subgraph_features::ResponseData =
+            self.graphql_query(request).await?;
+
+        Ok(response.subgraph_features.data_sources)
+    }
+
     async fn version(self: Arc<Self>) -> anyhow::Result<GraphNodeCollectedVersion> {
         let request = gql_types::IndexerVersion::build_query(gql_types::indexer_version::Variables);
 
@@ -302,16 +466,22 @@ impl IndexerClient for RealIndexer {
 
         let mut updates = HashMap::new();
         for entity_type_updates in response.entity_changes_in_block.updates {
-            updates
+            if updates
                 .insert(entity_type_updates.type_, entity_type_updates.entities)
-                .ok_or_else(|| anyhow!("duplicate entity types"))?;
+                .is_some()
+            {
+                return Err(anyhow!("duplicate entity types"));
+            }
         }
 
         let mut deletions = HashMap::new();
         for entity_type_deletions in response.entity_changes_in_block.deletions {
-            deletions
+            if deletions
                 .insert(entity_type_deletions.type_, entity_type_deletions.entities)
-                .ok_or_else(|| anyhow!("duplicate entity types"))?;
+                .is_some()
+            {
+                return Err(anyhow!("duplicate entity types"));
+            }
         }
 
         Ok(EntityChanges { updates, deletions })
@@ -321,10 +491,10 @@ impl IndexerClient for RealIndexer {
 mod gql_types {
     use std::str::FromStr;
 
-    use graphix_common_types::{BlockHash, IpfsCid, PoiBytes};
+    use graphix_common_types::{BlockHash, IpfsCid, PoiBytes, SubgraphHealth};
 
     use super::*;
-    use crate::BlockPointer;
+    use crate::{BlockPointer, SubgraphError};
 
     pub type JSONObject = serde_json::Value;
     pub type BigInt = String;
@@ -389,12 +559,65 @@ mod gql_types {
             let deployment = IpfsCid::from_str(&self.inner.subgraph)
                 .map_err(|e| anyhow!("invalid subgraph CID: {}", e))?;
 
+            let health = match &self.inner.health {
+                indexing_statuses::Health::healthy => SubgraphHealth::Healthy,
+                indexing_statuses::Health::unhealthy => SubgraphHealth::Unhealthy,
+                indexing_statuses::Health::failed => SubgraphHealth::Failed,
+                // Newer graph-node versions may report health values we don't
+                // know about. Those deployments are still worth monitoring.
+                indexing_statuses::Health::Other(health) => {
+                    warn!(%deployment, %health, "Unknown subgraph health, assuming unhealthy");
+                    SubgraphHealth::Unhealthy
+                }
+            };
+
+            let parse_block = |number: &str, hash: &str| -> anyhow::Result<BlockPointer> {
+                Ok(BlockPointer {
+                    number: number.parse()?,
+                    hash: Some(
+                        hash.parse()
+                            .map_err(|e| anyhow!("invalid block hash: {}", e))?,
+                    ),
+                })
+            };
+            let mut errors = vec![];
+            if let Some(error) = &self.inner.fatal_error {
+                errors.push(SubgraphError {
+                    message: error.message.clone(),
+                    block: error
+                        .block
+                        .as_ref()
+                        .map(|block| parse_block(&block.number, &block.hash))
+                        .transpose()?,
+                    handler: error.handler.clone(),
+                    deterministic: error.deterministic,
+                    fatal: true,
+                });
+            }
+            for error in &self.inner.non_fatal_errors {
+                errors.push(SubgraphError {
+                    message: error.message.clone(),
+                    block: error
+                        .block
+                        .as_ref()
+                        .map(|block| parse_block(&block.number, &block.hash))
+                        .transpose()?,
+                    handler: error.handler.clone(),
+                    deterministic: error.deterministic,
+                    fatal: false,
+                });
+            }
+
             Ok(IndexingStatus {
                 indexer: self.indexer,
                 deployment,
                 network: chain.network.clone(),
                 latest_block,
                 earliest_block_num,
+                health,
+                synced: self.inner.synced,
+                paused: self.inner.paused,
+                errors,
             })
         }
     }
@@ -410,29 +633,35 @@ mod gql_types {
     )]
     pub struct ProofsOfIndexing;
 
+    /// A PoI paired with the network of the request that it answers, which
+    /// `graph-node` doesn't include in its response.
     impl TryInto<ProofOfIndexing>
-        for WithIndexer<proofs_of_indexing::ProofsOfIndexingPublicProofsOfIndexing>
+        for WithIndexer<(
+            proofs_of_indexing::ProofsOfIndexingPublicProofsOfIndexing,
+            String,
+        )>
     {
         type Error = anyhow::Error;
 
         fn try_into(self) -> Result<ProofOfIndexing, Self::Error> {
-            let deployment = IpfsCid::from_str(&self.inner.deployment)
+            let (poi, network) = self.inner;
+            let deployment = IpfsCid::from_str(&poi.deployment)
                 .map_err(|e| anyhow!("invalid deployment CID: {}", e))?;
 
             Ok(ProofOfIndexing {
                 indexer: self.indexer,
                 deployment,
+                network,
                 block: BlockPointer {
-                    number: self.inner.block.number.parse()?,
-                    hash: self
-                        .inner
+                    number: poi.block.number.parse()?,
+                    hash: poi
                         .block
                         .hash
                         .map(|hash_string| str::parse::<BlockHash>(hash_string.as_str()))
                         .transpose()
                         .map_err(|e| anyhow!("invalid block hash: {}", e))?,
                 },
-                proof_of_indexing: str::parse::<PoiBytes>(self.inner.proof_of_indexing.as_str())
+                proof_of_indexing: str::parse::<PoiBytes>(poi.proof_of_indexing.as_str())
                     .map_err(|e| anyhow!("invalid PoI value: {}", e))?,
             })
         }
@@ -456,6 +685,15 @@ mod gql_types {
     )]
     pub struct SubgraphApiVersions;
 
+    #[derive(GraphQLQuery)]
+    #[graphql(
+        schema_path = "graphql/indexer/schema.gql",
+        query_path = "graphql/indexer/queries/subgraph-features.gql",
+        response_derives = "Debug",
+        variables_derives = "Debug"
+    )]
+    pub struct SubgraphFeatures;
+
     #[derive(GraphQLQuery)]
     #[graphql(
         schema_path = "graphql/indexer/schema.gql",
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{GraphqlRequest, GraphqlResponse, DEFAULT_TIMEOUT, PAGINATION_SIZE};
+
+const NETWORK_EPOCH_BLOCK_NUMBERS_QUERY: &str =
+    include_str!("queries/network_epoch_block_numbers.graphql");
+
+/// A GraphQL client for the epoch block oracle subgraph, which knows the first
+/// block of every protocol epoch on each network supported by The Graph.
+#[derive(Debug, Clone)]
+pub struct EpochBlockOracleClient {
+    endpoint: Url,
+    timeout: Duration,
+    client: reqwest::Client,
+}
+
+/// The first block of a protocol epoch on some network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochStartBlock {
+    pub epoch: u64,
+    pub block_number: u64,
+}
+
+impl EpochBlockOracleClient {
+    pub fn new(endpoint: Url) -> Self {
+        Self {
+            endpoint,
+            timeout: DEFAULT_TIMEOUT,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Returns the start blocks of all epochs after `after_epoch` on the
+    /// network with the given CAIP-2 chain ID, in ascending order.
+    pub async fn epoch_start_blocks(
+        &self,
+        caip2: &str,
+        after_epoch: Option<u64>,
+    ) -> anyhow::Result<Vec<EpochStartBlock>> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ResponseData {
+            network_epoch_block_numbers: Vec<NetworkEpochBlockNumber>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct NetworkEpochBlockNumber {
+            epoch_number: String,
+            block_number: String,
+        }
+
+        let mut epochs: Vec<EpochStartBlock> = vec![];
+        loop {
+            // Pages are keyed by epoch number rather than skipped over, as
+            // `skip` gets slow for large values.
+            let after = match epochs.last().map(|e| e.epoch).or(after_epoch) {
+                Some(epoch) => epoch.to_string(),
+                None => "-1".to_string(),
+            };
+            let request = GraphqlRequest {
+                query: NETWORK_EPOCH_BLOCK_NUMBERS_QUERY.to_string(),
+                variables: BTreeMap::from_iter([
+                    ("network".to_string(), caip2.into()),
+                    ("after".to_string(), after.into()),
+                    ("first".to_string(), PAGINATION_SIZE.into()),
+                ]),
+            };
+            let response: GraphqlResponse = self
+                .client
+                .post(self.endpoint.as_str())
+                .json(&request)
+                .timeout(self.timeout)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let data = response.data.ok_or_else(|| {
+                anyhow!(
+                    "error(s) querying epoch start blocks from the epoch block oracle: {:?}",
+                    response.errors.unwrap_or_default()
+                )
+            })?;
+            let page = serde_json::from_value::<ResponseData>(data)?.network_epoch_block_numbers;
+
+            let page_len = page.len();
+            for item in page {
+                epochs.push(EpochStartBlock {
+                    epoch: item.epoch_number.parse()?,
+                    block_number: item.block_number.parse()?,
+                });
+            }
+
+            if page_len < PAGINATION_SIZE {
+                break;
+            }
+        }
+
+        Ok(epochs)
+    }
+}
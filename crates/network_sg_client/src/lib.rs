@@ -1,20 +1,34 @@
 #![allow(dead_code)]
 
+mod api_key;
+mod epoch_block_oracle;
+
 use std::collections::BTreeMap;
+use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::anyhow;
-use graphix_common_types::IndexerAddress;
-use graphix_indexer_client::{IndexerClient as IndexerTrait, RealIndexer};
+use graphix_common_types::{IndexerAddress, PoiBytes};
+use graphix_indexer_client::{
+    CircuitBreakerConfig, IndexerClient as IndexerTrait, RateLimitConfig, RealIndexer,
+};
 use prometheus::IntCounterVec;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use tracing::warn;
 use url::Url;
 
+pub use crate::api_key::{redact_endpoint, GatewayApiKey};
+pub use crate::epoch_block_oracle::{EpochBlockOracleClient, EpochStartBlock};
+
 const PAGINATION_SIZE: usize = 100;
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Retry delays, including those that the gateway asks for, are capped so
+/// that a single query can't hold up a polling cycle for too long.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
 
 /// A GraphQL client that can query the network subgraph and extract useful
 /// data.
@@ -22,22 +36,46 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 /// The queries available for this client are oriented towards the needs of
 /// Graphix, namely to find high-quality and important indexers and subgraph
 /// deployments.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct NetworkSubgraphClient {
     endpoint: Url,
+    name: Option<String>,
+    api_key: Option<GatewayApiKey>,
     timeout: Duration,
+    max_attempts: u32,
+    retry_delay: Duration,
+    rate_limit: Option<(RateLimitConfig, IntCounterVec)>,
+    circuit_breaker: Option<(CircuitBreakerConfig, IntCounterVec)>,
     client: reqwest::Client,
     // Metrics
     // -------
     public_poi_requests: IntCounterVec,
 }
 
+/// The endpoint and API key are redacted, so that they don't end up in logs.
+impl fmt::Debug for NetworkSubgraphClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetworkSubgraphClient")
+            .field("endpoint", &redact_endpoint(&self.endpoint))
+            .field("name", &self.name)
+            .field("api_key", &self.api_key)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
 impl NetworkSubgraphClient {
     /// Creates a new [`NetworkSubgraphClient`] with the given endpoint.
     pub fn new(endpoint: Url, public_poi_requests: IntCounterVec) -> Self {
         Self {
             endpoint,
+            name: None,
+            api_key: None,
             timeout: DEFAULT_TIMEOUT,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            rate_limit: None,
+            circuit_breaker: None,
             client: reqwest::Client::new(),
             public_poi_requests,
         }
@@ -51,6 +89,71 @@ impl NetworkSubgraphClient {
         self
     }
 
+    /// Retries failed queries until they were attempted `max_attempts` times,
+    /// waiting `initial_delay` before the first retry and twice as long before
+    /// each following one. Rate-limited queries wait as long as the gateway
+    /// asks them to instead, if it does.
+    ///
+    /// The default is 5 attempts, starting with a delay of 500ms.
+    pub fn with_retries(mut self, max_attempts: u32, initial_delay: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_delay = initial_delay;
+        self
+    }
+
+    /// Sets a human-readable name for this network subgraph (e.g.
+    /// `arbitrum-one`). All indexers, subgraph deployments and on-chain PoIs
+    /// returned by this client are tagged with it.
+    pub fn with_name(mut self, name: Option<String>) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Authenticates requests to the gateway with `api_key`, so that it doesn't
+    /// have to be embedded in the endpoint URL.
+    pub fn with_api_key(mut self, api_key: Option<GatewayApiKey>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Throttles requests to all indexers returned by this client. See
+    /// [`RealIndexer::with_rate_limit`].
+    pub fn with_rate_limit(
+        mut self,
+        rate_limit: Option<RateLimitConfig>,
+        throttled_requests: IntCounterVec,
+    ) -> Self {
+        self.rate_limit = rate_limit.map(|config| (config, throttled_requests));
+        self
+    }
+
+    /// Breaks the circuit of all indexers returned by this client when they
+    /// keep failing. See [`RealIndexer::with_circuit_breaker`].
+    pub fn with_circuit_breaker(
+        mut self,
+        circuit_breaker: Option<CircuitBreakerConfig>,
+        circuit_breaker_events: IntCounterVec,
+    ) -> Self {
+        self.circuit_breaker = circuit_breaker.map(|config| (config, circuit_breaker_events));
+        self
+    }
+
+    /// Applies this client's name, rate limit and circuit breaker to an
+    /// indexer it found.
+    fn configure_indexer(&self, indexer: RealIndexer) -> RealIndexer {
+        let indexer = indexer.with_network_subgraph(self.name.clone());
+        let indexer = match &self.rate_limit {
+            Some((config, throttled_requests)) => {
+                indexer.with_rate_limit(Some(*config), throttled_requests.clone())
+            }
+            None => indexer,
+        };
+        match &self.circuit_breaker {
+            Some((config, events)) => indexer.with_circuit_breaker(Some(*config), events.clone()),
+            None => indexer,
+        }
+    }
+
     pub async fn indexers_by_staked_tokens(&self) -> anyhow::Result<Vec<Arc<dyn IndexerTrait>>> {
         let response_data: GraphqlResponseTopIndexers = self
             .graphql_query_no_errors(
@@ -69,7 +172,7 @@ impl NetworkSubgraphClient {
             );
 
             match real_indexer {
-                Ok(indexer) => indexers.push(Arc::new(indexer)),
+                Ok(indexer) => indexers.push(Arc::new(self.configure_indexer(indexer))),
                 Err(e) => warn!(
                     err = %e.to_string(),
                     indexer_id,
@@ -97,14 +200,17 @@ impl NetworkSubgraphClient {
 
         let mut indexer_clients: Vec<Arc<dyn IndexerTrait>> = vec![];
         for indexer in indexers {
-            if let Some(url) = indexer.url {
+            if let Some(url) = &indexer.url {
                 let address = str::parse::<IndexerAddress>(&indexer.id)
                     .map_err(|e| anyhow!("invalid indexer address: {}", e))?;
-                let real_indexer = RealIndexer::new(
-                    indexer.default_display_name,
-                    address,
-                    Url::parse(&format!("{}/status", url))?.to_string(),
-                    self.public_poi_requests.clone(),
+                let real_indexer = self.configure_indexer(
+                    RealIndexer::new(
+                        indexer.default_display_name.clone(),
+                        address,
+                        Url::parse(&format!("{}/status", url))?.to_string(),
+                        self.public_poi_requests.clone(),
+                    )
+                    .with_operator_contact(indexer.operator_contact()),
                 );
                 indexer_clients.push(Arc::new(real_indexer));
             }
@@ -139,6 +245,8 @@ impl NetworkSubgraphClient {
         struct IndexerData {
             url: String,
             default_display_name: Option<String>,
+            #[serde(default)]
+            account: Option<GraphAccount>,
         }
 
         let indexer_data = response_data
@@ -146,22 +254,46 @@ impl NetworkSubgraphClient {
             .first()
             .ok_or_else(|| anyhow::anyhow!("No indexer found for address {}", address))?;
 
-        let indexer = RealIndexer::new(
-            indexer_data.default_display_name.clone(),
-            *address,
-            Url::parse(&format!("{}/status", indexer_data.url))?.to_string(),
-            self.public_poi_requests.clone(),
+        let operator_contact = indexer_data
+            .account
+            .as_ref()
+            .and_then(|account| account.metadata.as_ref())
+            .and_then(|metadata| metadata.website.clone());
+        let indexer = self.configure_indexer(
+            RealIndexer::new(
+                indexer_data.default_display_name.clone(),
+                *address,
+                Url::parse(&format!("{}/status", indexer_data.url))?.to_string(),
+                self.public_poi_requests.clone(),
+            )
+            .with_operator_contact(operator_contact),
         );
 
         Ok(Arc::new(indexer))
     }
 
+    /// Returns the metadata of the indexers with the given addresses. Indexers
+    /// that aren't on the network subgraph are left out.
+    pub async fn indexers_metadata(
+        &self,
+        addresses: &[IndexerAddress],
+    ) -> anyhow::Result<Vec<IndexerMetadata>> {
+        self.paginate::<GraphqlResponseIndexersMetadata, _>(
+            queries::INDEXERS_METADATA_QUERY,
+            vec![("ids".to_string(), serde_json::to_value(addresses)?)],
+            "error(s) querying indexer metadata from the network subgraph",
+            |response_data| response_data.indexers,
+            None,
+        )
+        .await
+    }
+
     /// Returns all subgraph deployments, ordered by curation signal amounts.
     pub async fn subgraph_deployments_by_signal(
         &self,
         limit: Option<u32>,
     ) -> anyhow::Result<Vec<SubgraphDeploymentWithAllocations>> {
-        let subgraph_deployments = self
+        let mut subgraph_deployments = self
             .paginate::<GraphqlResponseSgDeployments, _>(
                 queries::DEPLOYMENTS_QUERY,
                 vec![],
@@ -171,17 +303,165 @@ impl NetworkSubgraphClient {
             )
             .await?;
 
+        for deployment in subgraph_deployments.iter_mut() {
+            deployment.network_subgraph = self.name.clone();
+        }
+
         Ok(subgraph_deployments)
     }
 
+    /// Returns the PoIs that indexers submitted on-chain when closing their
+    /// allocations, most recently closed first.
+    ///
+    /// An allocation's PoI refers to the first block of the epoch in which the
+    /// allocation was closed, on the network of its subgraph deployment. That
+    /// block isn't known to the network subgraph, which only tracks epochs on
+    /// the protocol chain, so it's left to the caller to resolve.
+    pub async fn closed_allocation_pois(
+        &self,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Vec<ClosedAllocationPoi>> {
+        let allocations = self
+            .paginate::<GraphqlResponseAllocations, _>(
+                queries::CLOSED_ALLOCATION_POIS_QUERY,
+                vec![],
+                "error(s) querying closed allocations from the network subgraph",
+                |response_data| response_data.allocations,
+                limit,
+            )
+            .await?;
+
+        allocations
+            .into_iter()
+            .map(|allocation| {
+                Ok(ClosedAllocationPoi {
+                    indexer_address: str::parse(&allocation.indexer.id)
+                        .map_err(|e| anyhow!("invalid indexer address: {}", e))?,
+                    deployment: allocation.subgraph_deployment.ipfs_hash,
+                    poi: str::parse(&allocation.poi).map_err(|e| anyhow!("invalid PoI: {}", e))?,
+                    allocation_id: allocation.id,
+                    epoch: allocation.closed_at_epoch,
+                    network_subgraph: self.name.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the active allocations of the indexers with the given
+    /// addresses.
+    pub async fn active_allocations(
+        &self,
+        addresses: &[IndexerAddress],
+    ) -> anyhow::Result<Vec<ActiveAllocation>> {
+        let allocations = self
+            .paginate::<GraphqlResponseActiveAllocations, _>(
+                queries::ACTIVE_ALLOCATIONS_QUERY,
+                vec![("indexers".to_string(), serde_json::to_value(addresses)?)],
+                "error(s) querying active allocations from the network subgraph",
+                |response_data| response_data.allocations,
+                None,
+            )
+            .await?;
+
+        allocations
+            .into_iter()
+            .map(|allocation| {
+                Ok(ActiveAllocation {
+                    indexer_address: str::parse(&allocation.indexer.id)
+                        .map_err(|e| anyhow!("invalid indexer address: {}", e))?,
+                    deployment: allocation.subgraph_deployment.ipfs_hash,
+                    allocated_tokens: allocation.allocated_tokens,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the display names and version labels that the subgraph
+    /// deployments with the given IPFS CIDs were published with. Deployments
+    /// that were never published are left out.
+    pub async fn deployment_versions(
+        &self,
+        ipfs_hashes: &[String],
+    ) -> anyhow::Result<Vec<DeploymentVersion>> {
+        let mut versions = vec![];
+        // Keeps the `ipfsHash_in` filter of each query reasonably small.
+        for ipfs_hashes in ipfs_hashes.chunks(10 * PAGINATION_SIZE) {
+            let deployments = self
+                .paginate::<GraphqlResponseDeploymentVersions, _>(
+                    queries::DEPLOYMENT_VERSIONS_QUERY,
+                    vec![("ipfsHashes".to_string(), serde_json::to_value(ipfs_hashes)?)],
+                    "error(s) querying deployment versions from the network subgraph",
+                    |response_data| response_data.subgraph_deployments,
+                    None,
+                )
+                .await?;
+
+            versions.extend(deployments.into_iter().filter_map(|deployment| {
+                let version = deployment.versions.into_iter().next()?;
+                Some(DeploymentVersion {
+                    deployment: deployment.ipfs_hash,
+                    display_name: version.subgraph.metadata.and_then(|m| m.display_name),
+                    label: version.metadata.and_then(|m| m.label),
+                })
+            }));
+        }
+
+        Ok(versions)
+    }
+
     /// A wrapper around [`NetworkSubgraphClient::graphql_query`] that requires
     /// no errors in the response, and deserializes the response data into the
-    /// given type.
+    /// given type. Failed queries are retried, see
+    /// [`NetworkSubgraphClient::with_retries`].
     async fn graphql_query_no_errors<T: DeserializeOwned>(
         &self,
         query: impl ToString,
         variables: Vec<(String, serde_json::Value)>,
         err_msg: &str,
+    ) -> anyhow::Result<T> {
+        let query = query.to_string();
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .graphql_query_no_errors_once(&query, variables.clone(), err_msg)
+                .await;
+            match result {
+                Err(err) if attempt < self.max_attempts => {
+                    let delay = self.retry_delay(attempt, &err);
+                    warn!(
+                        endpoint = %redact_endpoint(&self.endpoint),
+                        attempt,
+                        ?delay,
+                        error = %err,
+                        "Network subgraph query failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// How long to wait before retrying a query that failed with `err` on its
+    /// `attempt`-th attempt.
+    fn retry_delay(&self, attempt: u32, err: &anyhow::Error) -> Duration {
+        let delay = match err.downcast_ref::<RateLimited>() {
+            Some(RateLimited {
+                retry_after: Some(retry_after),
+            }) => *retry_after,
+            _ => self
+                .retry_delay
+                .saturating_mul(2u32.saturating_pow(attempt - 1)),
+        };
+        delay.min(MAX_RETRY_DELAY)
+    }
+
+    async fn graphql_query_no_errors_once<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: Vec<(String, serde_json::Value)>,
+        err_msg: &str,
     ) -> anyhow::Result<T> {
         let response = self.graphql_query(query, variables).await?;
         let response_data = response.data.ok_or_else(|| {
@@ -207,20 +487,38 @@ impl NetworkSubgraphClient {
             variables: BTreeMap::from_iter(variables),
         };
 
-        tracing::trace!(timeout = ?self.timeout, endpoint = %self.endpoint, "Sending GraphQL request");
+        tracing::trace!(timeout = ?self.timeout, endpoint = %redact_endpoint(&self.endpoint), "Sending GraphQL request");
 
-        Ok(self
+        let mut request = self
             .client
             .post(self.endpoint.as_str())
             .json(&request)
-            .timeout(self.timeout)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?)
+            .timeout(self.timeout);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key.as_str());
+        }
+        // Errors would otherwise include the endpoint, and with it any API key
+        // that it embeds.
+        let response = request.send().await.map_err(reqwest::Error::without_url)?;
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs);
+            return Err(RateLimited { retry_after }.into());
+        }
+
+        let response = response
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?;
+        Ok(response.json().await.map_err(reqwest::Error::without_url)?)
     }
 
+    /// Queries all pages of a paginated query, each with retries. If a page
+    /// still fails after that, the items of the previous pages are returned,
+    /// as long as there are any.
     async fn paginate<R: DeserializeOwned, T>(
         &self,
         query: impl ToString,
@@ -237,9 +535,23 @@ impl NetworkSubgraphClient {
             variables.push(("first".to_string(), page_size.into()));
             variables.push(("skip".to_string(), items.len().into()));
 
-            let response_data: R = self
+            let response_data: R = match self
                 .graphql_query_no_errors(query.to_string(), variables, error_msg)
-                .await?;
+                .await
+            {
+                Ok(response_data) => response_data,
+                // A later page failing shouldn't throw away the earlier ones.
+                Err(err) if !items.is_empty() => {
+                    warn!(
+                        endpoint = %redact_endpoint(&self.endpoint),
+                        items = items.len(),
+                        error = %err,
+                        "Failed to query the next page from the network subgraph, returning partial results"
+                    );
+                    break;
+                }
+                Err(err) => return Err(err),
+            };
 
             // If we got less than the page size, we're done.
             let page_items = response_items(response_data);
@@ -262,11 +574,33 @@ impl NetworkSubgraphClient {
     }
 }
 
+/// The network subgraph, usually behind a gateway, rejected a query with
+/// HTTP 429 Too Many Requests.
+#[derive(Debug)]
+pub struct RateLimited {
+    /// How long the gateway asked to wait with the `Retry-After` header, if
+    /// it did.
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rate limited by the network subgraph")?;
+        if let Some(retry_after) = self.retry_after {
+            write!(f, ", retry after {retry_after:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
 fn indexer_allocation_data_to_real_indexer(
     indexer_allocation: IndexerAllocation,
     public_poi_requests: IntCounterVec,
 ) -> anyhow::Result<RealIndexer> {
     let name = indexer_allocation.indexer.default_display_name.clone();
+    let operator_contact = indexer_allocation.indexer.operator_contact();
     let indexer = indexer_allocation.indexer;
     let address = str::parse(&indexer.id).map_err(|e| anyhow!("invalid indexer address: {}", e))?;
     let mut url: Url = indexer
@@ -274,12 +608,10 @@ fn indexer_allocation_data_to_real_indexer(
         .ok_or_else(|| anyhow!("Indexer without URL"))?
         .parse()?;
     url.set_path("/status");
-    Ok(RealIndexer::new(
-        name,
-        address,
-        url.to_string(),
-        public_poi_requests,
-    ))
+    Ok(
+        RealIndexer::new(name, address, url.to_string(), public_poi_requests)
+            .with_operator_contact(operator_contact),
+    )
 }
 
 #[derive(Serialize)]
@@ -309,11 +641,131 @@ struct GraphqlResponseTopIndexers {
     indexers: Vec<Indexer>,
 }
 
+#[derive(Deserialize)]
+struct GraphqlResponseIndexersMetadata {
+    indexers: Vec<IndexerMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlResponseAllocations {
+    allocations: Vec<ClosedAllocation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClosedAllocation {
+    id: String,
+    poi: String,
+    closed_at_epoch: u64,
+    indexer: AllocationIndexer,
+    subgraph_deployment: AllocationDeployment,
+}
+
+#[derive(Deserialize)]
+struct AllocationIndexer {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AllocationDeployment {
+    ipfs_hash: String,
+}
+
+#[derive(Deserialize)]
+struct GraphqlResponseActiveAllocations {
+    allocations: Vec<ActiveAllocationData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActiveAllocationData {
+    allocated_tokens: String,
+    indexer: AllocationIndexer,
+    subgraph_deployment: AllocationDeployment,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlResponseDeploymentVersions {
+    subgraph_deployments: Vec<DeploymentVersionsData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeploymentVersionsData {
+    ipfs_hash: String,
+    versions: Vec<SubgraphVersionData>,
+}
+
+#[derive(Deserialize)]
+struct SubgraphVersionData {
+    metadata: Option<SubgraphVersionMetadata>,
+    subgraph: SubgraphData,
+}
+
+#[derive(Deserialize)]
+struct SubgraphVersionMetadata {
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SubgraphData {
+    metadata: Option<SubgraphMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubgraphMetadata {
+    display_name: Option<String>,
+}
+
+/// A PoI that an indexer submitted on-chain when closing an allocation.
+#[derive(Debug, Clone)]
+pub struct ClosedAllocationPoi {
+    pub allocation_id: String,
+    pub indexer_address: IndexerAddress,
+    /// IPFS CID of the subgraph deployment.
+    pub deployment: String,
+    pub poi: PoiBytes,
+    /// The epoch in which the allocation was closed. The PoI refers to its
+    /// first block on the network of the subgraph deployment.
+    pub epoch: u64,
+    /// The name of the network subgraph this PoI was found on, if any.
+    pub network_subgraph: Option<String>,
+}
+
+/// An indexer's active allocation to a subgraph deployment.
+#[derive(Debug, Clone)]
+pub struct ActiveAllocation {
+    pub indexer_address: IndexerAddress,
+    /// IPFS CID of the subgraph deployment.
+    pub deployment: String,
+    /// In GRT wei, as a decimal string.
+    pub allocated_tokens: String,
+}
+
+/// The subgraph version that a subgraph deployment was most recently
+/// published as.
+#[derive(Debug, Clone)]
+pub struct DeploymentVersion {
+    /// IPFS CID of the subgraph deployment.
+    pub deployment: String,
+    /// The display name of the subgraph that the version belongs to.
+    pub display_name: Option<String>,
+    /// The version label, e.g. `v0.1.0`.
+    pub label: Option<String>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SubgraphDeploymentWithAllocations {
     pub ipfs_hash: String,
     pub indexer_allocations: Vec<IndexerAllocation>,
+    /// The name of the network subgraph this deployment was found on, if any.
+    #[serde(skip)]
+    pub network_subgraph: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -328,6 +780,48 @@ pub struct Indexer {
     pub id: String,
     pub default_display_name: Option<String>,
     pub url: Option<String>,
+    #[serde(default)]
+    pub account: Option<GraphAccount>,
+}
+
+impl Indexer {
+    /// The website that the indexer operator published in their account
+    /// metadata, which is the closest thing to contact information that the
+    /// network subgraph has.
+    pub fn operator_contact(&self) -> Option<String> {
+        self.account.as_ref()?.metadata.as_ref()?.website.clone()
+    }
+}
+
+/// What the network subgraph knows about an indexer. Token amounts are in
+/// GRT wei, as decimal strings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexerMetadata {
+    pub id: IndexerAddress,
+    pub url: Option<String>,
+    #[serde(rename = "geoHash")]
+    pub geohash: Option<String>,
+    pub staked_tokens: String,
+    pub allocated_tokens: String,
+    pub locked_tokens: String,
+    pub query_fees_collected: String,
+    pub query_fee_rebates: String,
+    pub rewards_earned: String,
+    pub indexer_indexing_rewards: String,
+    pub delegator_indexing_rewards: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphAccount {
+    pub metadata: Option<GraphAccountMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphAccountMetadata {
+    pub website: Option<String>,
 }
 
 mod queries {
@@ -337,20 +831,195 @@ mod queries {
         include_str!("queries/indexers_by_allocations.graphql");
     pub const DEPLOYMENTS_QUERY: &str = include_str!("queries/deployments.graphql");
     pub const INDEXER_BY_ADDRESS_QUERY: &str = include_str!("queries/indexer_by_address.graphql");
+    pub const INDEXERS_METADATA_QUERY: &str = include_str!("queries/indexers_metadata.graphql");
+    pub const CLOSED_ALLOCATION_POIS_QUERY: &str =
+        include_str!("queries/closed_allocation_pois.graphql");
+    pub const ACTIVE_ALLOCATIONS_QUERY: &str = include_str!("queries/active_allocations.graphql");
+    pub const DEPLOYMENT_VERSIONS_QUERY: &str = include_str!("queries/deployment_versions.graphql");
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Mutex;
+
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::Json;
+
     use super::*;
 
+    /// A network subgraph with `items` numbered items, which fails queries of
+    /// pages according to `failures`.
+    struct MockNetworkSubgraph {
+        items: usize,
+        /// By `skip`, the responses to the next queries of that page, before
+        /// it's served normally.
+        failures: Mutex<BTreeMap<usize, Vec<Response>>>,
+        /// If set, queries without it as a bearer token are unauthorized.
+        api_key: Option<&'static str>,
+    }
+
+    #[derive(Deserialize)]
+    struct Page {
+        items: Vec<usize>,
+    }
+
+    async fn serve(network_subgraph: MockNetworkSubgraph) -> NetworkSubgraphClient {
+        async fn handler(
+            State(network_subgraph): State<Arc<MockNetworkSubgraph>>,
+            headers: axum::http::HeaderMap,
+            Json(request): Json<serde_json::Value>,
+        ) -> Response {
+            if let Some(api_key) = network_subgraph.api_key {
+                let authorization = headers.get(axum::http::header::AUTHORIZATION);
+                if authorization.and_then(|value| value.to_str().ok())
+                    != Some(&format!("Bearer {api_key}"))
+                {
+                    return StatusCode::UNAUTHORIZED.into_response();
+                }
+            }
+            let variable = |name: &str| request["variables"][name].as_u64().unwrap() as usize;
+            let (first, skip) = (variable("first"), variable("skip"));
+            if let Some(failures) = network_subgraph.failures.lock().unwrap().get_mut(&skip) {
+                if !failures.is_empty() {
+                    return failures.remove(0);
+                }
+            }
+
+            let items: Vec<usize> = (skip..network_subgraph.items.min(skip + first)).collect();
+            Json(serde_json::json!({ "data": { "items": items } })).into_response()
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        let router = axum::Router::new()
+            .route("/", axum::routing::post(handler))
+            .with_state(Arc::new(network_subgraph));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        NetworkSubgraphClient::new(
+            endpoint.parse().unwrap(),
+            IntCounterVec::new(prometheus::Opts::new("foo", "bar"), &["a", "b"]).unwrap(),
+        )
+        .with_retries(3, Duration::from_millis(1))
+    }
+
+    async fn paginate(client: &NetworkSubgraphClient) -> anyhow::Result<Vec<usize>> {
+        client
+            .paginate::<Page, _>("query", vec![], "error", |page| page.items, None)
+            .await
+    }
+
+    fn server_error() -> Response {
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let rate_limited = (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "0")]).into_response();
+        let graphql_error = Json(serde_json::json!({ "errors": [{ "message": "boo" }] }));
+        let client = serve(MockNetworkSubgraph {
+            items: 150,
+            failures: Mutex::new(BTreeMap::from([
+                (0, vec![server_error(), rate_limited]),
+                (100, vec![graphql_error.into_response()]),
+            ])),
+            api_key: None,
+        })
+        .await;
+
+        let items = paginate(&client).await.unwrap();
+        assert_eq!(items, (0..150).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn failed_pages_return_partial_results() {
+        let client = serve(MockNetworkSubgraph {
+            items: 250,
+            failures: Mutex::new(BTreeMap::from([(
+                100,
+                vec![server_error(), server_error(), server_error()],
+            )])),
+            api_key: None,
+        })
+        .await;
+
+        let items = paginate(&client).await.unwrap();
+        assert_eq!(items, (0..100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn failed_first_page_is_an_error() {
+        let client = serve(MockNetworkSubgraph {
+            items: 50,
+            failures: Mutex::new(BTreeMap::from([(
+                0,
+                vec![server_error(), server_error(), server_error()],
+            )])),
+            api_key: None,
+        })
+        .await;
+
+        assert!(paginate(&client).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn api_key_is_sent_as_bearer_token() {
+        let network_subgraph = || MockNetworkSubgraph {
+            items: 10,
+            failures: Mutex::new(BTreeMap::new()),
+            api_key: Some("secret"),
+        };
+
+        let client = serve(network_subgraph()).await;
+        let err = paginate(&client).await.unwrap_err();
+        assert!(err.to_string().contains("401"));
+
+        let client = serve(network_subgraph())
+            .await
+            .with_api_key(Some(GatewayApiKey::new("secret")));
+        assert_eq!(paginate(&client).await.unwrap().len(), 10);
+        assert!(!format!("{client:?}").contains("secret"));
+    }
+
+    #[test]
+    fn retry_delays_back_off_exponentially_up_to_a_cap() {
+        let client = NetworkSubgraphClient::new(
+            "http://localhost".parse().unwrap(),
+            IntCounterVec::new(prometheus::Opts::new("foo", "bar"), &["a", "b"]).unwrap(),
+        );
+        let err = anyhow!("boo");
+        let delays: Vec<_> = (1..=4)
+            .map(|attempt| client.retry_delay(attempt, &err))
+            .collect();
+        assert_eq!(
+            delays,
+            [500, 1000, 2000, 4000].map(Duration::from_millis).to_vec()
+        );
+        assert_eq!(client.retry_delay(20, &err), MAX_RETRY_DELAY);
+
+        let rate_limited = |retry_after| {
+            anyhow::Error::from(RateLimited {
+                retry_after: Some(Duration::from_secs(retry_after)),
+            })
+        };
+        assert_eq!(
+            client.retry_delay(1, &rate_limited(10)),
+            Duration::from_secs(10)
+        );
+        assert_eq!(client.retry_delay(1, &rate_limited(3600)), MAX_RETRY_DELAY);
+    }
+
     fn network_sg_client_on_ethereum() -> NetworkSubgraphClient {
         let api_key = std::env::var("GRAPHIX_GATEWAY_API_KEY").unwrap();
         NetworkSubgraphClient::new(
-            format!(
-                "https://gateway.thegraph.com/api/{api_key}/subgraphs/id/DZz4kDTdmzWLWsV373w2bSmoar3umKKH9y82SUKr5qmp"
-            ).parse().unwrap(),
+            "https://gateway.thegraph.com/api/subgraphs/id/DZz4kDTdmzWLWsV373w2bSmoar3umKKH9y82SUKr5qmp"
+                .parse()
+                .unwrap(),
             IntCounterVec::new(prometheus::Opts::new("foo", "bar"), &["a", "b"]).unwrap(),
         )
+        .with_api_key(Some(GatewayApiKey::new(api_key)))
     }
 
     #[tokio::test]
@@ -394,6 +1063,13 @@ mod tests {
         assert_eq!(deployments.len(), 150);
     }
 
+    #[tokio::test]
+    async fn mainnet_closed_allocation_pois() {
+        let client = network_sg_client_on_ethereum();
+        let pois = client.closed_allocation_pois(Some(10)).await.unwrap();
+        assert_eq!(pois.len(), 10);
+    }
+
     #[tokio::test]
     async fn mainnet_fetch_ellipfra() {
         let client = network_sg_client_on_ethereum();
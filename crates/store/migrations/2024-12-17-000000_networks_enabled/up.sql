@@ -0,0 +1,3 @@
+-- Disabled networks are skipped by the main indexing loop, but all of their
+-- configuration and data is preserved.
+ALTER TABLE networks ADD COLUMN enabled BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1 @@
+ALTER TABLE indexers DROP COLUMN network_subgraph;
@@ -0,0 +1,3 @@
+-- The name of the network subgraph through which an indexer was discovered,
+-- if any. Used to tell apart indexers from multiple network subgraphs.
+ALTER TABLE indexers ADD COLUMN network_subgraph TEXT;
@@ -0,0 +1,16 @@
+-- The most recent indexing status flags reported by each indexer for each
+-- subgraph deployment, refreshed on every polling cycle. A paused or failed
+-- deployment is a legitimate reason for a stale PoI.
+CREATE TABLE indexing_statuses (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+  sg_deployment_id INTEGER NOT NULL REFERENCES sg_deployments(id) ON DELETE CASCADE,
+  health INTEGER NOT NULL,
+  synced BOOLEAN NOT NULL,
+  paused BOOLEAN,
+  updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+  UNIQUE (indexer_id, sg_deployment_id)
+);
+
+CREATE INDEX ON indexing_statuses (sg_deployment_id);
@@ -0,0 +1 @@
+DROP SCHEMA subgraph_feed CASCADE;
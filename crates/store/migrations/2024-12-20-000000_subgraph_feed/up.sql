@@ -0,0 +1,31 @@
+-- Graphix findings, laid out for ingestion by an external subgraph. Rows are
+-- append-only and `id`s are monotonically increasing, so consumers can use the
+-- last seen `id` as a cursor.
+CREATE SCHEMA subgraph_feed;
+
+-- The PoI that the majority of indexers agreed on for a subgraph deployment at
+-- a given block, one row per polling cycle. `consensus_poi` is NULL if there
+-- was no majority.
+CREATE TABLE subgraph_feed.poi_consensus (
+  id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  deployment TEXT NOT NULL,
+  block_number BIGINT NOT NULL,
+  block_hash BYTEA,
+  consensus_poi BYTEA,
+  indexer_count INTEGER NOT NULL,
+  agreeing_indexer_count INTEGER NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- Indexers whose PoI differs from the consensus PoI.
+CREATE TABLE subgraph_feed.poi_divergences (
+  id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  deployment TEXT NOT NULL,
+  block_number BIGINT NOT NULL,
+  indexer_address BYTEA NOT NULL,
+  poi BYTEA NOT NULL,
+  consensus_poi BYTEA NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON subgraph_feed.poi_divergences (deployment, block_number);
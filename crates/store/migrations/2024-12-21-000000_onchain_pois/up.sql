@@ -0,0 +1,17 @@
+-- PoIs that indexers submitted on-chain when closing allocations, as found on
+-- network subgraphs. Indexers and deployments are stored by address and CID
+-- rather than by reference, because Graphix doesn't necessarily track them.
+CREATE TABLE onchain_pois (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  allocation_id TEXT UNIQUE NOT NULL,
+  indexer_address BYTEA NOT NULL,
+  deployment TEXT NOT NULL,
+  poi BYTEA NOT NULL,
+  epoch BIGINT NOT NULL,
+  block_number BIGINT NOT NULL,
+  network_subgraph TEXT,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON onchain_pois (deployment, block_number);
+CREATE INDEX ON onchain_pois (indexer_address);
@@ -0,0 +1,3 @@
+ALTER TABLE indexing_statuses
+  DROP COLUMN latest_block_number,
+  DROP COLUMN earliest_block_number;
@@ -0,0 +1,5 @@
+-- Block ranges reported alongside the indexing status flags, so that block
+-- choice policies can be replayed on stored data.
+ALTER TABLE indexing_statuses
+  ADD COLUMN latest_block_number BIGINT,
+  ADD COLUMN earliest_block_number BIGINT;
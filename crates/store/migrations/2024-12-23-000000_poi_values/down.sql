@@ -0,0 +1,13 @@
+ALTER TABLE pois
+ADD COLUMN poi BYTEA;
+
+UPDATE pois
+SET poi = poi_values.poi
+FROM poi_values
+WHERE pois.poi_value_id = poi_values.id;
+
+ALTER TABLE pois
+ALTER COLUMN poi SET NOT NULL,
+DROP COLUMN poi_value_id;
+
+DROP TABLE poi_values;
@@ -0,0 +1,23 @@
+-- Many indexers report the same PoI for the same subgraph deployment and
+-- block, so PoI bytes are stored only once and referenced by `pois`.
+CREATE TABLE poi_values (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  poi BYTEA NOT NULL UNIQUE
+);
+
+INSERT INTO poi_values (poi)
+SELECT DISTINCT poi FROM pois;
+
+ALTER TABLE pois
+ADD COLUMN poi_value_id INTEGER REFERENCES poi_values(id);
+
+UPDATE pois
+SET poi_value_id = poi_values.id
+FROM poi_values
+WHERE pois.poi = poi_values.poi;
+
+ALTER TABLE pois
+ALTER COLUMN poi_value_id SET NOT NULL,
+DROP COLUMN poi;
+
+CREATE INDEX ON pois (poi_value_id);
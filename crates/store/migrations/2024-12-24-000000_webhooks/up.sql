@@ -0,0 +1,11 @@
+-- Webhooks registered by API key holders, notified of PoI divergences in the
+-- given subgraph deployments. Deleting an API key also deletes its webhooks.
+CREATE TABLE webhooks (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  api_key_public_prefix TEXT NOT NULL REFERENCES graphix_api_tokens(public_prefix) ON DELETE CASCADE,
+  url TEXT NOT NULL,
+  deployments TEXT[] NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON webhooks (api_key_public_prefix);
@@ -0,0 +1,7 @@
+-- Written by the indexing loop when it shuts down cleanly, after finishing its
+-- pending database writes.
+CREATE TABLE indexing_loop_checkpoints (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  indexer_addresses BYTEA[] NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE graphix_api_tokens
+DROP COLUMN organization;
@@ -0,0 +1,6 @@
+-- API keys of the same organization form a tenant, and share user-generated
+-- data such as webhooks.
+ALTER TABLE graphix_api_tokens
+ADD COLUMN organization TEXT;
+
+CREATE INDEX ON graphix_api_tokens (organization);
@@ -0,0 +1,3 @@
+ALTER TABLE indexers
+DROP COLUMN network_subgraph_operator_contact,
+DROP COLUMN manual_operator_contact;
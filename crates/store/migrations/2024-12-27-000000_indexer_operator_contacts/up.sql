@@ -0,0 +1,5 @@
+-- Contact information of indexer operators, either found on the network
+-- subgraph or set manually. The manually set one takes precedence.
+ALTER TABLE indexers
+ADD COLUMN network_subgraph_operator_contact TEXT,
+ADD COLUMN manual_operator_contact TEXT;
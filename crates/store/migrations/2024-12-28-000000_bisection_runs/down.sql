@@ -0,0 +1,44 @@
+ALTER TABLE divergence_investigation_reports
+ADD COLUMN report JSONB;
+
+UPDATE divergence_investigation_reports r
+SET report = jsonb_build_object(
+  'uuid', r.uuid,
+  'status', 'Complete',
+  'error', r.error,
+  'bisection_runs', COALESCE((
+    SELECT jsonb_agg(jsonb_build_object(
+      'uuid', runs.uuid,
+      'poi1', '0x' || encode(runs.poi1, 'hex'),
+      'poi2', '0x' || encode(runs.poi2, 'hex'),
+      'indexer1_operator_contact', runs.indexer1_operator_contact,
+      'indexer2_operator_contact', runs.indexer2_operator_contact,
+      'divergence_block_bounds', jsonb_build_object(
+        'lower_bound', jsonb_build_object('number', runs.divergence_lower_bound, 'hash', NULL),
+        'upper_bound', jsonb_build_object('number', runs.divergence_upper_bound, 'hash', NULL)
+      ),
+      'bisects', COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+          'block', jsonb_build_object(
+            'number', steps.block_number,
+            'hash', '0x' || encode(steps.block_hash, 'hex')
+          ),
+          'indexer1_response', steps.indexer1_response,
+          'indexer2_response', steps.indexer2_response
+        ) ORDER BY steps.id)
+        FROM bisection_steps steps
+        WHERE steps.bisection_run_id = runs.id
+      ), '[]'::jsonb),
+      'error', runs.error
+    ) ORDER BY runs.id)
+    FROM bisection_runs runs
+    WHERE runs.investigation_uuid = r.uuid
+  ), '[]'::jsonb)
+);
+
+ALTER TABLE divergence_investigation_reports
+ALTER COLUMN report SET NOT NULL,
+DROP COLUMN error;
+
+DROP TABLE bisection_steps;
+DROP TABLE bisection_runs;
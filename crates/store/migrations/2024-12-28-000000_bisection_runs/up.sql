@@ -0,0 +1,115 @@
+-- Bisection runs used to be stored as part of opaque JSON reports. Normalized
+-- tables make it possible to query divergences by block, deployment, and time.
+CREATE TABLE bisection_runs (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  uuid UUID NOT NULL UNIQUE,
+  investigation_uuid UUID NOT NULL REFERENCES divergence_investigation_reports(uuid) ON DELETE CASCADE,
+  poi1 BYTEA NOT NULL,
+  poi2 BYTEA NOT NULL,
+  -- The collected PoIs, deployment, and block that the bisection run started
+  -- from, if known. Reports outlive the data they were created from.
+  poi1_id INTEGER REFERENCES pois(id) ON DELETE SET NULL,
+  poi2_id INTEGER REFERENCES pois(id) ON DELETE SET NULL,
+  sg_deployment_id INTEGER REFERENCES sg_deployments(id) ON DELETE SET NULL,
+  block_id BIGINT REFERENCES blocks(id) ON DELETE SET NULL,
+  divergence_lower_bound BIGINT NOT NULL,
+  divergence_upper_bound BIGINT NOT NULL,
+  indexer1_operator_contact TEXT,
+  indexer2_operator_contact TEXT,
+  error TEXT,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON bisection_runs (investigation_uuid);
+CREATE INDEX ON bisection_runs (sg_deployment_id);
+CREATE INDEX ON bisection_runs (divergence_upper_bound);
+CREATE INDEX ON bisection_runs (created_at);
+
+CREATE TABLE bisection_steps (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  bisection_run_id INTEGER NOT NULL REFERENCES bisection_runs(id) ON DELETE CASCADE,
+  block_number BIGINT NOT NULL,
+  block_hash BYTEA,
+  indexer1_response TEXT NOT NULL,
+  indexer2_response TEXT NOT NULL
+);
+
+CREATE INDEX ON bisection_steps (bisection_run_id);
+
+ALTER TABLE divergence_investigation_reports
+ADD COLUMN error TEXT;
+
+-- Move existing reports over.
+UPDATE divergence_investigation_reports
+SET error = report->>'error';
+
+INSERT INTO bisection_runs (
+  uuid,
+  investigation_uuid,
+  poi1,
+  poi2,
+  poi1_id,
+  poi2_id,
+  sg_deployment_id,
+  block_id,
+  divergence_lower_bound,
+  divergence_upper_bound,
+  indexer1_operator_contact,
+  indexer2_operator_contact,
+  error,
+  created_at
+)
+SELECT
+  (run->>'uuid')::uuid,
+  r.uuid,
+  decode(substr(run->>'poi1', 3), 'hex'),
+  decode(substr(run->>'poi2', 3), 'hex'),
+  poi1.id,
+  poi2.id,
+  poi1.sg_deployment_id,
+  poi1.block_id,
+  (run->'divergence_block_bounds'->'lower_bound'->>'number')::bigint,
+  (run->'divergence_block_bounds'->'upper_bound'->>'number')::bigint,
+  run->>'indexer1_operator_contact',
+  run->>'indexer2_operator_contact',
+  run->>'error',
+  r.created_at
+FROM divergence_investigation_reports r
+CROSS JOIN LATERAL jsonb_array_elements(r.report->'bisection_runs') WITH ORDINALITY AS runs(run, i)
+LEFT JOIN LATERAL (
+  SELECT pois.id, pois.sg_deployment_id, pois.block_id
+  FROM pois
+  INNER JOIN poi_values ON poi_values.id = pois.poi_value_id
+  WHERE poi_values.poi = decode(substr(run->>'poi1', 3), 'hex')
+  LIMIT 1
+) poi1 ON TRUE
+LEFT JOIN LATERAL (
+  SELECT pois.id
+  FROM pois
+  INNER JOIN poi_values ON poi_values.id = pois.poi_value_id
+  WHERE poi_values.poi = decode(substr(run->>'poi2', 3), 'hex')
+  LIMIT 1
+) poi2 ON TRUE
+ORDER BY r.created_at, i;
+
+INSERT INTO bisection_steps (
+  bisection_run_id,
+  block_number,
+  block_hash,
+  indexer1_response,
+  indexer2_response
+)
+SELECT
+  bisection_runs.id,
+  (step->'block'->>'number')::bigint,
+  decode(substr(step->'block'->>'hash', 3), 'hex'),
+  step->>'indexer1_response',
+  step->>'indexer2_response'
+FROM divergence_investigation_reports r
+CROSS JOIN LATERAL jsonb_array_elements(r.report->'bisection_runs') AS runs(run)
+INNER JOIN bisection_runs ON bisection_runs.uuid = (run->>'uuid')::uuid
+CROSS JOIN LATERAL jsonb_array_elements(run->'bisects') WITH ORDINALITY AS steps(step, i)
+ORDER BY bisection_runs.id, i;
+
+ALTER TABLE divergence_investigation_reports
+DROP COLUMN report;
@@ -0,0 +1,4 @@
+ALTER TABLE pois
+DROP COLUMN epoch;
+
+DROP TABLE network_epochs;
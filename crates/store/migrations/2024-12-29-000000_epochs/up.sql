@@ -0,0 +1,14 @@
+-- The first block of each protocol epoch, per network, as reported by the
+-- epoch block oracle.
+CREATE TABLE network_epochs (
+  network_id INTEGER NOT NULL REFERENCES networks(id) ON DELETE CASCADE,
+  epoch BIGINT NOT NULL,
+  start_block_number BIGINT NOT NULL,
+  PRIMARY KEY (network_id, epoch)
+);
+
+-- The protocol epoch that a PoI's block belongs to, once known.
+ALTER TABLE pois
+ADD COLUMN epoch BIGINT;
+
+CREATE INDEX ON pois (epoch);
@@ -0,0 +1,10 @@
+-- How often requests to each indexer failed, by error class (see
+-- `IndexerErrorClass`), and the most recent error of each class.
+CREATE TABLE indexer_errors (
+  indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+  error_class INTEGER NOT NULL,
+  count BIGINT NOT NULL,
+  last_error TEXT NOT NULL,
+  last_error_at TIMESTAMP NOT NULL,
+  PRIMARY KEY (indexer_id, error_class)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE indexers
+DROP COLUMN tags;
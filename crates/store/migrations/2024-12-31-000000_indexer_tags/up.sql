@@ -0,0 +1,6 @@
+-- Free-form labels that operators use to group indexers, e.g. "testnet" or
+-- "own-fleet". Kept sorted and without duplicates.
+ALTER TABLE indexers
+ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX ON indexers USING GIN (tags);
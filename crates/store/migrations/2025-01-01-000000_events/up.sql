@@ -0,0 +1,20 @@
+-- An append-only log of events written by all subsystems (see `EventKind`).
+-- GraphQL subscriptions and webhooks are fed from it.
+CREATE TABLE events (
+  id BIGSERIAL PRIMARY KEY,
+  kind INTEGER NOT NULL,
+  -- The IPFS CID of the subgraph deployment that the event is about, if any.
+  deployment TEXT,
+  payload JSONB NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON events (kind, id);
+CREATE INDEX ON events (deployment, id);
+
+-- The ID of the last event that each consumer of the event log (e.g. webhook
+-- delivery) processed.
+CREATE TABLE event_cursors (
+  consumer TEXT PRIMARY KEY,
+  last_event_id BIGINT NOT NULL
+);
@@ -0,0 +1 @@
+DROP TABLE indexing_errors;
@@ -0,0 +1,18 @@
+-- Errors that indexers' `graph-node`s reported while indexing subgraph
+-- deployments, as part of their indexing statuses. An error that's reported
+-- again only updates `last_seen_at`.
+CREATE TABLE indexing_errors (
+  id SERIAL PRIMARY KEY,
+  indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+  sg_deployment_id INTEGER NOT NULL REFERENCES sg_deployments(id) ON DELETE CASCADE,
+  fatal BOOLEAN NOT NULL,
+  deterministic BOOLEAN NOT NULL,
+  message TEXT NOT NULL,
+  block_number BIGINT,
+  block_hash BYTEA,
+  handler TEXT,
+  first_seen_at TIMESTAMP NOT NULL,
+  last_seen_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON indexing_errors (sg_deployment_id, indexer_id);
@@ -0,0 +1,4 @@
+-- Overrides the configured rate limit of GraphQL API requests for the key.
+-- NULL uses the configured rate limit.
+ALTER TABLE graphix_api_tokens
+ADD COLUMN requests_per_minute INTEGER;
@@ -0,0 +1,2 @@
+ALTER TABLE sg_deployments DROP CONSTRAINT sg_deployments_ipfs_cid_network_key;
+ALTER TABLE sg_deployments ADD CONSTRAINT sg_deployments_ipfs_cid_key UNIQUE (ipfs_cid);
@@ -0,0 +1,4 @@
+-- The same IPFS CID can be indexed on several networks, and each of them is a
+-- separate subgraph deployment.
+ALTER TABLE sg_deployments DROP CONSTRAINT sg_deployments_ipfs_cid_key;
+ALTER TABLE sg_deployments ADD CONSTRAINT sg_deployments_ipfs_cid_network_key UNIQUE (ipfs_cid, network);
@@ -0,0 +1 @@
+DROP TABLE indexer_latency_samples;
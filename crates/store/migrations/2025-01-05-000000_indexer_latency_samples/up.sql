@@ -0,0 +1,14 @@
+-- Timed requests to indexers, sampled periodically to benchmark their
+-- responsiveness. `probe` is the kind of request (see `LatencyProbe`), and
+-- `latency_ms` is measured on failure, too.
+CREATE TABLE indexer_latency_samples (
+  id BIGSERIAL PRIMARY KEY,
+  indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+  probe INTEGER NOT NULL,
+  latency_ms INTEGER NOT NULL,
+  success BOOLEAN NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON indexer_latency_samples (created_at);
+CREATE INDEX ON indexer_latency_samples (indexer_id, created_at);
@@ -0,0 +1 @@
+ALTER TABLE bisection_runs DROP COLUMN entity_diff;
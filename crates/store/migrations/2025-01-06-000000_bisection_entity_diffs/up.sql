@@ -0,0 +1 @@
+ALTER TABLE bisection_runs ADD COLUMN entity_diff JSONB;
@@ -0,0 +1 @@
+ALTER TABLE bisection_runs DROP COLUMN eth_call_diff;
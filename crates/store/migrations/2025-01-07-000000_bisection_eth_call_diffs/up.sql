@@ -0,0 +1 @@
+ALTER TABLE bisection_runs ADD COLUMN eth_call_diff JSONB;
@@ -0,0 +1 @@
+ALTER TABLE sg_deployments ADD COLUMN manifest JSONB;
@@ -0,0 +1 @@
+ALTER TABLE bisection_runs ADD COLUMN graft_divergence JSONB;
@@ -0,0 +1,6 @@
+DROP TABLE sg_deployment_events;
+
+ALTER TABLE sg_deployments
+  DROP COLUMN first_seen_at,
+  DROP COLUMN last_seen_at,
+  DROP COLUMN status;
@@ -0,0 +1,20 @@
+-- When indexers first and last reported each subgraph deployment in their
+-- indexing statuses, and whether they still do (see
+-- `DeploymentLifecycleStatus`). All of them are NULL for deployments that no
+-- indexer reported yet.
+ALTER TABLE sg_deployments
+  ADD COLUMN first_seen_at TIMESTAMP,
+  ADD COLUMN last_seen_at TIMESTAMP,
+  ADD COLUMN status INTEGER;
+
+-- Changes in how indexers report subgraph deployments in their indexing
+-- statuses, from one polling cycle to the next (see `DeploymentEventKind`).
+CREATE TABLE sg_deployment_events (
+  id SERIAL PRIMARY KEY,
+  indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+  sg_deployment_id INTEGER NOT NULL REFERENCES sg_deployments(id) ON DELETE CASCADE,
+  kind INTEGER NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON sg_deployment_events (sg_deployment_id, created_at);
@@ -0,0 +1,10 @@
+ALTER TABLE live_pois
+  DROP CONSTRAINT live_pois_indexer_id_fkey,
+  ADD CONSTRAINT live_pois_indexer_id_fkey
+    FOREIGN KEY (indexer_id) REFERENCES indexers(id);
+ALTER TABLE pois
+  DROP CONSTRAINT pois_indexer_id_fkey,
+  ADD CONSTRAINT pois_indexer_id_fkey
+    FOREIGN KEY (indexer_id) REFERENCES indexers(id);
+
+ALTER TABLE indexers DROP COLUMN archived_at;
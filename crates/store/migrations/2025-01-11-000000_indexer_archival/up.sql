@@ -0,0 +1,12 @@
+-- Archived indexers are no longer polled, but their PoIs are kept.
+ALTER TABLE indexers ADD COLUMN archived_at TIMESTAMP;
+
+-- Deleting an indexer deletes its PoIs, too.
+ALTER TABLE pois
+  DROP CONSTRAINT pois_indexer_id_fkey,
+  ADD CONSTRAINT pois_indexer_id_fkey
+    FOREIGN KEY (indexer_id) REFERENCES indexers(id) ON DELETE CASCADE;
+ALTER TABLE live_pois
+  DROP CONSTRAINT live_pois_indexer_id_fkey,
+  ADD CONSTRAINT live_pois_indexer_id_fkey
+    FOREIGN KEY (indexer_id) REFERENCES indexers(id) ON DELETE CASCADE;
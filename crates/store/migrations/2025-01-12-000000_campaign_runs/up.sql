@@ -0,0 +1,16 @@
+-- One row per run of a divergence investigation campaign, i.e. a recurring job
+-- that investigates the live PoI divergences of the subgraph deployments with
+-- the most curation signal.
+CREATE TABLE campaign_runs (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  campaign TEXT NOT NULL,
+  -- The subgraph deployments that were checked for divergences.
+  deployments TEXT[] NOT NULL,
+  -- The subgraph deployments whose live PoIs diverged.
+  diverging_deployments TEXT[] NOT NULL,
+  investigation_uuids UUID[] NOT NULL,
+  error TEXT,
+  created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON campaign_runs (campaign, id);
@@ -0,0 +1,16 @@
+-- Requests to collect the historical PoIs of a subgraph deployment over a
+-- block range. A background worker requests PoIs from all indexers at one
+-- block of the range at a time, so that backfills resume after a restart.
+CREATE TABLE poi_backfills (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  sg_deployment_id INTEGER NOT NULL REFERENCES sg_deployments(id) ON DELETE CASCADE,
+  from_block BIGINT NOT NULL,
+  to_block BIGINT NOT NULL,
+  step BIGINT NOT NULL,
+  -- The next block to request PoIs at, or NULL once the backfill is done.
+  next_block BIGINT,
+  pois_written INTEGER NOT NULL DEFAULT 0,
+  error TEXT,
+  created_at TIMESTAMP NOT NULL,
+  completed_at TIMESTAMP
+);
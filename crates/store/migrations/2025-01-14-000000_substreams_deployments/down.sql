@@ -0,0 +1 @@
+ALTER TABLE sg_deployments DROP COLUMN substreams;
@@ -0,0 +1,4 @@
+-- Whether a subgraph deployment is substreams-powered, as reported by an
+-- indexer's `subgraphFeatures`. Indexers may report null PoIs for those. NULL
+-- until an indexer was asked.
+ALTER TABLE sg_deployments ADD COLUMN substreams BOOLEAN;
@@ -0,0 +1,16 @@
+-- Block numbers for which indexers reported different block hashes, e.g.
+-- because some of them were on a chain reorg. PoIs at such blocks aren't
+-- necessarily comparable, so differences between them may not be divergences.
+CREATE TABLE block_hash_conflicts (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  network_id INTEGER NOT NULL REFERENCES networks(id) ON DELETE CASCADE,
+  block_number BIGINT NOT NULL,
+  detected_at TIMESTAMP NOT NULL,
+  UNIQUE (network_id, block_number)
+);
+
+INSERT INTO block_hash_conflicts (network_id, block_number, detected_at)
+SELECT network_id, number, NOW()
+FROM blocks
+GROUP BY network_id, number
+HAVING COUNT(*) > 1;
@@ -0,0 +1,13 @@
+-- Every destructive or configuration-changing GraphQL mutation, with the API
+-- key that made it, so that operators sharing an instance can tell who changed
+-- what. API keys are referred to by their public prefix, and entries are kept
+-- when the API key is deleted.
+CREATE TABLE admin_audit_log (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  api_key_public_prefix TEXT,
+  mutation TEXT NOT NULL,
+  payload JSONB NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX admin_audit_log_mutation ON admin_audit_log (mutation);
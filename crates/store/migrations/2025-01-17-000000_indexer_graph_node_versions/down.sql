@@ -0,0 +1 @@
+DROP TABLE indexer_graph_node_versions;
@@ -0,0 +1,16 @@
+-- The graph-node version that each indexer ran over time. A new row starts
+-- whenever an indexer reports a different version or commit than before, and
+-- the previous one ends at the same time. The current version has no
+-- `valid_to`.
+CREATE TABLE indexer_graph_node_versions (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+  version_string TEXT,
+  version_commit TEXT,
+  valid_from TIMESTAMP NOT NULL,
+  valid_to TIMESTAMP
+);
+
+CREATE INDEX indexer_graph_node_versions_indexer_id ON indexer_graph_node_versions (indexer_id);
+CREATE UNIQUE INDEX indexer_graph_node_versions_current
+  ON indexer_graph_node_versions (indexer_id) WHERE valid_to IS NULL;
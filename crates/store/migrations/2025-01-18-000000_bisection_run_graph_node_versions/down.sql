@@ -0,0 +1,3 @@
+ALTER TABLE bisection_runs
+  DROP COLUMN indexer1_graph_node_version,
+  DROP COLUMN indexer2_graph_node_version;
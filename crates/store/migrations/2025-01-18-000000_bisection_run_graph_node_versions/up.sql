@@ -0,0 +1,6 @@
+-- The graph-node versions that the two indexers of a bisection run were
+-- running when it was launched, to correlate divergences with graph-node
+-- releases.
+ALTER TABLE bisection_runs
+  ADD COLUMN indexer1_graph_node_version TEXT,
+  ADD COLUMN indexer2_graph_node_version TEXT;
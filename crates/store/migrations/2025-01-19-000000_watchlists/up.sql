@@ -0,0 +1,14 @@
+-- Named sets of subgraph deployments that are polled more often, whose
+-- divergences are investigated automatically, and whose events are also
+-- delivered to a dedicated webhook. Watchlists that are defined in the
+-- configuration are synced to this table on every polling cycle.
+CREATE TABLE watchlists (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  name TEXT NOT NULL UNIQUE,
+  deployments TEXT[] NOT NULL,
+  polling_period_in_seconds INTEGER,
+  investigate_divergences BOOLEAN NOT NULL,
+  webhook_url TEXT,
+  from_config BOOLEAN NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,9 @@
+DROP INDEX pending_divergence_investigation_requests_queue;
+ALTER TABLE pending_divergence_investigation_requests
+  DROP COLUMN status,
+  DROP COLUMN attempts,
+  DROP COLUMN priority,
+  DROP COLUMN locked_by,
+  DROP COLUMN heartbeat_at,
+  DROP COLUMN next_attempt_at,
+  DROP COLUMN last_error;
@@ -0,0 +1,15 @@
+-- Turns pending divergence investigation requests into a job queue that
+-- several Graphix replicas can work off concurrently. A job is claimed by
+-- setting `locked_by`, and the claiming replica keeps `heartbeat_at` fresh
+-- while it runs; jobs with a stale heartbeat are up for grabs again. Failed
+-- jobs are retried at `next_attempt_at`, until they run out of attempts.
+ALTER TABLE pending_divergence_investigation_requests
+  ADD COLUMN status INTEGER NOT NULL DEFAULT 1,
+  ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN priority INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN locked_by TEXT,
+  ADD COLUMN heartbeat_at TIMESTAMP,
+  ADD COLUMN next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  ADD COLUMN last_error TEXT;
+
+CREATE INDEX pending_divergence_investigation_requests_queue ON pending_divergence_investigation_requests (status, priority DESC, created_at);
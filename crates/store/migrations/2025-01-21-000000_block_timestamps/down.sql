@@ -0,0 +1,5 @@
+DROP INDEX blocks_without_timestamp;
+ALTER TABLE blocks
+  DROP COLUMN timestamp,
+  DROP COLUMN canonical_hash,
+  DROP COLUMN timestamp_checked_at;
@@ -0,0 +1,9 @@
+-- Block timestamps and canonical hashes, as resolved from a chain's JSON-RPC
+-- endpoint. `timestamp_checked_at` is set on every attempt, so that blocks
+-- the endpoint doesn't know about are only retried every now and then.
+ALTER TABLE blocks
+  ADD COLUMN timestamp TIMESTAMP,
+  ADD COLUMN canonical_hash BYTEA,
+  ADD COLUMN timestamp_checked_at TIMESTAMP;
+
+CREATE INDEX blocks_without_timestamp ON blocks (network_id, number DESC) WHERE timestamp IS NULL;
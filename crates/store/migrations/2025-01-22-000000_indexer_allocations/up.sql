@@ -0,0 +1,12 @@
+-- The tokens that indexers currently have allocated to subgraph deployments,
+-- as found on network subgraphs, summed over their active allocations. Like
+-- on-chain PoIs, indexers and deployments are stored by address and CID.
+CREATE TABLE indexer_allocations (
+  indexer_address BYTEA NOT NULL,
+  deployment TEXT NOT NULL,
+  allocated_tokens NUMERIC NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  PRIMARY KEY (indexer_address, deployment)
+);
+
+CREATE INDEX indexer_allocations_deployment ON indexer_allocations (deployment);
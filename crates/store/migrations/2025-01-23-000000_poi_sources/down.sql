@@ -0,0 +1 @@
+ALTER TABLE pois DROP COLUMN source;
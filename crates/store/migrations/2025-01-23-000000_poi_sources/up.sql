@@ -0,0 +1,4 @@
+-- Where each PoI came from: 1 for polling (including backfills), 2 for
+-- bisections of divergence investigations, whose PoIs are stored so that
+-- later investigations of overlapping block ranges don't fetch them again.
+ALTER TABLE pois ADD COLUMN source INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,11 @@
+-- Subscriptions of API key holders to single subgraph deployments, whose
+-- webhook URLs receive periodic digests of what happened to them. Deleting an
+-- API key also deletes its subscriptions.
+CREATE TABLE deployment_subscriptions (
+  id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  api_key_public_prefix TEXT NOT NULL REFERENCES graphix_api_tokens(public_prefix) ON DELETE CASCADE,
+  deployment TEXT NOT NULL,
+  webhook_url TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE (api_key_public_prefix, deployment, webhook_url)
+);
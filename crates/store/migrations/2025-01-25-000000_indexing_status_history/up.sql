@@ -0,0 +1,19 @@
+-- Every change of the indexing status flags that an indexer reported for a
+-- subgraph deployment, along with the block range at that time. Unlike
+-- `indexing_statuses`, rows are never overwritten, and they're only added
+-- when the health, sync or pause flags change, so that the table doesn't grow
+-- with every polling cycle.
+CREATE TABLE indexing_status_history (
+  id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+  sg_deployment_id INTEGER NOT NULL REFERENCES sg_deployments(id) ON DELETE CASCADE,
+  health INTEGER NOT NULL,
+  synced BOOLEAN NOT NULL,
+  paused BOOLEAN,
+  latest_block_number BIGINT NOT NULL,
+  earliest_block_number BIGINT NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON indexing_status_history (indexer_id, created_at DESC);
+CREATE INDEX ON indexing_status_history (sg_deployment_id, created_at DESC);
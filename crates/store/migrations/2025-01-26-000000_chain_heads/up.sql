@@ -0,0 +1,8 @@
+-- The latest block of each network, as reported by its JSON-RPC endpoint or
+-- else by the most advanced indexer, refreshed on every polling cycle. The
+-- indexing lag of a subgraph deployment is measured against it.
+CREATE TABLE chain_heads (
+  network_id INTEGER PRIMARY KEY REFERENCES networks(id) ON DELETE CASCADE,
+  block_number BIGINT NOT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
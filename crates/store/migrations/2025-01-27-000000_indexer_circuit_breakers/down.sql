@@ -0,0 +1 @@
+ALTER TABLE indexers DROP COLUMN circuit_open_until;
@@ -0,0 +1,3 @@
+-- Until when requests to an indexer are skipped by its circuit breaker, after
+-- too many consecutive failures. Refreshed on every polling cycle.
+ALTER TABLE indexers ADD COLUMN circuit_open_until TIMESTAMP;
@@ -0,0 +1 @@
+ALTER TABLE watchlists DROP COLUMN api_key_public_prefix;
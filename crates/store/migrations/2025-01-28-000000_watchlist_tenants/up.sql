@@ -0,0 +1,5 @@
+-- Watchlists created through the API belong to the tenant (i.e. organization)
+-- of the API key that created them. Those defined in the configuration belong
+-- to nobody.
+ALTER TABLE watchlists
+ADD COLUMN api_key_public_prefix TEXT;
@@ -0,0 +1,12 @@
+DROP TABLE sg_deployment_network_subgraphs;
+
+ALTER TABLE indexers ADD COLUMN network_subgraph TEXT;
+
+UPDATE indexers
+SET network_subgraph = (
+    SELECT min(network_subgraph)
+    FROM indexer_network_subgraphs
+    WHERE indexer_id = indexers.id
+);
+
+DROP TABLE indexer_network_subgraphs;
@@ -0,0 +1,19 @@
+-- Indexers and subgraph deployments may be found on multiple network
+-- subgraphs (e.g. arbitrum-one and mainnet), so which ones is a relation
+-- rather than a column.
+CREATE TABLE indexer_network_subgraphs (
+    indexer_id INTEGER NOT NULL REFERENCES indexers(id) ON DELETE CASCADE,
+    network_subgraph TEXT NOT NULL,
+    PRIMARY KEY (indexer_id, network_subgraph)
+);
+
+INSERT INTO indexer_network_subgraphs (indexer_id, network_subgraph)
+SELECT id, network_subgraph FROM indexers WHERE network_subgraph IS NOT NULL;
+
+ALTER TABLE indexers DROP COLUMN network_subgraph;
+
+CREATE TABLE sg_deployment_network_subgraphs (
+    sg_deployment_id INTEGER NOT NULL REFERENCES sg_deployments(id) ON DELETE CASCADE,
+    network_subgraph TEXT NOT NULL,
+    PRIMARY KEY (sg_deployment_id, network_subgraph)
+);
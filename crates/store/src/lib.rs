@@ -4,6 +4,9 @@ mod loader;
 pub mod models;
 mod schema;
 mod store;
+mod subgraph_feed_schema;
 
-pub use loader::StoreLoader;
-pub use store::{PoiLiveness, Store};
+pub use loader::{IdenticalPoisKey, StoreLoader};
+pub use store::{
+    AdvisoryLock, AuditedMutation, PoiLiveness, Store, StoreMetrics, WebhookLimitReached,
+};
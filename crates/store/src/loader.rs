@@ -44,9 +44,11 @@ impl async_graphql::dataloader::Loader<IntId> for StoreLoader<models::Poi> {
     type Error = String;
 
     async fn load(&self, keys: &[IntId]) -> Result<HashMap<IntId, Self::Value>, Self::Error> {
-        use schema::pois;
+        use schema::{poi_values, pois};
 
         Ok(pois::table
+            .inner_join(poi_values::table)
+            .select(models::Poi::COLUMNS)
             .filter(pois::id.eq_any(keys))
             .load::<models::Poi>(&mut self.store.conn_err_string().await?)
             .await
@@ -72,6 +74,11 @@ impl async_graphql::dataloader::Loader<IntId> for StoreLoader<models::SgDeployme
                 sg_names::name.nullable(),
                 sgd::network,
                 sgd::created_at,
+                sgd::manifest,
+                sgd::first_seen_at,
+                sgd::last_seen_at,
+                sgd::status,
+                sgd::substreams,
             ))
             .filter(sgd::id.eq_any(keys))
             .load::<models::SgDeployment>(&mut self.store.conn_err_string().await?)
@@ -94,7 +101,12 @@ impl async_graphql::dataloader::Loader<IntId> for StoreLoader<models::Network> {
             .filter(networks::id.eq_any(keys))
             .select((
                 networks::id,
-                (networks::id, networks::name, networks::caip2),
+                (
+                    networks::id,
+                    networks::name,
+                    networks::caip2,
+                    networks::enabled,
+                ),
             ))
             .load::<(IntId, models::Network)>(&mut self.store.conn_err_string().await?)
             .await
@@ -161,3 +173,95 @@ impl async_graphql::dataloader::Loader<IntId>
             .collect())
     }
 }
+
+/// The names of the network subgraphs on which an indexer was found.
+impl async_graphql::dataloader::Loader<IntId> for StoreLoader<models::IndexerNetworkSubgraph> {
+    type Value = Vec<String>;
+    type Error = String;
+
+    async fn load(&self, keys: &[IntId]) -> Result<HashMap<IntId, Self::Value>, Self::Error> {
+        let mut network_subgraphs: HashMap<IntId, Self::Value> = HashMap::new();
+        for row in self
+            .store
+            .indexer_network_subgraphs(keys)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            network_subgraphs
+                .entry(row.indexer_id)
+                .or_default()
+                .push(row.network_subgraph);
+        }
+
+        Ok(network_subgraphs)
+    }
+}
+
+/// The names of the network subgraphs on which indexers allocated to a
+/// subgraph deployment.
+impl async_graphql::dataloader::Loader<IntId> for StoreLoader<models::SgDeploymentNetworkSubgraph> {
+    type Value = Vec<String>;
+    type Error = String;
+
+    async fn load(&self, keys: &[IntId]) -> Result<HashMap<IntId, Self::Value>, Self::Error> {
+        let mut network_subgraphs: HashMap<IntId, Self::Value> = HashMap::new();
+        for row in self
+            .store
+            .sg_deployment_network_subgraphs(keys)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            network_subgraphs
+                .entry(row.sg_deployment_id)
+                .or_default()
+                .push(row.network_subgraph);
+        }
+
+        Ok(network_subgraphs)
+    }
+}
+
+/// Identifies the PoIs that are identical to a PoI, see
+/// [`Store::identical_pois`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdenticalPoisKey {
+    pub poi_id: IntId,
+    pub limit: u16,
+}
+
+impl async_graphql::dataloader::Loader<IdenticalPoisKey> for StoreLoader<Vec<models::Poi>> {
+    type Value = Vec<models::Poi>;
+    type Error = String;
+
+    async fn load(
+        &self,
+        keys: &[IdenticalPoisKey],
+    ) -> Result<HashMap<IdenticalPoisKey, Self::Value>, Self::Error> {
+        // The keys of a request usually all have the same limit.
+        let mut poi_ids_by_limit: HashMap<u16, Vec<IntId>> = HashMap::new();
+        for key in keys {
+            poi_ids_by_limit
+                .entry(key.limit)
+                .or_default()
+                .push(key.poi_id);
+        }
+
+        let mut identical_pois: HashMap<IdenticalPoisKey, Self::Value> =
+            keys.iter().map(|key| (*key, vec![])).collect();
+        for (limit, poi_ids) in poi_ids_by_limit {
+            let pois = self
+                .store
+                .identical_pois(&poi_ids, limit)
+                .await
+                .map_err(|e| e.to_string())?;
+            for (poi_id, poi) in pois {
+                identical_pois
+                    .entry(IdenticalPoisKey { poi_id, limit })
+                    .or_default()
+                    .push(poi);
+            }
+        }
+
+        Ok(identical_pois)
+    }
+}
@@ -7,8 +7,13 @@ use diesel::backend::Backend;
 use diesel::deserialize::FromSql;
 use diesel::pg::Pg;
 use diesel::sql_types::Jsonb;
-use diesel::{AsChangeset, AsExpression, FromSqlRow, Insertable, Queryable, Selectable};
-use graphix_common_types::{self as types, ApiKeyPermissionLevel};
+use diesel::{
+    AsChangeset, AsExpression, FromSqlRow, Insertable, Queryable, QueryableByName, Selectable,
+};
+use graphix_common_types::{
+    self as types, ApiKeyPermissionLevel, DeploymentEventKind, DeploymentLifecycleStatus,
+    EventKind, IndexerErrorClass, JobStatus, LatencyProbe, SubgraphHealth,
+};
 use graphix_indexer_client::IndexerId;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
@@ -16,17 +21,21 @@ use types::{BlockHash, IndexerAddress, IpfsCid, PoiBytes};
 use uuid::Uuid;
 
 use super::schema::*;
+use super::subgraph_feed_schema::{poi_consensus, poi_divergences};
 
 pub type IntId = i32;
 pub type BigIntId = i64;
 pub type SgDeploymentCid = String;
 
-#[derive(Queryable, Serialize, Debug)]
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = failed_queries)]
 pub struct FailedQueryRow {
+    pub id: IntId,
     pub indexer_id: IntId,
     pub query_name: String,
     pub raw_query: String,
     pub response: String,
+    #[diesel(column_name = request_timestamp)]
     pub timestamp: NaiveDateTime,
 }
 
@@ -38,6 +47,89 @@ pub struct Poi {
     pub indexer_id: IntId,
     pub block_id: BigIntId,
     pub created_at: NaiveDateTime,
+    /// Identical PoIs share the same value ID.
+    pub poi_value_id: IntId,
+    /// The protocol epoch that the PoI's block belongs to, if known.
+    pub epoch: Option<i64>,
+    pub source: types::PoiSource,
+}
+
+impl Poi {
+    /// The columns to select a [`Poi`] from `pois` joined with `poi_values`.
+    pub(crate) const COLUMNS: (
+        pois::id,
+        poi_values::poi,
+        pois::sg_deployment_id,
+        pois::indexer_id,
+        pois::block_id,
+        pois::created_at,
+        pois::poi_value_id,
+        pois::epoch,
+        pois::source,
+    ) = (
+        pois::id,
+        poi_values::poi,
+        pois::sg_deployment_id,
+        pois::indexer_id,
+        pois::block_id,
+        pois::created_at,
+        pois::poi_value_id,
+        pois::epoch,
+        pois::source,
+    );
+}
+
+/// The indexers whose live PoIs of a subgraph deployment have the same value.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct LivePoiGroup {
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub sg_deployment_id: IntId,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub poi: PoiBytes,
+    /// The most recently collected of the live PoIs.
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub poi_id: IntId,
+    #[diesel(sql_type = diesel::sql_types::Array<diesel::sql_types::Integer>)]
+    pub indexer_ids: Vec<IntId>,
+}
+
+/// The PoIs of two indexers for a subgraph deployment at the same block.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct PoiCrossCheckRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub network: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub block_number: i64,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub block_hash: BlockHash,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub poi1: PoiBytes,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub poi2: PoiBytes,
+    /// When the more recently collected of the two PoIs was collected.
+    #[diesel(sql_type = diesel::sql_types::Timestamp)]
+    pub collected_at: NaiveDateTime,
+}
+
+/// Where the differing PoIs of a [`PoiCrossCheckRow`] diverge, according to
+/// the latest successful bisection run of the two PoIs, with the indexers'
+/// PoIs at that block.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct PoiCrossCheckDivergenceRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub network: String,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub poi1: PoiBytes,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub poi2: PoiBytes,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub block_number: i64,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub block_hash: BlockHash,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub diverging_poi1: PoiBytes,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub diverging_poi2: PoiBytes,
 }
 
 #[derive(Selectable, Insertable, Debug)]
@@ -48,6 +140,105 @@ pub struct NewGraphNodeCollectedVersion {
     pub error_response: Option<String>,
 }
 
+/// How often requests to an indexer failed with a certain class of error.
+#[derive(Queryable, Clone, Selectable, Debug, SimpleObject)]
+#[diesel(table_name = indexer_errors)]
+pub struct IndexerErrorCount {
+    #[graphql(skip)]
+    pub indexer_id: IntId,
+    pub error_class: IndexerErrorClass,
+    pub count: i64,
+    /// The message of the most recent error of this class.
+    pub last_error: String,
+    pub last_error_at: NaiveDateTime,
+}
+
+/// A failed request to an indexer, before it's counted in the database.
+#[derive(Debug, Clone)]
+pub struct NewIndexerError {
+    pub class: IndexerErrorClass,
+    pub message: String,
+}
+
+/// A timed request to an indexer, before it's stored in the database.
+#[derive(Debug, Clone)]
+pub struct NewLatencySample {
+    pub probe: LatencyProbe,
+    pub latency_ms: i32,
+    pub success: bool,
+}
+
+/// Latency percentiles and success rate of one kind of request to an indexer,
+/// or to all indexers together.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct IndexerLatencyStats {
+    /// `None` for all indexers together.
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Integer>)]
+    pub indexer_id: Option<IntId>,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub probe: LatencyProbe,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub sample_count: i64,
+    #[diesel(sql_type = diesel::sql_types::Double)]
+    pub success_rate: f64,
+    // Percentiles only take successful requests into account, and are `None`
+    // if there were none.
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Double>)]
+    pub p50_ms: Option<f64>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Double>)]
+    pub p90_ms: Option<f64>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Double>)]
+    pub p99_ms: Option<f64>,
+}
+
+/// An entry of the event log, which GraphQL subscriptions and webhooks are fed
+/// from. This is also the JSON body that webhooks are called with.
+#[derive(Queryable, Clone, Selectable, Debug, Serialize, SimpleObject)]
+#[diesel(table_name = events)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    /// Event IDs increase monotonically.
+    pub id: BigIntId,
+    pub kind: EventKind,
+    /// The subgraph deployment that the event is about, if any.
+    pub deployment: Option<IpfsCid>,
+    /// Details of the event, which depend on its kind.
+    pub payload: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+/// An event, before it's appended to the event log.
+#[derive(Debug, Clone)]
+pub struct NewEvent {
+    pub kind: EventKind,
+    pub deployment: Option<IpfsCid>,
+    pub payload: serde_json::Value,
+}
+
+/// A destructive or configuration-changing GraphQL mutation, as recorded in
+/// the admin audit log.
+#[derive(Queryable, Clone, Selectable, Debug, SimpleObject)]
+#[diesel(table_name = admin_audit_log)]
+pub struct AdminAuditLogEntry {
+    pub id: IntId,
+    /// The public prefix of the API key that made the mutation.
+    pub api_key_public_prefix: Option<String>,
+    /// The name of the mutation, e.g. `deleteNetwork`.
+    pub mutation: String,
+    /// The arguments of the mutation, without any secrets.
+    pub payload: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = admin_audit_log)]
+pub struct NewAdminAuditLogEntry<'a> {
+    pub api_key_public_prefix: Option<String>,
+    pub mutation: &'a str,
+    pub payload: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
 #[derive(Queryable, Clone, Selectable, Debug, SimpleObject)]
 #[diesel(table_name = graph_node_collected_versions)]
 pub struct GraphNodeCollectedVersion {
@@ -70,14 +261,86 @@ impl GraphNodeCollectedVersion {
     }
 }
 
+/// A graph-node version that an indexer ran during a period of time.
+#[derive(Queryable, Clone, Selectable, Debug, SimpleObject)]
+#[diesel(table_name = indexer_graph_node_versions)]
+pub struct IndexerGraphNodeVersion {
+    #[graphql(skip)]
+    pub id: IntId,
+    #[graphql(skip)]
+    pub indexer_id: IntId,
+    pub version_string: Option<String>,
+    pub version_commit: Option<String>,
+    /// When the indexer was first seen running this version.
+    pub valid_from: NaiveDateTime,
+    /// When the indexer was first seen running another version, if it was.
+    pub valid_to: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = indexer_graph_node_versions)]
+pub struct NewIndexerGraphNodeVersion {
+    pub indexer_id: IntId,
+    pub version_string: Option<String>,
+    pub version_commit: Option<String>,
+    pub valid_from: NaiveDateTime,
+}
+
+/// How many indexers ran a graph-node version at some point in time.
+#[derive(Queryable, Clone, Debug, SimpleObject)]
+pub struct GraphNodeVersionCount {
+    pub version_string: Option<String>,
+    pub version_commit: Option<String>,
+    pub indexers: i64,
+}
+
+/// How many bisection runs found a divergence between indexers that ran a
+/// pair of graph-node versions. The pair is unordered: `version1` sorts
+/// before `version2`, and unknown versions come last.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DivergenceVersionPair {
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub version1: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub version2: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub bisection_runs: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub deployments: i64,
+}
+
 #[derive(Insertable, Debug)]
 #[diesel(table_name = pois)]
 pub struct NewPoi {
-    pub poi: PoiBytes,
+    pub poi_value_id: IntId,
     pub created_at: NaiveDateTime,
     pub sg_deployment_id: IntId,
     pub indexer_id: IntId,
     pub block_id: BigIntId,
+    pub source: types::PoiSource,
+}
+
+/// A PoI together with the details that are needed to make sense of it
+/// outside of Graphix, as exported by the PoI export endpoint.
+#[derive(Queryable, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PoiExportRow {
+    pub id: IntId,
+    pub deployment: IpfsCid,
+    pub network: String,
+    pub block_number: i64,
+    pub block_hash: BlockHash,
+    pub indexer_address: IndexerAddress,
+    pub poi: PoiBytes,
+    pub created_at: NaiveDateTime,
+    pub epoch: Option<i64>,
+    pub block_timestamp: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = poi_values)]
+pub struct NewPoiValue {
+    pub poi: PoiBytes,
 }
 
 #[derive(Queryable, Clone, Debug, Serialize)]
@@ -86,6 +349,12 @@ pub struct Block {
     pub network_id: IntId,
     pub number: i64,
     pub hash: BlockHash,
+    /// Resolved from the chain's JSON-RPC endpoint, if configured.
+    pub timestamp: Option<NaiveDateTime>,
+    /// The hash of the block at this height on the canonical chain, as of
+    /// when [`Block::timestamp`] was resolved.
+    pub canonical_hash: Option<BlockHash>,
+    pub timestamp_checked_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Insertable)]
@@ -96,12 +365,178 @@ pub struct NewBlock {
     pub hash: BlockHash,
 }
 
+#[derive(Debug, Insertable)]
+#[diesel(table_name = block_hash_conflicts)]
+pub struct NewBlockHashConflict {
+    pub network_id: IntId,
+    pub block_number: i64,
+    pub detected_at: NaiveDateTime,
+}
+
+/// A block number for which indexers reported different block hashes.
+#[derive(Debug, Clone)]
+pub struct BlockHashConflict {
+    pub id: IntId,
+    pub network: String,
+    pub block_number: i64,
+    pub detected_at: NaiveDateTime,
+    /// The reported block hashes, each with the indexers whose PoIs refer to
+    /// it.
+    pub hashes: Vec<(BlockHash, Vec<IndexerAddress>)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DivergenceInvestigationRequest {
     pub pois: Vec<PoiBytes>,
     pub query_block_caches: bool,
     pub query_eth_call_caches: bool,
     pub query_entity_changes: bool,
+    /// Whether to also bisect the PoIs of the graft base that a divergence
+    /// was inherited from.
+    #[serde(default)]
+    pub bisect_graft_bases: bool,
+}
+
+/// A divergence investigation request in the job queue, see
+/// [`Store::claim_divergence_investigation_job`](crate::Store::claim_divergence_investigation_job).
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = pending_divergence_investigation_requests)]
+pub struct DivergenceInvestigationJob {
+    pub uuid: Uuid,
+    /// A serialized [`DivergenceInvestigationRequest`].
+    pub request: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub status: JobStatus,
+    /// How many times the job was claimed, including the current attempt.
+    pub attempts: i32,
+    /// Jobs with a higher priority are claimed first.
+    pub priority: i32,
+    /// The worker that claimed the job, if it's running.
+    pub locked_by: Option<String>,
+    pub heartbeat_at: Option<NaiveDateTime>,
+    /// Queued jobs aren't claimed before this time, to back off after
+    /// failed attempts.
+    pub next_attempt_at: NaiveDateTime,
+    /// The error of the last failed attempt, if any.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = network_epochs)]
+pub struct NewNetworkEpoch {
+    pub network_id: IntId,
+    pub epoch: i64,
+    pub start_block_number: i64,
+}
+
+/// PoI statistics of a subgraph deployment during a single protocol epoch.
+#[derive(Debug, Clone, Queryable)]
+pub struct EpochSummary {
+    pub sg_deployment_id: IntId,
+    pub epoch: i64,
+    /// The number of PoIs collected during the epoch.
+    pub poi_count: i64,
+    /// The number of indexers that PoIs were collected from.
+    pub indexer_count: i64,
+    /// The number of blocks that PoIs were collected for.
+    pub block_count: i64,
+    /// The number of distinct PoI values. Anything above `block_count`
+    /// indicates that indexers diverged during the epoch.
+    pub distinct_poi_count: i64,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = bisection_runs)]
+pub struct NewBisectionRun {
+    pub uuid: Uuid,
+    pub investigation_uuid: Uuid,
+    pub poi1: PoiBytes,
+    pub poi2: PoiBytes,
+    pub poi1_id: Option<IntId>,
+    pub poi2_id: Option<IntId>,
+    pub sg_deployment_id: Option<IntId>,
+    pub block_id: Option<BigIntId>,
+    pub divergence_lower_bound: i64,
+    pub divergence_upper_bound: i64,
+    pub indexer1_operator_contact: Option<String>,
+    pub indexer2_operator_contact: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub entity_diff: Option<serde_json::Value>,
+    pub eth_call_diff: Option<serde_json::Value>,
+    pub graft_divergence: Option<serde_json::Value>,
+    pub indexer1_graph_node_version: Option<String>,
+    pub indexer2_graph_node_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Queryable, QueryableByName, Selectable)]
+#[diesel(table_name = bisection_runs)]
+pub struct BisectionRun {
+    pub id: IntId,
+    pub uuid: Uuid,
+    /// The UUID of the divergence investigation that this bisection run is
+    /// part of.
+    pub investigation_uuid: Uuid,
+    pub poi1: PoiBytes,
+    pub poi2: PoiBytes,
+    pub poi1_id: Option<IntId>,
+    pub poi2_id: Option<IntId>,
+    pub sg_deployment_id: Option<IntId>,
+    /// The block of the bisected PoIs.
+    pub block_id: Option<BigIntId>,
+    pub divergence_lower_bound: i64,
+    /// The first block number at which the two indexers diverge, once the
+    /// bisection run is complete.
+    pub divergence_upper_bound: i64,
+    pub indexer1_operator_contact: Option<String>,
+    pub indexer2_operator_contact: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    /// A serialized [`graphix_common_types::EntityDiff`] of the entity
+    /// changes at the diverging block, if they were requested.
+    pub entity_diff: Option<serde_json::Value>,
+    /// A serialized [`graphix_common_types::EthCallDiff`] of the cached
+    /// Ethereum calls at the diverging block, if they were requested.
+    pub eth_call_diff: Option<serde_json::Value>,
+    /// A serialized [`graphix_common_types::GraftDivergence`], if the
+    /// divergence was inherited from a graft base.
+    pub graft_divergence: Option<serde_json::Value>,
+    /// The graph-node version of the indexer of `poi1` when the bisection run
+    /// was launched, if known.
+    pub indexer1_graph_node_version: Option<String>,
+    /// The graph-node version of the indexer of `poi2` when the bisection run
+    /// was launched, if known.
+    pub indexer2_graph_node_version: Option<String>,
+}
+
+/// The latest bisection run that involves a certain PoI, on either side.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct LatestBisectionRunRow {
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub poi: PoiBytes,
+    #[diesel(embed)]
+    pub bisection_run: BisectionRun,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = bisection_steps)]
+pub struct NewBisectionStep {
+    pub bisection_run_id: IntId,
+    pub block_number: i64,
+    pub block_hash: Option<BlockHash>,
+    pub indexer1_response: String,
+    pub indexer2_response: String,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = bisection_steps)]
+pub struct BisectionStep {
+    pub id: IntId,
+    pub bisection_run_id: IntId,
+    pub block_number: i64,
+    pub block_hash: Option<BlockHash>,
+    pub indexer1_response: String,
+    pub indexer2_response: String,
 }
 
 #[derive(Debug, Clone, Queryable, Selectable, Serialize)]
@@ -114,6 +549,26 @@ pub struct Indexer {
     pub network_subgraph_metadata: Option<IntId>,
     #[serde(skip)]
     pub created_at: NaiveDateTime,
+    pub network_subgraph_operator_contact: Option<String>,
+    pub manual_operator_contact: Option<String>,
+    pub tags: Vec<String>,
+    /// When the indexer was archived with `archiveIndexer`, if it was.
+    #[serde(skip)]
+    pub archived_at: Option<NaiveDateTime>,
+    /// Until when requests to the indexer are skipped by its circuit breaker,
+    /// as of the latest polling cycle.
+    #[serde(skip)]
+    pub circuit_open_until: Option<NaiveDateTime>,
+}
+
+impl Indexer {
+    /// How to reach the operator of this indexer. Contact information that
+    /// was set manually takes precedence over the network subgraph's.
+    pub fn operator_contact(&self) -> Option<&str> {
+        self.manual_operator_contact
+            .as_deref()
+            .or(self.network_subgraph_operator_contact.as_deref())
+    }
 }
 
 impl IndexerId for Indexer {
@@ -133,6 +588,8 @@ pub struct ApiKeyDbRow {
     pub sha256_api_key_hash: Vec<u8>,
     pub notes: Option<String>,
     pub permission_level: ApiKeyPermissionLevel,
+    pub organization: Option<String>,
+    pub requests_per_minute: Option<i32>,
 }
 
 #[derive(Debug, Clone, SimpleObject)]
@@ -140,6 +597,11 @@ pub struct ApiKeyPublicMetadata {
     pub public_prefix: String,
     pub notes: Option<String>,
     pub permission_level: ApiKeyPermissionLevel,
+    /// API keys of the same organization share webhooks.
+    pub organization: Option<String>,
+    /// The maximum number of GraphQL API requests per minute, if it differs
+    /// from the configured rate limit.
+    pub requests_per_minute: Option<i32>,
 }
 
 impl From<ApiKeyDbRow> for ApiKeyPublicMetadata {
@@ -148,6 +610,8 @@ impl From<ApiKeyDbRow> for ApiKeyPublicMetadata {
             public_prefix: sak.public_prefix,
             notes: sak.notes,
             permission_level: sak.permission_level,
+            organization: sak.organization,
+            requests_per_minute: sak.requests_per_minute,
         }
     }
 }
@@ -213,6 +677,7 @@ pub struct Network {
     pub id: IntId,
     pub name: String,
     pub caip2: Option<String>,
+    pub enabled: bool,
 }
 
 #[derive(Debug, Insertable, AsChangeset, Serialize)]
@@ -260,6 +725,7 @@ pub struct NewNetwork {
 pub struct NewIndexer {
     pub address: IndexerAddress,
     pub name: Option<String>,
+    pub network_subgraph_operator_contact: Option<String>,
 }
 
 #[derive(Debug, Clone, async_graphql::SimpleObject)]
@@ -267,6 +733,7 @@ pub struct NewlyCreatedApiKey {
     pub api_key: String,
     pub notes: Option<String>,
     pub permission_level: ApiKeyPermissionLevel,
+    pub organization: Option<String>,
 }
 
 #[derive(Debug, Clone, Queryable, Serialize)]
@@ -277,6 +744,24 @@ pub struct SgDeployment {
     pub network_id: IntId,
     #[serde(skip)]
     pub created_at: NaiveDateTime,
+    /// A serialized [`graphix_common_types::SubgraphManifest`], once the
+    /// manifest was fetched from IPFS.
+    #[serde(skip)]
+    pub manifest: Option<serde_json::Value>,
+    /// When an indexer first reported the deployment in its indexing
+    /// statuses.
+    #[serde(skip)]
+    pub first_seen_at: Option<NaiveDateTime>,
+    /// When an indexer last reported the deployment in its indexing statuses.
+    #[serde(skip)]
+    pub last_seen_at: Option<NaiveDateTime>,
+    /// `None` if no indexer reported the deployment yet.
+    #[serde(skip)]
+    pub status: Option<DeploymentLifecycleStatus>,
+    /// Whether the deployment is substreams-powered, or `None` if no indexer
+    /// was asked yet.
+    #[serde(skip)]
+    pub substreams: Option<bool>,
 }
 
 #[derive(Debug, Insertable)]
@@ -287,12 +772,407 @@ pub struct NewSgDeployment {
     pub created_at: NaiveDateTime,
 }
 
-#[derive(Debug, Insertable, AsChangeset)]
-#[diesel(table_name = live_pois)]
-pub struct NewLivePoi {
-    pub poi_id: IntId,
+#[derive(Debug, Insertable)]
+#[diesel(table_name = indexing_statuses)]
+pub struct NewIndexingStatus {
+    pub indexer_id: IntId,
+    pub sg_deployment_id: IntId,
+    pub health: SubgraphHealth,
+    pub synced: bool,
+    pub paused: Option<bool>,
+    pub updated_at: NaiveDateTime,
+    pub latest_block_number: i64,
+    pub earliest_block_number: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = indexing_statuses)]
+pub struct IndexingStatus {
+    pub id: IntId,
+    pub indexer_id: IntId,
+    pub sg_deployment_id: IntId,
+    pub health: SubgraphHealth,
+    pub synced: bool,
+    pub paused: Option<bool>,
+    pub updated_at: NaiveDateTime,
+    /// `None` for statuses written before block numbers were stored.
+    pub latest_block_number: Option<i64>,
+    pub earliest_block_number: Option<i64>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = indexing_status_history)]
+pub struct NewIndexingStatusHistoryEntry {
+    pub indexer_id: IntId,
+    pub sg_deployment_id: IntId,
+    pub health: SubgraphHealth,
+    pub synced: bool,
+    pub paused: Option<bool>,
+    pub latest_block_number: i64,
+    pub earliest_block_number: i64,
+    pub created_at: NaiveDateTime,
+}
+
+/// An indexing status that an indexer reported for a subgraph deployment,
+/// when its health, sync or pause flags changed.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = indexing_status_history)]
+pub struct IndexingStatusHistoryEntry {
+    pub id: BigIntId,
+    pub indexer_id: IntId,
+    pub sg_deployment_id: IntId,
+    pub health: SubgraphHealth,
+    pub synced: bool,
+    pub paused: Option<bool>,
+    pub latest_block_number: i64,
+    pub earliest_block_number: i64,
+    pub created_at: NaiveDateTime,
+}
+
+/// How far the latest indexing status of an indexer for a subgraph deployment
+/// is behind the head of its chain.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct IndexingLag {
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub indexer_id: IntId,
+    #[diesel(sql_type = diesel::sql_types::Binary)]
+    pub indexer_address: IndexerAddress,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub sg_deployment_id: IntId,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub network: String,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub health: SubgraphHealth,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub latest_block_number: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub chain_head_block_number: i64,
+    /// When the indexer reported its indexing status.
+    #[diesel(sql_type = diesel::sql_types::Timestamp)]
+    pub updated_at: NaiveDateTime,
+}
+
+impl IndexingLag {
+    /// How many blocks the subgraph deployment is behind the chain head. Never
+    /// negative, as the chain head may be refreshed before indexers catch up
+    /// with it.
+    pub fn blocks_behind(&self) -> i64 {
+        (self.chain_head_block_number - self.latest_block_number).max(0)
+    }
+}
+
+/// An error that an indexer's `graph-node` reported while indexing a subgraph
+/// deployment.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = indexing_errors)]
+pub struct IndexingError {
+    pub id: IntId,
+    pub indexer_id: IntId,
+    pub sg_deployment_id: IntId,
+    pub fatal: bool,
+    pub deterministic: bool,
+    pub message: String,
+    pub block_number: Option<i64>,
+    pub block_hash: Option<BlockHash>,
+    pub handler: Option<String>,
+    pub first_seen_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+}
+
+/// A change in how an indexer reports a subgraph deployment in its indexing
+/// statuses.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = sg_deployment_events)]
+pub struct SgDeploymentEvent {
+    pub id: IntId,
+    pub indexer_id: IntId,
+    pub sg_deployment_id: IntId,
+    pub kind: DeploymentEventKind,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = sg_deployment_events)]
+pub struct NewSgDeploymentEvent {
+    pub indexer_id: IntId,
+    pub sg_deployment_id: IntId,
+    pub kind: DeploymentEventKind,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = indexing_errors)]
+pub struct NewIndexingError {
+    pub indexer_id: IntId,
     pub sg_deployment_id: IntId,
+    pub fatal: bool,
+    pub deterministic: bool,
+    pub message: String,
+    pub block_number: Option<i64>,
+    pub block_hash: Option<BlockHash>,
+    pub handler: Option<String>,
+    pub first_seen_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = onchain_pois)]
+pub struct NewOnchainPoi {
+    pub allocation_id: String,
+    pub indexer_address: IndexerAddress,
+    pub deployment: IpfsCid,
+    pub poi: PoiBytes,
+    pub epoch: i64,
+    pub block_number: i64,
+    pub network_subgraph: Option<String>,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = onchain_pois)]
+pub struct OnchainPoi {
+    pub id: IntId,
+    pub allocation_id: String,
+    pub indexer_address: IndexerAddress,
+    pub deployment: IpfsCid,
+    pub poi: PoiBytes,
+    pub epoch: i64,
+    pub block_number: i64,
+    pub network_subgraph: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A network subgraph on which an indexer was found.
+#[derive(Debug, Clone, Insertable, Queryable, Selectable)]
+#[diesel(table_name = indexer_network_subgraphs)]
+pub struct IndexerNetworkSubgraph {
     pub indexer_id: IntId,
+    pub network_subgraph: String,
+}
+
+/// A network subgraph on which indexers allocated to a subgraph deployment.
+#[derive(Debug, Clone, Insertable, Queryable, Selectable)]
+#[diesel(table_name = sg_deployment_network_subgraphs)]
+pub struct SgDeploymentNetworkSubgraph {
+    pub sg_deployment_id: IntId,
+    pub network_subgraph: String,
+}
+
+/// The tokens that an indexer has allocated to a subgraph deployment, summed
+/// over its active allocations.
+#[derive(Debug, Clone, Insertable, Queryable, Selectable)]
+#[diesel(table_name = indexer_allocations)]
+pub struct IndexerAllocation {
+    pub indexer_address: IndexerAddress,
+    pub deployment: IpfsCid,
+    pub allocated_tokens: BigDecimal,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = indexing_loop_checkpoints)]
+pub struct NewIndexingLoopCheckpoint {
+    pub indexer_addresses: Vec<IndexerAddress>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = indexing_loop_checkpoints)]
+pub struct IndexingLoopCheckpoint {
+    pub id: IntId,
+    /// The indexers that were polled during the last polling cycle. Never
+    /// contains `None`; PostgreSQL arrays are always nullable.
+    pub indexer_addresses: Vec<Option<IndexerAddress>>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = campaign_runs)]
+pub struct NewCampaignRun {
+    pub campaign: String,
+    pub deployments: Vec<IpfsCid>,
+    pub diverging_deployments: Vec<IpfsCid>,
+    pub investigation_uuids: Vec<Uuid>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A run of a divergence investigation campaign, which launched divergence
+/// investigations for the subgraph deployments with the most curation signal.
+/// PostgreSQL arrays are always nullable, but never contain `None`.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = campaign_runs)]
+pub struct CampaignRun {
+    pub id: IntId,
+    pub campaign: String,
+    /// The subgraph deployments that were checked for divergences.
+    pub deployments: Vec<Option<IpfsCid>>,
+    /// The subgraph deployments whose live PoIs diverged.
+    pub diverging_deployments: Vec<Option<IpfsCid>>,
+    pub investigation_uuids: Vec<Option<Uuid>>,
+    /// Why the run failed, if it did. Investigations that were launched
+    /// before the failure are still listed.
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = poi_backfills)]
+pub struct NewPoiBackfill {
+    pub sg_deployment_id: IntId,
+    pub from_block: i64,
+    pub to_block: i64,
+    pub step: i64,
+    pub next_block: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A request to collect the historical PoIs of a subgraph deployment at every
+/// `step` blocks from `from_block` to `to_block`, as non-live PoIs.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = poi_backfills)]
+pub struct PoiBackfill {
+    pub id: IntId,
+    pub sg_deployment_id: IntId,
+    pub from_block: i64,
+    pub to_block: i64,
+    pub step: i64,
+    /// The next block to request PoIs at, or `None` once the backfill is
+    /// done.
+    pub next_block: Option<i64>,
+    pub pois_written: i32,
+    /// Why the backfill failed, if it did.
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webhooks)]
+pub struct NewWebhook {
+    pub api_key_public_prefix: String,
+    pub url: String,
+    pub deployments: Vec<IpfsCid>,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = webhooks)]
+pub struct Webhook {
+    pub id: IntId,
+    pub api_key_public_prefix: String,
+    pub url: String,
+    /// Never contains `None`; PostgreSQL arrays are always nullable.
+    pub deployments: Vec<Option<IpfsCid>>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Webhook {
+    pub fn deployments(&self) -> impl Iterator<Item = &IpfsCid> {
+        self.deployments.iter().flatten()
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = deployment_subscriptions)]
+pub struct NewDeploymentSubscription {
+    pub api_key_public_prefix: String,
+    pub deployment: IpfsCid,
+    pub webhook_url: String,
+}
+
+/// A subscription to a single subgraph deployment, whose webhook URL receives
+/// periodic digests of what happened to it, see
+/// `graphix_lib::subscription_digests`.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = deployment_subscriptions)]
+pub struct DeploymentSubscription {
+    pub id: IntId,
+    pub api_key_public_prefix: String,
+    pub deployment: IpfsCid,
+    pub webhook_url: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable, AsChangeset)]
+#[diesel(table_name = watchlists, treat_none_as_null = true)]
+pub struct NewWatchlist {
+    pub name: String,
+    pub deployments: Vec<IpfsCid>,
+    pub polling_period_in_seconds: Option<i32>,
+    pub investigate_divergences: bool,
+    pub webhook_url: Option<String>,
+    pub from_config: bool,
+    pub api_key_public_prefix: Option<String>,
+}
+
+/// A named set of subgraph deployments that get more attention than the
+/// others, see `graphix_lib::watchlists`.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = watchlists)]
+pub struct Watchlist {
+    pub id: IntId,
+    pub name: String,
+    /// Never contains `None`; PostgreSQL arrays are always nullable.
+    pub deployments: Vec<Option<IpfsCid>>,
+    /// How often the PoIs of the deployments are collected, if more often
+    /// than usual.
+    pub polling_period_in_seconds: Option<i32>,
+    /// Whether new divergences among the deployments' PoIs launch a
+    /// divergence investigation.
+    pub investigate_divergences: bool,
+    /// Also receives the events about the deployments.
+    pub webhook_url: Option<String>,
+    /// Whether the watchlist is defined in the configuration, rather than
+    /// with the `setWatchlist` mutation.
+    pub from_config: bool,
+    pub created_at: NaiveDateTime,
+    /// The API key that created the watchlist, whose organization it belongs
+    /// to. `None` for watchlists that are defined in the configuration.
+    pub api_key_public_prefix: Option<String>,
+}
+
+impl Watchlist {
+    pub fn deployments(&self) -> impl Iterator<Item = &IpfsCid> {
+        self.deployments.iter().flatten()
+    }
+}
+
+/// Size statistics of a database table, as sampled from PostgreSQL's
+/// statistics views.
+#[derive(Debug, Clone, QueryableByName, SimpleObject)]
+pub struct TableStats {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub schema_name: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub table_name: String,
+    /// An estimate of the number of live rows, as maintained by PostgreSQL.
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub row_count: i64,
+    /// The size of the table on disk, including indexes and TOAST data.
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub total_size_bytes: i64,
+}
+
+/// A row of the `subgraph_feed.poi_consensus` table.
+#[derive(Debug, Clone, PartialEq, Eq, Insertable)]
+#[diesel(table_name = poi_consensus)]
+pub struct NewFeedPoiConsensus {
+    pub deployment: IpfsCid,
+    pub block_number: i64,
+    pub block_hash: Option<BlockHash>,
+    pub consensus_poi: Option<PoiBytes>,
+    pub indexer_count: i32,
+    pub agreeing_indexer_count: i32,
+}
+
+/// A row of the `subgraph_feed.poi_divergences` table.
+#[derive(Debug, Clone, PartialEq, Eq, Insertable)]
+#[diesel(table_name = poi_divergences)]
+pub struct NewFeedPoiDivergence {
+    pub deployment: IpfsCid,
+    pub block_number: i64,
+    pub indexer_address: IndexerAddress,
+    pub poi: PoiBytes,
+    pub consensus_poi: PoiBytes,
 }
 
 #[derive(FromSqlRow, AsExpression, Serialize, Deserialize, Debug, Default)]
@@ -347,6 +1227,8 @@ impl FromSql<Jsonb, Pg> for DivergingBlock {
 mod tests {
     use std::str::FromStr;
 
+    use quickcheck_macros::quickcheck;
+
     use super::*;
 
     #[test]
@@ -356,4 +1238,68 @@ mod tests {
 
         assert_eq!(api_key, parsed);
     }
+
+    #[quickcheck]
+    fn api_key_from_str_roundtrip(public_part: u128, private_part: u128) -> bool {
+        let api_key = ApiKey {
+            public_part: Uuid::from_u128(public_part),
+            private_part: Uuid::from_u128(private_part),
+        };
+
+        ApiKey::from_str(&api_key.to_string()) == Ok(api_key)
+    }
+
+    #[quickcheck]
+    fn api_key_from_str_never_panics(s: String) -> bool {
+        let _ = ApiKey::from_str(&s);
+        true
+    }
+
+    #[quickcheck]
+    fn api_key_from_str_rejects_mutations(index: usize, c: char) -> bool {
+        let api_key = ApiKey::generate().to_string();
+
+        // Replace a single character with one that can't appear in a key.
+        if c.is_ascii_hexdigit() || c == '-' {
+            return true;
+        }
+        let mut chars: Vec<char> = api_key.chars().collect();
+        let index = "graphix-".len() + index % (chars.len() - "graphix-".len());
+        chars[index] = c;
+        let mutated: String = chars.into_iter().collect();
+
+        ApiKey::from_str(&mutated).is_err()
+    }
+
+    #[test]
+    fn api_key_from_str_rejects_malformed() {
+        let public_part = Uuid::new_v4();
+        let private_part = Uuid::new_v4();
+        let (public, private) = (public_part.as_simple(), private_part.as_simple());
+
+        for s in [
+            String::new(),
+            "graphix".to_string(),
+            "graphix--".to_string(),
+            format!("graphix-{public}"),
+            format!("graphix-{public}-"),
+            format!("graphix-{public}-{private}-"),
+            format!("graphix-{public}-{private}-{private}"),
+            format!("Graphix-{public}-{private}"),
+            format!("0xgraphix-{public}-{private}"),
+            format!(" graphix-{public}-{private}"),
+            format!("graphix-{public}-{private} "),
+            // Hyphenated UUIDs.
+            format!(
+                "graphix-{}-{}",
+                public_part.hyphenated(),
+                private_part.hyphenated()
+            ),
+            // Too short and too long.
+            format!("graphix-{}-{private}", &public.to_string()[1..]),
+            format!("graphix-{public}-{private}0"),
+        ] {
+            assert!(ApiKey::from_str(&s).is_err(), "{s:?}");
+        }
+    }
 }
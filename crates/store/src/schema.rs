@@ -1,11 +1,89 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    admin_audit_log (id) {
+        id -> Int4,
+        api_key_public_prefix -> Nullable<Text>,
+        mutation -> Text,
+        payload -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    bisection_runs (id) {
+        id -> Int4,
+        uuid -> Uuid,
+        investigation_uuid -> Uuid,
+        poi1 -> Bytea,
+        poi2 -> Bytea,
+        poi1_id -> Nullable<Int4>,
+        poi2_id -> Nullable<Int4>,
+        sg_deployment_id -> Nullable<Int4>,
+        block_id -> Nullable<Int8>,
+        divergence_lower_bound -> Int8,
+        divergence_upper_bound -> Int8,
+        indexer1_operator_contact -> Nullable<Text>,
+        indexer2_operator_contact -> Nullable<Text>,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+        entity_diff -> Nullable<Jsonb>,
+        eth_call_diff -> Nullable<Jsonb>,
+        graft_divergence -> Nullable<Jsonb>,
+        indexer1_graph_node_version -> Nullable<Text>,
+        indexer2_graph_node_version -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    bisection_steps (id) {
+        id -> Int4,
+        bisection_run_id -> Int4,
+        block_number -> Int8,
+        block_hash -> Nullable<Bytea>,
+        indexer1_response -> Text,
+        indexer2_response -> Text,
+    }
+}
+
+diesel::table! {
+    block_hash_conflicts (id) {
+        id -> Int4,
+        network_id -> Int4,
+        block_number -> Int8,
+        detected_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     blocks (id) {
         id -> Int8,
         network_id -> Int4,
         number -> Int8,
         hash -> Bytea,
+        timestamp -> Nullable<Timestamp>,
+        canonical_hash -> Nullable<Bytea>,
+        timestamp_checked_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    campaign_runs (id) {
+        id -> Int4,
+        campaign -> Text,
+        deployments -> Array<Nullable<Text>>,
+        diverging_deployments -> Array<Nullable<Text>>,
+        investigation_uuids -> Array<Nullable<Uuid>>,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    chain_heads (network_id) {
+        network_id -> Int4,
+        block_number -> Int8,
+        updated_at -> Timestamp,
     }
 }
 
@@ -16,10 +94,37 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    deployment_subscriptions (id) {
+        id -> Int4,
+        api_key_public_prefix -> Text,
+        deployment -> Text,
+        webhook_url -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     divergence_investigation_reports (uuid) {
         uuid -> Uuid,
-        report -> Jsonb,
+        created_at -> Timestamp,
+        error -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    event_cursors (consumer) {
+        consumer -> Text,
+        last_event_id -> Int8,
+    }
+}
+
+diesel::table! {
+    events (id) {
+        id -> Int8,
+        kind -> Int4,
+        deployment -> Nullable<Text>,
+        payload -> Jsonb,
         created_at -> Timestamp,
     }
 }
@@ -51,6 +156,49 @@ diesel::table! {
         sha256_api_key_hash -> Bytea,
         notes -> Nullable<Text>,
         permission_level -> Int4,
+        organization -> Nullable<Text>,
+        requests_per_minute -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    indexer_errors (indexer_id, error_class) {
+        indexer_id -> Int4,
+        error_class -> Int4,
+        count -> Int8,
+        last_error -> Text,
+        last_error_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    indexer_graph_node_versions (id) {
+        id -> Int4,
+        indexer_id -> Int4,
+        version_string -> Nullable<Text>,
+        version_commit -> Nullable<Text>,
+        valid_from -> Timestamp,
+        valid_to -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    indexer_latency_samples (id) {
+        id -> Int8,
+        indexer_id -> Int4,
+        probe -> Int4,
+        latency_ms -> Int4,
+        success -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    indexer_allocations (indexer_address, deployment) {
+        indexer_address -> Bytea,
+        deployment -> Text,
+        allocated_tokens -> Numeric,
+        updated_at -> Timestamp,
     }
 }
 
@@ -71,6 +219,13 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    indexer_network_subgraphs (indexer_id, network_subgraph) {
+        indexer_id -> Int4,
+        network_subgraph -> Text,
+    }
+}
+
 diesel::table! {
     indexers (id) {
         id -> Int4,
@@ -79,6 +234,63 @@ diesel::table! {
         graph_node_version -> Nullable<Int4>,
         network_subgraph_metadata -> Nullable<Int4>,
         created_at -> Timestamp,
+        network_subgraph_operator_contact -> Nullable<Text>,
+        manual_operator_contact -> Nullable<Text>,
+        tags -> Array<Text>,
+        archived_at -> Nullable<Timestamp>,
+        circuit_open_until -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    indexing_loop_checkpoints (id) {
+        id -> Int4,
+        indexer_addresses -> Array<Nullable<Bytea>>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    indexing_errors (id) {
+        id -> Int4,
+        indexer_id -> Int4,
+        sg_deployment_id -> Int4,
+        fatal -> Bool,
+        deterministic -> Bool,
+        message -> Text,
+        block_number -> Nullable<Int8>,
+        block_hash -> Nullable<Bytea>,
+        handler -> Nullable<Text>,
+        first_seen_at -> Timestamp,
+        last_seen_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    indexing_status_history (id) {
+        id -> Int8,
+        indexer_id -> Int4,
+        sg_deployment_id -> Int4,
+        health -> Int4,
+        synced -> Bool,
+        paused -> Nullable<Bool>,
+        latest_block_number -> Int8,
+        earliest_block_number -> Int8,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    indexing_statuses (id) {
+        id -> Int4,
+        indexer_id -> Int4,
+        sg_deployment_id -> Int4,
+        health -> Int4,
+        synced -> Bool,
+        paused -> Nullable<Bool>,
+        updated_at -> Timestamp,
+        latest_block_number -> Nullable<Int8>,
+        earliest_block_number -> Nullable<Int8>,
     }
 }
 
@@ -91,12 +303,35 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    network_epochs (network_id, epoch) {
+        network_id -> Int4,
+        epoch -> Int8,
+        start_block_number -> Int8,
+    }
+}
+
 diesel::table! {
     networks (id) {
         id -> Int4,
         name -> Text,
         caip2 -> Nullable<Text>,
         created_at -> Timestamp,
+        enabled -> Bool,
+    }
+}
+
+diesel::table! {
+    onchain_pois (id) {
+        id -> Int4,
+        allocation_id -> Text,
+        indexer_address -> Bytea,
+        deployment -> Text,
+        poi -> Bytea,
+        epoch -> Int8,
+        block_number -> Int8,
+        network_subgraph -> Nullable<Text>,
+        created_at -> Timestamp,
     }
 }
 
@@ -105,17 +340,48 @@ diesel::table! {
         uuid -> Uuid,
         request -> Jsonb,
         created_at -> Timestamp,
+        status -> Int4,
+        attempts -> Int4,
+        priority -> Int4,
+        locked_by -> Nullable<Text>,
+        heartbeat_at -> Nullable<Timestamp>,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Text>,
     }
 }
 
 diesel::table! {
-    pois (id) {
+    poi_backfills (id) {
+        id -> Int4,
+        sg_deployment_id -> Int4,
+        from_block -> Int8,
+        to_block -> Int8,
+        step -> Int8,
+        next_block -> Nullable<Int8>,
+        pois_written -> Int4,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    poi_values (id) {
         id -> Int4,
         poi -> Bytea,
+    }
+}
+
+diesel::table! {
+    pois (id) {
+        id -> Int4,
         sg_deployment_id -> Int4,
         indexer_id -> Int4,
         block_id -> Int8,
         created_at -> Timestamp,
+        poi_value_id -> Int4,
+        epoch -> Nullable<Int8>,
+        source -> Int4,
     }
 }
 
@@ -129,12 +395,34 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    sg_deployment_network_subgraphs (sg_deployment_id, network_subgraph) {
+        sg_deployment_id -> Int4,
+        network_subgraph -> Text,
+    }
+}
+
 diesel::table! {
     sg_deployments (id) {
         id -> Int4,
         ipfs_cid -> Text,
         network -> Int4,
         created_at -> Timestamp,
+        manifest -> Nullable<Jsonb>,
+        first_seen_at -> Nullable<Timestamp>,
+        last_seen_at -> Nullable<Timestamp>,
+        status -> Nullable<Int4>,
+        substreams -> Nullable<Bool>,
+    }
+}
+
+diesel::table! {
+    sg_deployment_events (id) {
+        id -> Int4,
+        indexer_id -> Int4,
+        sg_deployment_id -> Int4,
+        kind -> Int4,
+        created_at -> Timestamp,
     }
 }
 
@@ -147,34 +435,108 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webhooks (id) {
+        id -> Int4,
+        api_key_public_prefix -> Text,
+        url -> Text,
+        deployments -> Array<Nullable<Text>>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    watchlists (id) {
+        id -> Int4,
+        name -> Text,
+        deployments -> Array<Nullable<Text>>,
+        polling_period_in_seconds -> Nullable<Int4>,
+        investigate_divergences -> Bool,
+        webhook_url -> Nullable<Text>,
+        from_config -> Bool,
+        created_at -> Timestamp,
+        api_key_public_prefix -> Nullable<Text>,
+    }
+}
+
+diesel::joinable!(bisection_runs -> blocks (block_id));
+diesel::joinable!(bisection_runs -> divergence_investigation_reports (investigation_uuid));
+diesel::joinable!(bisection_runs -> sg_deployments (sg_deployment_id));
+diesel::joinable!(bisection_steps -> bisection_runs (bisection_run_id));
+diesel::joinable!(block_hash_conflicts -> networks (network_id));
 diesel::joinable!(blocks -> networks (network_id));
+diesel::joinable!(chain_heads -> networks (network_id));
+diesel::joinable!(deployment_subscriptions -> graphix_api_tokens (api_key_public_prefix));
 diesel::joinable!(failed_queries -> indexers (indexer_id));
+diesel::joinable!(indexer_errors -> indexers (indexer_id));
+diesel::joinable!(indexer_graph_node_versions -> indexers (indexer_id));
+diesel::joinable!(indexer_latency_samples -> indexers (indexer_id));
+diesel::joinable!(indexer_network_subgraphs -> indexers (indexer_id));
 diesel::joinable!(indexers -> graph_node_collected_versions (graph_node_version));
 diesel::joinable!(indexers -> indexer_network_subgraph_metadata (network_subgraph_metadata));
+diesel::joinable!(indexing_errors -> indexers (indexer_id));
+diesel::joinable!(indexing_errors -> sg_deployments (sg_deployment_id));
+diesel::joinable!(indexing_status_history -> indexers (indexer_id));
+diesel::joinable!(indexing_status_history -> sg_deployments (sg_deployment_id));
+diesel::joinable!(indexing_statuses -> indexers (indexer_id));
+diesel::joinable!(indexing_statuses -> sg_deployments (sg_deployment_id));
 diesel::joinable!(live_pois -> indexers (indexer_id));
 diesel::joinable!(live_pois -> pois (poi_id));
 diesel::joinable!(live_pois -> sg_deployments (sg_deployment_id));
+diesel::joinable!(network_epochs -> networks (network_id));
+diesel::joinable!(poi_backfills -> sg_deployments (sg_deployment_id));
 diesel::joinable!(pois -> blocks (block_id));
 diesel::joinable!(pois -> indexers (indexer_id));
+diesel::joinable!(pois -> poi_values (poi_value_id));
 diesel::joinable!(pois -> sg_deployments (sg_deployment_id));
 diesel::joinable!(sg_deployment_api_versions -> sg_deployments (sg_deployment_id));
+diesel::joinable!(sg_deployment_events -> indexers (indexer_id));
+diesel::joinable!(sg_deployment_events -> sg_deployments (sg_deployment_id));
+diesel::joinable!(sg_deployment_network_subgraphs -> sg_deployments (sg_deployment_id));
 diesel::joinable!(sg_deployments -> networks (network));
 diesel::joinable!(sg_names -> sg_deployments (sg_deployment_id));
+diesel::joinable!(webhooks -> graphix_api_tokens (api_key_public_prefix));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    admin_audit_log,
+    bisection_runs,
+    bisection_steps,
+    block_hash_conflicts,
     blocks,
+    campaign_runs,
+    chain_heads,
     configs,
+    deployment_subscriptions,
     divergence_investigation_reports,
+    event_cursors,
+    events,
     failed_queries,
     graph_node_collected_versions,
     graphix_api_tokens,
+    indexer_allocations,
+    indexer_errors,
+    indexer_graph_node_versions,
+    indexer_latency_samples,
     indexer_network_subgraph_metadata,
+    indexer_network_subgraphs,
     indexers,
+    indexing_loop_checkpoints,
+    indexing_errors,
+    indexing_status_history,
+    indexing_statuses,
     live_pois,
+    network_epochs,
     networks,
+    onchain_pois,
     pending_divergence_investigation_requests,
+    poi_backfills,
+    poi_values,
     pois,
     sg_deployment_api_versions,
+    sg_deployment_events,
+    sg_deployment_network_subgraphs,
     sg_deployments,
     sg_names,
+    watchlists,
+    webhooks,
 );
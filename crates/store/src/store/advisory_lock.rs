@@ -0,0 +1,51 @@
+use diesel::sql_types::{BigInt, Bool};
+use diesel::QueryableByName;
+use diesel_async::pooled_connection::deadpool::Object;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use super::Store;
+
+/// A PostgreSQL session-level advisory lock, see
+/// [`Store::try_advisory_lock`]. It's held on a dedicated connection that
+/// doesn't go back to the pool, so it's released as soon as this value is
+/// dropped or the connection breaks, e.g. because the process died.
+pub struct AdvisoryLock {
+    conn: AsyncPgConnection,
+    key: i64,
+}
+
+#[derive(QueryableByName)]
+struct Locked {
+    #[diesel(sql_type = Bool)]
+    locked: bool,
+}
+
+impl AdvisoryLock {
+    pub fn key(&self) -> i64 {
+        self.key
+    }
+
+    /// Fails if the lock's connection broke, in which case the lock is
+    /// already released and might be held by someone else.
+    pub async fn check(&mut self) -> anyhow::Result<()> {
+        diesel::sql_query("SELECT 1")
+            .execute(&mut self.conn)
+            .await?;
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Takes the session-level advisory lock `key` unless it's already held,
+    /// e.g. by another Graphix replica that shares the database. Returns
+    /// `None` in that case, without waiting.
+    pub async fn try_advisory_lock(&self, key: i64) -> anyhow::Result<Option<AdvisoryLock>> {
+        let mut conn = Object::take(self.conn().await?);
+        let Locked { locked } = diesel::sql_query("SELECT pg_try_advisory_lock($1) AS locked")
+            .bind::<BigInt, _>(key)
+            .get_result(&mut conn)
+            .await?;
+
+        Ok(locked.then_some(AdvisoryLock { conn, key }))
+    }
+}
@@ -2,39 +2,110 @@
 //! transactions.
 
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use chrono::Utc;
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel::sql_types;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
-use graphix_common_types::IpfsCid;
-use graphix_common_types::{inputs, IndexerAddress};
-use graphix_indexer_client::{BlockPointer, IndexerClient, IndexerId, WritablePoi};
-use tracing::info;
+use graphix_common_types::{
+    inputs, BlockHash, DeploymentEventKind, DeploymentLifecycleStatus, IndexerAddress, IpfsCid,
+    PoiBytes, PoiSource, SubgraphHealth,
+};
+use graphix_indexer_client::{
+    BlockPointer, IndexerClient, IndexerId, IndexingStatus, SubgraphError, WritablePoi,
+};
+use tracing::{info, warn};
 
-use super::PoiLiveness;
+use super::{AuditedMutation, PoiLiveness};
 use crate::models::{
-    self, Indexer as IndexerModel, NewIndexer, NewLivePoi, NewPoi, NewSgDeployment, SgDeployment,
+    self, ApiKey, BigIntId, Indexer as IndexerModel, IntId, NewIndexer, NewIndexingError,
+    NewIndexingStatus, NewIndexingStatusHistoryEntry, NewPoi, NewPoiValue, NewSgDeployment,
+    NewSgDeploymentEvent, SgDeployment,
 };
 use crate::schema::{self, live_pois, sg_names};
 
+/// Overwrites the stored configuration, or stores it if there's none yet.
+pub(super) async fn overwrite_config(
+    conn: &mut AsyncPgConnection,
+    config: &serde_json::Value,
+) -> anyhow::Result<()> {
+    use schema::configs;
+
+    let updated = diesel::update(configs::table)
+        .set(configs::config.eq(config))
+        .execute(conn)
+        .await?;
+    if updated == 0 {
+        diesel::insert_into(configs::table)
+            .values(configs::config.eq(config))
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Locks the configuration table until the end of the transaction that
+/// `conn` is in, and returns the stored configuration (`{}` if there is none).
+pub(super) async fn lock_config(conn: &mut AsyncPgConnection) -> anyhow::Result<serde_json::Value> {
+    use schema::configs;
+
+    // Unlike `SELECT ... FOR UPDATE`, this also serializes updates while
+    // there's no row to lock yet.
+    diesel::sql_query("LOCK TABLE configs IN SHARE ROW EXCLUSIVE MODE")
+        .execute(conn)
+        .await?;
+    Ok(configs::table
+        .order_by(configs::id.desc())
+        .select(configs::config)
+        .first::<serde_json::Value>(conn)
+        .await
+        .optional()?
+        .unwrap_or_else(|| serde_json::json!({})))
+}
+
+pub(super) async fn write_admin_audit_log_entry(
+    conn: &mut AsyncPgConnection,
+    mutation: &AuditedMutation<'_>,
+    payload: serde_json::Value,
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    use schema::admin_audit_log;
+
+    let entry = models::NewAdminAuditLogEntry {
+        api_key_public_prefix: mutation.api_key.map(ApiKey::public_part_as_string),
+        mutation: mutation.mutation,
+        payload,
+        created_at: now,
+    };
+    diesel::insert_into(admin_audit_log::table)
+        .values(entry)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
 // This is a single SQL statement, a transaction is not necessary.
 pub(super) async fn pois(
     conn: &mut AsyncPgConnection,
     indexer_address: Option<&IndexerAddress>,
+    indexer_tag: Option<&str>,
+    network: Option<&str>,
     sg_deployments: Option<&[IpfsCid]>,
     block_range: Option<inputs::BlockRange>,
+    epoch: Option<u64>,
     limit: Option<u16>,
     live_only: bool,
 ) -> anyhow::Result<Vec<models::Poi>> {
-    #![allow(non_snake_case)]
-    use schema::{blocks, indexers, pois, sg_deployments as sgd};
+    #![allow(non_snake_case, clippy::too_many_arguments)]
+    use schema::{blocks, indexers, networks, poi_values, pois, sg_deployments as sgd};
 
     let FALSE = diesel::dsl::sql::<sql_types::Bool>("false");
     let TRUE = diesel::dsl::sql::<sql_types::Bool>("true");
 
-    let selection = pois::all_columns;
+    let selection = models::Poi::COLUMNS;
 
     // TODO: optimize this into a single comparison in the absence of lower or
     // upper bounds.
@@ -58,11 +129,35 @@ pub(super) async fn pois(
         None => sgd::ipfs_cid.eq_any([]).or(TRUE.clone()),
     };
 
+    let network_ids = networks::table
+        .select(networks::id)
+        .filter(networks::name.eq(network.unwrap_or_default()));
+    let network_filter = match network {
+        Some(_) => sgd::network.eq_any(network_ids).or(FALSE.clone()),
+        None => sgd::network.eq_any(network_ids).or(TRUE.clone()),
+    };
+
     let default_indexer_address = IndexerAddress::default();
     let indexer_filter = match indexer_address {
         // Ugly hacks to have the match arms' types match.
-        Some(addr) => indexers::address.eq(addr).or(FALSE),
-        None => indexers::address.eq(&default_indexer_address).or(TRUE),
+        Some(addr) => indexers::address.eq(addr).or(FALSE.clone()),
+        None => indexers::address
+            .eq(&default_indexer_address)
+            .or(TRUE.clone()),
+    };
+
+    let indexer_tag_filter = match indexer_tag {
+        Some(tag) => indexers::tags
+            .contains(vec![tag.to_string()])
+            .or(FALSE.clone()),
+        None => indexers::tags
+            .contains(Vec::<String>::new())
+            .or(TRUE.clone()),
+    };
+
+    let epoch_filter = match epoch {
+        Some(epoch) => pois::epoch.eq(i64::try_from(epoch)?).or(FALSE),
+        None => pois::epoch.eq(0).or(TRUE),
     };
 
     let order_by = (blocks::number.desc(), schema::pois::created_at.desc());
@@ -74,11 +169,15 @@ pub(super) async fn pois(
                 .inner_join(sgd::table)
                 .inner_join(indexers::table)
                 .inner_join(blocks::table)
+                .inner_join(poi_values::table)
                 .select(selection)
                 .order_by(order_by)
                 .filter(deployments_filter)
+                .filter(network_filter)
                 .filter(blocks_filter)
                 .filter(indexer_filter)
+                .filter(indexer_tag_filter)
+                .filter(epoch_filter)
                 .limit(limit);
             Ok(query.load::<models::Poi>(conn).await?)
         }
@@ -89,159 +188,838 @@ pub(super) async fn pois(
                 .inner_join(indexers::table)
                 .inner_join(blocks::table)
                 .inner_join(live_pois::table)
+                .inner_join(poi_values::table)
                 .select(selection)
                 .order_by(order_by)
                 .filter(deployments_filter)
+                .filter(network_filter)
                 .filter(blocks_filter)
                 .filter(indexer_filter)
+                .filter(indexer_tag_filter)
+                .filter(epoch_filter)
                 .limit(limit);
             Ok(query.load::<models::Poi>(conn).await?)
         }
     }
 }
 
+/// Which PoIs [`tag_poi_epochs`] considers.
+pub(super) enum PoiEpochScope<'a> {
+    /// The PoIs with the given IDs, e.g. those that were just written.
+    Pois(&'a [IntId]),
+    /// All PoIs on the given networks, e.g. after their epochs were updated.
+    Networks(&'a [IntId]),
+}
+
+/// Tags the PoIs in `scope` that don't have an epoch yet with the latest epoch
+/// that started at or before their block, if the network's epochs are known.
+/// PoIs of networks without epochs are skipped, rather than rewritten with a
+/// `NULL` epoch on every call.
+pub(super) async fn tag_poi_epochs(
+    conn: &mut AsyncPgConnection,
+    scope: PoiEpochScope<'_>,
+) -> anyhow::Result<()> {
+    let (condition, ids) = match scope {
+        PoiEpochScope::Pois(ids) => ("pois.id = ANY($1)", ids),
+        PoiEpochScope::Networks(ids) => ("blocks.network_id = ANY($1)", ids),
+    };
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    diesel::sql_query(format!(
+        "UPDATE pois \
+        SET epoch = ( \
+            SELECT network_epochs.epoch \
+            FROM network_epochs \
+            WHERE network_epochs.network_id = blocks.network_id \
+                AND network_epochs.start_block_number <= blocks.number \
+            ORDER BY network_epochs.epoch DESC \
+            LIMIT 1 \
+        ) \
+        FROM blocks \
+        WHERE pois.epoch IS NULL AND blocks.id = pois.block_id AND {condition} \
+            AND EXISTS ( \
+                SELECT 1 FROM network_epochs \
+                WHERE network_epochs.network_id = blocks.network_id \
+                    AND network_epochs.start_block_number <= blocks.number \
+            )"
+    ))
+    .bind::<sql_types::Array<sql_types::Integer>, _>(ids)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Fetches any collected PoI with the given value.
+pub(super) async fn poi_by_value(
+    conn: &mut AsyncPgConnection,
+    poi: &PoiBytes,
+) -> anyhow::Result<Option<models::Poi>> {
+    use schema::{poi_values, pois};
+
+    let query = pois::table
+        .inner_join(poi_values::table)
+        .select(models::Poi::COLUMNS)
+        .filter(poi_values::poi.eq(poi));
+
+    Ok(query.get_result(conn).await.optional()?)
+}
+
+/// Groups the live PoIs of the given subgraph deployments by value, so that
+/// agreement can be computed without loading every PoI.
+pub(super) async fn live_poi_groups(
+    conn: &mut AsyncPgConnection,
+    sg_deployment_ids: &[IntId],
+) -> anyhow::Result<Vec<models::LivePoiGroup>> {
+    Ok(diesel::sql_query(
+        "SELECT live_pois.sg_deployment_id, poi_values.poi, \
+            MAX(live_pois.poi_id) AS poi_id, \
+            ARRAY_AGG(live_pois.indexer_id ORDER BY live_pois.indexer_id) AS indexer_ids \
+        FROM live_pois \
+        JOIN pois ON pois.id = live_pois.poi_id \
+        JOIN poi_values ON poi_values.id = pois.poi_value_id \
+        WHERE live_pois.sg_deployment_id = ANY($1) \
+        GROUP BY live_pois.sg_deployment_id, poi_values.poi",
+    )
+    .bind::<sql_types::Array<sql_types::Integer>, _>(sg_deployment_ids)
+    .load(conn)
+    .await?)
+}
+
 pub async fn write_indexers(
     conn: &mut AsyncPgConnection,
     indexers: &[impl AsRef<dyn IndexerClient>],
 ) -> anyhow::Result<()> {
+    use diesel::upsert::excluded;
     use schema::indexers;
 
+    // The same indexer may be discovered through multiple data sources; only
+    // the first occurrence is kept, as PostgreSQL won't upsert the same row
+    // twice within a single statement.
+    let mut seen_addresses = HashSet::new();
     let insertable_indexers = indexers
         .iter()
-        .map(|indexer| {
-            let indexer = indexer.as_ref();
-            NewIndexer {
-                address: indexer.address().to_owned(),
-                name: indexer.name().map(|s| s.to_string()),
-            }
+        .map(|indexer| indexer.as_ref())
+        .filter(|indexer| seen_addresses.insert(indexer.address()))
+        .map(|indexer| NewIndexer {
+            address: indexer.address().to_owned(),
+            name: indexer.name().map(|s| s.to_string()),
+            network_subgraph_operator_contact: indexer.operator_contact().map(|s| s.to_string()),
         })
         .collect::<Vec<_>>();
 
+    // Keep the contact information up to date with the current
+    // configuration.
     diesel::insert_into(indexers::table)
         .values(insertable_indexers)
-        .on_conflict_do_nothing()
+        .on_conflict(indexers::address)
+        .do_update()
+        .set(
+            indexers::network_subgraph_operator_contact
+                .eq(excluded(indexers::network_subgraph_operator_contact)),
+        )
         .execute(conn)
         .await?;
 
     Ok(())
 }
 
-// The caller must make sure that `conn` is within a transaction.
+/// The maximum number of rows per multi-row `INSERT`, as Postgres allows at
+/// most 65535 bind parameters per statement.
+const INSERT_CHUNK_SIZE: usize = 4096;
+
+/// Returns the IDs of the written PoIs. The caller must make sure that
+/// `conn` is within a transaction.
 pub(super) async fn write_pois<W>(
     conn: &mut AsyncPgConnection,
     pois: Vec<W>,
     live: PoiLiveness,
-) -> anyhow::Result<()>
+    source: PoiSource,
+    now: NaiveDateTime,
+) -> anyhow::Result<Vec<IntId>>
 where
     W: WritablePoi + Send + Sync,
     W::IndexerId: Send + Sync,
 {
-    use diesel::insert_into;
-    use schema::pois;
-
     let len = pois.len();
 
-    // Group PoIs by deployment
+    // Group PoIs by deployment and network
     let mut grouped_pois = BTreeMap::new();
     for poi in pois.iter() {
         grouped_pois
-            .entry(poi.deployment_cid())
+            .entry((poi.deployment_cid(), poi.network()))
             .or_insert_with(Vec::new)
             .push(poi);
     }
 
-    for (deployment, poi_group) in grouped_pois {
-        let sg_deployment_id = get_or_insert_deployment(conn, &deployment).await?;
+    // Make sure all PoIs have the same block ptr
+    for poi_group in grouped_pois.values() {
         let block_ptr = poi_group[0].block();
-
-        // Make sure all PoIs have the same block ptr
         if !poi_group.iter().all(|poi| poi.block() == block_ptr) {
             return Err(anyhow::anyhow!(
                 "All PoIs for a given deployment must have the same block"
             ));
         }
+    }
 
-        let block_id = get_or_insert_block(conn, block_ptr).await?;
-
-        let mut new_pois = vec![];
+    // Everything that the PoIs refer to is looked up (or inserted) with a
+    // handful of statements for all of them, rather than row by row.
+    let network_ids =
+        get_or_insert_networks(conn, grouped_pois.keys().map(|(_, network)| *network)).await?;
+    let sg_deployment_ids = get_or_insert_deployments(
+        conn,
+        grouped_pois
+            .keys()
+            .map(|(deployment, network)| (deployment, network_ids[*network])),
+        now,
+    )
+    .await?;
+    let block_ids = get_or_insert_blocks(
+        conn,
+        grouped_pois
+            .iter()
+            .map(|((_, network), poi_group)| (network_ids[*network], poi_group[0].block())),
+    )
+    .await?;
+    record_block_hash_conflicts(
+        conn,
+        grouped_pois.iter().map(|((_, network), poi_group)| {
+            (network_ids[*network], poi_group[0].block().number as i64)
+        }),
+        now,
+    )
+    .await?;
+    let poi_value_ids =
+        get_or_insert_poi_values(conn, pois.iter().map(|poi| *poi.proof_of_indexing())).await?;
+    let indexer_ids = get_indexer_ids(conn, pois.iter().map(|poi| poi.indexer_id())).await?;
 
-        for poi in poi_group.iter() {
-            let indexer_id =
-                get_indexer_id(conn, poi.indexer_id().name(), &poi.indexer_id().address()).await?;
+    let mut new_pois = vec![];
+    let mut block_numbers = BTreeMap::new();
+    for ((deployment, network), poi_group) in &grouped_pois {
+        let network_id = network_ids[*network];
+        let sg_deployment_id = sg_deployment_ids[&(deployment.clone(), network_id)];
+        let block_id = block_ids[&(network_id, block_hash(poi_group[0].block())?.clone())];
+        block_numbers.insert(sg_deployment_id, poi_group[0].block().number as i64);
 
+        for poi in poi_group {
+            let indexer_id = poi.indexer_id();
             new_pois.push(NewPoi {
                 sg_deployment_id,
-                indexer_id,
+                indexer_id: indexer_ids
+                    [&(indexer_id.name().map(Cow::into_owned), indexer_id.address())],
                 block_id,
-                poi: *poi.proof_of_indexing(),
-                created_at: Utc::now().naive_utc(),
+                poi_value_id: poi_value_ids[poi.proof_of_indexing()],
+                created_at: now,
+                source,
             });
         }
+    }
+
+    let inserted = insert_pois(conn, &new_pois).await?;
+    let ids = inserted.iter().map(|poi| poi.id).collect();
+
+    if live == PoiLiveness::Live {
+        write_live_pois(
+            conn,
+            inserted
+                .into_iter()
+                .map(|poi| (poi.id, poi.sg_deployment_id, poi.indexer_id))
+                .collect(),
+            block_numbers,
+        )
+        .await?;
+    }
+
+    info!(%len, "Wrote POIs to database");
+    Ok(ids)
+}
+
+#[derive(QueryableByName)]
+struct InsertedPoi {
+    #[diesel(sql_type = sql_types::Integer)]
+    id: IntId,
+    #[diesel(sql_type = sql_types::Integer)]
+    sg_deployment_id: IntId,
+    #[diesel(sql_type = sql_types::Integer)]
+    indexer_id: IntId,
+}
+
+/// Inserts all PoIs with a single statement. Each column is bound as one
+/// array rather than each value as a parameter, so the statement stays the
+/// same for any number of PoIs instead of growing with them, and isn't subject
+/// to the bind parameter limit.
+async fn insert_pois(
+    conn: &mut AsyncPgConnection,
+    new_pois: &[NewPoi],
+) -> anyhow::Result<Vec<InsertedPoi>> {
+    use sql_types::{Array, BigInt, Integer, Timestamp};
+
+    Ok(diesel::sql_query(
+        "INSERT INTO pois (sg_deployment_id, indexer_id, block_id, poi_value_id, created_at, source) \
+        SELECT * FROM UNNEST($1::int4[], $2::int4[], $3::int8[], $4::int4[], $5::timestamp[], \
+            $6::int4[]) \
+        RETURNING id, sg_deployment_id, indexer_id",
+    )
+    .bind::<Array<Integer>, _>(
+        new_pois
+            .iter()
+            .map(|p| p.sg_deployment_id)
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Integer>, _>(new_pois.iter().map(|p| p.indexer_id).collect::<Vec<_>>())
+    .bind::<Array<BigInt>, _>(new_pois.iter().map(|p| p.block_id).collect::<Vec<_>>())
+    .bind::<Array<Integer>, _>(new_pois.iter().map(|p| p.poi_value_id).collect::<Vec<_>>())
+    .bind::<Array<Timestamp>, _>(new_pois.iter().map(|p| p.created_at).collect::<Vec<_>>())
+    .bind::<Array<Integer>, _>(new_pois.iter().map(|p| p.source).collect::<Vec<_>>())
+    .load(conn)
+    .await?)
+}
+
+/// Makes the given PoIs the live PoIs of their indexers for their
+/// deployments, and any other indexers' PoIs for those deployments at other
+/// blocks no longer live. Other indexers' live PoIs at the same block are
+/// kept, so that the PoIs of a polling cycle can be written indexer by
+/// indexer. Live PoIs are upserted rather than deleted and reinserted, so that
+/// concurrent readers and writers never observe a deployment without live
+/// PoIs.
+async fn write_live_pois(
+    conn: &mut AsyncPgConnection,
+    id_deployment_and_indexer: Vec<(IntId, IntId, IntId)>,
+    block_numbers: BTreeMap<IntId, i64>,
+) -> anyhow::Result<()> {
+    use sql_types::{Array, BigInt, Integer};
+
+    // An upsert can't update the same row twice, so only the last PoI of
+    // each indexer is kept.
+    let poi_ids_by_key: BTreeMap<(IntId, IntId), IntId> = id_deployment_and_indexer
+        .into_iter()
+        .map(|(poi_id, sg_deployment_id, indexer_id)| ((sg_deployment_id, indexer_id), poi_id))
+        .collect();
+    let (keys, poi_ids): (Vec<(IntId, IntId)>, Vec<IntId>) =
+        poi_ids_by_key.iter().map(|(key, id)| (*key, *id)).unzip();
+    let (sg_deployment_ids, indexer_ids): (Vec<IntId>, Vec<IntId>) = keys.into_iter().unzip();
+
+    // Like `insert_pois`, with one array per column.
+    diesel::sql_query(
+        "INSERT INTO live_pois (poi_id, sg_deployment_id, indexer_id) \
+        SELECT * FROM UNNEST($1::int4[], $2::int4[], $3::int4[]) \
+        ON CONFLICT (sg_deployment_id, indexer_id) DO UPDATE SET poi_id = excluded.poi_id",
+    )
+    .bind::<Array<Integer>, _>(&poi_ids)
+    .bind::<Array<Integer>, _>(&sg_deployment_ids)
+    .bind::<Array<Integer>, _>(&indexer_ids)
+    .execute(conn)
+    .await?;
+
+    // Live PoIs at other blocks than the new ones are from previous polling
+    // cycles, and belong to indexers that didn't respond in this one. Blocks
+    // are compared by number, as indexers may disagree on block hashes.
+    let (sg_deployment_ids, block_numbers): (Vec<IntId>, Vec<i64>) =
+        block_numbers.into_iter().unzip();
+    diesel::sql_query(
+        "DELETE FROM live_pois \
+        USING pois, blocks, UNNEST($1::int4[], $2::int8[]) AS latest(sg_deployment_id, number) \
+        WHERE live_pois.poi_id = pois.id \
+            AND pois.block_id = blocks.id \
+            AND live_pois.sg_deployment_id = latest.sg_deployment_id \
+            AND blocks.number <> latest.number",
+    )
+    .bind::<Array<Integer>, _>(&sg_deployment_ids)
+    .bind::<Array<BigInt>, _>(&block_numbers)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// The caller must make sure that `conn` is within a transaction.
+pub(super) async fn write_indexing_statuses(
+    conn: &mut AsyncPgConnection,
+    statuses: &[IndexingStatus],
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    use diesel::upsert::excluded;
+    use schema::{indexing_statuses, networks, sg_deployments};
+
+    let mut new_statuses = vec![];
+    for status in statuses {
+        let indexer_id =
+            get_indexer_id(conn, status.indexer.name(), &status.indexer.address()).await?;
+        let network_id = get_or_insert_network(conn, &status.network).await?;
+        let sg_deployment_id =
+            get_or_insert_deployment(conn, &status.deployment, network_id, now).await?;
+
+        new_statuses.push(NewIndexingStatus {
+            indexer_id,
+            sg_deployment_id,
+            health: status.health,
+            synced: status.synced,
+            paused: status.paused,
+            updated_at: now,
+            latest_block_number: status.latest_block.number.try_into()?,
+            earliest_block_number: status.earliest_block_num.try_into()?,
+        });
 
-        // Insert all PoIs for this deployment
-        let id_and_indexer: Vec<(i32, i32)> = insert_into(pois::table)
-            .values(&new_pois)
-            .returning((pois::id, pois::indexer_id))
-            .get_results(conn)
+        if !status.errors.is_empty() {
+            write_indexing_errors(conn, indexer_id, sg_deployment_id, &status.errors, now).await?;
+        }
+    }
+
+    // The statuses that the same indexers reported in previous polling
+    // cycles. Those of disabled networks aren't compared, as they're no
+    // longer written.
+    let indexer_ids: BTreeSet<IntId> = new_statuses.iter().map(|s| s.indexer_id).collect();
+    let previous_statuses: Vec<(IntId, IntId, IntId, Option<bool>)> = indexing_statuses::table
+        .inner_join(sg_deployments::table.inner_join(networks::table))
+        .filter(indexing_statuses::indexer_id.eq_any(&indexer_ids))
+        .filter(networks::enabled.eq(true))
+        .select((
+            indexing_statuses::id,
+            indexing_statuses::indexer_id,
+            indexing_statuses::sg_deployment_id,
+            indexing_statuses::paused,
+        ))
+        .load(conn)
+        .await?;
+
+    write_indexing_status_history(conn, &new_statuses, now).await?;
+
+    diesel::insert_into(indexing_statuses::table)
+        .values(&new_statuses)
+        .on_conflict((
+            indexing_statuses::indexer_id,
+            indexing_statuses::sg_deployment_id,
+        ))
+        .do_update()
+        .set((
+            indexing_statuses::health.eq(excluded(indexing_statuses::health)),
+            indexing_statuses::synced.eq(excluded(indexing_statuses::synced)),
+            indexing_statuses::paused.eq(excluded(indexing_statuses::paused)),
+            indexing_statuses::updated_at.eq(excluded(indexing_statuses::updated_at)),
+            indexing_statuses::latest_block_number
+                .eq(excluded(indexing_statuses::latest_block_number)),
+            indexing_statuses::earliest_block_number
+                .eq(excluded(indexing_statuses::earliest_block_number)),
+        ))
+        .execute(conn)
+        .await?;
+
+    write_deployment_lifecycle(conn, previous_statuses, &new_statuses, now).await?;
+
+    info!(len = statuses.len(), "Wrote indexing statuses to database");
+    Ok(())
+}
+
+/// Appends the indexing statuses whose health, sync or pause flags differ
+/// from the stored ones, or that weren't stored yet, to the history. Must be
+/// called before the new statuses overwrite the stored ones.
+async fn write_indexing_status_history(
+    conn: &mut AsyncPgConnection,
+    new_statuses: &[NewIndexingStatus],
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    use schema::{indexing_status_history, indexing_statuses};
+
+    let indexer_ids: BTreeSet<IntId> = new_statuses.iter().map(|s| s.indexer_id).collect();
+    let previous_flags: HashMap<(IntId, IntId), (SubgraphHealth, bool, Option<bool>)> =
+        indexing_statuses::table
+            .filter(indexing_statuses::indexer_id.eq_any(&indexer_ids))
+            .select((
+                indexing_statuses::indexer_id,
+                indexing_statuses::sg_deployment_id,
+                indexing_statuses::health,
+                indexing_statuses::synced,
+                indexing_statuses::paused,
+            ))
+            .load::<(IntId, IntId, SubgraphHealth, bool, Option<bool>)>(conn)
+            .await?
+            .into_iter()
+            .map(|(indexer_id, sg_deployment_id, health, synced, paused)| {
+                ((indexer_id, sg_deployment_id), (health, synced, paused))
+            })
+            .collect();
+
+    let entries: Vec<NewIndexingStatusHistoryEntry> = new_statuses
+        .iter()
+        .filter(|status| {
+            previous_flags.get(&(status.indexer_id, status.sg_deployment_id))
+                != Some(&(status.health, status.synced, status.paused))
+        })
+        .map(|status| NewIndexingStatusHistoryEntry {
+            indexer_id: status.indexer_id,
+            sg_deployment_id: status.sg_deployment_id,
+            health: status.health,
+            synced: status.synced,
+            paused: status.paused,
+            latest_block_number: status.latest_block_number,
+            earliest_block_number: status.earliest_block_number,
+            created_at: now,
+        })
+        .collect();
+
+    for chunk in entries.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(indexing_status_history::table)
+            .values(chunk)
+            .execute(conn)
             .await?;
+    }
+
+    Ok(())
+}
+
+/// Compares the indexing statuses of a polling cycle against those of the
+/// previous ones and records how indexers started, stopped, paused or resumed
+/// reporting subgraph deployments, as well as the resulting lifecycle of each
+/// deployment.
+///
+/// Only the indexers that reported statuses in this cycle are compared, so
+/// that unreachable indexers don't look like they removed their deployments.
+/// The statuses of deployments that an indexer no longer reports are deleted.
+async fn write_deployment_lifecycle(
+    conn: &mut AsyncPgConnection,
+    previous_statuses: Vec<(IntId, IntId, IntId, Option<bool>)>,
+    new_statuses: &[NewIndexingStatus],
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    use schema::{indexing_statuses, sg_deployment_events, sg_deployments};
 
-        if live == PoiLiveness::Live {
-            // Clear any live pois for this deployment
-            diesel::delete(
-                live_pois::table.filter(live_pois::sg_deployment_id.eq(sg_deployment_id)),
-            )
+    let previous_paused: HashMap<(IntId, IntId), Option<bool>> = previous_statuses
+        .iter()
+        .map(|&(_, indexer_id, sg_deployment_id, paused)| ((indexer_id, sg_deployment_id), paused))
+        .collect();
+    let reported: HashSet<(IntId, IntId)> = new_statuses
+        .iter()
+        .map(|status| (status.indexer_id, status.sg_deployment_id))
+        .collect();
+
+    let mut events = vec![];
+    let mut event = |indexer_id, sg_deployment_id, kind| {
+        events.push(NewSgDeploymentEvent {
+            indexer_id,
+            sg_deployment_id,
+            kind,
+            created_at: now,
+        })
+    };
+    for status in new_statuses {
+        let paused = status.paused == Some(true);
+        match previous_paused.get(&(status.indexer_id, status.sg_deployment_id)) {
+            None => event(
+                status.indexer_id,
+                status.sg_deployment_id,
+                DeploymentEventKind::Added,
+            ),
+            Some(previous) if (*previous == Some(true)) != paused => event(
+                status.indexer_id,
+                status.sg_deployment_id,
+                if paused {
+                    DeploymentEventKind::Paused
+                } else {
+                    DeploymentEventKind::Resumed
+                },
+            ),
+            Some(_) => {}
+        }
+    }
+    let mut removed_status_ids = vec![];
+    for (id, indexer_id, sg_deployment_id, _) in previous_statuses {
+        if !reported.contains(&(indexer_id, sg_deployment_id)) {
+            event(indexer_id, sg_deployment_id, DeploymentEventKind::Removed);
+            removed_status_ids.push(id);
+        }
+    }
+
+    diesel::delete(
+        indexing_statuses::table.filter(indexing_statuses::id.eq_any(&removed_status_ids)),
+    )
+    .execute(conn)
+    .await?;
+    diesel::insert_into(sg_deployment_events::table)
+        .values(&events)
+        .execute(conn)
+        .await?;
+
+    let seen_ids: BTreeSet<IntId> = reported.iter().map(|&(_, id)| id).collect();
+    diesel::update(sg_deployments::table.filter(sg_deployments::id.eq_any(&seen_ids)))
+        .set(sg_deployments::last_seen_at.eq(now))
+        .execute(conn)
+        .await?;
+    diesel::update(
+        sg_deployments::table
+            .filter(sg_deployments::id.eq_any(&seen_ids))
+            .filter(sg_deployments::first_seen_at.is_null()),
+    )
+    .set(sg_deployments::first_seen_at.eq(now))
+    .execute(conn)
+    .await?;
+
+    // The lifecycle status of a deployment depends on the statuses reported
+    // by all indexers, including those that weren't compared.
+    let changed_ids: BTreeSet<IntId> = events.iter().map(|e| e.sg_deployment_id).collect();
+    let remaining: Vec<(IntId, Option<bool>)> = indexing_statuses::table
+        .filter(indexing_statuses::sg_deployment_id.eq_any(&changed_ids))
+        .select((
+            indexing_statuses::sg_deployment_id,
+            indexing_statuses::paused,
+        ))
+        .load(conn)
+        .await?;
+    let mut lifecycle: BTreeMap<IntId, DeploymentLifecycleStatus> = changed_ids
+        .iter()
+        .map(|&id| (id, DeploymentLifecycleStatus::Removed))
+        .collect();
+    for (sg_deployment_id, paused) in remaining {
+        let status = lifecycle
+            .entry(sg_deployment_id)
+            .or_insert(DeploymentLifecycleStatus::Removed);
+        if paused != Some(true) {
+            *status = DeploymentLifecycleStatus::Active;
+        } else if *status == DeploymentLifecycleStatus::Removed {
+            *status = DeploymentLifecycleStatus::Paused;
+        }
+    }
+
+    let mut ids_by_status: BTreeMap<DeploymentLifecycleStatus, Vec<IntId>> = BTreeMap::new();
+    for (id, status) in lifecycle {
+        ids_by_status.entry(status).or_default().push(id);
+    }
+    for (status, ids) in ids_by_status {
+        diesel::update(sg_deployments::table.filter(sg_deployments::id.eq_any(&ids)))
+            .set(sg_deployments::status.eq(status))
             .execute(conn)
             .await?;
+    }
+
+    Ok(())
+}
 
-            for (poi_id, indexer_id) in id_and_indexer {
-                let value = NewLivePoi {
-                    poi_id,
-                    sg_deployment_id,
-                    indexer_id,
-                };
-                diesel::insert_into(live_pois::table)
-                    .values(&value)
-                    .execute(conn)
-                    .await?;
-            }
+/// Inserts the errors that haven't been reported before, and updates
+/// `last_seen_at` of the others.
+async fn write_indexing_errors(
+    conn: &mut AsyncPgConnection,
+    indexer_id: IntId,
+    sg_deployment_id: IntId,
+    errors: &[SubgraphError],
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    use schema::indexing_errors;
+
+    let known_errors: Vec<(IntId, bool, String, Option<i64>)> = indexing_errors::table
+        .filter(indexing_errors::indexer_id.eq(indexer_id))
+        .filter(indexing_errors::sg_deployment_id.eq(sg_deployment_id))
+        .select((
+            indexing_errors::id,
+            indexing_errors::fatal,
+            indexing_errors::message,
+            indexing_errors::block_number,
+        ))
+        .load(conn)
+        .await?;
+
+    let mut seen_ids = vec![];
+    let mut new_errors = vec![];
+    for error in errors {
+        let block_number = error
+            .block
+            .as_ref()
+            .map(|block| i64::try_from(block.number))
+            .transpose()?;
+        let known = known_errors.iter().find(|(_, fatal, message, number)| {
+            *fatal == error.fatal && *message == error.message && *number == block_number
+        });
+
+        match known {
+            Some((id, ..)) => seen_ids.push(*id),
+            None => new_errors.push(NewIndexingError {
+                indexer_id,
+                sg_deployment_id,
+                fatal: error.fatal,
+                deterministic: error.deterministic,
+                message: error.message.clone(),
+                block_number,
+                block_hash: error.block.as_ref().and_then(|block| block.hash.clone()),
+                handler: error.handler.clone(),
+                first_seen_at: now,
+                last_seen_at: now,
+            }),
         }
     }
 
-    info!(%len, "Wrote POIs to database");
+    diesel::update(indexing_errors::table.filter(indexing_errors::id.eq_any(&seen_ids)))
+        .set(indexing_errors::last_seen_at.eq(now))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(indexing_errors::table)
+        .values(&new_errors)
+        .execute(conn)
+        .await?;
+
     Ok(())
 }
 
-async fn get_or_insert_block(
+fn block_hash(block: &BlockPointer) -> anyhow::Result<&BlockHash> {
+    block
+        .hash
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("PoI of block {} without a block hash", block.number))
+}
+
+/// Returns the IDs of the given blocks by network and hash, inserting the ones
+/// that are not stored yet. Blocks that are inserted concurrently by another
+/// writer are skipped and then selected like the existing ones.
+async fn get_or_insert_blocks(
     conn: &mut AsyncPgConnection,
-    block: &BlockPointer,
-) -> anyhow::Result<i64> {
+    blocks: impl Iterator<Item = (IntId, &BlockPointer)>,
+) -> anyhow::Result<HashMap<(IntId, BlockHash), BigIntId>> {
     use schema::blocks;
 
-    // First, attempt to find the existing block by hash
-    // TODO: also filter by network to be extra safe
-    let existing_block: Option<models::Block> = blocks::table
-        .filter(blocks::hash.eq(&block.hash.as_ref().unwrap().0.as_slice()))
-        .get_result(conn)
-        .await
-        .optional()?;
+    let mut numbers: BTreeMap<(IntId, BlockHash), i64> = BTreeMap::new();
+    for (network_id, block) in blocks {
+        numbers.insert(
+            (network_id, block_hash(block)?.clone()),
+            block.number as i64,
+        );
+    }
 
-    if let Some(existing_block) = existing_block {
-        // If the block exists, return its id
-        Ok(existing_block.id)
-    } else {
-        // If the block doesn't exist, insert a new one and return its id
-        let new_block = models::NewBlock {
-            number: block.number as i64,
-            hash: block.hash.clone().unwrap(),
-            network_id: 1, // FIXME: network assumed to be mainnet, see also: hardcoded-mainnet
-        };
-        let block_id = diesel::insert_into(blocks::table)
-            .values(&new_block)
-            .returning(blocks::id)
-            .get_result(conn)
+    let mut ids = select_block_ids(conn, numbers.keys()).await?;
+
+    let new_blocks: Vec<models::NewBlock> = numbers
+        .into_iter()
+        .filter(|(key, _)| !ids.contains_key(key))
+        .map(|((network_id, hash), number)| models::NewBlock {
+            network_id,
+            number,
+            hash,
+        })
+        .collect();
+    for chunk in new_blocks.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(blocks::table)
+            .values(chunk)
+            .on_conflict((blocks::network_id, blocks::hash))
+            .do_nothing()
+            .execute(conn)
+            .await?;
+    }
+    if !new_blocks.is_empty() {
+        let new_keys: Vec<(IntId, BlockHash)> = new_blocks
+            .into_iter()
+            .map(|block| (block.network_id, block.hash))
+            .collect();
+        ids.extend(select_block_ids(conn, new_keys.iter()).await?);
+    }
+
+    Ok(ids)
+}
+
+/// Returns the IDs of those of the given blocks that are stored, by network
+/// and hash.
+async fn select_block_ids(
+    conn: &mut AsyncPgConnection,
+    keys: impl Iterator<Item = &(IntId, BlockHash)> + Clone,
+) -> anyhow::Result<HashMap<(IntId, BlockHash), BigIntId>> {
+    use schema::blocks;
+
+    let network_ids: BTreeSet<IntId> = keys.clone().map(|(id, _)| *id).collect();
+    let hashes: BTreeSet<&BlockHash> = keys.clone().map(|(_, hash)| hash).collect();
+    let keys: HashSet<&(IntId, BlockHash)> = keys.collect();
+
+    Ok(blocks::table
+        .select((blocks::network_id, blocks::hash, blocks::id))
+        .filter(blocks::network_id.eq_any(&network_ids))
+        .filter(blocks::hash.eq_any(&hashes))
+        .load::<(IntId, BlockHash, BigIntId)>(conn)
+        .await?
+        .into_iter()
+        .map(|(network_id, hash, id)| ((network_id, hash), id))
+        .filter(|key| keys.contains(&key.0))
+        .collect())
+}
+
+/// Records the given block numbers as block hash conflicts if more than one
+/// block hash is stored for them, i.e. if indexers disagree on the block.
+async fn record_block_hash_conflicts(
+    conn: &mut AsyncPgConnection,
+    blocks: impl Iterator<Item = (IntId, i64)>,
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    use schema::{block_hash_conflicts, blocks};
+
+    let blocks: BTreeSet<(IntId, i64)> = blocks.collect();
+    let network_ids: BTreeSet<IntId> = blocks.iter().map(|(id, _)| *id).collect();
+    let numbers: BTreeSet<i64> = blocks.iter().map(|(_, number)| *number).collect();
+
+    let mut hash_counts: BTreeMap<(IntId, i64), usize> = BTreeMap::new();
+    for block in blocks::table
+        .select((blocks::network_id, blocks::number))
+        .filter(blocks::network_id.eq_any(&network_ids))
+        .filter(blocks::number.eq_any(&numbers))
+        .load::<(IntId, i64)>(conn)
+        .await?
+    {
+        *hash_counts.entry(block).or_default() += 1;
+    }
+
+    let conflicts: Vec<models::NewBlockHashConflict> = hash_counts
+        .into_iter()
+        .filter(|(block, count)| *count > 1 && blocks.contains(block))
+        .map(
+            |((network_id, block_number), _)| models::NewBlockHashConflict {
+                network_id,
+                block_number,
+                detected_at: now,
+            },
+        )
+        .collect();
+    for chunk in conflicts.chunks(INSERT_CHUNK_SIZE) {
+        let inserted = diesel::insert_into(block_hash_conflicts::table)
+            .values(chunk)
+            .on_conflict((
+                block_hash_conflicts::network_id,
+                block_hash_conflicts::block_number,
+            ))
+            .do_nothing()
+            .execute(conn)
+            .await?;
+        if inserted > 0 {
+            warn!(
+                conflicts = inserted,
+                "Indexers reported different block hashes for the same block numbers"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the IDs of the given PoI values, inserting the ones that are not
+/// stored yet.
+async fn get_or_insert_poi_values(
+    conn: &mut AsyncPgConnection,
+    pois: impl Iterator<Item = PoiBytes>,
+) -> anyhow::Result<HashMap<PoiBytes, i32>> {
+    use schema::poi_values;
+
+    let pois: BTreeSet<PoiBytes> = pois.collect();
+    let new_poi_values: Vec<_> = pois.iter().map(|poi| NewPoiValue { poi: *poi }).collect();
+
+    for chunk in new_poi_values.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(poi_values::table)
+            .values(chunk)
+            .on_conflict(poi_values::poi)
+            .do_nothing()
+            .execute(conn)
             .await?;
-        Ok(block_id)
     }
+
+    let ids: Vec<(i32, PoiBytes)> = poi_values::table
+        .select((poi_values::id, poi_values::poi))
+        .filter(poi_values::poi.eq_any(&pois))
+        .load(conn)
+        .await?;
+
+    Ok(ids.into_iter().map(|(id, poi)| (poi, id)).collect())
 }
 
 pub async fn get_indexer_id<'a>(
@@ -269,9 +1047,163 @@ pub async fn get_indexer_id<'a>(
     }
 }
 
+/// Returns the IDs of the given indexers by name and address, like
+/// [`get_indexer_id`] does for a single one.
+async fn get_indexer_ids<I: IndexerId>(
+    conn: &mut AsyncPgConnection,
+    indexers: impl Iterator<Item = I>,
+) -> anyhow::Result<HashMap<(Option<String>, IndexerAddress), IntId>> {
+    use schema::indexers;
+
+    let keys: BTreeSet<(Option<String>, IndexerAddress)> = indexers
+        .map(|indexer| (indexer.name().map(Cow::into_owned), indexer.address()))
+        .collect();
+    let addresses: BTreeSet<&IndexerAddress> = keys.iter().map(|(_, address)| address).collect();
+
+    let existing: HashSet<(Option<String>, IndexerAddress, IntId)> = indexers::table
+        .select((indexers::name, indexers::address, indexers::id))
+        .filter(indexers::address.eq_any(&addresses))
+        .load(conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    keys.into_iter()
+        .map(|(name, address)| {
+            let id = existing
+                .iter()
+                .find(|(n, a, _)| *n == name && *a == address)
+                .map(|(_, _, id)| *id)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Indexer with name {:?} and/or address {:?} not found",
+                        &name,
+                        address
+                    )
+                })?;
+            Ok(((name, address), id))
+        })
+        .collect()
+}
+
+/// Returns the IDs of the networks with the given names, inserting the ones
+/// that are not known yet (see [`get_or_insert_network`]).
+async fn get_or_insert_networks<'a>(
+    conn: &mut AsyncPgConnection,
+    network_names: impl Iterator<Item = &'a str>,
+) -> anyhow::Result<HashMap<&'a str, IntId>> {
+    use schema::networks;
+
+    let network_names: BTreeSet<&str> = network_names.collect();
+    let new_networks: Vec<_> = network_names
+        .iter()
+        .map(|name| models::NewNetwork {
+            name: name.to_string(),
+            caip2: None,
+        })
+        .collect();
+
+    diesel::insert_into(networks::table)
+        .values(&new_networks)
+        .on_conflict(networks::name)
+        .do_nothing()
+        .execute(conn)
+        .await?;
+
+    let ids: HashMap<String, IntId> = networks::table
+        .select((networks::name, networks::id))
+        .filter(networks::name.eq_any(&network_names))
+        .load::<(String, IntId)>(conn)
+        .await?
+        .into_iter()
+        .collect();
+    Ok(network_names
+        .into_iter()
+        .filter_map(|name| Some((name, *ids.get(name)?)))
+        .collect())
+}
+
+/// Returns the ID of the network with the given name, inserting it if it's
+/// not known yet, e.g. because it's missing from the configuration. Networks
+/// inserted this way don't have a CAIP-2 ID.
+async fn get_or_insert_network(
+    conn: &mut AsyncPgConnection,
+    network_name: &str,
+) -> anyhow::Result<IntId> {
+    use schema::networks;
+
+    diesel::insert_into(networks::table)
+        .values(models::NewNetwork {
+            name: network_name.to_string(),
+            caip2: None,
+        })
+        .on_conflict(networks::name)
+        .do_nothing()
+        .execute(conn)
+        .await?;
+
+    Ok(networks::table
+        .select(networks::id)
+        .filter(networks::name.eq(network_name))
+        .get_result(conn)
+        .await?)
+}
+
+/// Returns the IDs of the given subgraph deployments by IPFS CID and network,
+/// inserting the ones that are not stored yet (see
+/// [`get_or_insert_deployment`]).
+async fn get_or_insert_deployments<'a>(
+    conn: &mut AsyncPgConnection,
+    deployments: impl Iterator<Item = (&'a IpfsCid, IntId)>,
+    now: NaiveDateTime,
+) -> anyhow::Result<HashMap<(IpfsCid, IntId), IntId>> {
+    use schema::sg_deployments;
+
+    let deployments: BTreeSet<(&IpfsCid, IntId)> = deployments.collect();
+    let new_sg_deployments: Vec<_> = deployments
+        .iter()
+        .map(|(cid, network_id)| NewSgDeployment {
+            ipfs_cid: cid.to_string(),
+            network: *network_id,
+            created_at: now,
+        })
+        .collect();
+
+    for chunk in new_sg_deployments.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(sg_deployments::table)
+            .values(chunk)
+            .on_conflict((sg_deployments::ipfs_cid, sg_deployments::network))
+            .do_nothing()
+            .execute(conn)
+            .await?;
+    }
+
+    let cids: BTreeSet<String> = deployments.iter().map(|(cid, _)| cid.to_string()).collect();
+    let network_ids: BTreeSet<IntId> = deployments.iter().map(|(_, id)| *id).collect();
+    let ids: HashMap<(IpfsCid, IntId), IntId> = sg_deployments::table
+        .select((
+            sg_deployments::ipfs_cid,
+            sg_deployments::network,
+            sg_deployments::id,
+        ))
+        .filter(sg_deployments::ipfs_cid.eq_any(&cids))
+        .filter(sg_deployments::network.eq_any(&network_ids))
+        .load::<(IpfsCid, IntId, IntId)>(conn)
+        .await?
+        .into_iter()
+        .map(|(cid, network_id, id)| ((cid, network_id), id))
+        .collect();
+    Ok(ids)
+}
+
+/// Returns the ID of the subgraph deployment with the given IPFS CID on the
+/// given network, inserting it if necessary. The same IPFS CID can be indexed
+/// on several networks, and each of them is a separate deployment.
 async fn get_or_insert_deployment(
     conn: &mut AsyncPgConnection,
     deployment_cid: &IpfsCid,
+    network_id: IntId,
+    now: NaiveDateTime,
 ) -> Result<i32, anyhow::Error> {
     use schema::sg_deployments;
 
@@ -283,8 +1215,14 @@ async fn get_or_insert_deployment(
             sg_names::name.nullable(),
             sg_deployments::network,
             sg_deployments::created_at,
+            sg_deployments::manifest,
+            sg_deployments::first_seen_at,
+            sg_deployments::last_seen_at,
+            sg_deployments::status,
+            sg_deployments::substreams,
         ))
         .filter(sg_deployments::ipfs_cid.eq(&deployment_cid))
+        .filter(sg_deployments::network.eq(network_id))
         .get_result(conn)
         .await
         .optional()?;
@@ -296,8 +1234,8 @@ async fn get_or_insert_deployment(
             // If the sg_deployment doesn't exist, insert a new one and return its id
             let new_sg_deployment = NewSgDeployment {
                 ipfs_cid: deployment_cid.to_string(),
-                network: 1, // Network assumed to be mainnet, see also: hardcoded-mainnet
-                created_at: Utc::now().naive_utc(),
+                network: network_id,
+                created_at: now,
             };
             diesel::insert_into(sg_deployments::table)
                 .values(&new_sg_deployment)
@@ -0,0 +1,76 @@
+//! Streaming of large query results with keyset pagination.
+
+use std::future::Future;
+
+use futures::{stream, Stream};
+
+/// Streams the pages returned by `load_page`, which is given the key of the
+/// last row of the previous page (if any) and the maximum page size. Only a
+/// single page is held in memory at a time, and unlike a database cursor, no
+/// connection is held in between pages. Stops after a short page, or after
+/// `limit` rows.
+pub(crate) fn keyset_pages<T, K, F, Fut>(
+    page_size: u16,
+    limit: Option<usize>,
+    key: fn(&T) -> K,
+    load_page: F,
+) -> impl Stream<Item = anyhow::Result<Vec<T>>>
+where
+    F: FnMut(Option<K>, u16) -> Fut,
+    Fut: Future<Output = anyhow::Result<Vec<T>>>,
+{
+    let state = (load_page, None, limit.unwrap_or(usize::MAX));
+
+    stream::try_unfold(state, move |(mut load_page, after, remaining)| async move {
+        if remaining == 0 {
+            return Ok(None);
+        }
+
+        let page_size = page_size.min(remaining.try_into().unwrap_or(u16::MAX));
+        let rows = load_page(after, page_size).await?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        // A short page is the last one.
+        let remaining = if rows.len() < usize::from(page_size) {
+            0
+        } else {
+            remaining - rows.len()
+        };
+        let after = rows.last().map(key);
+
+        Ok(Some((rows, (load_page, after, remaining))))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    fn pages(rows: u32, page_size: u16, limit: Option<usize>) -> Vec<Vec<u32>> {
+        let stream = keyset_pages(
+            page_size,
+            limit,
+            |row: &u32| *row,
+            |after, page_size| {
+                let first = after.map_or(0, |after| after + 1);
+                let page = (first..rows).take(page_size.into()).collect();
+                async move { Ok(page) }
+            },
+        );
+
+        block_on(stream.try_collect()).unwrap()
+    }
+
+    #[test]
+    fn keyset_pages_cover_all_rows_once() {
+        assert_eq!(pages(0, 2, None), Vec::<Vec<u32>>::new());
+        assert_eq!(pages(5, 2, None), vec![vec![0, 1], vec![2, 3], vec![4]]);
+        assert_eq!(pages(4, 2, None), vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(pages(5, 2, Some(3)), vec![vec![0, 1], vec![2]]);
+    }
+}
@@ -0,0 +1,55 @@
+use std::future::Future;
+use std::time::Instant;
+
+use prometheus::{Gauge, HistogramVec, IntCounterVec, IntGaugeVec};
+
+/// Prometheus metrics of database operations, see [`super::Store::with_metrics`].
+/// Registering them is up to the caller.
+#[derive(Clone, Debug)]
+pub struct StoreMetrics {
+    /// Duration of store operations in seconds, labeled by `method`.
+    pub query_duration_seconds: HistogramVec,
+    /// Number of failed store operations, labeled by `method`.
+    pub query_errors: IntCounterVec,
+    /// Connections in the pool, labeled by `state`: `max`, `open`, `idle` and
+    /// `waiting` (i.e. tasks that wait for a connection).
+    pub pool_connections: IntGaugeVec,
+    /// Replication lag of the read replica, as of the last time it was
+    /// measured, see [`super::Store::with_read_replica`].
+    pub read_replica_lag_seconds: Gauge,
+}
+
+impl StoreMetrics {
+    pub(super) async fn observe<T>(
+        &self,
+        method: &'static str,
+        operation: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let start = Instant::now();
+        let result = operation.await;
+
+        self.query_duration_seconds
+            .with_label_values(&[method])
+            .observe(start.elapsed().as_secs_f64());
+        if result.is_err() {
+            self.query_errors.with_label_values(&[method]).inc();
+        }
+
+        result
+    }
+
+    /// `available` is negative when tasks are waiting for a connection.
+    pub(super) fn set_pool_status(&self, max_size: usize, size: usize, available: isize) {
+        let idle = available.max(0) as i64;
+        let waiting = (-available).max(0) as i64;
+
+        for (state, value) in [
+            ("max", max_size as i64),
+            ("open", size as i64),
+            ("idle", idle),
+            ("waiting", waiting),
+        ] {
+            self.pool_connections.with_label_values(&[state]).set(value);
+        }
+    }
+}
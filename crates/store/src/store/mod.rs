@@ -1,6 +1,10 @@
+mod advisory_lock;
 mod diesel_queries;
+mod keyset;
+mod metrics;
+mod read_replica;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -12,22 +16,53 @@ use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use diesel_async::scoped_futures::ScopedFutureExt;
 use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use diesel_async_migrations::{embed_migrations, EmbeddedMigrations};
-use graphix_common_types::{inputs, ApiKeyPermissionLevel, IndexerAddress, IpfsCid, PoiBytes};
-use graphix_indexer_client::{IndexerClient, IndexerId, WritablePoi};
+use graphix_common_types::{
+    inputs, ApiKeyPermissionLevel, BisectionReport, BisectionRunReport, BlockHash, Clock,
+    DeploymentEventKind, DeploymentName, DivergenceBlockBounds, DivergenceInvestigationReport,
+    DivergenceInvestigationStatus, IndexerAddress, IndexerTags, InstanceConfig,
+    InstanceConfigImport, IpfsCid, JobStatus, PartialBlock, PoiBytes, PoiSource, SubgraphManifest,
+    SystemClock,
+};
+use graphix_indexer_client::{IndexerClient, IndexerId, IndexingStatus, WithIndexer, WritablePoi};
 use tracing::info;
 use uuid::Uuid;
 
+pub use self::advisory_lock::AdvisoryLock;
+use self::diesel_queries::PoiEpochScope;
+pub use self::metrics::StoreMetrics;
+use self::read_replica::ReadReplica;
 use crate::models::{
-    ApiKey, ApiKeyDbRow, ApiKeyPublicMetadata, FailedQueryRow, Indexer as IndexerModel, IntId,
-    NewIndexerNetworkSubgraphMetadata, NewNetwork, NewlyCreatedApiKey, Poi, SgDeployment,
+    ApiKey, ApiKeyDbRow, ApiKeyPublicMetadata, BigIntId, DeploymentSubscription,
+    DivergenceInvestigationJob, FailedQueryRow, Indexer as IndexerModel, IntId,
+    NewDeploymentSubscription, NewEvent, NewIndexerNetworkSubgraphMetadata, NewNetwork,
+    NewNetworkEpoch, NewWebhook, NewlyCreatedApiKey, Poi, PoiExportRow, SgDeployment, Webhook,
 };
 use crate::{models, schema};
 
+/// Returned by [`Store::create_webhook`] when the API key already has the
+/// maximum number of webhooks.
+#[derive(Debug, thiserror::Error)]
+#[error("API key already has the maximum number of webhooks ({max_webhooks})")]
+pub struct WebhookLimitReached {
+    pub max_webhooks: u32,
+}
+
+/// The mutation and API key of an admin audit log entry that is written
+/// together with the change it records, e.g. by [`Store::update_config`].
+#[derive(Debug, Clone, Copy)]
+pub struct AuditedMutation<'a> {
+    pub api_key: Option<&'a ApiKey>,
+    pub mutation: &'a str,
+}
+
 /// An abstraction over all database operations. It uses [`Arc`] internally, so
 /// it's cheaply cloneable.
 #[derive(Clone)]
 pub struct Store {
     pool: Pool<AsyncPgConnection>,
+    read_replica: Option<ReadReplica>,
+    clock: Arc<dyn Clock>,
+    metrics: Option<StoreMetrics>,
 }
 
 impl Debug for Store {
@@ -38,15 +73,15 @@ impl Debug for Store {
 }
 
 impl Store {
+    // A `const` rather than a `static` on purpose: `EmbeddedMigrations`
+    // remembers whether it has set up the migrations table, and each store
+    // (e.g. one per test database) needs a fresh copy.
+    #[allow(clippy::declare_interior_mutable_const)]
     const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
     /// Connects to the database and runs all pending migrations.
     pub async fn new(db_url: &str) -> anyhow::Result<Self> {
-        info!("Initializing database connection pool");
-
-        let manager = AsyncDieselConnectionManager::new(db_url);
-        let pool = Pool::builder(manager).build()?;
-        let store = Self { pool };
+        let store = Self::new_read_only(db_url).await?;
 
         store.run_migrations().await?;
 
@@ -58,11 +93,28 @@ impl Store {
         Ok(store)
     }
 
+    /// Connects to the database without running migrations or creating the
+    /// master API key, so that nothing is written to it.
+    pub async fn new_read_only(db_url: &str) -> anyhow::Result<Self> {
+        info!("Initializing database connection pool");
+
+        let manager = AsyncDieselConnectionManager::new(db_url);
+        let pool = Pool::builder(manager).build()?;
+
+        Ok(Self {
+            pool,
+            read_replica: None,
+            clock: Arc::new(SystemClock),
+            metrics: None,
+        })
+    }
+
     async fn run_migrations(&self) -> anyhow::Result<()> {
         let mut conn = self.pool.get().await?;
 
         info!("Run database migrations");
 
+        #[allow(clippy::borrow_interior_mutable_const)]
         Self::MIGRATIONS
             .run_pending_migrations(&mut conn)
             .await
@@ -71,7 +123,37 @@ impl Store {
         Ok(())
     }
 
+    /// Replaces the clock used to timestamp new rows, e.g. with a
+    /// [`graphix_common_types::MockClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Records the latency and errors of the most frequent operations, and the
+    /// status of the connection pool, in the given metrics.
+    pub fn with_metrics(mut self, metrics: StoreMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    async fn observe<T>(
+        &self,
+        method: &'static str,
+        operation: impl std::future::Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        match &self.metrics {
+            Some(metrics) => metrics.observe(method, operation).await,
+            None => operation.await,
+        }
+    }
+
     pub async fn conn(&self) -> anyhow::Result<Object<AsyncPgConnection>> {
+        if let Some(metrics) = &self.metrics {
+            let status = self.pool.status();
+            metrics.set_pool_status(status.max_size, status.size, status.available);
+        }
+
         Ok(self.pool.get().await?)
     }
 
@@ -80,6 +162,28 @@ impl Store {
     }
 }
 
+/// The byte strings that start with the given hex digits: from the prefix
+/// padded with zeros (inclusive), until the prefix padded with `f`s and
+/// incremented (exclusive), which is unbounded if it's all `f`s.
+fn hex_prefix_range(hex_prefix: &str) -> anyhow::Result<(Vec<u8>, Option<Vec<u8>>)> {
+    let padded = |digit: char| {
+        let mut hex = hex_prefix.to_string();
+        if hex.len() % 2 == 1 {
+            hex.push(digit);
+        }
+        hex::decode(hex).map_err(|err| anyhow!("invalid hex prefix `{hex_prefix}`: {err}"))
+    };
+    let from = padded('0')?;
+    let mut until = padded('f')?;
+    while let Some(last) = until.pop() {
+        if last < u8::MAX {
+            until.push(last + 1);
+            return Ok((from, Some(until)));
+        }
+    }
+    Ok((from, None))
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PoiLiveness {
     Live,
@@ -88,6 +192,15 @@ pub enum PoiLiveness {
 
 /// Getters.
 impl Store {
+    /// Fails unless the database can be queried.
+    pub async fn check_connection(&self) -> anyhow::Result<()> {
+        diesel::sql_query("SELECT 1")
+            .execute(&mut self.conn().await?)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn current_config(&self) -> anyhow::Result<Option<serde_json::Value>> {
         use schema::configs;
 
@@ -105,46 +218,177 @@ impl Store {
         &self,
         filter: inputs::SgDeploymentsQuery,
     ) -> anyhow::Result<Vec<SgDeployment>> {
-        use schema::sg_deployments as sgd;
+        self.observe("sg_deployments", async {
+            use schema::sg_deployments as sgd;
 
-        let mut query = sgd::table
-            .inner_join(schema::networks::table)
-            .left_join(schema::sg_names::table)
-            .select((
-                sgd::id,
-                sgd::ipfs_cid,
-                schema::sg_names::name.nullable(),
-                sgd::network,
-                sgd::created_at,
-            ))
-            .order_by(sgd::ipfs_cid.asc())
-            .into_boxed();
+            let mut query = sgd::table
+                .inner_join(schema::networks::table)
+                .left_join(schema::sg_names::table)
+                .select((
+                    sgd::id,
+                    sgd::ipfs_cid,
+                    schema::sg_names::name.nullable(),
+                    sgd::network,
+                    sgd::created_at,
+                    sgd::manifest,
+                    sgd::first_seen_at,
+                    sgd::last_seen_at,
+                    sgd::status,
+                    sgd::substreams,
+                ))
+                .order_by(sgd::ipfs_cid.asc())
+                .into_boxed();
 
-        if let Some(network_name) = filter.network_name {
-            query = query.filter(schema::networks::name.eq(network_name));
-        }
-        if let Some(name) = filter.name {
-            query = query.filter(schema::sg_names::name.eq(name));
-        }
-        if let Some(ipfs_cid) = filter.ipfs_cid {
-            query = query.filter(sgd::ipfs_cid.eq(ipfs_cid.to_string()));
-        }
-        if let Some(limit) = filter.limit {
-            query = query.limit(limit.into());
-        }
+            if let Some(network_name) = filter.network_name {
+                query = query.filter(schema::networks::name.eq(network_name));
+            }
+            if let Some(name) = filter.name {
+                query = query.filter(schema::sg_names::name.eq(name));
+            }
+            if let Some(ipfs_cid) = filter.ipfs_cid {
+                query = query.filter(sgd::ipfs_cid.eq(ipfs_cid.to_string()));
+            }
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit.into());
+            }
 
-        Ok(query.load::<SgDeployment>(&mut self.conn().await?).await?)
+            Ok(query.load::<SgDeployment>(&mut self.conn().await?).await?)
+        })
+        .await
     }
 
     /// Fetches a Poi from the database.
     pub async fn poi(&self, poi: &PoiBytes) -> anyhow::Result<Option<Poi>> {
-        use schema::pois;
+        let mut conn = self.conn().await?;
+        diesel_queries::poi_by_value(&mut conn, poi).await
+    }
+
+    /// Fetches all collected PoIs with the given value, newest first, and
+    /// whether each of them is live. Optionally restricted to subgraph
+    /// deployments of a single network.
+    pub async fn pois_by_value(
+        &self,
+        poi: &PoiBytes,
+        network: Option<&str>,
+    ) -> anyhow::Result<Vec<(Poi, bool)>> {
+        self.observe("pois_by_value", async {
+            use schema::{live_pois, networks, poi_values, pois, sg_deployments};
+
+            let mut query = pois::table
+                .inner_join(poi_values::table)
+                .inner_join(sg_deployments::table.inner_join(networks::table))
+                .left_join(live_pois::table)
+                .select((Poi::COLUMNS, live_pois::id.nullable()))
+                .filter(poi_values::poi.eq(poi))
+                .order_by(pois::created_at.desc())
+                .into_boxed();
+            if let Some(network) = network {
+                query = query.filter(networks::name.eq(network));
+            }
+            let rows: Vec<(Poi, Option<IntId>)> = query.load(&mut self.read_conn().await?).await?;
+
+            Ok(rows
+                .into_iter()
+                .map(|(poi, live_poi_id)| (poi, live_poi_id.is_some()))
+                .collect())
+        })
+        .await
+    }
+
+    /// Fetches the collected PoIs whose value starts with the given hex
+    /// digits, newest first, and whether each of them is live. The prefix can
+    /// have an odd number of digits, e.g. if it was truncated in a log.
+    pub async fn pois_by_prefix(
+        &self,
+        hex_prefix: &str,
+        limit: u16,
+    ) -> anyhow::Result<Vec<(Poi, bool)>> {
+        self.observe("pois_by_prefix", async {
+            use schema::{live_pois, poi_values, pois};
+
+            // A range rather than e.g. `LIKE`, so that the index on PoI
+            // values is used.
+            let (from, until) = hex_prefix_range(hex_prefix)?;
+            let mut query = pois::table
+                .inner_join(poi_values::table)
+                .left_join(live_pois::table)
+                .select((Poi::COLUMNS, live_pois::id.nullable()))
+                .filter(poi_values::poi.ge(from))
+                .order_by(pois::created_at.desc())
+                .limit(limit.into())
+                .into_boxed();
+            if let Some(until) = until {
+                query = query.filter(poi_values::poi.lt(until));
+            }
+            let rows: Vec<(Poi, Option<IntId>)> = query.load(&mut self.read_conn().await?).await?;
+
+            Ok(rows
+                .into_iter()
+                .map(|(poi, live_poi_id)| (poi, live_poi_id.is_some()))
+                .collect())
+        })
+        .await
+    }
+
+    /// For each of the given PoIs, fetches up to `limit` other collected PoIs
+    /// with the same value, newest first. Returns them along with the ID of
+    /// the PoI they're identical to.
+    pub async fn identical_pois(
+        &self,
+        poi_ids: &[IntId],
+        limit: u16,
+    ) -> anyhow::Result<Vec<(IntId, Poi)>> {
+        use diesel::sql_types::{Array, BigInt, Integer};
+
+        #[derive(QueryableByName)]
+        struct IdenticalPoi {
+            #[diesel(sql_type = Integer)]
+            poi_id: IntId,
+            #[diesel(sql_type = Integer)]
+            identical_poi_id: IntId,
+        }
+
+        self.observe("identical_pois", async {
+            use schema::{poi_values, pois};
 
-        let query = pois::table
-            .select(pois::all_columns)
-            .filter(pois::poi.eq(poi));
+            let mut conn = self.read_conn().await?;
+            // One index lookup per PoI, rather than loading every identical
+            // PoI of all of them.
+            let identical: Vec<IdenticalPoi> = diesel::sql_query(
+                "SELECT p.id AS poi_id, i.id AS identical_poi_id \
+                FROM pois p \
+                CROSS JOIN LATERAL ( \
+                    SELECT id, created_at FROM pois \
+                    WHERE poi_value_id = p.poi_value_id AND id <> p.id \
+                    ORDER BY created_at DESC \
+                    LIMIT $2 \
+                ) AS i \
+                WHERE p.id = ANY($1) \
+                ORDER BY p.id, i.created_at DESC",
+            )
+            .bind::<Array<Integer>, _>(poi_ids)
+            .bind::<BigInt, _>(i64::from(limit))
+            .load(&mut conn)
+            .await?;
 
-        Ok(query.get_result(&mut self.conn().await?).await.optional()?)
+            let identical_poi_ids: Vec<IntId> =
+                identical.iter().map(|row| row.identical_poi_id).collect();
+            let pois: HashMap<IntId, Poi> = pois::table
+                .inner_join(poi_values::table)
+                .select(Poi::COLUMNS)
+                .filter(pois::id.eq_any(identical_poi_ids))
+                .load::<Poi>(&mut conn)
+                .await?
+                .into_iter()
+                .map(|poi| (poi.id, poi))
+                .collect();
+
+            Ok(identical
+                .into_iter()
+                .filter_map(|row| Some((row.poi_id, pois.get(&row.identical_poi_id)?.clone())))
+                .collect())
+        })
+        .await
     }
 
     pub async fn failed_query(
@@ -161,13 +405,9 @@ impl Store {
         let failed_query = failed_queries::table
             .filter(failed_queries::indexer_id.eq(indexer_id))
             .filter(failed_queries::query_name.eq(query_name))
-            .select((
-                failed_queries::indexer_id,
-                failed_queries::query_name,
-                failed_queries::raw_query,
-                failed_queries::response,
-                failed_queries::request_timestamp,
-            ))
+            .order_by(failed_queries::id.desc())
+            .limit(1)
+            .select(FailedQueryRow::as_select())
             .get_result::<FailedQueryRow>(conn)
             .await
             .optional()?;
@@ -175,200 +415,1714 @@ impl Store {
         Ok(failed_query)
     }
 
-    /// Returns all networks stored in the database. Filtering is not really
-    /// necessary here because the number of networks is expected to be small,
-    /// so filtering can be done client-side.
-    pub async fn networks(&self) -> anyhow::Result<Vec<models::Network>> {
-        use schema::networks;
+    /// Returns the most recent failed queries, optionally only those of one
+    /// indexer or with one query name.
+    pub async fn failed_queries(
+        &self,
+        indexer: Option<&IndexerAddress>,
+        query_name: Option<&str>,
+        limit: u16,
+    ) -> anyhow::Result<Vec<FailedQueryRow>> {
+        use schema::{failed_queries, indexers};
 
-        let mut conn = self.conn().await?;
-        Ok(networks::table
-            .select((networks::id, networks::name, networks::caip2))
-            .load(&mut conn)
+        let mut query = failed_queries::table
+            .inner_join(indexers::table)
+            .select(FailedQueryRow::as_select())
+            .order_by(failed_queries::id.desc())
+            .limit(limit.into())
+            .into_boxed();
+        if let Some(indexer) = indexer {
+            query = query.filter(indexers::address.eq(indexer));
+        }
+        if let Some(query_name) = query_name {
+            query = query.filter(failed_queries::query_name.eq(query_name));
+        }
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    pub async fn failed_query_by_id(&self, id: IntId) -> anyhow::Result<Option<FailedQueryRow>> {
+        use schema::failed_queries;
+
+        Ok(failed_queries::table
+            .select(FailedQueryRow::as_select())
+            .find(id)
+            .get_result(&mut self.conn().await?)
+            .await
+            .optional()?)
+    }
+
+    /// Counts of failed requests to an indexer, by error class, most frequent
+    /// first.
+    pub async fn indexer_errors(
+        &self,
+        indexer_id: IntId,
+    ) -> anyhow::Result<Vec<models::IndexerErrorCount>> {
+        use schema::indexer_errors;
+
+        Ok(indexer_errors::table
+            .filter(indexer_errors::indexer_id.eq(indexer_id))
+            .order_by((indexer_errors::count.desc(), indexer_errors::error_class))
+            .select(models::IndexerErrorCount::as_select())
+            .load(&mut self.conn().await?)
             .await?)
     }
 
-    /// Returns all indexers stored in the database.
-    pub async fn indexers(
+    /// Latency percentiles and success rates of requests to indexers that were
+    /// sampled since `since`, by kind of request. Contains the stats of the
+    /// given indexer (or of each indexer, if none is given), followed by those
+    /// of all indexers together, which have no `indexer_id`.
+    pub async fn indexer_latency_stats(
         &self,
-        filter: inputs::IndexersQuery,
-    ) -> anyhow::Result<Vec<models::Indexer>> {
-        use schema::indexers;
+        indexer_address: Option<&IndexerAddress>,
+        since: chrono::NaiveDateTime,
+    ) -> anyhow::Result<Vec<models::IndexerLatencyStats>> {
+        use diesel::sql_types::{Binary, Nullable, Timestamp};
 
-        let mut query = indexers::table.select(indexers::all_columns).into_boxed();
+        self.observe("indexer_latency_stats", async {
+            let query = diesel::sql_query(
+                "SELECT s.indexer_id, s.probe, \
+                    COUNT(*) AS sample_count, \
+                    AVG(CASE WHEN s.success THEN 1.0 ELSE 0.0 END)::float8 AS success_rate, \
+                    percentile_cont(0.5) WITHIN GROUP (ORDER BY s.latency_ms) \
+                        FILTER (WHERE s.success) AS p50_ms, \
+                    percentile_cont(0.9) WITHIN GROUP (ORDER BY s.latency_ms) \
+                        FILTER (WHERE s.success) AS p90_ms, \
+                    percentile_cont(0.99) WITHIN GROUP (ORDER BY s.latency_ms) \
+                        FILTER (WHERE s.success) AS p99_ms \
+                FROM indexer_latency_samples s \
+                WHERE s.created_at >= $1 \
+                GROUP BY GROUPING SETS ((s.indexer_id, s.probe), (s.probe)) \
+                HAVING GROUPING(s.indexer_id) = 1 \
+                    OR $2 IS NULL \
+                    OR s.indexer_id IN (SELECT id FROM indexers WHERE address = $2) \
+                ORDER BY s.indexer_id NULLS LAST, s.probe",
+            )
+            .bind::<Timestamp, _>(since)
+            .bind::<Nullable<Binary>, _>(indexer_address);
 
-        if let Some(address) = filter.address {
-            query = query.filter(indexers::address.eq(address));
-        }
-        if let Some(limit) = filter.limit {
-            query = query.limit(limit.into());
-        }
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Fetches events from the event log that match `filter`, oldest first.
+    pub async fn events(&self, filter: inputs::EventsQuery) -> anyhow::Result<Vec<models::Event>> {
+        self.observe("events", async {
+            use schema::events;
 
-        Ok(query.load::<IndexerModel>(&mut self.conn().await?).await?)
+            let mut query = events::table
+                .select(models::Event::as_select())
+                .order_by(events::id)
+                .into_boxed();
+
+            if !filter.kinds.is_empty() {
+                query = query.filter(events::kind.eq_any(filter.kinds));
+            }
+            if let Some(deployment) = filter.deployment {
+                query = query.filter(events::deployment.eq(deployment));
+            }
+            if let Some(after_id) = filter.after_id {
+                query = query.filter(events::id.gt(after_id));
+            }
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit.into());
+            }
+
+            Ok(query.load(&mut self.conn().await?).await?)
+        })
+        .await
     }
 
-    /// Queries the database for proofs of indexing that refer to the specified
-    /// subgraph deployments and in the given [`inputs::BlockRange`], if given.
-    pub async fn pois(
+    /// Records a mutation that `api_key` made in the admin audit log.
+    pub async fn write_admin_audit_log_entry(
         &self,
-        sg_deployments: &[IpfsCid],
-        block_range: Option<inputs::BlockRange>,
-        limit: Option<u16>,
-    ) -> anyhow::Result<Vec<Poi>> {
-        let mut conn = self.conn().await?;
-        diesel_queries::pois(
-            &mut conn,
-            None,
-            Some(sg_deployments),
-            block_range,
-            limit,
-            false,
+        api_key: Option<&ApiKey>,
+        mutation: &str,
+        payload: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let mutation = AuditedMutation { api_key, mutation };
+        let now = self.clock.now().naive_utc();
+        diesel_queries::write_admin_audit_log_entry(
+            &mut *self.conn().await?,
+            &mutation,
+            payload,
+            now,
         )
         .await
     }
 
-    /// Like `pois`, but only returns live pois.
-    pub async fn live_pois(
+    /// Returns the admin audit log, most recent entries first, optionally only
+    /// for the given mutation.
+    pub async fn admin_audit_log(
         &self,
-        indexer_address: Option<&IndexerAddress>,
-        sg_deployments_cids: Option<&[IpfsCid]>,
-        block_range: Option<inputs::BlockRange>,
+        mutation: Option<&str>,
         limit: Option<u16>,
-    ) -> anyhow::Result<Vec<Poi>> {
-        let mut conn = self.conn().await?;
-        diesel_queries::pois(
-            &mut conn,
-            indexer_address,
-            sg_deployments_cids,
-            block_range,
-            limit,
-            true,
-        )
-        .await
-    }
+    ) -> anyhow::Result<Vec<models::AdminAuditLogEntry>> {
+        use schema::admin_audit_log;
 
-    pub async fn api_keys(&self) -> anyhow::Result<Vec<ApiKeyPublicMetadata>> {
-        use schema::graphix_api_tokens;
+        let mut query = admin_audit_log::table
+            .select(models::AdminAuditLogEntry::as_select())
+            .order_by(admin_audit_log::id.desc())
+            .into_boxed();
+        if let Some(mutation) = mutation {
+            query = query.filter(admin_audit_log::mutation.eq(mutation));
+        }
+        if let Some(limit) = limit {
+            query = query.limit(limit.into());
+        }
 
-        Ok(graphix_api_tokens::table
-            .load::<ApiKeyDbRow>(&mut self.conn().await?)
-            .await?
-            .into_iter()
-            .map(ApiKeyPublicMetadata::from)
-            .collect())
+        Ok(query.load(&mut self.conn().await?).await?)
     }
 
-    pub async fn permission_level(
-        &self,
-        api_key: &ApiKey,
-    ) -> anyhow::Result<Option<ApiKeyPermissionLevel>> {
-        use schema::graphix_api_tokens;
+    /// The ID of the most recent event in the event log, if any.
+    pub async fn latest_event_id(&self) -> anyhow::Result<Option<BigIntId>> {
+        use schema::events;
 
-        Ok(graphix_api_tokens::table
-            .select(graphix_api_tokens::permission_level)
-            .filter(graphix_api_tokens::sha256_api_key_hash.eq(api_key.hash()))
+        Ok(events::table
+            .select(diesel::dsl::max(events::id))
             .get_result(&mut self.conn().await?)
-            .await
-            .optional()?)
+            .await?)
     }
 
-    pub async fn get_first_pending_divergence_investigation_request(
-        &self,
-    ) -> anyhow::Result<Option<(Uuid, serde_json::Value)>> {
-        use schema::pending_divergence_investigation_requests as requests;
+    /// The ID of the last event that `consumer` processed, if it ever did.
+    pub async fn event_cursor(&self, consumer: &str) -> anyhow::Result<Option<BigIntId>> {
+        use schema::event_cursors;
 
-        Ok(requests::table
-            .select((requests::uuid, requests::request))
-            .first::<(Uuid, serde_json::Value)>(&mut self.conn().await?)
+        Ok(event_cursors::table
+            .select(event_cursors::last_event_id)
+            .filter(event_cursors::consumer.eq(consumer))
+            .get_result(&mut self.conn().await?)
             .await
             .optional()?)
     }
 
-    /// Fetches the divergence investigation report with the given UUID, if it
-    /// exists.
-    pub async fn divergence_investigation_report(
-        &self,
-        uuid: &Uuid,
-    ) -> anyhow::Result<Option<serde_json::Value>> {
-        use schema::divergence_investigation_reports as reports;
+    /// Returns all networks stored in the database. Filtering is not really
+    /// necessary here because the number of networks is expected to be small,
+    /// so filtering can be done client-side.
+    pub async fn networks(&self) -> anyhow::Result<Vec<models::Network>> {
+        use schema::networks;
 
-        Ok(reports::table
-            .select(reports::report)
-            .filter(reports::uuid.eq(uuid))
-            .first(&mut self.conn().await?)
-            .await
-            .optional()?)
+        let mut conn = self.conn().await?;
+        Ok(networks::table
+            .select((
+                networks::id,
+                networks::name,
+                networks::caip2,
+                networks::enabled,
+            ))
+            .load(&mut conn)
+            .await?)
     }
 
-    pub async fn divergence_investigation_request_exists(
-        &self,
-        uuid: &Uuid,
-    ) -> anyhow::Result<bool> {
-        use schema::pending_divergence_investigation_requests as requests;
+    /// Returns the names of all networks that were disabled via
+    /// [`Store::set_network_enabled`].
+    pub async fn disabled_networks(&self) -> anyhow::Result<Vec<String>> {
+        use schema::networks;
 
-        let exists = requests::table
-            .filter(requests::uuid.eq(uuid))
-            .count()
-            .get_result::<i64>(&mut self.conn().await?)
-            .await?
-            > 0;
-        Ok(exists)
+        Ok(networks::table
+            .select(networks::name)
+            .filter(networks::enabled.eq(false))
+            .load(&mut self.conn().await?)
+            .await?)
     }
-}
 
-/// Setters and write operations.
-impl Store {
-    pub async fn overwrite_config(&self, config: serde_json::Value) -> anyhow::Result<()> {
-        use schema::configs;
+    /// Returns the latest indexing statuses for the given subgraph deployments
+    /// (or all of them, if empty), optionally restricted to a single indexer.
+    pub async fn indexing_statuses(
+        &self,
+        sg_deployments: &[IpfsCid],
+        indexer_address: Option<&IndexerAddress>,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<models::IndexingStatus>> {
+        self.observe("indexing_statuses", async {
+            use schema::{indexers, indexing_statuses, sg_deployments};
 
-        diesel::update(configs::table)
-            .set(configs::config.eq(config))
-            .execute(&mut self.conn().await?)
-            .await?;
+            let mut query = indexing_statuses::table
+                .inner_join(indexers::table)
+                .inner_join(sg_deployments::table)
+                .select(models::IndexingStatus::as_select())
+                .order_by(indexing_statuses::updated_at.desc())
+                .into_boxed();
 
-        Ok(())
+            if !sg_deployments.is_empty() {
+                query = query.filter(sg_deployments::ipfs_cid.eq_any(sg_deployments));
+            }
+            if let Some(address) = indexer_address {
+                query = query.filter(indexers::address.eq(address));
+            }
+            if let Some(limit) = limit {
+                query = query.limit(limit.into());
+            }
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
     }
 
-    async fn create_master_api_key(&self) -> anyhow::Result<()> {
-        let api_key = self
-            .create_api_key(None, ApiKeyPermissionLevel::Admin)
-            .await?;
+    /// Returns the changes of the indexing statuses of an indexer, of a
+    /// subgraph deployment, or of both, most recent first.
+    pub async fn indexing_status_history(
+        &self,
+        indexer_id: Option<IntId>,
+        sg_deployment_id: Option<IntId>,
+        limit: u16,
+    ) -> anyhow::Result<Vec<models::IndexingStatusHistoryEntry>> {
+        self.observe("indexing_status_history", async {
+            use schema::indexing_status_history;
 
-        let description = format!("Master API key created during database initialization. Use it to create a new private API key and then delete it for security reasons. `{}`", api_key.api_key);
-        self.modify_api_key(
-            &api_key.api_key,
-            Some(&description),
-            ApiKeyPermissionLevel::Admin,
-        )
-        .await?;
+            let mut query = indexing_status_history::table
+                .select(models::IndexingStatusHistoryEntry::as_select())
+                .order_by((
+                    indexing_status_history::created_at.desc(),
+                    indexing_status_history::id.desc(),
+                ))
+                .limit(limit.into())
+                .into_boxed();
 
-        info!(api_key = ?api_key.api_key, "Created master API key");
+            if let Some(indexer_id) = indexer_id {
+                query = query.filter(indexing_status_history::indexer_id.eq(indexer_id));
+            }
+            if let Some(sg_deployment_id) = sg_deployment_id {
+                query =
+                    query.filter(indexing_status_history::sg_deployment_id.eq(sg_deployment_id));
+            }
 
-        Ok(())
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
     }
 
-    pub async fn create_networks_if_missing(&self, networks: &[NewNetwork]) -> anyhow::Result<()> {
-        use schema::networks;
+    /// Returns all changes of the indexing statuses of the given subgraph
+    /// deployments (or of all of them, if empty), oldest first.
+    pub async fn indexing_status_history_of_deployments(
+        &self,
+        sg_deployment_ids: &[IntId],
+    ) -> anyhow::Result<Vec<models::IndexingStatusHistoryEntry>> {
+        self.observe("indexing_status_history_of_deployments", async {
+            use schema::indexing_status_history;
 
-        let mut conn = self.conn().await?;
+            let mut query = indexing_status_history::table
+                .select(models::IndexingStatusHistoryEntry::as_select())
+                .order_by((
+                    indexing_status_history::created_at,
+                    indexing_status_history::id,
+                ))
+                .into_boxed();
+            if !sg_deployment_ids.is_empty() {
+                query = query
+                    .filter(indexing_status_history::sg_deployment_id.eq_any(sg_deployment_ids));
+            }
 
-        // batch insert
-        diesel::insert_into(networks::table)
-            .values(networks)
-            .on_conflict_do_nothing()
-            .execute(&mut conn)
-            .await?;
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
 
-        Ok(())
+    /// Returns the highest latest block that any indexer reported in its
+    /// indexing statuses for each network, by network name.
+    pub async fn reported_chain_heads(&self) -> anyhow::Result<Vec<(String, i64)>> {
+        self.observe("reported_chain_heads", async {
+            use diesel::dsl::max;
+            use schema::{indexing_statuses, networks, sg_deployments};
+
+            let heads: Vec<(String, Option<i64>)> = indexing_statuses::table
+                .inner_join(sg_deployments::table.inner_join(networks::table))
+                .group_by(networks::name)
+                .select((networks::name, max(indexing_statuses::latest_block_number)))
+                .load(&mut self.conn().await?)
+                .await?;
+
+            Ok(heads
+                .into_iter()
+                .filter_map(|(network, head)| Some((network, head?)))
+                .collect())
+        })
+        .await
     }
 
-    pub async fn create_sg_deployment(
+    /// Returns how far the latest indexing statuses of an indexer, or of all
+    /// indexers, are behind the heads of their chains, most blocks behind
+    /// first, up to `limit` of them. Statuses without a block number, or of
+    /// networks without a stored chain head, are left out.
+    pub async fn indexing_lags(
         &self,
-        network_name: &str,
-        ipfs_cid: &str,
-    ) -> anyhow::Result<()> {
-        use schema::sg_deployments as sgd;
-
+        indexer_address: Option<&IndexerAddress>,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<models::IndexingLag>> {
+        use diesel::sql_types::{BigInt, Binary, Nullable};
+
+        self.observe("indexing_lags", async {
+            let query = diesel::sql_query(
+                "SELECT s.indexer_id, i.address AS indexer_address, s.sg_deployment_id, \
+                    n.name AS network, s.health, s.latest_block_number, \
+                    h.block_number AS chain_head_block_number, s.updated_at \
+                FROM indexing_statuses s \
+                JOIN indexers i ON i.id = s.indexer_id \
+                JOIN sg_deployments d ON d.id = s.sg_deployment_id \
+                JOIN networks n ON n.id = d.network \
+                JOIN chain_heads h ON h.network_id = n.id \
+                WHERE s.latest_block_number IS NOT NULL \
+                    AND ($1::bytea IS NULL OR i.address = $1) \
+                ORDER BY h.block_number - s.latest_block_number DESC, s.id \
+                LIMIT $2",
+            )
+            .bind::<Nullable<Binary>, _>(indexer_address)
+            .bind::<Nullable<BigInt>, _>(limit.map(i64::from));
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Returns the errors that indexers reported while indexing subgraph
+    /// deployments, most recently seen first.
+    pub async fn indexing_errors(
+        &self,
+        sg_deployments: &[IpfsCid],
+        indexer_address: Option<&IndexerAddress>,
+        fatal: Option<bool>,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<models::IndexingError>> {
+        self.observe("indexing_errors", async {
+            use schema::{indexers, indexing_errors, sg_deployments};
+
+            let mut query = indexing_errors::table
+                .inner_join(indexers::table)
+                .inner_join(sg_deployments::table)
+                .select(models::IndexingError::as_select())
+                .order_by((
+                    indexing_errors::last_seen_at.desc(),
+                    indexing_errors::id.desc(),
+                ))
+                .into_boxed();
+
+            if !sg_deployments.is_empty() {
+                query = query.filter(sg_deployments::ipfs_cid.eq_any(sg_deployments));
+            }
+            if let Some(address) = indexer_address {
+                query = query.filter(indexers::address.eq(address));
+            }
+            if let Some(fatal) = fatal {
+                query = query.filter(indexing_errors::fatal.eq(fatal));
+            }
+            if let Some(limit) = limit {
+                query = query.limit(limit.into());
+            }
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Returns the changes in how indexers reported subgraph deployments in
+    /// their indexing statuses, most recent first.
+    pub async fn sg_deployment_events(
+        &self,
+        sg_deployments: &[IpfsCid],
+        indexer_address: Option<&IndexerAddress>,
+        kind: Option<DeploymentEventKind>,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<models::SgDeploymentEvent>> {
+        self.observe("sg_deployment_events", async {
+            use schema::{indexers, sg_deployment_events, sg_deployments};
+
+            let mut query = sg_deployment_events::table
+                .inner_join(indexers::table)
+                .inner_join(sg_deployments::table)
+                .select(models::SgDeploymentEvent::as_select())
+                .order_by((
+                    sg_deployment_events::created_at.desc(),
+                    sg_deployment_events::id.desc(),
+                ))
+                .into_boxed();
+
+            if !sg_deployments.is_empty() {
+                query = query.filter(sg_deployments::ipfs_cid.eq_any(sg_deployments));
+            }
+            if let Some(address) = indexer_address {
+                query = query.filter(indexers::address.eq(address));
+            }
+            if let Some(kind) = kind {
+                query = query.filter(sg_deployment_events::kind.eq(kind));
+            }
+            if let Some(limit) = limit {
+                query = query.limit(limit.into());
+            }
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Returns on-chain PoIs, most recent first, each paired with the PoI that
+    /// Graphix collected from the same indexer for the same subgraph
+    /// deployment and block, on the deployment's network, if any. With
+    /// `mismatches_only`, only on-chain PoIs that differ from the collected
+    /// one are returned, leaving out expected null PoIs of substreams-powered
+    /// deployments and blocks with block hash conflicts.
+    pub async fn onchain_poi_cross_checks(
+        &self,
+        sg_deployments: &[IpfsCid],
+        indexer_address: Option<&IndexerAddress>,
+        network: Option<&str>,
+        mismatches_only: bool,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<(models::OnchainPoi, Option<PoiBytes>)>> {
+        use schema::{blocks, indexers, networks, onchain_pois, poi_values, pois, sg_deployments};
+
+        let mut conn = self.read_conn().await?;
+
+        let mut query = onchain_pois::table
+            .select(models::OnchainPoi::as_select())
+            .order_by(onchain_pois::block_number.desc())
+            .into_boxed();
+        if !sg_deployments.is_empty() {
+            query = query.filter(onchain_pois::deployment.eq_any(sg_deployments));
+        }
+        if let Some(address) = indexer_address {
+            query = query.filter(onchain_pois::indexer_address.eq(address));
+        }
+        if let Some(network) = network {
+            let deployments_on_network = sg_deployments::table
+                .inner_join(networks::table)
+                .filter(networks::name.eq(network.to_string()))
+                .select(sg_deployments::ipfs_cid);
+            query = query.filter(onchain_pois::deployment.eq_any(deployments_on_network));
+        }
+        if mismatches_only {
+            query = query.filter(diesel::dsl::sql::<diesel::sql_types::Bool>(
+                "EXISTS ( \
+                    SELECT 1 FROM pois \
+                    JOIN indexers ON indexers.id = pois.indexer_id \
+                    JOIN sg_deployments ON sg_deployments.id = pois.sg_deployment_id \
+                    JOIN blocks ON blocks.id = pois.block_id \
+                        AND blocks.network_id = sg_deployments.network \
+                    JOIN poi_values ON poi_values.id = pois.poi_value_id \
+                    WHERE indexers.address = onchain_pois.indexer_address \
+                        AND sg_deployments.ipfs_cid = onchain_pois.deployment \
+                        AND blocks.number = onchain_pois.block_number \
+                        AND poi_values.poi <> onchain_pois.poi \
+                        AND NOT ( \
+                            COALESCE(sg_deployments.substreams, false) \
+                            AND decode(repeat('00', 32), 'hex') \
+                                IN (poi_values.poi, onchain_pois.poi) \
+                        ) \
+                        AND NOT EXISTS ( \
+                            SELECT 1 FROM block_hash_conflicts \
+                            WHERE block_hash_conflicts.network_id = sg_deployments.network \
+                                AND block_hash_conflicts.block_number = onchain_pois.block_number \
+                        ) \
+                )",
+            ));
+        }
+        if let Some(limit) = limit {
+            query = query.limit(limit.into());
+        }
+        let onchain_pois: Vec<models::OnchainPoi> = query.load(&mut conn).await?;
+
+        let deployments: Vec<&IpfsCid> = onchain_pois.iter().map(|poi| &poi.deployment).collect();
+        let block_numbers: Vec<i64> = onchain_pois.iter().map(|poi| poi.block_number).collect();
+        let collected_pois: Vec<(IndexerAddress, IpfsCid, i64, PoiBytes)> = pois::table
+            .inner_join(indexers::table)
+            .inner_join(sg_deployments::table)
+            .inner_join(blocks::table)
+            .inner_join(poi_values::table)
+            .select((
+                indexers::address,
+                sg_deployments::ipfs_cid,
+                blocks::number,
+                poi_values::poi,
+            ))
+            .filter(sg_deployments::ipfs_cid.eq_any(deployments))
+            .filter(blocks::number.eq_any(block_numbers))
+            .filter(blocks::network_id.eq(sg_deployments::network))
+            .load(&mut conn)
+            .await?;
+
+        let collected_pois: HashMap<_, _> = collected_pois
+            .into_iter()
+            .map(|(address, deployment, block_number, poi)| {
+                ((address, deployment, block_number), poi)
+            })
+            .collect();
+
+        Ok(onchain_pois
+            .into_iter()
+            .map(|onchain_poi| {
+                let key = (
+                    onchain_poi.indexer_address,
+                    onchain_poi.deployment.clone(),
+                    onchain_poi.block_number,
+                );
+                let collected_poi = collected_pois.get(&key).copied();
+                (onchain_poi, collected_poi)
+            })
+            .collect())
+    }
+
+    /// Returns the block numbers for which indexers reported different block
+    /// hashes, most recently detected first, with the indexers whose PoIs
+    /// refer to each of the hashes.
+    pub async fn block_hash_conflicts(
+        &self,
+        network: Option<&str>,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<models::BlockHashConflict>> {
+        use schema::{block_hash_conflicts as bhc, blocks, indexers, networks, pois};
+
+        let mut conn = self.read_conn().await?;
+
+        let mut query = bhc::table
+            .inner_join(networks::table)
+            .select((
+                bhc::id,
+                bhc::network_id,
+                networks::name,
+                bhc::block_number,
+                bhc::detected_at,
+            ))
+            .order_by((bhc::detected_at.desc(), bhc::id.desc()))
+            .into_boxed();
+        if let Some(network) = network {
+            query = query.filter(networks::name.eq(network));
+        }
+        if let Some(limit) = limit {
+            query = query.limit(limit.into());
+        }
+        let conflicts: Vec<(IntId, IntId, String, i64, chrono::NaiveDateTime)> =
+            query.load(&mut conn).await?;
+
+        let network_ids: Vec<IntId> = conflicts.iter().map(|c| c.1).collect();
+        let block_numbers: Vec<i64> = conflicts.iter().map(|c| c.3).collect();
+        let blocks: Vec<models::Block> = blocks::table
+            .select(blocks::all_columns)
+            .filter(blocks::network_id.eq_any(network_ids))
+            .filter(blocks::number.eq_any(block_numbers))
+            .order_by(blocks::id)
+            .load(&mut conn)
+            .await?;
+        let block_ids: Vec<BigIntId> = blocks.iter().map(|block| block.id).collect();
+        let reporting_indexers: Vec<(BigIntId, IndexerAddress)> = pois::table
+            .inner_join(indexers::table)
+            .select((pois::block_id, indexers::address))
+            .filter(pois::block_id.eq_any(block_ids))
+            .distinct()
+            .order_by((pois::block_id, indexers::address))
+            .load(&mut conn)
+            .await?;
+
+        Ok(conflicts
+            .into_iter()
+            .map(|(id, network_id, network, block_number, detected_at)| {
+                let hashes = blocks
+                    .iter()
+                    .filter(|block| block.network_id == network_id && block.number == block_number)
+                    .map(|block| {
+                        let indexers = reporting_indexers
+                            .iter()
+                            .filter(|(block_id, _)| *block_id == block.id)
+                            .map(|(_, address)| *address)
+                            .collect();
+                        (block.hash.clone(), indexers)
+                    })
+                    .collect();
+                models::BlockHashConflict {
+                    id,
+                    network,
+                    block_number,
+                    detected_at,
+                    hashes,
+                }
+            })
+            .collect())
+    }
+
+    /// The block numbers with block hash conflicts on the networks of the
+    /// given subgraph deployments, by IPFS CID. PoIs at those blocks might
+    /// differ because of a chain reorg rather than a divergence.
+    pub async fn block_hash_conflicts_of_deployments(
+        &self,
+        sg_deployments: &[IpfsCid],
+    ) -> anyhow::Result<HashSet<(IpfsCid, i64)>> {
+        use schema::{block_hash_conflicts as bhc, sg_deployments as sgd};
+
+        Ok(bhc::table
+            .inner_join(sgd::table.on(sgd::network.eq(bhc::network_id)))
+            .select((sgd::ipfs_cid, bhc::block_number))
+            .filter(sgd::ipfs_cid.eq_any(sg_deployments))
+            .load::<(IpfsCid, i64)>(&mut self.read_conn().await?)
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// Returns all indexers stored in the database, except archived ones
+    /// unless [`inputs::IndexersQuery::include_archived`] is set.
+    pub async fn indexers(
+        &self,
+        filter: inputs::IndexersQuery,
+    ) -> anyhow::Result<Vec<models::Indexer>> {
+        self.observe("indexers", async {
+            use schema::indexers;
+
+            let mut query = indexers::table.select(indexers::all_columns).into_boxed();
+
+            if let Some(address) = filter.address {
+                query = query.filter(indexers::address.eq(address));
+            }
+            if let Some(tag) = filter.tag {
+                query = query.filter(indexers::tags.contains(vec![tag]));
+            }
+            if !filter.include_archived {
+                query = query.filter(indexers::archived_at.is_null());
+            }
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit.into());
+            }
+
+            Ok(query.load::<IndexerModel>(&mut self.conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Queries the database for proofs of indexing that refer to the specified
+    /// subgraph deployments and in the given [`inputs::BlockRange`] and
+    /// protocol epoch, if given. With `indexer_tag`, only PoIs of indexers with
+    /// that tag are returned.
+    pub async fn pois(
+        &self,
+        network: Option<&str>,
+        sg_deployments: &[IpfsCid],
+        block_range: Option<inputs::BlockRange>,
+        epoch: Option<u64>,
+        indexer_tag: Option<&str>,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<Poi>> {
+        self.observe("pois", async {
+            let mut conn = self.read_conn().await?;
+            diesel_queries::pois(
+                &mut conn,
+                None,
+                indexer_tag,
+                network,
+                Some(sg_deployments),
+                block_range,
+                epoch,
+                limit,
+                false,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Fetches a page of up to `page_size` PoIs that match `filter`, with IDs
+    /// greater than `after_id`, ordered by ID. [`inputs::PoisQuery::limit`] is
+    /// ignored, callers are responsible for it.
+    pub async fn poi_export_page(
+        &self,
+        filter: &inputs::PoisQuery,
+        after_id: Option<IntId>,
+        page_size: u16,
+    ) -> anyhow::Result<Vec<PoiExportRow>> {
+        self.observe("poi_export_page", async {
+            use schema::{blocks, indexers, networks, poi_values, pois, sg_deployments};
+
+            let mut query = pois::table
+                .inner_join(sg_deployments::table.inner_join(networks::table))
+                .inner_join(blocks::table)
+                .inner_join(indexers::table)
+                .inner_join(poi_values::table)
+                .select((
+                    pois::id,
+                    sg_deployments::ipfs_cid,
+                    networks::name,
+                    blocks::number,
+                    blocks::hash,
+                    indexers::address,
+                    poi_values::poi,
+                    pois::created_at,
+                    pois::epoch,
+                    blocks::timestamp,
+                ))
+                .order_by(pois::id)
+                .limit(page_size.into())
+                .into_boxed();
+
+            if let Some(after_id) = after_id {
+                query = query.filter(pois::id.gt(after_id));
+            }
+            if let Some(network) = &filter.network {
+                query = query.filter(networks::name.eq(network));
+            }
+            if !filter.deployments.is_empty() {
+                query = query.filter(sg_deployments::ipfs_cid.eq_any(&filter.deployments));
+            }
+            if let Some(block_range) = &filter.block_range {
+                if let Some(start) = block_range.start {
+                    query = query.filter(blocks::number.ge(i64::try_from(start)?));
+                }
+                if let Some(end) = block_range.end {
+                    query = query.filter(blocks::number.le(i64::try_from(end)?));
+                }
+            }
+            if let Some(epoch) = filter.epoch {
+                query = query.filter(pois::epoch.eq(i64::try_from(epoch)?));
+            }
+            if let Some(tag) = &filter.indexer_tag {
+                query = query.filter(indexers::tags.contains(vec![tag]));
+            }
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Streams the PoIs that match `filter`, in pages of up to `page_size`
+    /// rows, for exports that can span millions of rows.
+    pub fn poi_export_stream(
+        &self,
+        filter: inputs::PoisQuery,
+        page_size: u16,
+    ) -> impl futures::Stream<Item = anyhow::Result<Vec<PoiExportRow>>> {
+        let store = self.clone();
+        let limit = filter.limit.map(usize::from);
+        let filter = Arc::new(filter);
+
+        keyset::keyset_pages(
+            page_size,
+            limit,
+            |row: &PoiExportRow| row.id,
+            move |after_id, page_size| {
+                let store = store.clone();
+                let filter = filter.clone();
+                async move { store.poi_export_page(&filter, after_id, page_size).await }
+            },
+        )
+    }
+
+    /// Like `pois`, but only returns live pois.
+    pub async fn live_pois(
+        &self,
+        indexer_address: Option<&IndexerAddress>,
+        network: Option<&str>,
+        sg_deployments_cids: Option<&[IpfsCid]>,
+        block_range: Option<inputs::BlockRange>,
+        indexer_tag: Option<&str>,
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<Poi>> {
+        self.observe("live_pois", async {
+            let mut conn = self.read_conn().await?;
+            diesel_queries::pois(
+                &mut conn,
+                indexer_address,
+                indexer_tag,
+                network,
+                sg_deployments_cids,
+                block_range,
+                None,
+                limit,
+                true,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// The most recently stored PoI of an indexer for a subgraph deployment on
+    /// `network` at `block_number`, along with the hash of its block. The PoI
+    /// of a block doesn't change, so there's no need to ask the indexer again.
+    pub async fn stored_poi(
+        &self,
+        indexer_address: &IndexerAddress,
+        deployment: &IpfsCid,
+        network: &str,
+        block_number: u64,
+    ) -> anyhow::Result<Option<(Poi, BlockHash)>> {
+        use schema::{blocks, indexers, networks, poi_values, pois, sg_deployments as sgd};
+
+        self.observe("stored_poi", async {
+            let query = pois::table
+                .inner_join(sgd::table.inner_join(networks::table))
+                .inner_join(indexers::table)
+                .inner_join(blocks::table)
+                .inner_join(poi_values::table)
+                .select((Poi::COLUMNS, blocks::hash))
+                .filter(sgd::ipfs_cid.eq(deployment))
+                .filter(networks::name.eq(network))
+                .filter(indexers::address.eq(indexer_address))
+                .filter(blocks::number.eq(i64::try_from(block_number)?))
+                .order_by(pois::id.desc());
+
+            Ok(query.first(&mut self.read_conn().await?).await.optional()?)
+        })
+        .await
+    }
+
+    /// Pairs the PoIs of two indexers for a subgraph deployment at every block
+    /// at which both have one, stored or live, newest block first. Of several
+    /// PoIs of an indexer at the same block, the most recently collected one
+    /// is used.
+    pub async fn poi_cross_checks(
+        &self,
+        indexer1: &IndexerAddress,
+        indexer2: &IndexerAddress,
+        deployment: &IpfsCid,
+        limit: u16,
+    ) -> anyhow::Result<Vec<models::PoiCrossCheckRow>> {
+        use diesel::sql_types::{BigInt, Binary, Text};
+
+        self.observe("poi_cross_checks", async {
+            let query = diesel::sql_query(
+                "SELECT network, block_number, block_hash, poi1, poi2, collected_at FROM ( \
+                    SELECT DISTINCT ON (b.id) \
+                        n.name AS network, b.number AS block_number, b.hash AS block_hash, \
+                        v1.poi AS poi1, v2.poi AS poi2, \
+                        GREATEST(p1.created_at, p2.created_at) AS collected_at \
+                    FROM pois p1 \
+                    JOIN pois p2 \
+                        ON p2.block_id = p1.block_id AND p2.sg_deployment_id = p1.sg_deployment_id \
+                    JOIN indexers i1 ON i1.id = p1.indexer_id \
+                    JOIN indexers i2 ON i2.id = p2.indexer_id \
+                    JOIN sg_deployments d ON d.id = p1.sg_deployment_id \
+                    JOIN networks n ON n.id = d.network \
+                    JOIN blocks b ON b.id = p1.block_id \
+                    JOIN poi_values v1 ON v1.id = p1.poi_value_id \
+                    JOIN poi_values v2 ON v2.id = p2.poi_value_id \
+                    WHERE i1.address = $1 AND i2.address = $2 AND d.ipfs_cid = $3 \
+                    ORDER BY b.id, p1.id DESC, p2.id DESC \
+                ) AS cross_checks \
+                ORDER BY block_number DESC, network \
+                LIMIT $4",
+            )
+            .bind::<Binary, _>(indexer1)
+            .bind::<Binary, _>(indexer2)
+            .bind::<Text, _>(deployment)
+            .bind::<BigInt, _>(i64::from(limit));
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// For each of the given cross-checked PoI pairs of two indexers, by
+    /// network, the block at which the latest successful bisection run of the
+    /// two PoIs found them to diverge, with the indexers' most recently
+    /// collected PoIs at that block. Pairs that weren't bisected, or whose
+    /// PoIs at the diverging block weren't stored, are left out.
+    pub async fn poi_cross_check_divergences(
+        &self,
+        indexer1: &IndexerAddress,
+        indexer2: &IndexerAddress,
+        deployment: &IpfsCid,
+        pairs: &[(String, PoiBytes, PoiBytes)],
+    ) -> anyhow::Result<Vec<models::PoiCrossCheckDivergenceRow>> {
+        use diesel::sql_types::{Array, Binary, Text};
+
+        self.observe("poi_cross_check_divergences", async {
+            let unique_pairs: HashSet<_> = pairs.iter().collect();
+            let mut networks = vec![];
+            let mut pois1 = vec![];
+            let mut pois2 = vec![];
+            for (network, poi1, poi2) in unique_pairs {
+                networks.push(network.clone());
+                pois1.push(*poi1);
+                pois2.push(*poi2);
+            }
+
+            let query = diesel::sql_query(
+                "SELECT q.network, q.poi1, q.poi2, r.divergence_upper_bound AS block_number, \
+                    s1.block_hash, s1.poi AS diverging_poi1, s2.poi AS diverging_poi2 \
+                FROM unnest($4::text[], $5::bytea[], $6::bytea[]) AS q(network, poi1, poi2) \
+                JOIN LATERAL ( \
+                    SELECT r.divergence_upper_bound FROM bisection_runs r \
+                    JOIN sg_deployments d ON d.id = r.sg_deployment_id \
+                    WHERE d.ipfs_cid = $3 AND r.error IS NULL \
+                        AND ((r.poi1 = q.poi1 AND r.poi2 = q.poi2) \
+                            OR (r.poi1 = q.poi2 AND r.poi2 = q.poi1)) \
+                    ORDER BY r.id DESC \
+                    LIMIT 1 \
+                ) r ON true \
+                JOIN LATERAL ( \
+                    SELECT v.poi, b.hash AS block_hash FROM pois p \
+                    JOIN sg_deployments d ON d.id = p.sg_deployment_id \
+                    JOIN networks n ON n.id = d.network \
+                    JOIN indexers i ON i.id = p.indexer_id \
+                    JOIN blocks b ON b.id = p.block_id \
+                    JOIN poi_values v ON v.id = p.poi_value_id \
+                    WHERE d.ipfs_cid = $3 AND n.name = q.network AND i.address = $1 \
+                        AND b.number = r.divergence_upper_bound \
+                    ORDER BY p.id DESC \
+                    LIMIT 1 \
+                ) s1 ON true \
+                JOIN LATERAL ( \
+                    SELECT v.poi FROM pois p \
+                    JOIN sg_deployments d ON d.id = p.sg_deployment_id \
+                    JOIN networks n ON n.id = d.network \
+                    JOIN indexers i ON i.id = p.indexer_id \
+                    JOIN blocks b ON b.id = p.block_id \
+                    JOIN poi_values v ON v.id = p.poi_value_id \
+                    WHERE d.ipfs_cid = $3 AND n.name = q.network AND i.address = $2 \
+                        AND b.number = r.divergence_upper_bound \
+                    ORDER BY p.id DESC \
+                    LIMIT 1 \
+                ) s2 ON true",
+            )
+            .bind::<Binary, _>(indexer1)
+            .bind::<Binary, _>(indexer2)
+            .bind::<Text, _>(deployment)
+            .bind::<Array<Text>, _>(networks)
+            .bind::<Array<Binary>, _>(pois1)
+            .bind::<Array<Binary>, _>(pois2);
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Groups the live PoIs of the given subgraph deployments by value, which
+    /// is all that's needed to tell which indexers agree with each other.
+    pub async fn live_poi_groups(
+        &self,
+        sg_deployment_ids: &[IntId],
+    ) -> anyhow::Result<Vec<models::LivePoiGroup>> {
+        self.observe("live_poi_groups", async {
+            let mut conn = self.read_conn().await?;
+            diesel_queries::live_poi_groups(&mut conn, sg_deployment_ids).await
+        })
+        .await
+    }
+
+    pub async fn api_keys(&self) -> anyhow::Result<Vec<ApiKeyPublicMetadata>> {
+        use schema::graphix_api_tokens;
+
+        Ok(graphix_api_tokens::table
+            .load::<ApiKeyDbRow>(&mut self.conn().await?)
+            .await?
+            .into_iter()
+            .map(ApiKeyPublicMetadata::from)
+            .collect())
+    }
+
+    /// Fetches the metadata of an API key, if it exists.
+    pub async fn api_key_metadata(
+        &self,
+        api_key: &ApiKey,
+    ) -> anyhow::Result<Option<ApiKeyPublicMetadata>> {
+        use schema::graphix_api_tokens;
+
+        let row: Option<ApiKeyDbRow> = graphix_api_tokens::table
+            .filter(graphix_api_tokens::sha256_api_key_hash.eq(api_key.hash()))
+            .get_result(&mut self.conn().await?)
+            .await
+            .optional()?;
+
+        Ok(row.map(Into::into))
+    }
+
+    pub async fn permission_level(
+        &self,
+        api_key: &ApiKey,
+    ) -> anyhow::Result<Option<ApiKeyPermissionLevel>> {
+        use schema::graphix_api_tokens;
+
+        Ok(graphix_api_tokens::table
+            .select(graphix_api_tokens::permission_level)
+            .filter(graphix_api_tokens::sha256_api_key_hash.eq(api_key.hash()))
+            .get_result(&mut self.conn().await?)
+            .await
+            .optional()?)
+    }
+
+    /// Returns the public prefixes of all API keys of the same tenant as
+    /// `api_key`, i.e. of the same organization. API keys without an
+    /// organization are tenants of their own.
+    async fn tenant_api_key_prefixes(&self, api_key: &ApiKey) -> anyhow::Result<Vec<String>> {
+        use schema::graphix_api_tokens;
+
+        let mut conn = self.conn().await?;
+        let organization: Option<String> = graphix_api_tokens::table
+            .select(graphix_api_tokens::organization)
+            .filter(graphix_api_tokens::sha256_api_key_hash.eq(api_key.hash()))
+            .get_result(&mut conn)
+            .await
+            .optional()?
+            .flatten();
+
+        match organization {
+            Some(organization) => Ok(graphix_api_tokens::table
+                .select(graphix_api_tokens::public_prefix)
+                .filter(graphix_api_tokens::organization.eq(organization))
+                .load(&mut conn)
+                .await?),
+            None => Ok(vec![api_key.public_part_as_string()]),
+        }
+    }
+
+    /// Fetches the webhooks registered by the tenant of the given API key, or
+    /// all of them if `None`.
+    pub async fn webhooks(&self, api_key: Option<&ApiKey>) -> anyhow::Result<Vec<Webhook>> {
+        use schema::webhooks;
+
+        let mut query = webhooks::table
+            .select(Webhook::as_select())
+            .order_by(webhooks::id)
+            .into_boxed();
+        if let Some(api_key) = api_key {
+            let prefixes = self.tenant_api_key_prefixes(api_key).await?;
+            query = query.filter(webhooks::api_key_public_prefix.eq_any(prefixes));
+        }
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    /// Fetches the deployment subscriptions of the tenant of the given API key,
+    /// or all of them if `None`.
+    pub async fn deployment_subscriptions(
+        &self,
+        api_key: Option<&ApiKey>,
+    ) -> anyhow::Result<Vec<DeploymentSubscription>> {
+        use schema::deployment_subscriptions;
+
+        let mut query = deployment_subscriptions::table
+            .select(DeploymentSubscription::as_select())
+            .order_by(deployment_subscriptions::id)
+            .into_boxed();
+        if let Some(api_key) = api_key {
+            let prefixes = self.tenant_api_key_prefixes(api_key).await?;
+            query = query.filter(deployment_subscriptions::api_key_public_prefix.eq_any(prefixes));
+        }
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    /// How many PoIs were collected from indexers for each of `deployments`
+    /// since `since`, leaving out the ones that were fetched while bisecting.
+    /// Deployments without any are left out.
+    pub async fn poi_counts_since(
+        &self,
+        deployments: &[IpfsCid],
+        since: chrono::NaiveDateTime,
+    ) -> anyhow::Result<Vec<(IpfsCid, i64)>> {
+        use schema::{pois, sg_deployments as sgd};
+
+        self.observe("poi_counts_since", async {
+            Ok(pois::table
+                .inner_join(sgd::table)
+                .filter(sgd::ipfs_cid.eq_any(deployments))
+                .filter(pois::created_at.gt(since))
+                .filter(pois::source.eq(PoiSource::Polling))
+                .group_by(sgd::ipfs_cid)
+                .select((sgd::ipfs_cid, diesel::dsl::count(pois::id)))
+                .load(&mut self.read_conn().await?)
+                .await?)
+        })
+        .await
+    }
+
+    /// Fetches the watchlists that are defined in the configuration and those
+    /// of the tenant of the given API key, or all of them if `None`. Ordered
+    /// by name.
+    pub async fn watchlists(
+        &self,
+        api_key: Option<&ApiKey>,
+    ) -> anyhow::Result<Vec<models::Watchlist>> {
+        use schema::watchlists;
+
+        let mut query = watchlists::table
+            .select(models::Watchlist::as_select())
+            .order_by(watchlists::name)
+            .into_boxed();
+        if let Some(api_key) = api_key {
+            let prefixes = self.tenant_api_key_prefixes(api_key).await?;
+            query = query.filter(
+                watchlists::from_config.or(watchlists::api_key_public_prefix.eq_any(prefixes)),
+            );
+        }
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    /// Fetches the checkpoint that the indexing loop wrote when it last shut
+    /// down cleanly, if any.
+    pub async fn last_indexing_loop_checkpoint(
+        &self,
+    ) -> anyhow::Result<Option<models::IndexingLoopCheckpoint>> {
+        use schema::indexing_loop_checkpoints;
+
+        Ok(indexing_loop_checkpoints::table
+            .select(models::IndexingLoopCheckpoint::as_select())
+            .order_by(indexing_loop_checkpoints::id.desc())
+            .first(&mut self.conn().await?)
+            .await
+            .optional()?)
+    }
+
+    /// Returns the time at which PoIs were last collected, i.e. the end of the
+    /// last polling cycle that produced any.
+    pub async fn last_poi_collection_time(&self) -> anyhow::Result<Option<chrono::NaiveDateTime>> {
+        use schema::pois;
+
+        Ok(pois::table
+            .select(pois::created_at)
+            .order_by(pois::id.desc())
+            .first(&mut self.conn().await?)
+            .await
+            .optional()?)
+    }
+
+    /// Samples the row counts and on-disk sizes of all Graphix tables.
+    pub async fn table_stats(&self) -> anyhow::Result<Vec<models::TableStats>> {
+        let query = diesel::sql_query(
+            "SELECT schemaname::text AS schema_name, \
+                relname::text AS table_name, \
+                n_live_tup AS row_count, \
+                pg_total_relation_size(relid) AS total_size_bytes \
+            FROM pg_stat_user_tables \
+            ORDER BY schemaname, relname",
+        );
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    /// Returns the version of the latest database migration that was run,
+    /// e.g. `20250113000000`.
+    pub async fn schema_migration_version(&self) -> anyhow::Result<Option<String>> {
+        #[derive(QueryableByName)]
+        struct MigrationVersion {
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+            version: Option<String>,
+        }
+
+        let query = diesel::sql_query(
+            "SELECT MAX(version)::text AS version FROM __diesel_schema_migrations",
+        );
+        let row: MigrationVersion = query.get_result(&mut self.conn().await?).await?;
+
+        Ok(row.version)
+    }
+
+    /// Returns the on-disk size of the whole database.
+    pub async fn database_size_bytes(&self) -> anyhow::Result<i64> {
+        #[derive(QueryableByName)]
+        struct DatabaseSize {
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            size_bytes: i64,
+        }
+
+        let query = diesel::sql_query("SELECT pg_database_size(current_database()) AS size_bytes");
+        let row: DatabaseSize = query.get_result(&mut self.conn().await?).await?;
+
+        Ok(row.size_bytes)
+    }
+
+    /// Returns the latest known epoch of the given network, if any.
+    pub async fn latest_network_epoch(&self, network_id: IntId) -> anyhow::Result<Option<i64>> {
+        use schema::network_epochs;
+
+        Ok(network_epochs::table
+            .select(diesel::dsl::max(network_epochs::epoch))
+            .filter(network_epochs::network_id.eq(network_id))
+            .get_result(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// Returns the start blocks of `epochs` on the networks of the given
+    /// subgraph deployments, as `(deployment, epoch, start block number)`.
+    /// Deployments that are known on several networks appear once for each.
+    pub async fn sg_deployment_epoch_start_blocks(
+        &self,
+        sg_deployments: &[IpfsCid],
+        epochs: &[i64],
+    ) -> anyhow::Result<Vec<(IpfsCid, i64, i64)>> {
+        use schema::{network_epochs, sg_deployments as sgd};
+
+        Ok(sgd::table
+            .inner_join(network_epochs::table.on(network_epochs::network_id.eq(sgd::network)))
+            .select((
+                sgd::ipfs_cid,
+                network_epochs::epoch,
+                network_epochs::start_block_number,
+            ))
+            .filter(sgd::ipfs_cid.eq_any(sg_deployments))
+            .filter(network_epochs::epoch.eq_any(epochs))
+            .distinct()
+            .load(&mut self.read_conn().await?)
+            .await?)
+    }
+
+    /// Aggregates the collected PoIs of the given subgraph deployments (or all
+    /// of them, if none are given) by protocol epoch, most recent first. PoIs
+    /// without a known epoch are left out.
+    pub async fn epoch_summaries(
+        &self,
+        sg_deployments: &[IpfsCid],
+        limit: Option<u16>,
+    ) -> anyhow::Result<Vec<models::EpochSummary>> {
+        self.observe("epoch_summaries", async {
+            use diesel::dsl::{count_distinct, count_star};
+            use schema::{pois, sg_deployments};
+
+            let mut query = pois::table
+                .inner_join(sg_deployments::table)
+                .filter(pois::epoch.is_not_null())
+                .group_by((pois::sg_deployment_id, pois::epoch))
+                .select((
+                    pois::sg_deployment_id,
+                    pois::epoch.assume_not_null(),
+                    count_star(),
+                    count_distinct(pois::indexer_id),
+                    count_distinct(pois::block_id),
+                    count_distinct(pois::poi_value_id),
+                ))
+                .order_by((pois::epoch.desc(), pois::sg_deployment_id))
+                .into_boxed();
+
+            if !sg_deployments.is_empty() {
+                query = query.filter(sg_deployments::ipfs_cid.eq_any(sg_deployments));
+            }
+            if let Some(limit) = limit {
+                query = query.limit(limit.into());
+            }
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// The divergence investigation request with the given UUID, if it's
+    /// still in the job queue, i.e. it wasn't completed yet.
+    pub async fn divergence_investigation_job(
+        &self,
+        uuid: &Uuid,
+    ) -> anyhow::Result<Option<DivergenceInvestigationJob>> {
+        use schema::pending_divergence_investigation_requests as requests;
+
+        Ok(requests::table
+            .select(DivergenceInvestigationJob::as_select())
+            .filter(requests::uuid.eq(uuid))
+            .first(&mut self.conn().await?)
+            .await
+            .optional()?)
+    }
+
+    /// All divergence investigation requests in the job queue, including
+    /// running and failed ones, in the order they're claimed in.
+    pub async fn divergence_investigation_jobs(
+        &self,
+    ) -> anyhow::Result<Vec<DivergenceInvestigationJob>> {
+        use schema::pending_divergence_investigation_requests as requests;
+
+        Ok(requests::table
+            .select(DivergenceInvestigationJob::as_select())
+            .order_by((requests::priority.desc(), requests::created_at))
+            .load(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// The number of divergence investigation requests in the job queue with
+    /// each status.
+    pub async fn divergence_investigation_queue_depth(
+        &self,
+    ) -> anyhow::Result<Vec<(JobStatus, i64)>> {
+        use schema::pending_divergence_investigation_requests as requests;
+
+        Ok(requests::table
+            .group_by(requests::status)
+            .select((requests::status, diesel::dsl::count_star()))
+            .load(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// Fetches the divergence investigation report with the given UUID, if it
+    /// exists. Reports of investigations that are still running only contain
+    /// the bisection runs that were completed so far.
+    pub async fn divergence_investigation_report(
+        &self,
+        uuid: &Uuid,
+    ) -> anyhow::Result<Option<DivergenceInvestigationReport>> {
+        use schema::{
+            bisection_runs, bisection_steps, divergence_investigation_reports as reports,
+        };
+
+        let mut conn = self.conn().await?;
+
+        let Some(error) = reports::table
+            .select(reports::error)
+            .filter(reports::uuid.eq(uuid))
+            .first::<Option<String>>(&mut conn)
+            .await
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let runs: Vec<models::BisectionRun> = bisection_runs::table
+            .select(models::BisectionRun::as_select())
+            .filter(bisection_runs::investigation_uuid.eq(uuid))
+            .order_by(bisection_runs::id)
+            .load(&mut conn)
+            .await?;
+        let steps: Vec<models::BisectionStep> = bisection_steps::table
+            .select(models::BisectionStep::as_select())
+            .filter(bisection_steps::bisection_run_id.eq_any(runs.iter().map(|run| run.id)))
+            .order_by(bisection_steps::id)
+            .load(&mut conn)
+            .await?;
+        drop(conn);
+
+        let mut steps_by_run: HashMap<IntId, Vec<BisectionReport>> = HashMap::new();
+        for step in steps {
+            steps_by_run
+                .entry(step.bisection_run_id)
+                .or_default()
+                .push(BisectionReport {
+                    block: PartialBlock {
+                        number: step.block_number,
+                        hash: step.block_hash,
+                    },
+                    indexer1_response: step.indexer1_response,
+                    indexer2_response: step.indexer2_response,
+                });
+        }
+
+        // Requests that failed for good are complete too, with the error of
+        // their last attempt if the report doesn't have one.
+        let (status, error) = match self.divergence_investigation_job(uuid).await? {
+            Some(job) if job.status == JobStatus::Failed => (
+                DivergenceInvestigationStatus::Complete,
+                error.or(job.last_error),
+            ),
+            Some(_) => (DivergenceInvestigationStatus::InProgress, error),
+            None => (DivergenceInvestigationStatus::Complete, error),
+        };
+
+        Ok(Some(DivergenceInvestigationReport {
+            uuid: *uuid,
+            status,
+            bisection_runs: runs
+                .into_iter()
+                .map(|run| {
+                    Ok(BisectionRunReport {
+                        uuid: run.uuid,
+                        poi1: run.poi1,
+                        poi2: run.poi2,
+                        indexer1_operator_contact: run.indexer1_operator_contact,
+                        indexer2_operator_contact: run.indexer2_operator_contact,
+                        divergence_block_bounds: DivergenceBlockBounds {
+                            lower_bound: PartialBlock {
+                                number: run.divergence_lower_bound,
+                                hash: None,
+                            },
+                            upper_bound: PartialBlock {
+                                number: run.divergence_upper_bound,
+                                hash: None,
+                            },
+                        },
+                        bisects: steps_by_run.remove(&run.id).unwrap_or_default(),
+                        error: run.error,
+                        entity_diff: run.entity_diff.map(serde_json::from_value).transpose()?,
+                        eth_call_diff: run.eth_call_diff.map(serde_json::from_value).transpose()?,
+                        graft_divergence: run
+                            .graft_divergence
+                            .map(serde_json::from_value)
+                            .transpose()?,
+                        indexer1_graph_node_version: run.indexer1_graph_node_version,
+                        indexer2_graph_node_version: run.indexer2_graph_node_version,
+                    })
+                })
+                .collect::<Result<_, serde_json::Error>>()?,
+            error,
+        }))
+    }
+
+    /// Queries the bisection runs of all divergence investigations, most
+    /// recent first.
+    pub async fn bisection_runs(
+        &self,
+        filter: &inputs::BisectionRunsQuery,
+    ) -> anyhow::Result<Vec<models::BisectionRun>> {
+        self.observe("bisection_runs", async {
+            use schema::{bisection_runs, sg_deployments};
+
+            let mut query = bisection_runs::table
+                .left_join(sg_deployments::table)
+                .select(models::BisectionRun::as_select())
+                .order_by(bisection_runs::id.desc())
+                .into_boxed();
+
+            if !filter.deployments.is_empty() {
+                query = query.filter(sg_deployments::ipfs_cid.eq_any(&filter.deployments));
+            }
+            if let Some(block_number) = filter.divergence_block_number {
+                query =
+                    query.filter(bisection_runs::divergence_upper_bound.eq(block_number as i64));
+            }
+            if let Some(since) = filter.since {
+                query = query.filter(bisection_runs::created_at.ge(since));
+            }
+            if let Some(poi) = filter.poi {
+                query = query.filter(
+                    bisection_runs::poi1
+                        .eq(poi)
+                        .or(bisection_runs::poi2.eq(poi)),
+                );
+            }
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit.into());
+            }
+
+            Ok(query.load(&mut self.read_conn().await?).await?)
+        })
+        .await
+    }
+
+    /// Returns the most recent bisection run of each of the given PoIs, if
+    /// any, by PoI.
+    pub async fn latest_bisection_runs(
+        &self,
+        pois: &[PoiBytes],
+    ) -> anyhow::Result<HashMap<PoiBytes, models::BisectionRun>> {
+        use diesel::sql_types::{Array, Binary};
+
+        self.observe("latest_bisection_runs", async {
+            let query = diesel::sql_query(
+                "SELECT q.poi, r.* \
+                FROM unnest($1::bytea[]) AS q(poi) \
+                JOIN LATERAL ( \
+                    SELECT * FROM bisection_runs \
+                    WHERE poi1 = q.poi OR poi2 = q.poi \
+                    ORDER BY id DESC \
+                    LIMIT 1 \
+                ) r ON true",
+            )
+            .bind::<Array<Binary>, _>(pois);
+
+            let rows: Vec<models::LatestBisectionRunRow> =
+                query.load(&mut self.read_conn().await?).await?;
+            Ok(rows
+                .into_iter()
+                .map(|row| (row.poi, row.bisection_run))
+                .collect())
+        })
+        .await
+    }
+
+    pub async fn divergence_investigation_request_exists(
+        &self,
+        uuid: &Uuid,
+    ) -> anyhow::Result<bool> {
+        use schema::pending_divergence_investigation_requests as requests;
+
+        let exists = requests::table
+            .filter(requests::uuid.eq(uuid))
+            .count()
+            .get_result::<i64>(&mut self.conn().await?)
+            .await?
+            > 0;
+        Ok(exists)
+    }
+}
+
+/// Setters and write operations.
+impl Store {
+    pub async fn overwrite_config(&self, config: serde_json::Value) -> anyhow::Result<()> {
+        let mut conn = self.conn().await?;
+        diesel_queries::overwrite_config(&mut conn, &config).await
+    }
+
+    /// Applies `update` to the stored configuration (`{}` if there is none)
+    /// and stores the result if `update` returns `Ok(Some(payload))`, in
+    /// which case `mutation` is recorded in the admin audit log with
+    /// `payload` as well. The table is locked for the duration, so that
+    /// concurrent updates don't overwrite each other; an error of `update` is
+    /// returned as is and leaves the stored configuration unchanged. Returns
+    /// whether the configuration changed.
+    pub async fn update_config<E>(
+        &self,
+        mutation: AuditedMutation<'_>,
+        update: impl FnOnce(&mut serde_json::Value) -> Result<Option<serde_json::Value>, E> + Send,
+    ) -> anyhow::Result<Result<bool, E>>
+    where
+        E: Send,
+    {
+        let now = self.clock.now().naive_utc();
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let mut config = diesel_queries::lock_config(conn).await?;
+                    let payload = match update(&mut config) {
+                        Ok(Some(payload)) => payload,
+                        Ok(None) => return Ok(Ok(false)),
+                        Err(err) => return Ok(Err(err)),
+                    };
+                    diesel_queries::overwrite_config(conn, &config).await?;
+                    diesel_queries::write_admin_audit_log_entry(conn, &mutation, payload, now)
+                        .await?;
+                    Ok(Ok(true))
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Bundles `config` with the subgraph deployment names and indexer tags,
+    /// to restore them on another instance with
+    /// [`Store::import_instance_config`].
+    pub async fn export_instance_config(
+        &self,
+        config: serde_json::Value,
+    ) -> anyhow::Result<InstanceConfig> {
+        use schema::{indexers, sg_deployments as sgd, sg_names};
+
+        let mut conn = self.conn().await?;
+        let deployment_names = sg_names::table
+            .inner_join(sgd::table)
+            .select((sgd::ipfs_cid, sg_names::name))
+            .distinct()
+            .order_by((sgd::ipfs_cid, sg_names::name))
+            .load::<(IpfsCid, String)>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|(ipfs_cid, name)| DeploymentName { ipfs_cid, name })
+            .collect();
+        let indexer_tags = indexers::table
+            .select((indexers::address, indexers::tags))
+            .order_by(indexers::address)
+            .load::<(IndexerAddress, Vec<String>)>(&mut conn)
+            .await?
+            .into_iter()
+            .filter(|(_, tags)| !tags.is_empty())
+            .map(|(address, tags)| IndexerTags { address, tags })
+            .collect();
+
+        Ok(InstanceConfig {
+            config,
+            deployment_names,
+            indexer_tags,
+        })
+    }
+
+    /// Overwrites the configuration, and the names and tags of the subgraph
+    /// deployments and indexers in `instance_config` that exist, all at once.
+    /// Tags replace the existing ones. `mutation` is recorded in the admin
+    /// audit log in the same transaction, with the payload that `payload`
+    /// returns for the previously stored configuration (`{}` if there is
+    /// none).
+    pub async fn import_instance_config(
+        &self,
+        instance_config: &InstanceConfig,
+        mutation: AuditedMutation<'_>,
+        payload: impl FnOnce(&serde_json::Value) -> serde_json::Value + Send,
+    ) -> anyhow::Result<InstanceConfigImport> {
+        use schema::{indexers, sg_deployments as sgd, sg_names};
+
+        let now = self.clock.now().naive_utc();
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let mut import = InstanceConfigImport::default();
+                    let previous_config = diesel_queries::lock_config(conn).await?;
+                    diesel_queries::overwrite_config(conn, &instance_config.config).await?;
+                    diesel_queries::write_admin_audit_log_entry(
+                        conn,
+                        &mutation,
+                        payload(&previous_config),
+                        now,
+                    )
+                    .await?;
+
+                    for DeploymentName { ipfs_cid, name } in &instance_config.deployment_names {
+                        let new_names: Vec<_> = sgd::table
+                            .select(sgd::id)
+                            .filter(sgd::ipfs_cid.eq(ipfs_cid))
+                            .load::<IntId>(conn)
+                            .await?
+                            .into_iter()
+                            .map(|id| (sg_names::sg_deployment_id.eq(id), sg_names::name.eq(name)))
+                            .collect();
+                        if new_names.is_empty() {
+                            import.skipped_deployments.push(ipfs_cid.clone());
+                            continue;
+                        }
+
+                        diesel::insert_into(sg_names::table)
+                            .values(&new_names)
+                            .on_conflict(sg_names::sg_deployment_id)
+                            .do_update()
+                            .set(sg_names::name.eq(name))
+                            .execute(conn)
+                            .await?;
+                        import.named_deployments += 1;
+                    }
+
+                    for IndexerTags { address, tags } in &instance_config.indexer_tags {
+                        let mut tags = tags.clone();
+                        tags.sort();
+                        tags.dedup();
+
+                        let updated = diesel::update(indexers::table)
+                            .filter(indexers::address.eq(address))
+                            .set(indexers::tags.eq(tags))
+                            .execute(conn)
+                            .await?;
+                        if updated == 0 {
+                            import.skipped_indexers.push(*address);
+                        } else {
+                            import.tagged_indexers += 1;
+                        }
+                    }
+
+                    Ok(import)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    async fn create_master_api_key(&self) -> anyhow::Result<()> {
+        let api_key = self
+            .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+            .await?;
+
+        let description = format!("Master API key created during database initialization. Use it to create a new private API key and then delete it for security reasons. `{}`", api_key.api_key);
+        self.modify_api_key(
+            &api_key.api_key,
+            Some(&description),
+            ApiKeyPermissionLevel::Admin,
+        )
+        .await?;
+
+        info!(api_key = ?api_key.api_key, "Created master API key");
+
+        Ok(())
+    }
+
+    pub async fn create_networks_if_missing(&self, networks: &[NewNetwork]) -> anyhow::Result<()> {
+        use schema::networks;
+
+        let mut conn = self.conn().await?;
+
+        // batch insert
+        diesel::insert_into(networks::table)
+            .values(networks)
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_sg_deployment(
+        &self,
+        network_name: &str,
+        ipfs_cid: &str,
+    ) -> anyhow::Result<()> {
+        use schema::sg_deployments as sgd;
+
         diesel::insert_into(sgd::table)
             .values((
                 sgd::ipfs_cid.eq(ipfs_cid),
@@ -384,66 +2138,1457 @@ impl Store {
         Ok(())
     }
 
-    pub async fn set_deployment_name(
+    /// Names the subgraph deployments with the given IPFS CID on all networks.
+    pub async fn set_deployment_name(
+        &self,
+        sg_deployment_id: &str,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        use schema::{sg_deployments as sgd, sg_names};
+
+        let mut conn = self.conn().await?;
+        let ids: Vec<IntId> = sgd::table
+            .select(sgd::id)
+            .filter(sgd::ipfs_cid.eq(sg_deployment_id))
+            .load(&mut conn)
+            .await?;
+        if ids.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Subgraph deployment {sg_deployment_id} not found"
+            ));
+        }
+        let new_names: Vec<_> = ids
+            .into_iter()
+            .map(|id| (sg_names::sg_deployment_id.eq(id), sg_names::name.eq(name)))
+            .collect();
+
+        diesel::insert_into(sg_names::table)
+            .values(&new_names)
+            .on_conflict(sg_names::sg_deployment_id)
+            .do_update()
+            .set(sg_names::name.eq(name))
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Names the subgraph deployments in `names` on all networks, and returns
+    /// how many were named. Unless `overwrite` is set, deployments that
+    /// already have a name keep it. Unknown deployments are ignored.
+    pub async fn set_deployment_names(
+        &self,
+        names: &[DeploymentName],
+        overwrite: bool,
+    ) -> anyhow::Result<usize> {
+        self.observe("set_deployment_names", async {
+            use diesel::upsert::excluded;
+            use schema::{sg_deployments as sgd, sg_names};
+
+            if names.is_empty() {
+                return Ok(0);
+            }
+
+            let mut conn = self.conn().await?;
+            let names_by_cid: HashMap<&IpfsCid, &str> = names
+                .iter()
+                .map(|name| (&name.ipfs_cid, name.name.as_str()))
+                .collect();
+            let new_names: Vec<_> = sgd::table
+                .select((sgd::id, sgd::ipfs_cid))
+                .filter(sgd::ipfs_cid.eq_any(names_by_cid.keys()))
+                .load::<(IntId, IpfsCid)>(&mut conn)
+                .await?
+                .into_iter()
+                .map(|(id, ipfs_cid)| {
+                    (
+                        sg_names::sg_deployment_id.eq(id),
+                        sg_names::name.eq(names_by_cid[&ipfs_cid]),
+                    )
+                })
+                .collect();
+
+            let insert = diesel::insert_into(sg_names::table)
+                .values(&new_names)
+                .on_conflict(sg_names::sg_deployment_id);
+            let named = if overwrite {
+                insert
+                    .do_update()
+                    .set(sg_names::name.eq(excluded(sg_names::name)))
+                    .execute(&mut conn)
+                    .await?
+            } else {
+                insert.do_nothing().execute(&mut conn).await?
+            };
+
+            Ok(named)
+        })
+        .await
+    }
+
+    /// The IPFS CIDs of all subgraph deployments whose manifest wasn't fetched
+    /// yet.
+    pub async fn sg_deployments_without_manifest(&self) -> anyhow::Result<Vec<IpfsCid>> {
+        self.observe("sg_deployments_without_manifest", async {
+            use schema::sg_deployments as sgd;
+
+            Ok(sgd::table
+                .select(sgd::ipfs_cid)
+                .filter(sgd::manifest.is_null())
+                .distinct()
+                .order_by(sgd::ipfs_cid)
+                .load(&mut self.conn().await?)
+                .await?)
+        })
+        .await
+    }
+
+    /// The IPFS CIDs of all subgraph deployments that aren't known to be, or
+    /// not to be, substreams-powered yet.
+    pub async fn sg_deployments_with_unknown_kind(&self) -> anyhow::Result<Vec<IpfsCid>> {
+        use schema::sg_deployments as sgd;
+
+        Ok(sgd::table
+            .select(sgd::ipfs_cid)
+            .filter(sgd::substreams.is_null())
+            .distinct()
+            .order_by(sgd::ipfs_cid)
+            .load(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// Which of the given subgraph deployments are stored, along with the
+    /// names of their networks.
+    pub async fn known_sg_deployments(
+        &self,
+        ipfs_cids: &[IpfsCid],
+    ) -> anyhow::Result<HashSet<(IpfsCid, String)>> {
+        use schema::{networks, sg_deployments as sgd};
+
+        Ok(sgd::table
+            .inner_join(networks::table)
+            .select((sgd::ipfs_cid, networks::name))
+            .filter(sgd::ipfs_cid.eq_any(ipfs_cids))
+            .load::<(IpfsCid, String)>(&mut self.conn().await?)
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// The IPFS CIDs of all substreams-powered subgraph deployments.
+    pub async fn substreams_sg_deployments(&self) -> anyhow::Result<HashSet<IpfsCid>> {
+        use schema::sg_deployments as sgd;
+
+        Ok(sgd::table
+            .select(sgd::ipfs_cid)
+            .filter(sgd::substreams.eq(true))
+            .load::<IpfsCid>(&mut self.conn().await?)
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// Tags the subgraph deployments with the given IPFS CID on all networks
+    /// as substreams-powered, or not.
+    pub async fn set_sg_deployment_substreams(
+        &self,
+        ipfs_cid: &IpfsCid,
+        substreams: bool,
+    ) -> anyhow::Result<()> {
+        use schema::sg_deployments as sgd;
+
+        diesel::update(sgd::table.filter(sgd::ipfs_cid.eq(ipfs_cid.to_string())))
+            .set(sgd::substreams.eq(substreams))
+            .execute(&mut self.conn().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Stores the manifest metadata of the subgraph deployments with the given
+    /// IPFS CID on all networks.
+    pub async fn set_sg_deployment_manifest(
+        &self,
+        ipfs_cid: &IpfsCid,
+        manifest: &SubgraphManifest,
+    ) -> anyhow::Result<()> {
+        self.observe("set_sg_deployment_manifest", async {
+            use schema::sg_deployments as sgd;
+
+            diesel::update(sgd::table.filter(sgd::ipfs_cid.eq(ipfs_cid.to_string())))
+                .set(sgd::manifest.eq(serde_json::to_value(manifest)?))
+                .execute(&mut self.conn().await?)
+                .await?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Up to `limit` blocks on `network` whose timestamp wasn't resolved yet,
+    /// most recent first. Blocks that were already checked less than
+    /// `recheck_after` ago are skipped.
+    pub async fn blocks_without_timestamp(
+        &self,
+        network: &str,
+        recheck_after: std::time::Duration,
+        limit: u16,
+    ) -> anyhow::Result<Vec<models::Block>> {
+        self.observe("blocks_without_timestamp", async {
+            use schema::{blocks, networks};
+
+            let checked_before =
+                self.clock.now().naive_utc() - chrono::Duration::from_std(recheck_after)?;
+            Ok(blocks::table
+                .inner_join(networks::table)
+                .select(blocks::all_columns)
+                .filter(networks::name.eq(network))
+                .filter(blocks::timestamp.is_null())
+                .filter(
+                    blocks::timestamp_checked_at
+                        .is_null()
+                        .or(blocks::timestamp_checked_at.lt(checked_before)),
+                )
+                .order_by(blocks::number.desc())
+                .limit(limit.into())
+                .load(&mut self.conn().await?)
+                .await?)
+        })
+        .await
+    }
+
+    /// Stores the timestamp and canonical hash of a block, as resolved from
+    /// the chain. `None` records an unsuccessful attempt, so that the block
+    /// isn't checked again right away.
+    pub async fn set_block_timestamp(
+        &self,
+        block_id: BigIntId,
+        resolved: Option<(chrono::NaiveDateTime, &BlockHash)>,
+    ) -> anyhow::Result<()> {
+        self.observe("set_block_timestamp", async {
+            use schema::blocks;
+
+            let (timestamp, canonical_hash) = resolved.unzip();
+            diesel::update(blocks::table.filter(blocks::id.eq(block_id)))
+                .set((
+                    blocks::timestamp.eq(timestamp),
+                    blocks::canonical_hash.eq(canonical_hash),
+                    blocks::timestamp_checked_at.eq(self.clock.now().naive_utc()),
+                ))
+                .execute(&mut self.conn().await?)
+                .await?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Sets (or, with `None`, clears) the manually maintained operator contact
+    /// information of an indexer, which takes precedence over the one found
+    /// on the network subgraph.
+    pub async fn set_indexer_operator_contact(
+        &self,
+        address: &IndexerAddress,
+        contact: Option<&str>,
+    ) -> anyhow::Result<models::Indexer> {
+        use schema::indexers;
+
+        diesel::update(indexers::table.filter(indexers::address.eq(address)))
+            .set(indexers::manual_operator_contact.eq(contact))
+            .returning(indexers::all_columns)
+            .get_result(&mut self.conn().await?)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow!("indexer {address} not found"))
+    }
+
+    /// Adds a tag to an indexer. Returns the updated indexer, or `None` if no
+    /// indexer with the given address exists.
+    pub async fn add_indexer_tag(
+        &self,
+        address: &IndexerAddress,
+        tag: &str,
+    ) -> anyhow::Result<Option<models::Indexer>> {
+        self.update_indexer_tags(address, |tags| {
+            tags.push(tag.to_string());
+        })
+        .await
+    }
+
+    /// Removes a tag from an indexer. Returns the updated indexer, or `None` if
+    /// no indexer with the given address exists.
+    pub async fn remove_indexer_tag(
+        &self,
+        address: &IndexerAddress,
+        tag: &str,
+    ) -> anyhow::Result<Option<models::Indexer>> {
+        self.update_indexer_tags(address, |tags| {
+            tags.retain(|t| t != tag);
+        })
+        .await
+    }
+
+    async fn update_indexer_tags(
+        &self,
+        address: &IndexerAddress,
+        update: impl FnOnce(&mut Vec<String>) + Send,
+    ) -> anyhow::Result<Option<models::Indexer>> {
+        use schema::indexers;
+
+        let address = *address;
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let Some(mut tags) = indexers::table
+                        .select(indexers::tags)
+                        .filter(indexers::address.eq(&address))
+                        .for_update()
+                        .get_result::<Vec<String>>(conn)
+                        .await
+                        .optional()?
+                    else {
+                        return Ok(None);
+                    };
+
+                    update(&mut tags);
+                    tags.sort();
+                    tags.dedup();
+
+                    let indexer = diesel::update(indexers::table)
+                        .filter(indexers::address.eq(&address))
+                        .set(indexers::tags.eq(tags))
+                        .returning(indexers::all_columns)
+                        .get_result(conn)
+                        .await?;
+                    Ok(Some(indexer))
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Archives an indexer: it's no longer polled nor listed by
+    /// [`Store::indexers`] by default, and its PoIs are no longer live, but
+    /// all of its data is kept. Archiving an archived indexer is a no-op.
+    /// Returns the archived indexer, or `None` if no indexer with the given
+    /// address exists.
+    pub async fn archive_indexer(
+        &self,
+        address: &IndexerAddress,
+    ) -> anyhow::Result<Option<models::Indexer>> {
+        use schema::{indexers, live_pois};
+
+        let address = *address;
+        let now = self.clock.now().naive_utc();
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let archived = diesel::update(indexers::table)
+                        .filter(indexers::address.eq(&address))
+                        .filter(indexers::archived_at.is_null())
+                        .set(indexers::archived_at.eq(now))
+                        .returning(indexers::all_columns)
+                        .get_result::<IndexerModel>(conn)
+                        .await
+                        .optional()?;
+                    let Some(indexer) = archived else {
+                        // Already archived, or unknown.
+                        return Ok(indexers::table
+                            .select(indexers::all_columns)
+                            .filter(indexers::address.eq(&address))
+                            .get_result(conn)
+                            .await
+                            .optional()?);
+                    };
+
+                    diesel::delete(live_pois::table.filter(live_pois::indexer_id.eq(indexer.id)))
+                        .execute(conn)
+                        .await?;
+                    Ok(Some(indexer))
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// The addresses of all indexers that were archived with
+    /// [`Store::archive_indexer`].
+    pub async fn archived_indexers(&self) -> anyhow::Result<Vec<IndexerAddress>> {
+        use schema::indexers;
+
+        Ok(indexers::table
+            .select(indexers::address)
+            .filter(indexers::archived_at.is_not_null())
+            .load(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// Deletes an indexer together with **all** of its related data (PoIs,
+    /// indexing statuses, errors, etc.). Returns `false` if no indexer with
+    /// the given address exists.
+    pub async fn delete_indexer(&self, address: &IndexerAddress) -> anyhow::Result<bool> {
+        use schema::indexers;
+
+        let deleted = diesel::delete(indexers::table.filter(indexers::address.eq(address)))
+            .execute(&mut self.conn().await?)
+            .await?;
+        // The `ON DELETE CASCADE`s take care of the rest of the cleanup.
+
+        Ok(deleted > 0)
+    }
+
+    /// Deletes the network with the given name from the database, together with
+    /// **all** of its related data (indexers, deployments, etc.).
+    pub async fn delete_network(&self, network_name: &str) -> anyhow::Result<()> {
+        use schema::networks;
+
+        diesel::delete(networks::table.filter(networks::name.eq(network_name)))
+            .execute(&mut self.conn().await?)
+            .await?;
+        // The `ON DELETE CASCADE`s should take care of the rest of the cleanup.
+
+        Ok(())
+    }
+
+    /// Enables or disables the network with the given name. Disabled networks
+    /// are not polled, but their data is left untouched. Returns the updated
+    /// network, or `None` if no network with the given name exists.
+    pub async fn set_network_enabled(
+        &self,
+        network_name: &str,
+        enabled: bool,
+    ) -> anyhow::Result<Option<models::Network>> {
+        use schema::networks;
+
+        Ok(
+            diesel::update(networks::table.filter(networks::name.eq(network_name)))
+                .set(networks::enabled.eq(enabled))
+                .returning((
+                    networks::id,
+                    networks::name,
+                    networks::caip2,
+                    networks::enabled,
+                ))
+                .get_result(&mut self.conn().await?)
+                .await
+                .optional()?,
+        )
+    }
+
+    pub async fn create_network(&self, network: &NewNetwork) -> anyhow::Result<IntId> {
+        use schema::networks;
+
+        let id = diesel::insert_into(networks::table)
+            .values(network)
+            .returning(networks::id)
+            .get_result(&mut self.conn().await?)
+            .await?;
+
+        Ok(id)
+    }
+
+    pub async fn write_pois<W>(&self, pois: Vec<W>, live: PoiLiveness) -> anyhow::Result<()>
+    where
+        W: WritablePoi + Send + Sync,
+        W::IndexerId: Send + Sync,
+    {
+        self.observe(
+            "write_pois",
+            self.write_pois_from_source(pois, live, PoiSource::Polling),
+        )
+        .await
+    }
+
+    /// Stores PoIs that were fetched while bisecting, which are never live,
+    /// so that later bisections can use them with [`Store::stored_poi`].
+    pub async fn write_investigation_pois<W>(&self, pois: Vec<W>) -> anyhow::Result<()>
+    where
+        W: WritablePoi + Send + Sync,
+        W::IndexerId: Send + Sync,
+    {
+        self.observe(
+            "write_investigation_pois",
+            self.write_pois_from_source(pois, PoiLiveness::NotLive, PoiSource::Investigation),
+        )
+        .await
+    }
+
+    async fn write_pois_from_source<W>(
+        &self,
+        pois: Vec<W>,
+        live: PoiLiveness,
+        source: PoiSource,
+    ) -> anyhow::Result<()>
+    where
+        W: WritablePoi + Send + Sync,
+        W::IndexerId: Send + Sync,
+    {
+        let now = self.clock.now().naive_utc();
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let ids = diesel_queries::write_pois(conn, pois, live, source, now).await?;
+                    diesel_queries::tag_poi_epochs(conn, PoiEpochScope::Pois(&ids)).await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Stores the start blocks of protocol epochs, and tags the PoIs of the
+    /// affected networks with their epochs.
+    pub async fn write_network_epochs(&self, epochs: &[NewNetworkEpoch]) -> anyhow::Result<()> {
+        use schema::network_epochs;
+
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    diesel::insert_into(network_epochs::table)
+                        .values(epochs)
+                        .on_conflict_do_nothing()
+                        .execute(conn)
+                        .await?;
+                    let mut network_ids: Vec<IntId> =
+                        epochs.iter().map(|epoch| epoch.network_id).collect();
+                    network_ids.sort_unstable();
+                    network_ids.dedup();
+                    diesel_queries::tag_poi_epochs(conn, PoiEpochScope::Networks(&network_ids))
+                        .await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Stores the latest health, sync, and pause flags of each (indexer,
+    /// deployment) pair, overwriting those from previous polling cycles.
+    pub async fn write_indexing_statuses(&self, statuses: &[IndexingStatus]) -> anyhow::Result<()> {
+        self.observe("write_indexing_statuses", async {
+            let now = self.clock.now().naive_utc();
+            self.conn()
+                .await?
+                .transaction::<_, Error, _>(|conn| {
+                    async move {
+                        diesel_queries::write_indexing_statuses(conn, statuses, now).await?;
+                        Ok(())
+                    }
+                    .scope_boxed()
+                })
+                .await
+        })
+        .await
+    }
+
+    /// Stores the latest block of each of the given networks, by network
+    /// name. Networks that aren't stored are ignored.
+    pub async fn write_chain_heads(&self, heads: &[(String, i64)]) -> anyhow::Result<()> {
+        use diesel::upsert::excluded;
+        use schema::{chain_heads, networks};
+
+        self.observe("write_chain_heads", async {
+            let now = self.clock.now().naive_utc();
+            let mut conn = self.conn().await?;
+            let network_ids: HashMap<String, IntId> = networks::table
+                .select((networks::name, networks::id))
+                .load(&mut conn)
+                .await?
+                .into_iter()
+                .collect();
+            let rows: Vec<_> = heads
+                .iter()
+                .filter_map(|(network, block_number)| {
+                    Some((
+                        chain_heads::network_id.eq(network_ids.get(network)?),
+                        chain_heads::block_number.eq(block_number),
+                        chain_heads::updated_at.eq(now),
+                    ))
+                })
+                .collect();
+            if rows.is_empty() {
+                return Ok(());
+            }
+
+            diesel::insert_into(chain_heads::table)
+                .values(&rows)
+                .on_conflict(chain_heads::network_id)
+                .do_update()
+                .set((
+                    chain_heads::block_number.eq(excluded(chain_heads::block_number)),
+                    chain_heads::updated_at.eq(excluded(chain_heads::updated_at)),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Appends entries to the `subgraph_feed` schema.
+    pub async fn write_subgraph_feed(
+        &self,
+        consensus: &[models::NewFeedPoiConsensus],
+        divergences: &[models::NewFeedPoiDivergence],
+    ) -> anyhow::Result<()> {
+        use crate::subgraph_feed_schema::{poi_consensus, poi_divergences};
+
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    diesel::insert_into(poi_consensus::table)
+                        .values(consensus)
+                        .execute(conn)
+                        .await?;
+                    diesel::insert_into(poi_divergences::table)
+                        .values(divergences)
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Stores on-chain PoIs, skipping those of allocations that are already
+    /// known.
+    pub async fn write_onchain_pois(&self, pois: &[models::NewOnchainPoi]) -> anyhow::Result<()> {
+        self.observe("write_onchain_pois", async {
+            use schema::onchain_pois;
+
+            diesel::insert_into(onchain_pois::table)
+                .values(pois)
+                .on_conflict(onchain_pois::allocation_id)
+                .do_nothing()
+                .execute(&mut self.conn().await?)
+                .await?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Replaces the stored allocations of the given indexers with
+    /// `allocations`, so that closed allocations are forgotten.
+    pub async fn replace_indexer_allocations(
+        &self,
+        indexer_addresses: &[IndexerAddress],
+        allocations: &[models::IndexerAllocation],
+    ) -> anyhow::Result<()> {
+        use schema::indexer_allocations;
+
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    diesel::delete(
+                        indexer_allocations::table
+                            .filter(indexer_allocations::indexer_address.eq_any(indexer_addresses)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::insert_into(indexer_allocations::table)
+                        .values(allocations)
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Returns the allocations of all indexers to the given subgraph
+    /// deployments.
+    pub async fn indexer_allocations(
+        &self,
+        deployments: &[IpfsCid],
+    ) -> anyhow::Result<Vec<models::IndexerAllocation>> {
+        use schema::indexer_allocations;
+
+        Ok(indexer_allocations::table
+            .select(models::IndexerAllocation::as_select())
+            .filter(indexer_allocations::deployment.eq_any(deployments))
+            .load(&mut self.read_conn().await?)
+            .await?)
+    }
+
+    /// Returns the staked tokens of the indexers with the given addresses, as
+    /// of the last refresh of their network subgraph metadata. Indexers
+    /// without metadata are left out.
+    pub async fn indexer_staked_tokens(
+        &self,
+        addresses: &[IndexerAddress],
+    ) -> anyhow::Result<Vec<(IndexerAddress, bigdecimal::BigDecimal)>> {
+        use schema::{indexer_network_subgraph_metadata, indexers};
+
+        Ok(indexers::table
+            .inner_join(indexer_network_subgraph_metadata::table)
+            .select((
+                indexers::address,
+                indexer_network_subgraph_metadata::staked_tokens,
+            ))
+            .filter(indexers::address.eq_any(addresses))
+            .load(&mut self.read_conn().await?)
+            .await?)
+    }
+
+    pub async fn write_indexers(
+        &self,
+        indexers: &[impl AsRef<dyn IndexerClient>],
+    ) -> anyhow::Result<()> {
+        self.observe("write_indexers", async {
+            let mut conn = self.conn().await?;
+            diesel_queries::write_indexers(&mut conn, indexers).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Records the network subgraphs on which `indexers` were found, so that
+    /// an indexer that's found on multiple network subgraphs is attributed
+    /// to all of them. Attributions are only ever added, so that they survive
+    /// a network subgraph that fails to respond. Indexers that aren't stored
+    /// yet are skipped.
+    pub async fn write_indexer_network_subgraphs(
+        &self,
+        indexers: &[impl AsRef<dyn IndexerClient>],
+    ) -> anyhow::Result<()> {
+        use schema::{indexer_network_subgraphs, indexers};
+
+        self.observe("write_indexer_network_subgraphs", async {
+            let attributions: HashSet<(IndexerAddress, String)> = indexers
+                .iter()
+                .filter_map(|indexer| {
+                    let indexer = indexer.as_ref();
+                    Some((indexer.address(), indexer.network_subgraph()?.into_owned()))
+                })
+                .collect();
+            if attributions.is_empty() {
+                return Ok(());
+            }
+
+            let mut conn = self.conn().await?;
+            let addresses: Vec<_> = attributions.iter().map(|(address, _)| *address).collect();
+            let indexer_ids: HashMap<IndexerAddress, IntId> = indexers::table
+                .select((indexers::address, indexers::id))
+                .filter(indexers::address.eq_any(&addresses))
+                .load(&mut conn)
+                .await?
+                .into_iter()
+                .collect();
+            let rows: Vec<_> = attributions
+                .into_iter()
+                .filter_map(|(address, network_subgraph)| {
+                    Some(models::IndexerNetworkSubgraph {
+                        indexer_id: *indexer_ids.get(&address)?,
+                        network_subgraph,
+                    })
+                })
+                .collect();
+
+            diesel::insert_into(indexer_network_subgraphs::table)
+                .values(rows)
+                .on_conflict_do_nothing()
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Returns the network subgraphs on which the given indexers were found,
+    /// ordered by indexer and name.
+    pub async fn indexer_network_subgraphs(
+        &self,
+        indexer_ids: &[IntId],
+    ) -> anyhow::Result<Vec<models::IndexerNetworkSubgraph>> {
+        use schema::indexer_network_subgraphs;
+
+        Ok(indexer_network_subgraphs::table
+            .select(models::IndexerNetworkSubgraph::as_select())
+            .filter(indexer_network_subgraphs::indexer_id.eq_any(indexer_ids))
+            .order_by((
+                indexer_network_subgraphs::indexer_id,
+                indexer_network_subgraphs::network_subgraph,
+            ))
+            .load(&mut self.read_conn().await?)
+            .await?)
+    }
+
+    /// Records that indexers allocated to the subgraph deployments with the
+    /// given IPFS CIDs on `network_subgraph`. Like indexer attributions, these
+    /// are only ever added. Deployments that aren't stored yet are skipped.
+    pub async fn write_sg_deployment_network_subgraphs(
+        &self,
+        network_subgraph: &str,
+        deployments: &[IpfsCid],
+    ) -> anyhow::Result<()> {
+        use diesel::sql_types::Text;
+        use schema::{sg_deployment_network_subgraphs as sgdns, sg_deployments as sgd};
+
+        self.observe("write_sg_deployment_network_subgraphs", async {
+            diesel::insert_into(sgdns::table)
+                .values(
+                    sgd::table
+                        .select((sgd::id, network_subgraph.into_sql::<Text>()))
+                        .filter(sgd::ipfs_cid.eq_any(deployments)),
+                )
+                .into_columns((sgdns::sg_deployment_id, sgdns::network_subgraph))
+                .on_conflict_do_nothing()
+                .execute(&mut self.conn().await?)
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Returns the network subgraphs on which indexers allocated to the given
+    /// subgraph deployments, ordered by deployment and name.
+    pub async fn sg_deployment_network_subgraphs(
+        &self,
+        sg_deployment_ids: &[IntId],
+    ) -> anyhow::Result<Vec<models::SgDeploymentNetworkSubgraph>> {
+        use schema::sg_deployment_network_subgraphs as sgdns;
+
+        Ok(sgdns::table
+            .select(models::SgDeploymentNetworkSubgraph::as_select())
+            .filter(sgdns::sg_deployment_id.eq_any(sg_deployment_ids))
+            .order_by((sgdns::sg_deployment_id, sgdns::network_subgraph))
+            .load(&mut self.read_conn().await?)
+            .await?)
+    }
+
+    /// Records until when the circuit breakers of `indexers` skip them, and
+    /// clears it for all other indexers.
+    pub async fn write_indexer_circuits(
+        &self,
+        indexers: &[impl AsRef<dyn IndexerClient>],
+    ) -> anyhow::Result<()> {
+        use schema::indexers;
+
+        let open: Vec<(IndexerAddress, chrono::NaiveDateTime)> = indexers
+            .iter()
+            .filter_map(|indexer| {
+                let indexer = indexer.as_ref();
+                Some((indexer.address(), indexer.circuit_open_until()?.naive_utc()))
+            })
+            .collect();
+        self.observe("write_indexer_circuits", async {
+            self.conn()
+                .await?
+                .transaction::<_, Error, _>(|conn| {
+                    async move {
+                        let addresses: Vec<_> = open.iter().map(|(address, _)| *address).collect();
+                        diesel::update(indexers::table)
+                            .filter(indexers::circuit_open_until.is_not_null())
+                            .filter(indexers::address.ne_all(addresses))
+                            .set(indexers::circuit_open_until.eq(None::<chrono::NaiveDateTime>))
+                            .execute(conn)
+                            .await?;
+                        for (address, open_until) in &open {
+                            diesel::update(indexers::table)
+                                .filter(indexers::address.eq(address))
+                                .set(indexers::circuit_open_until.eq(open_until))
+                                .execute(conn)
+                                .await?;
+                        }
+                        Ok(())
+                    }
+                    .scope_boxed()
+                })
+                .await
+        })
+        .await
+    }
+
+    pub async fn delete_indexer_network_subgraph_metadata(
+        &self,
+        indexer_id: IntId,
+    ) -> anyhow::Result<()> {
+        use schema::indexers;
+
+        diesel::update(indexers::table.filter(indexers::id.eq(indexer_id)))
+            .set(indexers::network_subgraph_metadata.eq::<Option<IntId>>(None))
+            .execute(&mut self.conn().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_or_update_indexer_network_subgraph_metadata(
         &self,
-        sg_deployment_id: &str,
-        name: &str,
+        indexer_id: IntId,
+        metadata: NewIndexerNetworkSubgraphMetadata,
+    ) -> anyhow::Result<IntId> {
+        use schema::{indexer_network_subgraph_metadata, indexers};
+
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                // Fetch the metadata id from indexer_id, and update it if it exists
+                // create a new one and set the foreign key to the indexer_id if it doesn't exist
+                async move {
+                    let metadata_id = indexers::table
+                        .select(indexers::network_subgraph_metadata)
+                        .filter(indexers::id.eq(indexer_id))
+                        .get_result::<Option<IntId>>(conn)
+                        .await?;
+
+                    let metadata_id = match metadata_id {
+                        Some(id) => {
+                            diesel::update(
+                                indexer_network_subgraph_metadata::table
+                                    .filter(indexer_network_subgraph_metadata::id.eq(id)),
+                            )
+                            .set(metadata)
+                            .execute(conn)
+                            .await?;
+                            id
+                        }
+                        None => {
+                            let metadata_id =
+                                diesel::insert_into(indexer_network_subgraph_metadata::table)
+                                    .values(&metadata)
+                                    .returning(indexer_network_subgraph_metadata::id)
+                                    .get_result(conn)
+                                    .await?;
+
+                            diesel::update(indexers::table)
+                                .filter(indexers::id.eq(indexer_id))
+                                .set(indexers::network_subgraph_metadata.eq(metadata_id))
+                                .execute(conn)
+                                .await?;
+
+                            metadata_id
+                        }
+                    };
+
+                    Ok(metadata_id)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        Ok(indexer_id)
+    }
+
+    pub async fn create_api_key(
+        &self,
+        notes: Option<&str>,
+        permission_level: ApiKeyPermissionLevel,
+        organization: Option<&str>,
+    ) -> anyhow::Result<NewlyCreatedApiKey> {
+        use schema::graphix_api_tokens;
+
+        let api_key = ApiKey::generate();
+        let stored_api_key = ApiKeyDbRow {
+            public_prefix: api_key.public_part_as_string(),
+            sha256_api_key_hash: api_key.hash(),
+            notes: notes.map(|s| s.to_string()),
+            permission_level,
+            organization: organization.map(|s| s.to_string()),
+            requests_per_minute: None,
+        };
+
+        diesel::insert_into(graphix_api_tokens::table)
+            .values(&[stored_api_key])
+            .execute(&mut self.conn().await?)
+            .await?;
+
+        Ok(NewlyCreatedApiKey {
+            api_key: api_key.to_string(),
+            notes: notes.map(|s| s.to_string()),
+            permission_level,
+            organization: organization.map(|s| s.to_string()),
+        })
+    }
+
+    pub async fn modify_api_key(
+        &self,
+        api_key_s: &str,
+        notes: Option<&str>,
+        permission_level: ApiKeyPermissionLevel,
     ) -> anyhow::Result<()> {
-        use schema::{sg_deployments as sgd, sg_names};
+        use schema::graphix_api_tokens;
 
-        diesel::insert_into(sg_names::table)
-            .values((
-                sg_names::sg_deployment_id.eq(sgd::table
-                    .select(sgd::id)
-                    .filter(sgd::ipfs_cid.eq(sg_deployment_id))
-                    .single_value()
-                    .assume_not_null()),
-                sg_names::name.eq(name),
+        let api_key = ApiKey::from_str(api_key_s).map_err(|e| anyhow!("invalid api key: {}", e))?;
+
+        diesel::update(graphix_api_tokens::table)
+            .filter(graphix_api_tokens::sha256_api_key_hash.eq(api_key.hash()))
+            .set((
+                graphix_api_tokens::notes.eq(notes),
+                graphix_api_tokens::permission_level.eq(permission_level),
             ))
-            .on_conflict(sg_names::sg_deployment_id)
-            .do_update()
-            .set(sg_names::name.eq(name))
             .execute(&mut self.conn().await?)
             .await?;
 
         Ok(())
     }
 
-    /// Deletes the network with the given name from the database, together with
-    /// **all** of its related data (indexers, deployments, etc.).
-    pub async fn delete_network(&self, network_name: &str) -> anyhow::Result<()> {
-        use schema::networks;
+    /// Overrides the configured rate limit of GraphQL API requests for an API
+    /// key, or reverts to it if `requests_per_minute` is `None`. Returns
+    /// whether the API key exists.
+    pub async fn set_api_key_rate_limit(
+        &self,
+        api_key: &ApiKey,
+        requests_per_minute: Option<u32>,
+    ) -> anyhow::Result<bool> {
+        use schema::graphix_api_tokens;
 
-        diesel::delete(networks::table.filter(networks::name.eq(network_name)))
+        let requests_per_minute = requests_per_minute.map(i32::try_from).transpose()?;
+        let updated = diesel::update(graphix_api_tokens::table)
+            .filter(graphix_api_tokens::sha256_api_key_hash.eq(api_key.hash()))
+            .set(graphix_api_tokens::requests_per_minute.eq(requests_per_minute))
+            .execute(&mut self.conn().await?)
+            .await?;
+
+        Ok(updated > 0)
+    }
+
+    /// Records that the indexing loop shut down cleanly after polling the given
+    /// indexers.
+    pub async fn write_indexing_loop_checkpoint(
+        &self,
+        indexer_addresses: &[IndexerAddress],
+    ) -> anyhow::Result<()> {
+        use schema::indexing_loop_checkpoints;
+
+        let checkpoint = models::NewIndexingLoopCheckpoint {
+            indexer_addresses: indexer_addresses.to_vec(),
+            created_at: self.clock.now().naive_utc(),
+        };
+        diesel::insert_into(indexing_loop_checkpoints::table)
+            .values(&checkpoint)
+            .execute(&mut self.conn().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records a run of a divergence investigation campaign.
+    pub async fn write_campaign_run(
+        &self,
+        campaign: &str,
+        deployments: &[IpfsCid],
+        diverging_deployments: &[IpfsCid],
+        investigation_uuids: &[Uuid],
+        error: Option<&str>,
+    ) -> anyhow::Result<models::CampaignRun> {
+        use schema::campaign_runs;
+
+        let run = models::NewCampaignRun {
+            campaign: campaign.to_string(),
+            deployments: deployments.to_vec(),
+            diverging_deployments: diverging_deployments.to_vec(),
+            investigation_uuids: investigation_uuids.to_vec(),
+            error: error.map(str::to_string),
+            created_at: self.clock.now().naive_utc(),
+        };
+
+        Ok(diesel::insert_into(campaign_runs::table)
+            .values(&run)
+            .returning(models::CampaignRun::as_returning())
+            .get_result(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// Fetches the most recent runs of divergence investigation campaigns, or
+    /// of a single campaign, newest first.
+    pub async fn campaign_runs(
+        &self,
+        campaign: Option<&str>,
+        limit: u16,
+    ) -> anyhow::Result<Vec<models::CampaignRun>> {
+        use schema::campaign_runs;
+
+        let mut query = campaign_runs::table
+            .select(models::CampaignRun::as_select())
+            .order_by(campaign_runs::id.desc())
+            .limit(limit.into())
+            .into_boxed();
+        if let Some(campaign) = campaign {
+            query = query.filter(campaign_runs::campaign.eq(campaign));
+        }
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    /// Requests the PoIs of a subgraph deployment to be backfilled over a
+    /// block range.
+    pub async fn create_poi_backfill(
+        &self,
+        sg_deployment_id: IntId,
+        from_block: u64,
+        to_block: u64,
+        step: u64,
+    ) -> anyhow::Result<models::PoiBackfill> {
+        use schema::poi_backfills;
+
+        let backfill = models::NewPoiBackfill {
+            sg_deployment_id,
+            from_block: from_block.try_into()?,
+            to_block: to_block.try_into()?,
+            step: step.try_into()?,
+            next_block: Some(from_block.try_into()?),
+            created_at: self.clock.now().naive_utc(),
+        };
+
+        Ok(diesel::insert_into(poi_backfills::table)
+            .values(&backfill)
+            .returning(models::PoiBackfill::as_returning())
+            .get_result(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// Fetches the most recent PoI backfills, or those of the given subgraph
+    /// deployments, newest first.
+    pub async fn poi_backfills(
+        &self,
+        sg_deployments: &[IpfsCid],
+        limit: u16,
+    ) -> anyhow::Result<Vec<models::PoiBackfill>> {
+        use schema::{poi_backfills, sg_deployments};
+
+        let mut query = poi_backfills::table
+            .inner_join(sg_deployments::table)
+            .select(models::PoiBackfill::as_select())
+            .order_by(poi_backfills::id.desc())
+            .limit(limit.into())
+            .into_boxed();
+        if !sg_deployments.is_empty() {
+            query = query.filter(sg_deployments::ipfs_cid.eq_any(sg_deployments));
+        }
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    /// Returns the oldest PoI backfill that isn't done yet, along with the
+    /// IPFS CID and network of its subgraph deployment.
+    pub async fn first_pending_poi_backfill(
+        &self,
+    ) -> anyhow::Result<Option<(models::PoiBackfill, IpfsCid, String)>> {
+        use schema::{networks, poi_backfills, sg_deployments};
+
+        Ok(poi_backfills::table
+            .inner_join(sg_deployments::table.inner_join(networks::table))
+            .select((
+                models::PoiBackfill::as_select(),
+                sg_deployments::ipfs_cid,
+                networks::name,
+            ))
+            .filter(poi_backfills::next_block.is_not_null())
+            .order_by(poi_backfills::id)
+            .first(&mut self.conn().await?)
+            .await
+            .optional()?)
+    }
+
+    /// Records that a PoI backfill wrote `pois_written` more PoIs and moved on
+    /// to `next_block`. The backfill is done once `next_block` is `None`,
+    /// e.g. because it failed with `error`.
+    pub async fn record_poi_backfill_progress(
+        &self,
+        id: IntId,
+        next_block: Option<u64>,
+        pois_written: usize,
+        error: Option<&str>,
+    ) -> anyhow::Result<()> {
+        use schema::poi_backfills;
+
+        let next_block = next_block.map(i64::try_from).transpose()?;
+        let pois_written = i32::try_from(pois_written)?;
+        let completed_at = next_block.is_none().then(|| self.clock.now().naive_utc());
+        diesel::update(poi_backfills::table.find(id))
+            .set((
+                poi_backfills::next_block.eq(next_block),
+                poi_backfills::pois_written.eq(poi_backfills::pois_written + pois_written),
+                poi_backfills::error.eq(error),
+                poi_backfills::completed_at.eq(completed_at),
+            ))
             .execute(&mut self.conn().await?)
             .await?;
-        // The `ON DELETE CASCADE`s should take care of the rest of the cleanup.
 
         Ok(())
     }
 
-    pub async fn create_network(&self, network: &NewNetwork) -> anyhow::Result<IntId> {
-        use schema::networks;
+    /// Registers a new webhook for the given API key, unless the API key
+    /// already has `max_webhooks` webhooks.
+    pub async fn create_webhook(
+        &self,
+        api_key: &ApiKey,
+        url: &str,
+        deployments: &[IpfsCid],
+        max_webhooks: u32,
+    ) -> anyhow::Result<Webhook> {
+        use schema::webhooks;
+
+        let new_webhook = NewWebhook {
+            api_key_public_prefix: api_key.public_part_as_string(),
+            url: url.to_string(),
+            deployments: deployments.to_vec(),
+        };
+
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let count: i64 = webhooks::table
+                        .filter(
+                            webhooks::api_key_public_prefix.eq(&new_webhook.api_key_public_prefix),
+                        )
+                        .count()
+                        .get_result(conn)
+                        .await?;
+                    if count >= max_webhooks.into() {
+                        return Err(WebhookLimitReached { max_webhooks }.into());
+                    }
+
+                    Ok(diesel::insert_into(webhooks::table)
+                        .values(&new_webhook)
+                        .returning(Webhook::as_returning())
+                        .get_result(conn)
+                        .await?)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Deletes a webhook, if it belongs to the tenant of the given API key (any
+    /// tenant if `None`). Returns whether a webhook was deleted.
+    pub async fn delete_webhook(
+        &self,
+        id: IntId,
+        api_key: Option<&ApiKey>,
+    ) -> anyhow::Result<bool> {
+        use schema::webhooks;
+
+        let mut query = diesel::delete(webhooks::table)
+            .filter(webhooks::id.eq(id))
+            .into_boxed();
+        if let Some(api_key) = api_key {
+            let prefixes = self.tenant_api_key_prefixes(api_key).await?;
+            query = query.filter(webhooks::api_key_public_prefix.eq_any(prefixes));
+        }
+
+        Ok(query.execute(&mut self.conn().await?).await? > 0)
+    }
+
+    /// Subscribes `webhook_url` to digests of `deployment` on behalf of the
+    /// given API key, unless the API key already has `max_subscriptions`
+    /// subscriptions. Subscribing again returns the existing subscription.
+    pub async fn create_deployment_subscription(
+        &self,
+        api_key: &ApiKey,
+        deployment: &IpfsCid,
+        webhook_url: &str,
+        max_subscriptions: u32,
+    ) -> anyhow::Result<DeploymentSubscription> {
+        use schema::deployment_subscriptions;
+
+        let new_subscription = NewDeploymentSubscription {
+            api_key_public_prefix: api_key.public_part_as_string(),
+            deployment: deployment.clone(),
+            webhook_url: webhook_url.to_string(),
+        };
+
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let existing = deployment_subscriptions::table
+                        .filter(
+                            deployment_subscriptions::api_key_public_prefix
+                                .eq(&new_subscription.api_key_public_prefix),
+                        )
+                        .filter(
+                            deployment_subscriptions::deployment.eq(&new_subscription.deployment),
+                        )
+                        .filter(
+                            deployment_subscriptions::webhook_url.eq(&new_subscription.webhook_url),
+                        )
+                        .select(DeploymentSubscription::as_select())
+                        .first(conn)
+                        .await
+                        .optional()?;
+                    if let Some(existing) = existing {
+                        return Ok(existing);
+                    }
+
+                    let count: i64 = deployment_subscriptions::table
+                        .filter(
+                            deployment_subscriptions::api_key_public_prefix
+                                .eq(&new_subscription.api_key_public_prefix),
+                        )
+                        .count()
+                        .get_result(conn)
+                        .await?;
+                    if count >= max_subscriptions.into() {
+                        return Err(WebhookLimitReached {
+                            max_webhooks: max_subscriptions,
+                        }
+                        .into());
+                    }
+
+                    // A no-op update in case of a concurrent subscription, so
+                    // that the existing row is returned.
+                    Ok(diesel::insert_into(deployment_subscriptions::table)
+                        .values(&new_subscription)
+                        .on_conflict((
+                            deployment_subscriptions::api_key_public_prefix,
+                            deployment_subscriptions::deployment,
+                            deployment_subscriptions::webhook_url,
+                        ))
+                        .do_update()
+                        .set(
+                            deployment_subscriptions::webhook_url.eq(&new_subscription.webhook_url),
+                        )
+                        .returning(DeploymentSubscription::as_returning())
+                        .get_result(conn)
+                        .await?)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Deletes a deployment subscription, if it belongs to the tenant of the
+    /// given API key (any tenant if `None`). Returns whether a subscription
+    /// was deleted.
+    pub async fn delete_deployment_subscription(
+        &self,
+        id: IntId,
+        api_key: Option<&ApiKey>,
+    ) -> anyhow::Result<bool> {
+        use schema::deployment_subscriptions;
+
+        let mut query = diesel::delete(deployment_subscriptions::table)
+            .filter(deployment_subscriptions::id.eq(id))
+            .into_boxed();
+        if let Some(api_key) = api_key {
+            let prefixes = self.tenant_api_key_prefixes(api_key).await?;
+            query = query.filter(deployment_subscriptions::api_key_public_prefix.eq_any(prefixes));
+        }
+
+        Ok(query.execute(&mut self.conn().await?).await? > 0)
+    }
+
+    /// Creates a watchlist, or replaces the one with the same name if it
+    /// belongs to the tenant of the given API key (any tenant if `None`).
+    /// Watchlists that are defined in the configuration are never replaced.
+    /// Returns `None` if the name is taken by a watchlist that can't be
+    /// replaced.
+    pub async fn set_watchlist(
+        &self,
+        watchlist: &models::NewWatchlist,
+        api_key: Option<&ApiKey>,
+    ) -> anyhow::Result<Option<models::Watchlist>> {
+        use schema::watchlists;
+
+        let prefixes = match api_key {
+            Some(api_key) => Some(self.tenant_api_key_prefixes(api_key).await?),
+            None => None,
+        };
+
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let existing: Option<(bool, Option<String>)> = watchlists::table
+                        .select((watchlists::from_config, watchlists::api_key_public_prefix))
+                        .filter(watchlists::name.eq(&watchlist.name))
+                        .for_update()
+                        .get_result(conn)
+                        .await
+                        .optional()?;
+
+                    let mut watchlist = watchlist.clone();
+                    if let Some((from_config, owner)) = existing {
+                        let owned = match (&prefixes, &owner) {
+                            (None, _) => true,
+                            (Some(prefixes), Some(owner)) => prefixes.contains(owner),
+                            (Some(_), None) => false,
+                        };
+                        if from_config || !owned {
+                            return Ok(None);
+                        }
+                        // Replacing a watchlist doesn't change its tenant.
+                        watchlist.api_key_public_prefix = owner;
+                    }
+
+                    Ok(Some(
+                        diesel::insert_into(watchlists::table)
+                            .values(&watchlist)
+                            .on_conflict(watchlists::name)
+                            .do_update()
+                            .set(&watchlist)
+                            .returning(models::Watchlist::as_returning())
+                            .get_result(conn)
+                            .await?,
+                    ))
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Deletes a watchlist, if it belongs to the tenant of the given API key
+    /// (any tenant if `None`). Returns whether a watchlist was deleted.
+    pub async fn delete_watchlist(
+        &self,
+        name: &str,
+        api_key: Option<&ApiKey>,
+    ) -> anyhow::Result<bool> {
+        use schema::watchlists;
 
-        let id = diesel::insert_into(networks::table)
-            .values(network)
-            .returning(networks::id)
-            .get_result(&mut self.conn().await?)
-            .await?;
+        let mut query = diesel::delete(watchlists::table)
+            .filter(watchlists::name.eq(name))
+            .into_boxed();
+        if let Some(api_key) = api_key {
+            let prefixes = self.tenant_api_key_prefixes(api_key).await?;
+            query = query.filter(watchlists::api_key_public_prefix.eq_any(prefixes));
+        }
 
-        Ok(id)
+        Ok(query.execute(&mut self.conn().await?).await? > 0)
     }
 
-    pub async fn write_pois<W>(&self, pois: Vec<W>, live: PoiLiveness) -> anyhow::Result<()>
-    where
-        W: WritablePoi + Send + Sync,
-        W::IndexerId: Send + Sync,
-    {
+    /// Replaces the watchlists that were defined in the configuration with
+    /// `watchlists`, which take over any watchlists of the same name that were
+    /// created with [`Store::set_watchlist`].
+    pub async fn sync_config_watchlists(
+        &self,
+        watchlists: &[models::NewWatchlist],
+    ) -> anyhow::Result<()> {
+        use schema::watchlists as wl;
+
         self.conn()
             .await?
             .transaction::<_, Error, _>(|conn| {
                 async move {
-                    diesel_queries::write_pois(conn, pois, live).await?;
+                    let names: Vec<&str> = watchlists.iter().map(|w| w.name.as_str()).collect();
+                    diesel::delete(wl::table)
+                        .filter(wl::from_config)
+                        .filter(wl::name.ne_all(names))
+                        .execute(conn)
+                        .await?;
+
+                    for watchlist in watchlists {
+                        diesel::insert_into(wl::table)
+                            .values(watchlist)
+                            .on_conflict(wl::name)
+                            .do_update()
+                            .set(watchlist)
+                            .execute(conn)
+                            .await?;
+                    }
+
                     Ok(())
                 }
                 .scope_boxed()
@@ -451,133 +3596,215 @@ impl Store {
             .await
     }
 
-    pub async fn write_indexers(
+    /// Stores a failed query to an indexer, which must already exist. The
+    /// query and response are expected to be sanitized by the caller.
+    pub async fn write_failed_query(
         &self,
-        indexers: &[impl AsRef<dyn IndexerClient>],
+        indexer: &impl IndexerId,
+        query_name: &str,
+        raw_query: &str,
+        response: &str,
     ) -> anyhow::Result<()> {
+        use schema::failed_queries;
+
+        let now = self.clock.now().naive_utc();
         let mut conn = self.conn().await?;
-        diesel_queries::write_indexers(&mut conn, indexers).await?;
+        let indexer_id =
+            diesel_queries::get_indexer_id(&mut conn, indexer.name(), &indexer.address()).await?;
+
+        diesel::insert_into(failed_queries::table)
+            .values((
+                failed_queries::indexer_id.eq(indexer_id),
+                failed_queries::query_name.eq(query_name),
+                failed_queries::raw_query.eq(raw_query),
+                failed_queries::response.eq(response),
+                failed_queries::request_timestamp.eq(now),
+            ))
+            .execute(&mut conn)
+            .await?;
+
         Ok(())
     }
 
-    pub async fn delete_indexer_network_subgraph_metadata(
+    /// Appends events to the event log.
+    pub async fn write_events(&self, events: &[NewEvent]) -> anyhow::Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        self.observe("write_events", async {
+            use schema::events;
+
+            let now = self.clock.now().naive_utc();
+            let rows: Vec<_> = events
+                .iter()
+                .map(|event| {
+                    (
+                        events::kind.eq(event.kind),
+                        events::deployment.eq(event.deployment.as_ref()),
+                        events::payload.eq(&event.payload),
+                        events::created_at.eq(now),
+                    )
+                })
+                .collect();
+
+            self.conn()
+                .await?
+                .transaction::<_, Error, _>(|conn| {
+                    async move {
+                        // Consumers read events by increasing ID, so events
+                        // must become visible in ID order. Concurrent writers
+                        // would otherwise commit them out of order.
+                        diesel::sql_query("LOCK TABLE events IN EXCLUSIVE MODE")
+                            .execute(conn)
+                            .await?;
+                        diesel::insert_into(events::table)
+                            .values(&rows)
+                            .execute(conn)
+                            .await?;
+                        Ok(())
+                    }
+                    .scope_boxed()
+                })
+                .await
+        })
+        .await
+    }
+
+    /// Records that `consumer` processed all events up to `last_event_id`.
+    pub async fn set_event_cursor(
         &self,
-        indexer_id: IntId,
+        consumer: &str,
+        last_event_id: BigIntId,
     ) -> anyhow::Result<()> {
-        use schema::indexers;
+        use schema::event_cursors;
 
-        diesel::update(indexers::table.filter(indexers::id.eq(indexer_id)))
-            .set(indexers::network_subgraph_metadata.eq::<Option<IntId>>(None))
+        diesel::insert_into(event_cursors::table)
+            .values((
+                event_cursors::consumer.eq(consumer),
+                event_cursors::last_event_id.eq(last_event_id),
+            ))
+            .on_conflict(event_cursors::consumer)
+            .do_update()
+            .set(event_cursors::last_event_id.eq(last_event_id))
             .execute(&mut self.conn().await?)
             .await?;
 
         Ok(())
     }
 
-    pub async fn create_or_update_indexer_network_subgraph_metadata(
+    /// Counts failed requests to indexers by error class, and keeps the most
+    /// recent error message of each class. Indexers must already exist.
+    pub async fn write_indexer_errors(
         &self,
-        indexer_id: IntId,
-        metadata: NewIndexerNetworkSubgraphMetadata,
-    ) -> anyhow::Result<IntId> {
-        use schema::{indexer_network_subgraph_metadata, indexers};
+        errors: &[WithIndexer<models::NewIndexerError>],
+    ) -> anyhow::Result<()> {
+        use diesel::upsert::excluded;
+        use schema::indexer_errors;
 
+        let now = self.clock.now().naive_utc();
         self.conn()
             .await?
             .transaction::<_, Error, _>(|conn| {
-                // Fetch the metadata id from indexer_id, and update it if it exists
-                // create a new one and set the foreign key to the indexer_id if it doesn't exist
                 async move {
-                    let metadata_id = indexers::table
-                        .select(indexers::network_subgraph_metadata)
-                        .filter(indexers::id.eq(indexer_id))
-                        .get_result::<Option<IntId>>(conn)
+                    for error in errors {
+                        let indexer_id = diesel_queries::get_indexer_id(
+                            conn,
+                            error.indexer.name(),
+                            &error.indexer.address(),
+                        )
                         .await?;
 
-                    let metadata_id = match metadata_id {
-                        Some(id) => {
-                            diesel::update(
-                                indexer_network_subgraph_metadata::table
-                                    .filter(indexer_network_subgraph_metadata::id.eq(id)),
-                            )
-                            .set(metadata)
+                        diesel::insert_into(indexer_errors::table)
+                            .values((
+                                indexer_errors::indexer_id.eq(indexer_id),
+                                indexer_errors::error_class.eq(error.inner.class),
+                                indexer_errors::count.eq(1),
+                                indexer_errors::last_error.eq(&error.inner.message),
+                                indexer_errors::last_error_at.eq(now),
+                            ))
+                            .on_conflict((indexer_errors::indexer_id, indexer_errors::error_class))
+                            .do_update()
+                            .set((
+                                indexer_errors::count.eq(indexer_errors::count + 1),
+                                indexer_errors::last_error.eq(excluded(indexer_errors::last_error)),
+                                indexer_errors::last_error_at
+                                    .eq(excluded(indexer_errors::last_error_at)),
+                            ))
                             .execute(conn)
                             .await?;
-                            id
-                        }
-                        None => {
-                            let metadata_id =
-                                diesel::insert_into(indexer_network_subgraph_metadata::table)
-                                    .values(&metadata)
-                                    .returning(indexer_network_subgraph_metadata::id)
-                                    .get_result(conn)
-                                    .await?;
+                    }
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
 
-                            diesel::update(indexers::table)
-                                .filter(indexers::id.eq(indexer_id))
-                                .set(indexers::network_subgraph_metadata.eq(metadata_id))
-                                .execute(conn)
-                                .await?;
+    /// Stores timed requests to indexers. Indexers must already exist.
+    pub async fn write_latency_samples(
+        &self,
+        samples: &[WithIndexer<models::NewLatencySample>],
+    ) -> anyhow::Result<()> {
+        use schema::indexer_latency_samples;
 
-                            metadata_id
-                        }
-                    };
+        let now = self.clock.now().naive_utc();
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    for sample in samples {
+                        let indexer_id = diesel_queries::get_indexer_id(
+                            conn,
+                            sample.indexer.name(),
+                            &sample.indexer.address(),
+                        )
+                        .await?;
 
-                    Ok(metadata_id)
+                        diesel::insert_into(indexer_latency_samples::table)
+                            .values((
+                                indexer_latency_samples::indexer_id.eq(indexer_id),
+                                indexer_latency_samples::probe.eq(sample.inner.probe),
+                                indexer_latency_samples::latency_ms.eq(sample.inner.latency_ms),
+                                indexer_latency_samples::success.eq(sample.inner.success),
+                                indexer_latency_samples::created_at.eq(now),
+                            ))
+                            .execute(conn)
+                            .await?;
+                    }
+                    Ok(())
                 }
                 .scope_boxed()
             })
-            .await?;
-
-        Ok(indexer_id)
+            .await
     }
 
-    pub async fn create_api_key(
+    /// Deletes latency samples that were taken before `cutoff`. Returns how
+    /// many were deleted.
+    pub async fn delete_latency_samples_before(
         &self,
-        notes: Option<&str>,
-        permission_level: ApiKeyPermissionLevel,
-    ) -> anyhow::Result<NewlyCreatedApiKey> {
-        use schema::graphix_api_tokens;
-
-        let api_key = ApiKey::generate();
-        let stored_api_key = ApiKeyDbRow {
-            public_prefix: api_key.public_part_as_string(),
-            sha256_api_key_hash: api_key.hash(),
-            notes: notes.map(|s| s.to_string()),
-            permission_level,
-        };
+        cutoff: chrono::NaiveDateTime,
+    ) -> anyhow::Result<usize> {
+        use schema::indexer_latency_samples;
 
-        diesel::insert_into(graphix_api_tokens::table)
-            .values(&[stored_api_key])
+        Ok(diesel::delete(indexer_latency_samples::table)
+            .filter(indexer_latency_samples::created_at.lt(cutoff))
             .execute(&mut self.conn().await?)
-            .await?;
-
-        Ok(NewlyCreatedApiKey {
-            api_key: api_key.to_string(),
-            notes: notes.map(|s| s.to_string()),
-            permission_level,
-        })
+            .await?)
     }
 
-    pub async fn modify_api_key(
+    /// Deletes failed queries that were stored before `cutoff`. Returns how
+    /// many were deleted.
+    pub async fn delete_failed_queries_before(
         &self,
-        api_key_s: &str,
-        notes: Option<&str>,
-        permission_level: ApiKeyPermissionLevel,
-    ) -> anyhow::Result<()> {
-        use schema::graphix_api_tokens;
-
-        let api_key = ApiKey::from_str(api_key_s).map_err(|e| anyhow!("invalid api key: {}", e))?;
+        cutoff: chrono::NaiveDateTime,
+    ) -> anyhow::Result<usize> {
+        use schema::failed_queries;
 
-        diesel::update(graphix_api_tokens::table)
-            .filter(graphix_api_tokens::sha256_api_key_hash.eq(api_key.hash()))
-            .set((
-                graphix_api_tokens::notes.eq(notes),
-                graphix_api_tokens::permission_level.eq(permission_level),
-            ))
+        Ok(diesel::delete(failed_queries::table)
+            .filter(failed_queries::request_timestamp.lt(cutoff))
             .execute(&mut self.conn().await?)
-            .await?;
-
-        Ok(())
+            .await?)
     }
 
     pub async fn delete_api_key(&self, api_key_s: &str) -> anyhow::Result<()> {
@@ -593,6 +3820,10 @@ impl Store {
         Ok(())
     }
 
+    /// Stores the graph-node versions that indexers reported, or the errors
+    /// they responded with, as their latest version. Indexers that report a
+    /// different version or commit than before start a new period in their
+    /// version history.
     pub async fn write_graph_node_versions(
         &self,
         versions: HashMap<
@@ -600,65 +3831,463 @@ impl Store {
             anyhow::Result<graphix_common_types::GraphNodeCollectedVersion>,
         >,
     ) -> anyhow::Result<()> {
-        use schema::graph_node_collected_versions;
-        for version in versions.values() {
-            let conn = &mut self.conn().await?;
-
-            let new_version = match version {
-                Ok(v) => models::NewGraphNodeCollectedVersion {
-                    version_string: v.version.clone(),
-                    version_commit: v.commit.clone(),
-                    error_response: None,
-                },
-                Err(err) => models::NewGraphNodeCollectedVersion {
-                    version_string: None,
-                    version_commit: None,
-                    error_response: Some(err.to_string()),
-                },
+        self.observe("write_graph_node_versions", async {
+            use schema::{
+                graph_node_collected_versions, indexer_graph_node_versions as ignv, indexers,
             };
 
-            diesel::insert_into(graph_node_collected_versions::table)
-                .values(&new_version)
-                .execute(conn)
+            let now = self.clock.now().naive_utc();
+            for (indexer, version) in versions.iter() {
+                let conn = &mut self.conn().await?;
+
+                let new_version = match version {
+                    Ok(v) => models::NewGraphNodeCollectedVersion {
+                        version_string: v.version.clone(),
+                        version_commit: v.commit.clone(),
+                        error_response: None,
+                    },
+                    Err(err) => models::NewGraphNodeCollectedVersion {
+                        version_string: None,
+                        version_commit: None,
+                        error_response: Some(err.to_string()),
+                    },
+                };
+
+                let version_id: IntId = diesel::insert_into(graph_node_collected_versions::table)
+                    .values(&new_version)
+                    .returning(graph_node_collected_versions::id)
+                    .get_result(conn)
+                    .await?;
+                let indexer_id: Option<IntId> = diesel::update(indexers::table)
+                    .filter(indexers::address.eq(indexer.address()))
+                    .set(indexers::graph_node_version.eq(version_id))
+                    .returning(indexers::id)
+                    .get_result(conn)
+                    .await
+                    .optional()?;
+
+                // Failing to report a version doesn't mean that it changed.
+                let (Some(indexer_id), Ok(version)) = (indexer_id, version) else {
+                    continue;
+                };
+                conn.transaction::<_, Error, _>(|conn| {
+                    async move {
+                        let current: Option<(Option<String>, Option<String>)> = ignv::table
+                            .select((ignv::version_string, ignv::version_commit))
+                            .filter(ignv::indexer_id.eq(indexer_id))
+                            .filter(ignv::valid_to.is_null())
+                            .for_update()
+                            .get_result(conn)
+                            .await
+                            .optional()?;
+                        let reported = (version.version.clone(), version.commit.clone());
+                        if current.as_ref() == Some(&reported) {
+                            return Ok(());
+                        }
+
+                        diesel::update(ignv::table)
+                            .filter(ignv::indexer_id.eq(indexer_id))
+                            .filter(ignv::valid_to.is_null())
+                            .set(ignv::valid_to.eq(now))
+                            .execute(conn)
+                            .await?;
+                        diesel::insert_into(ignv::table)
+                            .values(models::NewIndexerGraphNodeVersion {
+                                indexer_id,
+                                version_string: reported.0,
+                                version_commit: reported.1,
+                                valid_from: now,
+                            })
+                            .execute(conn)
+                            .await?;
+                        Ok(())
+                    }
+                    .scope_boxed()
+                })
                 .await?;
-        }
+            }
 
-        Ok(())
+            Ok(())
+        })
+        .await
+    }
+
+    /// Counts the bisection runs that found a divergence, optionally only
+    /// those created since `since`, by the pair of graph-node versions that
+    /// the two indexers ran, most common first.
+    pub async fn divergences_by_graph_node_version_pair(
+        &self,
+        since: Option<chrono::NaiveDateTime>,
+    ) -> anyhow::Result<Vec<models::DivergenceVersionPair>> {
+        use diesel::sql_types::{Nullable, Timestamp};
+
+        let query = diesel::sql_query(
+            "SELECT version1, version2, \
+                COUNT(*) AS bisection_runs, \
+                COUNT(DISTINCT sg_deployment_id) AS deployments \
+            FROM ( \
+                SELECT sg_deployment_id, \
+                    CASE WHEN v2 IS NULL OR v1 <= v2 THEN v1 ELSE v2 END AS version1, \
+                    CASE WHEN v2 IS NULL OR v1 <= v2 THEN v2 ELSE v1 END AS version2 \
+                FROM ( \
+                    SELECT sg_deployment_id, \
+                        indexer1_graph_node_version AS v1, \
+                        indexer2_graph_node_version AS v2 \
+                    FROM bisection_runs \
+                    WHERE error IS NULL AND ($1 IS NULL OR created_at >= $1) \
+                ) runs \
+            ) pairs \
+            GROUP BY version1, version2 \
+            ORDER BY bisection_runs DESC, version1 NULLS LAST, version2 NULLS LAST",
+        )
+        .bind::<Nullable<Timestamp>, _>(since);
+
+        Ok(query.load(&mut self.conn().await?).await?)
+    }
+
+    /// The graph-node versions that an indexer ran, oldest first.
+    pub async fn indexer_graph_node_version_history(
+        &self,
+        indexer_id: IntId,
+    ) -> anyhow::Result<Vec<models::IndexerGraphNodeVersion>> {
+        use schema::indexer_graph_node_versions as ignv;
+
+        Ok(ignv::table
+            .select(models::IndexerGraphNodeVersion::as_select())
+            .filter(ignv::indexer_id.eq(indexer_id))
+            .order_by(ignv::valid_from)
+            .load(&mut self.conn().await?)
+            .await?)
+    }
+
+    /// How many indexers ran each graph-node version and commit at `at`, or
+    /// currently, most common first. Archived indexers are only counted for
+    /// past points in time.
+    pub async fn graph_node_version_distribution(
+        &self,
+        at: Option<chrono::NaiveDateTime>,
+    ) -> anyhow::Result<Vec<models::GraphNodeVersionCount>> {
+        use diesel::dsl::count_distinct;
+        use schema::{indexer_graph_node_versions as ignv, indexers};
+
+        let indexer_count = count_distinct(ignv::indexer_id);
+        let mut query = ignv::table
+            .inner_join(indexers::table)
+            .group_by((ignv::version_string, ignv::version_commit))
+            .select((ignv::version_string, ignv::version_commit, indexer_count))
+            .order_by((
+                indexer_count.desc(),
+                ignv::version_string,
+                ignv::version_commit,
+            ))
+            .into_boxed();
+        query = match at {
+            Some(at) => query.filter(
+                ignv::valid_from
+                    .le(at)
+                    .and(ignv::valid_to.is_null().or(ignv::valid_to.gt(at))),
+            ),
+            None => query
+                .filter(ignv::valid_to.is_null())
+                .filter(indexers::archived_at.is_null()),
+        };
+
+        Ok(query.load(&mut self.conn().await?).await?)
     }
 
+    /// Queues a divergence investigation request. Requests with a higher
+    /// `priority` are claimed first.
     pub async fn create_divergence_investigation_request(
         &self,
         request: serde_json::Value,
+        priority: i32,
     ) -> anyhow::Result<Uuid> {
         use schema::pending_divergence_investigation_requests as requests;
 
         let uuid = uuid::Uuid::new_v4();
+        let now = self.clock.now().naive_utc();
         diesel::insert_into(requests::table)
-            .values((requests::uuid.eq(&uuid), requests::request.eq(&request)))
+            .values((
+                requests::uuid.eq(&uuid),
+                requests::request.eq(&request),
+                requests::priority.eq(priority),
+                requests::created_at.eq(now),
+                requests::next_attempt_at.eq(now),
+            ))
             .execute(&mut self.conn().await?)
             .await?;
 
         Ok(uuid)
     }
 
-    pub async fn create_or_update_divergence_investigation_report(
+    /// Claims the next divergence investigation request of the job queue for
+    /// `worker`, if any is due. Requests are claimed by priority, then in the
+    /// order they were created. Rows are locked with `SKIP LOCKED`, so that
+    /// several workers, e.g. of different Graphix replicas, never claim the
+    /// same request.
+    ///
+    /// Running requests whose heartbeat is older than `stale_after` are
+    /// claimed again, since their worker likely died; those that already used
+    /// up their `max_attempts` fail instead.
+    pub async fn claim_divergence_investigation_job(
+        &self,
+        worker: &str,
+        stale_after: std::time::Duration,
+        max_attempts: i32,
+    ) -> anyhow::Result<Option<DivergenceInvestigationJob>> {
+        use schema::pending_divergence_investigation_requests as requests;
+
+        let now = self.clock.now().naive_utc();
+        let stale_before = now - chrono::Duration::from_std(stale_after)?;
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    diesel::update(requests::table)
+                        .filter(requests::status.eq(JobStatus::Running))
+                        .filter(requests::heartbeat_at.lt(stale_before))
+                        .filter(requests::attempts.ge(max_attempts))
+                        .set((
+                            requests::status.eq(JobStatus::Failed),
+                            requests::locked_by.eq(None::<String>),
+                            requests::last_error.eq("The worker stopped sending heartbeats"),
+                        ))
+                        .execute(conn)
+                        .await?;
+
+                    let Some(uuid) = requests::table
+                        .select(requests::uuid)
+                        .filter(
+                            requests::status
+                                .eq(JobStatus::Queued)
+                                .and(requests::next_attempt_at.le(now))
+                                .or(requests::status
+                                    .eq(JobStatus::Running)
+                                    .and(requests::heartbeat_at.lt(stale_before))),
+                        )
+                        .order_by((requests::priority.desc(), requests::created_at))
+                        .for_update()
+                        .skip_locked()
+                        .first::<Uuid>(conn)
+                        .await
+                        .optional()?
+                    else {
+                        return Ok(None);
+                    };
+
+                    let job = diesel::update(requests::table)
+                        .filter(requests::uuid.eq(uuid))
+                        .set((
+                            requests::status.eq(JobStatus::Running),
+                            requests::attempts.eq(requests::attempts + 1),
+                            requests::locked_by.eq(worker),
+                            requests::heartbeat_at.eq(now),
+                        ))
+                        .returning(DivergenceInvestigationJob::as_returning())
+                        .get_result(conn)
+                        .await?;
+                    Ok(Some(job))
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Tells other workers that `worker` is still running a divergence
+    /// investigation request. Returns `false` if the request was claimed by
+    /// another worker in the meantime, or if it no longer exists.
+    pub async fn heartbeat_divergence_investigation_job(
         &self,
         uuid: &Uuid,
-        report: serde_json::Value,
-    ) -> anyhow::Result<()> {
-        use schema::divergence_investigation_reports as reports;
+        worker: &str,
+    ) -> anyhow::Result<bool> {
+        use schema::pending_divergence_investigation_requests as requests;
 
-        diesel::insert_into(reports::table)
-            .values((reports::uuid.eq(&uuid), reports::report.eq(&report)))
-            .on_conflict(reports::uuid)
-            .do_update()
-            .set(reports::report.eq(&report))
+        let now = self.clock.now().naive_utc();
+        let updated = diesel::update(requests::table)
+            .filter(requests::uuid.eq(uuid))
+            .filter(requests::status.eq(JobStatus::Running))
+            .filter(requests::locked_by.eq(worker))
+            .set(requests::heartbeat_at.eq(now))
             .execute(&mut self.conn().await?)
             .await?;
 
-        Ok(())
+        Ok(updated > 0)
+    }
+
+    /// Records a failed attempt of `worker` at a divergence investigation
+    /// request. It's retried after `backoff`, which doubles with each failed
+    /// attempt, unless it already used up its `max_attempts`.
+    pub async fn fail_divergence_investigation_job(
+        &self,
+        uuid: &Uuid,
+        worker: &str,
+        error: &str,
+        max_attempts: i32,
+        backoff: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        use schema::pending_divergence_investigation_requests as requests;
+
+        let uuid = *uuid;
+        let now = self.clock.now().naive_utc();
+        self.conn()
+            .await?
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let Some(attempts) = requests::table
+                        .select(requests::attempts)
+                        .filter(requests::uuid.eq(uuid))
+                        .filter(requests::locked_by.eq(worker))
+                        .for_update()
+                        .get_result::<i32>(conn)
+                        .await
+                        .optional()?
+                    else {
+                        // Another worker took over in the meantime.
+                        return Ok(());
+                    };
+
+                    let (status, next_attempt_at) = if attempts >= max_attempts {
+                        (JobStatus::Failed, now)
+                    } else {
+                        let exponent = attempts.clamp(1, 16) as u32 - 1;
+                        let backoff = chrono::Duration::from_std(backoff * 2u32.pow(exponent))?;
+                        (JobStatus::Queued, now + backoff)
+                    };
+                    diesel::update(requests::table)
+                        .filter(requests::uuid.eq(uuid))
+                        .set((
+                            requests::status.eq(status),
+                            requests::locked_by.eq(None::<String>),
+                            requests::next_attempt_at.eq(next_attempt_at),
+                            requests::last_error.eq(error),
+                        ))
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Writes a (possibly partial) divergence investigation report. Bisection
+    /// runs are written only once, so they must be complete by the time they
+    /// are added to the report.
+    pub async fn create_or_update_divergence_investigation_report(
+        &self,
+        report: &DivergenceInvestigationReport,
+    ) -> anyhow::Result<()> {
+        self.observe("create_or_update_divergence_investigation_report", async {
+            use schema::{
+                bisection_runs, bisection_steps, divergence_investigation_reports as reports,
+            };
+
+            let now = self.clock.now().naive_utc();
+            self.conn()
+                .await?
+                .transaction::<_, Error, _>(|conn| {
+                    async move {
+                        diesel::insert_into(reports::table)
+                            .values((
+                                reports::uuid.eq(&report.uuid),
+                                reports::error.eq(&report.error),
+                                reports::created_at.eq(now),
+                            ))
+                            .on_conflict(reports::uuid)
+                            .do_update()
+                            .set(reports::error.eq(&report.error))
+                            .execute(conn)
+                            .await?;
+
+                        for run in &report.bisection_runs {
+                            let poi1 = diesel_queries::poi_by_value(conn, &run.poi1).await?;
+                            let poi2 = diesel_queries::poi_by_value(conn, &run.poi2).await?;
+                            let new_run = models::NewBisectionRun {
+                                uuid: run.uuid,
+                                investigation_uuid: report.uuid,
+                                poi1: run.poi1,
+                                poi2: run.poi2,
+                                poi1_id: poi1.as_ref().map(|poi| poi.id),
+                                poi2_id: poi2.as_ref().map(|poi| poi.id),
+                                sg_deployment_id: poi1.as_ref().map(|poi| poi.sg_deployment_id),
+                                block_id: poi1.as_ref().map(|poi| poi.block_id),
+                                divergence_lower_bound: run
+                                    .divergence_block_bounds
+                                    .lower_bound
+                                    .number,
+                                divergence_upper_bound: run
+                                    .divergence_block_bounds
+                                    .upper_bound
+                                    .number,
+                                indexer1_operator_contact: run.indexer1_operator_contact.clone(),
+                                indexer2_operator_contact: run.indexer2_operator_contact.clone(),
+                                error: run.error.clone(),
+                                created_at: now,
+                                entity_diff: run
+                                    .entity_diff
+                                    .as_ref()
+                                    .map(serde_json::to_value)
+                                    .transpose()?,
+                                eth_call_diff: run
+                                    .eth_call_diff
+                                    .as_ref()
+                                    .map(serde_json::to_value)
+                                    .transpose()?,
+                                graft_divergence: run
+                                    .graft_divergence
+                                    .as_ref()
+                                    .map(serde_json::to_value)
+                                    .transpose()?,
+                                indexer1_graph_node_version: run
+                                    .indexer1_graph_node_version
+                                    .clone(),
+                                indexer2_graph_node_version: run
+                                    .indexer2_graph_node_version
+                                    .clone(),
+                            };
+
+                            let run_id: Option<IntId> = diesel::insert_into(bisection_runs::table)
+                                .values(new_run)
+                                .on_conflict(bisection_runs::uuid)
+                                .do_nothing()
+                                .returning(bisection_runs::id)
+                                .get_result(conn)
+                                .await
+                                .optional()?;
+                            // Already written.
+                            let Some(run_id) = run_id else {
+                                continue;
+                            };
+
+                            let new_steps: Vec<_> = run
+                                .bisects
+                                .iter()
+                                .map(|step| models::NewBisectionStep {
+                                    bisection_run_id: run_id,
+                                    block_number: step.block.number,
+                                    block_hash: step.block.hash.clone(),
+                                    indexer1_response: step.indexer1_response.clone(),
+                                    indexer2_response: step.indexer2_response.clone(),
+                                })
+                                .collect();
+                            diesel::insert_into(bisection_steps::table)
+                                .values(new_steps)
+                                .execute(conn)
+                                .await?;
+                        }
+
+                        Ok(())
+                    }
+                    .scope_boxed()
+                })
+                .await
+        })
+        .await
     }
 
+    /// Removes a divergence investigation request from the job queue, once
+    /// it's completed.
     pub async fn delete_divergence_investigation_request(&self, uuid: &Uuid) -> anyhow::Result<()> {
         use schema::pending_divergence_investigation_requests as requests;
 
@@ -0,0 +1,132 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use diesel::sql_types::Double;
+use diesel::QueryableByName;
+use diesel_async::pooled_connection::deadpool::{Object, Pool};
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use tracing::warn;
+
+use super::Store;
+
+/// How long the replication lag of a read replica is trusted before it's
+/// measured again.
+const LAG_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A read replica of the database, see [`Store::with_read_replica`].
+#[derive(Clone)]
+pub(super) struct ReadReplica {
+    pool: Pool<AsyncPgConnection>,
+    max_lag: Duration,
+    /// When the replica was last checked, and whether it could be used.
+    last_check: Arc<Mutex<Option<(Instant, bool)>>>,
+}
+
+#[derive(QueryableByName)]
+struct Lag {
+    #[diesel(sql_type = Double)]
+    lag_seconds: f64,
+}
+
+impl ReadReplica {
+    pub(super) fn new(db_url: &str, max_lag: Duration) -> anyhow::Result<Self> {
+        let manager = AsyncDieselConnectionManager::new(db_url);
+        let pool = Pool::builder(manager).build()?;
+
+        Ok(Self {
+            pool,
+            max_lag,
+            last_check: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// How far the replica is behind the primary database. Replicas that
+    /// replayed everything they received aren't lagging behind, even if the
+    /// primary hasn't written anything in a while; the same goes for
+    /// databases that aren't replicas at all.
+    async fn lag(conn: &mut AsyncPgConnection) -> anyhow::Result<Duration> {
+        let Lag { lag_seconds } = diesel::sql_query(
+            "SELECT CASE
+                WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                ELSE COALESCE(EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp()), 0)
+            END::FLOAT8 AS lag_seconds",
+        )
+        .get_result(conn)
+        .await?;
+
+        Ok(Duration::from_secs_f64(lag_seconds.max(0.0)))
+    }
+}
+
+impl Store {
+    /// Sends heavy read queries, e.g. of PoIs, to the read replica at
+    /// `db_url`, unless its replication lag exceeds `max_lag` or it can't be
+    /// reached, in which case they fall back to the primary database. All
+    /// other queries, and all writes, always go to the primary database.
+    pub fn with_read_replica(mut self, db_url: &str, max_lag: Duration) -> anyhow::Result<Self> {
+        self.read_replica = Some(ReadReplica::new(db_url, max_lag)?);
+        Ok(self)
+    }
+
+    /// A connection for read queries that can tolerate data that's slightly
+    /// out of date: to the read replica if there's one that's usable, and to
+    /// the primary database otherwise.
+    pub(super) async fn read_conn(&self) -> anyhow::Result<Object<AsyncPgConnection>> {
+        let Some(replica) = &self.read_replica else {
+            return self.conn().await;
+        };
+
+        let last_check = *replica.last_check.lock().unwrap();
+        match last_check {
+            Some((checked_at, true)) if checked_at.elapsed() < LAG_CHECK_INTERVAL => {
+                if let Ok(conn) = replica.pool.get().await {
+                    return Ok(conn);
+                }
+            }
+            Some((checked_at, false)) if checked_at.elapsed() < LAG_CHECK_INTERVAL => {}
+            _ => {
+                if let Some(conn) = self.check_read_replica(replica).await {
+                    return Ok(conn);
+                }
+            }
+        }
+
+        self.conn().await
+    }
+
+    /// Measures the replication lag of `replica`, and returns a connection to
+    /// it if it's usable.
+    async fn check_read_replica(&self, replica: &ReadReplica) -> Option<Object<AsyncPgConnection>> {
+        let result = async {
+            let mut conn = replica.pool.get().await?;
+            let lag = ReadReplica::lag(&mut conn).await?;
+            anyhow::Ok((conn, lag))
+        }
+        .await;
+
+        let conn = match result {
+            Ok((conn, lag)) => {
+                if let Some(metrics) = &self.metrics {
+                    metrics.read_replica_lag_seconds.set(lag.as_secs_f64());
+                }
+                if lag <= replica.max_lag {
+                    Some(conn)
+                } else {
+                    warn!(
+                        lag_seconds = lag.as_secs(),
+                        "Read replica is lagging behind, using the primary database"
+                    );
+                    None
+                }
+            }
+            Err(err) => {
+                warn!(error = %err, "Read replica is unavailable, using the primary database");
+                None
+            }
+        };
+
+        *replica.last_check.lock().unwrap() = Some((Instant::now(), conn.is_some()));
+        conn
+    }
+}
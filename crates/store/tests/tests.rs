@@ -1,7 +1,50 @@
 mod common;
 
-use graphix_common_types::inputs::SgDeploymentsQuery;
-use graphix_store::models::{Network, NewNetwork};
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Duration};
+use futures::TryStreamExt;
+use graphix_common_types::inputs::{
+    BisectionRunsQuery, BlockRange, EventsQuery, IndexersQuery, PoisQuery, SgDeploymentsQuery,
+};
+use graphix_common_types::{
+    ApiKeyPermissionLevel, BisectionReport, BisectionRunReport, ChangedEthCall, Clock,
+    DeploymentEventKind, DeploymentLifecycleStatus, DeploymentName, DivergenceBlockBounds,
+    DivergenceInvestigationReport, DivergenceInvestigationStatus, EntityChange, EntityDiff,
+    EthCallDiff, EventKind, Graft, GraftDivergence, GraphNodeCollectedVersion, IndexerAddress,
+    IndexerErrorClass, IpfsCid, JobStatus, LatencyProbe, MockClock, PartialBlock, PoiSource,
+    SubgraphHealth, SubgraphManifest,
+};
+use graphix_indexer_client::{
+    classify_error, BlockPointer, CircuitBreakerConfig, IndexerClient, IndexingStatus,
+    ProofOfIndexing, RealIndexer, SubgraphError, WithIndexer,
+};
+use graphix_lib::alerts::AlertRouter;
+use graphix_lib::block_choice::{self, BlockChoicePolicy};
+use graphix_lib::campaigns::{self, CronSchedule};
+use graphix_lib::config::{
+    AlertsConfig, CampaignConfig, Config, ConfigSource, IndexerConfig, NetworkSubgraphConfig,
+    WatchlistConfig,
+};
+use graphix_lib::graphql_api::{request_schema, GraphixState};
+use graphix_lib::leader_election::LeaderElection;
+use graphix_lib::test_utils::graph_node::{canonical_poi, MockDeployment, MockGraphNode};
+use graphix_lib::test_utils::mocks::{DeploymentDetails, MockIndexer, PartialProofOfIndexing};
+use graphix_lib::test_utils::{deployments, ipfs_cid};
+use graphix_lib::{indexing_loop, network_subgraph_metadata, poi_backfill, substreams, watchlists};
+use graphix_store::models::{
+    ApiKey, IndexerAllocation, Network, NewEvent, NewFeedPoiConsensus, NewFeedPoiDivergence,
+    NewIndexerError, NewIndexerNetworkSubgraphMetadata, NewLatencySample, NewNetwork,
+    NewNetworkEpoch, NewOnchainPoi, NewWatchlist,
+};
+use graphix_store::{PoiLiveness, StoreMetrics, WebhookLimitReached};
+use quickcheck::Arbitrary;
+use tokio::sync::watch;
+use uuid::Uuid;
 
 use crate::common::EmptyStoreForTesting;
 
@@ -34,7 +77,8 @@ async fn create_then_delete_network() {
         vec![Network {
             id: 1,
             name: "mainnet".to_string(),
-            caip2: Some("eip155:1".to_string())
+            caip2: Some("eip155:1".to_string()),
+            enabled: true,
         }]
     );
 
@@ -44,175 +88,5505 @@ async fn create_then_delete_network() {
 }
 
 #[tokio::test]
-#[should_panic] // FIXME
-async fn deployments_with_name() {
+async fn disable_then_enable_network() {
     let store = EmptyStoreForTesting::new().await.unwrap();
 
-    let ipfs_cid1 = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
-    let ipfs_cid2 = "QmYzsCjrVwwXtdsNm3PZVNziLGmb9o513GUzkq5wwhgXDT";
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+    assert!(store.disabled_networks().await.unwrap().is_empty());
+
+    let network = store
+        .set_network_enabled("mainnet", false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!network.enabled);
+    assert_eq!(store.disabled_networks().await.unwrap(), vec!["mainnet"]);
+
+    store.set_network_enabled("mainnet", true).await.unwrap();
+    assert!(store.disabled_networks().await.unwrap().is_empty());
+
+    // Unknown networks are reported as such.
+    assert!(store
+        .set_network_enabled("arbitrum-one", false)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn indexers_and_deployments_are_attributed_to_network_subgraphs() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let address = IndexerAddress::from([1; 20]);
+    let indexer = |network_subgraph: Option<&str>| -> Arc<dyn IndexerClient> {
+        Arc::new(
+            RealIndexer::new(
+                Some("indexer".to_string()),
+                address,
+                "http://localhost:8030/graphql".to_string(),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            )
+            .with_network_subgraph(network_subgraph.map(str::to_string)),
+        )
+    };
 
+    // The same indexer is found on both network subgraphs.
+    let configured = [indexer(Some("arbitrum-one")), indexer(Some("mainnet"))];
+    store.write_indexers(&configured).await.unwrap();
     store
-        .create_sg_deployment("mainnet", ipfs_cid1)
+        .write_indexer_network_subgraphs(&configured)
         .await
         .unwrap();
+    let indexers = store.indexers(IndexersQuery::default()).await.unwrap();
+    assert_eq!(indexers.len(), 1);
+    let network_subgraphs = || async {
+        store
+            .indexer_network_subgraphs(&[indexers[0].id])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|attribution| attribution.network_subgraph)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(network_subgraphs().await, ["arbitrum-one", "mainnet"]);
+
+    // Attributions survive a network subgraph that fails to respond.
     store
-        .create_sg_deployment("mainnet", ipfs_cid2)
+        .write_indexer_network_subgraphs(&[indexer(Some("mainnet")), indexer(None)])
         .await
         .unwrap();
-    store.set_deployment_name(ipfs_cid2, "foo").await.unwrap();
+    assert_eq!(network_subgraphs().await, ["arbitrum-one", "mainnet"]);
 
-    let deployments = {
-        let filter = SgDeploymentsQuery {
-            name: Some("foo".to_string()),
-            ..Default::default()
-        };
-        store.sg_deployments(filter).await.unwrap()
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let unknown_deployment =
+        IpfsCid::from_str("QmYzsCjrVwwXtdsNm3PZVNziLGmb9o513GUzkq5wwhgXDT").unwrap();
+    let poi = ProofOfIndexing {
+        indexer: configured[0].clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: 42,
+            hash: Some(vec![42; 32].into()),
+        },
+        proof_of_indexing: [1; 32].into(),
     };
-    assert!(deployments.len() == 1);
-    assert_eq!(deployments[0].cid.to_string(), ipfs_cid1);
-    // FIXME:
-    //assert_eq!(deployments[0].name, Some("foo".to_string()));
+    store
+        .write_pois(vec![poi], PoiLiveness::Live)
+        .await
+        .unwrap();
+    for network_subgraph in ["mainnet", "arbitrum-one", "mainnet"] {
+        store
+            .write_sg_deployment_network_subgraphs(
+                network_subgraph,
+                &[deployment.clone(), unknown_deployment.clone()],
+            )
+            .await
+            .unwrap();
+    }
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let response = request_schema(state, None)
+        .execute(
+            r#"{
+                indexers { networkSubgraphs }
+                deployments { cid networkSubgraphs }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "indexers": [{ "networkSubgraphs": ["arbitrum-one", "mainnet"] }],
+            "deployments": [{
+                "cid": deployment.to_string(),
+                "networkSubgraphs": ["arbitrum-one", "mainnet"],
+            }],
+        })
+    );
 }
 
 #[tokio::test]
-async fn create_divergence_investigation_request() {
+async fn indexer_circuits_are_recorded() {
     let store = EmptyStoreForTesting::new().await.unwrap();
+    let address = IndexerAddress::from([42; 20]);
+    let indexer = |circuit_breaker| -> Arc<dyn IndexerClient> {
+        Arc::new(
+            RealIndexer::new(
+                Some("indexer".to_string()),
+                address,
+                // Nothing listens on port 1.
+                "http://127.0.0.1:1/graphql".to_string(),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            )
+            .with_circuit_breaker(
+                circuit_breaker,
+                graphix_lib::metrics()
+                    .indexer_circuit_breaker_events
+                    .clone(),
+            ),
+        )
+    };
+    let circuit_open_until =
+        || async { store.indexers(IndexersQuery::default()).await.unwrap()[0].circuit_open_until };
 
-    let uuid = store
-        .create_divergence_investigation_request(serde_json::json!({}))
+    let indexer_with_circuit_breaker = indexer(Some(CircuitBreakerConfig {
+        failure_threshold: 1,
+        cooldown_in_seconds: 300,
+    }));
+    store
+        .write_indexers(&[indexer_with_circuit_breaker.clone()])
         .await
         .unwrap();
+    assert!(indexer_with_circuit_breaker.clone().ping().await.is_err());
+    let err = indexer_with_circuit_breaker
+        .clone()
+        .ping()
+        .await
+        .unwrap_err();
+    assert_eq!(classify_error(&err), IndexerErrorClass::CircuitOpen);
 
-    let req = store
-        .get_first_pending_divergence_investigation_request()
+    store
+        .write_indexer_circuits(&[indexer_with_circuit_breaker])
+        .await
+        .unwrap();
+    assert!(circuit_open_until().await.is_some());
+
+    store
+        .write_indexer_circuits(&[indexer(None)])
         .await
-        .unwrap()
         .unwrap();
-    assert_eq!(req.0, uuid);
+    assert!(circuit_open_until().await.is_none());
 }
 
-//#[tokio::test]
-//async fn poi_db_roundtrip() {
-//    let docker_cli = Cli::default();
-//    let store = EmptyStoreForTesting::new(&docker_cli).await.unwrap();
-//
-//    let mut rng = fast_rng(0);
-//    let indexers = gen_indexers(&mut rng, 100);
-//
-//    let indexing_statuses = queries::query_indexing_statuses(indexers, metrics()).await;
-//    let pois =
-//        queries::query_proofs_of_indexing(indexing_statuses, BlockChoicePolicy::Earliest).await;
-//
-//    let mut conn = store.conn().unwrap();
-//    conn.test_transaction(|conn| test_pois(conn, &pois, PoiLiveness::NotLive, false));
-//    conn.test_transaction(|conn| test_pois(conn, &pois, PoiLiveness::Live, true));
-//}
+#[tokio::test]
+async fn manual_operator_contact_takes_precedence() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let address = IndexerAddress::from([1; 20]);
+    let indexer: Arc<dyn IndexerClient> = Arc::new(
+        RealIndexer::new(
+            Some("indexer".to_string()),
+            address,
+            "http://localhost:8030/graphql".to_string(),
+            graphix_lib::metrics()
+                .public_proofs_of_indexing_requests
+                .clone(),
+        )
+        .with_operator_contact(Some("https://indexer.example".to_string())),
+    );
 
-//#[tokio::test]
-//async fn test_additional_pois() {
-//    let docker_cli = Cli::default();
-//    let store = EmptyStoreForTesting::new(&docker_cli).await.unwrap();
-//
-//    let mut rng = fast_rng(0);
-//    let indexers = gen_indexers(&mut rng, 100);
-//
-//    let indexing_statuses = queries::query_indexing_statuses(indexers, metrics()).await;
-//    let pois =
-//        queries::query_proofs_of_indexing(indexing_statuses, BlockChoicePolicy::Earliest).await;
-//
-//    let mut conn = store.conn().unwrap();
-//
-//    conn.test_transaction(|conn| -> Result<(), anyhow::Error> {
-//        // Write the original PoIs as Live
-//        diesel_queries::write_pois(conn, &pois, PoiLiveness::Live).unwrap();
-//
-//        // Choose a deployment to add an additional PoI
-//        let mut additional_poi = pois[0].clone(); // clone one of the original PoIs
-//        additional_poi.block.number += 1; // bump the number to N + 1
-//        additional_poi.block.hash = Some(gen_bytes32(&mut rng));
-//        additional_poi.proof_of_indexing = gen_bytes32(&mut rng); // Optionally, set new PoI value if needed
-//
-//        // Write the additional PoI
-//        diesel_queries::write_pois(conn, &[additional_poi.clone()], PoiLiveness::Live).unwrap();
-//
-//        // Query the live PoIs for the specific deployment
-//        let specific_deployment_pois = diesel_queries::pois(
-//            conn,
-//            None,
-//            Some(&[additional_poi.deployment.0.clone()]),
-//            None,
-//            None,
-//            true,
-//        )
-//        .unwrap();
-//
-//        // Assert that only the new PoI exists as live PoIs for that deployment
-//        assert_eq!(specific_deployment_pois.len(), 1);
-//        assert_eq!(
-//            specific_deployment_pois[0].block.number as u64,
-//            additional_poi.block.number
-//        );
-//
-//        Ok(())
-//    });
-//}
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+    let indexers = store.indexers(IndexersQuery::default()).await.unwrap();
+    assert_eq!(
+        indexers[0].operator_contact(),
+        Some("https://indexer.example")
+    );
 
-//fn test_pois(
-//    conn: &mut diesel::PgConnection,
-//    pois: &[ProofOfIndexing],
-//    liveness: PoiLiveness,
-//    live_poi_test: bool,
-//) -> Result<(), anyhow::Error> {
-//    diesel_queries::write_pois(conn, pois, liveness).unwrap();
-//    let all_deployments: Vec<String> = pois.iter().map(|poi| poi.deployment.0.clone()).collect();
-//
-//    // Common logic to create poi_triples
-//    let poi_triples: BTreeSet<(String, String, Vec<u8>)> = pois
-//        .iter()
-//        .map(|poi| {
-//            (
-//                poi.deployment.0.clone(),
-//                poi.indexer.address_string(),
-//                poi.proof_of_indexing.0.to_vec(),
-//            )
-//        })
-//        .collect();
-//    let read_pois = diesel_queries::pois(
-//        conn,
-//        None,
-//        Some(&all_deployments),
-//        None,
-//        None,
-//        live_poi_test,
-//    )
-//    .unwrap();
-//    let read_poi_triples: BTreeSet<(String, String, Vec<u8>)> = read_pois
-//        .into_iter()
-//        .map(|poi| (poi.sg_deployment.cid, poi.indexer.name.unwrap(), poi.poi))
-//        .collect();
-//
-//    assert!(poi_triples == read_poi_triples);
-//
-//    if live_poi_test {
-//        // Specific test for live pois
-//        assert_eq!(
-//            diesel_queries::pois(conn, None, Some(&all_deployments), None, None, true)
-//                .unwrap()
-//                .into_iter()
-//                .map(|poi| poi.id)
-//                .collect::<Vec<_>>(),
-//            diesel_queries::pois(conn, None, Some(&all_deployments), None, None, false)
-//                .unwrap()
-//                .into_iter()
-//                .map(|poi| poi.id)
-//                .collect::<Vec<_>>()
-//        );
-//    } else {
-//        // Specific test for not live pois
-//        let live_pois =
-//            diesel_queries::pois(conn, None, Some(&all_deployments), None, None, true).unwrap();
-//        assert!(live_pois.is_empty());
-//    }
-//
-//    Ok(())
-//}
+    let updated = store
+        .set_indexer_operator_contact(&address, Some("ops@indexer.example"))
+        .await
+        .unwrap();
+    assert_eq!(updated.operator_contact(), Some("ops@indexer.example"));
+
+    // Rewriting the indexer doesn't override the manual contact.
+    store.write_indexers(&[indexer]).await.unwrap();
+    let indexers = store.indexers(IndexersQuery::default()).await.unwrap();
+    assert_eq!(indexers[0].operator_contact(), Some("ops@indexer.example"));
+
+    let cleared = store
+        .set_indexer_operator_contact(&address, None)
+        .await
+        .unwrap();
+    assert_eq!(cleared.operator_contact(), Some("https://indexer.example"));
+
+    assert!(store
+        .set_indexer_operator_contact(&IndexerAddress::from([2; 20]), None)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn write_then_overwrite_indexing_statuses() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    // Newly discovered deployments are assigned to the network with ID 1.
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://localhost:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let status = |health, paused| IndexingStatus {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        latest_block: BlockPointer {
+            number: 1,
+            hash: None,
+        },
+        earliest_block_num: 0,
+        health,
+        synced: false,
+        paused,
+        errors: vec![],
+    };
+
+    store
+        .write_indexing_statuses(&[status(SubgraphHealth::Healthy, Some(true))])
+        .await
+        .unwrap();
+    clock.advance(Duration::minutes(2));
+    store
+        .write_indexing_statuses(&[status(SubgraphHealth::Failed, Some(false))])
+        .await
+        .unwrap();
+
+    let statuses = store
+        .indexing_statuses(&[deployment], None, None)
+        .await
+        .unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].health, SubgraphHealth::Failed);
+    assert_eq!(statuses[0].paused, Some(false));
+    assert!(!statuses[0].synced);
+    assert_eq!(statuses[0].updated_at, clock.now().naive_utc());
+}
+
+#[tokio::test]
+async fn indexing_status_changes_are_kept_in_history() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://localhost:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let status = |latest_block, health| IndexingStatus {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        latest_block: BlockPointer {
+            number: latest_block,
+            hash: None,
+        },
+        earliest_block_num: 0,
+        health,
+        synced: true,
+        paused: Some(false),
+        errors: vec![],
+    };
+
+    // Only the first status and the change of health are kept in the
+    // history, while the latest status is always overwritten.
+    for (latest_block, health) in [
+        (1, SubgraphHealth::Healthy),
+        (2, SubgraphHealth::Healthy),
+        (3, SubgraphHealth::Failed),
+    ] {
+        store
+            .write_indexing_statuses(&[status(latest_block, health)])
+            .await
+            .unwrap();
+        clock.advance(Duration::minutes(2));
+    }
+
+    let statuses = store
+        .indexing_statuses(&[deployment.clone()], None, None)
+        .await
+        .unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].latest_block_number, Some(3));
+
+    let history = store
+        .indexing_status_history(Some(statuses[0].indexer_id), None, 10)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].health, SubgraphHealth::Failed);
+    assert_eq!(history[0].latest_block_number, 3);
+    assert_eq!(history[1].health, SubgraphHealth::Healthy);
+    assert_eq!(history[1].latest_block_number, 1);
+
+    let history = store
+        .indexing_status_history(None, Some(statuses[0].sg_deployment_id), 1)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].health, SubgraphHealth::Failed);
+
+    // Block choices are replayed at every change, and on the latest status.
+    let replayed = block_choice::replay(&store, &[deployment.clone()])
+        .await
+        .unwrap();
+    assert_eq!(replayed.len(), 1);
+    let blocks: Vec<_> = replayed[0]
+        .snapshots
+        .iter()
+        .map(|snapshot| (snapshot.at.is_some(), snapshot.choices[0].block))
+        .collect();
+    assert_eq!(blocks, [(true, Some(1)), (true, Some(3)), (false, Some(3))]);
+}
+
+#[tokio::test]
+async fn indexing_lags_are_measured_against_chain_heads() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let status = |indexer: &Arc<dyn IndexerClient>, latest_block| IndexingStatus {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        latest_block: BlockPointer {
+            number: latest_block,
+            hash: None,
+        },
+        earliest_block_num: 0,
+        health: SubgraphHealth::Healthy,
+        synced: true,
+        paused: Some(false),
+        errors: vec![],
+    };
+    store
+        .write_indexing_statuses(&[status(&indexers[0], 100), status(&indexers[1], 40)])
+        .await
+        .unwrap();
+
+    // Without a chain head, lags can't be measured.
+    assert!(store.indexing_lags(None, None).await.unwrap().is_empty());
+
+    let reported_heads = store.reported_chain_heads().await.unwrap();
+    assert_eq!(reported_heads, vec![("mainnet".to_string(), 100)]);
+    store
+        .write_chain_heads(&[("mainnet".to_string(), 110), ("unknown".to_string(), 1)])
+        .await
+        .unwrap();
+
+    let lags = store.indexing_lags(None, None).await.unwrap();
+    assert_eq!(lags.len(), 2);
+    assert_eq!(lags[0].indexer_address, indexers[1].address());
+    assert_eq!(lags[0].blocks_behind(), 70);
+    assert_eq!(lags[1].blocks_behind(), 10);
+
+    let lags = store.indexing_lags(None, Some(1)).await.unwrap();
+    assert_eq!(lags.len(), 1);
+    assert_eq!(lags[0].blocks_behind(), 70);
+
+    let lags = store
+        .indexing_lags(Some(&indexers[0].address()), None)
+        .await
+        .unwrap();
+    assert_eq!(lags.len(), 1);
+    assert_eq!(lags[0].network, "mainnet");
+    assert_eq!(lags[0].chain_head_block_number, 110);
+}
+
+#[tokio::test]
+async fn deployment_lifecycle_is_tracked() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://localhost:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let kept = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let dropped = IpfsCid::from_str("QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7").unwrap();
+    let status = |deployment: &IpfsCid, paused| IndexingStatus {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        latest_block: BlockPointer {
+            number: 1,
+            hash: None,
+        },
+        earliest_block_num: 0,
+        health: SubgraphHealth::Healthy,
+        synced: true,
+        paused,
+        errors: vec![],
+    };
+    let deployment = |cid: &IpfsCid| {
+        let store = &store;
+        let cid = cid.clone();
+        async move {
+            store
+                .sg_deployments(SgDeploymentsQuery {
+                    ipfs_cid: Some(cid),
+                    ..Default::default()
+                })
+                .await
+                .unwrap()
+                .remove(0)
+        }
+    };
+
+    let first_seen_at = clock.now().naive_utc();
+    store
+        .write_indexing_statuses(&[status(&kept, Some(false)), status(&dropped, Some(false))])
+        .await
+        .unwrap();
+    clock.advance(Duration::minutes(2));
+    store
+        .write_indexing_statuses(&[status(&kept, Some(true))])
+        .await
+        .unwrap();
+
+    let kept_deployment = deployment(&kept).await;
+    assert_eq!(kept_deployment.first_seen_at, Some(first_seen_at));
+    assert_eq!(kept_deployment.last_seen_at, Some(clock.now().naive_utc()));
+    assert_eq!(
+        kept_deployment.status,
+        Some(DeploymentLifecycleStatus::Paused)
+    );
+    let dropped_deployment = deployment(&dropped).await;
+    assert_eq!(dropped_deployment.last_seen_at, Some(first_seen_at));
+    assert_eq!(
+        dropped_deployment.status,
+        Some(DeploymentLifecycleStatus::Removed)
+    );
+    assert_eq!(
+        store
+            .indexing_statuses(&[dropped.clone()], None, None)
+            .await
+            .unwrap()
+            .len(),
+        0
+    );
+
+    let events = store
+        .sg_deployment_events(&[], None, None, None)
+        .await
+        .unwrap();
+    let kinds = |sg_deployment_id| {
+        events
+            .iter()
+            .filter(|event| event.sg_deployment_id == sg_deployment_id)
+            .map(|event| event.kind)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        kinds(kept_deployment.id),
+        vec![DeploymentEventKind::Paused, DeploymentEventKind::Added]
+    );
+    assert_eq!(
+        kinds(dropped_deployment.id),
+        vec![DeploymentEventKind::Removed, DeploymentEventKind::Added]
+    );
+
+    // Indexers that don't respond don't remove their deployments.
+    clock.advance(Duration::minutes(2));
+    store.write_indexing_statuses(&[]).await.unwrap();
+    assert_eq!(
+        deployment(&kept).await.status,
+        Some(DeploymentLifecycleStatus::Paused)
+    );
+    let removed = store
+        .sg_deployment_events(&[], None, Some(DeploymentEventKind::Removed), None)
+        .await
+        .unwrap();
+    assert_eq!(removed.len(), 1);
+}
+
+#[tokio::test]
+async fn indexing_errors_are_deduplicated() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://localhost:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let error = |message: &str, fatal| SubgraphError {
+        message: message.to_string(),
+        block: Some(BlockPointer {
+            number: 42,
+            hash: Some(vec![1; 32].into()),
+        }),
+        handler: Some("handleTransfer".to_string()),
+        deterministic: true,
+        fatal,
+    };
+    let status = |errors| IndexingStatus {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        latest_block: BlockPointer {
+            number: 42,
+            hash: None,
+        },
+        earliest_block_num: 0,
+        health: SubgraphHealth::Failed,
+        synced: false,
+        paused: Some(false),
+        errors,
+    };
+
+    store
+        .write_indexing_statuses(&[status(vec![error("boom", true)])])
+        .await
+        .unwrap();
+    clock.advance(Duration::minutes(2));
+    store
+        .write_indexing_statuses(&[status(vec![error("boom", true), error("meh", false)])])
+        .await
+        .unwrap();
+
+    let errors = store
+        .indexing_errors(&[deployment.clone()], None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(errors.len(), 2);
+    let fatal = errors.iter().find(|error| error.fatal).unwrap();
+    assert_eq!(fatal.message, "boom");
+    assert_eq!(fatal.block_number, Some(42));
+    assert!(fatal.first_seen_at < fatal.last_seen_at);
+    assert_eq!(fatal.last_seen_at, clock.now().naive_utc());
+
+    let non_fatal = store
+        .indexing_errors(&[deployment], None, Some(false), None)
+        .await
+        .unwrap();
+    assert_eq!(non_fatal.len(), 1);
+    assert_eq!(non_fatal[0].message, "meh");
+}
+
+#[tokio::test]
+async fn cross_check_onchain_pois() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let network_id = store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://localhost:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    store
+        .write_pois(
+            vec![ProofOfIndexing {
+                indexer: indexer.clone(),
+                deployment: deployment.clone(),
+                network: "mainnet".to_string(),
+                block: BlockPointer {
+                    number: 42,
+                    hash: Some(vec![42; 32].into()),
+                },
+                proof_of_indexing: [1; 32].into(),
+            }],
+            PoiLiveness::Live,
+        )
+        .await
+        .unwrap();
+
+    let onchain_poi = |allocation_id: &str, block_number, poi_byte| NewOnchainPoi {
+        allocation_id: allocation_id.to_string(),
+        indexer_address: indexer.address(),
+        deployment: deployment.clone(),
+        poi: [poi_byte; 32].into(),
+        epoch: 1,
+        block_number,
+        network_subgraph: None,
+    };
+    store
+        .write_onchain_pois(&[onchain_poi("0x01", 42, 2), onchain_poi("0x02", 41, 1)])
+        .await
+        .unwrap();
+
+    let cross_checks = store
+        .onchain_poi_cross_checks(&[], None, None, false, None)
+        .await
+        .unwrap();
+    assert_eq!(cross_checks.len(), 2);
+    assert_eq!(cross_checks[0].0.allocation_id, "0x01");
+    assert_eq!(cross_checks[0].1, Some([1; 32].into()));
+    assert_eq!(cross_checks[1].1, None);
+
+    // The mismatch is found even if later on-chain PoIs can't be compared.
+    store
+        .write_onchain_pois(&[onchain_poi("0x03", 43, 1)])
+        .await
+        .unwrap();
+    let mismatches = store
+        .onchain_poi_cross_checks(&[], None, None, true, Some(1))
+        .await
+        .unwrap();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].0.allocation_id, "0x01");
+
+    let on_network =
+        |network| store.onchain_poi_cross_checks(&[], None, Some(network), false, None);
+    assert_eq!(on_network("mainnet").await.unwrap().len(), 3);
+    assert!(on_network("gnosis").await.unwrap().is_empty());
+
+    // On-chain PoIs refer to the start block of their epoch on the
+    // deployment's network.
+    store
+        .write_network_epochs(&[NewNetworkEpoch {
+            network_id,
+            epoch: 1,
+            start_block_number: 40,
+        }])
+        .await
+        .unwrap();
+    assert_eq!(
+        store
+            .sg_deployment_epoch_start_blocks(std::slice::from_ref(&deployment), &[1, 2])
+            .await
+            .unwrap(),
+        vec![(deployment, 1, 40)]
+    );
+}
+
+#[tokio::test]
+async fn indexers_can_be_filtered_by_tag() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: None,
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=3)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let address = IndexerAddress::from([1; 20]);
+    store.add_indexer_tag(&address, "testnet").await.unwrap();
+    store.add_indexer_tag(&address, "own-fleet").await.unwrap();
+    let tagged = store
+        .add_indexer_tag(&address, "testnet")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(tagged.tags, vec!["own-fleet", "testnet"]);
+    store
+        .add_indexer_tag(&IndexerAddress::from([2; 20]), "own-fleet")
+        .await
+        .unwrap();
+    assert!(store
+        .add_indexer_tag(&IndexerAddress::from([9; 20]), "testnet")
+        .await
+        .unwrap()
+        .is_none());
+
+    let filter = IndexersQuery {
+        tag: Some("own-fleet".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(store.indexers(filter).await.unwrap().len(), 2);
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .map(|indexer| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [1; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let pois = store
+        .pois(
+            None,
+            &[deployment.clone()],
+            None,
+            None,
+            Some("own-fleet"),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(pois.len(), 2);
+    let live_pois = store
+        .live_pois(
+            None,
+            None,
+            Some(&[deployment.clone()]),
+            None,
+            Some("testnet"),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(live_pois.len(), 1);
+
+    let untagged = store
+        .remove_indexer_tag(&address, "testnet")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(untagged.tags, vec!["own-fleet"]);
+    let pois = store
+        .pois(None, &[deployment], None, None, Some("testnet"), None)
+        .await
+        .unwrap();
+    assert!(pois.is_empty());
+}
+
+#[tokio::test]
+async fn indexers_can_be_archived_and_deleted() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .map(|indexer| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [1; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let archived_address = IndexerAddress::from([1; 20]);
+    let archived = store
+        .archive_indexer(&archived_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let archived_at = archived.archived_at.unwrap();
+    // Archiving twice keeps the original timestamp.
+    let archived = store
+        .archive_indexer(&archived_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(archived.archived_at, Some(archived_at));
+    assert!(store
+        .archive_indexer(&IndexerAddress::from([9; 20]))
+        .await
+        .unwrap()
+        .is_none());
+
+    assert_eq!(
+        store.archived_indexers().await.unwrap(),
+        vec![archived_address]
+    );
+    assert_eq!(store.indexers(Default::default()).await.unwrap().len(), 1);
+    let filter = IndexersQuery {
+        include_archived: true,
+        ..Default::default()
+    };
+    assert_eq!(store.indexers(filter).await.unwrap().len(), 2);
+
+    // The archived indexer's PoIs are kept, but are no longer live.
+    let pois = store
+        .pois(None, &[deployment.clone()], None, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(pois.len(), 2);
+    let live_pois = store
+        .live_pois(None, None, Some(&[deployment.clone()]), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(live_pois.len(), 1);
+    assert_ne!(live_pois[0].indexer_id, archived.id);
+
+    // Deleting an indexer deletes its PoIs, too.
+    let deleted_address = IndexerAddress::from([2; 20]);
+    assert!(store.delete_indexer(&deleted_address).await.unwrap());
+    assert!(!store.delete_indexer(&deleted_address).await.unwrap());
+    let pois = store
+        .pois(None, &[deployment.clone()], None, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(pois.len(), 1);
+    assert_eq!(pois[0].indexer_id, archived.id);
+}
+
+#[tokio::test]
+async fn identical_pois_share_value() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=4)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .zip([1, 1, 1, 2])
+        .map(|(indexer, poi_byte)| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [poi_byte; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let pois = store
+        .pois(None, &[deployment.clone()], None, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(pois.len(), 4);
+    let poi = pois.iter().find(|poi| poi.poi == [1; 32].into()).unwrap();
+    let unique_poi = pois.iter().find(|poi| poi.poi == [2; 32].into()).unwrap();
+
+    let identical_pois = store
+        .identical_pois(&[poi.id, unique_poi.id], 10)
+        .await
+        .unwrap();
+    assert_eq!(identical_pois.len(), 2);
+    for (poi_id, identical_poi) in &identical_pois {
+        assert_eq!(*poi_id, poi.id);
+        assert_eq!(identical_poi.poi, poi.poi);
+        assert_eq!(identical_poi.poi_value_id, poi.poi_value_id);
+        assert_ne!(identical_poi.indexer_id, poi.indexer_id);
+    }
+
+    let limited = store.identical_pois(&[poi.id], 1).await.unwrap();
+    assert_eq!(limited.len(), 1);
+
+    // The limit is capped by `maxListInputItems`.
+    for (max_list_input_items, expected) in [(100, [0, 2, 2, 2]), (1, [0, 1, 1, 1])] {
+        let config = Config {
+            max_list_input_items,
+            ..Default::default()
+        };
+        let state = Arc::new(GraphixState::new(
+            (*store).clone(),
+            watch::channel(config).1,
+        ));
+        let response = request_schema(state, None)
+            .execute(format!(
+                r#"{{
+                    proofsOfIndexing(deployments: ["{deployment}"]) {{
+                        identicalPois(limit: 10) {{ hash }}
+                    }}
+                }}"#
+            ))
+            .await;
+        assert_eq!(response.errors, vec![]);
+        let data = response.data.into_json().unwrap();
+        let mut counts: Vec<usize> = data["proofsOfIndexing"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|poi| poi["identicalPois"].as_array().unwrap().len())
+            .collect();
+        counts.sort();
+        assert_eq!(counts, expected);
+    }
+}
+
+#[tokio::test]
+async fn live_pois_are_grouped_by_value() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=3)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .zip([1, 1, 2])
+        .map(|(indexer, poi_byte)| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [poi_byte; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let live_pois = store
+        .live_pois(None, None, Some(&[deployment.clone()]), None, None, None)
+        .await
+        .unwrap();
+    let deployment_id = live_pois[0].sg_deployment_id;
+
+    let mut groups = store.live_poi_groups(&[deployment_id]).await.unwrap();
+    groups.sort_by_key(|group| group.poi);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].poi, [1; 32].into());
+    assert_eq!(groups[0].indexer_ids.len(), 2);
+    assert_eq!(groups[1].poi, [2; 32].into());
+    assert_eq!(groups[1].indexer_ids.len(), 1);
+    for group in &groups {
+        let poi = live_pois.iter().find(|poi| poi.id == group.poi_id).unwrap();
+        assert_eq!(poi.poi, group.poi);
+        assert!(group.indexer_ids.contains(&poi.indexer_id));
+    }
+
+    assert!(store.live_poi_groups(&[]).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn pois_of_two_indexers_are_cross_checked_per_block() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let poi = |indexer: usize, block: u8, poi_byte: u8| ProofOfIndexing {
+        indexer: indexers[indexer].clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: block.into(),
+            hash: Some(vec![block; 32].into()),
+        },
+        proof_of_indexing: [poi_byte; 32].into(),
+    };
+    // Both indexers agree at block 1 and disagree at block 2, and only the
+    // first one has a PoI at block 3. PoIs of a deployment are written one
+    // block at a time.
+    store
+        .write_pois(vec![poi(0, 1, 1), poi(1, 1, 1)], PoiLiveness::NotLive)
+        .await
+        .unwrap();
+    store
+        .write_pois(vec![poi(0, 2, 2), poi(1, 2, 3)], PoiLiveness::NotLive)
+        .await
+        .unwrap();
+    store
+        .write_pois(vec![poi(0, 3, 4)], PoiLiveness::Live)
+        .await
+        .unwrap();
+
+    let address1 = indexers[0].address();
+    let address2 = indexers[1].address();
+    let cross_checks = store
+        .poi_cross_checks(&address1, &address2, &deployment, 10)
+        .await
+        .unwrap();
+    assert_eq!(cross_checks.len(), 2);
+    assert_eq!(cross_checks[0].block_number, 2);
+    assert_eq!(cross_checks[0].network, "mainnet");
+    assert_eq!(cross_checks[0].poi1, [2; 32].into());
+    assert_eq!(cross_checks[0].poi2, [3; 32].into());
+    assert_eq!(cross_checks[1].block_number, 1);
+    assert_eq!(cross_checks[1].poi1, cross_checks[1].poi2);
+
+    let limited = store
+        .poi_cross_checks(&address1, &address2, &deployment, 1)
+        .await
+        .unwrap();
+    assert_eq!(limited.len(), 1);
+    assert_eq!(limited[0].block_number, 2);
+
+    // A successful bisection run of the differing PoIs, in either order,
+    // finds where they diverge.
+    let block = |number| PartialBlock { number, hash: None };
+    let bisection_run = |error: Option<&str>| BisectionRunReport {
+        uuid: Uuid::new_v4(),
+        poi1: [3; 32].into(),
+        poi2: [2; 32].into(),
+        indexer1_operator_contact: None,
+        indexer2_operator_contact: None,
+        divergence_block_bounds: DivergenceBlockBounds {
+            lower_bound: block(1),
+            upper_bound: block(2),
+        },
+        bisects: vec![],
+        error: error.map(str::to_string),
+        entity_diff: None,
+        eth_call_diff: None,
+        graft_divergence: None,
+        indexer1_graph_node_version: None,
+        indexer2_graph_node_version: None,
+    };
+    store
+        .create_or_update_divergence_investigation_report(&DivergenceInvestigationReport {
+            uuid: Uuid::new_v4(),
+            status: DivergenceInvestigationStatus::Complete,
+            bisection_runs: vec![bisection_run(None), bisection_run(Some("failed"))],
+            error: None,
+        })
+        .await
+        .unwrap();
+    let pairs = [
+        ("mainnet".to_string(), [2; 32].into(), [3; 32].into()),
+        ("mainnet".to_string(), [2; 32].into(), [4; 32].into()),
+    ];
+    let divergences = store
+        .poi_cross_check_divergences(&address1, &address2, &deployment, &pairs)
+        .await
+        .unwrap();
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(divergences[0].poi2, [3; 32].into());
+    assert_eq!(divergences[0].block_number, 2);
+    assert_eq!(divergences[0].block_hash, vec![2; 32].into());
+    assert_eq!(divergences[0].diverging_poi1, [2; 32].into());
+    assert_eq!(divergences[0].diverging_poi2, [3; 32].into());
+}
+
+#[tokio::test]
+async fn investigation_pois_are_stored_but_not_live() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer-1".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://indexer-1:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let poi = ProofOfIndexing {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: 42,
+            hash: Some(vec![42; 32].into()),
+        },
+        proof_of_indexing: [1; 32].into(),
+    };
+    store.write_investigation_pois(vec![poi]).await.unwrap();
+
+    let (stored, block_hash) = store
+        .stored_poi(&indexer.address(), &deployment, "mainnet", 42)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.poi, [1; 32].into());
+    assert_eq!(stored.source, PoiSource::Investigation);
+    assert_eq!(block_hash, vec![42; 32].into());
+
+    assert!(store
+        .stored_poi(&indexer.address(), &deployment, "mainnet", 43)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(store
+        .live_pois(None, None, Some(&[deployment]), None, None, None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn same_deployment_on_several_networks() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    // Both networks have a block with the same number and hash, and the
+    // indexers disagree on the PoI.
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .zip(["mainnet", "gnosis"])
+        .enumerate()
+        .map(|(i, (indexer, network))| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: network.to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [i as u8; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    // Networks that are missing from the configuration are created.
+    let networks = store.networks().await.unwrap();
+    assert_eq!(networks.len(), 2);
+    assert_eq!(networks[1].name, "gnosis");
+    assert_eq!(networks[1].caip2, None);
+
+    let deployments = store
+        .sg_deployments(SgDeploymentsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(deployments.len(), 2);
+    assert!(deployments.iter().all(|d| d.cid == deployment));
+    assert_ne!(deployments[0].network_id, deployments[1].network_id);
+
+    for (network, poi_byte) in [("mainnet", 0), ("gnosis", 1)] {
+        let pois = store
+            .live_pois(
+                None,
+                Some(network),
+                Some(&[deployment.clone()]),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(pois.len(), 1);
+        assert_eq!(pois[0].poi, [poi_byte; 32].into());
+
+        let pois = store
+            .pois(Some(network), &[deployment.clone()], None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(pois.len(), 1);
+
+        let pois = store
+            .pois_by_value(&[poi_byte; 32].into(), Some(network))
+            .await
+            .unwrap();
+        assert_eq!(pois.len(), 1);
+    }
+    assert!(store
+        .pois_by_value(&[0; 32].into(), Some("gnosis"))
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Blocks are not shared across networks.
+    let pois = store
+        .pois(None, &[deployment], None, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(pois.len(), 2);
+    assert_ne!(pois[0].block_id, pois[1].block_id);
+}
+
+#[tokio::test]
+async fn live_pois_are_replaced_per_indexer() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois_at = |indexers: &[Arc<dyn IndexerClient>], number: u64| -> Vec<ProofOfIndexing> {
+        indexers
+            .iter()
+            .map(|indexer| ProofOfIndexing {
+                indexer: indexer.clone(),
+                deployment: deployment.clone(),
+                network: "mainnet".to_string(),
+                block: BlockPointer {
+                    number,
+                    hash: Some(vec![number as u8; 32].into()),
+                },
+                proof_of_indexing: [number as u8; 32].into(),
+            })
+            .collect()
+    };
+    let live_pois = || async {
+        let mut pois: Vec<_> = store
+            .live_pois(None, None, Some(&[deployment.clone()]), None, None, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|poi| (poi.indexer_id, poi.poi))
+            .collect();
+        pois.sort();
+        pois
+    };
+
+    store
+        .write_pois(pois_at(&indexers, 42), PoiLiveness::Live)
+        .await
+        .unwrap();
+    assert_eq!(
+        live_pois().await,
+        vec![(1, [42; 32].into()), (2, [42; 32].into())]
+    );
+
+    // Concurrent cycles don't conflict on live PoIs.
+    let (first, second) = tokio::join!(
+        store.write_pois(pois_at(&indexers, 42), PoiLiveness::Live),
+        store.write_pois(pois_at(&indexers, 42), PoiLiveness::Live),
+    );
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(live_pois().await.len(), 2);
+
+    // Indexers without a PoI in the latest cycle no longer have a live PoI.
+    store
+        .write_pois(pois_at(&indexers[..1], 43), PoiLiveness::Live)
+        .await
+        .unwrap();
+    assert_eq!(live_pois().await, vec![(1, [43; 32].into())]);
+
+    let superseded = store.pois_by_value(&[42; 32].into(), None).await.unwrap();
+    assert_eq!(superseded.len(), 6);
+    assert!(superseded.iter().all(|(_, live)| !live));
+    let live = store.pois_by_value(&[43; 32].into(), None).await.unwrap();
+    assert_eq!(live.len(), 1);
+    assert!(live[0].1);
+
+    // PoIs of the same cycle can be written indexer by indexer.
+    store
+        .write_pois(pois_at(&indexers[1..], 43), PoiLiveness::Live)
+        .await
+        .unwrap();
+    assert_eq!(
+        live_pois().await,
+        vec![(1, [43; 32].into()), (2, [43; 32].into())]
+    );
+
+    // Concurrent cycles can both be the first to see a block.
+    let (first, second) = tokio::join!(
+        store.write_pois(pois_at(&indexers[..1], 44), PoiLiveness::Live),
+        store.write_pois(pois_at(&indexers[1..], 44), PoiLiveness::Live),
+    );
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(
+        live_pois().await,
+        vec![(1, [44; 32].into()), (2, [44; 32].into())]
+    );
+}
+
+#[tokio::test]
+async fn read_replica_serves_pois_and_falls_back_to_the_primary() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://indexer:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+    let poi = ProofOfIndexing {
+        indexer,
+        deployment: IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: 42,
+            hash: Some(vec![42; 32].into()),
+        },
+        proof_of_indexing: [42; 32].into(),
+    };
+    store
+        .write_pois(vec![poi], PoiLiveness::Live)
+        .await
+        .unwrap();
+
+    // The test database isn't a replica, so it never lags behind.
+    let max_lag = std::time::Duration::from_secs(30);
+    let with_replica = (*store)
+        .clone()
+        .with_read_replica(store.connection_string(), max_lag)
+        .unwrap();
+    let with_unreachable_replica = (*store)
+        .clone()
+        .with_read_replica("postgres://postgres@127.0.0.1:1/postgres", max_lag)
+        .unwrap();
+    for store in [with_replica, with_unreachable_replica] {
+        let pois = store
+            .live_pois(None, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(pois.len(), 1);
+        assert_eq!(pois[0].poi, [42; 32].into());
+    }
+}
+
+#[tokio::test]
+async fn slow_indexers_dont_hold_back_poi_collection() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let deployment = ipfs_cid(deployments::ARB1_LIDO);
+    let graph_nodes = [
+        MockGraphNode::start(
+            "indexer-1",
+            vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 100)],
+        )
+        .await,
+        MockGraphNode::start(
+            "indexer-2",
+            vec![MockDeployment::new(deployment.clone(), "mainnet", 0, 100)],
+        )
+        .await,
+    ];
+    graph_nodes[1].set_poi_response_delay(std::time::Duration::from_secs(60));
+    let indexers = graph_nodes
+        .iter()
+        .map(MockGraphNode::indexer)
+        .collect::<Vec<_>>();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let (indexing_statuses, _) =
+        indexing_loop::query_indexing_statuses(&indexers, graphix_lib::metrics()).await;
+    let (_shutdown_sender, mut shutdown_receiver) = watch::channel(false);
+    let pois = indexing_loop::collect_proofs_of_indexing(
+        &store,
+        indexing_statuses,
+        &BlockChoicePolicy::Earliest.into(),
+        std::time::Duration::from_secs(1),
+        &mut shutdown_receiver,
+        graphix_lib::metrics(),
+    )
+    .await;
+
+    assert_eq!(pois.len(), 1);
+    assert_eq!(pois[0].indexer.address(), graph_nodes[0].address());
+    let live_pois = store
+        .live_pois(None, None, Some(&[deployment]), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(live_pois.len(), 1);
+    assert_eq!(live_pois[0].poi, canonical_poi(&pois[0].deployment, 100));
+}
+
+/// Writes a polling cycle's worth of PoIs of many indexers and deployments,
+/// and reports how long it takes with `--nocapture`.
+#[tokio::test]
+async fn write_pois_benchmark() {
+    const INDEXERS: u8 = 100;
+    const DEPLOYMENTS: usize = 200;
+
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=INDEXERS)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let mut gen = quickcheck::Gen::new(32);
+    let mut deployments = BTreeSet::new();
+    while deployments.len() < DEPLOYMENTS {
+        deployments.insert(IpfsCid::arbitrary(&mut gen));
+    }
+    let pois_at = |number: u64| -> Vec<ProofOfIndexing> {
+        deployments
+            .iter()
+            .flat_map(|deployment| {
+                indexers.iter().map(move |indexer| ProofOfIndexing {
+                    indexer: indexer.clone(),
+                    deployment: deployment.clone(),
+                    network: "mainnet".to_string(),
+                    block: BlockPointer {
+                        number,
+                        hash: Some(vec![number as u8; 32].into()),
+                    },
+                    proof_of_indexing: [indexer.address().0[0] % 3; 32].into(),
+                })
+            })
+            .collect()
+    };
+
+    // The first cycle inserts all deployments and blocks, later ones only
+    // look them up.
+    for number in [1, 2] {
+        let pois = pois_at(number);
+        let len = pois.len();
+        let start = Instant::now();
+        store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+        println!(
+            "Wrote {len} PoIs at block {number} in {:?}",
+            start.elapsed()
+        );
+    }
+
+    let stored_deployments = store
+        .sg_deployments(SgDeploymentsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(stored_deployments.len(), DEPLOYMENTS);
+    let live_pois = store
+        .live_pois(
+            None,
+            None,
+            Some(&[stored_deployments[0].cid.clone()]),
+            Some(BlockRange {
+                start: Some(2),
+                end: None,
+            }),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(live_pois.len(), INDEXERS as usize);
+}
+
+#[tokio::test]
+async fn poi_export_pages() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=3)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .map(|indexer| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [1; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let filter = PoisQuery {
+        network: Some("mainnet".to_string()),
+        deployments: vec![deployment.clone()],
+        block_range: Some(BlockRange {
+            start: Some(42),
+            end: None,
+        }),
+        epoch: None,
+        indexer_tag: None,
+        limit: None,
+    };
+    let first_page = store.poi_export_page(&filter, None, 2).await.unwrap();
+    assert_eq!(first_page.len(), 2);
+    assert_eq!(first_page[0].network, "mainnet");
+    assert_eq!(first_page[0].block_number, 42);
+    let second_page = store
+        .poi_export_page(&filter, Some(first_page[1].id), 2)
+        .await
+        .unwrap();
+    assert_eq!(second_page.len(), 1);
+
+    let pages: Vec<_> = store
+        .poi_export_stream(filter, 2)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[1][0].id, second_page[0].id);
+
+    let filter = PoisQuery {
+        block_range: Some(BlockRange {
+            start: None,
+            end: Some(41),
+        }),
+        ..Default::default()
+    };
+    assert!(store
+        .poi_export_page(&filter, None, 2)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn api_key_rate_limits() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+
+    let api_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Operator, None)
+        .await
+        .unwrap();
+    let api_key = ApiKey::from_str(&api_key.api_key).unwrap();
+    let metadata = store.api_key_metadata(&api_key).await.unwrap().unwrap();
+    assert_eq!(metadata.requests_per_minute, None);
+
+    assert!(store
+        .set_api_key_rate_limit(&api_key, Some(30))
+        .await
+        .unwrap());
+    let metadata = store.api_key_metadata(&api_key).await.unwrap().unwrap();
+    assert_eq!(metadata.requests_per_minute, Some(30));
+
+    assert!(store.set_api_key_rate_limit(&api_key, None).await.unwrap());
+    let metadata = store.api_key_metadata(&api_key).await.unwrap().unwrap();
+    assert_eq!(metadata.requests_per_minute, None);
+
+    let unknown = ApiKey::generate();
+    assert!(store.api_key_metadata(&unknown).await.unwrap().is_none());
+    assert!(!store
+        .set_api_key_rate_limit(&unknown, Some(30))
+        .await
+        .unwrap());
+}
+
+/// Every mutation must reject requests without an API key, with an unknown
+/// API key, or with an API key below the required permission level.
+#[tokio::test]
+async fn mutations_reject_missing_and_insufficient_api_keys() {
+    use ApiKeyPermissionLevel::{Admin, Operator};
+
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let operator_key = store
+        .create_api_key(None, Operator, None)
+        .await
+        .unwrap()
+        .api_key;
+    let operator_key = ApiKey::from_str(&operator_key).unwrap();
+
+    let cid = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+    let indexer = format!("0x{}", "00".repeat(20));
+    let (poi1, poi2) = (
+        format!("0x{}", "01".repeat(32)),
+        format!("0x{}", "02".repeat(32)),
+    );
+    let mutations = [
+        (
+            Operator,
+            format!(r#"launchDivergenceInvestigation(pois: ["{poi1}", "{poi2}"]) {{ uuid }}"#),
+        ),
+        (Operator, format!(r#"investigateDeployment(cid: "{cid}")"#)),
+        (
+            Operator,
+            format!(
+                r#"investigateAgainstConsensus(indexerAddress: "{indexer}", deploymentCid: "{cid}") {{ uuid }}"#
+            ),
+        ),
+        (Admin, r#"setConfiguration(config: {})"#.to_string()),
+        (
+            Admin,
+            "importConfig(document: { config: {} }) { taggedIndexers }".to_string(),
+        ),
+        (
+            Admin,
+            "createApiKey(permissionLevel: ADMIN) { apiKey }".to_string(),
+        ),
+        (Admin, format!(r#"deleteApiKey(apiKey: "{operator_key}")"#)),
+        (
+            Admin,
+            format!(r#"modifyApiKey(apiKey: "{operator_key}", permissionLevel: ADMIN)"#),
+        ),
+        (
+            Admin,
+            format!(r#"setApiKeyRateLimit(apiKey: "{operator_key}", requestsPerMinute: 1)"#),
+        ),
+        (
+            Operator,
+            format!(
+                r#"createWebhook(url: "https://example.com", deployments: ["{cid}"]) {{ id }}"#
+            ),
+        ),
+        (Operator, "deleteWebhook(id: 1)".to_string()),
+        (
+            Operator,
+            format!(
+                r#"subscribeToDeployment(cid: "{cid}", webhookUrl: "https://example.com") {{ id }}"#
+            ),
+        ),
+        (Operator, "unsubscribeFromDeployment(id: 1)".to_string()),
+        (
+            Operator,
+            format!(r#"setWatchlist(name: "foo", deployments: ["{cid}"]) {{ name }}"#),
+        ),
+        (Operator, r#"deleteWatchlist(name: "foo")"#.to_string()),
+        (
+            Operator,
+            format!(r#"setDeploymentName(deploymentIpfsCid: "{cid}", name: "foo") {{ id }}"#),
+        ),
+        (Operator, "syncDeploymentNames".to_string()),
+        (
+            Admin,
+            format!(
+                r#"setIndexerOperatorContact(indexerAddress: "{indexer}", contact: "foo") {{ address }}"#
+            ),
+        ),
+        (
+            Operator,
+            format!(r#"addIndexerTag(indexerAddress: "{indexer}", tag: "foo") {{ address }}"#),
+        ),
+        (
+            Operator,
+            format!(r#"removeIndexerTag(indexerAddress: "{indexer}", tag: "foo") {{ address }}"#),
+        ),
+        (
+            Admin,
+            r#"setChainEnabled(network: "mainnet", enabled: false) { name }"#.to_string(),
+        ),
+        (Admin, r#"deleteNetwork(network: "mainnet")"#.to_string()),
+        (
+            Admin,
+            format!(r#"archiveIndexer(indexerAddress: "{indexer}") {{ address }}"#),
+        ),
+        (
+            Admin,
+            format!(r#"deleteIndexer(indexerAddress: "{indexer}")"#),
+        ),
+        (
+            Admin,
+            r#"addTrackedIndexer(name: "foo", statusUrl: "http://localhost:8030/graphql")"#
+                .to_string(),
+        ),
+        (
+            Admin,
+            format!(r#"removeTrackedIndexer(address: "{indexer}")"#),
+        ),
+        (
+            Admin,
+            format!(
+                r#"backfillPois(deployment: "{cid}", fromBlock: 0, toBlock: 10, step: 1) {{ id }}"#
+            ),
+        ),
+        (
+            Operator,
+            "retryFailedQuery(id: 1) { succeeded }".to_string(),
+        ),
+        (
+            Admin,
+            "testIndexerConnectivity(url: \"http://localhost:8030/graphql\") { reachable }"
+                .to_string(),
+        ),
+    ];
+
+    // Mutations that are added later must be added here, too.
+    let response = request_schema(state.clone(), None)
+        .execute("{ __schema { mutationType { fields { name } } } }")
+        .await
+        .data
+        .into_json()
+        .unwrap();
+    let all_mutations: BTreeSet<&str> = response["__schema"]["mutationType"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["name"].as_str().unwrap())
+        .collect();
+    let tested_mutations: BTreeSet<&str> = mutations
+        .iter()
+        .map(|(_, mutation)| mutation.split('(').next().unwrap())
+        .collect();
+    assert_eq!(all_mutations, tested_mutations);
+
+    for (permission_level, mutation) in &mutations {
+        let mut api_keys = vec![None, Some(ApiKey::generate())];
+        if *permission_level == Admin {
+            api_keys.push(Some(operator_key.clone()));
+        }
+
+        for api_key in api_keys {
+            let response = request_schema(state.clone(), api_key.clone())
+                .execute(format!("mutation {{ {mutation} }}"))
+                .await;
+
+            assert_eq!(response.errors.len(), 1, "{mutation} with {api_key:?}");
+            let code = response.errors[0]
+                .extensions
+                .as_ref()
+                .and_then(|extensions| extensions.get("code"));
+            assert_eq!(
+                code,
+                Some(&async_graphql::Value::from("UNAUTHORIZED")),
+                "{mutation} with {api_key:?}: {:?}",
+                response.errors
+            );
+        }
+    }
+
+    // Nothing was changed.
+    assert!(store
+        .api_key_metadata(&operator_key)
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn federation_entities_are_resolved_by_key() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer-1".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://indexer-1:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let poi = ProofOfIndexing {
+        indexer,
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: 42,
+            hash: Some(vec![42; 32].into()),
+        },
+        proof_of_indexing: [1; 32].into(),
+    };
+    store
+        .write_pois(vec![poi], PoiLiveness::Live)
+        .await
+        .unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let address = IndexerAddress::from([1; 20]).to_string();
+    let hash = format!("0x{}", "01".repeat(32));
+    let request = async_graphql::Request::new(
+        r#"query ($representations: [_Any!]!) {
+            _entities(representations: $representations) {
+                ... on Indexer { address }
+                ... on SubgraphDeployment { cid }
+                ... on ProofOfIndexing { hash block { number } }
+            }
+        }"#,
+    )
+    .variables(async_graphql::Variables::from_json(serde_json::json!({
+        "representations": [
+            { "__typename": "Indexer", "address": address },
+            { "__typename": "SubgraphDeployment", "cid": deployment.to_string() },
+            { "__typename": "ProofOfIndexing", "hash": hash },
+            { "__typename": "ProofOfIndexing", "hash": format!("0x{}", "02".repeat(32)) },
+        ]
+    })));
+    let response = request_schema(state.clone(), None).execute(request).await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "_entities": [
+                { "address": address },
+                { "cid": deployment.to_string() },
+                { "hash": hash, "block": { "number": 42 } },
+                null,
+            ]
+        })
+    );
+
+    let response = request_schema(state, None)
+        .execute("{ _service { sdl } }")
+        .await
+        .data
+        .into_json()
+        .unwrap();
+    let sdl = response["_service"]["sdl"].as_str().unwrap();
+    assert!(sdl.contains(r#"type Indexer @key(fields: "address")"#));
+    assert!(sdl.contains(r#"type SubgraphDeployment @key(fields: "cid")"#));
+    assert!(sdl.contains(r#"type ProofOfIndexing @key(fields: "hash")"#));
+}
+
+#[tokio::test]
+async fn pois_can_be_searched_by_prefix() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let poi_hash = |first_bytes: &[u8], rest: u8| {
+        let mut bytes = [rest; 32];
+        bytes[..first_bytes.len()].copy_from_slice(first_bytes);
+        bytes
+    };
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = [
+        poi_hash(&[0xab, 0xcd, 0xef, 0x01, 0x10], 0),
+        poi_hash(&[0xab, 0xcd, 0xef, 0x01, 0x20], 0),
+        poi_hash(&[], 0xff),
+    ]
+    .into_iter()
+    .zip(indexers.iter().cycle())
+    .map(|(poi, indexer)| ProofOfIndexing {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: 42,
+            hash: Some(vec![42; 32].into()),
+        },
+        proof_of_indexing: poi.into(),
+    })
+    .collect();
+    store.write_pois(pois, PoiLiveness::NotLive).await.unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let search = |prefix: &str| {
+        let state = state.clone();
+        let request = async_graphql::Request::new(
+            r#"query ($prefix: String!) {
+                poiSearch(prefix: $prefix) {
+                    hash
+                    deployment { cid }
+                    indexer { address }
+                }
+            }"#,
+        )
+        .variables(async_graphql::Variables::from_json(
+            serde_json::json!({ "prefix": prefix }),
+        ));
+        async move { request_schema(state, None).execute(request).await }
+    };
+    let hashes = |response: async_graphql::Response| {
+        assert_eq!(response.errors, vec![]);
+        let mut hashes: Vec<String> = response.data.into_json().unwrap()["poiSearch"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|poi| poi["hash"].as_str().unwrap().to_string())
+            .collect();
+        hashes.sort();
+        hashes
+    };
+
+    assert_eq!(
+        hashes(search("0xabcdef01").await),
+        vec![
+            format!("0xabcdef0110{}", "00".repeat(27)),
+            format!("0xabcdef0120{}", "00".repeat(27)),
+        ]
+    );
+    // Odd numbers of digits and upper case are fine.
+    assert_eq!(
+        hashes(search("ABCDEF012").await),
+        vec![format!("0xabcdef0120{}", "00".repeat(27))]
+    );
+    assert_eq!(
+        hashes(search("ffffffff").await),
+        vec![format!("0x{}", "ff".repeat(32))]
+    );
+    assert_eq!(hashes(search("abcdef03").await), Vec::<String>::new());
+
+    let response = search("0xabcdef").await;
+    assert_eq!(
+        response.errors[0].message,
+        "`prefix` must have 8 to 64 hex digits, got `0xabcdef`"
+    );
+
+    let response = search(&format!("0x{}", "ff".repeat(32))).await;
+    let poi = &response.data.into_json().unwrap()["poiSearch"][0];
+    assert_eq!(poi["deployment"]["cid"], deployment.to_string());
+    assert_eq!(
+        poi["indexer"]["address"],
+        IndexerAddress::from([1; 20]).to_string()
+    );
+}
+
+#[tokio::test]
+async fn block_timestamps_are_resolved_once() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://localhost:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let pois = [
+        ("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA", 10),
+        ("QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7", 20),
+    ]
+    .into_iter()
+    .map(|(deployment, number)| ProofOfIndexing {
+        indexer: indexer.clone(),
+        deployment: ipfs_cid(deployment),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number,
+            hash: Some(vec![number as u8; 32].into()),
+        },
+        proof_of_indexing: [1; 32].into(),
+    })
+    .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let recheck_after = std::time::Duration::from_secs(60 * 60);
+    let blocks = store
+        .blocks_without_timestamp("mainnet", recheck_after, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        blocks.iter().map(|block| block.number).collect::<Vec<_>>(),
+        vec![20, 10]
+    );
+    assert!(store
+        .blocks_without_timestamp("sepolia", recheck_after, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let timestamp = DateTime::from_timestamp(1_600_000_000, 0)
+        .unwrap()
+        .naive_utc();
+    let canonical_hash = vec![0xff; 32].into();
+    store
+        .set_block_timestamp(blocks[0].id, Some((timestamp, &canonical_hash)))
+        .await
+        .unwrap();
+    // The RPC endpoint didn't know about the other block.
+    store.set_block_timestamp(blocks[1].id, None).await.unwrap();
+    assert!(store
+        .blocks_without_timestamp("mainnet", recheck_after, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    clock.advance(Duration::hours(2));
+    let blocks = store
+        .blocks_without_timestamp("mainnet", recheck_after, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        blocks.iter().map(|block| block.number).collect::<Vec<_>>(),
+        vec![10]
+    );
+
+    let filter = PoisQuery {
+        network: Some("mainnet".to_string()),
+        deployments: vec![],
+        block_range: Some(BlockRange {
+            start: Some(20),
+            end: None,
+        }),
+        epoch: None,
+        indexer_tag: None,
+        limit: None,
+    };
+    let rows = store.poi_export_page(&filter, None, 10).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].block_timestamp, Some(timestamp));
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let response = request_schema(state, None)
+        .execute(
+            r#"{
+                proofsOfIndexing(deployments: ["QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7"]) {
+                    block { timestamp estimatedTimestamp canonicalHash }
+                }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap()["proofsOfIndexing"][0]["block"],
+        serde_json::json!({
+            "timestamp": "2020-09-13T12:26:40+00:00",
+            "estimatedTimestamp": "2020-09-13T12:26:40+00:00",
+            "canonicalHash": format!("0x{}", "ff".repeat(32)),
+        })
+    );
+}
+
+#[tokio::test]
+async fn poi_report_lists_disagreements_with_consensus() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=3)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    // Indexer 1 disagrees with the other two on the first deployment, and
+    // all three agree on the second.
+    let deployments = [
+        IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap(),
+        IpfsCid::from_str("QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7").unwrap(),
+    ];
+    let mut pois = vec![];
+    for (indexer, poi_byte) in indexers.iter().zip([1, 2, 2]) {
+        for (deployment, poi_byte) in deployments.iter().zip([poi_byte, 3]) {
+            pois.push(ProofOfIndexing {
+                indexer: indexer.clone(),
+                deployment: deployment.clone(),
+                network: "mainnet".to_string(),
+                block: BlockPointer {
+                    number: 42,
+                    hash: Some(vec![42; 32].into()),
+                },
+                proof_of_indexing: [poi_byte; 32].into(),
+            });
+        }
+    }
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    // Only the latest of several bisection runs of the PoI is reported.
+    let block = |number| PartialBlock { number, hash: None };
+    let investigate = |upper_bound| {
+        let store = &store;
+        async move {
+            let investigation_uuid = Uuid::new_v4();
+            store
+                .create_or_update_divergence_investigation_report(&DivergenceInvestigationReport {
+                    uuid: investigation_uuid,
+                    status: DivergenceInvestigationStatus::Complete,
+                    bisection_runs: vec![BisectionRunReport {
+                        uuid: Uuid::new_v4(),
+                        poi1: [1; 32].into(),
+                        poi2: [2; 32].into(),
+                        indexer1_operator_contact: None,
+                        indexer2_operator_contact: None,
+                        divergence_block_bounds: DivergenceBlockBounds {
+                            lower_bound: block(upper_bound - 1),
+                            upper_bound: block(upper_bound),
+                        },
+                        bisects: vec![],
+                        error: None,
+                        entity_diff: None,
+                        eth_call_diff: None,
+                        graft_divergence: None,
+                        indexer1_graph_node_version: None,
+                        indexer2_graph_node_version: None,
+                    }],
+                    error: None,
+                })
+                .await
+                .unwrap();
+            investigation_uuid
+        }
+    };
+    investigate(11).await;
+    let investigation_uuid = investigate(21).await;
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let report = |indexer_address: IndexerAddress| {
+        let state = state.clone();
+        async move {
+            let request = async_graphql::Request::new(
+                r#"query ($indexerAddress: HexString!) {
+                    myPoiReport(indexerAddress: $indexerAddress) {
+                        deployments
+                        deploymentsInConsensus
+                        deploymentsWithoutConsensus
+                        disagreements {
+                            deployment { cid }
+                            poi { hash }
+                            consensusPoi { hash }
+                            totalIndexers
+                            nAgreeingIndexers
+                            nConsensusIndexers
+                            bisectionRun { investigationUuid }
+                            divergenceBlockBounds {
+                                lowerBound { number }
+                                upperBound { number }
+                            }
+                        }
+                    }
+                }"#,
+            )
+            .variables(async_graphql::Variables::from_json(serde_json::json!({
+                "indexerAddress": indexer_address.to_string(),
+            })));
+            let response = request_schema(state, None).execute(request).await;
+            assert_eq!(response.errors, vec![]);
+            response.data.into_json().unwrap()["myPoiReport"].clone()
+        }
+    };
+
+    assert_eq!(
+        report(IndexerAddress::from([1; 20])).await,
+        serde_json::json!({
+            "deployments": 2,
+            "deploymentsInConsensus": 1,
+            "deploymentsWithoutConsensus": 0,
+            "disagreements": [{
+                "deployment": { "cid": deployments[0].to_string() },
+                "poi": { "hash": format!("0x{}", "01".repeat(32)) },
+                "consensusPoi": { "hash": format!("0x{}", "02".repeat(32)) },
+                "totalIndexers": 3,
+                "nAgreeingIndexers": 1,
+                "nConsensusIndexers": 2,
+                "bisectionRun": { "investigationUuid": investigation_uuid.to_string() },
+                "divergenceBlockBounds": {
+                    "lowerBound": { "number": 20 },
+                    "upperBound": { "number": 21 },
+                },
+            }],
+        })
+    );
+
+    let in_consensus = report(IndexerAddress::from([2; 20])).await;
+    assert_eq!(in_consensus["deploymentsInConsensus"], 2);
+    assert_eq!(in_consensus["disagreements"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn poi_agreement_can_be_weighed_by_stake() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=3)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    // Indexers 1 and 2 agree, but indexer 3 allocated more than both of them
+    // together.
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .zip([1, 1, 2])
+        .map(|(indexer, poi_byte)| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [poi_byte; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+    let addresses: Vec<IndexerAddress> = indexers.iter().map(|i| i.address()).collect();
+    let allocation = |indexer: u8, tokens: u32| IndexerAllocation {
+        indexer_address: IndexerAddress::from([indexer; 20]),
+        deployment: deployment.clone(),
+        allocated_tokens: tokens.into(),
+        updated_at: chrono::Utc::now().naive_utc(),
+    };
+    store
+        .replace_indexer_allocations(&addresses, &[allocation(1, 500), allocation(3, 900)])
+        .await
+        .unwrap();
+    // Replacing forgets allocations that were closed in the meantime.
+    store
+        .replace_indexer_allocations(
+            &addresses,
+            &[allocation(1, 100), allocation(2, 100), allocation(3, 600)],
+        )
+        .await
+        .unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let ratios = |indexer: u8, consensus_mode: &str| {
+        let state = state.clone();
+        let request = async_graphql::Request::new(format!(
+            r#"query ($indexerAddress: HexString!) {{
+                poiAgreementRatios(
+                    indexerAddress: $indexerAddress,
+                    consensusMode: {consensus_mode}
+                ) {{
+                    poi {{ allocatedTokens }}
+                    nAgreeingIndexers
+                    hasConsensus
+                    inConsensus
+                    disagreementStakeWeight
+                }}
+            }}"#
+        ))
+        .variables(async_graphql::Variables::from_json(serde_json::json!({
+            "indexerAddress": IndexerAddress::from([indexer; 20]).to_string(),
+        })));
+        async move {
+            let response = request_schema(state, None).execute(request).await;
+            assert_eq!(response.errors, vec![]);
+            response.data.into_json().unwrap()["poiAgreementRatios"][0].clone()
+        }
+    };
+
+    assert_eq!(
+        ratios(1, "COUNT").await,
+        serde_json::json!({
+            "poi": { "allocatedTokens": 100.0 },
+            "nAgreeingIndexers": 2,
+            "hasConsensus": true,
+            "inConsensus": true,
+            "disagreementStakeWeight": 0.75,
+        })
+    );
+    assert_eq!(
+        ratios(1, "STAKE_WEIGHTED").await,
+        serde_json::json!({
+            "poi": { "allocatedTokens": 100.0 },
+            "nAgreeingIndexers": 2,
+            "hasConsensus": true,
+            "inConsensus": false,
+            "disagreementStakeWeight": 0.75,
+        })
+    );
+    assert_eq!(
+        ratios(3, "STAKE_WEIGHTED").await,
+        serde_json::json!({
+            "poi": { "allocatedTokens": 600.0 },
+            "nAgreeingIndexers": 1,
+            "hasConsensus": true,
+            "inConsensus": true,
+            "disagreementStakeWeight": 0.25,
+        })
+    );
+
+    // Without allocations to the deployment, indexers are weighed by their
+    // staked tokens.
+    store
+        .replace_indexer_allocations(&addresses, &[])
+        .await
+        .unwrap();
+    for indexer in store.indexers(IndexersQuery::default()).await.unwrap() {
+        let staked_tokens = if indexer.address == IndexerAddress::from([3; 20]) {
+            50
+        } else {
+            100
+        };
+        let zero = BigDecimal::from(0);
+        store
+            .create_or_update_indexer_network_subgraph_metadata(
+                indexer.id,
+                NewIndexerNetworkSubgraphMetadata {
+                    geohash: None,
+                    indexer_url: None,
+                    staked_tokens: BigDecimal::from(staked_tokens),
+                    allocated_tokens: zero.clone(),
+                    locked_tokens: zero.clone(),
+                    query_fees_collected: zero.clone(),
+                    query_fee_rebates: zero.clone(),
+                    rewards_earned: zero.clone(),
+                    indexer_indexing_rewards: zero.clone(),
+                    delegator_indexing_rewards: zero,
+                    last_updated_at: chrono::Utc::now().naive_utc(),
+                },
+            )
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        ratios(3, "STAKE_WEIGHTED").await,
+        serde_json::json!({
+            "poi": { "allocatedTokens": null },
+            "nAgreeingIndexers": 1,
+            "hasConsensus": true,
+            "inConsensus": false,
+            "disagreementStakeWeight": 0.8,
+        })
+    );
+}
+
+#[tokio::test]
+async fn campaigns_investigate_diverging_deployments() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    // The indexers disagree on the first deployment only. The third one has
+    // no PoIs at all.
+    let deployments = [
+        IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap(),
+        IpfsCid::from_str("QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7").unwrap(),
+        IpfsCid::from_str("QmdHQVHirs3yPygcgo3HNttXaFCS4pnoGiMx3aKXr192En").unwrap(),
+    ];
+    let mut pois = vec![];
+    for (indexer, poi_byte) in indexers.iter().zip([1, 2]) {
+        for (deployment, poi_byte) in deployments.iter().zip([poi_byte, 3]) {
+            pois.push(ProofOfIndexing {
+                indexer: indexer.clone(),
+                deployment: deployment.clone(),
+                network: "mainnet".to_string(),
+                block: BlockPointer {
+                    number: 42,
+                    hash: Some(vec![42; 32].into()),
+                },
+                proof_of_indexing: [poi_byte; 32].into(),
+            });
+        }
+    }
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let campaign = CampaignConfig {
+        name: "top".to_string(),
+        schedule: CronSchedule::from_str("0 * * * *").unwrap(),
+        top_deployments: 3,
+        network_subgraph: None,
+        bisect_graft_bases: false,
+    };
+    let run = campaigns::investigate_deployments(&store, &campaign, &deployments)
+        .await
+        .unwrap();
+    assert_eq!(
+        run.diverging_deployments,
+        vec![Some(deployments[0].clone())]
+    );
+    assert_eq!(run.investigation_uuids.len(), 1);
+    let investigation_uuid = run.investigation_uuids[0].unwrap();
+    assert!(store
+        .divergence_investigation_request_exists(&investigation_uuid)
+        .await
+        .unwrap());
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let response = request_schema(state, None)
+        .execute(
+            r#"{
+                campaignRuns(campaign: "top") {
+                    campaign
+                    deployments
+                    divergingDeployments
+                    investigationUuids
+                    error
+                }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "campaignRuns": [{
+                "campaign": "top",
+                "deployments": deployments.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "divergingDeployments": [deployments[0].to_string()],
+                "investigationUuids": [investigation_uuid.to_string()],
+                "error": null,
+            }]
+        })
+    );
+}
+
+#[tokio::test]
+async fn network_subgraph_metadata_is_refreshed() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    // The second indexer is no longer on the network subgraph, so its old
+    // metadata isn't refreshed.
+    let indexer_ids: Vec<_> = store
+        .indexers(IndexersQuery::default())
+        .await
+        .unwrap()
+        .iter()
+        .map(|indexer| (indexer.address, indexer.id))
+        .collect();
+    let indexer2_id = indexer_ids
+        .iter()
+        .find(|(address, _)| *address == IndexerAddress::from([2; 20]))
+        .unwrap()
+        .1;
+    let zero = BigDecimal::from(0);
+    store
+        .create_or_update_indexer_network_subgraph_metadata(
+            indexer2_id,
+            NewIndexerNetworkSubgraphMetadata {
+                geohash: None,
+                indexer_url: None,
+                staked_tokens: zero.clone(),
+                allocated_tokens: zero.clone(),
+                locked_tokens: zero.clone(),
+                query_fees_collected: zero.clone(),
+                query_fee_rebates: zero.clone(),
+                rewards_earned: zero.clone(),
+                indexer_indexing_rewards: zero.clone(),
+                delegator_indexing_rewards: zero,
+                last_updated_at: DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let response = serde_json::json!({
+        "data": {
+            "indexers": [{
+                "id": IndexerAddress::from([1; 20]).to_string(),
+                "url": "https://indexer-1.example",
+                "geoHash": "u33d",
+                "stakedTokens": "1000",
+                "allocatedTokens": "500",
+                "lockedTokens": "0",
+                "queryFeesCollected": "0",
+                "queryFeeRebates": "0",
+                "rewardsEarned": "0",
+                "indexerIndexingRewards": "0",
+                "delegatorIndexingRewards": "0",
+            }]
+        }
+    });
+    let router = axum::Router::new().route(
+        "/",
+        axum::routing::post(move || async move { axum::Json(response) }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let config = Config {
+        sources: vec![ConfigSource::NetworkSubgraph(NetworkSubgraphConfig {
+            name: None,
+            endpoint,
+            api_key: None,
+            query: Default::default(),
+            stake_threshold: 0.0,
+            limit: None,
+            collect_onchain_pois: false,
+            polling_period_in_seconds: None,
+        })],
+        ..Default::default()
+    };
+    let refreshed = network_subgraph_metadata::refresh_indexers_metadata(
+        &store,
+        &config,
+        graphix_lib::metrics(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(refreshed, 1);
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(config).1,
+    ));
+    let response = request_schema(state, None)
+        .execute(
+            r#"{
+                indexers {
+                    address
+                    networkSubgraphMetadata { geohash stakedTokens }
+                    networkSubgraphMetadataIsStale
+                }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    let mut indexers = response.data.into_json().unwrap()["indexers"]
+        .as_array()
+        .unwrap()
+        .clone();
+    indexers.sort_by_key(|indexer| indexer["address"].as_str().unwrap().to_string());
+    assert_eq!(
+        indexers,
+        vec![
+            serde_json::json!({
+                "address": IndexerAddress::from([1; 20]).to_string(),
+                "networkSubgraphMetadata": { "geohash": "u33d", "stakedTokens": 1000.0 },
+                "networkSubgraphMetadataIsStale": false,
+            }),
+            serde_json::json!({
+                "address": IndexerAddress::from([2; 20]).to_string(),
+                "networkSubgraphMetadata": { "geohash": null, "stakedTokens": 0.0 },
+                "networkSubgraphMetadataIsStale": true,
+            }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn webhooks_are_scoped_to_organizations() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+
+    let mut api_keys = vec![];
+    for organization in [Some("team-a"), Some("team-a"), None] {
+        let api_key = store
+            .create_api_key(None, ApiKeyPermissionLevel::Operator, organization)
+            .await
+            .unwrap();
+        api_keys.push(ApiKey::from_str(&api_key.api_key).unwrap());
+    }
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let create_webhook = |api_key| {
+        store.create_webhook(
+            api_key,
+            "https://example.com/webhook",
+            std::slice::from_ref(&deployment),
+            2,
+        )
+    };
+    let webhook = create_webhook(&api_keys[0]).await.unwrap();
+    assert_eq!(webhook.deployments().collect::<Vec<_>>(), vec![&deployment]);
+    create_webhook(&api_keys[0]).await.unwrap();
+    // Limits are per API key, not per organization.
+    assert!(create_webhook(&api_keys[0]).await.is_err());
+    create_webhook(&api_keys[1]).await.unwrap();
+    create_webhook(&api_keys[2]).await.unwrap();
+
+    assert_eq!(store.webhooks(Some(&api_keys[1])).await.unwrap().len(), 3);
+    assert_eq!(store.webhooks(Some(&api_keys[2])).await.unwrap().len(), 1);
+    assert_eq!(store.webhooks(None).await.unwrap().len(), 4);
+
+    assert!(!store
+        .delete_webhook(webhook.id, Some(&api_keys[2]))
+        .await
+        .unwrap());
+    assert!(store
+        .delete_webhook(webhook.id, Some(&api_keys[1]))
+        .await
+        .unwrap());
+    assert_eq!(store.webhooks(None).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn deployment_subscriptions_are_idempotent_and_scoped_to_organizations() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+
+    let mut api_keys = vec![];
+    for organization in [Some("team-a"), None] {
+        let api_key = store
+            .create_api_key(None, ApiKeyPermissionLevel::Operator, organization)
+            .await
+            .unwrap();
+        api_keys.push(ApiKey::from_str(&api_key.api_key).unwrap());
+    }
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let url = "https://example.com/digest";
+    let subscription = store
+        .create_deployment_subscription(&api_keys[0], &deployment, url, 1)
+        .await
+        .unwrap();
+    assert_eq!(subscription.deployment, deployment);
+    // Subscribing again doesn't count towards the limit.
+    let again = store
+        .create_deployment_subscription(&api_keys[0], &deployment, url, 1)
+        .await
+        .unwrap();
+    assert_eq!(again.id, subscription.id);
+    let err = store
+        .create_deployment_subscription(&api_keys[0], &deployment, "https://example.com/b", 1)
+        .await
+        .unwrap_err();
+    assert!(err.is::<WebhookLimitReached>());
+    store
+        .create_deployment_subscription(&api_keys[1], &deployment, url, 1)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .deployment_subscriptions(Some(&api_keys[0]))
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(store.deployment_subscriptions(None).await.unwrap().len(), 2);
+
+    assert!(!store
+        .delete_deployment_subscription(subscription.id, Some(&api_keys[1]))
+        .await
+        .unwrap());
+    assert!(store
+        .delete_deployment_subscription(subscription.id, Some(&api_keys[0]))
+        .await
+        .unwrap());
+    assert_eq!(store.deployment_subscriptions(None).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn manifests_are_stored_for_a_deployment_on_all_networks() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://indexer:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = ["mainnet", "gnosis"]
+        .into_iter()
+        .map(|network| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: network.to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [1; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    assert_eq!(
+        store.sg_deployments_without_manifest().await.unwrap(),
+        vec![deployment.clone()]
+    );
+
+    let manifest = SubgraphManifest {
+        network: Some("mainnet".to_string()),
+        start_block: 10,
+        features: vec!["grafting".to_string()],
+        graft_chain: vec![Graft {
+            base: IpfsCid::from_str("QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7").unwrap(),
+            block: 20,
+        }],
+    };
+    store
+        .set_sg_deployment_manifest(&deployment, &manifest)
+        .await
+        .unwrap();
+
+    assert!(store
+        .sg_deployments_without_manifest()
+        .await
+        .unwrap()
+        .is_empty());
+    let deployments = store
+        .sg_deployments(SgDeploymentsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(deployments.len(), 2);
+    for deployment in deployments {
+        let stored: SubgraphManifest =
+            serde_json::from_value(deployment.manifest.unwrap()).unwrap();
+        assert_eq!(stored, manifest);
+    }
+}
+
+#[tokio::test]
+async fn pois_are_tagged_with_network_epochs() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let network_id = store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois_at = |block_number: u64| -> Vec<ProofOfIndexing> {
+        indexers
+            .iter()
+            .enumerate()
+            .map(|(i, indexer)| ProofOfIndexing {
+                indexer: indexer.clone(),
+                deployment: deployment.clone(),
+                network: "mainnet".to_string(),
+                block: BlockPointer {
+                    number: block_number,
+                    hash: Some(vec![block_number as u8; 32].into()),
+                },
+                // Indexers diverge at block 55.
+                proof_of_indexing: [(block_number as u8) + (block_number == 55) as u8 * i as u8;
+                    32]
+                    .into(),
+            })
+            .collect()
+    };
+
+    // PoIs written before their epoch is known are tagged later on.
+    store
+        .write_pois(pois_at(42), PoiLiveness::NotLive)
+        .await
+        .unwrap();
+    assert_eq!(store.latest_network_epoch(network_id).await.unwrap(), None);
+    store
+        .write_network_epochs(&[
+            NewNetworkEpoch {
+                network_id,
+                epoch: 10,
+                start_block_number: 40,
+            },
+            NewNetworkEpoch {
+                network_id,
+                epoch: 11,
+                start_block_number: 50,
+            },
+        ])
+        .await
+        .unwrap();
+    store
+        .write_pois(pois_at(55), PoiLiveness::Live)
+        .await
+        .unwrap();
+    assert_eq!(
+        store.latest_network_epoch(network_id).await.unwrap(),
+        Some(11)
+    );
+
+    let pois = store
+        .pois(None, &[deployment.clone()], None, Some(10), None, None)
+        .await
+        .unwrap();
+    assert_eq!(pois.len(), 2);
+    assert!(pois.iter().all(|poi| poi.epoch == Some(10)));
+
+    let summaries = store.epoch_summaries(&[], None).await.unwrap();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].epoch, 11);
+    assert_eq!(summaries[0].poi_count, 2);
+    assert_eq!(summaries[0].indexer_count, 2);
+    assert_eq!(summaries[0].block_count, 1);
+    assert_eq!(summaries[0].distinct_poi_count, 2);
+    assert_eq!(summaries[1].epoch, 10);
+    assert_eq!(summaries[1].distinct_poi_count, 1);
+
+    let filter = PoisQuery {
+        network: None,
+        deployments: vec![],
+        block_range: None,
+        epoch: Some(11),
+        indexer_tag: None,
+        limit: None,
+    };
+    let rows = store.poi_export_page(&filter, None, 10).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row.block_number == 55));
+}
+
+#[tokio::test]
+async fn store_operations_are_measured() {
+    let metrics = StoreMetrics {
+        query_duration_seconds: prometheus::HistogramVec::new(
+            prometheus::HistogramOpts::new("duration", "duration"),
+            &["method"],
+        )
+        .unwrap(),
+        query_errors: prometheus::IntCounterVec::new(
+            prometheus::Opts::new("errors", "errors"),
+            &["method"],
+        )
+        .unwrap(),
+        pool_connections: prometheus::IntGaugeVec::new(
+            prometheus::Opts::new("connections", "connections"),
+            &["state"],
+        )
+        .unwrap(),
+        read_replica_lag_seconds: prometheus::Gauge::new("lag", "lag").unwrap(),
+    };
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let store = (*store).clone().with_metrics(metrics.clone());
+
+    store
+        .sg_deployments(SgDeploymentsQuery::default())
+        .await
+        .unwrap();
+
+    let durations = metrics
+        .query_duration_seconds
+        .with_label_values(&["sg_deployments"]);
+    assert_eq!(durations.get_sample_count(), 1);
+    assert_eq!(
+        metrics
+            .query_errors
+            .with_label_values(&["sg_deployments"])
+            .get(),
+        0
+    );
+    assert!(metrics.pool_connections.with_label_values(&["max"]).get() > 0);
+}
+
+#[tokio::test]
+async fn check_connection() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store.check_connection().await.unwrap();
+}
+
+#[tokio::test]
+async fn failed_queries_are_pruned_after_retention() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://indexer:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    store
+        .write_failed_query(&indexer, "indexingStatuses", "{ a }", "first")
+        .await
+        .unwrap();
+    clock.advance(Duration::days(2));
+    store
+        .write_failed_query(&indexer, "indexingStatuses", "{ a }", "second")
+        .await
+        .unwrap();
+
+    let latest = store
+        .failed_query(&indexer, "indexingStatuses")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(latest.response, "second");
+
+    let cutoff = (clock.now() - Duration::days(1)).naive_utc();
+    assert_eq!(store.delete_failed_queries_before(cutoff).await.unwrap(), 1);
+    assert_eq!(store.delete_failed_queries_before(cutoff).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn failed_queries_can_be_listed_and_retried() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let mock_indexer = |name: &str, fail_indexing_statuses| -> Arc<dyn IndexerClient> {
+        Arc::new(MockIndexer {
+            name: name.to_string(),
+            deployment_details: vec![],
+            fail_indexing_statuses,
+        })
+    };
+    let healthy = mock_indexer("healthy", false);
+    let failing = mock_indexer("failing", true);
+    let indexers = vec![healthy.clone(), failing.clone()];
+    store.write_indexers(&indexers).await.unwrap();
+    for indexer in &indexers {
+        store
+            .write_failed_query(indexer, "indexingStatuses", "{ a }", "oops")
+            .await
+            .unwrap();
+    }
+    store
+        .write_failed_query(&failing, "version", "{ b }", "oops")
+        .await
+        .unwrap();
+
+    let state = Arc::new(
+        GraphixState::new((*store).clone(), watch::channel(Config::default()).1)
+            .with_indexers(watch::channel(indexers).1),
+    );
+    let operator_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Operator, None)
+        .await
+        .unwrap()
+        .api_key;
+    let operator_key = ApiKey::from_str(&operator_key).unwrap();
+
+    // Responses may contain sensitive data.
+    let response = request_schema(state.clone(), None)
+        .execute("{ failedQueries { id } }")
+        .await;
+    assert_eq!(response.errors.len(), 1);
+
+    let response = request_schema(state.clone(), Some(operator_key.clone()))
+        .execute(format!(
+            r#"{{
+                failedQueries(indexerAddress: "{}", queryName: "indexingStatuses") {{
+                    id
+                    queryName
+                    rawQuery
+                    response
+                }}
+            }}"#,
+            failing.address()
+        ))
+        .await;
+    assert_eq!(response.errors, vec![]);
+    let data = response.data.into_json().unwrap();
+    let failed_queries = data["failedQueries"].as_array().unwrap();
+    assert_eq!(failed_queries.len(), 1);
+    assert_eq!(failed_queries[0]["rawQuery"], "{ a }");
+    assert_eq!(failed_queries[0]["response"], "oops");
+    let failing_id = failed_queries[0]["id"].as_i64().unwrap();
+
+    let all = store.failed_queries(None, None, 10).await.unwrap();
+    assert_eq!(all.len(), 3);
+    let healthy_id = all
+        .iter()
+        .find(|failed_query| {
+            failed_query.id != failing_id as i32 && failed_query.query_name == "indexingStatuses"
+        })
+        .unwrap()
+        .id;
+    let version_id = all
+        .iter()
+        .find(|failed_query| failed_query.query_name == "version")
+        .unwrap()
+        .id;
+
+    let retry = |id: i64| {
+        let schema = request_schema(state.clone(), Some(operator_key.clone()));
+        async move {
+            schema
+                .execute(format!(
+                    "mutation {{ retryFailedQuery(id: {id}) {{ succeeded message }} }}"
+                ))
+                .await
+        }
+    };
+    let response = retry(healthy_id.into()).await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap()["retryFailedQuery"],
+        serde_json::json!({
+            "succeeded": true,
+            "message": "Indexer returned 0 indexing statuses",
+        })
+    );
+    let response = retry(failing_id).await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap()["retryFailedQuery"],
+        serde_json::json!({ "succeeded": false, "message": "boo" })
+    );
+    // Only some queries can be retried.
+    assert_eq!(retry(version_id.into()).await.errors.len(), 1);
+    assert_eq!(retry(i32::MAX.into()).await.errors.len(), 1);
+}
+
+#[tokio::test]
+async fn indexer_errors_are_counted_by_class() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexer: Arc<dyn IndexerClient> = Arc::new(RealIndexer::new(
+        Some("indexer".to_string()),
+        IndexerAddress::from([1; 20]),
+        "http://indexer:8030/graphql".to_string(),
+        graphix_lib::metrics()
+            .public_proofs_of_indexing_requests
+            .clone(),
+    ));
+    store.write_indexers(&[indexer.clone()]).await.unwrap();
+
+    let error = |class, message: &str| {
+        WithIndexer::new(
+            indexer.clone(),
+            NewIndexerError {
+                class,
+                message: message.to_string(),
+            },
+        )
+    };
+    store
+        .write_indexer_errors(&[
+            error(IndexerErrorClass::Timeout, "first timeout"),
+            error(IndexerErrorClass::Dns, "no such host"),
+        ])
+        .await
+        .unwrap();
+    store
+        .write_indexer_errors(&[error(IndexerErrorClass::Timeout, "second timeout")])
+        .await
+        .unwrap();
+
+    let indexer_id = store.indexers(Default::default()).await.unwrap()[0].id;
+    let errors = store.indexer_errors(indexer_id).await.unwrap();
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|e| (e.error_class, e.count, e.last_error.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (IndexerErrorClass::Timeout, 2, "second timeout"),
+            (IndexerErrorClass::Dns, 1, "no such host"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn indexer_latency_stats_have_percentiles_per_indexer_and_fleet() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let sample = |indexer: &Arc<dyn IndexerClient>, probe, latency_ms, success| {
+        WithIndexer::new(
+            indexer.clone(),
+            NewLatencySample {
+                probe,
+                latency_ms,
+                success,
+            },
+        )
+    };
+    let mut samples: Vec<_> = (1..=10)
+        .map(|i| sample(&indexers[0], LatencyProbe::Status, i * 10, true))
+        .collect();
+    samples.push(sample(&indexers[0], LatencyProbe::Status, 5000, false));
+    samples.push(sample(
+        &indexers[0],
+        LatencyProbe::ProofOfIndexing,
+        5000,
+        false,
+    ));
+    samples.push(sample(&indexers[1], LatencyProbe::Status, 1000, true));
+    samples.push(sample(&indexers[1], LatencyProbe::Status, 1000, true));
+    store.write_latency_samples(&samples).await.unwrap();
+
+    let since = chrono::Utc::now().naive_utc() - Duration::hours(1);
+    let stats = store.indexer_latency_stats(None, since).await.unwrap();
+    let indexer_ids: Vec<_> = store
+        .indexers(Default::default())
+        .await
+        .unwrap()
+        .iter()
+        .map(|indexer| (indexer.address, indexer.id))
+        .collect();
+    let id = |address| {
+        indexer_ids
+            .iter()
+            .find(|(a, _)| *a == address)
+            .map(|(_, id)| *id)
+    };
+    let summary: Vec<_> = stats
+        .iter()
+        .map(|s| (s.indexer_id, s.probe, s.sample_count, s.p50_ms))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                id(IndexerAddress::from([1; 20])),
+                LatencyProbe::Status,
+                11,
+                Some(55.0)
+            ),
+            (
+                id(IndexerAddress::from([1; 20])),
+                LatencyProbe::ProofOfIndexing,
+                1,
+                None
+            ),
+            (
+                id(IndexerAddress::from([2; 20])),
+                LatencyProbe::Status,
+                2,
+                Some(1000.0)
+            ),
+            (None, LatencyProbe::Status, 13, Some(65.0)),
+            (None, LatencyProbe::ProofOfIndexing, 1, None),
+        ]
+    );
+    // Failed requests count against the success rate, but not the latency.
+    assert!((stats[0].success_rate - 10.0 / 11.0).abs() < 1e-9);
+    assert!((stats[0].p99_ms.unwrap() - 99.1).abs() < 1e-9);
+    assert_eq!(stats[1].success_rate, 0.0);
+
+    // Stats of all indexers together are included for comparison.
+    let stats = store
+        .indexer_latency_stats(Some(&IndexerAddress::from([2; 20])), since)
+        .await
+        .unwrap();
+    let summary: Vec<_> = stats.iter().map(|s| (s.indexer_id, s.probe)).collect();
+    assert_eq!(
+        summary,
+        vec![
+            (id(IndexerAddress::from([2; 20])), LatencyProbe::Status),
+            (None, LatencyProbe::Status),
+            (None, LatencyProbe::ProofOfIndexing),
+        ]
+    );
+
+    let deleted = store
+        .delete_latency_samples_before(chrono::Utc::now().naive_utc() + Duration::minutes(1))
+        .await
+        .unwrap();
+    assert_eq!(deleted, samples.len());
+    assert!(store
+        .indexer_latency_stats(None, since)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn table_stats_include_graphix_tables() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+
+    let stats = store.table_stats().await.unwrap();
+    for table in ["pois", "poi_values", "indexers", "sg_deployments"] {
+        assert!(stats
+            .iter()
+            .any(|t| t.schema_name == "public" && t.table_name == table));
+    }
+    assert!(stats.iter().all(|t| t.total_size_bytes >= 0));
+}
+
+#[tokio::test]
+async fn version_info_includes_migration_version_and_database_size() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+
+    let response = request_schema(state, None)
+        .execute(
+            r#"{
+                graphixVersionInfo {
+                    version
+                    schemaMigrationVersion
+                    databaseSizeBytes
+                    tables { tableName rowCount }
+                }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    let info = &response.data.into_json().unwrap()["graphixVersionInfo"];
+
+    assert_eq!(info["version"], graphix_lib::GRAPHIX_VERSION);
+    // The latest migration, as named in `migrations/`.
+    let latest_migration = std::fs::read_dir("migrations")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .max()
+        .unwrap();
+    let latest_migration_version: String = latest_migration
+        .chars()
+        .take_while(|c| *c != '_')
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    assert_eq!(info["schemaMigrationVersion"], latest_migration_version);
+    assert!(info["databaseSizeBytes"].as_i64().unwrap() > 0);
+    assert!(info["tables"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|table| table["tableName"] == "pois"));
+}
+
+#[tokio::test]
+async fn divergence_investigation_reports_are_normalized() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: Some("eip155:1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .zip([1, 2])
+        .map(|(indexer, poi_byte)| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [poi_byte; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let block = |number| PartialBlock { number, hash: None };
+    let mut report = DivergenceInvestigationReport {
+        uuid: Uuid::new_v4(),
+        status: DivergenceInvestigationStatus::InProgress,
+        bisection_runs: vec![BisectionRunReport {
+            uuid: Uuid::new_v4(),
+            poi1: [1; 32].into(),
+            poi2: [2; 32].into(),
+            indexer1_operator_contact: None,
+            indexer2_operator_contact: Some("ops@indexer.example".to_string()),
+            divergence_block_bounds: DivergenceBlockBounds {
+                lower_bound: block(20),
+                upper_bound: block(21),
+            },
+            bisects: vec![
+                BisectionReport {
+                    block: block(21),
+                    indexer1_response: "a".to_string(),
+                    indexer2_response: "b".to_string(),
+                },
+                BisectionReport {
+                    block: block(20),
+                    indexer1_response: "c".to_string(),
+                    indexer2_response: "c".to_string(),
+                },
+            ],
+            error: None,
+            entity_diff: Some(EntityDiff {
+                block_number: 21,
+                added: vec![EntityChange {
+                    entity_type: "Token".to_string(),
+                    id: "0x1".to_string(),
+                    data: None,
+                }],
+                ..Default::default()
+            }),
+            eth_call_diff: Some(EthCallDiff {
+                block_number: 21,
+                indexer1_block_hash: vec![21; 32].into(),
+                indexer2_block_hash: vec![21; 32].into(),
+                added: vec![],
+                removed: vec![],
+                changed: vec![ChangedEthCall {
+                    id_hash: vec![1; 32].into(),
+                    contract_address: vec![2; 20].into(),
+                    indexer1_return_value: vec![3].into(),
+                    indexer2_return_value: vec![4].into(),
+                }],
+            }),
+            graft_divergence: Some(GraftDivergence {
+                base: IpfsCid::from_str("QmTgGDvB8XdBrFZTFjh2ynmjyk5AR1hd7ciqJZTqV5Lnw7").unwrap(),
+                graft_block: 30,
+                base_bisection: None,
+            }),
+            indexer1_graph_node_version: Some("0.35.0".to_string()),
+            indexer2_graph_node_version: Some("0.34.1".to_string()),
+        }],
+        error: None,
+    };
+    store
+        .create_or_update_divergence_investigation_report(&report)
+        .await
+        .unwrap();
+    // Bisection runs are only written once.
+    report.error = Some("failed".to_string());
+    store
+        .create_or_update_divergence_investigation_report(&report)
+        .await
+        .unwrap();
+
+    let stored = store
+        .divergence_investigation_report(&report.uuid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.status, DivergenceInvestigationStatus::Complete);
+    assert_eq!(stored.error.as_deref(), Some("failed"));
+    assert_eq!(stored.bisection_runs.len(), 1);
+    let run = &stored.bisection_runs[0];
+    assert_eq!(run.uuid, report.bisection_runs[0].uuid);
+    assert_eq!(run.divergence_block_bounds.upper_bound.number, 21);
+    assert_eq!(
+        run.indexer2_operator_contact.as_deref(),
+        Some("ops@indexer.example")
+    );
+    let step_blocks: Vec<i64> = run.bisects.iter().map(|b| b.block.number).collect();
+    assert_eq!(step_blocks, vec![21, 20]);
+    assert_eq!(run.entity_diff, report.bisection_runs[0].entity_diff);
+    assert_eq!(run.eth_call_diff, report.bisection_runs[0].eth_call_diff);
+    assert_eq!(
+        run.graft_divergence,
+        report.bisection_runs[0].graft_divergence
+    );
+    assert_eq!(run.indexer1_graph_node_version.as_deref(), Some("0.35.0"));
+    assert_eq!(run.indexer2_graph_node_version.as_deref(), Some("0.34.1"));
+
+    // Version pairs are unordered.
+    let pairs = store
+        .divergences_by_graph_node_version_pair(None)
+        .await
+        .unwrap();
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].version1.as_deref(), Some("0.34.1"));
+    assert_eq!(pairs[0].version2.as_deref(), Some("0.35.0"));
+    assert_eq!((pairs[0].bisection_runs, pairs[0].deployments), (1, 1));
+
+    let runs = |filter| {
+        let store = &store;
+        async move { store.bisection_runs(&filter).await.unwrap() }
+    };
+    let all_runs = runs(BisectionRunsQuery {
+        deployments: vec![deployment],
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(all_runs.len(), 1);
+    assert!(all_runs[0].poi1_id.is_some() && all_runs[0].block_id.is_some());
+    let at_block = |n| BisectionRunsQuery {
+        divergence_block_number: Some(n),
+        ..Default::default()
+    };
+    assert_eq!(runs(at_block(21)).await.len(), 1);
+    assert!(runs(at_block(20)).await.is_empty());
+    assert!(runs(BisectionRunsQuery {
+        since: Some(all_runs[0].created_at + Duration::days(1)),
+        ..Default::default()
+    })
+    .await
+    .is_empty());
+    let with_poi = |poi_byte| BisectionRunsQuery {
+        poi: Some([poi_byte; 32].into()),
+        ..Default::default()
+    };
+    assert_eq!(runs(with_poi(2)).await.len(), 1);
+    assert!(runs(with_poi(3)).await.is_empty());
+}
+
+#[tokio::test]
+async fn write_then_read_indexing_loop_checkpoint() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    assert!(store
+        .last_indexing_loop_checkpoint()
+        .await
+        .unwrap()
+        .is_none());
+
+    store
+        .write_indexing_loop_checkpoint(&[IndexerAddress::from([1; 20])])
+        .await
+        .unwrap();
+    store
+        .write_indexing_loop_checkpoint(&[
+            IndexerAddress::from([1; 20]),
+            IndexerAddress::from([2; 20]),
+        ])
+        .await
+        .unwrap();
+
+    let checkpoint = store
+        .last_indexing_loop_checkpoint()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        checkpoint.indexer_addresses,
+        vec![
+            Some(IndexerAddress::from([1; 20])),
+            Some(IndexerAddress::from([2; 20]))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn write_subgraph_feed() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+
+    store
+        .write_subgraph_feed(
+            &[NewFeedPoiConsensus {
+                deployment: deployment.clone(),
+                block_number: 42,
+                block_hash: None,
+                consensus_poi: Some([1; 32].into()),
+                indexer_count: 3,
+                agreeing_indexer_count: 2,
+            }],
+            &[NewFeedPoiDivergence {
+                deployment,
+                block_number: 42,
+                indexer_address: IndexerAddress::from([1; 20]),
+                poi: [2; 32].into(),
+                consensus_poi: [1; 32].into(),
+            }],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn alerts_are_routed_to_matching_channels() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=2)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+    store
+        .add_indexer_tag(&IndexerAddress::from([1; 20]), "team-a")
+        .await
+        .unwrap();
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .map(|indexer| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [1; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+
+    let received = Arc::new(std::sync::Mutex::new(vec![]));
+    let router = axum::Router::new().route(
+        "/:channel",
+        axum::routing::post({
+            let received = received.clone();
+            move |axum::extract::Path(channel): axum::extract::Path<String>,
+                  axum::Json(event): axum::Json<serde_json::Value>| async move {
+                received
+                    .lock()
+                    .unwrap()
+                    .push((channel, event["kind"].as_str().unwrap().to_string()));
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let config: AlertsConfig = serde_json::from_value(serde_json::json!({
+        "channels": [
+            { "type": "webhook", "name": "team-a", "url": format!("http://{address}/team-a") },
+            { "type": "webhook", "name": "oncall", "url": format!("http://{address}/oncall") },
+        ],
+        "routes": [
+            { "indexerTags": ["team-a"], "channels": ["team-a"] },
+            { "networks": ["mainnet"], "minSeverity": "critical", "channels": ["oncall"] },
+        ],
+    }))
+    .unwrap();
+    let divergence = |indexer: u8| NewEvent {
+        kind: EventKind::PoiDivergenceDetected,
+        deployment: Some(deployment.clone()),
+        payload: serde_json::json!({
+            "divergences": [{ "indexerAddress": IndexerAddress::from([indexer; 20]) }],
+        }),
+    };
+    let mut alert_router = AlertRouter::new();
+
+    // Events from before alerting was enabled aren't delivered.
+    store.write_events(&[divergence(1)]).await.unwrap();
+    alert_router.route(&store, &config).await.unwrap();
+    assert!(received.lock().unwrap().is_empty());
+
+    store
+        .write_events(&[
+            divergence(1),
+            divergence(2),
+            NewEvent {
+                kind: EventKind::IndexerUnreachable,
+                deployment: None,
+                payload: serde_json::json!({ "indexerAddress": IndexerAddress::from([1; 20]) }),
+            },
+            NewEvent {
+                kind: EventKind::ConfigChanged,
+                deployment: None,
+                payload: serde_json::json!({ "changedFields": [] }),
+            },
+        ])
+        .await
+        .unwrap();
+    alert_router.route(&store, &config).await.unwrap();
+    assert_eq!(
+        *received.lock().unwrap(),
+        [
+            ("team-a", "POI_DIVERGENCE_DETECTED"),
+            ("oncall", "POI_DIVERGENCE_DETECTED"),
+            ("oncall", "POI_DIVERGENCE_DETECTED"),
+            ("team-a", "INDEXER_UNREACHABLE"),
+        ]
+        .map(|(channel, kind)| (channel.to_string(), kind.to_string()))
+    );
+}
+
+#[tokio::test]
+async fn events_are_read_in_order_after_the_cursor() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    assert_eq!(store.latest_event_id().await.unwrap(), None);
+
+    store
+        .write_events(&[
+            NewEvent {
+                kind: EventKind::PoiDivergenceDetected,
+                deployment: Some(deployment.clone()),
+                payload: serde_json::json!({ "blockNumber": 42 }),
+            },
+            NewEvent {
+                kind: EventKind::ConfigChanged,
+                deployment: None,
+                payload: serde_json::json!({ "changedFields": [] }),
+            },
+        ])
+        .await
+        .unwrap();
+
+    let events = store.events(EventsQuery::default()).await.unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].id < events[1].id);
+    assert_eq!(events[0].kind, EventKind::PoiDivergenceDetected);
+    assert_eq!(store.latest_event_id().await.unwrap(), Some(events[1].id));
+
+    let filtered = store
+        .events(EventsQuery {
+            deployment: Some(deployment),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(filtered.len(), 1);
+
+    assert_eq!(store.event_cursor("webhooks").await.unwrap(), None);
+    store
+        .set_event_cursor("webhooks", events[0].id)
+        .await
+        .unwrap();
+    store
+        .set_event_cursor("webhooks", events[1].id)
+        .await
+        .unwrap();
+    let cursor = store.event_cursor("webhooks").await.unwrap();
+    assert_eq!(cursor, Some(events[1].id));
+
+    let after_cursor = store
+        .events(EventsQuery {
+            after_id: cursor,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(after_cursor.is_empty());
+}
+
+#[tokio::test]
+#[should_panic] // FIXME
+async fn deployments_with_name() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+
+    let ipfs_cid1 = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+    let ipfs_cid2 = "QmYzsCjrVwwXtdsNm3PZVNziLGmb9o513GUzkq5wwhgXDT";
+
+    store
+        .create_sg_deployment("mainnet", ipfs_cid1)
+        .await
+        .unwrap();
+    store
+        .create_sg_deployment("mainnet", ipfs_cid2)
+        .await
+        .unwrap();
+    store.set_deployment_name(ipfs_cid2, "foo").await.unwrap();
+
+    let deployments = {
+        let filter = SgDeploymentsQuery {
+            name: Some("foo".to_string()),
+            ..Default::default()
+        };
+        store.sg_deployments(filter).await.unwrap()
+    };
+    assert!(deployments.len() == 1);
+    assert_eq!(deployments[0].cid.to_string(), ipfs_cid1);
+    // FIXME:
+    //assert_eq!(deployments[0].name, Some("foo".to_string()));
+}
+
+#[tokio::test]
+async fn deployment_names_are_set_in_bulk() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let ipfs_cid1 = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+    let ipfs_cid2 = "QmYzsCjrVwwXtdsNm3PZVNziLGmb9o513GUzkq5wwhgXDT";
+    let unknown = "QmWmyoMoctfbAaiEs2G46gpeUmhqFRDW6KWo64y5r581Vz";
+
+    for network in ["mainnet", "gnosis"] {
+        store
+            .create_network(&NewNetwork {
+                name: network.to_string(),
+                caip2: None,
+            })
+            .await
+            .unwrap();
+    }
+    store
+        .create_sg_deployment("mainnet", ipfs_cid1)
+        .await
+        .unwrap();
+    store
+        .create_sg_deployment("gnosis", ipfs_cid1)
+        .await
+        .unwrap();
+    store
+        .create_sg_deployment("mainnet", ipfs_cid2)
+        .await
+        .unwrap();
+    store
+        .set_deployment_name(ipfs_cid2, "curated")
+        .await
+        .unwrap();
+
+    let names = |overwrite_name: &str| {
+        [ipfs_cid1, ipfs_cid2, unknown]
+            .into_iter()
+            .map(|ipfs_cid| DeploymentName {
+                ipfs_cid: IpfsCid::from_str(ipfs_cid).unwrap(),
+                name: format!("{overwrite_name} {ipfs_cid}"),
+            })
+            .collect::<Vec<_>>()
+    };
+    let deployment_names = || async {
+        store
+            .sg_deployments(SgDeploymentsQuery::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|deployment| (deployment.cid.to_string(), deployment.name))
+            .collect::<Vec<_>>()
+    };
+
+    // Deployments that already have a name keep it...
+    let named = store
+        .set_deployment_names(&names("synced"), false)
+        .await
+        .unwrap();
+    assert_eq!(named, 2);
+    let synced = Some(format!("synced {ipfs_cid1}"));
+    assert_eq!(
+        deployment_names().await,
+        vec![
+            (ipfs_cid1.to_string(), synced.clone()),
+            (ipfs_cid1.to_string(), synced),
+            (ipfs_cid2.to_string(), Some("curated".to_string())),
+        ]
+    );
+
+    // ...unless they're overwritten.
+    let named = store
+        .set_deployment_names(&names("resynced"), true)
+        .await
+        .unwrap();
+    assert_eq!(named, 3);
+    assert!(deployment_names()
+        .await
+        .iter()
+        .all(|(ipfs_cid, name)| name.as_deref() == Some(&format!("resynced {ipfs_cid}"))));
+}
+
+#[tokio::test]
+async fn create_divergence_investigation_request() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+
+    let uuid = store
+        .create_divergence_investigation_request(serde_json::json!({}), 0)
+        .await
+        .unwrap();
+
+    let job = store
+        .claim_divergence_investigation_job("worker", std::time::Duration::from_secs(60), 5)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.uuid, uuid);
+    assert_eq!(job.status, JobStatus::Running);
+    assert_eq!(job.attempts, 1);
+    assert_eq!(job.locked_by.as_deref(), Some("worker"));
+}
+
+#[tokio::test]
+async fn divergence_investigation_jobs_are_claimed_once_by_priority() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let stale_after = std::time::Duration::from_secs(60);
+
+    let low = store
+        .create_divergence_investigation_request(serde_json::json!({}), 0)
+        .await
+        .unwrap();
+    let high = store
+        .create_divergence_investigation_request(serde_json::json!({}), 10)
+        .await
+        .unwrap();
+
+    let (job1, job2, job3) = tokio::join!(
+        store.claim_divergence_investigation_job("worker1", stale_after, 5),
+        store.claim_divergence_investigation_job("worker2", stale_after, 5),
+        store.claim_divergence_investigation_job("worker3", stale_after, 5),
+    );
+    let mut claimed = [job1, job2, job3]
+        .into_iter()
+        .filter_map(|job| job.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(claimed.len(), 2);
+    claimed.sort_by_key(|job| -job.priority);
+    assert_eq!(claimed[0].uuid, high);
+    assert_eq!(claimed[1].uuid, low);
+    assert_ne!(claimed[0].locked_by, claimed[1].locked_by);
+
+    // Only the worker that claimed a job can keep it alive.
+    let owner = claimed[0].locked_by.clone().unwrap();
+    assert!(store
+        .heartbeat_divergence_investigation_job(&high, &owner)
+        .await
+        .unwrap());
+    assert!(!store
+        .heartbeat_divergence_investigation_job(&high, "worker4")
+        .await
+        .unwrap());
+
+    store
+        .delete_divergence_investigation_request(&high)
+        .await
+        .unwrap();
+    assert!(store
+        .divergence_investigation_job(&high)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn leader_election_fails_over_when_the_leader_goes_away() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let config = Config::default();
+
+    let mut replica1 = LeaderElection::new((*store).clone());
+    let mut replica2 = LeaderElection::new((*store).clone());
+    assert!(replica1.elect(&config).await);
+    assert!(!replica2.elect(&config).await);
+    // Staying the leader doesn't need the lock again.
+    assert!(replica1.elect(&config).await);
+    assert!(!replica2.elect(&config).await);
+
+    // Other instances that share the database have their own leader.
+    let other_instance = Config {
+        instance_name: "other".to_string(),
+        ..Config::default()
+    };
+    let mut replica3 = LeaderElection::new((*store).clone());
+    assert!(replica3.elect(&other_instance).await);
+
+    drop(replica1);
+    assert!(replica2.elect(&config).await);
+}
+
+#[tokio::test]
+async fn failed_divergence_investigation_jobs_are_retried_with_backoff() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    let stale_after = std::time::Duration::from_secs(60);
+    let backoff = std::time::Duration::from_secs(30);
+
+    let uuid = store
+        .create_divergence_investigation_request(serde_json::json!({}), 0)
+        .await
+        .unwrap();
+
+    for (attempt, backoff_secs) in [(1, 30), (2, 60)] {
+        let job = store
+            .claim_divergence_investigation_job("worker", stale_after, 3)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.attempts, attempt);
+        store
+            .fail_divergence_investigation_job(&uuid, "worker", "boom", 3, backoff)
+            .await
+            .unwrap();
+
+        clock.advance(Duration::seconds(backoff_secs - 1));
+        assert!(store
+            .claim_divergence_investigation_job("worker", stale_after, 3)
+            .await
+            .unwrap()
+            .is_none());
+        clock.advance(Duration::seconds(1));
+    }
+
+    // The last attempt fails for good.
+    store
+        .claim_divergence_investigation_job("worker", stale_after, 3)
+        .await
+        .unwrap()
+        .unwrap();
+    store
+        .fail_divergence_investigation_job(&uuid, "worker", "boom", 3, backoff)
+        .await
+        .unwrap();
+    clock.advance(Duration::days(1));
+    assert!(store
+        .claim_divergence_investigation_job("worker", stale_after, 3)
+        .await
+        .unwrap()
+        .is_none());
+
+    let job = store
+        .divergence_investigation_job(&uuid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.attempts, 3);
+    assert_eq!(job.last_error.as_deref(), Some("boom"));
+}
+
+#[tokio::test]
+async fn stale_divergence_investigation_jobs_are_claimed_again() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    let stale_after = std::time::Duration::from_secs(60);
+
+    let uuid = store
+        .create_divergence_investigation_request(serde_json::json!({}), 0)
+        .await
+        .unwrap();
+    store
+        .claim_divergence_investigation_job("worker1", stale_after, 2)
+        .await
+        .unwrap()
+        .unwrap();
+
+    clock.advance(Duration::seconds(30));
+    assert!(store
+        .heartbeat_divergence_investigation_job(&uuid, "worker1")
+        .await
+        .unwrap());
+    clock.advance(Duration::seconds(45));
+    assert!(store
+        .claim_divergence_investigation_job("worker2", stale_after, 2)
+        .await
+        .unwrap()
+        .is_none());
+
+    // worker1 stopped sending heartbeats.
+    clock.advance(Duration::seconds(30));
+    let job = store
+        .claim_divergence_investigation_job("worker2", stale_after, 2)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.uuid, uuid);
+    assert_eq!(job.attempts, 2);
+    assert!(!store
+        .heartbeat_divergence_investigation_job(&uuid, "worker1")
+        .await
+        .unwrap());
+
+    // worker2 died too, and there are no attempts left.
+    clock.advance(Duration::seconds(120));
+    assert!(store
+        .claim_divergence_investigation_job("worker3", stale_after, 2)
+        .await
+        .unwrap()
+        .is_none());
+    let job = store
+        .divergence_investigation_job(&uuid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+}
+
+//#[tokio::test]
+//async fn poi_db_roundtrip() {
+//    let docker_cli = Cli::default();
+//    let store = EmptyStoreForTesting::new(&docker_cli).await.unwrap();
+//
+//    let mut rng = fast_rng(0);
+//    let indexers = gen_indexers(&mut rng, 100);
+//
+//    let indexing_statuses = queries::query_indexing_statuses(indexers, metrics()).await;
+//    let pois =
+//        queries::query_proofs_of_indexing(indexing_statuses, BlockChoicePolicy::Earliest).await;
+//
+//    let mut conn = store.conn().unwrap();
+//    conn.test_transaction(|conn| test_pois(conn, &pois, PoiLiveness::NotLive, false));
+//    conn.test_transaction(|conn| test_pois(conn, &pois, PoiLiveness::Live, true));
+//}
+
+//#[tokio::test]
+//async fn test_additional_pois() {
+//    let docker_cli = Cli::default();
+//    let store = EmptyStoreForTesting::new(&docker_cli).await.unwrap();
+//
+//    let mut rng = fast_rng(0);
+//    let indexers = gen_indexers(&mut rng, 100);
+//
+//    let indexing_statuses = queries::query_indexing_statuses(indexers, metrics()).await;
+//    let pois =
+//        queries::query_proofs_of_indexing(indexing_statuses, BlockChoicePolicy::Earliest).await;
+//
+//    let mut conn = store.conn().unwrap();
+//
+//    conn.test_transaction(|conn| -> Result<(), anyhow::Error> {
+//        // Write the original PoIs as Live
+//        diesel_queries::write_pois(conn, &pois, PoiLiveness::Live).unwrap();
+//
+//        // Choose a deployment to add an additional PoI
+//        let mut additional_poi = pois[0].clone(); // clone one of the original PoIs
+//        additional_poi.block.number += 1; // bump the number to N + 1
+//        additional_poi.block.hash = Some(gen_bytes32(&mut rng));
+//        additional_poi.proof_of_indexing = gen_bytes32(&mut rng); // Optionally, set new PoI value if needed
+//
+//        // Write the additional PoI
+//        diesel_queries::write_pois(conn, &[additional_poi.clone()], PoiLiveness::Live).unwrap();
+//
+//        // Query the live PoIs for the specific deployment
+//        let specific_deployment_pois = diesel_queries::pois(
+//            conn,
+//            None,
+//            Some(&[additional_poi.deployment.0.clone()]),
+//            None,
+//            None,
+//            true,
+//        )
+//        .unwrap();
+//
+//        // Assert that only the new PoI exists as live PoIs for that deployment
+//        assert_eq!(specific_deployment_pois.len(), 1);
+//        assert_eq!(
+//            specific_deployment_pois[0].block.number as u64,
+//            additional_poi.block.number
+//        );
+//
+//        Ok(())
+//    });
+//}
+
+//fn test_pois(
+//    conn: &mut diesel::PgConnection,
+//    pois: &[ProofOfIndexing],
+//    liveness: PoiLiveness,
+//    live_poi_test: bool,
+//) -> Result<(), anyhow::Error> {
+//    diesel_queries::write_pois(conn, pois, liveness).unwrap();
+//    let all_deployments: Vec<String> = pois.iter().map(|poi| poi.deployment.0.clone()).collect();
+//
+//    // Common logic to create poi_triples
+//    let poi_triples: BTreeSet<(String, String, Vec<u8>)> = pois
+//        .iter()
+//        .map(|poi| {
+//            (
+//                poi.deployment.0.clone(),
+//                poi.indexer.address_string(),
+//                poi.proof_of_indexing.0.to_vec(),
+//            )
+//        })
+//        .collect();
+//    let read_pois = diesel_queries::pois(
+//        conn,
+//        None,
+//        Some(&all_deployments),
+//        None,
+//        None,
+//        live_poi_test,
+//    )
+//    .unwrap();
+//    let read_poi_triples: BTreeSet<(String, String, Vec<u8>)> = read_pois
+//        .into_iter()
+//        .map(|poi| (poi.sg_deployment.cid, poi.indexer.name.unwrap(), poi.poi))
+//        .collect();
+//
+//    assert!(poi_triples == read_poi_triples);
+//
+//    if live_poi_test {
+//        // Specific test for live pois
+//        assert_eq!(
+//            diesel_queries::pois(conn, None, Some(&all_deployments), None, None, true)
+//                .unwrap()
+//                .into_iter()
+//                .map(|poi| poi.id)
+//                .collect::<Vec<_>>(),
+//            diesel_queries::pois(conn, None, Some(&all_deployments), None, None, false)
+//                .unwrap()
+//                .into_iter()
+//                .map(|poi| poi.id)
+//                .collect::<Vec<_>>()
+//        );
+//    } else {
+//        // Specific test for not live pois
+//        let live_pois =
+//            diesel_queries::pois(conn, None, Some(&all_deployments), None, None, true).unwrap();
+//        assert!(live_pois.is_empty());
+//    }
+//
+//    Ok(())
+//}
+
+#[tokio::test]
+async fn pois_are_backfilled_over_a_block_range() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let poi = |number: u64| PartialProofOfIndexing {
+        block: BlockPointer {
+            number,
+            hash: Some(vec![number as u8; 32].into()),
+        },
+        proof_of_indexing: [number as u8; 32].into(),
+    };
+    let indexer: Arc<dyn IndexerClient> = Arc::new(MockIndexer {
+        name: "indexer".to_string(),
+        deployment_details: vec![DeploymentDetails {
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            latest_block: BlockPointer {
+                number: 30,
+                hash: None,
+            },
+            canonical_pois: vec![poi(10), poi(20), poi(30)],
+            earliest_block_num: 0,
+            substreams: false,
+        }],
+        fail_indexing_statuses: false,
+    });
+    let indexers = vec![indexer.clone()];
+    store.write_indexers(&indexers).await.unwrap();
+    let statuses = indexer.indexing_statuses().await.unwrap();
+    store.write_indexing_statuses(&statuses).await.unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let admin_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+        .await
+        .unwrap()
+        .api_key;
+    let admin_key = ApiKey::from_str(&admin_key).unwrap();
+    let response = request_schema(state.clone(), Some(admin_key))
+        .execute(format!(
+            r#"mutation {{
+                backfillPois(deployment: "{deployment}", fromBlock: 10, toBlock: 25, step: 10) {{
+                    nextBlock
+                }}
+            }}"#
+        ))
+        .await;
+    assert_eq!(response.errors, vec![]);
+
+    // One block at a time, until the backfill is done.
+    let mut steps = 0;
+    while poi_backfill::backfill_next_block(&store, &indexers)
+        .await
+        .unwrap()
+    {
+        steps += 1;
+    }
+    assert_eq!(steps, 2);
+
+    let pois = store
+        .pois(None, &[deployment.clone()], None, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(pois.len(), 2);
+    assert!(store
+        .live_pois(None, None, Some(&[deployment.clone()]), None, None, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let response = request_schema(state, None)
+        .execute(
+            r#"{
+                poiBackfills {
+                    deployment { cid }
+                    fromBlock
+                    toBlock
+                    nextBlock
+                    poisWritten
+                    error
+                }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "poiBackfills": [{
+                "deployment": { "cid": deployment.to_string() },
+                "fromBlock": 10,
+                "toBlock": 25,
+                "nextBlock": null,
+                "poisWritten": 2,
+                "error": null,
+            }]
+        })
+    );
+}
+
+/// In read-only mode, stored data is still served but all mutations fail,
+/// whatever the API key.
+#[tokio::test]
+async fn mutations_fail_in_read_only_mode() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let config = Config {
+        read_only: true,
+        ..Default::default()
+    };
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(config).1,
+    ));
+    let admin_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+        .await
+        .unwrap()
+        .api_key;
+    let admin_key = ApiKey::from_str(&admin_key).unwrap();
+
+    for mutation in [
+        "setConfiguration(config: {})",
+        "importConfig(document: { config: {} }) { taggedIndexers }",
+        r#"deleteWatchlist(name: "foo")"#,
+        "createApiKey(permissionLevel: ADMIN) { apiKey }",
+        r#"setChainEnabled(network: "mainnet", enabled: false) { name }"#,
+    ] {
+        let response = request_schema(state.clone(), Some(admin_key.clone()))
+            .execute(format!("mutation {{ {mutation} }}"))
+            .await;
+
+        assert_eq!(response.errors.len(), 1, "{mutation}");
+        let code = response.errors[0]
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get("code"));
+        assert_eq!(
+            code,
+            Some(&async_graphql::Value::from("READ_ONLY")),
+            "{mutation}: {:?}",
+            response.errors
+        );
+    }
+    assert_eq!(store.current_config().await.unwrap(), None);
+
+    let response = request_schema(state, None)
+        .execute("{ instance { readOnly } }")
+        .await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({ "instance": { "readOnly": true } })
+    );
+}
+
+#[tokio::test]
+async fn null_pois_of_substreams_deployments_are_expected() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let substreams = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let other = IpfsCid::from_str("QmYiiCtcXmSHXN3m2nyqLaTM7zi81KjVdZ9WXkcrCKrkjr").unwrap();
+    let details = |deployment: &IpfsCid, substreams| DeploymentDetails {
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        latest_block: BlockPointer {
+            number: 42,
+            hash: None,
+        },
+        canonical_pois: vec![],
+        earliest_block_num: 0,
+        substreams,
+    };
+    let indexer: Arc<dyn IndexerClient> = Arc::new(MockIndexer {
+        name: "indexer".to_string(),
+        deployment_details: vec![details(&substreams, true), details(&other, false)],
+        fail_indexing_statuses: false,
+    });
+    let indexers = vec![indexer.clone()];
+    store.write_indexers(&indexers).await.unwrap();
+    let statuses = indexer.clone().indexing_statuses().await.unwrap();
+    store.write_indexing_statuses(&statuses).await.unwrap();
+
+    assert_eq!(
+        substreams::tag_deployments(&store, &indexers)
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        substreams::tag_deployments(&store, &indexers)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        store.substreams_sg_deployments().await.unwrap(),
+        [substreams.clone()].into()
+    );
+
+    let poi = |deployment: &IpfsCid| ProofOfIndexing {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: 42,
+            hash: Some(vec![42; 32].into()),
+        },
+        proof_of_indexing: substreams::NULL_POI,
+    };
+    store
+        .write_pois(vec![poi(&substreams), poi(&other)], PoiLiveness::Live)
+        .await
+        .unwrap();
+    let onchain_poi = |allocation_id: &str, deployment: &IpfsCid| NewOnchainPoi {
+        allocation_id: allocation_id.to_string(),
+        indexer_address: indexer.address(),
+        deployment: deployment.clone(),
+        poi: [1; 32].into(),
+        epoch: 1,
+        block_number: 42,
+        network_subgraph: None,
+    };
+    store
+        .write_onchain_pois(&[
+            onchain_poi("0x01", &substreams),
+            onchain_poi("0x02", &other),
+        ])
+        .await
+        .unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let response = request_schema(state, None)
+        .execute("{ onchainPoiCrossChecks { allocationId substreams matches } }")
+        .await;
+    assert_eq!(response.errors, vec![]);
+    let mut cross_checks = response.data.into_json().unwrap()["onchainPoiCrossChecks"]
+        .as_array()
+        .unwrap()
+        .clone();
+    cross_checks.sort_by_key(|cross_check| cross_check["allocationId"].to_string());
+    assert_eq!(
+        cross_checks,
+        [
+            serde_json::json!({ "allocationId": "0x01", "substreams": true, "matches": null }),
+            serde_json::json!({ "allocationId": "0x02", "substreams": false, "matches": false }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn block_hash_conflicts_are_possible_reorgs() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let indexer = |name: &str| -> Arc<dyn IndexerClient> {
+        Arc::new(MockIndexer {
+            name: name.to_string(),
+            deployment_details: vec![],
+            fail_indexing_statuses: false,
+        })
+    };
+    let indexers = vec![indexer("a"), indexer("b")];
+    store.write_indexers(&indexers).await.unwrap();
+
+    let poi = |indexer: &Arc<dyn IndexerClient>, hash: u8, poi: u8| ProofOfIndexing {
+        indexer: indexer.clone(),
+        deployment: deployment.clone(),
+        network: "mainnet".to_string(),
+        block: BlockPointer {
+            number: 42,
+            hash: Some(vec![hash; 32].into()),
+        },
+        proof_of_indexing: [poi; 32].into(),
+    };
+    store
+        .write_pois(vec![poi(&indexers[0], 1, 1)], PoiLiveness::Live)
+        .await
+        .unwrap();
+    assert!(store
+        .block_hash_conflicts(None, None)
+        .await
+        .unwrap()
+        .is_empty());
+    // Writing the PoI again doesn't record the conflict twice.
+    for _ in 0..2 {
+        store
+            .write_pois(vec![poi(&indexers[1], 2, 2)], PoiLiveness::Live)
+            .await
+            .unwrap();
+    }
+
+    let conflicts = store.block_hash_conflicts(None, None).await.unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].network, "mainnet");
+    assert_eq!(conflicts[0].block_number, 42);
+    assert_eq!(
+        conflicts[0].hashes,
+        vec![
+            (vec![1; 32].into(), vec![indexers[0].address()]),
+            (vec![2; 32].into(), vec![indexers[1].address()]),
+        ]
+    );
+    assert!(store
+        .block_hash_conflicts(Some("arbitrum-one"), None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Indexer b submitted the PoI of indexer a's fork on-chain.
+    store
+        .write_onchain_pois(&[NewOnchainPoi {
+            allocation_id: "0x01".to_string(),
+            indexer_address: indexers[1].address(),
+            deployment: deployment.clone(),
+            poi: [1; 32].into(),
+            epoch: 1,
+            block_number: 42,
+            network_subgraph: None,
+        }])
+        .await
+        .unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let response = request_schema(state, None)
+        .execute(
+            r#"{
+                onchainPoiCrossChecks { matches possibleReorg }
+                blockHashConflicts(network: "mainnet") { blockNumber hashes { indexers } }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "onchainPoiCrossChecks": [{ "matches": null, "possibleReorg": true }],
+            "blockHashConflicts": [{
+                "blockNumber": 42,
+                "hashes": [
+                    { "indexers": [indexers[0].address().to_string()] },
+                    { "indexers": [indexers[1].address().to_string()] },
+                ],
+            }],
+        })
+    );
+}
+
+#[tokio::test]
+async fn admin_mutations_are_audited() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let admin_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+        .await
+        .unwrap()
+        .api_key;
+    let admin_key = ApiKey::from_str(&admin_key).unwrap();
+    let operator_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Operator, None)
+        .await
+        .unwrap()
+        .api_key;
+    store
+        .create_network(&NewNetwork {
+            name: "mainnet".to_string(),
+            caip2: None,
+        })
+        .await
+        .unwrap();
+
+    for mutation in [
+        r#"setConfiguration(config: { databaseUrl: "postgres://user:secret@db", instanceName: "a" })"#
+            .to_string(),
+        format!(r#"modifyApiKey(apiKey: "{operator_key}", permissionLevel: ADMIN)"#),
+        r#"setChainEnabled(network: "mainnet", enabled: false) { name }"#.to_string(),
+        r#"deleteNetwork(network: "mainnet")"#.to_string(),
+    ] {
+        let response = request_schema(state.clone(), Some(admin_key.clone()))
+            .execute(format!("mutation {{ {mutation} }}"))
+            .await;
+        assert_eq!(response.errors, vec![], "{mutation}");
+    }
+
+    // Only admins can read the audit log.
+    let operator_key = ApiKey::from_str(&operator_key).unwrap();
+    let response = request_schema(state.clone(), None)
+        .execute("{ adminAuditLog { id } }")
+        .await;
+    assert_eq!(response.errors.len(), 1);
+
+    let response = request_schema(state, Some(admin_key.clone()))
+        .execute(
+            r#"{
+                all: adminAuditLog { apiKeyPublicPrefix mutation payload }
+                deletions: adminAuditLog(mutation: "deleteNetwork") { mutation }
+            }"#,
+        )
+        .await;
+    assert_eq!(response.errors, vec![]);
+    let admin_prefix = admin_key.public_part_as_string();
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "all": [
+                {
+                    "apiKeyPublicPrefix": admin_prefix,
+                    "mutation": "deleteNetwork",
+                    "payload": { "network": "mainnet" },
+                },
+                {
+                    "apiKeyPublicPrefix": admin_prefix,
+                    "mutation": "setChainEnabled",
+                    "payload": { "network": "mainnet", "enabled": false },
+                },
+                {
+                    "apiKeyPublicPrefix": admin_prefix,
+                    "mutation": "modifyApiKey",
+                    "payload": {
+                        "publicPrefix": operator_key.public_part_as_string(),
+                        "notes": null,
+                        "permissionLevel": "ADMIN",
+                    },
+                },
+                // Only the names of the changed fields, which may contain
+                // secrets.
+                {
+                    "apiKeyPublicPrefix": admin_prefix,
+                    "mutation": "setConfiguration",
+                    "payload": { "changedFields": ["databaseUrl", "instanceName"] },
+                },
+            ],
+            "deletions": [{ "mutation": "deleteNetwork" }],
+        })
+    );
+}
+
+#[tokio::test]
+async fn graph_node_version_history_is_kept_per_indexer() {
+    let clock = MockClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap());
+    let store = EmptyStoreForTesting::with_clock(Arc::new(clock.clone()))
+        .await
+        .unwrap();
+    let indexer = |name: &str| -> Arc<dyn IndexerClient> {
+        Arc::new(MockIndexer {
+            name: name.to_string(),
+            deployment_details: vec![],
+            fail_indexing_statuses: false,
+        })
+    };
+    let indexers = vec![indexer("a"), indexer("b")];
+    store.write_indexers(&indexers).await.unwrap();
+    let version = |version: &str| {
+        Ok(GraphNodeCollectedVersion {
+            version: Some(version.to_string()),
+            commit: Some(format!("{version}-commit")),
+            error_response: None,
+            collected_at: Default::default(),
+        })
+    };
+
+    store
+        .write_graph_node_versions(HashMap::from([
+            (indexers[0].clone(), version("0.35.0")),
+            (indexers[1].clone(), version("0.35.0")),
+        ]))
+        .await
+        .unwrap();
+    let before_upgrade = clock.now().naive_utc();
+    clock.advance(Duration::minutes(2));
+    // Reporting the same version again, or failing to report one, doesn't
+    // start a new period.
+    store
+        .write_graph_node_versions(HashMap::from([
+            (indexers[0].clone(), version("0.35.0")),
+            (indexers[1].clone(), Err(anyhow::anyhow!("timeout"))),
+        ]))
+        .await
+        .unwrap();
+    clock.advance(Duration::minutes(2));
+    store
+        .write_graph_node_versions(HashMap::from([(indexers[0].clone(), version("0.36.0"))]))
+        .await
+        .unwrap();
+
+    let indexer_a = &store
+        .indexers(IndexersQuery {
+            address: Some(indexers[0].address()),
+            ..Default::default()
+        })
+        .await
+        .unwrap()[0];
+    let history = store
+        .indexer_graph_node_version_history(indexer_a.id)
+        .await
+        .unwrap();
+    let history: Vec<_> = history
+        .iter()
+        .map(|v| (v.version_string.as_deref(), v.valid_to.is_some()))
+        .collect();
+    assert_eq!(history, [(Some("0.35.0"), true), (Some("0.36.0"), false)]);
+    assert!(indexer_a.graph_node_version.is_some());
+
+    let distribution = |at| {
+        let store = (*store).clone();
+        async move {
+            store
+                .graph_node_version_distribution(at)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|count| (count.version_string.unwrap(), count.indexers))
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        distribution(None).await,
+        [("0.35.0".to_string(), 1), ("0.36.0".to_string(), 1)]
+    );
+    assert_eq!(
+        distribution(Some(before_upgrade)).await,
+        [("0.35.0".to_string(), 2)]
+    );
+}
+
+#[tokio::test]
+async fn instance_config_is_exported_and_imported() {
+    let indexer = |i: u8| -> Arc<dyn IndexerClient> {
+        Arc::new(RealIndexer::new(
+            None,
+            IndexerAddress::from([i; 20]),
+            format!("http://indexer-{i}:8030/graphql"),
+            graphix_lib::metrics()
+                .public_proofs_of_indexing_requests
+                .clone(),
+        ))
+    };
+    let admin_key = |store: &EmptyStoreForTesting| {
+        let store = (**store).clone();
+        async move {
+            let api_key = store
+                .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+                .await
+                .unwrap()
+                .api_key;
+            ApiKey::from_str(&api_key).unwrap()
+        }
+    };
+    let ipfs_cid1 = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+    let ipfs_cid2 = "QmYzsCjrVwwXtdsNm3PZVNziLGmb9o513GUzkq5wwhgXDT";
+
+    let mainnet = NewNetwork {
+        name: "mainnet".to_string(),
+        caip2: None,
+    };
+
+    let production = EmptyStoreForTesting::new().await.unwrap();
+    production.create_network(&mainnet).await.unwrap();
+    production
+        .write_indexers(&[indexer(1), indexer(2)])
+        .await
+        .unwrap();
+    production
+        .add_indexer_tag(&IndexerAddress::from([1; 20]), "own-fleet")
+        .await
+        .unwrap();
+    for ipfs_cid in [ipfs_cid1, ipfs_cid2] {
+        production
+            .create_sg_deployment("mainnet", ipfs_cid)
+            .await
+            .unwrap();
+        production
+            .set_deployment_name(ipfs_cid, &format!("name-{ipfs_cid}"))
+            .await
+            .unwrap();
+    }
+    let config = Config {
+        instance_name: "production".to_string(),
+        ..Default::default()
+    };
+    let state = Arc::new(GraphixState::new(
+        (*production).clone(),
+        watch::channel(config).1,
+    ));
+    let response = request_schema(state, Some(admin_key(&production).await))
+        .execute("{ exportConfig }")
+        .await;
+    assert_eq!(response.errors, vec![]);
+    let document = response.data.into_json().unwrap()["exportConfig"].clone();
+
+    // The staging instance only knows about one of the deployments so far.
+    let staging = EmptyStoreForTesting::new().await.unwrap();
+    staging.create_network(&mainnet).await.unwrap();
+    staging.write_indexers(&[indexer(1)]).await.unwrap();
+    staging
+        .create_sg_deployment("mainnet", ipfs_cid2)
+        .await
+        .unwrap();
+    let state = Arc::new(GraphixState::new(
+        (*staging).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let request = async_graphql::Request::new(
+        r#"mutation ($document: JSON!) {
+            importConfig(document: $document) {
+                namedDeployments
+                skippedDeployments
+                taggedIndexers
+                skippedIndexers
+            }
+        }"#,
+    )
+    .variables(async_graphql::Variables::from_json(serde_json::json!({
+        "document": document,
+    })));
+    let response = request_schema(state, Some(admin_key(&staging).await))
+        .execute(request)
+        .await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "importConfig": {
+                "namedDeployments": 1,
+                "skippedDeployments": [ipfs_cid1],
+                "taggedIndexers": 1,
+                "skippedIndexers": [],
+            }
+        })
+    );
+
+    let stored_config = staging.current_config().await.unwrap().unwrap();
+    assert_eq!(stored_config["instanceName"], "production");
+    let audit_log = staging
+        .admin_audit_log(Some("importConfig"), None)
+        .await
+        .unwrap();
+    assert_eq!(audit_log.len(), 1);
+    let changed_fields = audit_log[0].payload["changedFields"].as_array().unwrap();
+    assert!(changed_fields.contains(&serde_json::json!("instanceName")));
+    assert_eq!(audit_log[0].payload["deploymentNames"], 2);
+    let indexers = staging.indexers(Default::default()).await.unwrap();
+    assert_eq!(indexers[0].tags, vec!["own-fleet"]);
+    let deployments = staging
+        .sg_deployments(SgDeploymentsQuery {
+            name: Some(format!("name-{ipfs_cid2}")),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(deployments.len(), 1);
+}
+
+#[tokio::test]
+async fn watchlists_from_config_cant_be_changed_through_the_api() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let ipfs_cid1 = "QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA";
+    let ipfs_cid2 = "QmYzsCjrVwwXtdsNm3PZVNziLGmb9o513GUzkq5wwhgXDT";
+    let mut config = Config {
+        watchlists: vec![WatchlistConfig {
+            name: "configured".to_string(),
+            deployments: vec![ipfs_cid1.to_string()],
+            polling_period_in_seconds: None,
+            investigate_divergences: true,
+            webhook_url: None,
+        }],
+        ..Default::default()
+    };
+    watchlists::sync_watchlists(&store, &config).await.unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(config.clone()).1,
+    ));
+    let admin_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+        .await
+        .unwrap()
+        .api_key;
+    let admin_key = ApiKey::from_str(&admin_key).unwrap();
+    let execute = |query: String| {
+        let schema = request_schema(state.clone(), Some(admin_key.clone()));
+        async move { schema.execute(query).await }
+    };
+
+    let response = execute(format!(
+        r#"mutation {{
+            setWatchlist(
+                name: "critical",
+                deployments: ["{ipfs_cid2}"],
+                pollingPeriodInSeconds: 10,
+                webhookUrl: "https://example.com/critical"
+            ) {{ name }}
+        }}"#
+    ))
+    .await;
+    assert_eq!(response.errors, vec![]);
+
+    for mutation in [
+        format!(r#"setWatchlist(name: "configured", deployments: ["{ipfs_cid2}"]) {{ name }}"#),
+        r#"deleteWatchlist(name: "configured")"#.to_string(),
+    ] {
+        let response = execute(format!("mutation {{ {mutation} }}")).await;
+        assert_eq!(response.errors.len(), 1, "{mutation}");
+    }
+
+    let query = "{
+        watchlists {
+            name
+            deployments
+            pollingPeriodInSeconds
+            investigateDivergences
+            webhookUrl
+            fromConfig
+        }
+    }";
+    let response = execute(query.to_string()).await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({
+            "watchlists": [
+                {
+                    "name": "configured",
+                    "deployments": [ipfs_cid1],
+                    "pollingPeriodInSeconds": null,
+                    "investigateDivergences": true,
+                    "webhookUrl": null,
+                    "fromConfig": true,
+                },
+                {
+                    "name": "critical",
+                    "deployments": [ipfs_cid2],
+                    "pollingPeriodInSeconds": 10,
+                    "investigateDivergences": true,
+                    "webhookUrl": "https://example.com/critical",
+                    "fromConfig": false,
+                },
+            ]
+        })
+    );
+
+    // Watchlists that are removed from the configuration are deleted, but
+    // those created through the API are kept.
+    config.watchlists.clear();
+    watchlists::sync_watchlists(&store, &config).await.unwrap();
+    let names: Vec<_> = store
+        .watchlists(None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|watchlist| watchlist.name)
+        .collect();
+    assert_eq!(names, ["critical"]);
+
+    let response = execute(r#"mutation { deleteWatchlist(name: "critical") }"#.to_string()).await;
+    assert_eq!(response.errors, vec![]);
+    assert!(store.watchlists(None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn watchlists_are_scoped_to_organizations() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+
+    let mut api_keys = vec![];
+    for organization in [Some("team-a"), Some("team-a"), None] {
+        let api_key = store
+            .create_api_key(None, ApiKeyPermissionLevel::Operator, organization)
+            .await
+            .unwrap();
+        api_keys.push(ApiKey::from_str(&api_key.api_key).unwrap());
+    }
+
+    let watchlist = |name: &str, api_key: Option<&ApiKey>| NewWatchlist {
+        name: name.to_string(),
+        deployments: vec![
+            IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap(),
+        ],
+        polling_period_in_seconds: None,
+        investigate_divergences: true,
+        webhook_url: None,
+        from_config: api_key.is_none(),
+        api_key_public_prefix: api_key.map(ApiKey::public_part_as_string),
+    };
+    store
+        .sync_config_watchlists(&[watchlist("configured", None)])
+        .await
+        .unwrap();
+    let set = |name: &str, owner: &ApiKey, api_key: Option<&ApiKey>| {
+        let watchlist = watchlist(name, Some(owner));
+        let api_key = api_key.cloned();
+        let store = &store;
+        async move {
+            store
+                .set_watchlist(&watchlist, api_key.as_ref())
+                .await
+                .unwrap()
+        }
+    };
+    assert!(set("a", &api_keys[0], Some(&api_keys[0])).await.is_some());
+    assert!(set("b", &api_keys[2], Some(&api_keys[2])).await.is_some());
+    // Watchlists of other organizations and those from the configuration
+    // can't be taken over.
+    assert!(set("a", &api_keys[2], Some(&api_keys[2])).await.is_none());
+    assert!(set("configured", &api_keys[0], Some(&api_keys[0]))
+        .await
+        .is_none());
+    assert!(set("configured", &api_keys[0], None).await.is_none());
+    // Replacing a watchlist of the same organization keeps its owner.
+    let replaced = set("a", &api_keys[1], Some(&api_keys[1])).await.unwrap();
+    assert_eq!(
+        replaced.api_key_public_prefix,
+        Some(api_keys[0].public_part_as_string())
+    );
+
+    let names = |api_key: Option<&ApiKey>| {
+        let store = &store;
+        let api_key = api_key.cloned();
+        async move {
+            store
+                .watchlists(api_key.as_ref())
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|watchlist| watchlist.name)
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(names(Some(&api_keys[1])).await, ["a", "configured"]);
+    assert_eq!(names(Some(&api_keys[2])).await, ["b", "configured"]);
+    assert_eq!(names(None).await, ["a", "b", "configured"]);
+
+    assert!(!store
+        .delete_watchlist("a", Some(&api_keys[2]))
+        .await
+        .unwrap());
+    assert!(store
+        .delete_watchlist("a", Some(&api_keys[1]))
+        .await
+        .unwrap());
+    assert!(store.delete_watchlist("b", None).await.unwrap());
+}
+
+#[tokio::test]
+async fn tracked_indexers_are_added_to_the_stored_config() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    store
+        .overwrite_config(serde_json::json!({ "pollingPeriodInSeconds": 60 }))
+        .await
+        .unwrap();
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let admin_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+        .await
+        .unwrap()
+        .api_key;
+    let admin_key = ApiKey::from_str(&admin_key).unwrap();
+    let execute = |query: &str| {
+        let schema = request_schema(state.clone(), Some(admin_key.clone()));
+        let query = query.to_string();
+        async move { schema.execute(query).await }
+    };
+    let stored_config = || async {
+        serde_json::from_value::<Config>(store.current_config().await.unwrap().unwrap()).unwrap()
+    };
+
+    let address = IndexerAddress::from([1; 20]);
+    let response = execute(&format!(
+        r#"mutation {{
+            a: addTrackedIndexer(name: "node-1", statusUrl: "http://node-1:8030/graphql")
+            b: addTrackedIndexer(
+                name: "node-2",
+                statusUrl: "http://node-2:8030/graphql",
+                address: "{address}"
+            )
+        }}"#
+    ))
+    .await;
+    assert_eq!(response.errors, vec![]);
+    let placeholder_address = IndexerConfig::placeholder_address("node-1");
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({ "a": placeholder_address, "b": address })
+    );
+
+    let config = stored_config().await;
+    assert_eq!(config.polling_period_in_seconds, 60);
+    let indexers: Vec<_> = config
+        .indexers()
+        .into_iter()
+        .map(|indexer| (indexer.name.unwrap(), indexer.address))
+        .collect();
+    assert_eq!(
+        indexers,
+        [
+            ("node-1".to_string(), placeholder_address),
+            ("node-2".to_string(), address)
+        ]
+    );
+
+    for mutation in [
+        // Already configured.
+        r#"addTrackedIndexer(name: "node-1", statusUrl: "http://node-3:8030/graphql")"#,
+        r#"addTrackedIndexer(name: "node-3", statusUrl: "ftp://node-3/graphql")"#,
+        r#"addTrackedIndexer(name: " ", statusUrl: "http://node-3:8030/graphql")"#,
+    ] {
+        let response = execute(&format!("mutation {{ {mutation} }}")).await;
+        assert_eq!(response.errors.len(), 1, "{mutation}");
+    }
+
+    let remove = format!(r#"mutation {{ removeTrackedIndexer(address: "{address}") }}"#);
+    let response = execute(&remove).await;
+    assert_eq!(response.errors, vec![]);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({ "removeTrackedIndexer": true })
+    );
+    let response = execute(&remove).await;
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({ "removeTrackedIndexer": false })
+    );
+    assert_eq!(stored_config().await.indexers().len(), 1);
+
+    // Concurrent edits don't overwrite each other.
+    let responses = futures::future::join_all((3..=10).map(|i| {
+        execute(&format!(
+            r#"mutation {{ addTrackedIndexer(name: "node-{i}", statusUrl: "http://node-{i}:8030/graphql") }}"#
+        ))
+    }))
+    .await;
+    for response in responses {
+        assert_eq!(response.errors, vec![]);
+    }
+    assert_eq!(stored_config().await.indexers().len(), 9);
+}
+
+#[tokio::test]
+async fn grpc_api_serves_live_pois_and_launches_investigations() {
+    use graphix_lib::grpc_api::proto::graphix_client::GraphixClient;
+    use graphix_lib::grpc_api::{proto, GrpcApi};
+
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let indexers: Vec<Arc<dyn IndexerClient>> = (1..=3)
+        .map(|i| -> Arc<dyn IndexerClient> {
+            Arc::new(RealIndexer::new(
+                Some(format!("indexer-{i}")),
+                IndexerAddress::from([i; 20]),
+                format!("http://indexer-{i}:8030/graphql"),
+                graphix_lib::metrics()
+                    .public_proofs_of_indexing_requests
+                    .clone(),
+            ))
+        })
+        .collect();
+    store.write_indexers(&indexers).await.unwrap();
+
+    // Indexer 1 disagrees with the other two.
+    let deployment = IpfsCid::from_str("QmNY7gDNXHECV8SXoEY7hbfg4BX1aDMxTBDiFuG4huaSGA").unwrap();
+    let pois = indexers
+        .iter()
+        .zip([1, 2, 2])
+        .map(|(indexer, poi_byte)| ProofOfIndexing {
+            indexer: indexer.clone(),
+            deployment: deployment.clone(),
+            network: "mainnet".to_string(),
+            block: BlockPointer {
+                number: 42,
+                hash: Some(vec![42; 32].into()),
+            },
+            proof_of_indexing: [poi_byte; 32].into(),
+        })
+        .collect();
+    store.write_pois(pois, PoiLiveness::Live).await.unwrap();
+    let operator_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Operator, None)
+        .await
+        .unwrap()
+        .api_key;
+
+    let state = Arc::new(GraphixState::new(
+        (*store).clone(),
+        watch::channel(Config::default()).1,
+    ));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let router = GrpcApi::new(state).into_axum_router();
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    let mut client = GraphixClient::connect(format!("http://{address}"))
+        .await
+        .unwrap();
+
+    let live_pois = client
+        .list_live_pois(proto::ListLivePoisRequest {
+            deployments: vec![deployment.to_string()],
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .pois;
+    assert_eq!(live_pois.len(), 3);
+    assert!(live_pois
+        .iter()
+        .all(|poi| poi.deployment == deployment.to_string()
+            && poi.network == "mainnet"
+            && poi.block_number == 42));
+
+    let ratios = client
+        .get_poi_agreement_ratios(proto::GetPoiAgreementRatiosRequest {
+            indexer_address: IndexerAddress::from([1; 20]).to_string(),
+            network: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .ratios;
+    assert_eq!(ratios.len(), 1);
+    assert_eq!(
+        ratios[0].poi.as_ref().unwrap().hash,
+        format!("0x{}", "01".repeat(32))
+    );
+    assert_eq!(
+        (
+            ratios[0].total_indexers,
+            ratios[0].agreeing_indexers,
+            ratios[0].has_consensus,
+            ratios[0].in_consensus
+        ),
+        (3, 1, true, false)
+    );
+
+    let launch = proto::LaunchDivergenceInvestigationRequest {
+        pois: vec![
+            format!("0x{}", "01".repeat(32)),
+            format!("0x{}", "02".repeat(32)),
+        ],
+        ..Default::default()
+    };
+    let err = client
+        .launch_divergence_investigation(launch.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+    let mut request = tonic::Request::new(launch);
+    request
+        .metadata_mut()
+        .insert("graphix-api-key", operator_key.parse().unwrap());
+    let uuid = client
+        .launch_divergence_investigation(request)
+        .await
+        .unwrap()
+        .into_inner()
+        .uuid;
+    let uuid = Uuid::from_str(&uuid).unwrap();
+    assert!(store
+        .divergence_investigation_request_exists(&uuid)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn indexer_connectivity_is_tested_by_address_or_url() {
+    let store = EmptyStoreForTesting::new().await.unwrap();
+    let graph_node = MockGraphNode::start("indexer-1", vec![]).await;
+    let state = Arc::new(
+        GraphixState::new((*store).clone(), watch::channel(Config::default()).1)
+            .with_indexers(watch::channel(vec![graph_node.indexer()]).1),
+    );
+    let admin_key = store
+        .create_api_key(None, ApiKeyPermissionLevel::Admin, None)
+        .await
+        .unwrap()
+        .api_key;
+    let admin_key = ApiKey::from_str(&admin_key).unwrap();
+    let execute = |arguments: String| {
+        let schema = request_schema(state.clone(), Some(admin_key.clone()));
+        async move {
+            schema
+                .execute(format!(
+                    "mutation {{ testIndexerConnectivity({arguments}) {{ \
+                     reachable checks {{ request succeeded errorClass }} }} }}"
+                ))
+                .await
+        }
+    };
+
+    let response = execute(format!(r#"address: "{}""#, graph_node.address())).await;
+    assert_eq!(response.errors, vec![]);
+    let connectivity = &response.data.into_json().unwrap()["testIndexerConnectivity"];
+    assert_eq!(connectivity["reachable"], true);
+    let checks: Vec<_> = connectivity["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|check| {
+            (
+                check["request"].as_str().unwrap(),
+                check["succeeded"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        checks,
+        [
+            ("ping", true.into()),
+            ("version", true.into()),
+            ("indexingStatuses", true.into())
+        ]
+    );
+
+    // Nothing listens on port 1.
+    let response = execute(r#"url: "http://127.0.0.1:1/status""#.to_string()).await;
+    assert_eq!(response.errors, vec![]);
+    let connectivity = &response.data.into_json().unwrap()["testIndexerConnectivity"];
+    assert_eq!(connectivity["reachable"], false);
+    assert_eq!(connectivity["checks"][0]["errorClass"], "CONNECTION");
+
+    // Exactly one of the two must be given.
+    for arguments in [
+        String::new(),
+        format!(
+            r#"address: "{}", url: "{}""#,
+            graph_node.address(),
+            graph_node.status_url()
+        ),
+        format!(r#"address: "{}""#, IndexerAddress::from([9; 20])),
+    ] {
+        assert_eq!(execute(arguments).await.errors.len(), 1);
+    }
+}